@@ -0,0 +1,177 @@
+/// Base32768 encoding, compatible with rclone filename_encoding = base32768
+/// https://github.com/qntm/base32768
+///
+/// Every char encodes 15 bits. The last char can encode 7 bits using a
+/// different repertoire. Unused bits are padded with 1s.
+const BITS_PER_CHAR: usize = 15;
+const BITS_PER_FINAL_CHAR: usize = 7;
+
+/// Pairs of first and last chars of each range
+const REPERTOIRE: &str = "ҠҿԀԟڀڿݠޟ߀ߟကဟႠႿᄀᅟᆀᆟᇠሿበቿዠዿጠጿᎠᏟᐠᙟᚠᛟកសᠠᡟᣀᣟᦀᦟ᧠᧿ᨠᨿᯀᯟᰀᰟᴀᴟ⇠⇿⋀⋟⍀⏟␀␟─❟➀➿⠀⥿⦠⦿⨠⩟⪀⪿⫠⭟ⰀⰟⲀⳟⴀⴟⵀⵟ⺠⻟㇀㇟㐀䶟䷀龿ꀀꑿ꒠꒿ꔀꗿꙀꙟꚠꛟ꜀ꝟꞀꞟꡀꡟ";
+const FINAL_REPERTOIRE: &str = "ƀƟɀʟ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError(pub String);
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn ranges(repertoire: &'static str) -> impl Iterator<Item = (u32, u32)> {
+    let chars: Vec<u32> = repertoire.chars().map(|c| c as u32).collect();
+
+    (0..chars.len() / 2).map(move |i| (chars[i * 2], chars[i * 2 + 1]))
+}
+
+fn encode_char(repertoire: &'static str, mut z: u32) -> char {
+    for (first, last) in ranges(repertoire) {
+        let len = last - first + 1;
+
+        if z < len {
+            return char::from_u32(first + z).unwrap();
+        }
+
+        z -= len;
+    }
+
+    unreachable!("value out of repertoire range")
+}
+
+fn decode_char(repertoire: &'static str, c: char) -> Option<u32> {
+    let c = c as u32;
+    let mut offset = 0;
+
+    for (first, last) in ranges(repertoire) {
+        if c >= first && c <= last {
+            return Some(offset + c - first);
+        }
+
+        offset += last - first + 1;
+    }
+
+    None
+}
+
+pub fn encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() * 8 + BITS_PER_CHAR - 1) / BITS_PER_CHAR * 3);
+    let mut z: u32 = 0;
+    let mut num_z_bits = 0;
+
+    for byte in data {
+        for j in (0..8).rev() {
+            z = (z << 1) | ((*byte as u32 >> j) & 1);
+            num_z_bits += 1;
+
+            if num_z_bits == BITS_PER_CHAR {
+                out.push(encode_char(REPERTOIRE, z));
+
+                z = 0;
+                num_z_bits = 0;
+            }
+        }
+    }
+
+    if num_z_bits > 0 {
+        let (repertoire, bits) = if num_z_bits <= BITS_PER_FINAL_CHAR {
+            (FINAL_REPERTOIRE, BITS_PER_FINAL_CHAR)
+        } else {
+            (REPERTOIRE, BITS_PER_CHAR)
+        };
+
+        while num_z_bits < bits {
+            z = (z << 1) | 1;
+            num_z_bits += 1;
+        }
+
+        out.push(encode_char(repertoire, z));
+    }
+
+    out
+}
+
+pub fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+    let chars: Vec<char> = encoded.chars().collect();
+    let mut out = Vec::with_capacity(chars.len() * BITS_PER_CHAR / 8);
+    let mut byte: u32 = 0;
+    let mut num_byte_bits = 0;
+
+    for (i, c) in chars.iter().enumerate() {
+        let (z, bits) = match decode_char(REPERTOIRE, *c) {
+            Some(z) => (z, BITS_PER_CHAR),
+            None => match decode_char(FINAL_REPERTOIRE, *c) {
+                Some(z) if i == chars.len() - 1 => (z, BITS_PER_FINAL_CHAR),
+                Some(_) => {
+                    return Err(DecodeError(format!(
+                        "secondary character found before end of input at position {}",
+                        i
+                    )));
+                }
+                None => {
+                    return Err(DecodeError(format!(
+                        "unrecognised character at position {}",
+                        i
+                    )));
+                }
+            },
+        };
+
+        for j in (0..bits).rev() {
+            byte = (byte << 1) | ((z >> j) & 1);
+            num_byte_bits += 1;
+
+            if num_byte_bits == 8 {
+                out.push(byte as u8);
+
+                byte = 0;
+                num_byte_bits = 0;
+            }
+        }
+    }
+
+    // remaining bits are padding and must be all 1s
+    if byte != (1 << num_byte_bits) - 1 {
+        return Err(DecodeError(String::from("padding mismatch")));
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, DecodeError};
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(&[0]), "ڿ");
+        assert_eq!(encode(b"hello"), "媒腻㐿");
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("").unwrap(), b"");
+        assert_eq!(decode("ڿ").unwrap(), &[0]);
+        assert_eq!(decode("媒腻㐿").unwrap(), b"hello");
+
+        for len in 0..64 {
+            let data: Vec<u8> = (0..len).map(|i| (i * 97 + 13) as u8).collect();
+
+            assert_eq!(decode(&encode(&data)).unwrap(), data);
+        }
+
+        assert_eq!(
+            decode("a"),
+            Err(DecodeError(String::from(
+                "unrecognised character at position 0"
+            )))
+        );
+        assert_eq!(
+            decode("ƀ媒"),
+            Err(DecodeError(String::from(
+                "secondary character found before end of input at position 0"
+            )))
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use data_encoding::{BASE64URL_NOPAD, HEXLOWER};
 use futures::io::Cursor;
 use futures::AsyncReadExt;
 use std::str;
@@ -5,20 +6,28 @@ use std::sync::Arc;
 use xsalsa20poly1305::XSalsa20Poly1305;
 
 use super::cipher_keys::{derive_keys, DerivedKeys};
-use super::constants::{DATA_KEY_LEN, NAME_CIPHER_BLOCK_SIZE, NAME_KEY_LEN};
-use super::data_cipher::get_data_cipher;
+use super::constants::{
+    DATA_KEY_LEN, ENCRYPTED_SUFFIX, FILE_NONCE_SIZE, NAME_CIPHER_BLOCK_SIZE, NAME_KEY_LEN,
+};
+use super::data_cipher::{decrypt_block, encrypt_block, get_data_cipher};
 use super::decrypt_reader::DecryptReader;
 use super::encrypt_reader::EncryptReader;
 use super::errors::DecryptFilenameError;
+use super::hmac::hmac_sha256;
 use super::name_cipher::{
-    decrypt_filename, decrypt_path, encrypt_filename, encrypt_path, get_name_cipher,
+    decrypt_filename, encrypt_filename, get_name_cipher, FilenameEncoding, FilenameEncryption,
 };
+use super::name_obfuscate::{deobfuscate_filename, obfuscate_filename};
 use super::nonce::Nonce;
 
 pub struct Cipher {
+    data_key: [u8; DATA_KEY_LEN],
     name_key: [u8; NAME_KEY_LEN],
     name_tweak: [u8; NAME_CIPHER_BLOCK_SIZE],
     data_cipher: Arc<XSalsa20Poly1305>,
+    filename_encryption: FilenameEncryption,
+    filename_encoding: FilenameEncoding,
+    directory_name_encryption: bool,
 }
 
 impl Cipher {
@@ -40,32 +49,178 @@ impl Cipher {
         let data_cipher = get_data_cipher(&data_key);
 
         Self {
+            data_key,
             name_key,
             name_tweak,
             data_cipher: Arc::new(data_cipher),
+            filename_encryption: FilenameEncryption::Standard,
+            filename_encoding: FilenameEncoding::Base32,
+            directory_name_encryption: true,
+        }
+    }
+
+    /// Key material derived from the password. Can be used to recreate the
+    /// cipher with with_keys without the password.
+    pub fn derived_keys(&self) -> DerivedKeys {
+        DerivedKeys {
+            data_key: self.data_key,
+            name_key: self.name_key,
+            name_tweak: self.name_tweak,
+        }
+    }
+
+    pub fn with_filename_encryption(mut self, filename_encryption: FilenameEncryption) -> Self {
+        self.filename_encryption = filename_encryption;
+        self
+    }
+
+    pub fn filename_encryption(&self) -> FilenameEncryption {
+        self.filename_encryption
+    }
+
+    /// Filename encoding is only used with standard filename encryption
+    pub fn with_filename_encoding(mut self, filename_encoding: FilenameEncoding) -> Self {
+        self.filename_encoding = filename_encoding;
+        self
+    }
+
+    pub fn filename_encoding(&self) -> FilenameEncoding {
+        self.filename_encoding
+    }
+
+    /// If directory name encryption is disabled only file names are encrypted
+    pub fn with_directory_name_encryption(mut self, directory_name_encryption: bool) -> Self {
+        self.directory_name_encryption = directory_name_encryption;
+        self
+    }
+
+    pub fn directory_name_encryption(&self) -> bool {
+        self.directory_name_encryption
+    }
+
+    fn encrypt_name(&self, plaintext: &str) -> String {
+        match self.filename_encryption {
+            FilenameEncryption::Standard => encrypt_filename(
+                get_name_cipher(&self.name_key, &self.name_tweak),
+                self.filename_encoding,
+                plaintext,
+            ),
+            FilenameEncryption::Obfuscate => obfuscate_filename(&self.name_key, plaintext),
+            FilenameEncryption::Off => plaintext.to_owned(),
         }
     }
 
+    fn decrypt_name(&self, ciphertext: &str) -> Result<String, DecryptFilenameError> {
+        match self.filename_encryption {
+            FilenameEncryption::Standard => decrypt_filename(
+                get_name_cipher(&self.name_key, &self.name_tweak),
+                self.filename_encoding,
+                ciphertext,
+            ),
+            FilenameEncryption::Obfuscate => deobfuscate_filename(&self.name_key, ciphertext),
+            FilenameEncryption::Off => Ok(ciphertext.to_owned()),
+        }
+    }
+
+    /// Encrypt a file name. Use encrypt_dirname for directories because they
+    /// are not encrypted the same way in all modes.
     pub fn encrypt_filename(&self, plaintext: &str) -> String {
-        encrypt_filename(get_name_cipher(&self.name_key, &self.name_tweak), plaintext)
+        match self.filename_encryption {
+            FilenameEncryption::Off => format!("{}{}", plaintext, ENCRYPTED_SUFFIX),
+            _ => self.encrypt_name(plaintext),
+        }
+    }
+
+    pub fn encrypt_dirname(&self, plaintext: &str) -> String {
+        if !self.directory_name_encryption {
+            return plaintext.to_owned();
+        }
+
+        self.encrypt_name(plaintext)
     }
 
+    /// Encrypt a path where all the segments are directories
     pub fn encrypt_path(&self, plaintext: &str) -> String {
-        encrypt_path(get_name_cipher(&self.name_key, &self.name_tweak), plaintext)
+        self.encrypt_path_segments(plaintext, false)
+    }
+
+    /// Encrypt a path where the last segment is a file
+    pub fn encrypt_file_path(&self, plaintext: &str) -> String {
+        self.encrypt_path_segments(plaintext, true)
+    }
+
+    fn encrypt_path_segments(&self, plaintext: &str, is_file: bool) -> String {
+        match plaintext {
+            "/" => plaintext.to_owned(),
+            _ => {
+                let parts: Vec<&str> = plaintext.split("/").skip(1).collect();
+                let last_index = parts.len() - 1;
+                let mut encrypted_parts: Vec<String> = Vec::with_capacity(parts.len() + 1);
+                encrypted_parts.push(String::from(""));
+                for (i, part) in parts.into_iter().enumerate() {
+                    let encrypted_part = if is_file && i == last_index {
+                        self.encrypt_filename(part)
+                    } else {
+                        self.encrypt_dirname(part)
+                    };
+                    encrypted_parts.push(encrypted_part);
+                }
+                encrypted_parts.join("/")
+            }
+        }
     }
 
     pub fn decrypt_filename(&self, ciphertext: &str) -> Result<String, DecryptFilenameError> {
-        decrypt_filename(
-            get_name_cipher(&self.name_key, &self.name_tweak),
-            ciphertext,
-        )
+        match self.filename_encryption {
+            FilenameEncryption::Off => ciphertext
+                .strip_suffix(ENCRYPTED_SUFFIX)
+                .map(str::to_string)
+                .ok_or(DecryptFilenameError::NotAnEncryptedFile),
+            _ => self.decrypt_name(ciphertext),
+        }
+    }
+
+    pub fn decrypt_dirname(&self, ciphertext: &str) -> Result<String, DecryptFilenameError> {
+        if !self.directory_name_encryption {
+            return Ok(ciphertext.to_owned());
+        }
+
+        self.decrypt_name(ciphertext)
     }
 
+    /// Decrypt a path where all the segments are directories
     pub fn decrypt_path(&self, ciphertext: &str) -> Result<String, DecryptFilenameError> {
-        decrypt_path(
-            get_name_cipher(&self.name_key, &self.name_tweak),
-            ciphertext,
-        )
+        self.decrypt_path_segments(ciphertext, false)
+    }
+
+    /// Decrypt a path where the last segment is a file
+    pub fn decrypt_file_path(&self, ciphertext: &str) -> Result<String, DecryptFilenameError> {
+        self.decrypt_path_segments(ciphertext, true)
+    }
+
+    fn decrypt_path_segments(
+        &self,
+        ciphertext: &str,
+        is_file: bool,
+    ) -> Result<String, DecryptFilenameError> {
+        match ciphertext {
+            "/" => Ok(ciphertext.to_owned()),
+            _ => {
+                let parts: Vec<&str> = ciphertext.split("/").skip(1).collect();
+                let last_index = parts.len() - 1;
+                let mut decrypted_parts: Vec<String> = Vec::with_capacity(parts.len() + 1);
+                decrypted_parts.push(String::from(""));
+                for (i, part) in parts.into_iter().enumerate() {
+                    let decrypted_part = if is_file && i == last_index {
+                        self.decrypt_filename(part)?
+                    } else {
+                        self.decrypt_dirname(part)?
+                    };
+                    decrypted_parts.push(decrypted_part);
+                }
+                Ok(decrypted_parts.join("/"))
+            }
+        }
     }
 
     pub fn encrypt_reader<R>(&self, reader: R) -> EncryptReader<R> {
@@ -74,6 +229,19 @@ impl Cipher {
         EncryptReader::new(reader, self.data_cipher.clone(), nonce)
     }
 
+    pub fn encrypt_reader_with_nonce<R>(&self, reader: R, nonce: Nonce) -> EncryptReader<R> {
+        EncryptReader::new(reader, self.data_cipher.clone(), nonce)
+    }
+
+    pub fn encrypt_reader_at_block<R>(
+        &self,
+        reader: R,
+        nonce: Nonce,
+        block_index: u64,
+    ) -> EncryptReader<R> {
+        EncryptReader::new_at_block(reader, self.data_cipher.clone(), nonce, block_index)
+    }
+
     pub async fn encrypt_data(
         &self,
         data: &[u8],
@@ -88,6 +256,15 @@ impl Cipher {
         DecryptReader::new(reader, self.data_cipher.clone())
     }
 
+    pub fn decrypt_reader_at_block<R>(
+        &self,
+        reader: R,
+        nonce: Nonce,
+        block_index: u64,
+    ) -> DecryptReader<R> {
+        DecryptReader::new_at_block(reader, self.data_cipher.clone(), nonce, block_index)
+    }
+
     pub async fn decrypt_data(
         &self,
         data: &[u8],
@@ -97,14 +274,75 @@ impl Cipher {
 
         self.decrypt_reader(reader).read_to_end(out).await
     }
+
+    /// Encrypt a short value (e.g. a file tag) with a random nonce. The
+    /// result is the nonce followed by the encrypted value, base64 encoded.
+    pub fn encrypt_value(&self, plaintext: &str) -> String {
+        let nonce = Nonce::new_random().unwrap();
+        let encrypted = encrypt_block(&self.data_cipher, &nonce, plaintext.as_bytes()).unwrap();
+
+        let mut data = nonce.as_slice().to_vec();
+        data.extend_from_slice(&encrypted);
+
+        BASE64URL_NOPAD.encode(&data)
+    }
+
+    /// Decrypt a value encrypted with encrypt_value. Returns None if the
+    /// value was not encrypted with this cipher.
+    pub fn decrypt_value(&self, ciphertext: &str) -> Option<String> {
+        let data = BASE64URL_NOPAD.decode(ciphertext.as_bytes()).ok()?;
+
+        if data.len() < FILE_NONCE_SIZE {
+            return None;
+        }
+
+        let nonce = Nonce::new(data[..FILE_NONCE_SIZE].try_into().ok()?);
+        let decrypted = decrypt_block(&self.data_cipher, &nonce, &data[FILE_NONCE_SIZE..]).ok()?;
+
+        String::from_utf8(decrypted).ok()
+    }
+
+    /// Keyed hash (HMAC-SHA256 with the name key, lowercase hex) of a value
+    /// that is stored in the repo but must not be recognizable without the
+    /// repo keys, e.g. a plaintext content hash.
+    pub fn hash_value(&self, value: &str) -> String {
+        HEXLOWER.encode(&hmac_sha256(&self.name_key, value.as_bytes()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use futures::executor::block_on;
+    use futures::{executor::block_on, AsyncReadExt};
+
+    use crate::cipher::{
+        cipher_keys::DerivedKeys,
+        constants::{BLOCK_DATA_SIZE, BLOCK_SIZE, FILE_HEADER_SIZE},
+        data_cipher::{decrypt_header, encrypted_position, encrypted_range},
+        errors::DecryptFilenameError,
+        name_cipher::{FilenameEncoding, FilenameEncryption},
+    };
 
     use super::Cipher;
 
+    #[test]
+    fn test_derived_keys() {
+        let cipher = Cipher::new("testpassword", None);
+
+        let DerivedKeys {
+            data_key,
+            name_key,
+            name_tweak,
+        } = DerivedKeys::from_bytes(&cipher.derived_keys().to_bytes()).unwrap();
+
+        let keys_cipher = Cipher::with_keys(data_key, name_key, name_tweak);
+
+        assert_eq!(
+            keys_cipher.encrypt_filename("testfilename"),
+            "mvedi866srqc97sl5948oaej2g"
+        );
+        assert!(DerivedKeys::from_bytes(&[0; 10]).is_none());
+    }
+
     #[test]
     fn test_encrypt_filename() {
         // tested with rclone 1.60
@@ -160,6 +398,245 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encrypt_filename_base64() {
+        // same ciphertexts as the base32 names tested with rclone 1.60
+        let cipher =
+            Cipher::new("testpassword", None).with_filename_encoding(FilenameEncoding::Base64);
+
+        assert_eq!(
+            cipher.encrypt_filename("testfilename"),
+            "t9zZIMbm9MSflSpIjCnTFA"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilename"),
+            "O3LG7Wj7YXtYEAXKt0ZRHmvYqPGtVhtdmK03qGR5QsU"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilename"),
+            "xQtSuxGJxzb6tfSXVrzw3pSdk0mbqKt7VOnlxD2z5cjxiZ6j93VDwyrbxhhyTnWO"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilename"),
+            "j_aoD6MDRq-Fgw1yeZ6V0kswi35Ixem8kbKeZZIieLGN4DC-d3cKXbyWq3THieZVCXcLAQpq8hXJlYTpGfcBCg"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilename"),
+            "AEdGYlaqHDAE7BkCMS27kVCjn05RZFeU18-ZdRxHYnveuy9xDIcFUwttsCtWubfVuE4c0Mh0-ti3l-87JkIvzQ"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "5FmtXGX-zOjy5W6f0IX5ts7rJpuAI_qvvAKLNYb4onLDgMws9z6CnoGPjuDb6LB151JvWt_MJsinwZ2QV7qwIKZ52XoZZJ194e_ykDROIO4"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "nHWxXAdAr650IbE2Xx6GkGl1prjvfQfeBLJqYhe8SlmzuhCrTGHWspw5qG-itqdkLKrj1kEJu4D7e3Y_LiaP9BCmPbUcUfNyNAdmFyRKu4Ysc2PPOfvB1NYPICVnDAz_"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "L94OWQrBT-FQhGZ5BHiA875XA4SeNh1Q9bDKFiLE9vEKuj7Xa6okdPU0TYeZVGlDrvyUNuL7JbVsnhOchUbMV5c0Gaw366-mBovLPO6_602XurxSxlCPTBJNc8qQUYcHUpRR0HU-g0piJ4eUKwmiCA"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "kB2mIsjfFiCkSq9o-kYjQfvr5RVlgkxZ67Tu7zpzlm7M1OYG91eqkQNlSLOnkYbUEhrjFGo2V-iI9j8ntGb7wPHKuo4YE8DEi5g4XjdPantGsoD_pcoxDl5pLxhMo8GkTEoMIwdvJnO3_y27QywxDQ"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "AXHrlm_uELaZCmO9I7ITgQkcSBZFfj5GuvCD_eESkHB8kWJcB20IOq_mefJkujlt74xESrcLhwlZ4zZhgHB52n4wvSvByj_ToPIjbTZz_Hl7Qkr4PZ5fpK0a6hvMS0dvqRzOP0vRxyQzBkydbpZbws8yPRDooBB_HhM2egI4oTg"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "4hSDDNgeMBgksiuihkBOsrDfV71TQEMC6Br7pnqQ5t_rqRSeOR62XA3pEXxgjwHFcSAhBtKnusP-zkg7Plo3YkIdpfmNbx0L-pQIbhWoRiQHU3OSS4uEKbC3Sv2j9uek0JP2dotO3w_6YHiOpO3dpn66ntJeO6xwtx_n_KNTe9imCdUzJjrtfVHrbwDNCK59"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "VKKI0pNkt9hyADHXA8Eql0ru8EbIf0Z2b_2l0cTU9ciWTUvQA8F_ekyUKgdEKN4fStP13pRpU1FOBXDIlubax7LYvzr99TVtMqwotZY6qHBDyxjAq3jorjIJHbqgRaMS629sCGugVPFPkvByx_Qod9g8hdpwb4k6YSEh0p9CaJ32rw7JqxfQir9dShsm5GIC8YvQsIr9k3DTd-KapqxZYQ"
+        );
+    }
+
+    #[test]
+    fn test_decrypt_filename_base64() {
+        let cipher =
+            Cipher::new("testpassword", None).with_filename_encoding(FilenameEncoding::Base64);
+
+        assert_eq!(
+            cipher.decrypt_filename("t9zZIMbm9MSflSpIjCnTFA").unwrap(),
+            "testfilename"
+        );
+        assert_eq!(
+            cipher
+                .decrypt_filename("O3LG7Wj7YXtYEAXKt0ZRHmvYqPGtVhtdmK03qGR5QsU")
+                .unwrap(),
+            "testfilenametestfilename"
+        );
+        assert_eq!(
+            cipher
+                .decrypt_filename(
+                    "xQtSuxGJxzb6tfSXVrzw3pSdk0mbqKt7VOnlxD2z5cjxiZ6j93VDwyrbxhhyTnWO"
+                )
+                .unwrap(),
+            "testfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("j_aoD6MDRq-Fgw1yeZ6V0kswi35Ixem8kbKeZZIieLGN4DC-d3cKXbyWq3THieZVCXcLAQpq8hXJlYTpGfcBCg").unwrap(),
+            "testfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("AEdGYlaqHDAE7BkCMS27kVCjn05RZFeU18-ZdRxHYnveuy9xDIcFUwttsCtWubfVuE4c0Mh0-ti3l-87JkIvzQ").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("5FmtXGX-zOjy5W6f0IX5ts7rJpuAI_qvvAKLNYb4onLDgMws9z6CnoGPjuDb6LB151JvWt_MJsinwZ2QV7qwIKZ52XoZZJ194e_ykDROIO4").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("nHWxXAdAr650IbE2Xx6GkGl1prjvfQfeBLJqYhe8SlmzuhCrTGHWspw5qG-itqdkLKrj1kEJu4D7e3Y_LiaP9BCmPbUcUfNyNAdmFyRKu4Ysc2PPOfvB1NYPICVnDAz_").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("L94OWQrBT-FQhGZ5BHiA875XA4SeNh1Q9bDKFiLE9vEKuj7Xa6okdPU0TYeZVGlDrvyUNuL7JbVsnhOchUbMV5c0Gaw366-mBovLPO6_602XurxSxlCPTBJNc8qQUYcHUpRR0HU-g0piJ4eUKwmiCA").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("kB2mIsjfFiCkSq9o-kYjQfvr5RVlgkxZ67Tu7zpzlm7M1OYG91eqkQNlSLOnkYbUEhrjFGo2V-iI9j8ntGb7wPHKuo4YE8DEi5g4XjdPantGsoD_pcoxDl5pLxhMo8GkTEoMIwdvJnO3_y27QywxDQ").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("AXHrlm_uELaZCmO9I7ITgQkcSBZFfj5GuvCD_eESkHB8kWJcB20IOq_mefJkujlt74xESrcLhwlZ4zZhgHB52n4wvSvByj_ToPIjbTZz_Hl7Qkr4PZ5fpK0a6hvMS0dvqRzOP0vRxyQzBkydbpZbws8yPRDooBB_HhM2egI4oTg").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("4hSDDNgeMBgksiuihkBOsrDfV71TQEMC6Br7pnqQ5t_rqRSeOR62XA3pEXxgjwHFcSAhBtKnusP-zkg7Plo3YkIdpfmNbx0L-pQIbhWoRiQHU3OSS4uEKbC3Sv2j9uek0JP2dotO3w_6YHiOpO3dpn66ntJeO6xwtx_n_KNTe9imCdUzJjrtfVHrbwDNCK59").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("VKKI0pNkt9hyADHXA8Eql0ru8EbIf0Z2b_2l0cTU9ciWTUvQA8F_ekyUKgdEKN4fStP13pRpU1FOBXDIlubax7LYvzr99TVtMqwotZY6qHBDyxjAq3jorjIJHbqgRaMS629sCGugVPFPkvByx_Qod9g8hdpwb4k6YSEh0p9CaJ32rw7JqxfQir9dShsm5GIC8YvQsIr9k3DTd-KapqxZYQ").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+    }
+
+    #[test]
+    fn test_encrypt_filename_base32768() {
+        // same ciphertexts as the base32 names tested with rclone 1.60
+        let cipher =
+            Cipher::new("testpassword", None).with_filename_encoding(FilenameEncoding::Base32768);
+
+        assert_eq!(cipher.encrypt_filename("testfilename"), "艎岨㼜閬䬼笉㝘倳⡿");
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilename"),
+            "䏹堛卿屷脠晷㮮沱㕵鲊䑵篁腌褔閰諙䞢ʟ"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilename"),
+            "裥笎袑䊳帵縲唍捐閪䷄输惪舺稇燨搓饄拂娴旗偾Ⰻ巬㺲䵺訟"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilename"),
+            "湛偣髀嫊ꋌ⫵譓䓕辅犂阩⬞瑄洪挫㡢抸觘ᯗ跗庲鵒厶鬧歓㮂啁噰禷溷㥫ᚩⲻ暢ɿ"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilename"),
+            "ԃ矸焵䠃ԇ囄ᗢ君漈佇逪㲅挆薞奊䊇垝鸎豎㜈床犍臀冶茻鯎➃獬樇釂閏閛㥡⪓ɟ"
+        );
+        assert_eq!(
+            cipher.encrypt_filename(
+                "testfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+            ),
+            "颌醷Ⱏ錮混㯺昁ᨹ臇愩秐ዿ篝陪㲫⎘瞙址㿅痓驴ꂦ䕝蜻骸䎹邭鰍ꕡ䅢痣䏐刽剨㬏䏗眫㢵ꉣ陒湺㧈䐟"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "璚銷ဈ⣺騁⍤錞䓆溔菉絽鸰敐㤉笤㷼䭬鍎桵嬦㓵烐馰阂膳罫㮜揄◭鑣鵖鲟㵓䨽ቴ註佂渭諈┆⦲㣮霥涖䒹蹏ᔩ籯㙒耣ᆟʟ"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "㸯ᔖ䞘㬾⢄㟙顨黠ꁿ㰀雳见郧紣㩬䤄ꈘ椎渺鴚瞃稴軻┹傔睋藲槎㸙㴕羜㧜椃奵饆柺蠟唞牭⩋䒷噚递ꉋ裶㾂䓘㢍恅䩔址鮉䣎栴ꎦ烂㨃譪螔䛏"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "湮迨罻韂គ儝硔沃䜽ꆙ䣬纄褯唳萾惓熗妕䌀闕掴橭烱娇漣孤榼垦砒蘂㠬文肓敐䑹刈霠畣⚷㹸啻稺痈醈▝㵨衼蓉㷌㥨麔欤盁⪽蒬騗Ꜷ锰详㜟"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "ޘꆅ瑝蝫嬨修ꃧ塳曤浲ᑈ繃颕鈢▛蝲溘䕤劫曖躡鄟獓飄荼臛韨欋庼䉥娦峁暘䒶瘦⩒葮佟䶁颃峻䌿㕯媄縡鳙斩卺魭饲轍ꄱ賑ꏏ䧎䩳ᒦ䶛礫抌ꀱ骣瞠㚿㕉現暧⠓ɧ"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "靪䜃䅃襡枅漎歬暮羸帵鸊婤㹗䚫鶬ꄰ駏ꆊ䣓觱鰒隗碂ꌀ淠鞼䩄㚭㭽酯ꐼ溛䕭㐘溣肿狋拔㸵㩈嵪遱櫠鮗䋒咎◳圗䮾轝荔獩䗳肍䏾㘺嚜䧩䏻胇鰴ꇩ拗勐臯ꂟ㪪帝殐䶔責慍攈ꅻ虹盪驏"
+        );
+        assert_eq!(
+            cipher.encrypt_filename("testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"),
+            "傱䡴磌燝槰߇呧枊爅戜♹▔娓晖爃欴ꆄ䯓俚Ԝ⪛辒亴␄㪯╲胞葉䦊玥䉊霨燓崑鲻⪓繏笵胅劈脫㓪㑈挑汥呃瞼塩㔝乱嫂唖ꈀ䟮有鞯濘䋱ꖅ┝桄㶩蜿⛚困滔穈䳉阕抛㦖㸐殿網槄钦㙗沏䞡⣽瀘嬽ꋳ僊褫Ƈ"
+        );
+    }
+
+    #[test]
+    fn test_decrypt_filename_base32768() {
+        let cipher =
+            Cipher::new("testpassword", None).with_filename_encoding(FilenameEncoding::Base32768);
+
+        assert_eq!(
+            cipher.decrypt_filename("艎岨㼜閬䬼笉㝘倳⡿").unwrap(),
+            "testfilename"
+        );
+        assert_eq!(
+            cipher
+                .decrypt_filename("䏹堛卿屷脠晷㮮沱㕵鲊䑵篁腌褔閰諙䞢ʟ")
+                .unwrap(),
+            "testfilenametestfilename"
+        );
+        assert_eq!(
+            cipher
+                .decrypt_filename("裥笎袑䊳帵縲唍捐閪䷄输惪舺稇燨搓饄拂娴旗偾Ⰻ巬㺲䵺訟")
+                .unwrap(),
+            "testfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher
+                .decrypt_filename(
+                    "湛偣髀嫊ꋌ⫵譓䓕辅犂阩⬞瑄洪挫㡢抸觘ᯗ跗庲鵒厶鬧歓㮂啁噰禷溷㥫ᚩⲻ暢ɿ"
+                )
+                .unwrap(),
+            "testfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher
+                .decrypt_filename(
+                    "ԃ矸焵䠃ԇ囄ᗢ君漈佇逪㲅挆薞奊䊇垝鸎豎㜈床犍臀冶茻鯎➃獬樇釂閏閛㥡⪓ɟ"
+                )
+                .unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("颌醷Ⱏ錮混㯺昁ᨹ臇愩秐ዿ篝陪㲫⎘瞙址㿅痓驴ꂦ䕝蜻骸䎹邭鰍ꕡ䅢痣䏐刽剨㬏䏗眫㢵ꉣ陒湺㧈䐟").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("璚銷ဈ⣺騁⍤錞䓆溔菉絽鸰敐㤉笤㷼䭬鍎桵嬦㓵烐馰阂膳罫㮜揄◭鑣鵖鲟㵓䨽ቴ註佂渭諈┆⦲㣮霥涖䒹蹏ᔩ籯㙒耣ᆟʟ").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("㸯ᔖ䞘㬾⢄㟙顨黠ꁿ㰀雳见郧紣㩬䤄ꈘ椎渺鴚瞃稴軻┹傔睋藲槎㸙㴕羜㧜椃奵饆柺蠟唞牭⩋䒷噚递ꉋ裶㾂䓘㢍恅䩔址鮉䣎栴ꎦ烂㨃譪螔䛏").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("湮迨罻韂គ儝硔沃䜽ꆙ䣬纄褯唳萾惓熗妕䌀闕掴橭烱娇漣孤榼垦砒蘂㠬文肓敐䑹刈霠畣⚷㹸啻稺痈醈▝㵨衼蓉㷌㥨麔欤盁⪽蒬騗Ꜷ锰详㜟").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("ޘꆅ瑝蝫嬨修ꃧ塳曤浲ᑈ繃颕鈢▛蝲溘䕤劫曖躡鄟獓飄荼臛韨欋庼䉥娦峁暘䒶瘦⩒葮佟䶁颃峻䌿㕯媄縡鳙斩卺魭饲轍ꄱ賑ꏏ䧎䩳ᒦ䶛礫抌ꀱ骣瞠㚿㕉現暧⠓ɧ").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("靪䜃䅃襡枅漎歬暮羸帵鸊婤㹗䚫鶬ꄰ駏ꆊ䣓觱鰒隗碂ꌀ淠鞼䩄㚭㭽酯ꐼ溛䕭㐘溣肿狋拔㸵㩈嵪遱櫠鮗䋒咎◳圗䮾轝荔獩䗳肍䏾㘺嚜䧩䏻胇鰴ꇩ拗勐臯ꂟ㪪帝殐䶔責慍攈ꅻ虹盪驏").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_filename("傱䡴磌燝槰߇呧枊爅戜♹▔娓晖爃欴ꆄ䯓俚Ԝ⪛辒亴␄㪯╲胞葉䦊玥䉊霨燓崑鲻⪓繏笵胅劈脫㓪㑈挑汥呃瞼塩㔝乱嫂唖ꈀ䟮有鞯濘䋱ꖅ┝桄㶩蜿⛚困滔穈䳉阕抛㦖㸐殿網槄钦㙗沏䞡⣽瀘嬽ꋳ僊褫Ƈ").unwrap(),
+            "testfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilenametestfilename"
+        );
+    }
+
     #[test]
     fn test_encrypt_path() {
         let cipher = Cipher::new("testpassword", None);
@@ -175,6 +652,118 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encrypt_filename_obfuscate() {
+        let cipher = Cipher::with_keys([0; 32], [0; 32], [0; 16])
+            .with_filename_encryption(FilenameEncryption::Obfuscate);
+
+        assert_eq!(cipher.encrypt_filename(""), "");
+        assert_eq!(cipher.encrypt_filename("1"), "49.6");
+        assert_eq!(cipher.encrypt_filename("12"), "99.23");
+        assert_eq!(cipher.encrypt_filename("a"), "97.x");
+        assert_eq!(cipher.encrypt_filename("!"), "33.!!");
+        assert_eq!(cipher.encrypt_filename("\u{e9}"), "233.\u{b5}");
+        assert_eq!(cipher.encrypt_dirname("1"), "49.6");
+    }
+
+    #[test]
+    fn test_decrypt_filename_obfuscate() {
+        let cipher = Cipher::with_keys([0; 32], [0; 32], [0; 16])
+            .with_filename_encryption(FilenameEncryption::Obfuscate);
+
+        assert_eq!(cipher.decrypt_filename("49.6").unwrap(), "1");
+        assert_eq!(cipher.decrypt_filename("97.x").unwrap(), "a");
+        assert_eq!(cipher.decrypt_filename("33.!!").unwrap(), "!");
+        assert_eq!(cipher.decrypt_filename("!.test").unwrap(), "test");
+        assert_eq!(
+            cipher.decrypt_filename("test"),
+            Err(DecryptFilenameError::NotAnEncryptedFile)
+        );
+        assert_eq!(
+            cipher.decrypt_filename("x.test"),
+            Err(DecryptFilenameError::NotAnEncryptedFile)
+        );
+        assert_eq!(
+            cipher.decrypt_filename("33.!"),
+            Err(DecryptFilenameError::NotAnEncryptedFile)
+        );
+
+        let cipher = Cipher::new("testpassword", None)
+            .with_filename_encryption(FilenameEncryption::Obfuscate);
+
+        for name in [
+            "testfilename",
+            "Test File 1.txt",
+            "!important!",
+            "\u{e9}t\u{e9}",
+            "\u{17e}aba",
+            "\u{1f600}.png",
+        ] {
+            let encrypted = cipher.encrypt_filename(name);
+
+            assert_ne!(encrypted, name);
+            assert_eq!(cipher.decrypt_filename(&encrypted).unwrap(), name);
+        }
+    }
+
+    #[test]
+    fn test_filename_encryption_off() {
+        let cipher =
+            Cipher::new("testpassword", None).with_filename_encryption(FilenameEncryption::Off);
+
+        assert_eq!(cipher.encrypt_filename("file.txt"), "file.txt.bin");
+        assert_eq!(cipher.encrypt_dirname("dir"), "dir");
+        assert_eq!(cipher.encrypt_path("/dir/sub"), "/dir/sub");
+        assert_eq!(
+            cipher.encrypt_file_path("/dir/file.txt"),
+            "/dir/file.txt.bin"
+        );
+        assert_eq!(cipher.decrypt_filename("file.txt.bin").unwrap(), "file.txt");
+        assert_eq!(
+            cipher.decrypt_filename("file.txt"),
+            Err(DecryptFilenameError::NotAnEncryptedFile)
+        );
+        assert_eq!(cipher.decrypt_dirname("dir").unwrap(), "dir");
+        assert_eq!(cipher.decrypt_path("/dir/sub").unwrap(), "/dir/sub");
+        assert_eq!(
+            cipher.decrypt_file_path("/dir/file.txt.bin").unwrap(),
+            "/dir/file.txt"
+        );
+    }
+
+    #[test]
+    fn test_directory_name_encryption_disabled() {
+        let cipher = Cipher::new("testpassword", None).with_directory_name_encryption(false);
+
+        assert_eq!(cipher.encrypt_dirname("testfilename"), "testfilename");
+        assert_eq!(
+            cipher.encrypt_filename("testfilename"),
+            "mvedi866srqc97sl5948oaej2g"
+        );
+        assert_eq!(
+            cipher.encrypt_path("/testfilename/testfilename"),
+            "/testfilename/testfilename"
+        );
+        assert_eq!(
+            cipher.encrypt_file_path("/testfilename/testfilename"),
+            "/testfilename/mvedi866srqc97sl5948oaej2g"
+        );
+        assert_eq!(
+            cipher.decrypt_dirname("testfilename").unwrap(),
+            "testfilename"
+        );
+        assert_eq!(
+            cipher.decrypt_path("/testfilename/testfilename").unwrap(),
+            "/testfilename/testfilename"
+        );
+        assert_eq!(
+            cipher
+                .decrypt_file_path("/testfilename/mvedi866srqc97sl5948oaej2g")
+                .unwrap(),
+            "/testfilename/testfilename"
+        );
+    }
+
     #[test]
     fn test_decrypt_filename() {
         // tested with rclone 1.60
@@ -279,6 +868,89 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_decrypt_reader_at_block() {
+        block_on(async {
+            let cipher = Cipher::new("testpassword", None);
+
+            let data = (0..(BLOCK_DATA_SIZE * 2 + 100))
+                .map(|i| (i % 251) as u8)
+                .collect::<Vec<u8>>();
+
+            let mut encrypted = Vec::new();
+
+            cipher.encrypt_data(&data, &mut encrypted).await.unwrap();
+
+            let nonce = decrypt_header(&encrypted).unwrap();
+
+            let offset = BLOCK_DATA_SIZE as i64 + 10;
+            let range = encrypted_range(offset, Some(20));
+            let start = range.offset as usize;
+            let end = start + range.length.unwrap() as usize;
+
+            let mut decrypted = Vec::new();
+
+            cipher
+                .decrypt_reader_at_block(&encrypted[start..end], nonce, range.block_index)
+                .with_skip(range.skip)
+                .take(20)
+                .read_to_end(&mut decrypted)
+                .await
+                .unwrap();
+
+            assert_eq!(decrypted, &data[offset as usize..offset as usize + 20]);
+        })
+    }
+
+    #[test]
+    fn test_encrypt_reader_at_block() {
+        block_on(async {
+            let cipher = Cipher::new("testpassword", None);
+
+            let data = (0..(BLOCK_DATA_SIZE * 2 + 100))
+                .map(|i| (i % 251) as u8)
+                .collect::<Vec<u8>>();
+
+            let mut encrypted = Vec::new();
+
+            cipher.encrypt_data(&data, &mut encrypted).await.unwrap();
+
+            let nonce = decrypt_header(&encrypted).unwrap();
+
+            for offset in [
+                0,
+                5,
+                FILE_HEADER_SIZE,
+                FILE_HEADER_SIZE + 10,
+                FILE_HEADER_SIZE + BLOCK_SIZE,
+                FILE_HEADER_SIZE + 2 * BLOCK_SIZE + 20,
+            ] {
+                let position = encrypted_position(offset as i64);
+                let reader = &data[position.plaintext_offset as usize..];
+
+                let mut resumed = Vec::new();
+
+                if position.includes_header {
+                    cipher
+                        .encrypt_reader_with_nonce(reader, nonce.clone())
+                        .with_skip(position.skip)
+                        .read_to_end(&mut resumed)
+                        .await
+                        .unwrap();
+                } else {
+                    cipher
+                        .encrypt_reader_at_block(reader, nonce.clone(), position.block_index)
+                        .with_skip(position.skip)
+                        .read_to_end(&mut resumed)
+                        .await
+                        .unwrap();
+                }
+
+                assert_eq!(resumed, &encrypted[offset..]);
+            }
+        })
+    }
+
     #[test]
     fn test_decrypt_data() {
         block_on(async {
@@ -300,4 +972,20 @@ mod tests {
             assert_eq!(std::str::from_utf8(&decrypted).unwrap(), "testdata");
         })
     }
+
+    #[test]
+    fn test_encrypt_value() {
+        let cipher = Cipher::new("testpassword", None);
+        let other_cipher = Cipher::new("otherpassword", None);
+
+        let encrypted = cipher.encrypt_value("testvalue");
+
+        assert_ne!(encrypted, cipher.encrypt_value("testvalue"));
+        assert_eq!(
+            cipher.decrypt_value(&encrypted).as_deref(),
+            Some("testvalue")
+        );
+        assert_eq!(other_cipher.decrypt_value(&encrypted), None);
+        assert_eq!(cipher.decrypt_value("invalid"), None);
+    }
 }
@@ -8,6 +8,32 @@ pub struct DerivedKeys {
     pub name_tweak: [u8; NAME_CIPHER_BLOCK_SIZE],
 }
 
+impl DerivedKeys {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(KEY_LEN);
+
+        bytes.extend_from_slice(&self.data_key);
+        bytes.extend_from_slice(&self.name_key);
+        bytes.extend_from_slice(&self.name_tweak);
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != KEY_LEN {
+            return None;
+        }
+
+        Some(Self {
+            data_key: bytes[0..DATA_KEY_LEN].try_into().unwrap(),
+            name_key: bytes[DATA_KEY_LEN..DATA_KEY_LEN + NAME_KEY_LEN]
+                .try_into()
+                .unwrap(),
+            name_tweak: bytes[DATA_KEY_LEN + NAME_KEY_LEN..].try_into().unwrap(),
+        })
+    }
+}
+
 pub fn derive_keys(password: &str, salt: Option<&str>) -> DerivedKeys {
     let password_bytes = password.as_bytes();
     let salt_bytes = match &salt {
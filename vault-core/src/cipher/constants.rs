@@ -9,6 +9,10 @@ pub const NAME_KEY_LEN: usize = 32;
 pub const NAME_CIPHER_BLOCK_SIZE: usize = 16;
 pub const KEY_LEN: usize = DATA_KEY_LEN + NAME_KEY_LEN + NAME_CIPHER_BLOCK_SIZE;
 
+/// Suffix added to file names when filename encryption is off. Same as rclone
+/// default
+pub const ENCRYPTED_SUFFIX: &str = ".bin";
+
 pub const FILE_MAGIC: &[u8] = b"RCLONE\x00\x00";
 pub const FILE_MAGIC_SIZE: usize = FILE_MAGIC.len();
 pub const FILE_NONCE_SIZE: usize = 24;
@@ -5,8 +5,10 @@ use std::sync::{Arc, Mutex};
 use xsalsa20poly1305::aead::{AeadInPlace, KeyInit};
 use xsalsa20poly1305::XSalsa20Poly1305;
 
-use super::constants::{BLOCK_DATA_SIZE, BLOCK_HEADER_SIZE, BLOCK_SIZE, FILE_HEADER_SIZE};
-use super::errors::DecryptSizeError;
+use super::constants::{
+    BLOCK_DATA_SIZE, BLOCK_HEADER_SIZE, BLOCK_SIZE, FILE_HEADER_SIZE, FILE_MAGIC, FILE_MAGIC_SIZE,
+};
+use super::errors::{DecryptHeaderError, DecryptSizeError};
 use super::nonce::Nonce;
 use super::CipherError;
 
@@ -78,6 +80,111 @@ pub fn decrypt_size(size: i64) -> Result<i64, DecryptSizeError> {
     Ok(decrypted_size)
 }
 
+/// decrypt_header parses the file header and returns the file nonce
+pub fn decrypt_header(header: &[u8]) -> Result<Nonce, DecryptHeaderError> {
+    if header.len() < FILE_HEADER_SIZE {
+        return Err(DecryptHeaderError::EncryptedFileTooShort);
+    }
+
+    if &header[..FILE_MAGIC_SIZE] != FILE_MAGIC {
+        return Err(DecryptHeaderError::EncryptedBadMagic);
+    }
+
+    Ok(Nonce::new(
+        header[FILE_MAGIC_SIZE..FILE_HEADER_SIZE]
+            .try_into()
+            .unwrap(),
+    ))
+}
+
+/// EncryptedRange describes the part of the encrypted file that has to be read
+/// to decrypt a plaintext range
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedRange {
+    /// index of the first block that has to be decrypted
+    pub block_index: u64,
+    /// offset in the encrypted file. if the range starts in the first block
+    /// the offset is 0 and the file header is included
+    pub offset: i64,
+    /// encrypted length, None means until the end of the file
+    pub length: Option<i64>,
+    /// number of plaintext bytes to discard from the first decrypted block
+    pub skip: u64,
+}
+
+impl EncryptedRange {
+    pub fn includes_header(&self) -> bool {
+        self.offset == 0
+    }
+}
+
+/// encrypted_range calculates the encrypted range of blocks needed to decrypt
+/// the plaintext range starting at offset. length None means until the end of
+/// the file
+pub fn encrypted_range(offset: i64, length: Option<i64>) -> EncryptedRange {
+    let block_index = offset / BLOCK_DATA_SIZE as i64;
+    let skip = (offset % BLOCK_DATA_SIZE as i64) as u64;
+
+    let encrypted_offset = match block_index {
+        0 => 0,
+        _ => FILE_HEADER_SIZE as i64 + block_index * BLOCK_SIZE as i64,
+    };
+
+    let encrypted_length = length.map(|length| {
+        let end_block_index = if length > 0 {
+            (offset + length - 1) / BLOCK_DATA_SIZE as i64
+        } else {
+            block_index
+        };
+
+        FILE_HEADER_SIZE as i64 + (end_block_index + 1) * BLOCK_SIZE as i64 - encrypted_offset
+    });
+
+    EncryptedRange {
+        block_index: block_index as u64,
+        offset: encrypted_offset,
+        length: encrypted_length,
+        skip,
+    }
+}
+
+/// EncryptedPosition describes where encryption has to be restarted to
+/// continue writing the encrypted file at an offset
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedPosition {
+    /// index of the first block that has to be encrypted
+    pub block_index: u64,
+    /// plaintext offset of the first block
+    pub plaintext_offset: i64,
+    /// true if the file header has to be written before the first block
+    pub includes_header: bool,
+    /// number of encrypted bytes to discard
+    pub skip: u64,
+}
+
+/// encrypted_position calculates the position from which the encrypted file
+/// can be continued at the encrypted offset
+pub fn encrypted_position(offset: i64) -> EncryptedPosition {
+    if offset < FILE_HEADER_SIZE as i64 {
+        return EncryptedPosition {
+            block_index: 0,
+            plaintext_offset: 0,
+            includes_header: true,
+            skip: offset as u64,
+        };
+    }
+
+    let block_index = (offset - FILE_HEADER_SIZE as i64) / BLOCK_SIZE as i64;
+    let skip = (offset - FILE_HEADER_SIZE as i64) % BLOCK_SIZE as i64;
+
+    EncryptedPosition {
+        block_index: block_index as u64,
+        plaintext_offset: block_index * BLOCK_DATA_SIZE as i64,
+        includes_header: false,
+        skip: skip as u64,
+    }
+}
+
 pub fn decrypt_on_progress(
     encrypted_on_progress: Box<dyn Fn(usize) + Send + Sync>,
 ) -> Box<dyn Fn(usize) + Send + Sync> {
@@ -104,3 +211,136 @@ pub fn decrypt_on_progress(
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::{
+        constants::{BLOCK_DATA_SIZE, BLOCK_SIZE, FILE_HEADER_SIZE, FILE_MAGIC},
+        errors::DecryptHeaderError,
+        nonce::Nonce,
+    };
+
+    use super::{
+        decrypt_header, encrypted_position, encrypted_range, EncryptedPosition, EncryptedRange,
+    };
+
+    #[test]
+    fn test_decrypt_header() {
+        let nonce = Nonce::new(&[
+            1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        ]);
+
+        let mut header = FILE_MAGIC.to_vec();
+        header.extend_from_slice(nonce.as_slice());
+
+        assert_eq!(decrypt_header(&header), Ok(nonce));
+        assert_eq!(
+            decrypt_header(&header[..10]),
+            Err(DecryptHeaderError::EncryptedFileTooShort)
+        );
+
+        header[0] = b'X';
+
+        assert_eq!(
+            decrypt_header(&header),
+            Err(DecryptHeaderError::EncryptedBadMagic)
+        );
+    }
+
+    #[test]
+    fn test_encrypted_range() {
+        let header = FILE_HEADER_SIZE as i64;
+        let block_data = BLOCK_DATA_SIZE as i64;
+        let block = BLOCK_SIZE as i64;
+
+        assert_eq!(
+            encrypted_range(0, None),
+            EncryptedRange {
+                block_index: 0,
+                offset: 0,
+                length: None,
+                skip: 0,
+            }
+        );
+        assert_eq!(
+            encrypted_range(10, Some(20)),
+            EncryptedRange {
+                block_index: 0,
+                offset: 0,
+                length: Some(header + block),
+                skip: 10,
+            }
+        );
+        assert_eq!(
+            encrypted_range(block_data - 1, Some(2)),
+            EncryptedRange {
+                block_index: 0,
+                offset: 0,
+                length: Some(header + 2 * block),
+                skip: BLOCK_DATA_SIZE as u64 - 1,
+            }
+        );
+        assert_eq!(
+            encrypted_range(block_data, Some(block_data)),
+            EncryptedRange {
+                block_index: 1,
+                offset: header + block,
+                length: Some(block),
+                skip: 0,
+            }
+        );
+        assert_eq!(
+            encrypted_range(3 * block_data + 5, None),
+            EncryptedRange {
+                block_index: 3,
+                offset: header + 3 * block,
+                length: None,
+                skip: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_encrypted_position() {
+        let header = FILE_HEADER_SIZE as i64;
+        let block_data = BLOCK_DATA_SIZE as i64;
+        let block = BLOCK_SIZE as i64;
+
+        assert_eq!(
+            encrypted_position(0),
+            EncryptedPosition {
+                block_index: 0,
+                plaintext_offset: 0,
+                includes_header: true,
+                skip: 0,
+            }
+        );
+        assert_eq!(
+            encrypted_position(10),
+            EncryptedPosition {
+                block_index: 0,
+                plaintext_offset: 0,
+                includes_header: true,
+                skip: 10,
+            }
+        );
+        assert_eq!(
+            encrypted_position(header),
+            EncryptedPosition {
+                block_index: 0,
+                plaintext_offset: 0,
+                includes_header: false,
+                skip: 0,
+            }
+        );
+        assert_eq!(
+            encrypted_position(header + 2 * block + 5),
+            EncryptedPosition {
+                block_index: 2,
+                plaintext_offset: 2 * block_data,
+                includes_header: false,
+                skip: 5,
+            }
+        );
+    }
+}
@@ -9,7 +9,7 @@ use std::sync::Arc;
 use xsalsa20poly1305::XSalsa20Poly1305;
 
 use super::constants::{
-    BLOCK_HEADER_SIZE, BLOCK_SIZE, FILE_MAGIC, FILE_MAGIC_SIZE, FILE_NONCE_SIZE,
+    BLOCK_HEADER_SIZE, BLOCK_SIZE, FILE_HEADER_SIZE, FILE_MAGIC, FILE_MAGIC_SIZE, FILE_NONCE_SIZE,
 };
 use super::data_cipher::decrypt_block;
 use super::nonce::Nonce;
@@ -42,7 +42,9 @@ pin_project! {
         #[pin]
         inner: R,
         state: DecryptReaderState,
-        data_cipher: Arc<XSalsa20Poly1305>
+        data_cipher: Arc<XSalsa20Poly1305>,
+        skip: u64,
+        block_index: u64,
     }
 }
 
@@ -55,8 +57,42 @@ impl<R> DecryptReader<R> {
                 pos: 0,
             },
             data_cipher,
+            skip: 0,
+            block_index: 0,
         }
     }
+
+    /// Create a reader that starts decrypting at block_index. inner must be
+    /// positioned at the start of the block and nonce must be the file nonce
+    /// read from the file header.
+    pub fn new_at_block(
+        inner: R,
+        data_cipher: Arc<XSalsa20Poly1305>,
+        nonce: Nonce,
+        block_index: u64,
+    ) -> Self {
+        let mut nonce = nonce;
+
+        nonce.add(block_index);
+
+        Self {
+            inner,
+            state: DecryptReaderState::ReadingCiphertext {
+                nonce,
+                buffer: [0; BLOCK_SIZE],
+                pos: 0,
+            },
+            data_cipher,
+            skip: 0,
+            block_index,
+        }
+    }
+
+    /// Discard the first skip bytes of the plaintext
+    pub fn with_skip(mut self, skip: u64) -> Self {
+        self.skip = skip;
+        self
+    }
 }
 
 impl<R: AsyncRead> AsyncRead for DecryptReader<R> {
@@ -127,19 +163,45 @@ impl<R: AsyncRead> AsyncRead for DecryptReader<R> {
                         let decrypted =
                             match decrypt_block(this.data_cipher, &nonce, &buffer[..*pos]) {
                                 Ok(decrypted) => decrypted,
+                                Err(CipherError::DecryptionError) => {
+                                    return Poll::Ready(Err(CipherError::BlockDecryptionError {
+                                        block_index: *this.block_index,
+                                        offset: FILE_HEADER_SIZE as u64
+                                            + *this.block_index * BLOCK_SIZE as u64,
+                                    }
+                                    .into()))
+                                }
                                 Err(e) => return Poll::Ready(Err(e.into())),
                             };
 
                         nonce.increment();
 
+                        *this.block_index += 1;
+
+                        let skip = cmp::min(*this.skip, decrypted.len() as u64);
+
+                        *this.skip -= skip;
+
+                        let skip = skip as usize;
+
                         *this.state = DecryptReaderState::WritingPlaintext {
                             nonce: mem::take(nonce),
                             buffer: decrypted,
-                            pos: 0,
+                            pos: skip,
                         };
                     }
                 }
                 DecryptReaderState::WritingPlaintext { nonce, buffer, pos } => {
+                    if *pos == buffer.len() {
+                        *this.state = DecryptReaderState::ReadingCiphertext {
+                            nonce: mem::take(nonce),
+                            buffer: [0; BLOCK_SIZE],
+                            pos: 0,
+                        };
+
+                        continue;
+                    }
+
                     let n = cmp::min(buf.len(), buffer.len() - *pos);
 
                     buf[..n].copy_from_slice(&buffer[*pos..*pos + n]);
@@ -171,10 +233,11 @@ mod tests {
     use futures::{channel::mpsc, AsyncRead};
     use xsalsa20poly1305::XSalsa20Poly1305;
 
-    use crate::cipher::constants::FILE_MAGIC;
+    use crate::cipher::constants::{BLOCK_DATA_SIZE, FILE_MAGIC};
     use crate::cipher::data_cipher::{encrypt_block, get_data_cipher};
     use crate::cipher::nonce::Nonce;
     use crate::cipher::test_helpers::{assert_reader_pending, assert_reader_ready};
+    use crate::cipher::CipherError;
 
     use super::DecryptReader;
 
@@ -246,4 +309,81 @@ mod tests {
 
         assert_eq!(res, b"test");
     }
+
+    #[test]
+    fn test_decrypt_reader_at_block() {
+        let data_cipher = get_dummy_data_cipher();
+        let nonce = get_dummy_nonce();
+        let mut block_nonce = nonce.clone();
+        block_nonce.add(3);
+
+        let (tx, rx) = mpsc::unbounded::<Result<Vec<u8>>>();
+        let reader = rx.into_async_read();
+
+        let mut r = DecryptReader::new_at_block(reader, data_cipher.clone(), nonce, 3).with_skip(2);
+
+        assert_reader_pending!(r);
+
+        tx.unbounded_send(Ok(
+            encrypt_block(&data_cipher, &block_nonce, b"test").unwrap()
+        ))
+        .unwrap();
+
+        tx.close_channel();
+
+        let res = assert_reader_ready!(r).unwrap();
+        assert_eq!(res, b"st");
+
+        let res = assert_reader_ready!(r).unwrap();
+        assert_eq!(res.len(), 0);
+    }
+
+    #[test]
+    fn test_decrypt_reader_block_decryption_error() {
+        let data_cipher = get_dummy_data_cipher();
+        let nonce = get_dummy_nonce();
+        let mut block_nonce = nonce.clone();
+        block_nonce.increment();
+
+        let (tx, rx) = mpsc::unbounded::<Result<Vec<u8>>>();
+        let reader = rx.into_async_read();
+
+        let mut r = DecryptReader::new(reader, data_cipher.clone());
+
+        tx.unbounded_send(Ok(FILE_MAGIC.to_vec())).unwrap();
+        tx.unbounded_send(Ok(nonce.as_slice().to_vec())).unwrap();
+        tx.unbounded_send(Ok(encrypt_block(
+            &data_cipher,
+            &nonce,
+            &vec![0; BLOCK_DATA_SIZE],
+        )
+        .unwrap()))
+            .unwrap();
+
+        let mut corrupted_block = encrypt_block(&data_cipher, &block_nonce, b"test").unwrap();
+        corrupted_block[0] ^= 1;
+
+        tx.unbounded_send(Ok(corrupted_block)).unwrap();
+
+        tx.close_channel();
+
+        let res = assert_reader_ready!(r, BLOCK_DATA_SIZE).unwrap();
+        assert_eq!(res.len(), BLOCK_DATA_SIZE);
+
+        let err = assert_reader_ready!(r).unwrap_err();
+
+        match err
+            .get_ref()
+            .and_then(|err| err.downcast_ref::<CipherError>())
+        {
+            Some(CipherError::BlockDecryptionError {
+                block_index,
+                offset,
+            }) => {
+                assert_eq!(*block_index, 1);
+                assert_eq!(*offset, 32 + 65552);
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+    }
 }
@@ -9,7 +9,9 @@ use futures::{ready, AsyncRead};
 use pin_project_lite::pin_project;
 use xsalsa20poly1305::XSalsa20Poly1305;
 
-use super::constants::{BLOCK_DATA_SIZE, FILE_MAGIC, FILE_MAGIC_SIZE, FILE_NONCE_SIZE};
+use super::constants::{
+    BLOCK_DATA_SIZE, FILE_HEADER_SIZE, FILE_MAGIC, FILE_MAGIC_SIZE, FILE_NONCE_SIZE,
+};
 use super::data_cipher::encrypt_block;
 use super::nonce::Nonce;
 
@@ -40,7 +42,8 @@ pin_project! {
         #[pin]
         inner: R,
         state: EncryptReaderState,
-        data_cipher: Arc<XSalsa20Poly1305>
+        data_cipher: Arc<XSalsa20Poly1305>,
+        skip: u64,
     }
 }
 
@@ -50,8 +53,68 @@ impl<R> EncryptReader<R> {
             inner,
             state: EncryptReaderState::WritingMagic { nonce, pos: 0 },
             data_cipher,
+            skip: 0,
         }
     }
+
+    /// Create a reader that starts encrypting at block_index without writing
+    /// the file header. inner must be positioned at the start of the block and
+    /// nonce must be the file nonce.
+    pub fn new_at_block(
+        inner: R,
+        data_cipher: Arc<XSalsa20Poly1305>,
+        nonce: Nonce,
+        block_index: u64,
+    ) -> Self {
+        let mut nonce = nonce;
+
+        nonce.add(block_index);
+
+        Self {
+            inner,
+            state: EncryptReaderState::ReadingPlaintext {
+                nonce,
+                buffer: [0; BLOCK_DATA_SIZE],
+                pos: 0,
+            },
+            data_cipher,
+            skip: 0,
+        }
+    }
+
+    /// Discard the first skip bytes of the ciphertext, including the file
+    /// header if it has not been written yet
+    pub fn with_skip(mut self, skip: u64) -> Self {
+        let mut skip = skip;
+
+        if let EncryptReaderState::WritingMagic { nonce, pos: 0 } = &mut self.state {
+            let nonce = mem::take(nonce);
+            let header_skip = cmp::min(skip, FILE_HEADER_SIZE as u64) as usize;
+
+            skip -= header_skip as u64;
+
+            self.state = if header_skip < FILE_MAGIC_SIZE {
+                EncryptReaderState::WritingMagic {
+                    nonce,
+                    pos: header_skip,
+                }
+            } else if header_skip < FILE_HEADER_SIZE {
+                EncryptReaderState::WritingNonce {
+                    nonce,
+                    pos: header_skip - FILE_MAGIC_SIZE,
+                }
+            } else {
+                EncryptReaderState::ReadingPlaintext {
+                    nonce,
+                    buffer: [0; BLOCK_DATA_SIZE],
+                    pos: 0,
+                }
+            };
+        }
+
+        self.skip = skip;
+        self
+    }
 }
 
 impl<R: AsyncRead> AsyncRead for EncryptReader<R> {
@@ -115,14 +178,30 @@ impl<R: AsyncRead> AsyncRead for EncryptReader<R> {
 
                         nonce.increment();
 
+                        let skip = cmp::min(*this.skip, encrypted.len() as u64);
+
+                        *this.skip -= skip;
+
+                        let skip = skip as usize;
+
                         *this.state = EncryptReaderState::WritingCiphertext {
                             nonce: mem::take(nonce),
                             buffer: encrypted,
-                            pos: 0,
+                            pos: skip,
                         };
                     }
                 }
                 EncryptReaderState::WritingCiphertext { nonce, buffer, pos } => {
+                    if *pos == buffer.len() {
+                        *this.state = EncryptReaderState::ReadingPlaintext {
+                            nonce: mem::take(nonce),
+                            buffer: [0; BLOCK_DATA_SIZE],
+                            pos: 0,
+                        };
+
+                        continue;
+                    }
+
                     let n = cmp::min(buf.len(), buffer.len() - *pos);
 
                     buf[..n].copy_from_slice(&buffer[*pos..*pos + n]);
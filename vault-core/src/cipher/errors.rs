@@ -13,6 +13,8 @@ pub enum CipherError {
     EncryptionError,
     #[error("decryption error")]
     DecryptionError,
+    #[error("decryption error in block {block_index} at offset {offset}")]
+    BlockDecryptionError { block_index: u64, offset: u64 },
     #[error("generate nonce error: {0:?}")]
     GenerateNonceError(rand_core::Error),
 }
@@ -29,6 +31,14 @@ impl Into<std::io::Error> for CipherError {
     }
 }
 
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum DecryptHeaderError {
+    #[error("file is too short to be decrypted")]
+    EncryptedFileTooShort,
+    #[error("not an encrypted file - bad magic string")]
+    EncryptedBadMagic,
+}
+
 #[derive(Debug, Error, Clone, PartialEq, Eq)]
 pub enum DecryptSizeError {
     #[error("file is too short to be decrypted")]
@@ -45,4 +55,6 @@ pub enum DecryptFilenameError {
     DecryptError,
     #[error("unicode error: {0}")]
     UnicodeError(String),
+    #[error("not an encrypted file")]
+    NotAnEncryptedFile,
 }
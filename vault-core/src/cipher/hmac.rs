@@ -0,0 +1,48 @@
+use sha2::{Digest, Sha256};
+
+const BLOCK_SIZE: usize = 64;
+
+/// HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut block_key = [0u8; BLOCK_SIZE];
+
+    if key.len() > BLOCK_SIZE {
+        let hashed_key = Sha256::digest(key);
+        block_key[..hashed_key.len()].copy_from_slice(&hashed_key);
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.input(block_key.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+    inner.input(data);
+
+    let mut outer = Sha256::new();
+    outer.input(block_key.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>());
+    outer.input(inner.result());
+
+    outer.result().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use data_encoding::HEXLOWER;
+
+    use super::hmac_sha256;
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231 test cases 2 and 6
+        assert_eq!(
+            HEXLOWER.encode(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            HEXLOWER.encode(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+}
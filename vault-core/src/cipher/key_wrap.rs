@@ -0,0 +1,65 @@
+use rand_core::{OsRng, RngCore};
+
+use super::constants::FILE_NONCE_SIZE;
+use super::data_cipher::{decrypt_block, encrypt_block, get_data_cipher};
+use super::nonce::Nonce;
+use super::CipherError;
+
+pub const WRAP_KEY_LEN: usize = 32;
+
+pub fn generate_wrap_key() -> Result<[u8; WRAP_KEY_LEN], CipherError> {
+    let mut wrap_key = [0; WRAP_KEY_LEN];
+
+    (&mut OsRng).try_fill_bytes(&mut wrap_key)?;
+
+    Ok(wrap_key)
+}
+
+/// Encrypts key material with the wrap key. The random nonce is prepended to
+/// the ciphertext.
+pub fn wrap_key(wrap_key: &[u8; WRAP_KEY_LEN], key: &[u8]) -> Result<Vec<u8>, CipherError> {
+    let nonce = Nonce::new_random()?;
+
+    let ciphertext = encrypt_block(&get_data_cipher(wrap_key), &nonce, key)?;
+
+    let mut wrapped = Vec::with_capacity(FILE_NONCE_SIZE + ciphertext.len());
+    wrapped.extend_from_slice(nonce.as_slice());
+    wrapped.extend_from_slice(&ciphertext);
+
+    Ok(wrapped)
+}
+
+pub fn unwrap_key(wrap_key: &[u8; WRAP_KEY_LEN], wrapped: &[u8]) -> Result<Vec<u8>, CipherError> {
+    if wrapped.len() < FILE_NONCE_SIZE {
+        return Err(CipherError::DecryptionError);
+    }
+
+    let nonce = Nonce::new(wrapped[..FILE_NONCE_SIZE].try_into().unwrap());
+
+    decrypt_block(
+        &get_data_cipher(wrap_key),
+        &nonce,
+        &wrapped[FILE_NONCE_SIZE..],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_wrap_key, unwrap_key, wrap_key};
+
+    #[test]
+    fn test_wrap_unwrap_key() {
+        let key = [7u8; 80];
+        let wrap = generate_wrap_key().unwrap();
+
+        let wrapped = wrap_key(&wrap, &key).unwrap();
+
+        assert_ne!(&wrapped[24..], &key[..]);
+        assert_eq!(unwrap_key(&wrap, &wrapped).unwrap(), key);
+
+        let other_wrap = generate_wrap_key().unwrap();
+
+        assert!(unwrap_key(&other_wrap, &wrapped).is_err());
+        assert!(unwrap_key(&wrap, &wrapped[..10]).is_err());
+    }
+}
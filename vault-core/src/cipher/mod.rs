@@ -1,3 +1,4 @@
+pub mod base32768;
 pub mod cipher;
 pub mod cipher_keys;
 pub mod constants;
@@ -5,7 +6,10 @@ pub mod data_cipher;
 pub mod decrypt_reader;
 pub mod encrypt_reader;
 pub mod errors;
+pub mod hmac;
+pub mod key_wrap;
 pub mod name_cipher;
+pub mod name_obfuscate;
 pub mod nonce;
 pub mod random_password;
 #[cfg(test)]
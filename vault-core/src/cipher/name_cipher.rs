@@ -1,16 +1,107 @@
 use aes::Aes256;
-use data_encoding::BASE32HEX_NOPAD;
+use data_encoding::{BASE32HEX_NOPAD, BASE64URL_NOPAD};
 use eme_mode::{block_modes::BlockMode, block_padding::Pkcs7, Eme};
 
-use super::{constants::NAME_CIPHER_BLOCK_SIZE, errors::DecryptFilenameError};
+use super::{base32768, constants::NAME_CIPHER_BLOCK_SIZE, errors::DecryptFilenameError};
 
 type Aes256Eme = Eme<Aes256, Pkcs7>;
 
+/// Same as rclone filename_encryption option
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilenameEncryption {
+    Standard,
+    Obfuscate,
+    Off,
+}
+
+impl Default for FilenameEncryption {
+    fn default() -> Self {
+        Self::Standard
+    }
+}
+
+impl FilenameEncryption {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "standard" => Some(Self::Standard),
+            "obfuscate" => Some(Self::Obfuscate),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::Obfuscate => "obfuscate",
+            Self::Off => "off",
+        }
+    }
+}
+
+/// Same as rclone filename_encoding option
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilenameEncoding {
+    Base32,
+    Base64,
+    Base32768,
+}
+
+impl Default for FilenameEncoding {
+    fn default() -> Self {
+        Self::Base32
+    }
+}
+
+impl FilenameEncoding {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "base32" => Some(Self::Base32),
+            "base64" => Some(Self::Base64),
+            "base32768" => Some(Self::Base32768),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Base32 => "base32",
+            Self::Base64 => "base64",
+            Self::Base32768 => "base32768",
+        }
+    }
+
+    pub fn encode(&self, data: &[u8]) -> String {
+        match self {
+            Self::Base32 => BASE32HEX_NOPAD.encode(data).to_lowercase(),
+            Self::Base64 => BASE64URL_NOPAD.encode(data),
+            Self::Base32768 => base32768::encode(data),
+        }
+    }
+
+    pub fn decode(&self, encoded: &str) -> Result<Vec<u8>, DecryptFilenameError> {
+        match self {
+            Self::Base32 => BASE32HEX_NOPAD
+                .decode(encoded.to_uppercase().as_bytes())
+                .map_err(|e| DecryptFilenameError::DecodeError(e.to_string())),
+            Self::Base64 => BASE64URL_NOPAD
+                .decode(encoded.as_bytes())
+                .map_err(|e| DecryptFilenameError::DecodeError(e.to_string())),
+            Self::Base32768 => base32768::decode(encoded)
+                .map_err(|e| DecryptFilenameError::DecodeError(e.to_string())),
+        }
+    }
+}
+
 pub fn get_name_cipher(name_key: &[u8], name_tweak: &[u8]) -> Aes256Eme {
     Aes256Eme::new_from_slices(&name_key, &name_tweak).unwrap()
 }
 
-pub fn encrypt_filename(name_cipher: Aes256Eme, plaintext: &str) -> String {
+pub fn encrypt_filename(
+    name_cipher: Aes256Eme,
+    filename_encoding: FilenameEncoding,
+    plaintext: &str,
+) -> String {
     let plaintext_bytes = plaintext.as_bytes();
 
     let pos = plaintext_bytes.len();
@@ -21,36 +112,19 @@ pub fn encrypt_filename(name_cipher: Aes256Eme, plaintext: &str) -> String {
 
     let encrypted = name_cipher.encrypt(&mut buffer, pos).unwrap();
 
-    BASE32HEX_NOPAD.encode(&encrypted).to_lowercase()
-}
-
-pub fn encrypt_path(name_cipher: Aes256Eme, plaintext: &str) -> String {
-    match plaintext {
-        "/" => plaintext.to_owned(),
-        _ => {
-            let parts: Vec<&str> = plaintext.split("/").skip(1).collect();
-            let mut encrypted_parts: Vec<String> = Vec::with_capacity(parts.len() + 1);
-            encrypted_parts.push(String::from(""));
-            for part in parts {
-                let encrypted_part = encrypt_filename(name_cipher.clone(), &part);
-                encrypted_parts.push(encrypted_part);
-            }
-            encrypted_parts.join("/")
-        }
-    }
+    filename_encoding.encode(&encrypted)
 }
 
 pub fn decrypt_filename(
     name_cipher: Aes256Eme,
+    filename_encoding: FilenameEncoding,
     ciphertext: &str,
 ) -> Result<String, DecryptFilenameError> {
     if ciphertext.is_empty() {
         return Ok(String::from(""));
     }
 
-    let mut name_encrypted_buf = BASE32HEX_NOPAD
-        .decode(ciphertext.to_uppercase().as_bytes())
-        .map_err(|e| DecryptFilenameError::DecodeError(e.to_string()))?;
+    let mut name_encrypted_buf = filename_encoding.decode(ciphertext)?;
 
     let decrypted = name_cipher
         .decrypt(name_encrypted_buf.as_mut_slice().into())
@@ -59,22 +133,3 @@ pub fn decrypt_filename(
     String::from_utf8(decrypted.to_vec())
         .map_err(|e| DecryptFilenameError::UnicodeError(e.to_string()))
 }
-
-pub fn decrypt_path(
-    name_cipher: Aes256Eme,
-    ciphertext: &str,
-) -> Result<String, DecryptFilenameError> {
-    match ciphertext {
-        "/" => Ok(ciphertext.to_owned()),
-        _ => {
-            let parts: Vec<&str> = ciphertext.split("/").skip(1).collect();
-            let mut decrypted_parts: Vec<String> = Vec::with_capacity(parts.len() + 1);
-            decrypted_parts.push(String::from(""));
-            for part in parts {
-                let decrypted_part = decrypt_filename(name_cipher.clone(), &part)?;
-                decrypted_parts.push(decrypted_part);
-            }
-            Ok(decrypted_parts.join("/"))
-        }
-    }
-}
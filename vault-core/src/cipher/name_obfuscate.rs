@@ -0,0 +1,164 @@
+use super::errors::DecryptFilenameError;
+
+const OBFUSCATE_QUOTE_CHAR: char = '!';
+
+/// Based on rclone implementation
+/// https://github.com/rclone/rclone/blob/7be9855a706d1e09504f17949a90c54cd56fb2a5/backend/crypt/cipher.go#L318
+fn name_key_dir(name_key: &[u8]) -> u32 {
+    name_key.iter().map(|x| *x as u32).sum()
+}
+
+pub fn obfuscate_filename(name_key: &[u8], plaintext: &str) -> String {
+    if plaintext.is_empty() {
+        return String::from("");
+    }
+
+    // calculate a simple rotation based on the filename and the name key
+    let name_dir = plaintext.chars().map(|c| c as u32).sum::<u32>() % 256;
+
+    let mut result = format!("{}.", name_dir);
+
+    let dir = name_dir + name_key_dir(name_key);
+
+    for c in plaintext.chars() {
+        let r = c as u32;
+
+        match c {
+            OBFUSCATE_QUOTE_CHAR => {
+                result.push(OBFUSCATE_QUOTE_CHAR);
+                result.push(OBFUSCATE_QUOTE_CHAR);
+            }
+            '0'..='9' => {
+                let this_dir = (dir % 9) + 1;
+                let new_r = '0' as u32 + (r - '0' as u32 + this_dir) % 10;
+
+                result.push(char::from_u32(new_r).unwrap());
+            }
+            'A'..='Z' | 'a'..='z' => {
+                // try to avoid trivial A->a mappings
+                let this_dir = dir % 25 + 1;
+
+                let mut pos = r - 'A' as u32;
+                if pos >= 26 {
+                    // lower case
+                    pos -= 6;
+                }
+                pos = (pos + this_dir) % 52;
+                if pos >= 26 {
+                    pos += 6;
+                }
+
+                result.push(char::from_u32('A' as u32 + pos).unwrap());
+            }
+            '\u{A0}'..='\u{FF}' => {
+                // latin 1 supplement
+                let this_dir = (dir % 95) + 1;
+                let new_r = 0xA0 + (r - 0xA0 + this_dir) % 96;
+
+                result.push(char::from_u32(new_r).unwrap());
+            }
+            _ if r >= 0x100 => {
+                let this_dir = (dir % 127) + 1;
+                let base = r - r % 256;
+                let new_r = base + (r - base + this_dir) % 256;
+
+                match char::from_u32(new_r) {
+                    Some(new_c) => result.push(new_c),
+                    None => {
+                        // not a valid char, quote the original instead
+                        result.push(OBFUSCATE_QUOTE_CHAR);
+                        result.push(c);
+                    }
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+pub fn deobfuscate_filename(
+    name_key: &[u8],
+    ciphertext: &str,
+) -> Result<String, DecryptFilenameError> {
+    if ciphertext.is_empty() {
+        return Ok(String::from(""));
+    }
+
+    let (num, ciphertext) = ciphertext
+        .split_once('.')
+        .ok_or(DecryptFilenameError::NotAnEncryptedFile)?;
+
+    if num == "!" {
+        // no rotation, the original was not valid unicode
+        return Ok(ciphertext.to_owned());
+    }
+
+    let name_dir = num
+        .parse::<u32>()
+        .map_err(|_| DecryptFilenameError::NotAnEncryptedFile)?;
+
+    let dir = name_dir + name_key_dir(name_key);
+
+    let mut result = String::with_capacity(ciphertext.len());
+
+    let mut in_quote = false;
+
+    for c in ciphertext.chars() {
+        let r = c as u32;
+
+        if in_quote {
+            result.push(c);
+            in_quote = false;
+
+            continue;
+        }
+
+        match c {
+            OBFUSCATE_QUOTE_CHAR => {
+                in_quote = true;
+            }
+            '0'..='9' => {
+                let this_dir = (dir % 9) + 1;
+                let new_r = '0' as u32 + (r - '0' as u32 + 10 - this_dir) % 10;
+
+                result.push(char::from_u32(new_r).unwrap());
+            }
+            'A'..='Z' | 'a'..='z' => {
+                let this_dir = dir % 25 + 1;
+
+                let mut pos = r - 'A' as u32;
+                if pos >= 26 {
+                    pos -= 6;
+                }
+                pos = (pos + 52 - this_dir) % 52;
+                if pos >= 26 {
+                    pos += 6;
+                }
+
+                result.push(char::from_u32('A' as u32 + pos).unwrap());
+            }
+            '\u{A0}'..='\u{FF}' => {
+                let this_dir = (dir % 95) + 1;
+                let new_r = 0xA0 + (r - 0xA0 + 96 - this_dir) % 96;
+
+                result.push(char::from_u32(new_r).unwrap());
+            }
+            _ if r >= 0x100 => {
+                let this_dir = (dir % 127) + 1;
+                let base = r - r % 256;
+                let new_r = base + (r - base + 256 - this_dir) % 256;
+
+                result.push(char::from_u32(new_r).ok_or(DecryptFilenameError::NotAnEncryptedFile)?);
+            }
+            _ => result.push(c),
+        }
+    }
+
+    if in_quote {
+        return Err(DecryptFilenameError::NotAnEncryptedFile);
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+
+use super::errors::DeviceKeyStoreError;
+
+/// Platform keystore that holds the device key used to wrap remembered repo
+/// keys. The wrapped keys live in SecureStorage, so the device key has to be
+/// kept somewhere else and should never be readable as raw bytes (e.g. a
+/// non-extractable WebCrypto key or an OS keychain entry).
+#[async_trait]
+pub trait DeviceKeyStore {
+    /// Encrypts key material with the device key. The device key is created
+    /// on first use.
+    async fn wrap_key(&self, key: &[u8]) -> Result<Vec<u8>, String>;
+    /// Fails with `DeviceKeyStoreError::KeyNotFound` if the device key does
+    /// not exist (e.g. site data was cleared)
+    async fn unwrap_key(&self, wrapped: &[u8]) -> Result<Vec<u8>, DeviceKeyStoreError>;
+}
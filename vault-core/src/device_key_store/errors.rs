@@ -0,0 +1,12 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum DeviceKeyStoreError {
+    /// The device key does not exist or it is not the key the data was
+    /// wrapped with (e.g. site data was cleared and a new key was created).
+    /// The wrapped data can never be unwrapped again.
+    #[error("device key not found")]
+    KeyNotFound,
+    #[error("{0}")]
+    Other(String),
+}
@@ -0,0 +1,45 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::cipher::key_wrap::{generate_wrap_key, unwrap_key, wrap_key, WRAP_KEY_LEN};
+
+use super::{DeviceKeyStore, DeviceKeyStoreError};
+
+/// Keeps the device key in memory, remembered repos are forgotten on restart.
+/// Used on platforms without a keystore and in tests.
+#[derive(Default)]
+pub struct MemoryDeviceKeyStore {
+    device_key: Mutex<Option<[u8; WRAP_KEY_LEN]>>,
+}
+
+impl MemoryDeviceKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl DeviceKeyStore for MemoryDeviceKeyStore {
+    async fn wrap_key(&self, key: &[u8]) -> Result<Vec<u8>, String> {
+        let mut device_key = self.device_key.lock().unwrap();
+
+        if device_key.is_none() {
+            *device_key = Some(generate_wrap_key().map_err(|err| err.to_string())?);
+        }
+
+        wrap_key(device_key.as_ref().unwrap(), key).map_err(|err| err.to_string())
+    }
+
+    async fn unwrap_key(&self, wrapped: &[u8]) -> Result<Vec<u8>, DeviceKeyStoreError> {
+        let device_key = self.device_key.lock().unwrap();
+
+        match device_key.as_ref() {
+            // the data was wrapped with a different device key
+            Some(device_key) => {
+                unwrap_key(device_key, wrapped).map_err(|_| DeviceKeyStoreError::KeyNotFound)
+            }
+            None => Err(DeviceKeyStoreError::KeyNotFound),
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod device_key_store;
+pub mod errors;
+pub mod memory_device_key_store;
+
+pub use self::device_key_store::DeviceKeyStore;
+pub use self::errors::DeviceKeyStoreError;
@@ -0,0 +1,54 @@
+use thiserror::Error;
+
+use crate::{
+    cipher::errors::{DecryptFilenameError, DecryptHeaderError, DecryptSizeError},
+    remote::RemoteError,
+    repo_files_read::errors::GetFilesReaderError,
+    repos::errors::{RepoLockedError, RepoNotFoundError},
+    user_error::UserError,
+};
+
+#[derive(Error, Debug, Clone, UserError)]
+pub enum DownloadError {
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("file not found")]
+    FileNotFound,
+    #[error("files empty")]
+    FilesEmpty,
+    #[error("invalid range")]
+    InvalidRange,
+    #[error("{0}")]
+    DecryptFilenameError(#[from] DecryptFilenameError),
+    #[error("{0}")]
+    DecryptSizeError(#[from] DecryptSizeError),
+    #[error("{0}")]
+    DecryptHeaderError(#[from] DecryptHeaderError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+    #[error("failed to read file: {0}")]
+    ReadError(String),
+    #[error("write error: {0}")]
+    WriteError(String),
+    #[error("download aborted")]
+    Aborted,
+}
+
+impl From<GetFilesReaderError> for DownloadError {
+    fn from(err: GetFilesReaderError) -> Self {
+        match err {
+            GetFilesReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetFilesReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            GetFilesReaderError::FileNotFound => Self::FileNotFound,
+            GetFilesReaderError::FilesEmpty => Self::FilesEmpty,
+            GetFilesReaderError::InvalidRange => Self::InvalidRange,
+            GetFilesReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            GetFilesReaderError::DecryptSizeError(err) => Self::DecryptSizeError(err),
+            GetFilesReaderError::DecryptHeaderError(err) => Self::DecryptHeaderError(err),
+            GetFilesReaderError::RemoteError(err) => Self::RemoteError(err),
+            GetFilesReaderError::ReadError(err) => Self::ReadError(err),
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::cmp::min;
+
+use crate::{
+    file_types::file_icon_type::{ext_to_file_icon_type, FileIconType},
+    store,
+    utils::name_utils,
+};
+
+use super::{
+    errors::DownloadError,
+    state::{DownloadType, FileDownload, FileDownloadState},
+};
+
+pub fn get_next_id(state: &mut store::State) -> u32 {
+    let download_id = state.downloads.next_id;
+
+    state.downloads.next_id += 1;
+
+    download_id
+}
+
+pub struct FileDownloadAdded {
+    pub id: u32,
+    pub repo_id: String,
+    pub file_ids: Vec<String>,
+    pub typ: DownloadType,
+    pub name: Option<String>,
+    pub size: Option<i64>,
+    pub modified: Option<i64>,
+    pub icon_type: FileIconType,
+}
+
+pub fn file_download_added(state: &mut store::State, file: FileDownloadAdded, now: i64) {
+    state.downloads.files.insert(
+        file.id,
+        FileDownload {
+            id: file.id,
+            repo_id: file.repo_id,
+            file_ids: file.file_ids,
+            typ: file.typ,
+            name: file.name,
+            size: file.size,
+            modified: file.modified,
+            icon_type: file.icon_type,
+            started: now,
+            state: FileDownloadState::Waiting,
+            downloaded_bytes: 0,
+            attempts: 0,
+            order: state.downloads.total_count,
+        },
+    );
+
+    state.downloads.total_count += 1;
+
+    if let Some(size) = file.size {
+        state.downloads.total_bytes += size;
+    }
+}
+
+/// offset is the number of bytes kept from the previous attempt, they are
+/// counted as done again.
+pub fn file_download_downloading(state: &mut store::State, id: u32, offset: i64, now: i64) {
+    if let Some(file) = state.downloads.files.get_mut(&id) {
+        file.state = FileDownloadState::Downloading;
+        file.downloaded_bytes = offset;
+        file.attempts += 1;
+
+        state.downloads.done_bytes += offset;
+        state.downloads.downloading_count += 1;
+
+        if state.downloads.started.is_none() {
+            state.downloads.started = Some(now)
+        }
+    }
+}
+
+/// Sets the name and size once the reader is opened (archive names and sizes
+/// are not known before). Files that changed since the download was added
+/// are downloaded from the beginning with the new size and modified time.
+pub fn file_download_opened(
+    state: &mut store::State,
+    id: u32,
+    name: &str,
+    size: Option<i64>,
+    modified: Option<i64>,
+) {
+    if let Some(file) = state.downloads.files.get_mut(&id) {
+        if file.name.is_none() {
+            file.icon_type = match file.typ {
+                DownloadType::File => name_utils::name_to_ext(&name.to_lowercase())
+                    .and_then(ext_to_file_icon_type)
+                    .unwrap_or(FileIconType::Generic),
+                DownloadType::Archive { .. } => FileIconType::Archive,
+            };
+            file.name = Some(name.to_owned());
+        }
+
+        if let Some(size) = size {
+            state.downloads.total_bytes += size - file.size.unwrap_or(0);
+
+            file.size = Some(size);
+        }
+
+        if modified.is_some() {
+            file.modified = modified;
+        }
+    }
+}
+
+pub fn file_download_progress(state: &mut store::State, id: u32, n: i64) {
+    if let Some(file) = state.downloads.files.get_mut(&id) {
+        file.downloaded_bytes += n;
+
+        if let Some(size) = file.size {
+            file.downloaded_bytes = min(file.downloaded_bytes, size);
+        }
+
+        state.downloads.done_bytes += n;
+    }
+}
+
+pub fn file_download_done(state: &mut store::State, id: u32) {
+    if let Some(file) = state.downloads.files.get_mut(&id) {
+        if file.size.is_none() {
+            file.size = Some(file.downloaded_bytes);
+
+            state.downloads.total_bytes += file.downloaded_bytes;
+        }
+    }
+
+    state.downloads.done_count += 1;
+    state.downloads.downloading_count -= 1;
+
+    state.downloads.files.remove(&id);
+
+    if state.downloads.downloading_count == 0 {
+        state.downloads.started = None
+    }
+
+    if state.downloads.files.is_empty() {
+        reset(state);
+    }
+}
+
+/// downloaded_bytes are kept so that the retry can continue after them.
+pub fn file_download_failed(state: &mut store::State, id: u32, err: DownloadError) {
+    if let Some(file) = state.downloads.files.get_mut(&id) {
+        file.state = FileDownloadState::Failed { error: err };
+
+        state.downloads.done_bytes -= file.downloaded_bytes;
+        if let Some(size) = file.size {
+            state.downloads.failed_bytes += size;
+        }
+        state.downloads.downloading_count -= 1;
+        state.downloads.failed_count += 1;
+    }
+
+    if state.downloads.downloading_count == 0 {
+        state.downloads.started = None
+    }
+}
+
+pub fn file_download_abort(state: &mut store::State, id: u32) {
+    if let Some(file) = state.downloads.files.get(&id) {
+        state.downloads.total_count -= 1;
+
+        if let Some(size) = file.size {
+            state.downloads.total_bytes -= size;
+        }
+
+        match &file.state {
+            FileDownloadState::Waiting => {}
+            FileDownloadState::Downloading => {
+                state.downloads.done_bytes -= file.downloaded_bytes;
+                state.downloads.downloading_count -= 1;
+            }
+            FileDownloadState::Failed { .. } => {
+                if let Some(size) = file.size {
+                    state.downloads.failed_bytes -= size;
+                }
+
+                state.downloads.failed_count -= 1;
+            }
+        }
+    }
+
+    state.downloads.files.remove(&id);
+
+    if state.downloads.downloading_count == 0 {
+        state.downloads.started = None
+    }
+
+    if state.downloads.files.is_empty() {
+        reset(state);
+    }
+}
+
+pub fn file_download_abort_all(state: &mut store::State) -> Vec<u32> {
+    let ids: Vec<u32> = state.downloads.files.keys().cloned().collect();
+
+    for id in &ids {
+        file_download_abort(state, *id);
+    }
+
+    ids
+}
+
+pub fn file_download_retry(state: &mut store::State, id: u32, now: i64) {
+    if let Some(file) = state.downloads.files.get_mut(&id) {
+        match file.state {
+            FileDownloadState::Failed { .. } => {}
+            _ => return,
+        }
+
+        file.state = FileDownloadState::Waiting;
+
+        state.downloads.failed_count -= 1;
+
+        if let Some(size) = file.size {
+            state.downloads.failed_bytes -= size;
+        }
+    }
+
+    if state.downloads.started.is_none() {
+        state.downloads.started = Some(now)
+    }
+}
+
+pub fn file_download_retry_all(state: &mut store::State, now: i64) {
+    for id in state.downloads.files.keys().cloned().collect::<Vec<u32>>() {
+        file_download_retry(state, id, now);
+    }
+}
+
+pub fn reset(state: &mut store::State) {
+    state.downloads = Default::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        downloads::{errors::DownloadError, state::DownloadType},
+        file_types::file_icon_type::FileIconType,
+        store,
+    };
+
+    use super::{
+        file_download_added, file_download_done, file_download_downloading, file_download_failed,
+        file_download_opened, file_download_progress, file_download_retry, FileDownloadAdded,
+    };
+
+    fn add(state: &mut store::State, id: u32, typ: DownloadType, size: Option<i64>) {
+        file_download_added(
+            state,
+            FileDownloadAdded {
+                id,
+                repo_id: String::from("r1"),
+                file_ids: vec![String::from("r1:/file.txt")],
+                typ,
+                name: None,
+                size,
+                modified: None,
+                icon_type: FileIconType::Generic,
+            },
+            1,
+        );
+    }
+
+    #[test]
+    fn test_file_download_resume() {
+        let mut state = store::State::default();
+
+        add(&mut state, 0, DownloadType::File, Some(100));
+        add(&mut state, 1, DownloadType::File, Some(10));
+
+        file_download_downloading(&mut state, 0, 0, 1);
+        file_download_progress(&mut state, 0, 60);
+        file_download_failed(&mut state, 0, DownloadError::Aborted);
+
+        assert_eq!(state.downloads.done_bytes, 0);
+        assert_eq!(state.downloads.failed_bytes, 100);
+        assert_eq!(state.downloads.files.get(&0).unwrap().downloaded_bytes, 60);
+
+        file_download_retry(&mut state, 0, 2);
+
+        assert_eq!(state.downloads.failed_bytes, 0);
+        assert_eq!(state.downloads.failed_count, 0);
+
+        file_download_downloading(&mut state, 0, 60, 2);
+
+        assert_eq!(state.downloads.done_bytes, 60);
+
+        file_download_progress(&mut state, 0, 40);
+
+        assert_eq!(state.downloads.files.get(&0).unwrap().attempts, 2);
+
+        file_download_done(&mut state, 0);
+
+        assert!(state.downloads.files.get(&0).is_none());
+        assert_eq!(state.downloads.done_bytes, 100);
+        assert_eq!(state.downloads.total_bytes, 110);
+        assert_eq!(state.downloads.done_count, 1);
+        assert_eq!(state.downloads.downloading_count, 0);
+    }
+
+    #[test]
+    fn test_file_download_archive_size() {
+        let mut state = store::State::default();
+
+        add(
+            &mut state,
+            0,
+            DownloadType::Archive {
+                format: Default::default(),
+            },
+            None,
+        );
+        add(&mut state, 1, DownloadType::File, Some(10));
+
+        file_download_downloading(&mut state, 0, 0, 1);
+        file_download_opened(&mut state, 0, "Files.zip", None, None);
+        file_download_progress(&mut state, 0, 30);
+
+        let file = state.downloads.files.get(&0).unwrap();
+
+        assert_eq!(file.name.as_deref(), Some("Files.zip"));
+        assert_eq!(file.icon_type, FileIconType::Archive);
+
+        file_download_done(&mut state, 0);
+
+        assert_eq!(state.downloads.total_bytes, 40);
+        assert_eq!(state.downloads.done_bytes, 30);
+    }
+}
@@ -0,0 +1,207 @@
+use std::cmp::min;
+
+use crate::{common::state::RemainingTime, repo_files::selectors as repo_files_selectors, store};
+
+use super::{
+    errors::DownloadError,
+    state::{DownloadType, FileDownload, FileDownloadState},
+};
+
+const MAX_CONCURRENCY: u32 = 3;
+const MAX_AUTO_ATTEMPTS: u32 = 5;
+const MIN_TIME_PER_FILE_MS: u64 = 500;
+
+pub fn select_file<'a>(state: &'a store::State, id: u32) -> Option<&'a FileDownload> {
+    state.downloads.files.get(&id)
+}
+
+pub fn select_files<'a>(state: &'a store::State) -> Vec<&'a FileDownload> {
+    let mut files: Vec<&'a FileDownload> = state.downloads.files.values().collect();
+
+    files.sort_by_key(|file| file.order);
+
+    files
+}
+
+pub fn select_is_active(state: &store::State) -> bool {
+    !state.downloads.files.is_empty()
+}
+
+pub fn select_is_downloading(state: &store::State) -> bool {
+    state.downloads.downloading_count > 0
+}
+
+pub fn select_is_all_done(state: &store::State) -> bool {
+    state.downloads.total_count > 0 && state.downloads.total_count == state.downloads.done_count
+}
+
+pub fn select_can_retry(state: &store::State) -> bool {
+    state.downloads.failed_count > 0
+}
+
+pub fn select_can_abort(state: &store::State) -> bool {
+    state.downloads.total_count > state.downloads.done_count
+}
+
+pub fn select_remaining_count(state: &store::State) -> u32 {
+    state.downloads.total_count
+        - state.downloads.done_count
+        - state.downloads.failed_count
+        - state.downloads.downloading_count
+}
+
+pub fn select_remaining_bytes(state: &store::State) -> i64 {
+    state.downloads.total_bytes - state.downloads.done_bytes - state.downloads.failed_bytes
+}
+
+pub fn select_bytes_per_second(state: &store::State, now: i64) -> f64 {
+    match state.downloads.started {
+        Some(started) => state.downloads.done_bytes as f64 / ((now - started) / 1000) as f64,
+        None => 0.0,
+    }
+}
+
+pub fn select_remaining_time(state: &store::State, now: i64) -> RemainingTime {
+    let speed_bytes = select_bytes_per_second(state, now);
+    let remaining_bytes = select_remaining_bytes(state);
+    let extra_seconds = (select_remaining_count(state) as u64 * MIN_TIME_PER_FILE_MS) / 1000;
+
+    let total_seconds = (remaining_bytes as f64 / speed_bytes) + extra_seconds as f64;
+
+    RemainingTime::from_seconds(total_seconds)
+}
+
+pub fn select_percentage(state: &store::State) -> u8 {
+    if state.downloads.total_bytes > 0 {
+        min(
+            ((state.downloads.done_bytes as f64 * 100.0) / state.downloads.total_bytes as f64)
+                .floor() as u8,
+            100,
+        )
+    } else {
+        0
+    }
+}
+
+pub fn select_next_file<'a>(state: &'a store::State) -> Option<&'a FileDownload> {
+    if state.downloads.downloading_count >= MAX_CONCURRENCY {
+        return None;
+    }
+
+    select_files(state)
+        .into_iter()
+        .find(|file| match file.state {
+            FileDownloadState::Waiting => true,
+            _ => false,
+        })
+}
+
+pub fn select_can_auto_retry(state: &store::State, id: u32) -> bool {
+    state
+        .downloads
+        .files
+        .get(&id)
+        .map(|file| {
+            file.attempts < MAX_AUTO_ATTEMPTS
+                && match &file.state {
+                    // retrying cannot succeed until the repo is unlocked again
+                    FileDownloadState::Failed {
+                        error: DownloadError::RepoLocked(_),
+                    } => false,
+                    // the destination failed (e.g. disk full)
+                    FileDownloadState::Failed {
+                        error: DownloadError::WriteError(_),
+                    } => false,
+                    _ => true,
+                }
+        })
+        .unwrap_or(false)
+}
+
+/// Number of bytes that the next attempt can keep. Only single files can be
+/// continued, archives are generated again from the beginning.
+/// Files removed from the store fail when the download starts, only loaded
+/// files with a different size or modified time are considered changed.
+pub fn select_source_changed(state: &store::State, file: &FileDownload) -> bool {
+    match file
+        .file_ids
+        .first()
+        .and_then(|file_id| repo_files_selectors::select_file(state, file_id))
+    {
+        Some(repo_file) => {
+            repo_file.decrypted_size().ok() != file.size
+                || Some(repo_file.modified) != file.modified
+        }
+        None => false,
+    }
+}
+
+pub fn select_resume_offset(state: &store::State, id: u32) -> i64 {
+    state
+        .downloads
+        .files
+        .get(&id)
+        .filter(|file| !select_source_changed(state, file))
+        .and_then(|file| match (file.typ, file.size) {
+            (DownloadType::File, Some(size)) if file.downloaded_bytes < size => {
+                Some(file.downloaded_bytes)
+            }
+            _ => None,
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        downloads::{
+            errors::DownloadError,
+            mutations::{
+                file_download_added, file_download_downloading, file_download_failed,
+                file_download_progress, FileDownloadAdded,
+            },
+            state::DownloadType,
+        },
+        file_types::file_icon_type::FileIconType,
+        store,
+    };
+
+    use super::select_resume_offset;
+
+    #[test]
+    fn test_select_resume_offset() {
+        let mut state = store::State::default();
+
+        for (id, typ) in [
+            (0, DownloadType::File),
+            (
+                1,
+                DownloadType::Archive {
+                    format: Default::default(),
+                },
+            ),
+        ] {
+            file_download_added(
+                &mut state,
+                FileDownloadAdded {
+                    id,
+                    repo_id: String::from("r1"),
+                    file_ids: vec![String::from("r1:/file.txt")],
+                    typ,
+                    name: None,
+                    size: Some(100),
+                    modified: None,
+                    icon_type: FileIconType::Generic,
+                },
+                1,
+            );
+            file_download_downloading(&mut state, id, 0, 1);
+            file_download_progress(&mut state, id, 60);
+            file_download_failed(&mut state, id, DownloadError::Aborted);
+        }
+
+        assert_eq!(select_resume_offset(&state, 0), 60);
+        assert_eq!(select_resume_offset(&state, 1), 0);
+        assert_eq!(select_resume_offset(&state, 2), 0);
+    }
+}
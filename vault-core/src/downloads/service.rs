@@ -0,0 +1,721 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+use futures::channel::oneshot::{self, Receiver, Sender};
+use futures::future::Shared;
+use futures::{AsyncRead, AsyncReadExt, AsyncWriteExt, FutureExt};
+
+use crate::file_types::file_icon_type::FileIconType;
+use crate::http::{self, HttpError};
+use crate::remote::RemoteError;
+use crate::repo_files::{
+    selectors as repo_files_selectors,
+    state::{RepoFile, RepoFileType},
+};
+use crate::repo_files_read::{
+    state::{RepoFileRange, RepoFilesArchiveFormat},
+    RepoFilesReadService,
+};
+use crate::repos::errors::RepoLockedError;
+use crate::repos::selectors as repos_selectors;
+use crate::runtime;
+use crate::store;
+use crate::utils::abort_reader::AbortReader;
+
+use super::selectors;
+use super::{
+    errors::DownloadError,
+    mutations,
+    state::{DownloadType, DownloadWriter, RepoFileDownloadable},
+};
+
+pub type Downloadable = Pin<Box<dyn RepoFileDownloadable + Send + Sync>>;
+
+pub type DownloadResult = Result<(), DownloadError>;
+
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+pub struct DownloadsService {
+    repo_files_read_service: Arc<RepoFilesReadService>,
+    store: Arc<store::Store>,
+    runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
+    downloadables: Arc<RwLock<HashMap<u32, Downloadable>>>,
+    results: Arc<RwLock<HashMap<u32, Sender<DownloadResult>>>>,
+    abort_senders: Arc<RwLock<HashMap<u32, Sender<()>>>>,
+    abort_receivers: Arc<RwLock<HashMap<u32, Shared<Receiver<()>>>>>,
+}
+
+impl DownloadsService {
+    pub fn new(
+        repo_files_read_service: Arc<RepoFilesReadService>,
+        store: Arc<store::Store>,
+        runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
+    ) -> Self {
+        Self {
+            repo_files_read_service,
+            store,
+            runtime,
+            downloadables: Arc::new(RwLock::new(HashMap::new())),
+            results: Arc::new(RwLock::new(HashMap::new())),
+            abort_senders: Arc::new(RwLock::new(HashMap::new())),
+            abort_receivers: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn now(&self) -> i64 {
+        instant::now() as i64
+    }
+
+    pub fn get_next_id(&self) -> u32 {
+        self.store.mutate(store::Event::Downloads, |state| {
+            mutations::get_next_id(state)
+        })
+    }
+
+    /// Downloads a single file or an archive of the given format for dirs and
+    /// multiple files into downloadable.
+    pub async fn download(
+        self: Arc<Self>,
+        file_ids: &[String],
+        archive_format: RepoFilesArchiveFormat,
+        downloadable: Downloadable,
+    ) -> DownloadResult {
+        let files = self.select_repo_files(file_ids)?;
+
+        let repo_id = match files.get(0) {
+            Some(file) => file.repo_id.clone(),
+            None => return Err(DownloadError::FilesEmpty),
+        };
+
+        let (typ, name, size, modified, icon_type) = match files.as_slice() {
+            [file] if file.typ == RepoFileType::File => (
+                DownloadType::File,
+                Some(file.decrypted_name()?.to_owned()),
+                Some(file.decrypted_size()?),
+                Some(file.modified),
+                file.icon_type.clone(),
+            ),
+            _ => (
+                DownloadType::Archive {
+                    format: archive_format,
+                },
+                None,
+                None,
+                None,
+                FileIconType::Archive,
+            ),
+        };
+
+        let id = self.get_next_id();
+
+        self.downloadables.write().unwrap().insert(id, downloadable);
+
+        let (result_sender, result_receiver) = oneshot::channel();
+
+        self.results.write().unwrap().insert(id, result_sender);
+
+        let (abort_sender, abort_receiver) = oneshot::channel();
+
+        self.abort_senders.write().unwrap().insert(id, abort_sender);
+        self.abort_receivers
+            .write()
+            .unwrap()
+            .insert(id, abort_receiver.shared());
+
+        self.store.mutate(store::Event::Downloads, |state| {
+            mutations::file_download_added(
+                state,
+                mutations::FileDownloadAdded {
+                    id,
+                    repo_id,
+                    file_ids: file_ids.to_vec(),
+                    typ,
+                    name,
+                    size,
+                    modified,
+                    icon_type,
+                },
+                self.now(),
+            );
+        });
+
+        self.clone().process_next();
+
+        result_receiver.await.unwrap()
+    }
+
+    fn select_repo_files(&self, file_ids: &[String]) -> Result<Vec<RepoFile>, DownloadError> {
+        self.store.with_state(|state| {
+            file_ids
+                .iter()
+                .map(|file_id| {
+                    repo_files_selectors::select_file(state, file_id)
+                        .cloned()
+                        .ok_or(DownloadError::FileNotFound)
+                })
+                .collect()
+        })
+    }
+
+    pub fn abort_file(&self, id: u32) {
+        self.store.mutate(store::Event::Downloads, |state| {
+            mutations::file_download_abort(state, id);
+        });
+
+        self.abort_file_cleanup(id);
+    }
+
+    pub fn abort_file_cleanup(&self, id: u32) {
+        self.downloadables.write().unwrap().remove(&id);
+
+        if let Some(sender) = self.results.write().unwrap().remove(&id) {
+            let _ = sender.send(Err(DownloadError::Aborted));
+        }
+
+        if let Some(sender) = self.abort_senders.write().unwrap().remove(&id) {
+            let _ = sender.send(());
+        }
+
+        self.abort_receivers.write().unwrap().remove(&id);
+    }
+
+    pub fn abort_all(&self) {
+        let ids = self.store.mutate(store::Event::Downloads, |state| {
+            mutations::file_download_abort_all(state)
+        });
+
+        for id in ids {
+            self.abort_file_cleanup(id);
+        }
+    }
+
+    pub fn retry_file(self: Arc<Self>, id: u32) {
+        self.store.mutate(store::Event::Downloads, |state| {
+            mutations::file_download_retry(state, id, self.now());
+        });
+
+        self.process_next();
+    }
+
+    pub fn retry_all(self: Arc<Self>) {
+        self.store.mutate(store::Event::Downloads, |state| {
+            mutations::file_download_retry_all(state, self.now());
+        });
+
+        self.process_next();
+    }
+
+    fn process_next(self: Arc<Self>) {
+        if let Some(next_file_id) = self
+            .store
+            .with_state(|state| selectors::select_next_file(state).map(|file| file.id))
+        {
+            self.clone().download_file(next_file_id);
+
+            self.process_next();
+        }
+    }
+
+    fn download_file(self: Arc<Self>, id: u32) {
+        let (repo_id, file_ids, typ, offset) = match self.store.with_state(|state| {
+            selectors::select_file(state, id).map(|file| {
+                (
+                    file.repo_id.clone(),
+                    file.file_ids.clone(),
+                    file.typ,
+                    selectors::select_resume_offset(state, id),
+                )
+            })
+        }) {
+            Some(file) => file,
+            None => {
+                return;
+            }
+        };
+
+        let downloadable = match self.downloadables.write().unwrap().remove(&id) {
+            Some(downloadable) => downloadable,
+            None => {
+                // without a destination the download can never start
+                self.abort_file(id);
+
+                return;
+            }
+        };
+
+        // continue after the bytes written by the previous attempt if the file
+        // did not change and the destination supports it
+        let resume_writer = if offset > 0 {
+            downloadable.writer_at(offset)
+        } else {
+            None
+        };
+        let offset = if resume_writer.is_some() { offset } else { 0 };
+
+        self.store.mutate(store::Event::Downloads, |state| {
+            mutations::file_download_downloading(state, id, offset, self.now());
+        });
+
+        let abort: http::HttpRequestAbort = match self.abort_receivers.read().unwrap().get(&id) {
+            Some(receiver) => Some(
+                receiver
+                    .clone()
+                    .map(|res| res.map_err(|_| ()))
+                    .boxed()
+                    .shared(),
+            ),
+            None => None,
+        };
+
+        let download_future_self = self.clone();
+
+        let download_future = async move {
+            let result = match resume_writer {
+                Some(writer) => {
+                    download_future_self
+                        .download_file_resume(id, &file_ids, offset, writer, abort)
+                        .await
+                }
+                None => {
+                    download_future_self
+                        .clone()
+                        .download_file_full(id, &file_ids, typ, &*downloadable, abort)
+                        .await
+                }
+            };
+
+            match result {
+                Ok(()) => {
+                    download_future_self
+                        .store
+                        .mutate(store::Event::Downloads, |state| {
+                            mutations::file_download_done(state, id);
+                        });
+
+                    if let Some(sender) = download_future_self.results.write().unwrap().remove(&id)
+                    {
+                        let _ = sender.send(Ok(()));
+                    }
+
+                    download_future_self
+                        .abort_senders
+                        .write()
+                        .unwrap()
+                        .remove(&id);
+                    download_future_self
+                        .abort_receivers
+                        .write()
+                        .unwrap()
+                        .remove(&id);
+
+                    download_future_self.process_next();
+                }
+                Err(_)
+                    if download_future_self
+                        .store
+                        .with_state(|state| selectors::select_file(state, id).is_none()) =>
+                {
+                    // download was aborted by the user
+                    download_future_self.process_next();
+                }
+                Err(err) => {
+                    // download was aborted because the repo was locked
+                    let err = if download_future_self.store.with_state(|state| {
+                        repos_selectors::select_repo(state, &repo_id)
+                            .map(|repo| repo.state.is_locked())
+                            .unwrap_or(false)
+                    }) {
+                        DownloadError::RepoLocked(RepoLockedError)
+                    } else {
+                        err
+                    };
+
+                    download_future_self
+                        .downloadables
+                        .write()
+                        .unwrap()
+                        .insert(id, downloadable);
+
+                    download_future_self
+                        .store
+                        .mutate(store::Event::Downloads, |state| {
+                            mutations::file_download_failed(state, id, err);
+                        });
+
+                    if download_future_self
+                        .store
+                        .with_state(|state| selectors::select_can_auto_retry(state, id))
+                    {
+                        download_future_self.retry_file(id);
+                    } else {
+                        download_future_self.process_next();
+                    }
+                }
+            }
+        };
+
+        self.runtime.spawn(Box::pin(download_future));
+    }
+
+    async fn download_file_full(
+        self: Arc<Self>,
+        id: u32,
+        file_ids: &[String],
+        typ: DownloadType,
+        downloadable: &(dyn RepoFileDownloadable + Send + Sync),
+        abort: http::HttpRequestAbort,
+    ) -> DownloadResult {
+        let files = self.select_repo_files(file_ids)?;
+
+        let (format, modified) = match (typ, files.as_slice()) {
+            (DownloadType::File, [file]) => {
+                (RepoFilesArchiveFormat::default(), Some(file.modified))
+            }
+            (DownloadType::File, _) => (RepoFilesArchiveFormat::default(), None),
+            (DownloadType::Archive { format }, _) => (format, None),
+        };
+
+        let reader = self
+            .repo_files_read_service
+            .clone()
+            .get_files_reader_with_format(&files, format)
+            .await?;
+
+        self.store.mutate(store::Event::Downloads, |state| {
+            mutations::file_download_opened(state, id, &reader.name, reader.size, modified);
+        });
+
+        let writer = downloadable
+            .writer(&reader.name, reader.size, reader.content_type.as_deref())
+            .await
+            .map_err(|err| DownloadError::WriteError(err.to_string()))?;
+
+        self.copy(id, reader.reader, writer, abort).await
+    }
+
+    async fn download_file_resume(
+        &self,
+        id: u32,
+        file_ids: &[String],
+        offset: i64,
+        writer: futures::future::BoxFuture<'static, std::io::Result<DownloadWriter>>,
+        abort: http::HttpRequestAbort,
+    ) -> DownloadResult {
+        let file = self
+            .select_repo_files(file_ids)?
+            .into_iter()
+            .next()
+            .ok_or(DownloadError::FilesEmpty)?;
+
+        let reader = self
+            .repo_files_read_service
+            .get_file_reader_range(
+                &file,
+                &RepoFileRange {
+                    start: offset,
+                    end: None,
+                },
+            )
+            .await?;
+
+        let writer = writer
+            .await
+            .map_err(|err| DownloadError::WriteError(err.to_string()))?;
+
+        self.copy(id, reader.reader, writer, abort).await
+    }
+
+    /// Copies reader into writer. Progress is reported after the bytes are
+    /// written so that a retry can continue after them.
+    async fn copy(
+        &self,
+        id: u32,
+        reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>>,
+        mut writer: DownloadWriter,
+        abort: http::HttpRequestAbort,
+    ) -> DownloadResult {
+        let mut reader = AbortReader::new(reader, abort);
+
+        let mut buf = vec![0; COPY_BUFFER_SIZE];
+
+        loop {
+            let n = reader.read(&mut buf).await.map_err(|err| {
+                DownloadError::RemoteError(RemoteError::HttpError(HttpError::ResponseError(
+                    err.to_string(),
+                )))
+            })?;
+
+            if n == 0 {
+                break;
+            }
+
+            writer
+                .write_all(&buf[..n])
+                .await
+                .map_err(|err| DownloadError::WriteError(err.to_string()))?;
+
+            self.store.mutate(store::Event::Downloads, |state| {
+                mutations::file_download_progress(state, id, n as i64);
+            });
+        }
+
+        writer
+            .close()
+            .await
+            .map_err(|err| DownloadError::WriteError(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        sync::{Arc, Mutex},
+        task::{Context, Poll},
+    };
+
+    use futures::{executor::block_on, future::BoxFuture, stream, AsyncWrite};
+    use http::{
+        header::{CONTENT_LENGTH, RANGE},
+        HeaderMap,
+    };
+
+    use crate::{
+        cipher::constants::{BLOCK_DATA_SIZE, BLOCK_SIZE, FILE_HEADER_SIZE},
+        http::{mock_http_client::MockHttpResponse, HttpError},
+        repo_files::{
+            selectors as repo_files_selectors,
+            test_helpers::{TestContext, TEST_MOUNT_ID},
+        },
+        repo_files_read::state::RepoFilesArchiveFormat,
+        store,
+    };
+
+    use super::{
+        super::state::{DownloadWriter, RepoFileDownloadable},
+        DownloadsService,
+    };
+
+    struct MemoryWriter {
+        content: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl AsyncWrite for MemoryWriter {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.content.lock().unwrap().extend_from_slice(buf);
+
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Downloads into memory and records the offset of every attempt
+    #[derive(Clone, Default)]
+    struct MemoryDownloadable {
+        content: Arc<Mutex<Vec<u8>>>,
+        offsets: Arc<Mutex<Vec<i64>>>,
+    }
+
+    impl MemoryDownloadable {
+        fn open(&self, offset: i64) -> BoxFuture<'static, std::io::Result<DownloadWriter>> {
+            self.content.lock().unwrap().truncate(offset as usize);
+            self.offsets.lock().unwrap().push(offset);
+
+            let writer: DownloadWriter = Box::pin(MemoryWriter {
+                content: self.content.clone(),
+            });
+
+            Box::pin(async move { Ok(writer) })
+        }
+    }
+
+    impl RepoFileDownloadable for MemoryDownloadable {
+        fn writer(
+            &self,
+            _name: &str,
+            _size: Option<i64>,
+            _content_type: Option<&str>,
+        ) -> BoxFuture<'static, std::io::Result<DownloadWriter>> {
+            self.open(0)
+        }
+
+        fn writer_at(
+            &self,
+            offset: i64,
+        ) -> Option<BoxFuture<'static, std::io::Result<DownloadWriter>>> {
+            Some(self.open(offset))
+        }
+    }
+
+    #[test]
+    fn test_download_resume() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+
+        let content = (0..BLOCK_DATA_SIZE * 3 + 100)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<u8>>();
+        ctx.add_file("r1", "/F.bin", &content);
+
+        block_on(ctx.repo_files_service.load_files("r1", "/")).unwrap();
+
+        let encrypted = ctx
+            .fake_remote
+            .get(TEST_MOUNT_ID, &ctx.remote_file_path("r1", "/F.bin"))
+            .unwrap()
+            .content;
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let intercept_ranges = ranges.clone();
+
+        // the connection of the first attempt breaks in the middle of the
+        // third block
+        ctx.fake_remote.set_intercept(Some(Box::new(move |req| {
+            if req.method != "GET" || !req.url.contains("/files/get?") {
+                return None;
+            }
+
+            let mut ranges = intercept_ranges.lock().unwrap();
+
+            ranges.push(
+                req.headers
+                    .get(RANGE)
+                    .map(|range| range.to_str().unwrap().to_owned()),
+            );
+
+            if ranges.len() > 1 {
+                return None;
+            }
+
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_LENGTH, encrypted.len().into());
+
+            let mut res = MockHttpResponse::new(200, headers, Vec::new());
+            res.bytes_stream = Some(Box::pin(stream::iter(vec![
+                Ok(encrypted[..FILE_HEADER_SIZE + BLOCK_SIZE * 2 + 100].to_vec()),
+                Err(HttpError::ResponseError(String::from("connection reset"))),
+            ])));
+
+            Some(Ok(res))
+        })));
+
+        let downloads_service = Arc::new(DownloadsService::new(
+            ctx.repo_files_read_service.clone(),
+            ctx.store.clone(),
+            ctx.runtime.clone(),
+        ));
+        let downloadable = MemoryDownloadable::default();
+
+        block_on(downloads_service.download(
+            &[repo_files_selectors::get_file_id("r1", "/F.bin")],
+            RepoFilesArchiveFormat::default(),
+            Box::pin(downloadable.clone()),
+        ))
+        .unwrap();
+
+        let offsets = downloadable.offsets.lock().unwrap().clone();
+
+        // the retry continues after the blocks that were written
+        assert_eq!(offsets.len(), 2);
+        assert_eq!(offsets[0], 0);
+        assert!(offsets[1] > 0);
+        assert_eq!(offsets[1] % BLOCK_DATA_SIZE as i64, 0);
+
+        let ranges = ranges.lock().unwrap().clone();
+
+        assert_eq!(ranges.len(), 2);
+        assert!(ranges[1].is_some());
+
+        assert!(*downloadable.content.lock().unwrap() == content);
+    }
+
+    #[test]
+    fn test_download_resume_source_changed() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+
+        let content = (0..BLOCK_DATA_SIZE * 3 + 100)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<u8>>();
+        ctx.add_file("r1", "/F.bin", &content);
+
+        block_on(ctx.repo_files_service.load_files("r1", "/")).unwrap();
+
+        let encrypted = ctx
+            .fake_remote
+            .get(TEST_MOUNT_ID, &ctx.remote_file_path("r1", "/F.bin"))
+            .unwrap()
+            .content;
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let intercept_ranges = ranges.clone();
+        let intercept_store = ctx.store.clone();
+        let file_id = repo_files_selectors::get_file_id("r1", "/F.bin");
+        let intercept_file_id = file_id.clone();
+
+        // the file is modified while the first attempt breaks
+        ctx.fake_remote.set_intercept(Some(Box::new(move |req| {
+            if req.method != "GET" || !req.url.contains("/files/get?") {
+                return None;
+            }
+
+            let mut ranges = intercept_ranges.lock().unwrap();
+
+            ranges.push(
+                req.headers
+                    .get(RANGE)
+                    .map(|range| range.to_str().unwrap().to_owned()),
+            );
+
+            if ranges.len() > 1 {
+                return None;
+            }
+
+            intercept_store.mutate(store::Event::RepoFiles, |state| {
+                if let Some(file) = state.repo_files.files.get_mut(&intercept_file_id) {
+                    file.modified += 1000;
+                }
+            });
+
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_LENGTH, encrypted.len().into());
+
+            let mut res = MockHttpResponse::new(200, headers, Vec::new());
+            res.bytes_stream = Some(Box::pin(stream::iter(vec![
+                Ok(encrypted[..FILE_HEADER_SIZE + BLOCK_SIZE * 2 + 100].to_vec()),
+                Err(HttpError::ResponseError(String::from("connection reset"))),
+            ])));
+
+            Some(Ok(res))
+        })));
+
+        let downloads_service = Arc::new(DownloadsService::new(
+            ctx.repo_files_read_service.clone(),
+            ctx.store.clone(),
+            ctx.runtime.clone(),
+        ));
+        let downloadable = MemoryDownloadable::default();
+
+        block_on(downloads_service.download(
+            &[file_id],
+            RepoFilesArchiveFormat::default(),
+            Box::pin(downloadable.clone()),
+        ))
+        .unwrap();
+
+        // the retry starts from the beginning
+        assert_eq!(*downloadable.offsets.lock().unwrap(), vec![0, 0]);
+        assert_eq!(*ranges.lock().unwrap(), vec![None, None]);
+        assert!(*downloadable.content.lock().unwrap() == content);
+    }
+}
@@ -0,0 +1,78 @@
+use std::{collections::HashMap, pin::Pin};
+
+use futures::{future::BoxFuture, AsyncWrite};
+
+use crate::{
+    file_types::file_icon_type::FileIconType, repo_files_read::state::RepoFilesArchiveFormat,
+};
+
+use super::errors::DownloadError;
+
+pub type DownloadWriter = Pin<Box<dyn AsyncWrite + Send + Sync + 'static>>;
+
+/// Destination of a download (e.g. a file on disk).
+pub trait RepoFileDownloadable {
+    fn writer(
+        &self,
+        name: &str,
+        size: Option<i64>,
+        content_type: Option<&str>,
+    ) -> BoxFuture<'static, std::io::Result<DownloadWriter>>;
+
+    /// Returns a writer that continues after the first offset bytes written by
+    /// a previous attempt. Downloads into destinations that cannot continue
+    /// are retried from the beginning.
+    fn writer_at(
+        &self,
+        _offset: i64,
+    ) -> Option<BoxFuture<'static, std::io::Result<DownloadWriter>>> {
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DownloadType {
+    File,
+    Archive { format: RepoFilesArchiveFormat },
+}
+
+#[derive(Clone)]
+pub enum FileDownloadState {
+    Waiting,
+    Downloading,
+    Failed { error: DownloadError },
+}
+
+#[derive(Clone)]
+pub struct FileDownload {
+    pub id: u32,
+    pub repo_id: String,
+    pub file_ids: Vec<String>,
+    pub typ: DownloadType,
+    // archive names are known once the download starts
+    pub name: Option<String>,
+    pub size: Option<i64>,
+    /// Modified time of the downloaded file. A retry continues after the
+    /// bytes of the previous attempt only if size and modified did not change.
+    pub modified: Option<i64>,
+    pub icon_type: FileIconType,
+    pub started: i64,
+    pub state: FileDownloadState,
+    pub downloaded_bytes: i64,
+    pub attempts: u32,
+    pub order: u32,
+}
+
+#[derive(Clone, Default)]
+pub struct DownloadsState {
+    pub files: HashMap<u32, FileDownload>,
+    pub next_id: u32,
+    pub started: Option<i64>,
+    pub downloading_count: u32,
+    pub done_count: u32,
+    pub failed_count: u32,
+    pub total_count: u32,
+    pub done_bytes: i64,
+    pub failed_bytes: i64,
+    pub total_bytes: i64,
+}
@@ -0,0 +1,7 @@
+/// Plain key value storage. Values are encrypted with the repo cipher and
+/// base64 encoded by `EncryptedStorageService` before they are stored.
+pub trait EncryptedStorage {
+    fn get_item(&self, key: &str) -> Result<Option<String>, String>;
+    fn set_item(&self, key: &str, value: &str) -> Result<(), String>;
+    fn remove_item(&self, key: &str) -> Result<(), String>;
+}
@@ -0,0 +1,9 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum EncryptedStorageError {
+    #[error("invalid value: {0}")]
+    InvalidValue(String),
+    #[error("storage error: {0}")]
+    StorageError(String),
+}
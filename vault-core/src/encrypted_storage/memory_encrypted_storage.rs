@@ -0,0 +1,39 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use super::EncryptedStorage;
+
+/// Keeps the items in memory. Used in tests. Clones share the items.
+#[derive(Clone, Default)]
+pub struct MemoryEncryptedStorage {
+    items: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl MemoryEncryptedStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EncryptedStorage for MemoryEncryptedStorage {
+    fn get_item(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.items.lock().unwrap().get(key).cloned())
+    }
+
+    fn set_item(&self, key: &str, value: &str) -> Result<(), String> {
+        self.items
+            .lock()
+            .unwrap()
+            .insert(key.to_owned(), value.to_owned());
+
+        Ok(())
+    }
+
+    fn remove_item(&self, key: &str) -> Result<(), String> {
+        self.items.lock().unwrap().remove(key);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+pub mod encrypted_storage;
+pub mod errors;
+pub mod memory_encrypted_storage;
+pub mod service;
+
+pub use self::encrypted_storage::EncryptedStorage;
+pub use self::service::EncryptedStorageService;
@@ -0,0 +1,131 @@
+use data_encoding::BASE64;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::cipher::Cipher;
+
+use super::{encrypted_storage::EncryptedStorage, errors::EncryptedStorageError};
+
+pub struct EncryptedStorageService {
+    storage: Box<dyn EncryptedStorage + Send + Sync>,
+}
+
+impl EncryptedStorageService {
+    pub fn new(storage: Box<dyn EncryptedStorage + Send + Sync>) -> Self {
+        Self { storage }
+    }
+
+    pub async fn get<T>(
+        &self,
+        key: &str,
+        cipher: &Cipher,
+    ) -> Result<Option<T>, EncryptedStorageError>
+    where
+        T: DeserializeOwned,
+    {
+        let value = match self
+            .storage
+            .get_item(key)
+            .map_err(EncryptedStorageError::StorageError)?
+        {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        let encrypted = BASE64
+            .decode(value.as_bytes())
+            .map_err(|err| EncryptedStorageError::InvalidValue(err.to_string()))?;
+
+        let mut bytes = Vec::new();
+
+        cipher
+            .decrypt_data(&encrypted, &mut bytes)
+            .await
+            .map_err(|err| EncryptedStorageError::InvalidValue(err.to_string()))?;
+
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|err| EncryptedStorageError::InvalidValue(err.to_string()))
+    }
+
+    pub async fn set<T>(
+        &self,
+        key: &str,
+        cipher: &Cipher,
+        value: &T,
+    ) -> Result<(), EncryptedStorageError>
+    where
+        T: Serialize,
+    {
+        let bytes = serde_json::to_vec(value)
+            .map_err(|err| EncryptedStorageError::InvalidValue(err.to_string()))?;
+
+        let mut encrypted = Vec::new();
+
+        cipher
+            .encrypt_data(&bytes, &mut encrypted)
+            .await
+            .map_err(|err| EncryptedStorageError::StorageError(err.to_string()))?;
+
+        self.storage
+            .set_item(key, &BASE64.encode(&encrypted))
+            .map_err(EncryptedStorageError::StorageError)
+    }
+
+    pub fn remove(&self, key: &str) -> Result<(), EncryptedStorageError> {
+        self.storage
+            .remove_item(key)
+            .map_err(EncryptedStorageError::StorageError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use crate::cipher::test_helpers::create_cipher;
+
+    use super::{
+        super::{
+            encrypted_storage::EncryptedStorage, errors::EncryptedStorageError,
+            memory_encrypted_storage::MemoryEncryptedStorage,
+        },
+        EncryptedStorageService,
+    };
+
+    #[test]
+    fn test_set_get() {
+        let cipher = create_cipher();
+        let service = EncryptedStorageService::new(Box::new(MemoryEncryptedStorage::new()));
+
+        block_on(service.set("key", &cipher, &[1, 2, 3])).unwrap();
+
+        assert_eq!(
+            block_on(service.get::<Vec<i32>>("key", &cipher)).unwrap(),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(
+            block_on(service.get::<Vec<i32>>("missing", &cipher)).unwrap(),
+            None
+        );
+
+        service.remove("key").unwrap();
+
+        assert_eq!(
+            block_on(service.get::<Vec<i32>>("key", &cipher)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_invalid_value() {
+        let cipher = create_cipher();
+        let storage = MemoryEncryptedStorage::new();
+        storage.set_item("key", "not base64!").unwrap();
+        let service = EncryptedStorageService::new(Box::new(storage));
+
+        assert!(matches!(
+            block_on(service.get::<Vec<i32>>("key", &cipher)),
+            Err(EncryptedStorageError::InvalidValue(_))
+        ));
+    }
+}
@@ -0,0 +1,26 @@
+use super::WebSocketClient;
+
+/// WebSocket client that never connects. Used in tests.
+#[derive(Default)]
+pub struct MockWebSocketClient;
+
+impl MockWebSocketClient {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl WebSocketClient for MockWebSocketClient {
+    fn open(
+        &self,
+        _url: String,
+        _on_open: Box<dyn Fn() + Send + Sync + 'static>,
+        _on_message: Box<dyn Fn(String) + Send + Sync + 'static>,
+        _on_close: Box<dyn Fn() + Send + Sync + 'static>,
+    ) {
+    }
+
+    fn send(&self, _data: String) {}
+
+    fn close(&self) {}
+}
@@ -1,5 +1,6 @@
 pub mod event;
 pub mod message;
+pub mod mock_websocket_client;
 pub mod request;
 pub mod service;
 pub mod websocket_client;
@@ -3,6 +3,7 @@ use std::sync::{Arc, Mutex, Weak};
 
 use crate::remote_files::selectors::get_file_id;
 use crate::repo_files::RepoFilesService;
+use crate::repo_files_index::RepoFilesIndexService;
 use crate::runtime;
 use crate::{auth, utils::path_utils::join_paths};
 
@@ -54,6 +55,7 @@ pub struct EventStreamService {
     websocket_client: Box<dyn WebSocketClient + Send + Sync>,
     auth_provider: Arc<Box<dyn auth::AuthProvider + Send + Sync>>,
     repo_files_service: Arc<RepoFilesService>,
+    repo_files_index_service: Arc<RepoFilesIndexService>,
     runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
 
     connection_state: Arc<Mutex<ConnectionState>>,
@@ -70,6 +72,7 @@ impl EventStreamService {
         websocket_client: Box<dyn WebSocketClient + Send + Sync>,
         auth_provider: Arc<Box<dyn auth::AuthProvider + Send + Sync>>,
         repo_files_service: Arc<RepoFilesService>,
+        repo_files_index_service: Arc<RepoFilesIndexService>,
         runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
     ) -> EventStreamService {
         EventStreamService {
@@ -77,6 +80,7 @@ impl EventStreamService {
             websocket_client,
             auth_provider,
             repo_files_service,
+            repo_files_index_service,
             runtime,
 
             connection_state: Arc::new(Mutex::new(ConnectionState::Disconnected)),
@@ -384,29 +388,43 @@ impl EventStreamService {
                             file,
                             ..
                         } => {
-                            self.repo_files_service.remote_file_created(
-                                &mount_id,
-                                &join_paths(&mount_listener.path, &path),
-                                file,
-                            );
+                            let path = join_paths(&mount_listener.path, &path);
+
+                            self.repo_files_index_service
+                                .clone()
+                                .remote_file_created(&mount_id, &path, &file);
+                            self.repo_files_service
+                                .remote_file_created(&mount_id, &path, file);
                         }
-                        Event::FileRemovedEvent { mount_id, path, .. } => {
-                            self.repo_files_service.remote_file_removed(
-                                &mount_id,
-                                &join_paths(&mount_listener.path, &path),
-                            );
+                        Event::FileRemovedEvent {
+                            mount_id,
+                            path,
+                            file,
+                            ..
+                        } => {
+                            let path = join_paths(&mount_listener.path, &path);
+
+                            self.repo_files_index_service
+                                .clone()
+                                .remote_file_removed(&mount_id, &path, &file);
+                            self.repo_files_service
+                                .remote_file_removed(&mount_id, &path);
                         }
                         Event::FileCopiedEvent {
                             mount_id,
+                            path,
                             new_path,
                             file,
                             ..
                         } => {
-                            self.repo_files_service.remote_file_copied(
-                                &mount_id,
-                                &join_paths(&mount_listener.path, &new_path),
-                                file,
-                            );
+                            let path = join_paths(&mount_listener.path, &path);
+                            let new_path = join_paths(&mount_listener.path, &new_path);
+
+                            self.repo_files_index_service
+                                .clone()
+                                .remote_file_copied(&mount_id, &path, &new_path, &file);
+                            self.repo_files_service
+                                .remote_file_copied(&mount_id, &new_path, file);
                         }
                         Event::FileMovedEvent {
                             mount_id,
@@ -415,12 +433,14 @@ impl EventStreamService {
                             file,
                             ..
                         } => {
-                            self.repo_files_service.remote_file_moved(
-                                &mount_id,
-                                &join_paths(&mount_listener.path, &path),
-                                &join_paths(&mount_listener.path, &new_path),
-                                file,
-                            );
+                            let path = join_paths(&mount_listener.path, &path);
+                            let new_path = join_paths(&mount_listener.path, &new_path);
+
+                            self.repo_files_index_service
+                                .clone()
+                                .remote_file_moved(&mount_id, &path, &new_path, &file);
+                            self.repo_files_service
+                                .remote_file_moved(&mount_id, &path, &new_path, file);
                         }
                         _ => {}
                     }
@@ -18,6 +18,7 @@ static EXT_TO_CONTENT_TYPE: phf::Map<&'static str, &'static str> = phf_map! {
     "bat" => "text/plain",
     "bdm" => "application/vnd.syncml.dm+wbxml",
     "bmp" => "image/x-ms-bmp",
+    "bz2" => "application/x-bzip2",
     "c" => "text/plain",
     "cc" => "text/x-c",
     "cgm" => "image/cgm",
@@ -59,6 +60,7 @@ static EXT_TO_CONTENT_TYPE: phf::Map<&'static str, &'static str> = phf_map! {
     "gv" => "text/vnd.graphviz",
     "gz" => "application/gzip",
     "h" => "text/plain",
+    "heic" => "image/heic",
     "hh" => "text/x-c",
     "htc" => "text/x-component",
     "htm" => "text/html",
@@ -231,6 +233,7 @@ static EXT_TO_CONTENT_TYPE: phf::Map<&'static str, &'static str> = phf_map! {
     "xpm" => "image/x-xpixmap",
     "xul" => "text/xul",
     "xwd" => "image/x-xwindowdump",
+    "xz" => "application/x-xz",
     "yaml" => "application/yaml",
     "zip" => "application/zip",
 };
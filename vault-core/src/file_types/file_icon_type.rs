@@ -59,6 +59,7 @@ static EXT_TO_FILE_ICON_TYPE: phf::Map<&'static str, FileIconType> = phf_map! {
     "bmp" => FileIconType::Image,
     "boo" => FileIconType::Text,
     "brf" => FileIconType::Text,
+    "bz2" => FileIconType::Archive,
     "c" => FileIconType::Code,
     "c++" => FileIconType::Code,
     "cbl" => FileIconType::Text,
@@ -150,6 +151,7 @@ static EXT_TO_FILE_ICON_TYPE: phf::Map<&'static str, FileIconType> = phf_map! {
     "gz" => FileIconType::Archive,
     "h" => FileIconType::Code,
     "h++" => FileIconType::Text,
+    "heic" => FileIconType::Image,
     "hh" => FileIconType::Text,
     "hp" => FileIconType::Text,
     "hpp" => FileIconType::Text,
@@ -490,6 +492,7 @@ static EXT_TO_FILE_ICON_TYPE: phf::Map<&'static str, FileIconType> = phf_map! {
     "xslfo" => FileIconType::Text,
     "xul" => FileIconType::Text,
     "xwd" => FileIconType::Image,
+    "xz" => FileIconType::Archive,
     "yaml" => FileIconType::Code,
     "z" => FileIconType::Archive,
     "zip" => FileIconType::Archive,
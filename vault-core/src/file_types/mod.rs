@@ -1,2 +1,3 @@
 pub mod content_type;
 pub mod file_icon_type;
+pub mod sniff;
@@ -0,0 +1,208 @@
+use super::{
+    content_type::ext_to_content_type,
+    file_icon_type::{ext_to_file_icon_type, FileIconType},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SniffedFileType {
+    /// Usual extension of the format, used to look up the content type and
+    /// the icon type
+    pub ext: &'static str,
+    /// Generic formats (plain text, zip) that other formats are based on, e.g.
+    /// json is text and docx is a zip
+    pub is_generic: bool,
+}
+
+impl SniffedFileType {
+    fn new(ext: &'static str) -> Self {
+        Self {
+            ext,
+            is_generic: false,
+        }
+    }
+
+    fn generic(ext: &'static str) -> Self {
+        Self {
+            ext,
+            is_generic: true,
+        }
+    }
+
+    pub fn content_type(&self) -> Option<&'static str> {
+        ext_to_content_type(self.ext)
+    }
+
+    pub fn icon_type(&self) -> FileIconType {
+        ext_to_file_icon_type(self.ext).unwrap_or(FileIconType::Generic)
+    }
+}
+
+/// Recognizes common formats by the magic bytes at the start of the content.
+/// Content that is not recognized but is valid UTF-8 (or has a UTF-16 BOM) is
+/// plain text. bytes can be cut at any point, e.g. the first decrypted block.
+pub fn sniff_file_type(bytes: &[u8]) -> Option<SniffedFileType> {
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let starts_with = |offset: usize, magic: &[u8]| {
+        bytes.len() >= offset + magic.len() && &bytes[offset..offset + magic.len()] == magic
+    };
+
+    let ext = if starts_with(0, b"\x89PNG\r\n\x1a\n") {
+        "png"
+    } else if starts_with(0, b"\xff\xd8\xff") {
+        "jpg"
+    } else if starts_with(0, b"GIF87a") || starts_with(0, b"GIF89a") {
+        "gif"
+    } else if starts_with(0, b"RIFF") && starts_with(8, b"WEBP") {
+        "webp"
+    } else if starts_with(0, b"RIFF") && starts_with(8, b"WAVE") {
+        "wav"
+    } else if starts_with(0, b"RIFF") && starts_with(8, b"AVI ") {
+        "avi"
+    } else if starts_with(0, b"BM") && starts_with(6, b"\0\0\0\0") {
+        // the reserved bytes make "BM" at the start of a text unlikely to match
+        "bmp"
+    } else if starts_with(0, b"II*\0") || starts_with(0, b"MM\0*") {
+        "tiff"
+    } else if starts_with(4, b"ftyp") {
+        sniff_ftyp_brand(&bytes[8..bytes.len().min(12)])
+    } else if starts_with(0, b"\0\0\x01\0") {
+        "ico"
+    } else if starts_with(0, b"%PDF-") {
+        "pdf"
+    } else if starts_with(0, b"PK\x03\x04") || starts_with(0, b"PK\x05\x06") {
+        return Some(SniffedFileType::generic("zip"));
+    } else if starts_with(0, b"\x1f\x8b") {
+        "gz"
+    } else if starts_with(0, b"7z\xbc\xaf\x27\x1c") {
+        "7z"
+    } else if starts_with(0, b"Rar!\x1a\x07") {
+        "rar"
+    } else if starts_with(0, b"BZh") && matches!(bytes.get(3), Some(b'1'..=b'9')) {
+        "bz2"
+    } else if starts_with(0, b"\xfd7zXZ\0") {
+        "xz"
+    } else if starts_with(257, b"ustar") {
+        "tar"
+    } else if starts_with(0, b"ID3")
+        || starts_with(0, b"\xff\xfb")
+        || starts_with(0, b"\xff\xf3")
+        || starts_with(0, b"\xff\xf2")
+    {
+        "mp3"
+    } else if starts_with(0, b"fLaC") {
+        "flac"
+    } else if starts_with(0, b"OggS") {
+        "ogg"
+    } else if starts_with(0, b"\x1a\x45\xdf\xa3") {
+        if contains(&bytes[..bytes.len().min(64)], b"webm") {
+            "webm"
+        } else {
+            "mkv"
+        }
+    } else if is_text(bytes) {
+        return Some(SniffedFileType::generic("txt"));
+    } else {
+        return None;
+    };
+
+    Some(SniffedFileType::new(ext))
+}
+
+fn sniff_ftyp_brand(brand: &[u8]) -> &'static str {
+    match brand {
+        b"qt  " => "mov",
+        b"M4A " | b"M4B " => "m4a",
+        b"heic" | b"heix" | b"hevc" | b"mif1" | b"msf1" => "heic",
+        _ => "mp4",
+    }
+}
+
+fn contains(bytes: &[u8], needle: &[u8]) -> bool {
+    bytes.windows(needle.len()).any(|window| window == needle)
+}
+
+fn is_text(bytes: &[u8]) -> bool {
+    if bytes.starts_with(b"\xff\xfe") || bytes.starts_with(b"\xfe\xff") {
+        return true;
+    }
+
+    let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+
+    let valid = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        // the last character can be cut
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap()
+        }
+        Err(_) => return false,
+    };
+
+    !valid
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b'))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::file_types::file_icon_type::FileIconType;
+
+    use super::{sniff_file_type, SniffedFileType};
+
+    fn sniff_ext(bytes: &[u8]) -> Option<&'static str> {
+        sniff_file_type(bytes).map(|typ| typ.ext)
+    }
+
+    #[test]
+    fn test_sniff_file_type() {
+        assert_eq!(sniff_ext(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("png"));
+        assert_eq!(sniff_ext(b"\xff\xd8\xff\xe0\0\x10JFIF"), Some("jpg"));
+        assert_eq!(sniff_ext(b"RIFF\0\0\0\0WEBPVP8 "), Some("webp"));
+        assert_eq!(sniff_ext(b"RIFF\0\0\0\0WAVEfmt "), Some("wav"));
+        assert_eq!(sniff_ext(b"\0\0\0\x20ftypisom\0\0\x02\0"), Some("mp4"));
+        assert_eq!(sniff_ext(b"\0\0\0\x14ftypqt  "), Some("mov"));
+        assert_eq!(sniff_ext(b"\0\0\0\x18ftypheic"), Some("heic"));
+        assert_eq!(sniff_ext(b"%PDF-1.7\n"), Some("pdf"));
+        assert_eq!(sniff_ext(b"\x1f\x8b\x08\0"), Some("gz"));
+        assert_eq!(sniff_ext(b"ID3\x04\0"), Some("mp3"));
+        assert_eq!(
+            sniff_ext(b"\x1a\x45\xdf\xa3\x9f\x42\x82\x84webm"),
+            Some("webm")
+        );
+
+        let mut tar = vec![0u8; 512];
+        tar[..8].copy_from_slice(b"file.txt");
+        tar[257..263].copy_from_slice(b"ustar\0");
+        assert_eq!(sniff_ext(&tar), Some("tar"));
+
+        assert_eq!(sniff_file_type(b""), None);
+        assert_eq!(sniff_file_type(b"\0\x01\x02\x03binary"), None);
+    }
+
+    #[test]
+    fn test_sniff_file_type_generic() {
+        assert_eq!(
+            sniff_file_type(b"PK\x03\x04\x14\0"),
+            Some(SniffedFileType {
+                ext: "zip",
+                is_generic: true,
+            })
+        );
+
+        let text = sniff_file_type("Hello, \u{17e}ivjo!\n".as_bytes()).unwrap();
+        assert_eq!(text.ext, "txt");
+        assert!(text.is_generic);
+        assert_eq!(text.content_type(), Some("text/plain"));
+        assert_eq!(text.icon_type(), FileIconType::Text);
+
+        // text that starts like a bmp
+        assert_eq!(sniff_ext(b"BMW is a car brand"), Some("txt"));
+        // utf-8 character cut at the end of the block
+        assert_eq!(sniff_ext(&"abc\u{17e}".as_bytes()[..4]), Some("txt"));
+        assert_eq!(sniff_ext(b"\xef\xbb\xbfwith bom"), Some("txt"));
+        assert_eq!(sniff_ext(b"\xff\xfeu\0t\0f\0"), Some("txt"));
+        assert_eq!(sniff_ext(b"invalid \xc3\x28 utf-8"), None);
+    }
+}
@@ -2,7 +2,10 @@ pub mod auth;
 pub mod cipher;
 pub mod common;
 pub mod config;
+pub mod device_key_store;
 pub mod dir_pickers;
+pub mod downloads;
+pub mod encrypted_storage;
 pub mod eventstream;
 pub mod file_types;
 pub mod http;
@@ -13,18 +16,28 @@ pub mod rclone;
 pub mod remote;
 pub mod remote_files;
 pub mod remote_files_dir_pickers;
+pub mod repo_auto_lock;
 pub mod repo_config_backup;
 pub mod repo_create;
+pub mod repo_duplicates;
 pub mod repo_files;
 pub mod repo_files_browsers;
 pub mod repo_files_details;
 pub mod repo_files_dir_pickers;
+pub mod repo_files_index;
 pub mod repo_files_list;
 pub mod repo_files_move;
 pub mod repo_files_read;
+pub mod repo_files_search;
+pub mod repo_files_thumbnails;
+pub mod repo_import_export;
+pub mod repo_rekey;
 pub mod repo_remove;
 pub mod repo_space_usage;
+pub mod repo_sync;
+pub mod repo_trash;
 pub mod repo_unlock;
+pub mod repo_verify;
 pub mod repos;
 pub mod runtime;
 pub mod secure_storage;
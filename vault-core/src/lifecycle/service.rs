@@ -38,6 +38,8 @@ impl LifecycleService {
 
         if self.oauth2_service.is_authenticated() {
             self.on_login().await?;
+
+            self.repos_service.unlock_remembered_repos().await;
         }
 
         Ok(())
@@ -62,6 +64,9 @@ impl LifecycleService {
     fn on_logout(&self) {
         self.eventstream_service.disconnect();
 
+        // another user could log in on this device
+        self.repos_service.forget_all_repos();
+
         self.store.mutate_state(|state| {
             state.reset();
         });
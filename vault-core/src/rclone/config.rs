@@ -4,7 +4,11 @@ use ini::Ini;
 use slug::slugify;
 use thiserror::Error;
 
-use crate::{rclone::obscure::obscure, utils::path_utils::normalize_path};
+use crate::{
+    cipher::name_cipher::{FilenameEncoding, FilenameEncryption},
+    rclone::obscure::obscure,
+    utils::path_utils::normalize_path,
+};
 
 use super::obscure::reveal;
 
@@ -14,6 +18,9 @@ pub struct Config {
     pub path: String,
     pub password: String,
     pub salt: Option<String>,
+    pub filename_encryption: FilenameEncryption,
+    pub filename_encoding: FilenameEncoding,
+    pub directory_name_encryption: bool,
 }
 
 #[derive(Error, Clone, Debug)]
@@ -66,12 +73,46 @@ pub fn parse_config(config_str: &str) -> Result<Config, ParseConfigError> {
         })?),
         None => None,
     };
+    let filename_encryption = match props.get("filename_encryption") {
+        Some(filename_encryption) => {
+            FilenameEncryption::parse(filename_encryption).ok_or_else(|| {
+                ParseConfigError(format!(
+                    "unsupported filename_encryption: {}",
+                    filename_encryption
+                ))
+            })?
+        }
+        None => FilenameEncryption::Standard,
+    };
+    let filename_encoding = match props.get("filename_encoding") {
+        Some(filename_encoding) => FilenameEncoding::parse(filename_encoding).ok_or_else(|| {
+            ParseConfigError(format!(
+                "unsupported filename_encoding: {}",
+                filename_encoding
+            ))
+        })?,
+        None => FilenameEncoding::Base32,
+    };
+    let directory_name_encryption = match props.get("directory_name_encryption") {
+        Some(&"true") | Some(&"1") => true,
+        Some(&"false") | Some(&"0") => false,
+        Some(directory_name_encryption) => {
+            return Err(ParseConfigError(format!(
+                "invalid directory_name_encryption: {}",
+                directory_name_encryption
+            )));
+        }
+        None => true,
+    };
 
     Ok(Config {
         name: section_name.map(|name| name.to_string()),
         path: path.to_owned(),
         password: password.to_string(),
         salt,
+        filename_encryption,
+        filename_encoding,
+        directory_name_encryption,
     })
 }
 
@@ -92,6 +133,21 @@ pub fn generate_config(config: &Config) -> String {
             i.with_section(Some(&section_name))
                 .set("password2", obscure(&salt).unwrap());
         }
+
+        if config.filename_encryption != FilenameEncryption::Standard {
+            i.with_section(Some(&section_name))
+                .set("filename_encryption", config.filename_encryption.as_str());
+        }
+
+        if config.filename_encoding != FilenameEncoding::Base32 {
+            i.with_section(Some(&section_name))
+                .set("filename_encoding", config.filename_encoding.as_str());
+        }
+
+        if !config.directory_name_encryption {
+            i.with_section(Some(&section_name))
+                .set("directory_name_encryption", "false");
+        }
     }
 
     let mut out = Vec::new();
@@ -105,6 +161,8 @@ pub fn generate_config(config: &Config) -> String {
 pub mod tests {
     use regex::Regex;
 
+    use crate::cipher::name_cipher::{FilenameEncoding, FilenameEncryption};
+
     use super::{generate_config, parse_config, Config};
 
     #[test]
@@ -117,6 +175,9 @@ pub mod tests {
                 path: String::from("/Vault"),
                 password: String::from("testpassword"),
                 salt: Some(String::from("testsalt")),
+                filename_encryption: FilenameEncryption::Standard,
+                filename_encoding: FilenameEncoding::Base32,
+                directory_name_encryption: true,
             }
         );
 
@@ -128,6 +189,9 @@ pub mod tests {
                 path: String::from("/Vault"),
                 password: String::from("testpassword"),
                 salt: None,
+                filename_encryption: FilenameEncryption::Standard,
+                filename_encoding: FilenameEncoding::Base32,
+                directory_name_encryption: true,
             }
         );
 
@@ -139,6 +203,9 @@ pub mod tests {
                 path: String::from("/Vault"),
                 password: String::from("testpassword"),
                 salt: None,
+                filename_encryption: FilenameEncryption::Standard,
+                filename_encoding: FilenameEncoding::Base32,
+                directory_name_encryption: true,
             }
         );
 
@@ -150,6 +217,9 @@ pub mod tests {
                 path: String::from("/"),
                 password: String::from("testpassword"),
                 salt: None,
+                filename_encryption: FilenameEncryption::Standard,
+                filename_encoding: FilenameEncoding::Base32,
+                directory_name_encryption: true,
             }
         );
 
@@ -161,9 +231,89 @@ pub mod tests {
                 path: String::from("/"),
                 password: String::from("testpassword"),
                 salt: None,
+                filename_encryption: FilenameEncryption::Standard,
+                filename_encoding: FilenameEncoding::Base32,
+                directory_name_encryption: true,
+            }
+        );
+
+        assert_eq!(
+            parse_config("[vault-name]\ntype=crypt\nremote=koofr:/Vault\npassword=YMRulMcUAOo9raAGnYdie57EWnDFi_N283rEVw\nfilename_encryption=obfuscate\n")
+                .unwrap(),
+            Config {
+                name: Some(String::from("vault-name")),
+                path: String::from("/Vault"),
+                password: String::from("testpassword"),
+                salt: None,
+                filename_encryption: FilenameEncryption::Obfuscate,
+                filename_encoding: FilenameEncoding::Base32,
+                directory_name_encryption: true,
+            }
+        );
+
+        assert_eq!(
+            parse_config("[vault-name]\ntype=crypt\nremote=koofr:/Vault\npassword=YMRulMcUAOo9raAGnYdie57EWnDFi_N283rEVw\nfilename_encryption=off\n")
+                .unwrap(),
+            Config {
+                name: Some(String::from("vault-name")),
+                path: String::from("/Vault"),
+                password: String::from("testpassword"),
+                salt: None,
+                filename_encryption: FilenameEncryption::Off,
+                filename_encoding: FilenameEncoding::Base32,
+                directory_name_encryption: true,
+            }
+        );
+
+        assert_eq!(
+            parse_config("[vault-name]\ntype=crypt\nremote=koofr:/Vault\npassword=YMRulMcUAOo9raAGnYdie57EWnDFi_N283rEVw\nfilename_encoding=base32768\n")
+                .unwrap(),
+            Config {
+                name: Some(String::from("vault-name")),
+                path: String::from("/Vault"),
+                password: String::from("testpassword"),
+                salt: None,
+                filename_encryption: FilenameEncryption::Standard,
+                filename_encoding: FilenameEncoding::Base32768,
+                directory_name_encryption: true,
+            }
+        );
+
+        assert_eq!(
+            parse_config("[vault-name]\ntype=crypt\nremote=koofr:/Vault\npassword=YMRulMcUAOo9raAGnYdie57EWnDFi_N283rEVw\ndirectory_name_encryption=false\n")
+                .unwrap(),
+            Config {
+                name: Some(String::from("vault-name")),
+                path: String::from("/Vault"),
+                password: String::from("testpassword"),
+                salt: None,
+                filename_encryption: FilenameEncryption::Standard,
+                filename_encoding: FilenameEncoding::Base32,
+                directory_name_encryption: false,
             }
         );
 
+        assert_eq!(
+            parse_config("[vault-name]\ntype=crypt\nremote=koofr:/Vault\npassword=YMRulMcUAOo9raAGnYdie57EWnDFi_N283rEVw\ndirectory_name_encryption=foo\n")
+                .unwrap_err()
+                .to_string(),
+            "parse config failed: invalid directory_name_encryption: foo"
+        );
+
+        assert_eq!(
+            parse_config("[vault-name]\ntype=crypt\nremote=koofr:/Vault\npassword=YMRulMcUAOo9raAGnYdie57EWnDFi_N283rEVw\nfilename_encoding=foo\n")
+                .unwrap_err()
+                .to_string(),
+            "parse config failed: unsupported filename_encoding: foo"
+        );
+
+        assert_eq!(
+            parse_config("[vault-name]\ntype=crypt\nremote=koofr:/Vault\npassword=YMRulMcUAOo9raAGnYdie57EWnDFi_N283rEVw\nfilename_encryption=foo\n")
+                .unwrap_err()
+                .to_string(),
+            "parse config failed: unsupported filename_encryption: foo"
+        );
+
         assert_eq!(
             parse_config("[vault-1]\ntype=crypt\nremote=koofr:/Vault\npassword=YMRulMcUAOo9raAGnYdie57EWnDFi_N283rEVw\n\n[vault-2]\ntype=crypt\nremote=koofr:/Vault\npassword=YMRulMcUAOo9raAGnYdie57EWnDFi_N283rEVw\n")
                 .unwrap_err()
@@ -219,6 +369,9 @@ pub mod tests {
             path: String::from("/Vault"),
             password: String::from("testpassword"),
             salt: Some(String::from("testsalt")),
+            filename_encryption: FilenameEncryption::Standard,
+            filename_encoding: FilenameEncoding::Base32,
+            directory_name_encryption: true,
         });
         let expected =
             "^\\[vault-name\\]\ntype=crypt\nremote=koofr:/Vault\npassword=.*\npassword2=.*\n$";
@@ -229,6 +382,9 @@ pub mod tests {
             path: String::from("/Vault"),
             password: String::from("testpassword"),
             salt: Some(String::from("testsalt")),
+            filename_encryption: FilenameEncryption::Standard,
+            filename_encoding: FilenameEncoding::Base32,
+            directory_name_encryption: true,
         });
         let expected =
             "^\\[vault\\]\ntype=crypt\nremote=koofr:/Vault\npassword=.*\npassword2=.*\n$";
@@ -239,8 +395,47 @@ pub mod tests {
             path: String::from("/Vault"),
             password: String::from("testpassword"),
             salt: None,
+            filename_encryption: FilenameEncryption::Standard,
+            filename_encoding: FilenameEncoding::Base32,
+            directory_name_encryption: true,
         });
         let expected = "^\\[vault-name\\]\ntype=crypt\nremote=koofr:/Vault\npassword=.*\n$";
         assert!(Regex::new(expected).unwrap().is_match(&config));
+
+        let config = generate_config(&Config {
+            name: Some(String::from("Vault name")),
+            path: String::from("/Vault"),
+            password: String::from("testpassword"),
+            salt: None,
+            filename_encryption: FilenameEncryption::Obfuscate,
+            filename_encoding: FilenameEncoding::Base32,
+            directory_name_encryption: true,
+        });
+        let expected = "^\\[vault-name\\]\ntype=crypt\nremote=koofr:/Vault\npassword=.*\nfilename_encryption=obfuscate\n$";
+        assert!(Regex::new(expected).unwrap().is_match(&config));
+
+        let config = generate_config(&Config {
+            name: Some(String::from("Vault name")),
+            path: String::from("/Vault"),
+            password: String::from("testpassword"),
+            salt: None,
+            filename_encryption: FilenameEncryption::Standard,
+            filename_encoding: FilenameEncoding::Base64,
+            directory_name_encryption: true,
+        });
+        let expected = "^\\[vault-name\\]\ntype=crypt\nremote=koofr:/Vault\npassword=.*\nfilename_encoding=base64\n$";
+        assert!(Regex::new(expected).unwrap().is_match(&config));
+
+        let config = generate_config(&Config {
+            name: Some(String::from("Vault name")),
+            path: String::from("/Vault"),
+            password: String::from("testpassword"),
+            salt: None,
+            filename_encryption: FilenameEncryption::Standard,
+            filename_encoding: FilenameEncoding::Base32,
+            directory_name_encryption: false,
+        });
+        let expected = "^\\[vault-name\\]\ntype=crypt\nremote=koofr:/Vault\npassword=.*\ndirectory_name_encryption=false\n$";
+        assert!(Regex::new(expected).unwrap().is_match(&config));
     }
 }
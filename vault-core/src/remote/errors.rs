@@ -10,6 +10,7 @@ use super::models;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ApiErrorCode {
     NotFound,
+    MethodNotAllowed,
     AlreadyExists,
     NotDir,
     InvalidPath,
@@ -24,6 +25,7 @@ impl From<&str> for ApiErrorCode {
     fn from(code: &str) -> Self {
         match code {
             "NotFound" => Self::NotFound,
+            "MethodNotAllowed" => Self::MethodNotAllowed,
             "AlreadyExists" => Self::AlreadyExists,
             "NotDir" => Self::NotDir,
             "InvalidPath" => Self::InvalidPath,
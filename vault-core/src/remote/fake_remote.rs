@@ -0,0 +1,750 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use futures::AsyncReadExt;
+use http::{
+    header::{CONTENT_RANGE, CONTENT_TYPE, RANGE},
+    HeaderMap,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    http::{
+        mock_http_client::MockHttpResponse, HttpClient, HttpError, HttpRequest, HttpRequestBody,
+        HttpResponse,
+    },
+    utils::{hash_reader::ContentHasher, path_utils},
+};
+
+use super::{models, test_helpers::files_file_to_bundle_file};
+
+pub const FAKE_REMOTE_BASE_URL: &str = "https://app.koofr.net";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FakeFile {
+    pub typ: String,
+    pub content: Vec<u8>,
+    pub modified: i64,
+    pub tags: HashMap<String, Vec<String>>,
+}
+
+impl FakeFile {
+    pub fn dir() -> Self {
+        Self {
+            typ: String::from("dir"),
+            content: Vec::new(),
+            modified: 1,
+            tags: HashMap::new(),
+        }
+    }
+
+    pub fn file(content: Vec<u8>, modified: i64) -> Self {
+        Self {
+            typ: String::from("file"),
+            content,
+            modified,
+            tags: HashMap::new(),
+        }
+    }
+
+    fn to_files_file(&self, name: &str) -> models::FilesFile {
+        models::FilesFile {
+            name: name.to_owned(),
+            typ: self.typ.clone(),
+            modified: self.modified,
+            size: self.content.len() as i64,
+            content_type: match self.typ.as_str() {
+                "dir" => String::from(""),
+                _ => String::from("application/octet-stream"),
+            },
+            hash: match self.typ.as_str() {
+                "dir" => None,
+                _ => {
+                    let hasher = ContentHasher::new();
+                    hasher.update(&self.content);
+                    Some(hasher.hex_digest())
+                }
+            },
+            tags: self.tags.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FakeUploadSession {
+    pub mount_id: String,
+    pub parent_path: String,
+    pub name: String,
+    pub size: i64,
+    pub modified: Option<i64>,
+    pub autorename: bool,
+    pub overwrite: bool,
+    pub content: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct FakeRemoteState {
+    /// Files by mount id and path. Root of a mount always exists.
+    pub files: BTreeMap<(String, String), FakeFile>,
+    pub upload_sessions: HashMap<String, FakeUploadSession>,
+    pub vault_repos: Vec<models::VaultRepo>,
+    /// Method and url without the base url of every request
+    pub requests: Vec<String>,
+    next_id: u32,
+}
+
+/// Called before a request is handled. Returning a response skips the fake
+/// server, which is used to inject failures.
+pub type FakeRemoteIntercept =
+    Box<dyn Fn(&HttpRequest) -> Option<Result<MockHttpResponse, HttpError>> + Send + Sync>;
+
+/// In-memory Koofr API for service tests. Only the endpoints used by Remote
+/// are implemented.
+#[derive(Clone, Default)]
+pub struct FakeRemote {
+    pub state: Arc<Mutex<FakeRemoteState>>,
+    intercept: Arc<Mutex<Option<FakeRemoteIntercept>>>,
+}
+
+impl FakeRemote {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_intercept(&self, intercept: Option<FakeRemoteIntercept>) {
+        *self.intercept.lock().unwrap() = intercept;
+    }
+
+    pub fn add_dir(&self, mount_id: &str, path: &str) {
+        let mut state = self.state.lock().unwrap();
+
+        for path in path_utils::paths_chain(path) {
+            if path != "/" {
+                state
+                    .files
+                    .entry((mount_id.to_owned(), path))
+                    .or_insert_with(FakeFile::dir);
+            }
+        }
+    }
+
+    pub fn add_file(&self, mount_id: &str, path: &str, content: Vec<u8>, modified: i64) {
+        if let Some(parent_path) = path_utils::parent_path(path) {
+            self.add_dir(mount_id, parent_path);
+        }
+
+        self.state.lock().unwrap().files.insert(
+            (mount_id.to_owned(), path.to_owned()),
+            FakeFile::file(content, modified),
+        );
+    }
+
+    pub fn get(&self, mount_id: &str, path: &str) -> Option<FakeFile> {
+        get_file(&self.state.lock().unwrap(), mount_id, path)
+    }
+
+    /// Paths of all files and dirs in the mount, sorted
+    pub fn paths(&self, mount_id: &str) -> Vec<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .files
+            .keys()
+            .filter(|(file_mount_id, _)| file_mount_id == mount_id)
+            .map(|(_, path)| path.clone())
+            .collect()
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    fn handle(
+        &self,
+        method: &str,
+        path: &str,
+        query: &HashMap<String, String>,
+        headers: &HeaderMap,
+        body: Vec<u8>,
+    ) -> MockHttpResponse {
+        let mut state = self.state.lock().unwrap();
+
+        let api_path = path.strip_prefix("/content").unwrap_or(path);
+        let api_path = match api_path.strip_prefix("/api/v2.1/") {
+            Some(api_path) => api_path,
+            None => return error_response(404, "NotFound", "Not found"),
+        };
+
+        if api_path == "vault/repos" && method == "GET" {
+            return json_response(&models::VaultReposBundle {
+                repos: state.vault_repos.clone(),
+                mounts: HashMap::new(),
+            });
+        }
+
+        let (mount_id, endpoint) = match api_path
+            .strip_prefix("mounts/")
+            .and_then(|rest| rest.split_once('/'))
+        {
+            Some(x) => x,
+            None => return error_response(404, "NotFound", "Not found"),
+        };
+        let file_path = query.get("path").map(String::as_str).unwrap_or("/");
+
+        match (method, endpoint) {
+            ("GET", "bundle") => bundle(&state, mount_id, file_path),
+            ("GET", "files/info") => match get_file(&state, mount_id, file_path) {
+                Some(file) => json_response(&file.to_files_file(file_name(file_path))),
+                None => not_found(),
+            },
+            ("GET", "files/get") => get_content(&state, mount_id, file_path, headers),
+            ("GET", "files/listrecursive") => list_recursive(&state, mount_id, file_path),
+            ("POST", "files/put") => {
+                let name = query.get("filename").cloned().unwrap_or_default();
+                let modified = query.get("modified").and_then(|x| x.parse().ok());
+
+                put_file(
+                    &mut state,
+                    mount_id,
+                    file_path,
+                    &name,
+                    body,
+                    modified,
+                    query_bool(query, "autorename"),
+                    query_bool(query, "overwrite"),
+                )
+            }
+            ("POST", "files/upload-sessions") => {
+                state.next_id += 1;
+
+                let id = format!("session{}", state.next_id);
+
+                state.upload_sessions.insert(
+                    id.clone(),
+                    FakeUploadSession {
+                        mount_id: mount_id.to_owned(),
+                        parent_path: file_path.to_owned(),
+                        name: query.get("filename").cloned().unwrap_or_default(),
+                        size: query.get("size").and_then(|x| x.parse().ok()).unwrap_or(0),
+                        modified: query.get("modified").and_then(|x| x.parse().ok()),
+                        autorename: query_bool(query, "autorename"),
+                        overwrite: query_bool(query, "overwrite"),
+                        content: Vec::new(),
+                    },
+                );
+
+                json_response(&models::FilesUploadSession { id, offset: 0 })
+            }
+            ("GET", endpoint) if endpoint.starts_with("files/upload-sessions/") => {
+                let id = endpoint.trim_start_matches("files/upload-sessions/");
+
+                match state.upload_sessions.get(id) {
+                    Some(session) => json_response(&models::FilesUploadSession {
+                        id: id.to_owned(),
+                        offset: session.content.len() as i64,
+                    }),
+                    None => not_found(),
+                }
+            }
+            ("PUT", endpoint) if endpoint.starts_with("files/upload-sessions/") => {
+                let id = endpoint.trim_start_matches("files/upload-sessions/");
+                let start = headers
+                    .get(CONTENT_RANGE)
+                    .and_then(|x| x.to_str().ok())
+                    .and_then(super::RemoteFileContentRange::parse)
+                    .map(|range| range.start);
+
+                match state.upload_sessions.get_mut(id) {
+                    Some(session) => {
+                        if start != Some(session.content.len() as i64) {
+                            return error_response(400, "InvalidRange", "Invalid range");
+                        }
+
+                        session.content.extend(body);
+
+                        json_response(&models::FilesUploadSession {
+                            id: id.to_owned(),
+                            offset: session.content.len() as i64,
+                        })
+                    }
+                    None => not_found(),
+                }
+            }
+            ("POST", endpoint)
+                if endpoint.starts_with("files/upload-sessions/")
+                    && endpoint.ends_with("/finish") =>
+            {
+                let id = endpoint
+                    .trim_start_matches("files/upload-sessions/")
+                    .trim_end_matches("/finish");
+
+                match state.upload_sessions.remove(id) {
+                    Some(session) => {
+                        if session.content.len() as i64 != session.size {
+                            return error_response(400, "InvalidRange", "Upload not complete");
+                        }
+
+                        put_file(
+                            &mut state,
+                            &session.mount_id,
+                            &session.parent_path,
+                            &session.name,
+                            session.content,
+                            session.modified,
+                            session.autorename,
+                            session.overwrite,
+                        )
+                    }
+                    None => not_found(),
+                }
+            }
+            ("DELETE", "files/remove") => {
+                if get_file(&state, mount_id, file_path).is_none() {
+                    return not_found();
+                }
+
+                remove_tree(&mut state, mount_id, file_path);
+
+                empty_response()
+            }
+            ("POST", "files/folder") => {
+                let name = match parse_body::<models::FilesFolderCreate>(&body) {
+                    Some(req) => req.name,
+                    None => return bad_request(),
+                };
+
+                match get_file(&state, mount_id, file_path) {
+                    Some(parent) if parent.typ == "dir" => {}
+                    Some(_) => return error_response(400, "NotDir", "Not a dir"),
+                    None => return not_found(),
+                }
+
+                let path = path_utils::join_path_name(file_path, &name);
+
+                if get_file(&state, mount_id, &path).is_some() {
+                    return already_exists();
+                }
+
+                state
+                    .files
+                    .insert((mount_id.to_owned(), path), FakeFile::dir());
+
+                empty_response()
+            }
+            ("PUT", "files/rename") => {
+                let name = match parse_body::<models::FilesRename>(&body) {
+                    Some(req) => req.name,
+                    None => return bad_request(),
+                };
+                let to_path = match path_utils::parent_path(file_path) {
+                    Some(parent_path) => path_utils::join_path_name(parent_path, &name),
+                    None => return bad_request(),
+                };
+
+                transfer_tree(&mut state, mount_id, file_path, mount_id, &to_path, true)
+            }
+            ("PUT", "files/copy") => match parse_body::<models::FilesCopy>(&body) {
+                Some(req) => transfer_tree(
+                    &mut state,
+                    mount_id,
+                    file_path,
+                    &req.to_mount_id,
+                    &req.to_path,
+                    false,
+                ),
+                None => bad_request(),
+            },
+            ("PUT", "files/move") => match parse_body::<models::FilesMove>(&body) {
+                Some(req) => transfer_tree(
+                    &mut state,
+                    mount_id,
+                    file_path,
+                    &req.to_mount_id,
+                    &req.to_path,
+                    true,
+                ),
+                None => bad_request(),
+            },
+            ("POST", "files/tags/set") => {
+                let tags = match parse_body::<models::FilesTagsSet>(&body) {
+                    Some(req) => req.tags,
+                    None => return bad_request(),
+                };
+
+                match state
+                    .files
+                    .get_mut(&(mount_id.to_owned(), file_path.to_owned()))
+                {
+                    Some(file) => {
+                        file.tags = tags;
+
+                        empty_response()
+                    }
+                    None => not_found(),
+                }
+            }
+            _ => error_response(404, "NotFound", "Not found"),
+        }
+    }
+}
+
+#[async_trait]
+impl HttpClient for FakeRemote {
+    async fn request(
+        &self,
+        request: HttpRequest,
+    ) -> Result<Box<dyn HttpResponse + Send + Sync>, HttpError> {
+        let url = url::Url::parse(&request.url)
+            .map_err(|err| HttpError::ResponseError(err.to_string()))?;
+        let relative_url = request
+            .url
+            .strip_prefix(FAKE_REMOTE_BASE_URL)
+            .unwrap_or(&request.url)
+            .to_owned();
+
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .push(format!("{} {}", request.method, relative_url));
+
+        let intercepted = match &*self.intercept.lock().unwrap() {
+            Some(intercept) => intercept(&request),
+            None => None,
+        };
+
+        if let Some(res) = intercepted {
+            return res.map(|res| Box::new(res) as Box<dyn HttpResponse + Send + Sync>);
+        }
+
+        let HttpRequest {
+            method,
+            headers,
+            body,
+            on_body_progress,
+            ..
+        } = request;
+
+        let body = match body {
+            Some(HttpRequestBody::Bytes(bytes)) => bytes,
+            Some(HttpRequestBody::Reader(mut reader)) => {
+                let mut bytes = Vec::new();
+
+                reader
+                    .read_to_end(&mut bytes)
+                    .await
+                    .map_err(|err| HttpError::ResponseError(err.to_string()))?;
+
+                bytes
+            }
+            None => Vec::new(),
+        };
+
+        if let Some(on_body_progress) = on_body_progress {
+            on_body_progress(body.len());
+        }
+
+        let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+        let res = self.handle(&method, url.path(), &query, &headers, body);
+
+        Ok(Box::new(res))
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path_utils::path_to_name(path).unwrap_or("")
+}
+
+fn get_file(state: &FakeRemoteState, mount_id: &str, path: &str) -> Option<FakeFile> {
+    match path {
+        "/" => Some(FakeFile::dir()),
+        _ => state
+            .files
+            .get(&(mount_id.to_owned(), path.to_owned()))
+            .cloned(),
+    }
+}
+
+fn is_descendant(path: &str, parent_path: &str) -> bool {
+    match parent_path {
+        "/" => path != "/",
+        _ => path
+            .strip_prefix(parent_path)
+            .map(|rest| rest.starts_with('/'))
+            .unwrap_or(false),
+    }
+}
+
+/// Descendants of path sorted so that parents come before their children
+fn descendants(state: &FakeRemoteState, mount_id: &str, path: &str) -> Vec<(String, FakeFile)> {
+    state
+        .files
+        .iter()
+        .filter(|((file_mount_id, file_path), _)| {
+            file_mount_id == mount_id && is_descendant(file_path, path)
+        })
+        .map(|((_, file_path), file)| (file_path.clone(), file.clone()))
+        .collect()
+}
+
+fn bundle(state: &FakeRemoteState, mount_id: &str, path: &str) -> MockHttpResponse {
+    let file = match get_file(state, mount_id, path) {
+        Some(file) => file,
+        None => return not_found(),
+    };
+
+    let files = match file.typ.as_str() {
+        "dir" => Some(
+            descendants(state, mount_id, path)
+                .into_iter()
+                .filter(|(child_path, _)| path_utils::parent_path(child_path) == Some(path))
+                .map(|(child_path, child)| {
+                    files_file_to_bundle_file(child.to_files_file(file_name(&child_path)))
+                })
+                .collect(),
+        ),
+        _ => None,
+    };
+
+    json_response(&models::Bundle {
+        file: files_file_to_bundle_file(file.to_files_file(file_name(path))),
+        files,
+    })
+}
+
+fn get_content(
+    state: &FakeRemoteState,
+    mount_id: &str,
+    path: &str,
+    headers: &HeaderMap,
+) -> MockHttpResponse {
+    let content = match get_file(state, mount_id, path) {
+        Some(file) if file.typ == "file" => file.content,
+        Some(_) => return error_response(400, "NotFile", "Not a file"),
+        None => return not_found(),
+    };
+    let size = content.len();
+
+    let range = headers
+        .get(RANGE)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.strip_prefix("bytes="))
+        .and_then(|x| x.split_once('-'))
+        .and_then(|(start, end)| {
+            let start: usize = start.parse().ok()?;
+            let end: usize = match end {
+                "" => size.checked_sub(1)?,
+                end => end.parse::<usize>().ok()?.min(size.checked_sub(1)?),
+            };
+
+            Some((start, end))
+        });
+
+    let mut res_headers = HeaderMap::new();
+
+    match range {
+        Some((start, end)) if start <= end => {
+            let bytes = content[start..=end].to_vec();
+
+            res_headers.insert(
+                CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, size).parse().unwrap(),
+            );
+            res_headers.insert("Content-Length", bytes.len().into());
+
+            MockHttpResponse::new(206, res_headers, bytes)
+        }
+        _ => {
+            res_headers.insert("Content-Length", size.into());
+
+            MockHttpResponse::new(200, res_headers, content)
+        }
+    }
+}
+
+fn list_recursive(state: &FakeRemoteState, mount_id: &str, path: &str) -> MockHttpResponse {
+    let file = match get_file(state, mount_id, path) {
+        Some(file) => file,
+        None => return not_found(),
+    };
+
+    let mut items = vec![models::FilesListRecursiveItem::File {
+        path: String::from("/"),
+        file: file.to_files_file(file_name(path)),
+    }];
+
+    for (item_path, item) in descendants(state, mount_id, path) {
+        let relative_path = match path {
+            "/" => item_path.clone(),
+            _ => item_path[path.len()..].to_owned(),
+        };
+
+        items.push(models::FilesListRecursiveItem::File {
+            path: relative_path,
+            file: item.to_files_file(file_name(&item_path)),
+        });
+    }
+
+    let lines = items
+        .iter()
+        .map(|item| serde_json::to_string(item).unwrap())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    MockHttpResponse::new(200, HeaderMap::new(), lines.into_bytes())
+}
+
+fn put_file(
+    state: &mut FakeRemoteState,
+    mount_id: &str,
+    parent_path: &str,
+    name: &str,
+    content: Vec<u8>,
+    modified: Option<i64>,
+    autorename: bool,
+    overwrite: bool,
+) -> MockHttpResponse {
+    match get_file(state, mount_id, parent_path) {
+        Some(parent) if parent.typ == "dir" => {}
+        Some(_) => return error_response(400, "NotDir", "Not a dir"),
+        None => return not_found(),
+    }
+
+    let mut name = name.to_owned();
+
+    if get_file(
+        state,
+        mount_id,
+        &path_utils::join_path_name(parent_path, &name),
+    )
+    .is_some()
+    {
+        if autorename {
+            let base_name = name.clone();
+            let mut i = 1;
+
+            while get_file(
+                state,
+                mount_id,
+                &path_utils::join_path_name(parent_path, &name),
+            )
+            .is_some()
+            {
+                name = format!("{} ({})", base_name, i);
+                i += 1;
+            }
+        } else if !overwrite {
+            return already_exists();
+        }
+    }
+
+    let file = FakeFile::file(content, modified.unwrap_or(1));
+    let res = json_response(&file.to_files_file(&name));
+
+    state.files.insert(
+        (
+            mount_id.to_owned(),
+            path_utils::join_path_name(parent_path, &name),
+        ),
+        file,
+    );
+
+    res
+}
+
+fn remove_tree(state: &mut FakeRemoteState, mount_id: &str, path: &str) {
+    state.files.retain(|(file_mount_id, file_path), _| {
+        file_mount_id != mount_id || (file_path != path && !is_descendant(file_path, path))
+    });
+}
+
+fn transfer_tree(
+    state: &mut FakeRemoteState,
+    mount_id: &str,
+    path: &str,
+    to_mount_id: &str,
+    to_path: &str,
+    remove: bool,
+) -> MockHttpResponse {
+    let file = match get_file(state, mount_id, path) {
+        Some(file) => file,
+        None => return not_found(),
+    };
+
+    if get_file(state, to_mount_id, to_path).is_some() {
+        return already_exists();
+    }
+
+    match path_utils::parent_path(to_path).and_then(|p| get_file(state, to_mount_id, p)) {
+        Some(parent) if parent.typ == "dir" => {}
+        _ => return not_found(),
+    }
+
+    let mut files = vec![(path.to_owned(), file)];
+    files.extend(descendants(state, mount_id, path));
+
+    if remove {
+        remove_tree(state, mount_id, path);
+    }
+
+    for (file_path, file) in files {
+        let new_path = format!("{}{}", to_path, &file_path[path.len()..]);
+
+        state.files.insert((to_mount_id.to_owned(), new_path), file);
+    }
+
+    empty_response()
+}
+
+fn query_bool(query: &HashMap<String, String>, key: &str) -> bool {
+    query.get(key).map(|x| x == "true").unwrap_or(false)
+}
+
+fn parse_body<T: DeserializeOwned>(body: &[u8]) -> Option<T> {
+    serde_json::from_slice(body).ok()
+}
+
+fn json_response<T: Serialize>(value: &T) -> MockHttpResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        "application/json; charset=utf-8".parse().unwrap(),
+    );
+
+    MockHttpResponse::new(200, headers, serde_json::to_vec(value).unwrap())
+}
+
+fn empty_response() -> MockHttpResponse {
+    MockHttpResponse::new(200, HeaderMap::new(), Vec::new())
+}
+
+pub fn error_response(status_code: u16, code: &str, message: &str) -> MockHttpResponse {
+    let mut res = json_response(&models::ApiError {
+        error: models::ApiErrorDetails {
+            code: code.to_owned(),
+            message: message.to_owned(),
+            extra: None,
+        },
+        request_id: String::from("fake"),
+    });
+
+    res.status_code = status_code;
+
+    res
+}
+
+fn not_found() -> MockHttpResponse {
+    error_response(404, "NotFound", "Not found")
+}
+
+fn already_exists() -> MockHttpResponse {
+    error_response(409, "AlreadyExists", "Already exists")
+}
+
+fn bad_request() -> MockHttpResponse {
+    error_response(400, "BadRequest", "Bad request")
+}
@@ -1,8 +1,13 @@
 pub mod errors;
+#[cfg(test)]
+pub mod fake_remote;
 pub mod models;
 pub mod remote;
 #[cfg(test)]
 pub mod test_helpers;
 
 pub use self::errors::{ApiErrorCode, RemoteError};
-pub use self::remote::{Remote, RemoteFileReader, RemoteFileUploadConflictResolution};
+pub use self::remote::{
+    Remote, RemoteFileContentRange, RemoteFileRange, RemoteFileReader,
+    RemoteFileUploadConflictResolution,
+};
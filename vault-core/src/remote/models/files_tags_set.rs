@@ -0,0 +1,8 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct FilesTagsSet {
+    pub tags: HashMap<String, Vec<String>>,
+}
@@ -10,6 +10,8 @@ pub mod files_folder_create;
 pub mod files_list_recursive_item;
 pub mod files_move;
 pub mod files_rename;
+pub mod files_tags_set;
+pub mod files_upload_session;
 pub mod mount;
 pub mod places;
 pub mod shared;
@@ -17,6 +19,7 @@ pub mod shared_file;
 pub mod user;
 pub mod vault_repo;
 pub mod vault_repo_create;
+pub mod vault_repo_update;
 pub mod vault_repos_bundle;
 
 pub use self::api_error::ApiError;
@@ -31,6 +34,8 @@ pub use self::files_folder_create::FilesFolderCreate;
 pub use self::files_list_recursive_item::FilesListRecursiveItem;
 pub use self::files_move::FilesMove;
 pub use self::files_rename::FilesRename;
+pub use self::files_tags_set::FilesTagsSet;
+pub use self::files_upload_session::FilesUploadSession;
 pub use self::mount::Mount;
 pub use self::places::Places;
 pub use self::shared::Shared;
@@ -38,4 +43,5 @@ pub use self::shared_file::SharedFile;
 pub use self::user::User;
 pub use self::vault_repo::VaultRepo;
 pub use self::vault_repo_create::VaultRepoCreate;
+pub use self::vault_repo_update::VaultRepoUpdate;
 pub use self::vault_repos_bundle::VaultReposBundle;
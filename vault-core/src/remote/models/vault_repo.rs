@@ -8,6 +8,15 @@ pub struct VaultRepo {
     pub mount_id: String,
     pub path: String,
     pub salt: Option<String>,
+    #[serde(rename = "filenameEncryption", skip_serializing_if = "Option::is_none")]
+    pub filename_encryption: Option<String>,
+    #[serde(rename = "filenameEncoding", skip_serializing_if = "Option::is_none")]
+    pub filename_encoding: Option<String>,
+    #[serde(
+        rename = "directoryNameEncryption",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub directory_name_encryption: Option<bool>,
     #[serde(rename = "passwordValidator")]
     pub password_validator: String,
     #[serde(rename = "passwordValidatorEncrypted")]
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct VaultRepoUpdate {
+    pub salt: Option<String>,
+    #[serde(rename = "passwordValidator")]
+    pub password_validator: String,
+    #[serde(rename = "passwordValidatorEncrypted")]
+    pub password_validator_encrypted: String,
+}
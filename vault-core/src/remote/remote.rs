@@ -1,9 +1,10 @@
+use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::{Arc, RwLock};
 
 use futures::stream::{BoxStream, TryStreamExt};
 use futures::{AsyncBufReadExt, AsyncRead, StreamExt};
-use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use http::header::{AUTHORIZATION, CONTENT_RANGE, CONTENT_TYPE, RANGE};
 use http::{HeaderMap, HeaderValue};
 use serde::Serialize;
 use urlencoding::encode;
@@ -22,17 +23,77 @@ use super::ApiErrorCode;
 pub type ListRecursiveItemStream =
     BoxStream<'static, Result<models::FilesListRecursiveItem, RemoteError>>;
 
+/// Byte range of a file. end is inclusive (same as HTTP Range header), None
+/// means until the end of the file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteFileRange {
+    pub start: i64,
+    pub end: Option<i64>,
+}
+
+impl RemoteFileRange {
+    pub fn to_header_value(&self) -> String {
+        match self.end {
+            Some(end) => format!("bytes={}-{}", self.start, end),
+            None => format!("bytes={}-", self.start),
+        }
+    }
+}
+
+/// Parsed Content-Range response header. end is inclusive
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteFileContentRange {
+    pub start: i64,
+    pub end: i64,
+    pub size: i64,
+}
+
+impl RemoteFileContentRange {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (range, size) = value.strip_prefix("bytes ")?.split_once('/')?;
+        let (start, end) = range.split_once('-')?;
+
+        Some(Self {
+            start: start.parse().ok()?,
+            end: end.parse().ok()?,
+            size: size.parse().ok()?,
+        })
+    }
+}
+
 pub struct RemoteFileReader {
     pub size: i64,
+    pub content_range: Option<RemoteFileContentRange>,
     pub reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>>,
 }
 
+impl RemoteFileReader {
+    /// Size of the whole file, not just the returned range
+    pub fn total_size(&self) -> i64 {
+        match &self.content_range {
+            Some(content_range) => content_range.size,
+            None => self.size,
+        }
+    }
+}
+
 pub enum RemoteFileUploadConflictResolution {
     Autorename,
     Overwrite,
     Error,
 }
 
+impl RemoteFileUploadConflictResolution {
+    /// Returns autorename and overwrite query params
+    fn to_params(&self) -> (bool, bool) {
+        match self {
+            Self::Autorename => (true, false),
+            Self::Overwrite => (false, true),
+            Self::Error => (false, false),
+        }
+    }
+}
+
 pub type Logout = Box<dyn Fn() + Send + Sync + 'static>;
 
 pub struct Remote {
@@ -201,6 +262,30 @@ impl Remote {
         res_json(res).await
     }
 
+    pub async fn update_vault_repo(
+        &self,
+        repo_id: &str,
+        update: models::VaultRepoUpdate,
+    ) -> Result<models::VaultRepo, RemoteError> {
+        let (req_body, req_headers) = req_json(&update);
+
+        let res = self
+            .request(HttpRequest {
+                method: String::from("PUT"),
+                url: format!("/api/v2.1/vault/repos/{}", repo_id),
+                headers: req_headers,
+                body: req_body,
+                ..Default::default()
+            })
+            .await?;
+
+        if res.status_code() != 200 {
+            return res_error(res).await;
+        }
+
+        res_json(res).await
+    }
+
     pub async fn remove_vault_repo(&self, repo_id: &str) -> Result<(), RemoteError> {
         let res = self
             .request(HttpRequest {
@@ -362,7 +447,14 @@ impl Remote {
         &self,
         mount_id: &str,
         path: &str,
+        range: Option<RemoteFileRange>,
     ) -> Result<RemoteFileReader, RemoteError> {
+        let mut headers = HeaderMap::new();
+
+        if let Some(range) = &range {
+            headers.insert(RANGE, range.to_header_value().parse().unwrap());
+        }
+
         let res = self
             .request(HttpRequest {
                 method: String::from("GET"),
@@ -371,14 +463,25 @@ impl Remote {
                     mount_id,
                     encode(path)
                 ),
+                headers,
                 ..Default::default()
             })
             .await?;
 
-        if res.status_code() != 200 {
+        if res.status_code() != 200 && res.status_code() != 206 {
             return res_error(res).await;
         }
 
+        // server can ignore the range and return the whole file with 200
+        let content_range = match res.status_code() {
+            206 => res
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(RemoteFileContentRange::parse),
+            _ => None,
+        };
+
         let size = res
             .headers()
             .get("Content-Length")
@@ -395,6 +498,7 @@ impl Remote {
 
         Ok(RemoteFileReader {
             size,
+            content_range,
             reader: Box::pin(reader),
         })
     }
@@ -406,15 +510,12 @@ impl Remote {
         name: &str,
         reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>>,
         size: Option<i64>,
+        modified: Option<i64>,
         conflict_resolution: RemoteFileUploadConflictResolution,
         on_progress: Option<Box<dyn Fn(usize) + Send + Sync>>,
         abort: HttpRequestAbort,
     ) -> Result<models::FilesFile, RemoteError> {
-        let (autorename, overwrite) = match conflict_resolution {
-            RemoteFileUploadConflictResolution::Autorename => (true, false),
-            RemoteFileUploadConflictResolution::Overwrite => (false, true),
-            RemoteFileUploadConflictResolution::Error => (false, false),
-        };
+        let (autorename, overwrite) = conflict_resolution.to_params();
 
         let mut url = format!(
             "/content/api/v2.1/mounts/{}/files/put?path={}&filename={}&autorename={}&overwrite={}&info=true",
@@ -429,6 +530,10 @@ impl Remote {
             url = format!("{}&size={}", url, size);
         }
 
+        if let Some(modified) = modified {
+            url = format!("{}&modified={}", url, modified);
+        }
+
         let res = self
             .request(HttpRequest {
                 method: String::from("POST"),
@@ -448,6 +553,145 @@ impl Remote {
         res_json(res).await
     }
 
+    /// Creates a session for uploading a file in chunks. size is required
+    /// because chunks are sent with a Content-Range header. Servers without
+    /// upload sessions respond with NotFound or MethodNotAllowed.
+    pub async fn create_upload_session(
+        &self,
+        mount_id: &str,
+        parent_path: &str,
+        name: &str,
+        size: i64,
+        modified: Option<i64>,
+        conflict_resolution: RemoteFileUploadConflictResolution,
+    ) -> Result<models::FilesUploadSession, RemoteError> {
+        let (autorename, overwrite) = conflict_resolution.to_params();
+
+        let mut url = format!(
+            "/content/api/v2.1/mounts/{}/files/upload-sessions?path={}&filename={}&autorename={}&overwrite={}&size={}",
+            mount_id,
+            encode(parent_path),
+            encode(name),
+            autorename,
+            overwrite,
+            size,
+        );
+
+        if let Some(modified) = modified {
+            url = format!("{}&modified={}", url, modified);
+        }
+
+        let res = self
+            .request(HttpRequest {
+                method: String::from("POST"),
+                url,
+                ..Default::default()
+            })
+            .await?;
+
+        if res.status_code() != 200 {
+            return res_error(res).await;
+        }
+
+        res_json(res).await
+    }
+
+    /// Returns the upload session with the offset confirmed by the server
+    pub async fn get_upload_session(
+        &self,
+        mount_id: &str,
+        session_id: &str,
+    ) -> Result<models::FilesUploadSession, RemoteError> {
+        let res = self
+            .request(HttpRequest {
+                method: String::from("GET"),
+                url: format!(
+                    "/content/api/v2.1/mounts/{}/files/upload-sessions/{}",
+                    mount_id,
+                    encode(session_id)
+                ),
+                ..Default::default()
+            })
+            .await?;
+
+        if res.status_code() != 200 {
+            return res_error(res).await;
+        }
+
+        res_json(res).await
+    }
+
+    /// Uploads a chunk of length bytes at offset. size is the size of the
+    /// whole file.
+    pub async fn upload_session_chunk(
+        &self,
+        mount_id: &str,
+        session_id: &str,
+        offset: i64,
+        length: i64,
+        size: i64,
+        reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>>,
+        on_progress: Option<Box<dyn Fn(usize) + Send + Sync>>,
+        abort: HttpRequestAbort,
+    ) -> Result<models::FilesUploadSession, RemoteError> {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            CONTENT_RANGE,
+            format!("bytes {}-{}/{}", offset, offset + length - 1, size)
+                .parse()
+                .unwrap(),
+        );
+
+        let res = self
+            .request(HttpRequest {
+                method: String::from("PUT"),
+                url: format!(
+                    "/content/api/v2.1/mounts/{}/files/upload-sessions/{}",
+                    mount_id,
+                    encode(session_id)
+                ),
+                headers,
+                body: Some(HttpRequestBody::Reader(reader)),
+                on_body_progress: on_progress,
+                abort,
+                ..Default::default()
+            })
+            .await?;
+
+        if res.status_code() != 200 {
+            return res_error(res).await;
+        }
+
+        res_json(res).await
+    }
+
+    /// Finishes the upload session once all chunks are uploaded and returns
+    /// the created file
+    pub async fn finish_upload_session(
+        &self,
+        mount_id: &str,
+        session_id: &str,
+    ) -> Result<models::FilesFile, RemoteError> {
+        let res = self
+            .request(HttpRequest {
+                method: String::from("POST"),
+                url: format!(
+                    "/content/api/v2.1/mounts/{}/files/upload-sessions/{}/finish?info=true",
+                    mount_id,
+                    encode(session_id)
+                ),
+                ..Default::default()
+            })
+            .await?;
+
+        if res.status_code() != 200 {
+            return res_error(res).await;
+        }
+
+        res_json(res).await
+    }
+
     pub async fn delete_file(&self, mount_id: &str, path: &str) -> Result<(), RemoteError> {
         let res = self
             .request(HttpRequest {
@@ -596,6 +840,36 @@ impl Remote {
 
         Ok(())
     }
+
+    /// Replaces all tags of the file
+    pub async fn set_file_tags(
+        &self,
+        mount_id: &str,
+        path: &str,
+        tags: HashMap<String, Vec<String>>,
+    ) -> Result<(), RemoteError> {
+        let (req_body, req_headers) = req_json(&models::FilesTagsSet { tags });
+
+        let res = self
+            .request(HttpRequest {
+                method: String::from("POST"),
+                url: format!(
+                    "/api/v2.1/mounts/{}/files/tags/set?path={}",
+                    mount_id,
+                    encode(path)
+                ),
+                headers: req_headers,
+                body: req_body,
+                ..Default::default()
+            })
+            .await?;
+
+        if res.status_code() != 200 {
+            return res_error(res).await;
+        }
+
+        Ok(())
+    }
 }
 
 pub fn req_json<T>(value: &T) -> (Option<HttpRequestBody>, HeaderMap)
@@ -636,6 +910,12 @@ async fn res_error<T>(res: Box<dyn HttpResponse + Send + Sync>) -> Result<T, Rem
         404 => {
             return Err(RemoteError::from_code(ApiErrorCode::NotFound, "Not found"));
         }
+        405 => {
+            return Err(RemoteError::from_code(
+                ApiErrorCode::MethodNotAllowed,
+                "Method not allowed",
+            ));
+        }
         _ => (),
     }
 
@@ -677,7 +957,7 @@ pub mod tests {
         remote::{models, RemoteError},
     };
 
-    use super::Remote;
+    use super::{Remote, RemoteFileContentRange, RemoteFileRange};
 
     fn get_remote(
         on_request: Box<dyn Fn(HttpRequest) -> Result<MockHttpResponse, HttpError> + Send + Sync>,
@@ -717,6 +997,106 @@ pub mod tests {
         )
     }
 
+    #[test]
+    fn test_get_file_reader_range() {
+        let remote = get_remote(Box::new(|req| {
+            assert_eq!(
+                req.headers.get("Range").unwrap().to_str().unwrap(),
+                "bytes=10-19"
+            );
+
+            let mut headers = HeaderMap::new();
+            headers.insert("Content-Length", "10".parse().unwrap());
+            headers.insert("Content-Range", "bytes 10-19/100".parse().unwrap());
+
+            Ok(MockHttpResponse::new(206, headers, vec![1; 10]))
+        }));
+
+        let reader = block_on(async {
+            remote
+                .get_file_reader(
+                    "m1",
+                    "/file",
+                    Some(RemoteFileRange {
+                        start: 10,
+                        end: Some(19),
+                    }),
+                )
+                .await
+        })
+        .unwrap();
+
+        assert_eq!(reader.size, 10);
+        assert_eq!(
+            reader.content_range,
+            Some(RemoteFileContentRange {
+                start: 10,
+                end: 19,
+                size: 100,
+            })
+        );
+        assert_eq!(reader.total_size(), 100);
+    }
+
+    #[test]
+    fn test_upload_session_chunk() {
+        let remote = get_remote(Box::new(|req| {
+            assert_eq!(req.method, "PUT");
+            assert_eq!(
+                req.url,
+                "https://app.koofr.net/content/api/v2.1/mounts/m1/files/upload-sessions/s1"
+            );
+            assert_eq!(
+                req.headers.get("Content-Range").unwrap().to_str().unwrap(),
+                "bytes 100-199/1000"
+            );
+
+            Ok(MockHttpResponse::new(
+                200,
+                HeaderMap::new(),
+                String::from(r#"{"id":"s1","offset":200}"#).into_bytes(),
+            ))
+        }));
+
+        let session = block_on(async {
+            remote
+                .upload_session_chunk(
+                    "m1",
+                    "s1",
+                    100,
+                    100,
+                    1000,
+                    Box::pin(futures::io::Cursor::new(vec![0; 100])),
+                    None,
+                    None,
+                )
+                .await
+        })
+        .unwrap();
+
+        assert_eq!(
+            session,
+            models::FilesUploadSession {
+                id: String::from("s1"),
+                offset: 200,
+            }
+        );
+    }
+
+    #[test]
+    fn test_remote_file_content_range_parse() {
+        assert_eq!(
+            RemoteFileContentRange::parse("bytes 0-31/1000"),
+            Some(RemoteFileContentRange {
+                start: 0,
+                end: 31,
+                size: 1000,
+            })
+        );
+        assert_eq!(RemoteFileContentRange::parse("bytes */1000"), None);
+        assert_eq!(RemoteFileContentRange::parse("0-31/1000"), None);
+    }
+
     #[test]
     fn test_get_list_recursive() {
         let remote = get_remote(Box::new(|_| {
@@ -54,6 +54,9 @@ pub fn create_repo(repo_id: &str, mount_id: &str, path: &str) -> models::VaultRe
         mount_id: mount_id.to_owned(),
         path: path.to_owned(),
         salt: None,
+        filename_encryption: None,
+        filename_encoding: None,
+        directory_name_encryption: None,
         password_validator: String::from("pv"),
         password_validator_encrypted: String::from("pve"),
         added: 1,
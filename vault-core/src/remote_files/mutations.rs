@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::remote::models;
 use crate::store;
 use crate::utils::path_utils;
@@ -15,6 +17,8 @@ pub fn mount_to_remote_file(id: String, mount_id: String) -> RemoteFile {
         typ: RemoteFileType::Dir,
         size: 0,
         modified: 0,
+        hash: None,
+        tags: HashMap::new(),
     }
 }
 
@@ -35,6 +39,8 @@ pub fn files_file_to_remote_file(
         typ: file.typ.as_str().into(),
         size: file.size,
         modified: file.modified,
+        hash: file.hash,
+        tags: file.tags,
     }
 }
 
@@ -55,6 +61,8 @@ pub fn bundle_file_to_remote_file(
         typ: file.typ.as_str().into(),
         size: file.size,
         modified: file.modified,
+        hash: file.hash,
+        tags: file.tags,
     }
 }
 
@@ -70,6 +78,8 @@ fn bookmark_to_remote_file(id: String, bookmark: models::Bookmark) -> RemoteFile
         typ: RemoteFileType::Dir,
         size: 0,
         modified: 0,
+        hash: None,
+        tags: HashMap::new(),
     }
 }
 
@@ -83,6 +93,8 @@ fn shared_file_to_remote_file(id: String, shared_file: models::SharedFile) -> Re
         typ: shared_file.typ.as_str().into(),
         size: shared_file.size,
         modified: shared_file.modified,
+        hash: None,
+        tags: HashMap::new(),
     }
 }
 
@@ -101,6 +113,8 @@ fn dir_to_remote_file(id: String, mount_id: String, path: String) -> RemoteFile
         typ: RemoteFileType::Dir,
         size: 0,
         modified: 0,
+        hash: None,
+        tags: HashMap::new(),
     }
 }
 
@@ -302,6 +316,12 @@ pub fn file_created(state: &mut store::State, mount_id: &str, path: &str, file:
     }
 }
 
+pub fn file_tags_set(state: &mut store::State, file_id: &str, tags: HashMap<String, Vec<String>>) {
+    if let Some(file) = state.remote_files.files.get_mut(file_id) {
+        file.tags = tags;
+    }
+}
+
 pub fn remove_child(state: &mut store::State, parent_id: &str, child_id: &str) {
     if let Some(children) = state.remote_files.children.get_mut(parent_id) {
         children.retain(|id| id != &child_id);
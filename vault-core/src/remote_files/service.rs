@@ -6,8 +6,8 @@ use futures::AsyncRead;
 use crate::{
     http,
     remote::{
-        models, remote::ListRecursiveItemStream, Remote, RemoteError, RemoteFileReader,
-        RemoteFileUploadConflictResolution,
+        models, remote::ListRecursiveItemStream, ApiErrorCode, Remote, RemoteError,
+        RemoteFileRange, RemoteFileReader, RemoteFileUploadConflictResolution,
     },
     store,
     utils::path_utils,
@@ -91,8 +91,9 @@ impl RemoteFilesService {
         &self,
         mount_id: &str,
         path: &str,
+        range: Option<RemoteFileRange>,
     ) -> Result<RemoteFileReader, RemoteError> {
-        self.remote.get_file_reader(&mount_id, &path).await
+        self.remote.get_file_reader(&mount_id, &path, range).await
     }
 
     pub async fn get_list_recursive(
@@ -110,6 +111,7 @@ impl RemoteFilesService {
         name: &str,
         reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>>,
         size: Option<i64>,
+        modified: Option<i64>,
         conflict_resolution: RemoteFileUploadConflictResolution,
         on_progress: Option<Box<dyn Fn(usize) + Send + Sync>>,
         abort: http::HttpRequestAbort,
@@ -122,6 +124,7 @@ impl RemoteFilesService {
                 name,
                 reader,
                 size,
+                modified,
                 conflict_resolution,
                 on_progress,
                 abort,
@@ -136,6 +139,79 @@ impl RemoteFilesService {
         Ok((selectors::get_file_id(mount_id, &path), name))
     }
 
+    pub async fn create_upload_session(
+        &self,
+        mount_id: &str,
+        parent_path: &str,
+        name: &str,
+        size: i64,
+        modified: Option<i64>,
+        conflict_resolution: RemoteFileUploadConflictResolution,
+    ) -> Result<models::FilesUploadSession, RemoteError> {
+        self.remote
+            .create_upload_session(
+                mount_id,
+                parent_path,
+                name,
+                size,
+                modified,
+                conflict_resolution,
+            )
+            .await
+    }
+
+    pub async fn get_upload_session(
+        &self,
+        mount_id: &str,
+        session_id: &str,
+    ) -> Result<models::FilesUploadSession, RemoteError> {
+        self.remote.get_upload_session(mount_id, session_id).await
+    }
+
+    pub async fn upload_session_chunk(
+        &self,
+        mount_id: &str,
+        session_id: &str,
+        offset: i64,
+        length: i64,
+        size: i64,
+        reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>>,
+        on_progress: Option<Box<dyn Fn(usize) + Send + Sync>>,
+        abort: http::HttpRequestAbort,
+    ) -> Result<models::FilesUploadSession, RemoteError> {
+        self.remote
+            .upload_session_chunk(
+                mount_id,
+                session_id,
+                offset,
+                length,
+                size,
+                reader,
+                on_progress,
+                abort,
+            )
+            .await
+    }
+
+    pub async fn finish_upload_session(
+        &self,
+        mount_id: &str,
+        parent_path: &str,
+        session_id: &str,
+    ) -> Result<(String, String), RemoteError> {
+        let file = self
+            .remote
+            .finish_upload_session(mount_id, session_id)
+            .await?;
+
+        let name = file.name.clone();
+        let path = path_utils::join_path_name(parent_path, &name);
+
+        self.file_created(mount_id, &path, file);
+
+        Ok((selectors::get_file_id(mount_id, &path), name))
+    }
+
     pub async fn delete_file(&self, mount_id: &str, path: &str) -> Result<(), RemoteError> {
         self.remote.delete_file(mount_id, path).await?;
 
@@ -159,6 +235,28 @@ impl RemoteFilesService {
         Ok(())
     }
 
+    /// Creates all dirs of the path that do not exist yet.
+    pub async fn ensure_dirs(&self, mount_id: &str, path: &str) -> Result<(), RemoteError> {
+        for path in path_utils::paths_chain(path) {
+            if path == "/" {
+                continue;
+            }
+
+            let (parent_path, name) = path_utils::split_parent_name(&path).unwrap();
+
+            match self.create_dir(mount_id, parent_path, name).await {
+                Ok(())
+                | Err(RemoteError::ApiError {
+                    code: ApiErrorCode::AlreadyExists,
+                    ..
+                }) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn copy_file(
         &self,
         mount_id: &str,
@@ -192,6 +290,33 @@ impl RemoteFilesService {
         self.remote.rename_file(mount_id, path, new_name).await
     }
 
+    /// Sets the tag values, other tags of the file are kept. The API replaces
+    /// all tags, so the current tags are fetched first. Tags set by another
+    /// client between the two requests are still lost.
+    pub async fn set_file_tag(
+        &self,
+        mount_id: &str,
+        path: &str,
+        key: &str,
+        values: Vec<String>,
+    ) -> Result<(), RemoteError> {
+        let file_id = selectors::get_file_id(mount_id, path);
+
+        let mut tags = self.remote.get_file(mount_id, path).await?.tags;
+
+        tags.insert(key.to_owned(), values);
+
+        self.remote
+            .set_file_tags(mount_id, path, tags.clone())
+            .await?;
+
+        self.store.mutate(store::Event::RemoteFiles, |state| {
+            mutations::file_tags_set(state, &file_id, tags);
+        });
+
+        Ok(())
+    }
+
     pub fn file_created(&self, mount_id: &str, path: &str, file: models::FilesFile) {
         self.store.mutate(store::Event::RemoteFiles, |state| {
             mutations::file_created(state, mount_id, path, file);
@@ -146,6 +146,8 @@ pub struct RemoteFile {
     pub typ: RemoteFileType,
     pub size: i64,
     pub modified: i64,
+    pub hash: Option<String>,
+    pub tags: HashMap<String, Vec<String>>,
 }
 
 impl RemoteFile {
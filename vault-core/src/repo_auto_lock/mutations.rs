@@ -0,0 +1,7 @@
+use crate::store;
+
+use super::state::RepoAutoLock;
+
+pub fn set_policy(state: &mut store::State, policy: RepoAutoLock) {
+    state.repo_auto_lock.policy = policy;
+}
@@ -0,0 +1,7 @@
+use crate::store;
+
+use super::state::RepoAutoLock;
+
+pub fn select_policy<'a>(state: &'a store::State) -> &'a RepoAutoLock {
+    &state.repo_auto_lock.policy
+}
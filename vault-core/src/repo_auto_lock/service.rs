@@ -0,0 +1,138 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    repos::{selectors as repos_selectors, ReposService},
+    runtime, store,
+    uploads::{selectors as uploads_selectors, UploadsService},
+};
+
+use super::{
+    mutations, selectors,
+    state::{RepoAutoLock, RepoAutoLockUploads},
+};
+
+const CHECK_INTERVAL: i32 = 5000;
+
+/// Locks unlocked repos after they were not used for the configured time.
+/// Repo activity is tracked by ReposService::touch_repo, which is called by
+/// repo files operations and on every read of file contents, so long
+/// downloads and transfers keep the repo unlocked.
+pub struct RepoAutoLockService {
+    repos_service: Arc<ReposService>,
+    uploads_service: Arc<UploadsService>,
+    store: Arc<store::Store>,
+    runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
+
+    checker_alive: Arc<Mutex<Option<Arc<()>>>>,
+}
+
+impl RepoAutoLockService {
+    pub fn new(
+        repos_service: Arc<ReposService>,
+        uploads_service: Arc<UploadsService>,
+        store: Arc<store::Store>,
+        runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
+    ) -> Self {
+        Self {
+            repos_service,
+            uploads_service,
+            store,
+            runtime,
+
+            checker_alive: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn set_policy(self: Arc<Self>, policy: RepoAutoLock) {
+        let has_limit = policy.after.duration_ms().is_some();
+
+        self.store.mutate(store::Event::RepoAutoLock, |state| {
+            mutations::set_policy(state, policy);
+        });
+
+        if has_limit {
+            self.start_checker();
+        } else {
+            self.stop_checker();
+        }
+    }
+
+    /// Locks all idle repos. Called periodically while a limit is set.
+    pub fn check(&self) {
+        let now = self.repos_service.now();
+
+        let (policy, unlocked_repo_ids) = self.store.with_state(|state| {
+            (
+                selectors::select_policy(state).clone(),
+                repos_selectors::select_repos(state)
+                    .into_iter()
+                    .filter(|repo| repo.state.is_unlocked())
+                    .map(|repo| repo.id.clone())
+                    .collect::<Vec<String>>(),
+            )
+        });
+
+        for repo_id in unlocked_repo_ids {
+            let last_activity = match self.repos_service.get_last_activity(&repo_id) {
+                Some(last_activity) => last_activity,
+                None => {
+                    // unlocked before the policy was set
+                    self.repos_service.touch_repo(&repo_id);
+
+                    continue;
+                }
+            };
+
+            if policy.after.is_idle(last_activity, now) {
+                self.lock_repo(&repo_id, policy.uploads);
+            }
+        }
+    }
+
+    fn lock_repo(&self, repo_id: &str, uploads: RepoAutoLockUploads) {
+        match uploads {
+            RepoAutoLockUploads::Finish => {
+                if self
+                    .store
+                    .with_state(|state| uploads_selectors::select_repo_has_pending(state, repo_id))
+                {
+                    return;
+                }
+
+                let _ = self.repos_service.lock_repo(repo_id);
+            }
+            RepoAutoLockUploads::Pause => {
+                // lock first so that aborted uploads fail with repo locked
+                let _ = self.repos_service.lock_repo(repo_id);
+
+                self.uploads_service.pause_repo(repo_id);
+            }
+        }
+    }
+
+    fn start_checker(self: Arc<Self>) {
+        let checker_alive = Arc::new(());
+        let checker_alive_weak = Arc::downgrade(&checker_alive);
+
+        // replacing the previous one stops the previous checker
+        *self.checker_alive.lock().unwrap() = Some(checker_alive);
+
+        let checker_self = self.clone();
+
+        self.runtime.spawn(Box::pin(async move {
+            loop {
+                checker_self.runtime.sleep(CHECK_INTERVAL).await;
+
+                if checker_alive_weak.upgrade().is_none() {
+                    break;
+                }
+
+                checker_self.check();
+            }
+        }));
+    }
+
+    fn stop_checker(&self) {
+        *self.checker_alive.lock().unwrap() = None;
+    }
+}
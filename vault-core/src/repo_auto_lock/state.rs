@@ -0,0 +1,81 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepoAutoLockAfter {
+    NoLimit,
+    Inactive1Minute,
+    Inactive5Minutes,
+    Inactive10Minutes,
+    Inactive30Minutes,
+    Inactive1Hour,
+    Inactive2Hours,
+    Inactive4Hours,
+}
+
+impl Default for RepoAutoLockAfter {
+    fn default() -> Self {
+        Self::NoLimit
+    }
+}
+
+impl RepoAutoLockAfter {
+    pub fn duration_ms(&self) -> Option<i64> {
+        const MINUTE: i64 = 60 * 1000;
+
+        match self {
+            Self::NoLimit => None,
+            Self::Inactive1Minute => Some(MINUTE),
+            Self::Inactive5Minutes => Some(5 * MINUTE),
+            Self::Inactive10Minutes => Some(10 * MINUTE),
+            Self::Inactive30Minutes => Some(30 * MINUTE),
+            Self::Inactive1Hour => Some(60 * MINUTE),
+            Self::Inactive2Hours => Some(2 * 60 * MINUTE),
+            Self::Inactive4Hours => Some(4 * 60 * MINUTE),
+        }
+    }
+
+    pub fn is_idle(&self, last_activity: i64, now: i64) -> bool {
+        match self.duration_ms() {
+            Some(duration_ms) => now - last_activity >= duration_ms,
+            None => false,
+        }
+    }
+}
+
+/// What happens to uploads of a repo that becomes idle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepoAutoLockUploads {
+    /// The repo is locked after all its uploads are done
+    Finish,
+    /// The repo is locked immediately. In-flight uploads fail with repo locked
+    /// error and can be retried after the repo is unlocked
+    Pause,
+}
+
+impl Default for RepoAutoLockUploads {
+    fn default() -> Self {
+        Self::Finish
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepoAutoLock {
+    pub after: RepoAutoLockAfter,
+    pub uploads: RepoAutoLockUploads,
+}
+
+#[derive(Clone, Default)]
+pub struct RepoAutoLockState {
+    pub policy: RepoAutoLock,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RepoAutoLockAfter;
+
+    #[test]
+    fn test_is_idle() {
+        assert!(!RepoAutoLockAfter::NoLimit.is_idle(0, i64::MAX));
+        assert!(!RepoAutoLockAfter::Inactive1Minute.is_idle(1000, 60999));
+        assert!(RepoAutoLockAfter::Inactive1Minute.is_idle(1000, 61000));
+        assert!(RepoAutoLockAfter::Inactive1Hour.is_idle(0, 3600000));
+    }
+}
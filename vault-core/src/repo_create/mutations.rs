@@ -1,5 +1,9 @@
 use crate::{
-    common::state::Status, rclone, remote::RemoteError, remote_files::state::RemoteFilesLocation,
+    cipher::name_cipher::{FilenameEncoding, FilenameEncryption},
+    common::state::Status,
+    rclone,
+    remote::RemoteError,
+    remote_files::state::RemoteFilesLocation,
     store,
 };
 
@@ -18,6 +22,9 @@ pub fn init_loading(state: &mut store::State, salt: String) {
         location_dir_picker_id: None,
         password: String::from(""),
         salt: Some(salt),
+        filename_encryption: FilenameEncryption::Standard,
+        filename_encoding: FilenameEncoding::Base32,
+        directory_name_encryption: true,
         fill_from_rclone_config_error: None,
         create_status: Status::Initial,
     }));
@@ -116,6 +123,9 @@ pub fn fill_from_rclone_config(
                 path,
                 password,
                 salt,
+                filename_encryption,
+                filename_encoding,
+                directory_name_encryption,
                 ..
             } = config;
 
@@ -128,6 +138,9 @@ pub fn fill_from_rclone_config(
 
             form.password = password;
             form.salt = salt;
+            form.filename_encryption = filename_encryption;
+            form.filename_encoding = filename_encoding;
+            form.directory_name_encryption = directory_name_encryption;
 
             form.fill_from_rclone_config_error = None;
         }
@@ -240,6 +240,9 @@ impl RepoCreateService {
             location,
             password,
             salt,
+            filename_encryption,
+            filename_encoding,
+            directory_name_encryption,
             ..
         } = form;
 
@@ -266,7 +269,10 @@ impl RepoCreateService {
             _ => false,
         };
 
-        let cipher = cipher::Cipher::new(&password, salt.as_deref());
+        let cipher = cipher::Cipher::new(&password, salt.as_deref())
+            .with_filename_encryption(filename_encryption)
+            .with_filename_encoding(filename_encoding)
+            .with_directory_name_encryption(directory_name_encryption);
 
         let (password_validator, password_validator_encrypted) =
             generate_password_validator(&cipher).await;
@@ -277,6 +283,9 @@ impl RepoCreateService {
                 mount_id: location.mount_id.clone(),
                 path: location.path.clone(),
                 salt: salt.clone(),
+                filename_encryption: Some(filename_encryption.as_str().to_owned()),
+                filename_encoding: Some(filename_encoding.as_str().to_owned()),
+                directory_name_encryption: Some(directory_name_encryption),
                 password_validator,
                 password_validator_encrypted,
             })
@@ -285,7 +294,7 @@ impl RepoCreateService {
 
         if !already_exists {
             for name in DEFAULT_DIR_NAMES {
-                let encrypted_name = cipher.encrypt_filename(name);
+                let encrypted_name = cipher.encrypt_dirname(name);
 
                 self.remote_files_service
                     .create_dir(&location.mount_id, &location.path, &encrypted_name)
@@ -294,7 +303,7 @@ impl RepoCreateService {
         }
 
         self.store.mutate(store::Event::Repos, |state| {
-            repos_mutations::repo_loaded(state, repo);
+            let _ = repos_mutations::repo_loaded(state, repo);
         });
 
         let config = self
@@ -1,5 +1,9 @@
 use crate::{
-    common::state::Status, rclone, remote::RemoteError, remote_files::state::RemoteFilesLocation,
+    cipher::name_cipher::{FilenameEncoding, FilenameEncryption},
+    common::state::Status,
+    rclone,
+    remote::RemoteError,
+    remote_files::state::RemoteFilesLocation,
     repos::state::RepoConfig,
 };
 
@@ -19,6 +23,9 @@ pub struct RepoCreateForm {
     pub location_dir_picker_id: Option<u32>,
     pub password: String,
     pub salt: Option<String>,
+    pub filename_encryption: FilenameEncryption,
+    pub filename_encoding: FilenameEncoding,
+    pub directory_name_encryption: bool,
     pub fill_from_rclone_config_error: Option<rclone::config::ParseConfigError>,
     pub create_status: Status<RepoCreateError>,
 }
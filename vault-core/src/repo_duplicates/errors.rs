@@ -0,0 +1,54 @@
+use thiserror::Error;
+
+use crate::{
+    cipher::errors::DecryptFilenameError,
+    remote::RemoteError,
+    repo_files::errors::LoadFilesError,
+    repo_files_list::errors::{FilesListRecursiveItemError, GetListRecursiveError},
+    repos::errors::{RepoLockedError, RepoNotFoundError},
+    user_error::UserError,
+};
+
+#[derive(Error, Debug, Clone, UserError)]
+pub enum RepoDuplicatesError {
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("{0}")]
+    DecryptFilenameError(#[from] DecryptFilenameError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+}
+
+impl From<LoadFilesError> for RepoDuplicatesError {
+    fn from(err: LoadFilesError) -> Self {
+        match err {
+            LoadFilesError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFilesError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFilesError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<GetListRecursiveError> for RepoDuplicatesError {
+    fn from(err: GetListRecursiveError) -> Self {
+        match err {
+            GetListRecursiveError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetListRecursiveError::RepoLocked(err) => Self::RepoLocked(err),
+            GetListRecursiveError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            GetListRecursiveError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<FilesListRecursiveItemError> for RepoDuplicatesError {
+    fn from(err: FilesListRecursiveItemError) -> Self {
+        match err {
+            FilesListRecursiveItemError::DecryptFilenameError(err) => {
+                Self::DecryptFilenameError(err)
+            }
+            FilesListRecursiveItemError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::{
+    repo_files::{selectors::is_hidden_path, state::RepoFileType},
+    repo_files_list::{errors::FilesListRecursiveItemError, state::RepoFilesListRecursiveItem},
+};
+
+use super::{errors::RepoDuplicatesError, state::RepoDuplicatesGroup};
+
+/// Groups the files of a recursive list of the repo root by content hash.
+/// Returns the groups with more than one file (largest waste first) and the
+/// number of files without a content hash. Hidden dirs are left out.
+pub fn list_recursive_items_to_groups(
+    items: Vec<RepoFilesListRecursiveItem>,
+) -> Result<(Vec<RepoDuplicatesGroup>, u32), RepoDuplicatesError> {
+    let mut groups: HashMap<String, RepoDuplicatesGroup> = HashMap::new();
+    let mut unhashed_count = 0;
+
+    for item in items {
+        match item {
+            RepoFilesListRecursiveItem::File {
+                relative_repo_path: Ok(path),
+                file,
+            } => {
+                if file.typ != RepoFileType::File || is_hidden_path(&path) {
+                    continue;
+                }
+
+                let size = file.decrypted_size();
+
+                let (content_hash, size) = match (file.content_hash, size) {
+                    (Some(content_hash), Ok(size)) => (content_hash, size),
+                    _ => {
+                        unhashed_count += 1;
+
+                        continue;
+                    }
+                };
+
+                groups
+                    .entry(content_hash.clone())
+                    .or_insert_with(|| RepoDuplicatesGroup {
+                        content_hash,
+                        size,
+                        paths: Vec::new(),
+                    })
+                    .paths
+                    .push(path);
+            }
+            RepoFilesListRecursiveItem::File { .. } => {}
+            RepoFilesListRecursiveItem::Error {
+                error: FilesListRecursiveItemError::DecryptFilenameError(_),
+                ..
+            } => {}
+            RepoFilesListRecursiveItem::Error { error, .. } => return Err(error.into()),
+        }
+    }
+
+    let mut groups: Vec<RepoDuplicatesGroup> = groups
+        .into_values()
+        .filter(|group| group.paths.len() > 1)
+        .map(|mut group| {
+            group.paths.sort();
+            group
+        })
+        .collect();
+
+    groups.sort_by(|a, b| {
+        b.wasted_bytes()
+            .cmp(&a.wasted_bytes())
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+
+    Ok((groups, unhashed_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cipher::test_helpers::create_cipher,
+        repo_files::state::RepoFileSize,
+        repo_files_list::{
+            state::RepoFilesListRecursiveItem,
+            test_helpers::{create_list_recursive_item_dir, create_list_recursive_item_file},
+        },
+    };
+
+    use super::list_recursive_items_to_groups;
+
+    fn file(path: &str, size: i64, content_hash: Option<&str>) -> RepoFilesListRecursiveItem {
+        let cipher = create_cipher();
+
+        let mut item = create_list_recursive_item_file("m1", "/Vault", "r1", "/", path, &cipher);
+
+        if let RepoFilesListRecursiveItem::File { file, .. } = &mut item {
+            file.size = RepoFileSize::Decrypted { size };
+            file.content_hash = content_hash.map(str::to_string);
+        }
+
+        item
+    }
+
+    #[test]
+    fn test_list_recursive_items_to_groups() {
+        let cipher = create_cipher();
+
+        let items = vec![
+            create_list_recursive_item_dir("m1", "/Vault", "r1", "/", "/", &cipher),
+            create_list_recursive_item_dir("m1", "/Vault", "r1", "/", "/d", &cipher),
+            file("/d/a.txt", 10, Some("h1")),
+            file("/a.txt", 10, Some("h1")),
+            file("/b.txt", 10, Some("h2")),
+            file("/c.bin", 100, Some("h3")),
+            file("/d/c.bin", 100, Some("h3")),
+            file("/.trash/c.bin", 100, Some("h3")),
+            file("/.thumbnails/h3-256", 100, Some("h3")),
+            file("/old.txt", 10, None),
+        ];
+
+        let (groups, unhashed_count) = list_recursive_items_to_groups(items).unwrap();
+
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| (group.content_hash.as_str(), group.paths.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("h3", vec![String::from("/c.bin"), String::from("/d/c.bin")]),
+                ("h1", vec![String::from("/a.txt"), String::from("/d/a.txt")]),
+            ]
+        );
+        assert_eq!(groups[0].wasted_bytes(), 100);
+        assert_eq!(unhashed_count, 1);
+    }
+}
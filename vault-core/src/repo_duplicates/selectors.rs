@@ -0,0 +1,20 @@
+use crate::store;
+
+use super::state::RepoDuplicatesInfo;
+
+pub fn select_info<'a>(state: &'a store::State) -> Option<RepoDuplicatesInfo<'a>> {
+    state
+        .repo_duplicates
+        .as_ref()
+        .map(|repo_duplicates| RepoDuplicatesInfo {
+            repo_id: &repo_duplicates.repo_id,
+            status: &repo_duplicates.status,
+            groups: &repo_duplicates.groups,
+            unhashed_count: repo_duplicates.unhashed_count,
+            wasted_bytes: repo_duplicates
+                .groups
+                .iter()
+                .map(|group| group.wasted_bytes())
+                .sum(),
+        })
+}
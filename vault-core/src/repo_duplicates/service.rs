@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use futures::StreamExt;
+
+use crate::{
+    common::state::Status,
+    repo_files::{errors::RepoFilesErrors, selectors as repo_files_selectors, RepoFilesService},
+    repo_files_list::RepoFilesListService,
+    store,
+};
+
+use super::{
+    errors::RepoDuplicatesError,
+    mutations,
+    state::{RepoDuplicatesGroup, RepoDuplicatesState},
+};
+
+pub struct RepoDuplicatesService {
+    repo_files_service: Arc<RepoFilesService>,
+    repo_files_list_service: Arc<RepoFilesListService>,
+    store: Arc<store::Store>,
+}
+
+impl RepoDuplicatesService {
+    pub fn new(
+        repo_files_service: Arc<RepoFilesService>,
+        repo_files_list_service: Arc<RepoFilesListService>,
+        store: Arc<store::Store>,
+    ) -> Self {
+        Self {
+            repo_files_service,
+            repo_files_list_service,
+            store,
+        }
+    }
+
+    pub fn init(&self, repo_id: &str) {
+        self.store.mutate(store::Event::RepoDuplicates, |state| {
+            state.repo_duplicates = Some(RepoDuplicatesState {
+                repo_id: repo_id.to_owned(),
+                status: Status::Initial,
+                groups: Vec::new(),
+                unhashed_count: 0,
+            });
+        });
+    }
+
+    /// Lists the whole repo and groups the files by content hash
+    pub async fn find(&self) -> Result<(), RepoDuplicatesError> {
+        let repo_id = match self.store.mutate(store::Event::RepoDuplicates, |state| {
+            state.repo_duplicates.as_mut().map(|repo_duplicates| {
+                repo_duplicates.status = Status::Loading;
+
+                repo_duplicates.repo_id.clone()
+            })
+        }) {
+            Some(repo_id) => repo_id,
+            None => return Ok(()),
+        };
+
+        let res = self.find_groups(&repo_id).await;
+
+        self.store.mutate(store::Event::RepoDuplicates, |state| {
+            if let Some(ref mut repo_duplicates) = state.repo_duplicates {
+                if repo_duplicates.repo_id != repo_id {
+                    return;
+                }
+
+                match &res {
+                    Ok((groups, unhashed_count)) => {
+                        repo_duplicates.status = Status::Loaded;
+                        repo_duplicates.groups = groups.clone();
+                        repo_duplicates.unhashed_count = *unhashed_count;
+                    }
+                    Err(err) => {
+                        repo_duplicates.status = Status::Error { error: err.clone() };
+                    }
+                }
+            }
+        });
+
+        res.map(|_| ())
+    }
+
+    async fn find_groups(
+        &self,
+        repo_id: &str,
+    ) -> Result<(Vec<RepoDuplicatesGroup>, u32), RepoDuplicatesError> {
+        self.repo_files_service.load_files(repo_id, "/").await?;
+
+        let root_file = self
+            .store
+            .with_state(|state| {
+                repo_files_selectors::select_file(
+                    state,
+                    &repo_files_selectors::get_file_id(repo_id, "/"),
+                )
+                .cloned()
+            })
+            .ok_or_else(|| RepoDuplicatesError::RemoteError(RepoFilesErrors::not_found()))?;
+
+        let items = self
+            .repo_files_list_service
+            .get_list_recursive(&root_file)
+            .await?
+            .collect::<Vec<_>>()
+            .await;
+
+        mutations::list_recursive_items_to_groups(items)
+    }
+
+    pub fn destroy(&self, repo_id: &str) {
+        self.store.mutate(store::Event::RepoDuplicates, |state| {
+            if state.repo_duplicates.is_some()
+                && state.repo_duplicates.as_ref().unwrap().repo_id == repo_id
+            {
+                state.repo_duplicates = None;
+            }
+        })
+    }
+}
@@ -0,0 +1,36 @@
+use crate::common::state::Status;
+
+use super::errors::RepoDuplicatesError;
+
+/// Files with the same content hash
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepoDuplicatesGroup {
+    pub content_hash: String,
+    pub size: i64,
+    // sorted repo paths
+    pub paths: Vec<String>,
+}
+
+impl RepoDuplicatesGroup {
+    /// Bytes that would be freed by keeping only one of the files
+    pub fn wasted_bytes(&self) -> i64 {
+        self.size * (self.paths.len() as i64 - 1)
+    }
+}
+
+pub struct RepoDuplicatesInfo<'a> {
+    pub repo_id: &'a str,
+    pub status: &'a Status<RepoDuplicatesError>,
+    pub groups: &'a [RepoDuplicatesGroup],
+    pub unhashed_count: u32,
+    pub wasted_bytes: i64,
+}
+
+#[derive(Clone)]
+pub struct RepoDuplicatesState {
+    pub repo_id: String,
+    pub status: Status<RepoDuplicatesError>,
+    pub groups: Vec<RepoDuplicatesGroup>,
+    /// Files uploaded without a content hash cannot be compared
+    pub unhashed_count: u32,
+}
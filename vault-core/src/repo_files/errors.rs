@@ -25,6 +25,13 @@ impl RepoFilesErrors {
     pub fn invalid_path() -> RemoteError {
         RemoteError::from_code(ApiErrorCode::InvalidPath, "Invalid name or path")
     }
+
+    pub fn rekey_in_progress() -> RemoteError {
+        RemoteError::from_code(
+            ApiErrorCode::Other(String::from("RekeyInProgress")),
+            "Safe Box password is being changed",
+        )
+    }
 }
 
 #[derive(Error, Debug, Clone, PartialEq)]
@@ -93,6 +100,16 @@ pub enum DeleteFileError {
     RemoteError(#[from] RemoteError),
 }
 
+impl From<LoadFileError> for DeleteFileError {
+    fn from(err: LoadFileError) -> Self {
+        match err {
+            LoadFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFileError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
 #[derive(Error, Debug, Clone)]
 pub enum CreateDirError {
     #[error("{0}")]
@@ -117,6 +134,41 @@ impl UserError for CreateDirError {
     }
 }
 
+#[derive(Error, Debug, Clone)]
+pub enum CreateFileError {
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("{0}")]
+    DecryptFilenameError(#[from] DecryptFilenameError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+}
+
+impl UserError for CreateFileError {
+    fn user_error(&self) -> String {
+        match self {
+            Self::RemoteError(RemoteError::ApiError {
+                code: ApiErrorCode::AlreadyExists,
+                ..
+            }) => String::from("File with this name already exists."),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl From<UploadFileReaderError> for CreateFileError {
+    fn from(err: UploadFileReaderError) -> Self {
+        match err {
+            UploadFileReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            UploadFileReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            UploadFileReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            UploadFileReaderError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
 #[derive(Error, Debug, Clone)]
 pub enum EnsureDirError {
     #[error("{0}")]
@@ -129,6 +181,17 @@ pub enum EnsureDirError {
     RemoteError(#[from] RemoteError),
 }
 
+impl From<EnsureDirError> for UploadFileReaderError {
+    fn from(err: EnsureDirError) -> Self {
+        match err {
+            EnsureDirError::RepoNotFound(err) => Self::RepoNotFound(err),
+            EnsureDirError::RepoLocked(err) => Self::RepoLocked(err),
+            EnsureDirError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            EnsureDirError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
 #[derive(Error, Debug, Clone, UserError)]
 pub enum RenameFileError {
     #[error("{0}")]
@@ -141,6 +204,16 @@ pub enum RenameFileError {
     RemoteError(#[from] RemoteError),
 }
 
+impl From<LoadFileError> for RenameFileError {
+    fn from(err: LoadFileError) -> Self {
+        match err {
+            LoadFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFileError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
 #[derive(Error, Debug, Clone, UserError)]
 pub enum CopyFileError {
     #[error("invalid path")]
@@ -155,6 +228,16 @@ pub enum CopyFileError {
     RemoteError(#[from] RemoteError),
 }
 
+impl From<LoadFileError> for CopyFileError {
+    fn from(err: LoadFileError) -> Self {
+        match err {
+            LoadFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFileError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
 #[derive(Error, Debug, Clone, UserError)]
 pub enum MoveFileError {
     #[error("invalid path")]
@@ -169,6 +252,16 @@ pub enum MoveFileError {
     RemoteError(#[from] RemoteError),
 }
 
+impl From<LoadFileError> for MoveFileError {
+    fn from(err: LoadFileError) -> Self {
+        match err {
+            LoadFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFileError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
 impl From<CopyFileError> for MoveFileError {
     fn from(err: CopyFileError) -> Self {
         match err {
@@ -3,5 +3,7 @@ pub mod mutations;
 pub mod selectors;
 pub mod service;
 pub mod state;
+#[cfg(test)]
+pub mod test_helpers;
 
 pub use self::service::RepoFilesService;
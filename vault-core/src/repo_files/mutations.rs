@@ -1,10 +1,11 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     cipher::{data_cipher::decrypt_size, Cipher},
     file_types::{
         content_type::ext_to_content_type,
         file_icon_type::{ext_to_file_icon_type, FileIconType},
+        sniff::SniffedFileType,
     },
     remote_files::{
         selectors as remote_files_selectors,
@@ -17,7 +18,10 @@ use crate::{
 use super::{
     errors::DecryptFilesError,
     selectors,
-    state::{RepoFile, RepoFileName, RepoFilePath, RepoFileSize},
+    state::{
+        RepoFile, RepoFileName, RepoFilePath, RepoFileSize, RepoFileSniff, RepoFileType,
+        CONTENT_HASH_TAG,
+    },
 };
 
 pub fn sort_children(state: &mut store::State, file_id: &str) {
@@ -35,20 +39,31 @@ pub fn decrypt_files(
     path: &str,
     cipher: &Cipher,
 ) -> Result<(), DecryptFilesError> {
-    let (mount_id, full_path) =
-        selectors::select_repo_path_to_mount_path(state, repo_id, path, &cipher)?;
+    let (mount_id, full_path) = selectors::select_repo_path_to_mount_path(
+        state,
+        repo_id,
+        path,
+        &RepoFileType::Dir,
+        &cipher,
+    )?;
 
     let root_remote_file_id = remote_files_selectors::get_file_id(&mount_id, &full_path);
 
     if let Some(root_remote_file) = state.remote_files.files.get(&root_remote_file_id) {
         let root_repo_file = match path {
             "/" => get_root_file(repo_id, root_remote_file),
-            _ => decrypt_file(
-                repo_id,
-                path_utils::parent_path(path).unwrap(),
-                root_remote_file,
-                &cipher,
-            ),
+            _ => {
+                let mut file = decrypt_file(
+                    repo_id,
+                    path_utils::parent_path(path).unwrap(),
+                    root_remote_file,
+                    &cipher,
+                );
+
+                apply_sniff(&state.repo_files.sniffs, &mut file);
+
+                file
+            }
         };
         let root_repo_file_id = root_repo_file.id.clone();
 
@@ -64,7 +79,9 @@ pub fn decrypt_files(
                 .iter()
                 .filter_map(|id| state.remote_files.files.get(id))
             {
-                let repo_child = decrypt_file(repo_id, path, remote_child, &cipher);
+                let mut repo_child = decrypt_file(repo_id, path, remote_child, &cipher);
+
+                apply_sniff(&state.repo_files.sniffs, &mut repo_child);
 
                 children.push(repo_child.id.clone());
 
@@ -115,7 +132,11 @@ pub fn decrypt_file(
     remote_file: &RemoteFile,
     cipher: &Cipher,
 ) -> RepoFile {
-    let name = match cipher.decrypt_filename(&remote_file.name) {
+    let decrypted_name = match remote_file.typ {
+        RemoteFileType::Dir => cipher.decrypt_dirname(&remote_file.name),
+        RemoteFileType::File => cipher.decrypt_filename(&remote_file.name),
+    };
+    let name = match decrypted_name {
         Ok(name) => {
             let name_lower = name.to_lowercase();
 
@@ -180,6 +201,11 @@ pub fn decrypt_file(
         },
         RemoteFileType::Dir => (None, None, FileIconType::Folder),
     };
+    let content_hash = remote_file
+        .tags
+        .get(CONTENT_HASH_TAG)
+        .and_then(|values| values.first())
+        .and_then(|value| cipher.decrypt_value(value));
 
     RepoFile {
         id,
@@ -193,10 +219,56 @@ pub fn decrypt_file(
         typ: (&remote_file.typ).into(),
         size,
         modified: remote_file.modified,
+        content_hash,
+        remote_hash: remote_file.hash.clone(),
         icon_type,
     }
 }
 
+/// The sniffed type replaces the extension based content type and icon type
+/// if the extension is unknown or if it does not match the content. Generic
+/// types (text, zip) do not replace a known extension, e.g. json is text.
+pub fn apply_sniff(sniffs: &HashMap<String, RepoFileSniff>, file: &mut RepoFile) {
+    let file_type = match sniffs.get(&file.id) {
+        Some(RepoFileSniff {
+            modified,
+            size,
+            file_type: Some(file_type),
+        }) if *modified == file.modified && *size == file.size_force() => file_type,
+        _ => return,
+    };
+
+    if file.content_type.is_none()
+        || (!file_type.is_generic && file_type.icon_type() != file.icon_type)
+    {
+        file.content_type = file_type.content_type().map(str::to_string);
+        file.icon_type = file_type.icon_type();
+    }
+}
+
+pub fn file_sniffed(
+    state: &mut store::State,
+    file_id: &str,
+    modified: i64,
+    size: i64,
+    file_type: Option<SniffedFileType>,
+) {
+    let repo_files = &mut state.repo_files;
+
+    repo_files.sniffs.insert(
+        file_id.to_owned(),
+        RepoFileSniff {
+            modified,
+            size,
+            file_type,
+        },
+    );
+
+    if let Some(file) = repo_files.files.get_mut(file_id) {
+        apply_sniff(&repo_files.sniffs, file);
+    }
+}
+
 pub fn get_root_file(repo_id: &str, remote_file: &RemoteFile) -> RepoFile {
     RepoFile {
         id: selectors::get_file_id(repo_id, "/"),
@@ -212,15 +284,18 @@ pub fn get_root_file(repo_id: &str, remote_file: &RemoteFile) -> RepoFile {
         },
         ext: None,
         content_type: None,
-        typ: super::state::RepoFileType::Dir,
+        typ: RepoFileType::Dir,
         size: RepoFileSize::Decrypted { size: 0 },
         modified: 0,
+        content_hash: None,
+        remote_hash: None,
         icon_type: FileIconType::Folder,
     }
 }
 
 pub fn cleanup_file(state: &mut store::State, file_id: &str) {
     state.repo_files.files.remove(file_id);
+    state.repo_files.sniffs.remove(file_id);
 
     let file_id_prefix = if file_id.ends_with('/') {
         file_id.to_owned()
@@ -239,6 +314,15 @@ pub fn cleanup_file(state: &mut store::State, file_id: &str) {
         .repo_files
         .children
         .retain(|file_id, _| !file_id.starts_with(&file_id_prefix));
+
+    state
+        .repo_files
+        .sniffs
+        .retain(|file_id, _| !file_id.starts_with(&file_id_prefix));
+}
+
+pub fn set_content_hashes_enabled(state: &mut store::State, enabled: bool) {
+    state.repo_files.content_hashes_enabled = enabled;
 }
 
 #[cfg(test)]
@@ -248,12 +332,14 @@ mod tests {
             errors::{DecryptFilenameError, DecryptSizeError},
             test_helpers::create_cipher,
         },
-        file_types::file_icon_type::FileIconType,
+        file_types::{file_icon_type::FileIconType, sniff::sniff_file_type},
         remote_files::test_helpers as remote_files_test_helpers,
-        repo_files::state::{RepoFile, RepoFileName, RepoFilePath, RepoFileSize, RepoFileType},
+        repo_files::state::{
+            RepoFile, RepoFileName, RepoFilePath, RepoFileSize, RepoFileSniff, RepoFileType,
+        },
     };
 
-    use super::{decrypt_file, get_root_file};
+    use super::{apply_sniff, decrypt_file, get_root_file};
 
     #[test]
     fn test_get_root_file() {
@@ -278,6 +364,8 @@ mod tests {
                 typ: RepoFileType::Dir,
                 size: RepoFileSize::Decrypted { size: 0 },
                 modified: 0,
+                content_hash: None,
+                remote_hash: None,
                 icon_type: FileIconType::Folder,
             }
         )
@@ -310,6 +398,39 @@ mod tests {
                 typ: RepoFileType::Dir,
                 size: RepoFileSize::Decrypted { size: 0 },
                 modified: 1,
+                content_hash: None,
+                remote_hash: None,
+                icon_type: FileIconType::Folder,
+            }
+        )
+    }
+
+    #[test]
+    fn test_decrypt_file_dir_directory_name_encryption_disabled() {
+        let cipher = create_cipher().with_directory_name_encryption(false);
+        let remote_file = remote_files_test_helpers::create_dir("m1", "/Vault/D1");
+
+        assert_eq!(
+            decrypt_file("r1", "/", &remote_file, &cipher),
+            RepoFile {
+                id: String::from("r1:/D1"),
+                mount_id: remote_file.mount_id.clone(),
+                remote_path: remote_file.path.clone(),
+                repo_id: String::from("r1",),
+                path: RepoFilePath::Decrypted {
+                    path: String::from("/D1")
+                },
+                name: RepoFileName::Decrypted {
+                    name: String::from("D1"),
+                    name_lower: String::from("d1")
+                },
+                ext: None,
+                content_type: None,
+                typ: RepoFileType::Dir,
+                size: RepoFileSize::Decrypted { size: 0 },
+                modified: 1,
+                content_hash: None,
+                remote_hash: None,
                 icon_type: FileIconType::Folder,
             }
         )
@@ -346,6 +467,8 @@ mod tests {
                 typ: RepoFileType::Dir,
                 size: RepoFileSize::Decrypted { size: 0 },
                 modified: 1,
+                content_hash: None,
+                remote_hash: None,
                 icon_type: FileIconType::Folder,
             }
         )
@@ -378,6 +501,8 @@ mod tests {
                 typ: RepoFileType::File,
                 size: RepoFileSize::Decrypted { size: 52 },
                 modified: 1,
+                content_hash: None,
+                remote_hash: Some(String::from("hash")),
                 icon_type: FileIconType::Image,
             }
         )
@@ -418,8 +543,67 @@ mod tests {
                     error: DecryptSizeError::EncryptedFileTooShort
                 },
                 modified: 1,
+                content_hash: None,
+                remote_hash: Some(String::from("hash")),
                 icon_type: FileIconType::Generic,
             }
         )
     }
+
+    #[test]
+    fn test_apply_sniff() {
+        let cipher = create_cipher();
+
+        let sniffed_file = |name: &str, bytes: &[u8]| {
+            let remote_file = remote_files_test_helpers::create_file(
+                "m1",
+                &format!("/Vault/{}", cipher.encrypt_filename(name)),
+            );
+            let mut file = decrypt_file("r1", "/", &remote_file, &cipher);
+
+            let sniffs = [(
+                file.id.clone(),
+                RepoFileSniff {
+                    modified: file.modified,
+                    size: file.size_force(),
+                    file_type: sniff_file_type(bytes),
+                },
+            )]
+            .into_iter()
+            .collect();
+
+            apply_sniff(&sniffs, &mut file);
+
+            (file.content_type, file.icon_type)
+        };
+
+        // no extension
+        assert_eq!(
+            sniffed_file("scan", b"%PDF-1.7\n"),
+            (Some(String::from("application/pdf")), FileIconType::Pdf)
+        );
+        assert_eq!(
+            sniffed_file("README", b"Read me"),
+            (Some(String::from("text/plain")), FileIconType::Text)
+        );
+        // mislabelled
+        assert_eq!(
+            sniffed_file("photo.txt", b"\xff\xd8\xff\xe0"),
+            (Some(String::from("image/jpeg")), FileIconType::Image)
+        );
+        // generic types do not replace a known extension
+        assert_eq!(
+            sniffed_file("report.docx", b"PK\x03\x04"),
+            (
+                Some(String::from(
+                    "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+                )),
+                FileIconType::Document
+            )
+        );
+        assert_eq!(
+            sniffed_file("data.json", b"{}"),
+            (Some(String::from("application/json")), FileIconType::Code)
+        );
+    }
 }
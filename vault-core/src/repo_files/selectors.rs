@@ -1,5 +1,6 @@
 use crate::{
     cipher, remote::RemoteError, remote_files::selectors as remote_files_selectors,
+    repo_files_thumbnails::state::is_thumbnails_path, repo_trash::state::is_trash_path,
     repos::errors::RepoNotFoundError, repos::selectors as repos_selectors, repos::state::Repo,
     store, utils::path_utils,
 };
@@ -13,6 +14,11 @@ pub fn get_file_id(repo_id: &str, path: &str) -> String {
     format!("{}:{}", repo_id, path)
 }
 
+/// Internal dirs (trash, thumbnails cache) that are not listed with the files
+pub fn is_hidden_path(path: &str) -> bool {
+    is_trash_path(path) || is_thumbnails_path(path)
+}
+
 pub fn select_children<'a>(state: &'a store::State, file_id: &str) -> Option<&'a Vec<String>> {
     state.repo_files.children.get(file_id)
 }
@@ -39,6 +45,16 @@ pub fn select_file<'a>(state: &'a store::State, file_id: &str) -> Option<&'a Rep
     state.repo_files.files.get(file_id)
 }
 
+/// Files are sniffed once for each modified time and size
+pub fn select_needs_sniff(state: &store::State, file: &RepoFile) -> bool {
+    file.typ == RepoFileType::File
+        && matches!(file.decrypted_size(), Ok(size) if size > 0)
+        && !matches!(
+            state.repo_files.sniffs.get(&file.id),
+            Some(sniff) if sniff.modified == file.modified && sniff.size == file.size_force()
+        )
+}
+
 pub fn select_file_name<'a>(state: &'a store::State, file: &'a RepoFile) -> Option<&'a str> {
     match file.decrypted_path() {
         Ok("/") => repos_selectors::select_repo(state, &file.repo_id)
@@ -52,15 +68,27 @@ pub fn select_file_name<'a>(state: &'a store::State, file: &'a RepoFile) -> Opti
     }
 }
 
+pub fn select_file_type(state: &store::State, repo_id: &str, path: &str) -> Option<RepoFileType> {
+    select_file(state, &get_file_id(repo_id, path)).map(|file| file.typ.clone())
+}
+
+/// File names can be encrypted differently than directory names so the
+/// caller has to know the type of the file at path
 pub fn select_repo_path_to_mount_path<'a>(
     state: &'a store::State,
     repo_id: &str,
     path: &str,
+    typ: &RepoFileType,
     cipher: &cipher::Cipher,
 ) -> Result<(String, String), RepoNotFoundError> {
     let repo = repos_selectors::select_repo(state, repo_id)?;
 
-    let full_path = path_utils::join_paths(&repo.path, &cipher.encrypt_path(path));
+    let encrypted_path = match typ {
+        RepoFileType::File => cipher.encrypt_file_path(path),
+        RepoFileType::Dir => cipher.encrypt_path(path),
+    };
+
+    let full_path = path_utils::join_paths(&repo.path, &encrypted_path);
 
     Ok((repo.mount_id.clone(), full_path))
 }
@@ -132,6 +160,10 @@ pub fn select_check_new_name_valid(
 
     let new_path = path_utils::join_path_name(parent_path, new_name);
 
+    if is_hidden_path(&new_path) {
+        return Err(RepoFilesErrors::invalid_path());
+    }
+
     match select_children(state, &get_file_id(repo_id, parent_path)) {
         Some(ids) => {
             if ids.contains(&get_file_id(repo_id, &new_path)) {
@@ -237,12 +269,22 @@ pub fn select_sorted_files(
         .collect()
 }
 
+pub fn select_content_hashes_enabled(state: &store::State) -> bool {
+    state.repo_files.content_hashes_enabled
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
-        cipher::test_helpers as cipher_test_helpers,
+        cipher::{name_cipher::FilenameEncryption, test_helpers as cipher_test_helpers},
         remote::test_helpers as remote_test_helpers,
-        repo_files::selectors::{select_mount_path_to_repo_id, select_repo_mount_path_to_path},
+        repo_files::{
+            selectors::{
+                select_check_new_name_valid, select_mount_path_to_repo_id,
+                select_repo_mount_path_to_path, select_repo_path_to_mount_path,
+            },
+            state::RepoFileType,
+        },
         repos::mutations as repos_mutations,
         store,
     };
@@ -294,4 +336,45 @@ mod tests {
             Ok(String::from("/foo/bar"))
         );
     }
+
+    #[test]
+    fn test_select_repo_path_to_mount_path_unloaded_file() {
+        let mut state = store::State::default();
+        let repo = remote_test_helpers::create_repo("r1", "m1", "/Vault");
+        repos_mutations::repos_loaded(&mut state, vec![repo.clone()]);
+        let cipher =
+            cipher_test_helpers::create_cipher().with_filename_encryption(FilenameEncryption::Off);
+
+        // nothing is loaded in the store, the type comes from the caller
+        assert_eq!(
+            select_repo_path_to_mount_path(
+                &state,
+                "r1",
+                "/foo/bar.txt",
+                &RepoFileType::File,
+                &cipher
+            ),
+            Ok((String::from("m1"), String::from("/Vault/foo/bar.txt.bin")))
+        );
+        assert_eq!(
+            select_repo_path_to_mount_path(
+                &state,
+                "r1",
+                "/foo/bar.txt",
+                &RepoFileType::Dir,
+                &cipher
+            ),
+            Ok((String::from("m1"), String::from("/Vault/foo/bar.txt")))
+        );
+    }
+
+    #[test]
+    fn test_select_check_new_name_valid_hidden() {
+        let state = store::State::default();
+
+        assert!(select_check_new_name_valid(&state, "r1", "/", ".trash").is_err());
+        assert!(select_check_new_name_valid(&state, "r1", "/", ".thumbnails").is_err());
+        assert!(select_check_new_name_valid(&state, "r1", "/foo", ".trash").is_ok());
+        assert!(select_check_new_name_valid(&state, "r1", "/", "trash").is_ok());
+    }
 }
@@ -1,4 +1,5 @@
 use std::{
+    cmp::min,
     collections::HashMap,
     pin::Pin,
     sync::{Arc, Mutex},
@@ -6,21 +7,35 @@ use std::{
 
 use futures::{
     future::{BoxFuture, Shared},
-    AsyncRead, FutureExt,
+    AsyncRead, AsyncReadExt, FutureExt,
 };
 
 use crate::{
     cipher::{
-        data_cipher::{decrypt_on_progress, encrypted_size},
+        constants::{BLOCK_DATA_SIZE, BLOCK_SIZE, FILE_HEADER_SIZE},
+        data_cipher::{decrypt_on_progress, encrypted_position, encrypted_size, EncryptedPosition},
+        nonce::Nonce,
         Cipher,
     },
+    file_types::sniff::sniff_file_type,
     http,
     remote::{self, models},
-    remote_files::{state::RemoteFilesLocation, RemoteFilesService},
-    repo_files_read::{errors::GetFilesReaderError, state::RepoFileReader, RepoFilesReadService},
+    remote_files::{
+        selectors as remote_files_selectors, state::RemoteFilesLocation, RemoteFilesService,
+    },
+    repo_files_read::{
+        errors::GetFilesReaderError,
+        state::{RepoFileRange, RepoFileReader},
+        RepoFilesReadService,
+    },
+    repo_rekey::selectors as repo_rekey_selectors,
     repos::{errors::RepoLockedError, ReposService},
     store,
-    utils::path_utils,
+    utils::{
+        error_reader::error_reader,
+        hash_reader::{ContentHasher, HashReader},
+        path_utils,
+    },
 };
 
 use super::{
@@ -30,9 +45,15 @@ use super::{
         RepoFilesErrors, RepoMountPathToPathError, UploadFileReaderError,
     },
     mutations, selectors,
-    state::{RepoFileType, RepoFilesUploadConflictResolution, RepoFilesUploadResult},
+    state::{
+        RepoFileType, RepoFileUploadable, RepoFilesUploadConflictResolution, RepoFilesUploadResult,
+        RepoFilesUploadSession, CONTENT_HASH_TAG,
+    },
 };
 
+/// Number of encrypted blocks in one resumable upload chunk (about 8 MiB).
+const UPLOAD_CHUNK_BLOCKS: i64 = 128;
+
 pub struct RepoFilesService {
     repos_service: Arc<ReposService>,
     remote_files_service: Arc<RemoteFilesService>,
@@ -58,14 +79,26 @@ impl RepoFilesService {
         }
     }
 
+    pub fn content_hashes_enabled(&self) -> bool {
+        self.store
+            .with_state(selectors::select_content_hashes_enabled)
+    }
+
+    pub fn set_content_hashes_enabled(&self, enabled: bool) {
+        self.store.mutate(store::Event::RepoFiles, |state| {
+            mutations::set_content_hashes_enabled(state, enabled);
+        });
+    }
+
     pub fn get_repo_mount_path_cipher(
         &self,
         repo_id: &str,
         path: &str,
+        typ: &RepoFileType,
         cipher: &Cipher,
     ) -> Result<(String, String), GetRepoMountPathError> {
         self.store.with_state(|state| {
-            selectors::select_repo_path_to_mount_path(state, repo_id, path, cipher)
+            selectors::select_repo_path_to_mount_path(state, repo_id, path, typ, cipher)
                 .map_err(GetRepoMountPathError::RepoNotFound)
         })
     }
@@ -74,31 +107,51 @@ impl RepoFilesService {
         &self,
         repo_id: &str,
         path: &str,
+        typ: &RepoFileType,
     ) -> Result<(String, String), GetRepoMountPathError> {
         let cipher = self
             .repos_service
             .get_cipher(repo_id)
             .map_err(GetRepoMountPathError::RepoLocked)?;
 
-        self.get_repo_mount_path_cipher(repo_id, path, &cipher)
+        self.get_repo_mount_path_cipher(repo_id, path, typ, &cipher)
     }
 
     pub fn get_repo_remote_location(
         &self,
         repo_id: &str,
         path: &str,
+        typ: &RepoFileType,
     ) -> Result<RemoteFilesLocation, GetRepoMountPathError> {
-        self.get_repo_mount_path(repo_id, path)
+        self.get_repo_mount_path(repo_id, path, typ)
             .map(|(mount_id, path)| RemoteFilesLocation { mount_id, path })
     }
 
+    /// Type of the file at path. File and dir names can be encrypted
+    /// differently, so a file that is not in the store yet is loaded first.
+    pub async fn get_file_type(
+        &self,
+        repo_id: &str,
+        path: &str,
+    ) -> Result<RepoFileType, LoadFileError> {
+        match self
+            .store
+            .with_state(|state| selectors::select_file_type(state, repo_id, path))
+        {
+            Some(typ) => Ok(typ),
+            None => self.load_file_unknown_type(repo_id, path).await,
+        }
+    }
+
     pub async fn load_files(&self, repo_id: &str, path: &str) -> Result<(), LoadFilesError> {
-        let (mount_id, remote_path) =
-            self.get_repo_mount_path(repo_id, path)
-                .map_err(|e| match e {
-                    GetRepoMountPathError::RepoNotFound(err) => LoadFilesError::RepoNotFound(err),
-                    GetRepoMountPathError::RepoLocked(err) => LoadFilesError::RepoLocked(err),
-                })?;
+        self.repos_service.touch_repo(repo_id);
+
+        let (mount_id, remote_path) = self
+            .get_repo_mount_path(repo_id, path, &RepoFileType::Dir)
+            .map_err(|e| match e {
+                GetRepoMountPathError::RepoNotFound(err) => LoadFilesError::RepoNotFound(err),
+                GetRepoMountPathError::RepoLocked(err) => LoadFilesError::RepoLocked(err),
+            })?;
 
         self.remote_files_service
             .load_files(&mount_id, &remote_path)
@@ -114,8 +167,47 @@ impl RepoFilesService {
     }
 
     pub async fn load_file(&self, repo_id: &str, path: &str) -> Result<(), LoadFileError> {
+        self.repos_service.touch_repo(repo_id);
+
+        match self
+            .store
+            .with_state(|state| selectors::select_file_type(state, repo_id, path))
+        {
+            Some(typ) => self.load_file_type(repo_id, path, &typ).await?,
+            None => self.load_file_unknown_type(repo_id, path).await?,
+        };
+
+        Ok(())
+    }
+
+    /// A path that is not in the store yet is tried as a file first and then
+    /// as a dir
+    async fn load_file_unknown_type(
+        &self,
+        repo_id: &str,
+        path: &str,
+    ) -> Result<RepoFileType, LoadFileError> {
+        match self
+            .load_file_type(repo_id, path, &RepoFileType::File)
+            .await
+        {
+            Err(LoadFileError::RemoteError(remote::RemoteError::ApiError {
+                code: remote::ApiErrorCode::NotFound,
+                ..
+            })) => self.load_file_type(repo_id, path, &RepoFileType::Dir).await,
+            res => res,
+        }
+    }
+
+    /// Returns the type of the loaded file
+    async fn load_file_type(
+        &self,
+        repo_id: &str,
+        path: &str,
+        typ: &RepoFileType,
+    ) -> Result<RepoFileType, LoadFileError> {
         let (mount_id, remote_path) =
-            self.get_repo_mount_path(repo_id, path)
+            self.get_repo_mount_path(repo_id, path, typ)
                 .map_err(|e| match e {
                     GetRepoMountPathError::RepoNotFound(err) => LoadFileError::RepoNotFound(err),
                     GetRepoMountPathError::RepoLocked(err) => LoadFileError::RepoLocked(err),
@@ -130,7 +222,14 @@ impl RepoFilesService {
             let _ = self.decrypt_files(&repo_id, parent_path);
         }
 
-        Ok(())
+        Ok(self.store.with_state(|state| {
+            remote_files_selectors::select_file(
+                state,
+                &remote_files_selectors::get_file_id(&mount_id, &remote_path),
+            )
+            .map(|file| RepoFileType::from(&file.typ))
+            .unwrap_or_else(|| typ.clone())
+        }))
     }
 
     pub fn encrypt_filename(&self, repo_id: &str, name: &str) -> Result<String, RepoLockedError> {
@@ -139,6 +238,12 @@ impl RepoFilesService {
         Ok(cipher.encrypt_filename(name))
     }
 
+    pub fn encrypt_dirname(&self, repo_id: &str, name: &str) -> Result<String, RepoLockedError> {
+        let cipher = self.repos_service.get_cipher(repo_id)?;
+
+        Ok(cipher.encrypt_dirname(name))
+    }
+
     pub fn decrypt_files(&self, repo_id: &str, path: &str) -> Result<(), DecryptFilesError> {
         let cipher = self.repos_service.get_cipher(repo_id)?;
 
@@ -147,19 +252,99 @@ impl RepoFilesService {
         })
     }
 
+    /// Reads the first decrypted block of the file and corrects its content
+    /// type and icon type if the extension is unknown or does not match the
+    /// content. Failures are recorded as well so that the file is not read
+    /// again until it changes.
+    pub async fn sniff_file(self: Arc<Self>, file_id: &str) -> Result<(), GetFilesReaderError> {
+        let file = match self.store.with_state(|state| {
+            selectors::select_file(state, file_id)
+                .filter(|file| selectors::select_needs_sniff(state, file))
+                .cloned()
+        }) {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+        let res = self.clone().read_sniff_block(file_id).await;
+
+        let file_type = match &res {
+            Ok(buf) => sniff_file_type(buf),
+            Err(_) => None,
+        };
+
+        self.store.mutate(store::Event::RepoFiles, |state| {
+            mutations::file_sniffed(state, file_id, file.modified, file.size_force(), file_type);
+        });
+
+        res.map(|_| ())
+    }
+
+    async fn read_sniff_block(
+        self: Arc<Self>,
+        file_id: &str,
+    ) -> Result<Vec<u8>, GetFilesReaderError> {
+        let mut reader = self
+            .clone()
+            .get_file_reader(
+                file_id,
+                Some(RepoFileRange {
+                    start: 0,
+                    end: Some(BLOCK_DATA_SIZE as i64 - 1),
+                }),
+            )
+            .await?;
+
+        let mut buf = Vec::new();
+
+        reader
+            .reader
+            .read_to_end(&mut buf)
+            .await
+            .map_err(|err| GetFilesReaderError::ReadError(err.to_string()))?;
+
+        Ok(buf)
+    }
+
+    /// Repo cannot be changed while its files are re-encrypted
+    fn check_not_rekeying(&self, repo_id: &str) -> Result<(), remote::RemoteError> {
+        if self
+            .store
+            .with_state(|state| repo_rekey_selectors::select_is_rekeying(state, repo_id))
+        {
+            Err(RepoFilesErrors::rekey_in_progress())
+        } else {
+            Ok(())
+        }
+    }
+
     pub async fn get_file_reader(
         self: Arc<Self>,
         file_id: &str,
+        range: Option<RepoFileRange>,
     ) -> Result<RepoFileReader, GetFilesReaderError> {
         let file = self
             .store
             .with_state(|state| selectors::select_file(state, file_id).map(|file| file.clone()))
             .ok_or(GetFilesReaderError::FileNotFound)?;
 
-        self.repo_files_read_service
-            .clone()
-            .get_files_reader(&[file])
-            .await
+        match range {
+            Some(range) => {
+                if file.typ == RepoFileType::Dir {
+                    return Err(GetFilesReaderError::InvalidRange);
+                }
+
+                self.repo_files_read_service
+                    .get_file_reader_range(&file, &range)
+                    .await
+            }
+            None => {
+                self.repo_files_read_service
+                    .clone()
+                    .get_files_reader(&[file])
+                    .await
+            }
+        }
     }
 
     pub async fn upload_file_reader(
@@ -169,33 +354,39 @@ impl RepoFilesService {
         name: &str,
         reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>>,
         size: Option<i64>,
+        modified: Option<i64>,
         conflict_resolution: RepoFilesUploadConflictResolution,
         on_progress: Option<Box<dyn Fn(usize) + Send + Sync>>,
         abort: http::HttpRequestAbort,
     ) -> Result<RepoFilesUploadResult, UploadFileReaderError> {
-        self.clone()
-            .ensure_dirs(repo_id, parent_path)
-            .await
-            .map_err(|e| match e {
-                EnsureDirError::RepoNotFound(err) => UploadFileReaderError::RepoNotFound(err),
-                EnsureDirError::RepoLocked(err) => UploadFileReaderError::RepoLocked(err),
-                EnsureDirError::DecryptFilenameError(err) => {
-                    UploadFileReaderError::DecryptFilenameError(err)
-                }
-                EnsureDirError::RemoteError(err) => UploadFileReaderError::RemoteError(err),
-            })?;
+        self.repos_service.touch_repo(repo_id);
+
+        self.check_not_rekeying(repo_id)?;
+
+        self.clone().ensure_dirs(repo_id, parent_path).await?;
 
         let cipher = self.repos_service.get_cipher(&repo_id)?;
 
         let (mount_id, remote_parent_path) = self
             .store
             .with_state(|state| {
-                selectors::select_repo_path_to_mount_path(state, repo_id, parent_path, &cipher)
+                selectors::select_repo_path_to_mount_path(
+                    state,
+                    repo_id,
+                    parent_path,
+                    &RepoFileType::Dir,
+                    &cipher,
+                )
             })
             .map_err(UploadFileReaderError::RepoNotFound)?;
 
         let encrypted_size = size.map(encrypted_size);
         let encrypted_name = cipher.encrypt_filename(name);
+        let hasher = self.content_hashes_enabled().then(ContentHasher::new);
+        let reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>> = match &hasher {
+            Some(hasher) => Box::pin(HashReader::new(reader, hasher.clone())),
+            None => reader,
+        };
         let encrypted_reader = cipher.encrypt_reader(reader);
 
         let (_, remote_name) = self
@@ -206,6 +397,7 @@ impl RepoFilesService {
                 &encrypted_name,
                 Box::pin(encrypted_reader),
                 encrypted_size,
+                modified,
                 conflict_resolution.into(),
                 on_progress.map(decrypt_on_progress),
                 abort,
@@ -213,6 +405,216 @@ impl RepoFilesService {
             .await
             .map_err(UploadFileReaderError::RemoteError)?;
 
+        if let Some(hasher) = hasher {
+            self.set_content_hash(
+                &mount_id,
+                &path_utils::join_path_name(&remote_parent_path, &remote_name),
+                &cipher,
+                &hasher.hex_digest(),
+            )
+            .await;
+        }
+
+        let _ = self.decrypt_files(&repo_id, &parent_path);
+
+        let name = cipher.decrypt_filename(&remote_name)?;
+        let path = path_utils::join_path_name(parent_path, &name);
+        let file_id = selectors::get_file_id(repo_id, &path);
+
+        Ok(RepoFilesUploadResult { file_id, name })
+    }
+
+    /// Uploads the file in chunks. If session is given, the upload continues
+    /// at the offset confirmed by the server. on_session is called before the
+    /// first chunk so that a failed upload can later be resumed.
+    pub async fn upload_file_resumable(
+        self: Arc<Self>,
+        repo_id: &str,
+        parent_path: &str,
+        name: &str,
+        uploadable: &(dyn RepoFileUploadable + Send + Sync),
+        size: i64,
+        conflict_resolution: RepoFilesUploadConflictResolution,
+        session: Option<RepoFilesUploadSession>,
+        on_session: Box<dyn Fn(RepoFilesUploadSession) + Send + Sync>,
+        on_progress: Option<Box<dyn Fn(usize) + Send + Sync>>,
+        abort: http::HttpRequestAbort,
+    ) -> Result<RepoFilesUploadResult, UploadFileReaderError> {
+        self.repos_service.touch_repo(repo_id);
+
+        self.check_not_rekeying(repo_id)?;
+
+        self.clone().ensure_dirs(repo_id, parent_path).await?;
+
+        let cipher = self.repos_service.get_cipher(&repo_id)?;
+
+        // continuing a changed source would reuse the nonce for different
+        // content, so it is uploaded again in a new session
+        let session = session.filter(|session| {
+            session.source_size == size && session.source_modified == uploadable.modified()
+        });
+
+        let resumed = match session {
+            Some(session) => match self
+                .remote_files_service
+                .get_upload_session(&session.mount_id, &session.session_id)
+                .await
+            {
+                Ok(remote_session) => Some((session, remote_session.offset)),
+                // session expired, start again
+                Err(remote::RemoteError::ApiError {
+                    code: remote::ApiErrorCode::NotFound,
+                    ..
+                }) => None,
+                Err(err) => return Err(err.into()),
+            },
+            None => None,
+        };
+
+        let (session, offset) = match resumed {
+            Some(resumed) => resumed,
+            None => match self
+                .create_upload_session(
+                    repo_id,
+                    parent_path,
+                    name,
+                    &cipher,
+                    size,
+                    uploadable.modified(),
+                    conflict_resolution,
+                )
+                .await
+            {
+                Ok(session) => (session, 0),
+                // the server does not support upload sessions (the parent dir
+                // was ensured above), the file is uploaded in one request
+                Err(UploadFileReaderError::RemoteError(remote::RemoteError::ApiError {
+                    code: remote::ApiErrorCode::NotFound | remote::ApiErrorCode::MethodNotAllowed,
+                    ..
+                })) => {
+                    return self
+                        .clone()
+                        .upload_file_reader(
+                            repo_id,
+                            parent_path,
+                            name,
+                            uploadable.reader(),
+                            Some(size),
+                            uploadable.modified(),
+                            conflict_resolution,
+                            on_progress,
+                            abort,
+                        )
+                        .await;
+                }
+                Err(err) => return Err(err),
+            },
+        };
+
+        on_session(session.clone());
+
+        let on_progress: Option<Arc<Box<dyn Fn(usize) + Send + Sync>>> =
+            on_progress.map(|on_progress| Arc::new(decrypt_on_progress(on_progress)));
+
+        // bytes confirmed before the upload was resumed
+        if offset > 0 {
+            if let Some(on_progress) = &on_progress {
+                on_progress(offset as usize);
+            }
+        }
+
+        let mut hasher = if self.content_hashes_enabled() {
+            hash_prefix(uploadable, encrypted_position(offset).plaintext_offset).await
+        } else {
+            None
+        };
+
+        let mut offset = offset;
+
+        while offset < session.encrypted_size {
+            let position = encrypted_position(offset);
+
+            // chunks end at block boundaries so that the next chunk does not
+            // have to encrypt a block just to skip it
+            let chunk_end = min(
+                FILE_HEADER_SIZE as i64
+                    + (position.block_index as i64 + UPLOAD_CHUNK_BLOCKS) * BLOCK_SIZE as i64,
+                session.encrypted_size,
+            );
+            let length = chunk_end - offset;
+            // the plaintext is limited to the blocks of this chunk so that the
+            // hasher does not see the read-ahead of the next chunk twice
+            let plaintext_end = min(
+                (position.block_index as i64 + UPLOAD_CHUNK_BLOCKS) * BLOCK_DATA_SIZE as i64,
+                size,
+            );
+
+            let reader = encrypted_reader_at(
+                &cipher,
+                uploadable,
+                &session.nonce,
+                &position,
+                plaintext_end - position.plaintext_offset,
+                hasher.as_ref(),
+            );
+
+            let chunk_on_progress = on_progress.clone().map(|on_progress| {
+                Box::new(move |n| on_progress(n)) as Box<dyn Fn(usize) + Send + Sync>
+            });
+
+            let remote_session = self
+                .remote_files_service
+                .upload_session_chunk(
+                    &session.mount_id,
+                    &session.session_id,
+                    offset,
+                    length,
+                    session.encrypted_size,
+                    Box::pin(reader.take(length as u64)),
+                    chunk_on_progress,
+                    abort.clone(),
+                )
+                .await?;
+
+            if remote_session.offset <= offset {
+                return Err(UploadFileReaderError::RemoteError(
+                    remote::RemoteError::HttpError(http::HttpError::ResponseError(String::from(
+                        "upload session offset did not advance",
+                    ))),
+                ));
+            }
+
+            if remote_session.offset < chunk_end && hasher.is_some() {
+                // the next chunk starts inside blocks that were already hashed
+                hasher = hash_prefix(
+                    uploadable,
+                    encrypted_position(remote_session.offset).plaintext_offset,
+                )
+                .await;
+            }
+
+            offset = remote_session.offset;
+        }
+
+        let (_, remote_name) = self
+            .remote_files_service
+            .finish_upload_session(
+                &session.mount_id,
+                &session.remote_parent_path,
+                &session.session_id,
+            )
+            .await?;
+
+        if let Some(hasher) = hasher {
+            self.set_content_hash(
+                &session.mount_id,
+                &path_utils::join_path_name(&session.remote_parent_path, &remote_name),
+                &cipher,
+                &hasher.hex_digest(),
+            )
+            .await;
+        }
+
         let _ = self.decrypt_files(&repo_id, &parent_path);
 
         let name = cipher.decrypt_filename(&remote_name)?;
@@ -222,9 +624,130 @@ impl RepoFilesService {
         Ok(RepoFilesUploadResult { file_id, name })
     }
 
+    /// Returns the file at parent_path/name if it has the same content as
+    /// uploadable. Only files with a content hash can be compared and the
+    /// uploadable is read an extra time so it has to support reader_at.
+    pub async fn find_identical_file(
+        &self,
+        repo_id: &str,
+        parent_path: &str,
+        name: &str,
+        uploadable: &(dyn RepoFileUploadable + Send + Sync),
+    ) -> Option<RepoFilesUploadResult> {
+        let path = path_utils::join_path_name(parent_path, name);
+        let file_id = selectors::get_file_id(repo_id, &path);
+
+        self.load_file(repo_id, &path).await.ok()?;
+
+        let content_hash = self.store.with_state(|state| {
+            selectors::select_file(state, &file_id)
+                .filter(|file| {
+                    file.typ == RepoFileType::File
+                        && (uploadable.size().is_none()
+                            || file.decrypted_size().ok() == uploadable.size())
+                })
+                .and_then(|file| file.content_hash.clone())
+        })?;
+
+        let hasher = ContentHasher::new();
+        let reader = HashReader::new(uploadable.reader_at(0)?, hasher.clone());
+
+        futures::io::copy(reader, &mut futures::io::sink())
+            .await
+            .ok()?;
+
+        if hasher.hex_digest() == content_hash {
+            Some(RepoFilesUploadResult {
+                file_id,
+                name: name.to_owned(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Stores the encrypted plaintext hash in a tag of the uploaded file. The
+    /// file is already uploaded so it is only left without a content hash if
+    /// this fails.
+    async fn set_content_hash(
+        &self,
+        mount_id: &str,
+        remote_path: &str,
+        cipher: &Cipher,
+        content_hash: &str,
+    ) {
+        if let Err(err) = self
+            .remote_files_service
+            .set_file_tag(
+                mount_id,
+                remote_path,
+                CONTENT_HASH_TAG,
+                vec![cipher.encrypt_value(content_hash)],
+            )
+            .await
+        {
+            log::warn!("Failed to set content hash: {}", err);
+        }
+    }
+
+    async fn create_upload_session(
+        &self,
+        repo_id: &str,
+        parent_path: &str,
+        name: &str,
+        cipher: &Cipher,
+        size: i64,
+        modified: Option<i64>,
+        conflict_resolution: RepoFilesUploadConflictResolution,
+    ) -> Result<RepoFilesUploadSession, UploadFileReaderError> {
+        let (mount_id, remote_parent_path) = self
+            .store
+            .with_state(|state| {
+                selectors::select_repo_path_to_mount_path(
+                    state,
+                    repo_id,
+                    parent_path,
+                    &RepoFileType::Dir,
+                    cipher,
+                )
+            })
+            .map_err(UploadFileReaderError::RepoNotFound)?;
+
+        let encrypted_size = encrypted_size(size);
+        let encrypted_name = cipher.encrypt_filename(name);
+
+        let remote_session = self
+            .remote_files_service
+            .create_upload_session(
+                &mount_id,
+                &remote_parent_path,
+                &encrypted_name,
+                encrypted_size,
+                modified,
+                conflict_resolution.into(),
+            )
+            .await?;
+
+        Ok(RepoFilesUploadSession {
+            mount_id,
+            remote_parent_path,
+            session_id: remote_session.id,
+            nonce: Nonce::new_random().unwrap(),
+            encrypted_size,
+            source_size: size,
+            source_modified: modified,
+        })
+    }
+
     pub async fn delete_file(&self, repo_id: &str, path: &str) -> Result<(), DeleteFileError> {
+        self.repos_service.touch_repo(repo_id);
+
+        self.check_not_rekeying(repo_id)?;
+
+        let typ = self.get_file_type(repo_id, path).await?;
+
         let (mount_id, remote_path) =
-            self.get_repo_mount_path(repo_id, path)
+            self.get_repo_mount_path(repo_id, path, &typ)
                 .map_err(|e| match e {
                     GetRepoMountPathError::RepoLocked(err) => DeleteFileError::RepoLocked(err),
                     GetRepoMountPathError::RepoNotFound(err) => DeleteFileError::RepoNotFound(err),
@@ -248,14 +771,18 @@ impl RepoFilesService {
         parent_path: &str,
         name: &str,
     ) -> Result<(), CreateDirError> {
+        self.repos_service.touch_repo(repo_id);
+
+        self.check_not_rekeying(repo_id)?;
+
         let (mount_id, remote_parent_path) = self
-            .get_repo_mount_path(repo_id, parent_path)
+            .get_repo_mount_path(repo_id, parent_path, &RepoFileType::Dir)
             .map_err(|e| match e {
                 GetRepoMountPathError::RepoLocked(err) => CreateDirError::RepoLocked(err),
                 GetRepoMountPathError::RepoNotFound(err) => CreateDirError::RepoNotFound(err),
             })?;
 
-        let encrypted_name = self.encrypt_filename(repo_id, name)?;
+        let encrypted_name = self.encrypt_dirname(repo_id, name)?;
 
         self.remote_files_service
             .create_dir(&mount_id, &remote_parent_path, &encrypted_name)
@@ -388,16 +915,25 @@ impl RepoFilesService {
         path: &str,
         name: &str,
     ) -> Result<(), RenameFileError> {
+        self.repos_service.touch_repo(repo_id);
+
+        self.check_not_rekeying(repo_id)?;
+
         self.check_rename_file(repo_id, path, name)?;
 
+        let typ = self.get_file_type(repo_id, path).await?;
+
         let (mount_id, remote_path) =
-            self.get_repo_mount_path(&repo_id, &path)
+            self.get_repo_mount_path(&repo_id, &path, &typ)
                 .map_err(|e| match e {
                     GetRepoMountPathError::RepoLocked(err) => RenameFileError::RepoLocked(err),
                     GetRepoMountPathError::RepoNotFound(err) => RenameFileError::RepoNotFound(err),
                 })?;
 
-        let encrypted_name = self.encrypt_filename(&repo_id, name)?;
+        let encrypted_name = match typ {
+            RepoFileType::File => self.encrypt_filename(&repo_id, name)?,
+            RepoFileType::Dir => self.encrypt_dirname(&repo_id, name)?,
+        };
 
         self.remote_files_service
             .rename_file(&mount_id, &remote_path, &encrypted_name)
@@ -411,22 +947,33 @@ impl RepoFilesService {
         path: &str,
         to_parent_path: &str,
     ) -> Result<(), CopyFileError> {
-        let name = path_utils::path_to_name(path).ok_or(CopyFileError::InvalidPath)?;
+        self.repos_service.touch_repo(repo_id);
+
+        self.check_not_rekeying(repo_id)?;
+
+        path_utils::path_to_name(path).ok_or(CopyFileError::InvalidPath)?;
+
+        let typ = self.get_file_type(repo_id, path).await?;
 
         let (mount_id, remote_path) =
-            self.get_repo_mount_path(repo_id, path)
+            self.get_repo_mount_path(repo_id, path, &typ)
                 .map_err(|e| match e {
                     GetRepoMountPathError::RepoLocked(err) => CopyFileError::RepoLocked(err),
                     GetRepoMountPathError::RepoNotFound(err) => CopyFileError::RepoNotFound(err),
                 })?;
 
-        let (to_mount_id, to_remote_path) = self
-            .get_repo_mount_path(repo_id, &path_utils::join_path_name(to_parent_path, name))
+        let (to_mount_id, to_remote_parent_path) = self
+            .get_repo_mount_path(repo_id, to_parent_path, &RepoFileType::Dir)
             .map_err(|e| match e {
                 GetRepoMountPathError::RepoLocked(err) => CopyFileError::RepoLocked(err),
                 GetRepoMountPathError::RepoNotFound(err) => CopyFileError::RepoNotFound(err),
             })?;
 
+        // reuse the encrypted name, it does not depend on the parent path
+        let remote_name =
+            path_utils::path_to_name(&remote_path).ok_or(CopyFileError::InvalidPath)?;
+        let to_remote_path = path_utils::join_path_name(&to_remote_parent_path, remote_name);
+
         self.remote_files_service
             .copy_file(&mount_id, &remote_path, &to_mount_id, &to_remote_path)
             .await
@@ -439,22 +986,33 @@ impl RepoFilesService {
         path: &str,
         to_parent_path: &str,
     ) -> Result<(), MoveFileError> {
-        let name = path_utils::path_to_name(path).ok_or(MoveFileError::InvalidPath)?;
+        self.repos_service.touch_repo(repo_id);
+
+        self.check_not_rekeying(repo_id)?;
+
+        path_utils::path_to_name(path).ok_or(MoveFileError::InvalidPath)?;
+
+        let typ = self.get_file_type(repo_id, path).await?;
 
         let (mount_id, remote_path) =
-            self.get_repo_mount_path(repo_id, path)
+            self.get_repo_mount_path(repo_id, path, &typ)
                 .map_err(|e| match e {
                     GetRepoMountPathError::RepoLocked(err) => MoveFileError::RepoLocked(err),
                     GetRepoMountPathError::RepoNotFound(err) => MoveFileError::RepoNotFound(err),
                 })?;
 
-        let (to_mount_id, to_path) = self
-            .get_repo_mount_path(repo_id, &path_utils::join_path_name(to_parent_path, &name))
+        let (to_mount_id, to_remote_parent_path) = self
+            .get_repo_mount_path(repo_id, to_parent_path, &RepoFileType::Dir)
             .map_err(|e| match e {
                 GetRepoMountPathError::RepoLocked(err) => MoveFileError::RepoLocked(err),
                 GetRepoMountPathError::RepoNotFound(err) => MoveFileError::RepoNotFound(err),
             })?;
 
+        // reuse the encrypted name, it does not depend on the parent path
+        let remote_name =
+            path_utils::path_to_name(&remote_path).ok_or(MoveFileError::InvalidPath)?;
+        let to_path = path_utils::join_path_name(&to_remote_parent_path, remote_name);
+
         self.remote_files_service
             .move_file(&mount_id, &remote_path, &to_mount_id, &to_path)
             .await
@@ -536,3 +1094,476 @@ impl RepoFilesService {
         }
     }
 }
+
+/// Hashes the first length bytes of the uploadable, so that a resumed upload
+/// can continue the content hash. Returns None if they cannot be read, the
+/// file is then uploaded without a content hash.
+async fn hash_prefix(
+    uploadable: &(dyn RepoFileUploadable + Send + Sync),
+    length: i64,
+) -> Option<ContentHasher> {
+    let hasher = ContentHasher::new();
+
+    if length > 0 {
+        let reader = HashReader::new(uploadable.reader_at(0)?.take(length as u64), hasher.clone());
+
+        if let Err(err) = futures::io::copy(reader, &mut futures::io::sink()).await {
+            log::warn!("Failed to hash the uploaded part: {}", err);
+
+            return None;
+        }
+    }
+
+    Some(hasher)
+}
+
+fn encrypted_reader_at(
+    cipher: &Cipher,
+    uploadable: &(dyn RepoFileUploadable + Send + Sync),
+    nonce: &Nonce,
+    position: &EncryptedPosition,
+    plaintext_length: i64,
+    hasher: Option<&ContentHasher>,
+) -> Pin<Box<dyn AsyncRead + Send + Sync + 'static>> {
+    let reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>> =
+        match uploadable.reader_at(position.plaintext_offset) {
+            Some(reader) => Box::pin(reader.take(plaintext_length as u64)),
+            None => {
+                return error_reader(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "uploadable cannot be read at an offset",
+                ))
+            }
+        };
+    let reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>> = match hasher {
+        Some(hasher) => Box::pin(HashReader::new(reader, hasher.clone())),
+        None => reader,
+    };
+
+    if position.includes_header {
+        Box::pin(
+            cipher
+                .encrypt_reader_with_nonce(reader, nonce.clone())
+                .with_skip(position.skip),
+        )
+    } else {
+        Box::pin(
+            cipher
+                .encrypt_reader_at_block(reader, nonce.clone(), position.block_index)
+                .with_skip(position.skip),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    use futures::executor::block_on;
+
+    use crate::{
+        cipher::constants::BLOCK_DATA_SIZE,
+        remote::fake_remote::{error_response, FakeFile},
+        repo_files::{
+            errors::UploadFileReaderError,
+            state::{RepoFilesUploadConflictResolution, RepoFilesUploadSession, CONTENT_HASH_TAG},
+            test_helpers::{BytesUploadable, TestContext, TEST_MOUNT_ID},
+        },
+        utils::hash_reader::ContentHasher,
+    };
+
+    use super::UPLOAD_CHUNK_BLOCKS;
+
+    const CHUNK_DATA_SIZE: usize = BLOCK_DATA_SIZE * UPLOAD_CHUNK_BLOCKS as usize;
+
+    fn content(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn upload(
+        ctx: &TestContext,
+        uploadable: &BytesUploadable,
+        session: Option<RepoFilesUploadSession>,
+        last_session: &Arc<Mutex<Option<RepoFilesUploadSession>>>,
+    ) -> (Result<(), UploadFileReaderError>, usize) {
+        let last_session = last_session.clone();
+        let progress = Arc::new(AtomicUsize::new(0));
+        let on_progress_progress = progress.clone();
+
+        let res = block_on(ctx.repo_files_service.clone().upload_file_resumable(
+            "r1",
+            "/",
+            "file.bin",
+            uploadable,
+            uploadable.content.len() as i64,
+            RepoFilesUploadConflictResolution::Error,
+            session,
+            Box::new(move |session| {
+                *last_session.lock().unwrap() = Some(session);
+            }),
+            Some(Box::new(move |n| {
+                on_progress_progress.fetch_add(n, Ordering::SeqCst);
+            })),
+            None,
+        ))
+        .map(|_| ());
+
+        (res, progress.load(Ordering::SeqCst))
+    }
+
+    /// Fails the nth upload session chunk
+    fn fail_chunk(ctx: &TestContext, n: usize) {
+        let count = AtomicUsize::new(0);
+
+        ctx.fake_remote.set_intercept(Some(Box::new(move |req| {
+            if req.method == "PUT" && req.url.contains("/files/upload-sessions/") {
+                if count.fetch_add(1, Ordering::SeqCst) + 1 == n {
+                    return Some(Ok(error_response(500, "Other", "Internal error")));
+                }
+            }
+
+            None
+        })));
+    }
+
+    fn count_requests(ctx: &TestContext, prefix: &str) -> usize {
+        ctx.fake_remote
+            .requests()
+            .iter()
+            .filter(|req| req.starts_with(prefix))
+            .count()
+    }
+
+    /// Decrypted content hash tag of a file in the fake remote
+    fn content_hash(ctx: &TestContext, path: &str) -> Option<String> {
+        let file = ctx
+            .fake_remote
+            .get(TEST_MOUNT_ID, &ctx.remote_file_path("r1", path))?;
+        let value = file.tags.get(CONTENT_HASH_TAG)?.first()?.clone();
+
+        ctx.cipher("r1").decrypt_value(&value)
+    }
+
+    fn expected_hash(content: &[u8]) -> String {
+        let hasher = ContentHasher::new();
+        hasher.update(content);
+        hasher.hex_digest()
+    }
+
+    #[test]
+    fn test_upload_file_resumable_chunk_boundary() {
+        for chunks in [1, 2] {
+            let ctx = TestContext::new();
+            ctx.add_repo("r1", "/Vault");
+
+            let uploadable = BytesUploadable::new(&content(CHUNK_DATA_SIZE * chunks));
+            let last_session = Arc::new(Mutex::new(None));
+
+            upload(&ctx, &uploadable, None, &last_session).0.unwrap();
+
+            // the last chunk ends exactly at the end of the file, no empty
+            // chunk is sent
+            assert_eq!(
+                count_requests(
+                    &ctx,
+                    "PUT /content/api/v2.1/mounts/m1/files/upload-sessions/"
+                ),
+                chunks
+            );
+            assert!(ctx.file_content("r1", "/file.bin").unwrap() == uploadable.content);
+        }
+    }
+
+    #[test]
+    fn test_upload_file_resumable_resume() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+
+        let uploadable = BytesUploadable::new(&content(CHUNK_DATA_SIZE * 2 + 1000));
+        let last_session = Arc::new(Mutex::new(None));
+
+        fail_chunk(&ctx, 2);
+
+        assert!(upload(&ctx, &uploadable, None, &last_session).0.is_err());
+
+        ctx.fake_remote.set_intercept(None);
+
+        let session = last_session.lock().unwrap().clone().unwrap();
+
+        // the server only stored a part of the first chunk, the upload
+        // continues in the middle of a block
+        {
+            let mut state = ctx.fake_remote.state.lock().unwrap();
+            let remote_session = state.upload_sessions.get_mut(&session.session_id).unwrap();
+            let confirmed_len = remote_session.content.len() - 12345;
+            remote_session.content.truncate(confirmed_len);
+        }
+
+        let (res, progress) = upload(&ctx, &uploadable, Some(session.clone()), &last_session);
+
+        res.unwrap();
+
+        // the confirmed bytes are reported first so the progress adds up to
+        // the file size
+        assert_eq!(progress, uploadable.content.len());
+        assert_eq!(
+            last_session.lock().unwrap().as_ref().unwrap().session_id,
+            session.session_id
+        );
+        assert_eq!(
+            count_requests(
+                &ctx,
+                "POST /content/api/v2.1/mounts/m1/files/upload-sessions?"
+            ),
+            1
+        );
+        assert!(ctx.file_content("r1", "/file.bin").unwrap() == uploadable.content);
+    }
+
+    #[test]
+    fn test_upload_file_resumable_session_expired() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+
+        let uploadable = BytesUploadable::new(&content(CHUNK_DATA_SIZE + 1000));
+        let last_session = Arc::new(Mutex::new(None));
+
+        fail_chunk(&ctx, 2);
+
+        assert!(upload(&ctx, &uploadable, None, &last_session).0.is_err());
+
+        ctx.fake_remote.set_intercept(None);
+
+        let session = last_session.lock().unwrap().clone().unwrap();
+
+        ctx.fake_remote
+            .state
+            .lock()
+            .unwrap()
+            .upload_sessions
+            .remove(&session.session_id);
+
+        let (res, progress) = upload(&ctx, &uploadable, Some(session.clone()), &last_session);
+
+        res.unwrap();
+
+        assert_eq!(progress, uploadable.content.len());
+
+        // a new session is started from the beginning
+        assert_ne!(
+            last_session.lock().unwrap().as_ref().unwrap().session_id,
+            session.session_id
+        );
+        assert_eq!(
+            count_requests(
+                &ctx,
+                "POST /content/api/v2.1/mounts/m1/files/upload-sessions?"
+            ),
+            2
+        );
+        assert!(ctx.file_content("r1", "/file.bin").unwrap() == uploadable.content);
+    }
+
+    #[test]
+    fn test_upload_file_resumable_content_hash() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+        ctx.repo_files_service.set_content_hashes_enabled(true);
+
+        let uploadable = BytesUploadable::new(&content(CHUNK_DATA_SIZE * 2 + 1000));
+        let last_session = Arc::new(Mutex::new(None));
+
+        upload(&ctx, &uploadable, None, &last_session).0.unwrap();
+
+        assert_eq!(
+            content_hash(&ctx, "/file.bin"),
+            Some(expected_hash(&uploadable.content))
+        );
+    }
+
+    #[test]
+    fn test_upload_file_resumable_content_hash_resume() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+        ctx.repo_files_service.set_content_hashes_enabled(true);
+
+        let uploadable = BytesUploadable::new(&content(CHUNK_DATA_SIZE * 2 + 1000));
+        let last_session = Arc::new(Mutex::new(None));
+
+        fail_chunk(&ctx, 2);
+
+        assert!(upload(&ctx, &uploadable, None, &last_session).0.is_err());
+
+        ctx.fake_remote.set_intercept(None);
+
+        let session = last_session.lock().unwrap().clone().unwrap();
+
+        {
+            let mut state = ctx.fake_remote.state.lock().unwrap();
+            let remote_session = state.upload_sessions.get_mut(&session.session_id).unwrap();
+            let confirmed_len = remote_session.content.len() - 12345;
+            remote_session.content.truncate(confirmed_len);
+        }
+
+        upload(&ctx, &uploadable, Some(session), &last_session)
+            .0
+            .unwrap();
+
+        // the already uploaded part is hashed again from the beginning
+        assert_eq!(
+            content_hash(&ctx, "/file.bin"),
+            Some(expected_hash(&uploadable.content))
+        );
+    }
+
+    #[test]
+    fn test_upload_file_content_hashes_disabled() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+
+        let uploadable = BytesUploadable::new(&content(1000));
+        let last_session = Arc::new(Mutex::new(None));
+
+        upload(&ctx, &uploadable, None, &last_session).0.unwrap();
+
+        assert_eq!(content_hash(&ctx, "/file.bin"), None);
+        assert_eq!(
+            count_requests(&ctx, "POST /api/v2.1/mounts/m1/files/tags/set"),
+            0
+        );
+    }
+
+    #[test]
+    fn test_upload_file_content_hash_keeps_tags() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+
+        let remote_path = ctx.remote_file_path("r1", "/file.bin");
+        let mut file = FakeFile::file(Vec::new(), 1);
+        file.tags
+            .insert(String::from("other"), vec![String::from("value")]);
+        ctx.fake_remote
+            .state
+            .lock()
+            .unwrap()
+            .files
+            .insert((TEST_MOUNT_ID.to_owned(), remote_path.clone()), file);
+
+        // the store does not know about the file's tags
+        block_on(ctx.remote_files_service.set_file_tag(
+            TEST_MOUNT_ID,
+            &remote_path,
+            CONTENT_HASH_TAG,
+            vec![String::from("hash")],
+        ))
+        .unwrap();
+
+        let tags = ctx
+            .fake_remote
+            .get(TEST_MOUNT_ID, &remote_path)
+            .unwrap()
+            .tags;
+
+        assert_eq!(tags.get("other"), Some(&vec![String::from("value")]));
+        assert_eq!(
+            tags.get(CONTENT_HASH_TAG),
+            Some(&vec![String::from("hash")])
+        );
+    }
+
+    #[test]
+    fn test_upload_file_resumable_source_changed() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+
+        let uploadable = BytesUploadable::new(&content(CHUNK_DATA_SIZE + 1000));
+        let last_session = Arc::new(Mutex::new(None));
+
+        fail_chunk(&ctx, 2);
+
+        assert!(upload(&ctx, &uploadable, None, &last_session).0.is_err());
+
+        ctx.fake_remote.set_intercept(None);
+
+        let session = last_session.lock().unwrap().clone().unwrap();
+
+        // the source changed, its blocks must not be encrypted with the nonce
+        // of the first attempt
+        let changed = BytesUploadable::new(&content(CHUNK_DATA_SIZE + 2000));
+
+        upload(&ctx, &changed, Some(session.clone()), &last_session)
+            .0
+            .unwrap();
+
+        let new_session = last_session.lock().unwrap().clone().unwrap();
+        assert_ne!(new_session.session_id, session.session_id);
+        assert_ne!(new_session.nonce, session.nonce);
+        assert!(ctx.file_content("r1", "/file.bin").unwrap() == changed.content);
+    }
+
+    #[test]
+    fn test_upload_file_resumable_sessions_unsupported() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+
+        ctx.fake_remote.set_intercept(Some(Box::new(|req| {
+            if req.method == "POST" && req.url.contains("/files/upload-sessions?") {
+                return Some(Ok(error_response(
+                    405,
+                    "MethodNotAllowed",
+                    "Method not allowed",
+                )));
+            }
+
+            None
+        })));
+
+        let uploadable = BytesUploadable::new(&content(CHUNK_DATA_SIZE + 1000));
+        let last_session = Arc::new(Mutex::new(None));
+
+        upload(&ctx, &uploadable, None, &last_session).0.unwrap();
+
+        // the file is uploaded in one request instead
+        assert!(last_session.lock().unwrap().is_none());
+        assert_eq!(
+            count_requests(&ctx, "POST /content/api/v2.1/mounts/m1/files/put?"),
+            1
+        );
+        assert!(ctx.file_content("r1", "/file.bin").unwrap() == uploadable.content);
+    }
+
+    #[test]
+    fn test_sniff_file_failed() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+        ctx.add_file("r1", "/F.bin", b"data");
+
+        block_on(ctx.repo_files_service.load_files("r1", "/")).unwrap();
+
+        let reads = Arc::new(AtomicUsize::new(0));
+        let intercept_reads = reads.clone();
+
+        ctx.fake_remote.set_intercept(Some(Box::new(move |req| {
+            if req.method == "GET" && req.url.contains("/files/get?") {
+                intercept_reads.fetch_add(1, Ordering::SeqCst);
+
+                Some(Ok(error_response(500, "Internal", "Internal error")))
+            } else {
+                None
+            }
+        })));
+
+        assert!(block_on(ctx.repo_files_service.clone().sniff_file("r1:/F.bin")).is_err());
+        assert!(ctx.store.with_state(|state| matches!(
+            state.repo_files.sniffs.get("r1:/F.bin"),
+            Some(sniff) if sniff.file_type.is_none()
+        )));
+
+        // the failure is not retried until the file changes
+        assert!(block_on(ctx.repo_files_service.clone().sniff_file("r1:/F.bin")).is_ok());
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+    }
+}
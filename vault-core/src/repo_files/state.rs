@@ -7,8 +7,11 @@ use std::{
 use futures::AsyncRead;
 
 use crate::{
-    cipher::errors::{DecryptFilenameError, DecryptSizeError},
-    file_types::file_icon_type::FileIconType,
+    cipher::{
+        errors::{DecryptFilenameError, DecryptSizeError},
+        nonce::Nonce,
+    },
+    file_types::{file_icon_type::FileIconType, sniff::SniffedFileType},
     remote::RemoteFileUploadConflictResolution,
     remote_files::state::RemoteFileType,
 };
@@ -130,6 +133,9 @@ pub enum RepoFileSize {
     },
 }
 
+/// Remote tag with the encrypted content_hash
+pub const CONTENT_HASH_TAG: &str = "vault-sha256";
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RepoFile {
     pub id: String,
@@ -143,6 +149,10 @@ pub struct RepoFile {
     pub typ: RepoFileType,
     pub size: RepoFileSize,
     pub modified: i64,
+    /// SHA-256 of the plaintext (lowercase hex) if it was set on upload
+    pub content_hash: Option<String>,
+    /// Remote hash of the encrypted content
+    pub remote_hash: Option<String>,
     pub icon_type: FileIconType,
 }
 
@@ -155,6 +165,13 @@ impl RepoFile {
         self.name.decrypted_name()
     }
 
+    pub fn decrypted_size(&self) -> Result<i64, DecryptSizeError> {
+        match &self.size {
+            RepoFileSize::Decrypted { size } => Ok(*size),
+            RepoFileSize::DecryptError { error, .. } => Err(error.clone()),
+        }
+    }
+
     pub fn name_lower_force<'a>(&'a self) -> &'a str {
         match &self.name {
             RepoFileName::Decrypted { name_lower, .. } => name_lower,
@@ -173,13 +190,27 @@ impl RepoFile {
     }
 }
 
+/// Result of sniffing the start of the content. It only applies while the
+/// file has the same modified time and size.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepoFileSniff {
+    pub modified: i64,
+    pub size: i64,
+    pub file_type: Option<SniffedFileType>,
+}
+
 #[derive(Clone, Default)]
 pub struct RepoFilesState {
     pub files: HashMap<String, RepoFile>,
     pub children: HashMap<String, Vec<String>>,
     pub loaded_roots: HashSet<String>,
+    pub sniffs: HashMap<String, RepoFileSniff>,
+    /// Uploads store the plaintext content hash in CONTENT_HASH_TAG. Off by
+    /// default because it costs extra requests per uploaded file.
+    pub content_hashes_enabled: bool,
 }
 
+#[derive(Clone, Copy)]
 pub enum RepoFilesUploadConflictResolution {
     Overwrite,
     Error,
@@ -197,6 +228,39 @@ impl Into<RemoteFileUploadConflictResolution> for RepoFilesUploadConflictResolut
 pub trait RepoFileUploadable {
     fn size(&self) -> Option<i64>;
     fn reader(&self) -> Pin<Box<dyn AsyncRead + Send + Sync + 'static>>;
+
+    /// Returns a reader starting at offset. Uploadables that can be read at an
+    /// offset can be uploaded in chunks and resumed after a failure.
+    fn reader_at(&self, _offset: i64) -> Option<Pin<Box<dyn AsyncRead + Send + Sync + 'static>>> {
+        None
+    }
+
+    /// Returns a handle (e.g. a local path) that `UploadSources` can reopen
+    /// after a restart. Uploads without a source are restored as failed.
+    fn source(&self) -> Option<String> {
+        None
+    }
+
+    /// Original modification time in milliseconds. The remote uses the
+    /// upload time if it is not set.
+    fn modified(&self) -> Option<i64> {
+        None
+    }
+}
+
+/// Session of a resumable upload. The nonce is kept so that the encrypted file
+/// can be continued at the offset confirmed by the server. The source size and
+/// modified time are kept so that a changed source is not encrypted with the
+/// same nonce again.
+#[derive(Clone, Debug)]
+pub struct RepoFilesUploadSession {
+    pub mount_id: String,
+    pub remote_parent_path: String,
+    pub session_id: String,
+    pub nonce: Nonce,
+    pub encrypted_size: i64,
+    pub source_size: i64,
+    pub source_modified: Option<i64>,
 }
 
 pub struct RepoFilesUploadResult {
@@ -0,0 +1,258 @@
+use std::{pin::Pin, sync::Arc};
+
+use futures::{executor::block_on, io::Cursor, AsyncRead};
+
+use crate::{
+    auth::{mock_auth_provider::MockAuthProvider, AuthProvider},
+    cipher::{test_helpers::create_cipher, Cipher},
+    device_key_store::memory_device_key_store::MemoryDeviceKeyStore,
+    encrypted_storage::memory_encrypted_storage::MemoryEncryptedStorage,
+    eventstream::{mock_websocket_client::MockWebSocketClient, EventStreamService},
+    http::HttpClient,
+    remote::{
+        fake_remote::{FakeRemote, FAKE_REMOTE_BASE_URL},
+        test_helpers as remote_test_helpers, Remote,
+    },
+    remote_files::RemoteFilesService,
+    repo_files_index::RepoFilesIndexService,
+    repo_files_list::RepoFilesListService,
+    repo_files_read::RepoFilesReadService,
+    repos::{mutations as repos_mutations, ReposService},
+    runtime::{test_runtime::TestRuntime, Runtime},
+    secure_storage::{memory_secure_storage::MemorySecureStorage, SecureStorageService},
+    store,
+    utils::path_utils,
+};
+
+use super::{state::RepoFileUploadable, RepoFilesService};
+
+pub const TEST_MOUNT_ID: &str = "m1";
+
+/// Uploadable from memory that can be read at an offset
+pub struct BytesUploadable {
+    pub content: Vec<u8>,
+}
+
+impl BytesUploadable {
+    pub fn new(content: &[u8]) -> Self {
+        Self {
+            content: content.to_vec(),
+        }
+    }
+}
+
+impl RepoFileUploadable for BytesUploadable {
+    fn size(&self) -> Option<i64> {
+        Some(self.content.len() as i64)
+    }
+
+    fn reader(&self) -> Pin<Box<dyn AsyncRead + Send + Sync + 'static>> {
+        Box::pin(Cursor::new(self.content.clone()))
+    }
+
+    fn reader_at(&self, offset: i64) -> Option<Pin<Box<dyn AsyncRead + Send + Sync + 'static>>> {
+        Some(Box::pin(Cursor::new(
+            self.content[offset as usize..].to_vec(),
+        )))
+    }
+}
+
+/// Services needed to work with repo files, backed by a fake remote
+pub struct TestContext {
+    pub store: Arc<store::Store>,
+    pub fake_remote: FakeRemote,
+    pub runtime: Arc<Box<dyn Runtime + Send + Sync>>,
+    pub repos_service: Arc<ReposService>,
+    pub remote_files_service: Arc<RemoteFilesService>,
+    pub repo_files_list_service: Arc<RepoFilesListService>,
+    pub repo_files_read_service: Arc<RepoFilesReadService>,
+    pub repo_files_service: Arc<RepoFilesService>,
+    pub repo_files_index_service: Arc<RepoFilesIndexService>,
+    pub eventstream_service: Arc<EventStreamService>,
+}
+
+impl TestContext {
+    pub fn new() -> Self {
+        let store = Arc::new(store::Store::new(store::State::default()));
+        let fake_remote = FakeRemote::new();
+        let runtime: Arc<Box<dyn Runtime + Send + Sync>> = Arc::new(Box::new(TestRuntime));
+        let http_client: Arc<Box<dyn HttpClient + Send + Sync>> =
+            Arc::new(Box::new(fake_remote.clone()));
+        let auth_provider: Arc<Box<dyn AuthProvider + Send + Sync>> =
+            Arc::new(Box::new(MockAuthProvider::default()));
+        let remote = Arc::new(Remote::new(
+            String::from(FAKE_REMOTE_BASE_URL),
+            http_client,
+            auth_provider.clone(),
+        ));
+        let secure_storage_service = Arc::new(SecureStorageService::new(Box::new(
+            MemorySecureStorage::new(),
+        )));
+        let repos_service = Arc::new(ReposService::new(
+            remote.clone(),
+            secure_storage_service,
+            Arc::new(Box::new(MemoryDeviceKeyStore::new())),
+            store.clone(),
+        ));
+        let remote_files_service = Arc::new(RemoteFilesService::new(remote, store.clone()));
+        let repo_files_list_service = Arc::new(RepoFilesListService::new(
+            repos_service.clone(),
+            remote_files_service.clone(),
+        ));
+        let repo_files_read_service = Arc::new(RepoFilesReadService::new(
+            repos_service.clone(),
+            remote_files_service.clone(),
+            repo_files_list_service.clone(),
+            store.clone(),
+            runtime.clone(),
+        ));
+        let repo_files_service = Arc::new(RepoFilesService::new(
+            repos_service.clone(),
+            remote_files_service.clone(),
+            repo_files_read_service.clone(),
+            store.clone(),
+        ));
+        let repo_files_index_service = Arc::new(RepoFilesIndexService::new(
+            repos_service.clone(),
+            repo_files_service.clone(),
+            repo_files_list_service.clone(),
+            Box::new(MemoryEncryptedStorage::new()),
+            store.clone(),
+            runtime.clone(),
+        ));
+        let eventstream_service = Arc::new(EventStreamService::new(
+            String::from(FAKE_REMOTE_BASE_URL),
+            Box::new(MockWebSocketClient::new()),
+            auth_provider.clone(),
+            repo_files_service.clone(),
+            repo_files_index_service.clone(),
+            runtime.clone(),
+        ));
+
+        Self {
+            store,
+            fake_remote,
+            runtime,
+            repos_service,
+            remote_files_service,
+            repo_files_list_service,
+            repo_files_read_service,
+            repo_files_service,
+            repo_files_index_service,
+            eventstream_service,
+        }
+    }
+
+    /// Adds an unlocked repo at remote_path in the test mount
+    pub fn add_repo(&self, repo_id: &str, remote_path: &str) {
+        let repo = remote_test_helpers::create_repo(repo_id, TEST_MOUNT_ID, remote_path);
+
+        self.fake_remote.add_dir(TEST_MOUNT_ID, remote_path);
+        self.fake_remote
+            .state
+            .lock()
+            .unwrap()
+            .vault_repos
+            .push(repo.clone());
+
+        self.store.mutate(store::Event::Repos, |state| {
+            repos_mutations::repo_loaded(state, repo).unwrap();
+        });
+
+        self.repos_service
+            .unlock_repo_with_cipher(repo_id, create_cipher());
+    }
+
+    pub fn cipher(&self, repo_id: &str) -> Arc<Cipher> {
+        self.repos_service.get_cipher(repo_id).unwrap()
+    }
+
+    fn repo_remote_path(&self, repo_id: &str) -> String {
+        self.fake_remote
+            .state
+            .lock()
+            .unwrap()
+            .vault_repos
+            .iter()
+            .find(|repo| repo.id == repo_id)
+            .map(|repo| repo.path.clone())
+            .unwrap()
+    }
+
+    /// Remote path of a file in the repo
+    pub fn remote_file_path(&self, repo_id: &str, path: &str) -> String {
+        path_utils::join_paths(
+            &self.repo_remote_path(repo_id),
+            &self.cipher(repo_id).encrypt_file_path(path),
+        )
+    }
+
+    /// Adds an encrypted dir directly to the fake remote
+    pub fn add_dir(&self, repo_id: &str, path: &str) {
+        let remote_path = path_utils::join_paths(
+            &self.repo_remote_path(repo_id),
+            &self.cipher(repo_id).encrypt_path(path),
+        );
+
+        self.fake_remote.add_dir(TEST_MOUNT_ID, &remote_path);
+    }
+
+    /// Adds an encrypted file directly to the fake remote
+    pub fn add_file(&self, repo_id: &str, path: &str, content: &[u8]) {
+        let mut encrypted = Vec::new();
+
+        block_on(self.cipher(repo_id).encrypt_data(content, &mut encrypted)).unwrap();
+
+        self.fake_remote.add_file(
+            TEST_MOUNT_ID,
+            &self.remote_file_path(repo_id, path),
+            encrypted,
+            1,
+        );
+    }
+
+    /// Decrypted content of a file in the fake remote
+    pub fn file_content(&self, repo_id: &str, path: &str) -> Option<Vec<u8>> {
+        let file = self
+            .fake_remote
+            .get(TEST_MOUNT_ID, &self.remote_file_path(repo_id, path))?;
+
+        let mut decrypted = Vec::new();
+
+        block_on(
+            self.cipher(repo_id)
+                .decrypt_data(&file.content, &mut decrypted),
+        )
+        .unwrap();
+
+        Some(decrypted)
+    }
+
+    /// Decrypted paths of all files and dirs in the repo, sorted
+    pub fn repo_paths(&self, repo_id: &str) -> Vec<String> {
+        let repo_remote_path = self.repo_remote_path(repo_id);
+        let cipher = self.cipher(repo_id);
+
+        let mut paths = self
+            .fake_remote
+            .paths(TEST_MOUNT_ID)
+            .into_iter()
+            .filter_map(|remote_path| {
+                let relative_path = remote_path
+                    .strip_prefix(&repo_remote_path)
+                    .filter(|rest| rest.starts_with('/'))?
+                    .to_owned();
+                let file = self.fake_remote.get(TEST_MOUNT_ID, &remote_path)?;
+
+                match file.typ.as_str() {
+                    "dir" => cipher.decrypt_path(&relative_path).ok(),
+                    _ => cipher.decrypt_file_path(&relative_path).ok(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        paths.sort();
+
+        paths
+    }
+}
@@ -1,27 +1,53 @@
 use crate::{
     repo_files::{
-        errors::{CreateDirError, RepoFilesErrors},
+        errors::{CreateDirError, CreateFileError, RepoFilesErrors},
         selectors as repo_files_selectors,
         state::{RepoFile, RepoFileSize, RepoFilesBreadcrumb},
     },
+    repo_files_thumbnails::selectors as repo_files_thumbnails_selectors,
     repos::{
         selectors as repo_selectors,
         state::{Repo, RepoState},
     },
     selection::{selectors as selection_selectors, state::SelectionSummary},
     store,
+    utils::name_utils,
 };
 
 use super::state::{
-    RepoFilesBrowser, RepoFilesBrowserInfo, RepoFilesBrowserItem, RepoFilesBrowserLocation,
+    RepoFilesBrowser, RepoFilesBrowserCreateFileTemplate, RepoFilesBrowserInfo,
+    RepoFilesBrowserItem, RepoFilesBrowserLocation,
 };
 
+const MAX_SNIFF_FILES: usize = 50;
+
 pub fn select_file_ids<'a>(
     state: &'a store::State,
     repo_id: &str,
     path: &str,
 ) -> impl Iterator<Item = &'a str> {
-    repo_files_selectors::select_files(state, repo_id, path).map(|file| file.id.as_str())
+    // trashed files are listed by repo_trash
+    repo_files_selectors::select_files(state, repo_id, path)
+        .filter(|file| {
+            !matches!(file.decrypted_path(), Ok(path) if repo_files_selectors::is_hidden_path(path))
+        })
+        .map(|file| file.id.as_str())
+}
+
+/// Files with an unknown extension are sniffed so that they get a matching
+/// icon. Other files are only sniffed in details, reading all of them would be
+/// too slow.
+pub fn select_file_ids_to_sniff(state: &store::State, repo_id: &str, path: &str) -> Vec<String> {
+    select_file_ids(state, repo_id, path)
+        .filter_map(|id| repo_files_selectors::select_file(state, id))
+        .filter(|file| {
+            file.content_type.is_none()
+                && file.decrypted_name().is_ok()
+                && repo_files_selectors::select_needs_sniff(state, file)
+        })
+        .take(MAX_SNIFF_FILES)
+        .map(|file| file.id.clone())
+        .collect()
 }
 
 pub fn select_browser<'a>(
@@ -78,6 +104,7 @@ pub fn select_items<'a>(state: &'a store::State, browser_id: u32) -> Vec<RepoFil
                 .map(|file| RepoFilesBrowserItem {
                     file,
                     is_selected: select_is_selected(state, browser_id, &file.id),
+                    can_thumbnail: repo_files_thumbnails_selectors::can_thumbnail(file),
                 })
                 .collect()
         })
@@ -199,3 +226,37 @@ pub fn select_check_create_dir(
 
     Ok(())
 }
+
+pub fn select_check_create_file(
+    state: &store::State,
+    browser_id: u32,
+    name: &str,
+) -> Result<(), CreateFileError> {
+    let root_file = select_root_file(state, browser_id).ok_or_else(RepoFilesErrors::not_found)?;
+
+    let root_path = root_file.decrypted_path()?;
+
+    repo_files_selectors::select_check_new_name_valid(state, &root_file.repo_id, root_path, name)?;
+
+    Ok(())
+}
+
+/// Returns the default name of the template that is not used in the current
+/// dir yet.
+pub fn select_create_file_name(
+    state: &store::State,
+    browser_id: u32,
+    template: RepoFilesBrowserCreateFileTemplate,
+) -> String {
+    let name = template.default_name();
+
+    match select_root_file(state, browser_id)
+        .and_then(|root_file| Some((&root_file.repo_id, root_file.decrypted_path().ok()?)))
+    {
+        Some((repo_id, root_path)) => name_utils::unused_name(name, |name| {
+            repo_files_selectors::select_files(state, repo_id, root_path)
+                .any(|file| file.name_lower_force() == name.to_lowercase())
+        }),
+        None => name.to_owned(),
+    }
+}
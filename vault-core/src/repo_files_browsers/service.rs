@@ -1,42 +1,66 @@
 use std::sync::Arc;
 
-use futures::future::{self, BoxFuture};
+use futures::{
+    future::{self, BoxFuture},
+    io::Cursor,
+    stream, StreamExt,
+};
 
 use crate::{
     eventstream::{self, service::MountSubscription},
     remote_files::errors::RemoteFilesErrors,
     repo_files::{
-        errors::{self as repo_files_errors, CreateDirError, DeleteFileError, RepoFilesErrors},
-        state::{RepoFile, RepoFilePath, RepoFilesSortField},
+        errors::{self as repo_files_errors, CreateDirError, CreateFileError, RepoFilesErrors},
+        state::{RepoFile, RepoFilePath, RepoFilesSortField, RepoFilesUploadConflictResolution},
         RepoFilesService,
     },
-    repo_files_read::{errors::GetFilesReaderError, state::RepoFileReader, RepoFilesReadService},
+    repo_files_index::RepoFilesIndexService,
+    repo_files_read::{
+        errors::GetFilesReaderError,
+        state::{RepoFileReader, RepoFilesArchiveFormat},
+        RepoFilesReadService,
+    },
+    repo_trash::{errors::RepoTrashError, RepoTrashService},
     repos::selectors as repos_selectors,
-    store,
-    utils::path_utils::normalize_path,
+    runtime, store,
+    utils::path_utils::{self, normalize_path},
+};
+
+use super::{
+    mutations, selectors,
+    state::{RepoFilesBrowserCreateFileTemplate, RepoFilesBrowserLocation},
 };
 
-use super::{mutations, selectors, state::RepoFilesBrowserLocation};
+const SNIFF_CONCURRENCY: usize = 3;
 
 pub struct RepoFilesBrowsersService {
     repo_files_service: Arc<RepoFilesService>,
     repo_files_read_service: Arc<RepoFilesReadService>,
+    repo_trash_service: Arc<RepoTrashService>,
+    repo_files_index_service: Arc<RepoFilesIndexService>,
     eventstream_service: Arc<eventstream::EventStreamService>,
     store: Arc<store::Store>,
+    runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
 }
 
 impl RepoFilesBrowsersService {
     pub fn new(
         repo_files_service: Arc<RepoFilesService>,
         repo_files_read_service: Arc<RepoFilesReadService>,
+        repo_trash_service: Arc<RepoTrashService>,
+        repo_files_index_service: Arc<RepoFilesIndexService>,
         eventstream_service: Arc<eventstream::EventStreamService>,
         store: Arc<store::Store>,
+        runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
     ) -> Self {
         Self {
             repo_files_service,
             repo_files_read_service,
+            repo_trash_service,
+            repo_files_index_service,
             eventstream_service,
             store,
+            runtime,
         }
     }
 
@@ -165,6 +189,8 @@ impl RepoFilesBrowsersService {
             .store
             .with_state(|state| selectors::select_repo_id_path_owned(state, browser_id))
         {
+            self.repo_files_index_service.fill_dir(&repo_id, &path);
+
             let res = self.repo_files_service.load_files(&repo_id, &path).await;
 
             self.store.mutate(store::Event::RepoFilesBrowsers, |state| {
@@ -172,11 +198,39 @@ impl RepoFilesBrowsersService {
             });
 
             res?;
+
+            self.sniff_files(&repo_id, &path);
         }
 
         Ok(())
     }
 
+    /// Sniffing needs a request per file so it runs in the background and
+    /// does not delay the listing.
+    fn sniff_files(&self, repo_id: &str, path: &str) {
+        let file_ids = self
+            .store
+            .with_state(|state| selectors::select_file_ids_to_sniff(state, repo_id, path));
+
+        if file_ids.is_empty() {
+            return;
+        }
+
+        let repo_files_service = self.repo_files_service.clone();
+
+        self.runtime.spawn(Box::pin(async move {
+            stream::iter(file_ids)
+                .for_each_concurrent(SNIFF_CONCURRENCY, |file_id| {
+                    let repo_files_service = repo_files_service.clone();
+
+                    async move {
+                        let _ = repo_files_service.sniff_file(&file_id).await;
+                    }
+                })
+                .await;
+        }));
+    }
+
     pub fn select_file(
         &self,
         browser_id: u32,
@@ -211,6 +265,7 @@ impl RepoFilesBrowsersService {
     pub async fn get_selected_reader(
         self: Arc<Self>,
         browser_id: u32,
+        archive_format: RepoFilesArchiveFormat,
     ) -> Result<RepoFileReader, GetFilesReaderError> {
         let files: Vec<RepoFile> = self.store.with_state(|state| {
             selectors::select_selected_files(state, browser_id)
@@ -225,7 +280,7 @@ impl RepoFilesBrowsersService {
 
         self.repo_files_read_service
             .clone()
-            .get_files_reader(&files)
+            .get_files_reader_with_format(&files, archive_format)
             .await
     }
 
@@ -253,7 +308,56 @@ impl RepoFilesBrowsersService {
             .await
     }
 
-    pub async fn delete_selected(&self, browser_id: u32) -> Result<(), DeleteFileError> {
+    pub fn check_create_file(&self, browser_id: u32, name: &str) -> Result<(), CreateFileError> {
+        self.store
+            .with_state(|state| selectors::select_check_create_file(state, browser_id, name))
+    }
+
+    /// Creates a file with the template content in the current dir and
+    /// returns its path.
+    pub async fn create_file(
+        &self,
+        browser_id: u32,
+        name: &str,
+        template: RepoFilesBrowserCreateFileTemplate,
+    ) -> Result<String, CreateFileError> {
+        self.check_create_file(browser_id, name)?;
+
+        let (repo_id, parent_path) =
+            self.store
+                .with_state::<_, Result<_, CreateFileError>>(|state| {
+                    let root_file = selectors::select_root_file(state, browser_id)
+                        .ok_or_else(RepoFilesErrors::not_found)?;
+
+                    let root_path = root_file.decrypted_path()?;
+
+                    Ok((root_file.repo_id.clone(), root_path.to_owned()))
+                })?;
+
+        let content = template.content(name);
+        let size = content.len() as i64;
+
+        let res = self
+            .repo_files_service
+            .clone()
+            .upload_file_reader(
+                &repo_id,
+                &parent_path,
+                name,
+                Box::pin(Cursor::new(content)),
+                Some(size),
+                None,
+                RepoFilesUploadConflictResolution::Error,
+                None,
+                None,
+            )
+            .await?;
+
+        Ok(path_utils::join_path_name(&parent_path, &res.name))
+    }
+
+    /// Moves the selected files into the repo trash.
+    pub async fn delete_selected(&self, browser_id: u32) -> Result<(), RepoTrashError> {
         for (repo_id, path) in self.store.with_state(|state| {
             selectors::select_selected_files(state, browser_id)
                 .into_iter()
@@ -261,10 +365,152 @@ impl RepoFilesBrowsersService {
                 .collect::<Vec<(String, RepoFilePath)>>()
         }) {
             if let Ok(path) = path.decrypted_path() {
-                self.repo_files_service.delete_file(&repo_id, path).await?;
+                self.repo_trash_service.trash_file(&repo_id, path).await?;
             }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::executor::block_on;
+
+    use crate::{
+        remote::{ApiErrorCode, RemoteError},
+        repo_files::{errors::CreateFileError, test_helpers::TestContext},
+        repo_trash::RepoTrashService,
+    };
+
+    use super::{
+        super::{selectors, state::RepoFilesBrowserCreateFileTemplate},
+        RepoFilesBrowsersService,
+    };
+
+    fn setup() -> (TestContext, Arc<RepoFilesBrowsersService>, u32) {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+        ctx.add_file("r1", "/Notes/existing.txt", b"existing");
+
+        let browsers_service = Arc::new(RepoFilesBrowsersService::new(
+            ctx.repo_files_service.clone(),
+            ctx.repo_files_read_service.clone(),
+            Arc::new(RepoTrashService::new(
+                ctx.repo_files_service.clone(),
+                ctx.store.clone(),
+            )),
+            ctx.repo_files_index_service.clone(),
+            ctx.eventstream_service.clone(),
+            ctx.store.clone(),
+            ctx.runtime.clone(),
+        ));
+
+        let (browser_id, load_future) = browsers_service.clone().create("r1", "/Notes");
+        block_on(load_future).unwrap();
+
+        (ctx, browsers_service, browser_id)
+    }
+
+    fn create_file_name(
+        ctx: &TestContext,
+        browser_id: u32,
+        template: RepoFilesBrowserCreateFileTemplate,
+    ) -> String {
+        ctx.store
+            .with_state(|state| selectors::select_create_file_name(state, browser_id, template))
+    }
+
+    fn is_remote_error(res: Result<String, CreateFileError>, expected_code: ApiErrorCode) -> bool {
+        matches!(
+            res,
+            Err(CreateFileError::RemoteError(RemoteError::ApiError { code, .. })) if code == expected_code
+        )
+    }
+
+    #[test]
+    fn test_create_file() {
+        let (ctx, browsers_service, browser_id) = setup();
+
+        let name = create_file_name(&ctx, browser_id, RepoFilesBrowserCreateFileTemplate::Empty);
+
+        assert_eq!(name, "New file");
+        assert_eq!(
+            block_on(browsers_service.create_file(
+                browser_id,
+                &name,
+                RepoFilesBrowserCreateFileTemplate::Empty
+            ))
+            .unwrap(),
+            "/Notes/New file"
+        );
+        assert_eq!(
+            ctx.file_content("r1", "/Notes/New file").unwrap(),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[test]
+    fn test_create_file_template_unused_name() {
+        let (ctx, browsers_service, browser_id) = setup();
+
+        for expected_name in ["New note.md", "New note (1).md"] {
+            let name = create_file_name(
+                &ctx,
+                browser_id,
+                RepoFilesBrowserCreateFileTemplate::Markdown,
+            );
+
+            assert_eq!(name, expected_name);
+
+            block_on(browsers_service.create_file(
+                browser_id,
+                &name,
+                RepoFilesBrowserCreateFileTemplate::Markdown,
+            ))
+            .unwrap();
+        }
+
+        assert_eq!(
+            ctx.file_content("r1", "/Notes/New note.md").unwrap(),
+            b"# New note\n".to_vec()
+        );
+        assert_eq!(
+            ctx.file_content("r1", "/Notes/New note (1).md").unwrap(),
+            b"# New note (1)\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_create_file_invalid_name() {
+        let (ctx, browsers_service, browser_id) = setup();
+
+        for name in ["", "a/b"] {
+            assert!(is_remote_error(
+                block_on(browsers_service.create_file(
+                    browser_id,
+                    name,
+                    RepoFilesBrowserCreateFileTemplate::Text,
+                )),
+                ApiErrorCode::InvalidPath
+            ));
+        }
+
+        assert!(is_remote_error(
+            block_on(browsers_service.create_file(
+                browser_id,
+                "existing.txt",
+                RepoFilesBrowserCreateFileTemplate::Text,
+            )),
+            ApiErrorCode::AlreadyExists
+        ));
+
+        assert_eq!(ctx.repo_paths("r1"), vec!["/Notes", "/Notes/existing.txt"]);
+        assert_eq!(
+            ctx.file_content("r1", "/Notes/existing.txt").unwrap(),
+            b"existing".to_vec()
+        );
+    }
+}
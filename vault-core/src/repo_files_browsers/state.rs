@@ -8,11 +8,13 @@ use crate::{
         state::{RepoFile, RepoFilesSort},
     },
     selection::state::{Selection, SelectionSummary},
+    utils::name_utils,
 };
 
 pub struct RepoFilesBrowserItem<'a> {
     pub file: &'a RepoFile,
     pub is_selected: bool,
+    pub can_thumbnail: bool,
 }
 
 pub struct RepoFilesBrowserInfo<'a> {
@@ -33,6 +35,36 @@ pub struct RepoFilesBrowserInfo<'a> {
     pub can_delete_selected: bool,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepoFilesBrowserCreateFileTemplate {
+    Empty,
+    Text,
+    Markdown,
+}
+
+impl Default for RepoFilesBrowserCreateFileTemplate {
+    fn default() -> Self {
+        Self::Empty
+    }
+}
+
+impl RepoFilesBrowserCreateFileTemplate {
+    pub fn default_name(&self) -> &'static str {
+        match self {
+            Self::Empty => "New file",
+            Self::Text => "New note.txt",
+            Self::Markdown => "New note.md",
+        }
+    }
+
+    pub fn content(&self, name: &str) -> Vec<u8> {
+        match self {
+            Self::Empty | Self::Text => Vec::new(),
+            Self::Markdown => format!("# {}\n", name_utils::split_name_ext(name).0).into_bytes(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct RepoFilesBrowserLocation {
     pub repo_id: String,
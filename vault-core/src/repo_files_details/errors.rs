@@ -0,0 +1,58 @@
+use thiserror::Error;
+
+use crate::{
+    cipher::errors::DecryptFilenameError,
+    remote::RemoteError,
+    repo_files::errors::{LoadFileError, LoadFilesError, UploadFileReaderError},
+    repos::errors::{RepoLockedError, RepoNotFoundError},
+    user_error::UserError,
+};
+
+#[derive(Error, Debug, Clone, PartialEq, UserError)]
+pub enum SaveContentError {
+    #[error("file not found")]
+    FileNotFound,
+    #[error("content is not loaded")]
+    ContentNotLoaded,
+    #[error("file was changed since it was loaded")]
+    FileChanged,
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("{0}")]
+    DecryptFilenameError(#[from] DecryptFilenameError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+}
+
+impl From<LoadFileError> for SaveContentError {
+    fn from(err: LoadFileError) -> Self {
+        match err {
+            LoadFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFileError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<LoadFilesError> for SaveContentError {
+    fn from(err: LoadFilesError) -> Self {
+        match err {
+            LoadFilesError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFilesError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFilesError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<UploadFileReaderError> for SaveContentError {
+    fn from(err: UploadFileReaderError) -> Self {
+        match err {
+            UploadFileReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            UploadFileReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            UploadFileReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            UploadFileReaderError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
@@ -6,7 +6,13 @@ use crate::repo_files_read::errors::GetFilesReaderError;
 use crate::store;
 use crate::{common::state::Status, eventstream::service::MountSubscription};
 
-use super::state::{RepoFilesDetails, RepoFilesDetailsContent, RepoFilesDetailsLocation};
+use super::{
+    errors::SaveContentError,
+    state::{
+        RepoFilesDetails, RepoFilesDetailsContent, RepoFilesDetailsContentRemoteVersion,
+        RepoFilesDetailsLocation,
+    },
+};
 
 pub fn create_location(
     repo_id: String,
@@ -21,6 +27,9 @@ pub fn create_location(
             status: Status::Initial,
             bytes: None,
             version: 0,
+            remote_version: None,
+            is_dirty: false,
+            save_status: Status::Initial,
         },
     }
 }
@@ -109,6 +118,7 @@ pub fn content_loaded(
     details_id: u32,
     repo_id: String,
     path: String,
+    remote_version: RepoFilesDetailsContentRemoteVersion,
     res: Result<Vec<u8>, GetFilesReaderError>,
 ) {
     let mut location = match state
@@ -130,9 +140,85 @@ pub fn content_loaded(
             location.content.status = Status::Loaded;
             location.content.bytes = Some(bytes);
             location.content.version += 1;
+            location.content.remote_version = Some(remote_version);
+            location.content.is_dirty = false;
+            location.content.save_status = Status::Initial;
         }
         Err(err) => {
             location.content.status = Status::Error { error: err };
         }
     }
 }
+
+pub fn content_edited(state: &mut store::State, details_id: u32, bytes: Vec<u8>) {
+    let location = match state
+        .repo_files_details
+        .details
+        .get_mut(&details_id)
+        .and_then(|details| details.location.as_mut())
+    {
+        Some(location) => location,
+        _ => return,
+    };
+
+    location.content.bytes = Some(bytes);
+    location.content.version += 1;
+    location.content.is_dirty = true;
+}
+
+pub fn content_saving(state: &mut store::State, details_id: u32) {
+    let location = match state
+        .repo_files_details
+        .details
+        .get_mut(&details_id)
+        .and_then(|details| details.location.as_mut())
+    {
+        Some(location) => location,
+        _ => return,
+    };
+
+    location.content.save_status = Status::Loading;
+}
+
+/// Content version is the version that was saved, the content stays dirty if
+/// it was edited while saving. Remote version is only set if the file itself
+/// was overwritten.
+pub fn content_saved(
+    state: &mut store::State,
+    details_id: u32,
+    repo_id: &str,
+    path: &str,
+    content_version: u32,
+    res: &Result<Option<RepoFilesDetailsContentRemoteVersion>, SaveContentError>,
+) {
+    let location = match state
+        .repo_files_details
+        .details
+        .get_mut(&details_id)
+        .and_then(|details| details.location.as_mut())
+    {
+        Some(location) => location,
+        _ => return,
+    };
+
+    if location.repo_id != repo_id || location.path != path {
+        return;
+    }
+
+    match res {
+        Ok(remote_version) => {
+            location.content.save_status = Status::Loaded;
+
+            if location.content.version == content_version {
+                location.content.is_dirty = false;
+            }
+
+            if let Some(remote_version) = remote_version {
+                location.content.remote_version = Some(remote_version.clone());
+            }
+        }
+        Err(err) => {
+            location.content.save_status = Status::Error { error: err.clone() };
+        }
+    }
+}
@@ -93,6 +93,16 @@ pub fn select_info<'a>(state: &'a store::State, details_id: u32) -> Option<RepoF
                 .as_ref()
                 .map(|location| location.content.status.clone())
                 .unwrap_or(Status::Initial),
+            content_is_dirty: details
+                .location
+                .as_ref()
+                .map(|location| location.content.is_dirty)
+                .unwrap_or(false),
+            content_save_status: details
+                .location
+                .as_ref()
+                .map(|location| location.content.save_status.clone())
+                .unwrap_or(Status::Initial),
             can_download,
             can_copy,
             can_move,
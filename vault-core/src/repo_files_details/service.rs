@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use futures::{
     future::{self, BoxFuture},
+    io::Cursor,
     AsyncReadExt,
 };
 
@@ -10,14 +11,34 @@ use crate::{
     http::HttpError,
     remote::RemoteError,
     remote_files::errors::RemoteFilesErrors,
-    repo_files::{errors::LoadFilesError, state::RepoFile, RepoFilesService},
+    repo_files::{
+        errors::LoadFilesError,
+        selectors as repo_files_selectors,
+        state::{RepoFile, RepoFilesUploadConflictResolution},
+        RepoFilesService,
+    },
     repo_files_read::{errors::GetFilesReaderError, state::RepoFileReader, RepoFilesReadService},
     repos::selectors as repos_selectors,
     store,
-    utils::path_utils::normalize_path,
+    utils::{
+        name_utils,
+        path_utils::{self, normalize_path},
+    },
 };
 
-use super::{mutations, selectors, state::RepoFilesDetailsLocation};
+use super::{
+    errors::SaveContentError,
+    mutations, selectors,
+    state::{RepoFilesDetailsContentRemoteVersion, RepoFilesDetailsLocation},
+};
+
+struct SaveContent {
+    repo_id: String,
+    path: String,
+    bytes: Vec<u8>,
+    version: u32,
+    remote_version: Option<RepoFilesDetailsContentRemoteVersion>,
+}
 
 pub struct RepoFilesDetailsService {
     repo_files_service: Arc<RepoFilesService>,
@@ -113,6 +134,14 @@ impl RepoFilesDetailsService {
             });
 
             res?;
+
+            // the preview depends on the content type, the extension can be
+            // missing or wrong
+            let _ = self
+                .repo_files_service
+                .clone()
+                .sniff_file(&repo_files_selectors::get_file_id(&repo_id, &path))
+                .await;
         }
 
         Ok(())
@@ -134,6 +163,7 @@ impl RepoFilesDetailsService {
 
         let repo_id = file.repo_id.clone();
         let path = file.path.decrypted_path()?.to_owned();
+        let remote_version = RepoFilesDetailsContentRemoteVersion::from(&file);
 
         let res = match self
             .repo_files_read_service
@@ -157,12 +187,180 @@ impl RepoFilesDetailsService {
         let res_err = res.as_ref().map(|_| ()).map_err(|err| err.clone());
 
         self.store.mutate(store::Event::RepoFilesDetails, |state| {
-            mutations::content_loaded(state, details_id, repo_id, path, res);
+            mutations::content_loaded(state, details_id, repo_id, path, remote_version, res);
         });
 
         res_err
     }
 
+    pub fn edit_content(&self, details_id: u32, bytes: Vec<u8>) {
+        self.store.mutate(store::Event::RepoFilesDetails, |state| {
+            mutations::content_edited(state, details_id, bytes);
+        });
+    }
+
+    /// Overwrites the file with the edited content. Fails with
+    /// `SaveContentError::FileChanged` if the file was changed since the
+    /// content was loaded, `save_content_as_new_name` can be used instead.
+    pub async fn save_content(self: Arc<Self>, details_id: u32) -> Result<(), SaveContentError> {
+        let content = self.start_save(details_id)?;
+
+        let res = self.clone().overwrite_content(&content).await;
+
+        self.finish_save(details_id, &content, &res);
+
+        res.map(|_| ())
+    }
+
+    /// Saves the edited content next to the file with an unused name and
+    /// returns the new path.
+    pub async fn save_content_as_new_name(
+        self: Arc<Self>,
+        details_id: u32,
+    ) -> Result<String, SaveContentError> {
+        let content = self.start_save(details_id)?;
+
+        let res = self.clone().upload_content_as_new_name(&content).await;
+
+        self.finish_save(
+            details_id,
+            &content,
+            &res.as_ref().map(|_| None).map_err(|err| err.clone()),
+        );
+
+        res
+    }
+
+    fn start_save(&self, details_id: u32) -> Result<SaveContent, SaveContentError> {
+        self.store.mutate(
+            store::Event::RepoFilesDetails,
+            |state| -> Result<SaveContent, SaveContentError> {
+                let location = selectors::select_details_location(state, details_id)
+                    .ok_or(SaveContentError::FileNotFound)?;
+
+                let content = SaveContent {
+                    repo_id: location.repo_id.clone(),
+                    path: location.path.clone(),
+                    bytes: location
+                        .content
+                        .bytes
+                        .clone()
+                        .ok_or(SaveContentError::ContentNotLoaded)?,
+                    version: location.content.version,
+                    remote_version: location.content.remote_version.clone(),
+                };
+
+                mutations::content_saving(state, details_id);
+
+                Ok(content)
+            },
+        )
+    }
+
+    fn finish_save(
+        &self,
+        details_id: u32,
+        content: &SaveContent,
+        res: &Result<Option<RepoFilesDetailsContentRemoteVersion>, SaveContentError>,
+    ) {
+        self.store.mutate(store::Event::RepoFilesDetails, |state| {
+            mutations::content_saved(
+                state,
+                details_id,
+                &content.repo_id,
+                &content.path,
+                content.version,
+                res,
+            );
+        });
+    }
+
+    fn select_remote_version(
+        &self,
+        repo_id: &str,
+        path: &str,
+    ) -> Option<RepoFilesDetailsContentRemoteVersion> {
+        self.store.with_state(|state| {
+            repo_files_selectors::select_file(
+                state,
+                &repo_files_selectors::get_file_id(repo_id, path),
+            )
+            .map(RepoFilesDetailsContentRemoteVersion::from)
+        })
+    }
+
+    async fn overwrite_content(
+        self: Arc<Self>,
+        content: &SaveContent,
+    ) -> Result<Option<RepoFilesDetailsContentRemoteVersion>, SaveContentError> {
+        let (parent_path, name) =
+            path_utils::split_parent_name(&content.path).ok_or(SaveContentError::FileNotFound)?;
+
+        // the file could have been changed without an eventstream event so
+        // reload it before comparing
+        self.repo_files_service
+            .load_file(&content.repo_id, &content.path)
+            .await?;
+
+        if self.select_remote_version(&content.repo_id, &content.path) != content.remote_version {
+            return Err(SaveContentError::FileChanged);
+        }
+
+        self.repo_files_service
+            .clone()
+            .upload_file_reader(
+                &content.repo_id,
+                parent_path,
+                name,
+                Box::pin(Cursor::new(content.bytes.clone())),
+                Some(content.bytes.len() as i64),
+                None,
+                RepoFilesUploadConflictResolution::Overwrite,
+                None,
+                None,
+            )
+            .await?;
+
+        Ok(self.select_remote_version(&content.repo_id, &content.path))
+    }
+
+    async fn upload_content_as_new_name(
+        self: Arc<Self>,
+        content: &SaveContent,
+    ) -> Result<String, SaveContentError> {
+        let (parent_path, name) =
+            path_utils::split_parent_name(&content.path).ok_or(SaveContentError::FileNotFound)?;
+
+        self.repo_files_service
+            .load_files(&content.repo_id, parent_path)
+            .await?;
+
+        let new_name = self.store.with_state(|state| {
+            name_utils::unused_name(name, |name| {
+                repo_files_selectors::select_files(state, &content.repo_id, parent_path)
+                    .any(|file| file.decrypted_name().ok() == Some(name))
+            })
+        });
+
+        let res = self
+            .repo_files_service
+            .clone()
+            .upload_file_reader(
+                &content.repo_id,
+                parent_path,
+                &new_name,
+                Box::pin(Cursor::new(content.bytes.clone())),
+                Some(content.bytes.len() as i64),
+                None,
+                RepoFilesUploadConflictResolution::Error,
+                None,
+                None,
+            )
+            .await?;
+
+        Ok(path_utils::join_path_name(parent_path, &res.name))
+    }
+
     pub async fn get_file_reader(
         self: Arc<Self>,
         details_id: u32,
@@ -178,3 +376,173 @@ impl RepoFilesDetailsService {
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::executor::block_on;
+
+    use crate::{
+        common::state::Status,
+        repo_files::test_helpers::{TestContext, TEST_MOUNT_ID},
+    };
+
+    use super::{
+        super::{errors::SaveContentError, selectors},
+        RepoFilesDetailsService,
+    };
+
+    fn setup() -> (TestContext, Arc<RepoFilesDetailsService>, u32) {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+        ctx.add_file("r1", "/notes.txt", b"old notes");
+
+        let details_service = Arc::new(RepoFilesDetailsService::new(
+            ctx.repo_files_service.clone(),
+            ctx.repo_files_read_service.clone(),
+            ctx.eventstream_service.clone(),
+            ctx.store.clone(),
+        ));
+
+        let (details_id, load_future) = details_service.clone().create("r1", "/notes.txt");
+        block_on(load_future).unwrap();
+        block_on(details_service.clone().load_content(details_id)).unwrap();
+
+        (ctx, details_service, details_id)
+    }
+
+    fn is_dirty(ctx: &TestContext, details_id: u32) -> bool {
+        ctx.store.with_state(|state| {
+            selectors::select_info(state, details_id)
+                .unwrap()
+                .content_is_dirty
+        })
+    }
+
+    fn change_remote_file(ctx: &TestContext, path: &str, content: &[u8]) {
+        ctx.add_file("r1", path, content);
+
+        let remote_path = ctx.remote_file_path("r1", path);
+
+        ctx.fake_remote
+            .state
+            .lock()
+            .unwrap()
+            .files
+            .get_mut(&(TEST_MOUNT_ID.to_owned(), remote_path))
+            .unwrap()
+            .modified = 2;
+    }
+
+    #[test]
+    fn test_save_content() {
+        let (ctx, details_service, details_id) = setup();
+
+        assert!(!is_dirty(&ctx, details_id));
+
+        details_service.edit_content(details_id, b"new notes".to_vec());
+
+        assert!(is_dirty(&ctx, details_id));
+
+        block_on(details_service.clone().save_content(details_id)).unwrap();
+
+        assert!(!is_dirty(&ctx, details_id));
+        assert_eq!(
+            ctx.file_content("r1", "/notes.txt").unwrap(),
+            b"new notes".to_vec()
+        );
+
+        // the saved version is the new base, saving again is not a conflict
+        details_service.edit_content(details_id, b"newer notes".to_vec());
+
+        block_on(details_service.clone().save_content(details_id)).unwrap();
+
+        assert_eq!(
+            ctx.file_content("r1", "/notes.txt").unwrap(),
+            b"newer notes".to_vec()
+        );
+        assert_eq!(ctx.repo_paths("r1"), vec!["/notes.txt"]);
+    }
+
+    #[test]
+    fn test_save_content_remote_changed() {
+        let (ctx, details_service, details_id) = setup();
+
+        details_service.edit_content(details_id, b"new notes".to_vec());
+
+        change_remote_file(&ctx, "/notes.txt", b"changed on the server");
+
+        assert_eq!(
+            block_on(details_service.clone().save_content(details_id)),
+            Err(SaveContentError::FileChanged)
+        );
+
+        assert!(is_dirty(&ctx, details_id));
+        assert!(matches!(
+            ctx.store.with_state(|state| {
+                selectors::select_info(state, details_id)
+                    .unwrap()
+                    .content_save_status
+            }),
+            Status::Error {
+                error: SaveContentError::FileChanged
+            }
+        ));
+        assert_eq!(
+            ctx.file_content("r1", "/notes.txt").unwrap(),
+            b"changed on the server".to_vec()
+        );
+
+        assert_eq!(
+            block_on(details_service.clone().save_content_as_new_name(details_id)).unwrap(),
+            "/notes (1).txt"
+        );
+
+        assert!(!is_dirty(&ctx, details_id));
+        assert_eq!(
+            ctx.file_content("r1", "/notes.txt").unwrap(),
+            b"changed on the server".to_vec()
+        );
+        assert_eq!(
+            ctx.file_content("r1", "/notes (1).txt").unwrap(),
+            b"new notes".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_save_content_remote_changed_same_size_and_modified() {
+        let (ctx, details_service, details_id) = setup();
+
+        details_service.edit_content(details_id, b"new notes".to_vec());
+
+        ctx.add_file("r1", "/notes.txt", b"new notes");
+
+        assert_eq!(
+            block_on(details_service.clone().save_content(details_id)),
+            Err(SaveContentError::FileChanged)
+        );
+    }
+
+    #[test]
+    fn test_save_content_not_loaded() {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+        ctx.add_file("r1", "/notes.txt", b"old notes");
+
+        let details_service = Arc::new(RepoFilesDetailsService::new(
+            ctx.repo_files_service.clone(),
+            ctx.repo_files_read_service.clone(),
+            ctx.eventstream_service.clone(),
+            ctx.store.clone(),
+        ));
+
+        let (details_id, load_future) = details_service.clone().create("r1", "/notes.txt");
+        block_on(load_future).unwrap();
+
+        assert_eq!(
+            block_on(details_service.clone().save_content(details_id)),
+            Err(SaveContentError::ContentNotLoaded)
+        );
+    }
+}
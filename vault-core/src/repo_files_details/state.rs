@@ -7,6 +7,8 @@ use crate::{
     repo_files_read::errors::GetFilesReaderError,
 };
 
+use super::errors::SaveContentError;
+
 pub struct RepoFilesDetailsInfo<'a> {
     pub repo_id: Option<&'a str>,
     pub parent_path: Option<&'a str>,
@@ -14,17 +16,43 @@ pub struct RepoFilesDetailsInfo<'a> {
     pub status: Status<LoadFilesError>,
     pub file: Option<&'a RepoFile>,
     pub content_status: Status<GetFilesReaderError>,
+    pub content_is_dirty: bool,
+    pub content_save_status: Status<SaveContentError>,
     pub can_download: bool,
     pub can_copy: bool,
     pub can_move: bool,
     pub can_delete: bool,
 }
 
+/// The remote state of the file the content was loaded from or saved to. It
+/// is used to detect that the file was changed by someone else.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepoFilesDetailsContentRemoteVersion {
+    pub modified: i64,
+    pub size: i64,
+    /// Remote hash of the encrypted content, catches changes that keep the
+    /// size and the modified time
+    pub hash: Option<String>,
+}
+
+impl From<&RepoFile> for RepoFilesDetailsContentRemoteVersion {
+    fn from(file: &RepoFile) -> Self {
+        Self {
+            modified: file.modified,
+            size: file.size_force(),
+            hash: file.remote_hash.clone(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct RepoFilesDetailsContent {
     pub status: Status<GetFilesReaderError>,
     pub bytes: Option<Vec<u8>>,
     pub version: u32,
+    pub remote_version: Option<RepoFilesDetailsContentRemoteVersion>,
+    pub is_dirty: bool,
+    pub save_status: Status<SaveContentError>,
 }
 
 #[derive(Clone)]
@@ -3,6 +3,7 @@ use crate::dir_pickers::state::{DirPicker, DirPickerItem, DirPickerItemType};
 use crate::repo_files::errors::{CreateDirError, RepoFilesErrors};
 use crate::repo_files::selectors as repo_files_selectors;
 use crate::repo_files::state::{RepoFile, RepoFileType};
+use crate::repos::selectors as repos_selectors;
 use crate::store;
 
 use super::state::Options;
@@ -15,14 +16,41 @@ pub fn select_repo_id(state: &store::State, picker_id: u32) -> Option<String> {
     select_options(state, picker_id).map(|options| options.repo_id)
 }
 
+/// Returns the repo ids whose roots are shown in the picker. The picker repo
+/// comes first, followed by the other unlocked repos if they are included.
+pub fn select_root_repo_ids(state: &store::State, picker_id: u32) -> Vec<String> {
+    let options = match select_options(state, picker_id) {
+        Some(options) => options,
+        None => return Vec::new(),
+    };
+
+    let mut repo_ids = vec![options.repo_id.clone()];
+
+    if options.include_unlocked_repos {
+        repo_ids.extend(
+            repos_selectors::select_repos(state)
+                .into_iter()
+                .filter(|repo| repo.id != options.repo_id && repo.state.is_unlocked())
+                .map(|repo| repo.id.clone()),
+        );
+    }
+
+    repo_ids
+}
+
 pub fn select_items(state: &store::State, picker: &DirPicker) -> Vec<DirPickerItem> {
     let mut items: Vec<DirPickerItem> = Vec::new();
 
-    if let Some(repo_id) = select_repo_id(state, picker.id) {
+    let repo_ids = select_root_repo_ids(state, picker.id);
+
+    // multiple roots need arrows to be expanded separately
+    let root_depth = if repo_ids.len() > 1 { 1 } else { 0 };
+
+    for repo_id in repo_ids {
         let root_file_id = repo_files_selectors::get_file_id(&repo_id, "/");
 
         if let Some(root_file) = repo_files_selectors::select_file(state, &root_file_id) {
-            select_items_visit_file(state, picker, &mut items, root_file, 0);
+            select_items_visit_file(state, picker, &mut items, root_file, root_depth);
         }
     }
 
@@ -34,6 +62,7 @@ fn select_items_children<'a>(state: &'a store::State, file_id: &str) -> Option<V
         ids.iter()
             .filter_map(|id| repo_files_selectors::select_file(state, id))
             .filter(|file| file.typ == RepoFileType::Dir)
+            .filter(|file| !matches!(file.decrypted_path(), Ok(path) if repo_files_selectors::is_hidden_path(path)))
             .collect()
     })
 }
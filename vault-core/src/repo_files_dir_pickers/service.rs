@@ -44,11 +44,12 @@ impl RepoFilesDirPickersService {
         }
     }
 
-    pub fn create(&self, repo_id: &str) -> u32 {
+    pub fn create(&self, repo_id: &str, include_unlocked_repos: bool) -> u32 {
         self.helper.clone().create(
-            &[store::Event::RepoFiles],
+            &[store::Event::RepoFiles, store::Event::Repos],
             Options {
                 repo_id: repo_id.to_owned(),
+                include_unlocked_repos,
             },
         )
     }
@@ -63,7 +64,11 @@ impl RepoFilesDirPickersService {
     }
 
     pub async fn load(&self, picker_id: u32) -> Result<(), LoadFilesError> {
-        if let Some(repo_id) = self.get_repo_id(picker_id) {
+        let repo_ids = self
+            .store
+            .with_state(|state| selectors::select_root_repo_ids(state, picker_id));
+
+        for repo_id in repo_ids {
             self.repo_files_service.load_files(&repo_id, "/").await?;
         }
 
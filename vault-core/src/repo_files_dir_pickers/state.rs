@@ -3,4 +3,7 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct Options {
     pub repo_id: String,
+    /// Also show other unlocked repos as roots so that a destination can be
+    /// picked in a different repo.
+    pub include_unlocked_repos: bool,
 }
@@ -0,0 +1,526 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    cipher::{
+        data_cipher::{decrypt_size, encrypted_size},
+        Cipher,
+    },
+    common::state::Status,
+    remote::models,
+    remote_files::{
+        selectors as remote_files_selectors,
+        state::{RemoteFile, RemoteFileType},
+    },
+    repo_files::{
+        mutations as repo_files_mutations, selectors as repo_files_selectors,
+        state::{RepoFile, RepoFileType},
+    },
+    repo_files_list::{errors::FilesListRecursiveItemError, state::RepoFilesListRecursiveItem},
+    store,
+    utils::path_utils,
+};
+
+use super::{
+    errors::RepoFilesIndexError,
+    state::{RepoFilesIndex, RepoFilesIndexEntry, RepoFilesIndexRepo, INDEX_VERSION},
+};
+
+fn descendants_prefix(path: &str) -> String {
+    format!("{}/", path)
+}
+
+/// Builds an index from a recursive list of the repo root. Names that cannot
+/// be decrypted are left out, listing errors fail so that an incomplete index
+/// is never used.
+pub fn list_recursive_items_to_index(
+    items: Vec<RepoFilesListRecursiveItem>,
+    now: i64,
+) -> Result<RepoFilesIndex, RepoFilesIndexError> {
+    let mut entries = BTreeMap::new();
+
+    for item in items {
+        match item {
+            RepoFilesListRecursiveItem::File {
+                relative_repo_path: Ok(path),
+                file,
+            } => {
+                if path == "/" {
+                    continue;
+                }
+
+                entries.insert(path, repo_file_to_entry(&file));
+            }
+            RepoFilesListRecursiveItem::File { .. } => {}
+            RepoFilesListRecursiveItem::Error {
+                error: FilesListRecursiveItemError::DecryptFilenameError(_),
+                ..
+            } => {}
+            RepoFilesListRecursiveItem::Error { error, .. } => return Err(error.into()),
+        }
+    }
+
+    Ok(RepoFilesIndex {
+        version: INDEX_VERSION,
+        built: now,
+        entries,
+    })
+}
+
+pub fn repo_file_to_entry(file: &RepoFile) -> RepoFilesIndexEntry {
+    let is_dir = file.typ == RepoFileType::Dir;
+
+    RepoFilesIndexEntry {
+        is_dir,
+        size: if is_dir {
+            None
+        } else {
+            file.decrypted_size().ok()
+        },
+        modified: file.modified,
+    }
+}
+
+pub fn files_file_to_entry(file: &models::FilesFile) -> RepoFilesIndexEntry {
+    let is_dir = RemoteFileType::from(file.typ.as_str()) == RemoteFileType::Dir;
+
+    RepoFilesIndexEntry {
+        is_dir,
+        size: if is_dir {
+            None
+        } else {
+            decrypt_size(file.size).ok()
+        },
+        modified: file.modified,
+    }
+}
+
+pub fn is_stale(index: &RepoFilesIndex, now: i64, max_age_ms: i64) -> bool {
+    index.version != INDEX_VERSION || now - index.built >= max_age_ms
+}
+
+pub fn index_path_added(index: &mut RepoFilesIndex, path: &str, entry: RepoFilesIndexEntry) {
+    if path == "/" {
+        return;
+    }
+
+    // parents are implied by the path
+    if let Some(parent_path) = path_utils::parent_path(path) {
+        for parent_path in path_utils::paths_chain(parent_path) {
+            if parent_path != "/" && !index.entries.contains_key(&parent_path) {
+                index.entries.insert(
+                    parent_path,
+                    RepoFilesIndexEntry {
+                        is_dir: true,
+                        size: None,
+                        modified: entry.modified,
+                    },
+                );
+            }
+        }
+    }
+
+    index.entries.insert(path.to_owned(), entry);
+}
+
+fn take_descendants(index: &mut RepoFilesIndex, path: &str) -> Vec<(String, RepoFilesIndexEntry)> {
+    let prefix = descendants_prefix(path);
+
+    let paths = index
+        .entries
+        .range(prefix.clone()..)
+        .take_while(|(entry_path, _)| entry_path.starts_with(&prefix))
+        .map(|(entry_path, _)| entry_path.clone())
+        .collect::<Vec<_>>();
+
+    paths
+        .into_iter()
+        .filter_map(|entry_path| {
+            index
+                .entries
+                .remove(&entry_path)
+                .map(|entry| (entry_path, entry))
+        })
+        .collect()
+}
+
+pub fn index_path_removed(index: &mut RepoFilesIndex, path: &str) {
+    index.entries.remove(path);
+
+    take_descendants(index, path);
+}
+
+pub fn index_path_moved(
+    index: &mut RepoFilesIndex,
+    path: &str,
+    new_path: &str,
+    entry: RepoFilesIndexEntry,
+) {
+    index.entries.remove(path);
+
+    let descendants = take_descendants(index, path);
+
+    index_path_added(index, new_path, entry);
+
+    for (entry_path, descendant) in descendants {
+        index.entries.insert(
+            path_utils::join_paths(new_path, &entry_path[path.len()..]),
+            descendant,
+        );
+    }
+}
+
+pub fn index_path_copied(
+    index: &mut RepoFilesIndex,
+    path: &str,
+    new_path: &str,
+    entry: RepoFilesIndexEntry,
+) {
+    let prefix = descendants_prefix(path);
+
+    let descendants = index
+        .entries
+        .range(prefix.clone()..)
+        .take_while(|(entry_path, _)| entry_path.starts_with(&prefix))
+        .map(|(entry_path, descendant)| {
+            (
+                path_utils::join_paths(new_path, &entry_path[path.len()..]),
+                descendant.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    index_path_added(index, new_path, entry);
+
+    index.entries.extend(descendants);
+}
+
+/// Returns the paths and entries of the direct children of a dir.
+pub fn dir_children<'a>(
+    index: &'a RepoFilesIndex,
+    path: &str,
+) -> Vec<(&'a str, &'a RepoFilesIndexEntry)> {
+    let prefix = if path == "/" {
+        String::from("/")
+    } else {
+        descendants_prefix(path)
+    };
+
+    index
+        .entries
+        .range(prefix.clone()..)
+        .take_while(|(entry_path, _)| entry_path.starts_with(&prefix))
+        .filter(|(entry_path, _)| !entry_path[prefix.len()..].contains('/'))
+        .map(|(entry_path, entry)| (entry_path.as_str(), entry))
+        .collect()
+}
+
+/// Returns the paths and entries of all descendants of a dir, without the
+/// hidden dirs (trash, thumbnails cache).
+pub fn dir_descendants<'a>(
+    index: &'a RepoFilesIndex,
+    path: &str,
+) -> Vec<(&'a str, &'a RepoFilesIndexEntry)> {
+    let prefix = if path == "/" {
+        String::from("/")
+    } else {
+        descendants_prefix(path)
+    };
+
+    index
+        .entries
+        .range(prefix.clone()..)
+        .take_while(|(entry_path, _)| entry_path.starts_with(&prefix))
+        .filter(|(entry_path, _)| !repo_files_selectors::is_hidden_path(entry_path))
+        .map(|(entry_path, entry)| (entry_path.as_str(), entry))
+        .collect()
+}
+
+/// Builds the same repo file as a remote listing would. Names are encrypted
+/// deterministically so the remote path can be derived from the path.
+pub fn entry_to_repo_file(
+    repo_id: &str,
+    mount_id: &str,
+    repo_mount_path: &str,
+    path: &str,
+    entry: &RepoFilesIndexEntry,
+    cipher: &Cipher,
+) -> Option<RepoFile> {
+    let (parent_path, name) = path_utils::split_parent_name(path)?;
+
+    let (typ, encrypted_name) = if entry.is_dir {
+        (RemoteFileType::Dir, cipher.encrypt_dirname(name))
+    } else {
+        (RemoteFileType::File, cipher.encrypt_filename(name))
+    };
+
+    let remote_path = path_utils::join_path_name(
+        &path_utils::join_paths(repo_mount_path, &cipher.encrypt_path(parent_path)),
+        &encrypted_name,
+    );
+
+    let remote_file = RemoteFile {
+        id: remote_files_selectors::get_file_id(mount_id, &remote_path),
+        mount_id: mount_id.to_owned(),
+        path: remote_path,
+        name_lower: encrypted_name.to_lowercase(),
+        name: encrypted_name,
+        typ,
+        size: entry.size.map(encrypted_size).unwrap_or(0),
+        modified: entry.modified,
+        hash: None,
+        tags: HashMap::new(),
+    };
+
+    Some(repo_files_mutations::decrypt_file(
+        repo_id,
+        parent_path,
+        &remote_file,
+        cipher,
+    ))
+}
+
+pub fn set_enabled(state: &mut store::State, enabled: bool) {
+    state.repo_files_index.enabled = enabled;
+
+    if !enabled {
+        state.repo_files_index.repos.clear();
+    }
+}
+
+pub fn loading(state: &mut store::State, repo_id: &str) {
+    let index_repo = state
+        .repo_files_index
+        .repos
+        .entry(repo_id.to_owned())
+        .or_insert_with(|| RepoFilesIndexRepo {
+            status: Status::Initial,
+            index: None,
+        });
+
+    index_repo.status = match index_repo.index {
+        Some(_) => Status::Reloading,
+        None => Status::Loading,
+    };
+}
+
+pub fn loaded(
+    state: &mut store::State,
+    repo_id: &str,
+    res: Result<RepoFilesIndex, RepoFilesIndexError>,
+) {
+    // the repo could be locked in the meantime
+    if let Some(index_repo) = state.repo_files_index.repos.get_mut(repo_id) {
+        match res {
+            Ok(index) => {
+                index_repo.status = Status::Loaded;
+                index_repo.index = Some(index);
+            }
+            Err(error) => {
+                index_repo.status = Status::Error { error };
+            }
+        }
+    }
+}
+
+/// Returns true if the index was updated.
+pub fn update_index<F>(state: &mut store::State, repo_id: &str, f: F) -> bool
+where
+    F: FnOnce(&mut RepoFilesIndex),
+{
+    match state
+        .repo_files_index
+        .repos
+        .get_mut(repo_id)
+        .and_then(|index_repo| index_repo.index.as_mut())
+    {
+        Some(index) => {
+            f(index);
+
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn remove(state: &mut store::State, repo_id: &str) {
+    state.repo_files_index.repos.remove(repo_id);
+}
+
+/// Shows index files in a dir that was not loaded yet. The remote listing
+/// replaces them once it is loaded.
+pub fn fill_dir(state: &mut store::State, repo_id: &str, path: &str, files: Vec<RepoFile>) {
+    let dir_id = repo_files_selectors::get_file_id(repo_id, path);
+
+    if state.repo_files.children.contains_key(&dir_id) {
+        return;
+    }
+
+    let children = files.iter().map(|file| file.id.clone()).collect();
+
+    for file in files {
+        state
+            .repo_files
+            .files
+            .entry(file.id.clone())
+            .or_insert(file);
+    }
+
+    state.repo_files.children.insert(dir_id, children);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{
+        cipher::test_helpers::create_cipher,
+        repo_files_list::test_helpers::{
+            create_list_recursive_item_dir, create_list_recursive_item_file,
+        },
+    };
+
+    use super::{
+        super::state::{RepoFilesIndex, RepoFilesIndexEntry, INDEX_VERSION},
+        dir_children, entry_to_repo_file, index_path_added, index_path_copied, index_path_moved,
+        index_path_removed, is_stale, list_recursive_items_to_index,
+    };
+
+    fn dir() -> RepoFilesIndexEntry {
+        RepoFilesIndexEntry {
+            is_dir: true,
+            size: None,
+            modified: 1,
+        }
+    }
+
+    fn file(size: i64) -> RepoFilesIndexEntry {
+        RepoFilesIndexEntry {
+            is_dir: false,
+            size: Some(size),
+            modified: 1,
+        }
+    }
+
+    fn create_index(paths: &[(&str, RepoFilesIndexEntry)]) -> RepoFilesIndex {
+        RepoFilesIndex {
+            version: INDEX_VERSION,
+            built: 0,
+            entries: paths
+                .iter()
+                .map(|(path, entry)| (path.to_string(), entry.clone()))
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    fn paths(index: &RepoFilesIndex) -> Vec<&str> {
+        index.entries.keys().map(|path| path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_list_recursive_items_to_index() {
+        let cipher = create_cipher();
+
+        let index = list_recursive_items_to_index(
+            vec![
+                create_list_recursive_item_dir("m1", "/Vault", "r1", "/", "/", &cipher),
+                create_list_recursive_item_dir("m1", "/Vault", "r1", "/", "/D1", &cipher),
+                create_list_recursive_item_file("m1", "/Vault", "r1", "/", "/D1/F1", &cipher),
+            ],
+            1000,
+        )
+        .unwrap();
+
+        assert_eq!(paths(&index), vec!["/D1", "/D1/F1"]);
+        assert_eq!(index.built, 1000);
+        assert!(!is_stale(&index, 2000, 5000));
+        assert!(is_stale(&index, 6000, 5000));
+    }
+
+    #[test]
+    fn test_index_updates() {
+        let mut index = create_index(&[
+            ("/D1", dir()),
+            ("/D1/D2", dir()),
+            ("/D1/D2/F1", file(10)),
+            ("/D1 copy", dir()),
+            ("/F2", file(20)),
+        ]);
+
+        index_path_copied(&mut index, "/D1", "/D3", dir());
+        assert_eq!(
+            paths(&index),
+            vec![
+                "/D1",
+                "/D1 copy",
+                "/D1/D2",
+                "/D1/D2/F1",
+                "/D3",
+                "/D3/D2",
+                "/D3/D2/F1",
+                "/F2"
+            ]
+        );
+
+        index_path_moved(&mut index, "/D1", "/D4/D1", dir());
+        assert_eq!(
+            paths(&index),
+            vec![
+                "/D1 copy",
+                "/D3",
+                "/D3/D2",
+                "/D3/D2/F1",
+                "/D4",
+                "/D4/D1",
+                "/D4/D1/D2",
+                "/D4/D1/D2/F1",
+                "/F2"
+            ]
+        );
+
+        index_path_removed(&mut index, "/D3");
+        index_path_added(&mut index, "/F3", file(30));
+        assert_eq!(
+            paths(&index),
+            vec![
+                "/D1 copy",
+                "/D4",
+                "/D4/D1",
+                "/D4/D1/D2",
+                "/D4/D1/D2/F1",
+                "/F2",
+                "/F3"
+            ]
+        );
+
+        assert_eq!(
+            dir_children(&index, "/")
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>(),
+            vec!["/D1 copy", "/D4", "/F2", "/F3"]
+        );
+        assert_eq!(
+            dir_children(&index, "/D4/D1")
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>(),
+            vec!["/D4/D1/D2"]
+        );
+    }
+
+    #[test]
+    fn test_entry_to_repo_file() {
+        let cipher = create_cipher();
+
+        let repo_file =
+            entry_to_repo_file("r1", "m1", "/Vault", "/D1/F1.txt", &file(42), &cipher).unwrap();
+
+        assert_eq!(repo_file.id, "r1:/D1/F1.txt");
+        assert_eq!(repo_file.decrypted_name().unwrap(), "F1.txt");
+        assert_eq!(repo_file.decrypted_size().unwrap(), 42);
+        assert_eq!(
+            repo_file.remote_path,
+            format!("/Vault{}", cipher.encrypt_file_path("/D1/F1.txt"))
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use crate::store;
+
+use super::{mutations, state::RepoFilesIndex};
+
+pub fn select_is_enabled(state: &store::State) -> bool {
+    state.repo_files_index.enabled
+}
+
+pub fn select_index<'a>(state: &'a store::State, repo_id: &str) -> Option<&'a RepoFilesIndex> {
+    if !select_is_enabled(state) {
+        return None;
+    }
+
+    state
+        .repo_files_index
+        .repos
+        .get(repo_id)
+        .and_then(|index_repo| index_repo.index.as_ref())
+}
+
+pub fn select_fresh_index<'a>(
+    state: &'a store::State,
+    repo_id: &str,
+    now: i64,
+) -> Option<&'a RepoFilesIndex> {
+    select_index(state, repo_id)
+        .filter(|index| !mutations::is_stale(index, now, state.repo_files_index.max_age_ms))
+}
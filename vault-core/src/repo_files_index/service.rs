@@ -0,0 +1,428 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use futures::StreamExt;
+
+use crate::{
+    encrypted_storage::{EncryptedStorage, EncryptedStorageService},
+    remote::models,
+    remote_files::state::RemoteFileType,
+    repo_files::{
+        errors::RepoFilesErrors, selectors as repo_files_selectors, state::RepoFile,
+        RepoFilesService,
+    },
+    repo_files_list::RepoFilesListService,
+    repos::{selectors as repos_selectors, ReposService},
+    runtime, store,
+};
+
+use super::{
+    errors::RepoFilesIndexError,
+    mutations, selectors,
+    state::{get_storage_key, RepoFilesIndex, RepoFilesIndexEntry},
+};
+
+/// Event updates are batched into a single save.
+const SAVE_DELAY: i32 = 2000;
+
+/// Keeps an encrypted snapshot of all decrypted paths of unlocked repos so
+/// that browsing and search do not have to list and decrypt the whole repo.
+/// Indexes are updated from eventstream events and rebuilt once stale.
+pub struct RepoFilesIndexService {
+    repos_service: Arc<ReposService>,
+    repo_files_service: Arc<RepoFilesService>,
+    repo_files_list_service: Arc<RepoFilesListService>,
+    /// One index per repo. Indexes of large repos can exceed the storage
+    /// quota, so a failed save only means a rebuild on the next load.
+    storage: EncryptedStorageService,
+    store: Arc<store::Store>,
+    runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
+
+    pending_saves: Arc<Mutex<HashSet<String>>>,
+}
+
+impl RepoFilesIndexService {
+    pub fn new(
+        repos_service: Arc<ReposService>,
+        repo_files_service: Arc<RepoFilesService>,
+        repo_files_list_service: Arc<RepoFilesListService>,
+        storage: Box<dyn EncryptedStorage + Send + Sync>,
+        store: Arc<store::Store>,
+        runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
+    ) -> Self {
+        Self {
+            repos_service,
+            repo_files_service,
+            repo_files_list_service,
+            storage: EncryptedStorageService::new(storage),
+            store,
+            runtime,
+
+            pending_saves: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub fn now(&self) -> i64 {
+        instant::now() as i64
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.store.with_state(selectors::select_is_enabled)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.store.mutate(store::Event::RepoFilesIndex, |state| {
+            mutations::set_enabled(state, enabled);
+        });
+    }
+
+    /// Loads the stored index and rebuilds it if it is missing or stale.
+    pub async fn ensure_loaded(&self, repo_id: &str) -> Result<(), RepoFilesIndexError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let now = self.now();
+
+        if self
+            .store
+            .with_state(|state| selectors::select_fresh_index(state, repo_id, now).is_some())
+        {
+            return Ok(());
+        }
+
+        self.store.mutate(store::Event::RepoFilesIndex, |state| {
+            mutations::loading(state, repo_id);
+        });
+
+        let res = self.load_or_build(repo_id).await;
+        let res_err = res.as_ref().err().cloned();
+
+        self.store.mutate(store::Event::RepoFilesIndex, |state| {
+            mutations::loaded(state, repo_id, res);
+        });
+
+        match res_err {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn rebuild(&self, repo_id: &str) -> Result<(), RepoFilesIndexError> {
+        self.store.mutate(store::Event::RepoFilesIndex, |state| {
+            mutations::loading(state, repo_id);
+        });
+
+        let res = self.build_and_write(repo_id).await;
+        let res_err = res.as_ref().err().cloned();
+
+        self.store.mutate(store::Event::RepoFilesIndex, |state| {
+            mutations::loaded(state, repo_id, res);
+        });
+
+        match res_err {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the stored index of the repo.
+    pub fn clear(&self, repo_id: &str) -> Result<(), RepoFilesIndexError> {
+        self.store.mutate(store::Event::RepoFilesIndex, |state| {
+            mutations::remove(state, repo_id);
+        });
+
+        Ok(self.storage.remove(&get_storage_key(repo_id))?)
+    }
+
+    /// Returns the children of a dir from the index.
+    pub fn get_dir_files(&self, repo_id: &str, path: &str) -> Option<Vec<RepoFile>> {
+        self.get_files(repo_id, |index| mutations::dir_children(index, path))
+    }
+
+    /// Returns all descendants of a dir from a fresh index.
+    pub fn get_descendant_files(&self, repo_id: &str, path: &str) -> Option<Vec<RepoFile>> {
+        let now = self.now();
+
+        if self
+            .store
+            .with_state(|state| selectors::select_fresh_index(state, repo_id, now).is_none())
+        {
+            return None;
+        }
+
+        self.get_files(repo_id, |index| mutations::dir_descendants(index, path))
+    }
+
+    /// Shows the indexed children of a dir until the dir is loaded.
+    pub fn fill_dir(&self, repo_id: &str, path: &str) {
+        if let Some(files) = self.get_dir_files(repo_id, path) {
+            self.store.mutate(store::Event::RepoFiles, |state| {
+                mutations::fill_dir(state, repo_id, path, files);
+            });
+        }
+    }
+
+    pub fn remote_file_created(
+        self: Arc<Self>,
+        mount_id: &str,
+        path: &str,
+        file: &models::FilesFile,
+    ) {
+        if let Some((repo_id, path)) = self.mount_path_to_repo_path(mount_id, path, file) {
+            let entry = mutations::files_file_to_entry(file);
+
+            self.update_index(&repo_id, |index| {
+                mutations::index_path_added(index, &path, entry);
+            });
+        }
+    }
+
+    pub fn remote_file_removed(
+        self: Arc<Self>,
+        mount_id: &str,
+        path: &str,
+        file: &models::FilesFile,
+    ) {
+        if let Some((repo_id, path)) = self.mount_path_to_repo_path(mount_id, path, file) {
+            self.update_index(&repo_id, |index| {
+                mutations::index_path_removed(index, &path);
+            });
+        }
+    }
+
+    pub fn remote_file_copied(
+        self: Arc<Self>,
+        mount_id: &str,
+        path: &str,
+        new_path: &str,
+        file: &models::FilesFile,
+    ) {
+        self.remote_file_copied_moved(mount_id, path, new_path, file, false);
+    }
+
+    pub fn remote_file_moved(
+        self: Arc<Self>,
+        mount_id: &str,
+        path: &str,
+        new_path: &str,
+        file: &models::FilesFile,
+    ) {
+        self.remote_file_copied_moved(mount_id, path, new_path, file, true);
+    }
+
+    fn remote_file_copied_moved(
+        self: Arc<Self>,
+        mount_id: &str,
+        path: &str,
+        new_path: &str,
+        file: &models::FilesFile,
+        is_move: bool,
+    ) {
+        let entry = mutations::files_file_to_entry(file);
+
+        match (
+            self.mount_path_to_repo_path(mount_id, path, file),
+            self.mount_path_to_repo_path(mount_id, new_path, file),
+        ) {
+            (Some((repo_id, path)), Some((new_repo_id, new_path))) if repo_id == new_repo_id => {
+                self.update_index(&repo_id, |index| {
+                    if is_move {
+                        mutations::index_path_moved(index, &path, &new_path, entry);
+                    } else {
+                        mutations::index_path_copied(index, &path, &new_path, entry);
+                    }
+                });
+            }
+            (path, new_path) => {
+                // moved between repos or in or out of a repo, contents are
+                // unknown
+                if let Some((repo_id, path)) = path.filter(|_| is_move) {
+                    self.clone().update_index(&repo_id, |index| {
+                        mutations::index_path_removed(index, &path);
+                    });
+                }
+
+                if let Some((repo_id, new_path)) = new_path {
+                    self.update_index(&repo_id, |index| {
+                        mutations::index_path_added(index, &new_path, entry);
+                    });
+                }
+            }
+        }
+    }
+
+    fn get_files<F>(&self, repo_id: &str, f: F) -> Option<Vec<RepoFile>>
+    where
+        F: for<'b> FnOnce(&'b RepoFilesIndex) -> Vec<(&'b str, &'b RepoFilesIndexEntry)>,
+    {
+        let cipher = self.repos_service.get_cipher(repo_id).ok()?;
+
+        self.store.with_state(|state| {
+            let index = selectors::select_index(state, repo_id)?;
+            let repo = repos_selectors::select_repo(state, repo_id).ok()?;
+
+            Some(
+                f(index)
+                    .into_iter()
+                    .filter_map(|(path, entry)| {
+                        mutations::entry_to_repo_file(
+                            repo_id,
+                            &repo.mount_id,
+                            &repo.path,
+                            path,
+                            entry,
+                            &cipher,
+                        )
+                    })
+                    .collect(),
+            )
+        })
+    }
+
+    fn mount_path_to_repo_path(
+        &self,
+        mount_id: &str,
+        path: &str,
+        file: &models::FilesFile,
+    ) -> Option<(String, String)> {
+        let repo_id = self.store.with_state(|state| {
+            repo_files_selectors::select_mount_path_to_repo_id(state, mount_id, path)
+                .filter(|repo_id| selectors::select_index(state, repo_id).is_some())
+                .map(str::to_string)
+        })?;
+
+        let repo_path = self.store.with_state(|state| {
+            repos_selectors::select_repo(state, &repo_id)
+                .ok()
+                .map(|repo| repo.path.clone())
+        })?;
+
+        let cipher = self.repos_service.get_cipher(&repo_id).ok()?;
+
+        let encrypted_path = match repo_path.as_str() {
+            "/" => path,
+            _ => path.get(repo_path.len()..)?,
+        };
+
+        // the repo root is not indexed
+        if !encrypted_path.starts_with('/') || encrypted_path == "/" {
+            return None;
+        }
+
+        let decrypted_path = match RemoteFileType::from(file.typ.as_str()) {
+            RemoteFileType::File => cipher.decrypt_file_path(encrypted_path),
+            RemoteFileType::Dir => cipher.decrypt_path(encrypted_path),
+        }
+        .ok()?;
+
+        Some((repo_id, decrypted_path))
+    }
+
+    fn update_index<F>(self: Arc<Self>, repo_id: &str, f: F)
+    where
+        F: FnOnce(&mut RepoFilesIndex),
+    {
+        if self.store.mutate(store::Event::RepoFilesIndex, |state| {
+            mutations::update_index(state, repo_id, f)
+        }) {
+            self.schedule_save(repo_id);
+        }
+    }
+
+    fn schedule_save(self: Arc<Self>, repo_id: &str) {
+        if !self
+            .pending_saves
+            .lock()
+            .unwrap()
+            .insert(repo_id.to_owned())
+        {
+            return;
+        }
+
+        let save_self = self.clone();
+        let repo_id = repo_id.to_owned();
+
+        self.runtime.spawn(Box::pin(async move {
+            save_self.runtime.sleep(SAVE_DELAY).await;
+
+            save_self.pending_saves.lock().unwrap().remove(&repo_id);
+
+            if let Some(index) = save_self
+                .store
+                .with_state(|state| selectors::select_index(state, &repo_id).cloned())
+            {
+                let _ = save_self.write_index(&repo_id, &index).await;
+            }
+        }));
+    }
+
+    async fn load_or_build(&self, repo_id: &str) -> Result<RepoFilesIndex, RepoFilesIndexError> {
+        let max_age_ms = self
+            .store
+            .with_state(|state| state.repo_files_index.max_age_ms);
+
+        match self.read_index(repo_id).await {
+            Ok(Some(index)) if !mutations::is_stale(&index, self.now(), max_age_ms) => {
+                return Ok(index);
+            }
+            // invalid indexes are overwritten
+            Ok(_) | Err(RepoFilesIndexError::InvalidIndex(_)) => {}
+            Err(err) => return Err(err),
+        }
+
+        self.build_and_write(repo_id).await
+    }
+
+    async fn build_and_write(&self, repo_id: &str) -> Result<RepoFilesIndex, RepoFilesIndexError> {
+        self.repo_files_service.load_files(repo_id, "/").await?;
+
+        let root_file = self
+            .store
+            .with_state(|state| {
+                repo_files_selectors::select_file(
+                    state,
+                    &repo_files_selectors::get_file_id(repo_id, "/"),
+                )
+                .cloned()
+            })
+            .ok_or_else(|| RepoFilesIndexError::RemoteError(RepoFilesErrors::not_found()))?;
+
+        let items = self
+            .repo_files_list_service
+            .get_list_recursive(&root_file)
+            .await?
+            .collect::<Vec<_>>()
+            .await;
+
+        let index = mutations::list_recursive_items_to_index(items, self.now())?;
+
+        self.write_index(repo_id, &index).await?;
+
+        Ok(index)
+    }
+
+    async fn read_index(
+        &self,
+        repo_id: &str,
+    ) -> Result<Option<RepoFilesIndex>, RepoFilesIndexError> {
+        let cipher = self.repos_service.get_cipher(repo_id)?;
+
+        Ok(self.storage.get(&get_storage_key(repo_id), &cipher).await?)
+    }
+
+    async fn write_index(
+        &self,
+        repo_id: &str,
+        index: &RepoFilesIndex,
+    ) -> Result<(), RepoFilesIndexError> {
+        let cipher = self.repos_service.get_cipher(repo_id)?;
+
+        Ok(self
+            .storage
+            .set(&get_storage_key(repo_id), &cipher, index)
+            .await?)
+    }
+}
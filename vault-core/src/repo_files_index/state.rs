@@ -0,0 +1,57 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::state::Status;
+
+use super::errors::RepoFilesIndexError;
+
+pub const INDEX_VERSION: u32 = 1;
+
+/// Indexes older than this are rebuilt instead of updated from events.
+pub const DEFAULT_MAX_AGE_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RepoFilesIndexEntry {
+    #[serde(rename = "isDir")]
+    pub is_dir: bool,
+    pub size: Option<i64>,
+    pub modified: i64,
+}
+
+/// Snapshot of all decrypted paths of a repo. Paths are sorted so that the
+/// descendants of a dir are a contiguous range.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RepoFilesIndex {
+    pub version: u32,
+    pub built: i64,
+    pub entries: BTreeMap<String, RepoFilesIndexEntry>,
+}
+
+#[derive(Clone)]
+pub struct RepoFilesIndexRepo {
+    pub status: Status<RepoFilesIndexError>,
+    pub index: Option<RepoFilesIndex>,
+}
+
+#[derive(Clone)]
+pub struct RepoFilesIndexState {
+    pub enabled: bool,
+    pub max_age_ms: i64,
+    /// Only unlocked repos, indexes are dropped on lock
+    pub repos: HashMap<String, RepoFilesIndexRepo>,
+}
+
+impl Default for RepoFilesIndexState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_age_ms: DEFAULT_MAX_AGE_MS,
+            repos: HashMap::new(),
+        }
+    }
+}
+
+pub fn get_storage_key(repo_id: &str) -> String {
+    format!("vaultRepoFilesIndex:{}", repo_id)
+}
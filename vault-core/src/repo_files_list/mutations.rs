@@ -118,6 +118,8 @@ mod tests {
                     typ: RepoFileType::Dir,
                     size: RepoFileSize::Decrypted { size: 0 },
                     modified: 0,
+                    content_hash: None,
+                    remote_hash: None,
                     icon_type: FileIconType::Folder,
                 },
             }
@@ -153,6 +155,8 @@ mod tests {
                     typ: RepoFileType::Dir,
                     size: RepoFileSize::Decrypted { size: 0 },
                     modified: 1,
+                    content_hash: None,
+                    remote_hash: None,
                     icon_type: FileIconType::Folder,
                 },
             }
@@ -194,6 +198,8 @@ mod tests {
                     typ: RepoFileType::Dir,
                     size: RepoFileSize::Decrypted { size: 0 },
                     modified: 1,
+                    content_hash: None,
+                    remote_hash: None,
                     icon_type: FileIconType::Folder,
                 },
             }
@@ -229,6 +235,8 @@ mod tests {
                     typ: RepoFileType::File,
                     size: RepoFileSize::Decrypted { size: 52 },
                     modified: 1,
+                    content_hash: None,
+                    remote_hash: Some(String::from("hash")),
                     icon_type: FileIconType::Generic,
                 },
             }
@@ -275,6 +283,45 @@ mod tests {
                     typ: RepoFileType::File,
                     size: RepoFileSize::Decrypted { size: 52 },
                     modified: 1,
+                    content_hash: None,
+                    remote_hash: Some(String::from("hash")),
+                    icon_type: FileIconType::Generic,
+                },
+            }
+        )
+    }
+
+    #[test]
+    fn test_decrypt_files_list_recursive_item_file_directory_name_encryption_disabled() {
+        let cipher = create_cipher().with_directory_name_encryption(false);
+        let item = remote_test_helpers::create_files_list_recursive_item_file(
+            &format!("/D2/{}", cipher.encrypt_filename("F1")),
+            &cipher.encrypt_filename("F1"),
+        );
+
+        assert_eq!(
+            decrypt_files_list_recursive_item("m1", "/Vault/D1", "r1", "/D1", item, &cipher),
+            RepoFilesListRecursiveItem::File {
+                relative_repo_path: Ok(String::from("/D2/F1")),
+                file: RepoFile {
+                    id: String::from("r1:/D1/D2/F1"),
+                    mount_id: String::from("m1"),
+                    remote_path: format!("/Vault/D1/D2/{}", cipher.encrypt_filename("F1")),
+                    repo_id: String::from("r1"),
+                    path: RepoFilePath::Decrypted {
+                        path: String::from("/D1/D2/F1")
+                    },
+                    name: RepoFileName::Decrypted {
+                        name: String::from("F1"),
+                        name_lower: String::from("f1")
+                    },
+                    ext: None,
+                    content_type: None,
+                    typ: RepoFileType::File,
+                    size: RepoFileSize::Decrypted { size: 52 },
+                    modified: 1,
+                    content_hash: None,
+                    remote_hash: Some(String::from("hash")),
                     icon_type: FileIconType::Generic,
                 },
             }
@@ -316,6 +363,8 @@ mod tests {
                     typ: RepoFileType::File,
                     size: RepoFileSize::Decrypted { size: 52 },
                     modified: 1,
+                    content_hash: None,
+                    remote_hash: Some(String::from("hash")),
                     icon_type: FileIconType::Generic,
                 },
             }
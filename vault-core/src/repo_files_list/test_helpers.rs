@@ -36,7 +36,7 @@ pub fn create_list_recursive_item_file(
     item_path: &str,
     cipher: &Cipher,
 ) -> RepoFilesListRecursiveItem {
-    let encrypted_item_path = cipher.encrypt_path(item_path);
+    let encrypted_item_path = cipher.encrypt_file_path(item_path);
     let encrypted_item_name = path_utils::split_parent_name(&encrypted_item_path)
         .map(|(_, name)| name)
         .unwrap_or("");
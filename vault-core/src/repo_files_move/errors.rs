@@ -0,0 +1,142 @@
+use thiserror::Error;
+
+use crate::{
+    cipher::errors::{DecryptFilenameError, DecryptHeaderError, DecryptSizeError},
+    remote::RemoteError,
+    repo_files::errors::{
+        CopyFileError, DeleteFileError, EnsureDirError, LoadFilesError, MoveFileError,
+        RepoFilesErrors, UploadFileReaderError,
+    },
+    repo_files_list::errors::{FilesListRecursiveItemError, GetListRecursiveError},
+    repo_files_read::errors::GetFilesReaderError,
+    repos::errors::{RepoLockedError, RepoNotFoundError},
+    user_error::UserError,
+};
+
+#[derive(Error, Debug, Clone, UserError)]
+pub enum RepoFilesMoveError {
+    #[error("invalid path")]
+    InvalidPath,
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("{0}")]
+    DecryptFilenameError(#[from] DecryptFilenameError),
+    #[error("{0}")]
+    DecryptSizeError(#[from] DecryptSizeError),
+    #[error("{0}")]
+    DecryptHeaderError(#[from] DecryptHeaderError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+    #[error("failed to read file: {0}")]
+    ReadError(String),
+    #[error("transfer cancelled")]
+    Cancelled,
+}
+
+impl From<CopyFileError> for RepoFilesMoveError {
+    fn from(err: CopyFileError) -> Self {
+        match err {
+            CopyFileError::InvalidPath => Self::InvalidPath,
+            CopyFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            CopyFileError::RepoLocked(err) => Self::RepoLocked(err),
+            CopyFileError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            CopyFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<MoveFileError> for RepoFilesMoveError {
+    fn from(err: MoveFileError) -> Self {
+        match err {
+            MoveFileError::InvalidPath => Self::InvalidPath,
+            MoveFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            MoveFileError::RepoLocked(err) => Self::RepoLocked(err),
+            MoveFileError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            MoveFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<DeleteFileError> for RepoFilesMoveError {
+    fn from(err: DeleteFileError) -> Self {
+        match err {
+            DeleteFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            DeleteFileError::RepoLocked(err) => Self::RepoLocked(err),
+            DeleteFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<EnsureDirError> for RepoFilesMoveError {
+    fn from(err: EnsureDirError) -> Self {
+        match err {
+            EnsureDirError::RepoNotFound(err) => Self::RepoNotFound(err),
+            EnsureDirError::RepoLocked(err) => Self::RepoLocked(err),
+            EnsureDirError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            EnsureDirError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<LoadFilesError> for RepoFilesMoveError {
+    fn from(err: LoadFilesError) -> Self {
+        match err {
+            LoadFilesError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFilesError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFilesError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<UploadFileReaderError> for RepoFilesMoveError {
+    fn from(err: UploadFileReaderError) -> Self {
+        match err {
+            UploadFileReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            UploadFileReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            UploadFileReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            UploadFileReaderError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<GetFilesReaderError> for RepoFilesMoveError {
+    fn from(err: GetFilesReaderError) -> Self {
+        match err {
+            GetFilesReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetFilesReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            GetFilesReaderError::FileNotFound | GetFilesReaderError::FilesEmpty => {
+                Self::RemoteError(RepoFilesErrors::not_found())
+            }
+            GetFilesReaderError::InvalidRange => Self::InvalidPath,
+            GetFilesReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            GetFilesReaderError::DecryptSizeError(err) => Self::DecryptSizeError(err),
+            GetFilesReaderError::DecryptHeaderError(err) => Self::DecryptHeaderError(err),
+            GetFilesReaderError::RemoteError(err) => Self::RemoteError(err),
+            GetFilesReaderError::ReadError(err) => Self::ReadError(err),
+        }
+    }
+}
+
+impl From<GetListRecursiveError> for RepoFilesMoveError {
+    fn from(err: GetListRecursiveError) -> Self {
+        match err {
+            GetListRecursiveError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetListRecursiveError::RepoLocked(err) => Self::RepoLocked(err),
+            GetListRecursiveError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            GetListRecursiveError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<FilesListRecursiveItemError> for RepoFilesMoveError {
+    fn from(err: FilesListRecursiveItemError) -> Self {
+        match err {
+            FilesListRecursiveItemError::DecryptFilenameError(err) => {
+                Self::DecryptFilenameError(err)
+            }
+            FilesListRecursiveItemError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
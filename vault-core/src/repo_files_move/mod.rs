@@ -1,3 +1,5 @@
+pub mod errors;
+pub mod mutations;
 pub mod selectors;
 pub mod service;
 pub mod state;
@@ -0,0 +1,143 @@
+use crate::{
+    repo_files::state::RepoFileType, repo_files_list::state::RepoFilesListRecursiveItem, store,
+    utils::path_utils,
+};
+
+use super::{
+    errors::RepoFilesMoveError,
+    state::{RepoFilesMoveTransfer, RepoFilesMoveTransferEntry, RepoFilesMoveTransferItem},
+};
+
+/// Maps recursive list items of a source dir to entries under dest_path. Fails
+/// on items that cannot be decrypted so that nothing is silently left out.
+pub fn list_recursive_items_to_transfer_entries(
+    items: Vec<RepoFilesListRecursiveItem>,
+    dest_path: &str,
+) -> Result<Vec<RepoFilesMoveTransferEntry>, RepoFilesMoveError> {
+    let mut entries = Vec::with_capacity(items.len());
+
+    for item in items {
+        match item {
+            RepoFilesListRecursiveItem::File {
+                relative_repo_path,
+                file,
+            } => {
+                let relative_repo_path = relative_repo_path?;
+
+                entries.push(RepoFilesMoveTransferEntry {
+                    dest_path: path_utils::join_paths(dest_path, &relative_repo_path),
+                    file,
+                });
+            }
+            RepoFilesListRecursiveItem::Error { error, .. } => return Err(error.into()),
+        }
+    }
+
+    Ok(entries)
+}
+
+pub fn transfer_started(
+    state: &mut store::State,
+    dir_picker_id: u32,
+    dest_repo_id: &str,
+    items: &[RepoFilesMoveTransferItem],
+) {
+    if let Some(ref mut files_move) = state.repo_files_move {
+        if files_move.dir_picker_id != dir_picker_id {
+            return;
+        }
+
+        files_move.transfer = Some(RepoFilesMoveTransfer {
+            dest_repo_id: dest_repo_id.to_owned(),
+            total_count: items.iter().map(|item| item.entries.len()).sum(),
+            done_count: 0,
+            total_bytes: items
+                .iter()
+                .flat_map(|item| item.entries.iter())
+                .filter(|entry| entry.file.typ == RepoFileType::File)
+                .filter_map(|entry| entry.file.decrypted_size().ok())
+                .sum(),
+            done_bytes: 0,
+        });
+    }
+}
+
+pub fn transfer_progress(state: &mut store::State, dir_picker_id: u32, bytes: i64) {
+    if let Some(ref mut files_move) = state.repo_files_move {
+        if files_move.dir_picker_id != dir_picker_id {
+            return;
+        }
+
+        if let Some(ref mut transfer) = files_move.transfer {
+            transfer.done_bytes += bytes;
+        }
+    }
+}
+
+pub fn transfer_entry_done(state: &mut store::State, dir_picker_id: u32) {
+    if let Some(ref mut files_move) = state.repo_files_move {
+        if files_move.dir_picker_id != dir_picker_id {
+            return;
+        }
+
+        if let Some(ref mut transfer) = files_move.transfer {
+            transfer.done_count += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cipher::{errors::DecryptFilenameError, test_helpers::create_cipher},
+        repo_files_list::{
+            errors::FilesListRecursiveItemError,
+            state::RepoFilesListRecursiveItem,
+            test_helpers::{create_list_recursive_item_dir, create_list_recursive_item_file},
+        },
+    };
+
+    use super::{super::errors::RepoFilesMoveError, list_recursive_items_to_transfer_entries};
+
+    #[test]
+    fn test_list_recursive_items_to_transfer_entries() {
+        let cipher = create_cipher();
+
+        let entries = list_recursive_items_to_transfer_entries(
+            vec![
+                create_list_recursive_item_dir("m1", "/Vault", "r1", "/D1", "/", &cipher),
+                create_list_recursive_item_dir("m1", "/Vault", "r1", "/D1", "/D2", &cipher),
+                create_list_recursive_item_file("m1", "/Vault", "r1", "/D1", "/D2/F1", &cipher),
+            ],
+            "/Other/D1 (1)",
+        )
+        .unwrap();
+
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.dest_path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["/Other/D1 (1)", "/Other/D1 (1)/D2", "/Other/D1 (1)/D2/F1"]
+        );
+        assert_eq!(entries[2].file.decrypted_path().unwrap(), "/D1/D2/F1");
+
+        let res = list_recursive_items_to_transfer_entries(
+            vec![RepoFilesListRecursiveItem::Error {
+                mount_id: String::from("m1"),
+                remote_path: Some(String::from("/Vault/xxx")),
+                error: FilesListRecursiveItemError::DecryptFilenameError(
+                    DecryptFilenameError::DecryptError,
+                ),
+            }],
+            "/Other/D1",
+        );
+
+        assert!(matches!(
+            res,
+            Err(RepoFilesMoveError::DecryptFilenameError(
+                DecryptFilenameError::DecryptError
+            ))
+        ));
+    }
+}
@@ -1,12 +1,18 @@
+use std::collections::HashSet;
+
 use crate::{
     repo_files::{
         errors::{CreateDirError, MoveFileError, RepoFilesErrors},
+        selectors as repo_files_selectors,
         state::RepoFile,
     },
     repo_files_dir_pickers::selectors as repo_files_dir_pickers_selectors,
     store,
+    utils::name_utils,
 };
 
+use super::state::RepoFilesMoveTransfer;
+
 pub fn select_dir_picker_id(state: &store::State) -> Option<u32> {
     state.repo_files_move.as_ref().map(|x| x.dir_picker_id)
 }
@@ -39,3 +45,41 @@ pub fn select_check_move<'a>(state: &'a store::State) -> Result<(), MoveFileErro
 
     Ok(())
 }
+
+pub fn select_transfer<'a>(state: &'a store::State) -> Option<&'a RepoFilesMoveTransfer> {
+    state
+        .repo_files_move
+        .as_ref()
+        .and_then(|files_move| files_move.transfer.as_ref())
+}
+
+/// Returns false once the move was canceled or replaced with a new one.
+pub fn select_is_transferring(state: &store::State, dir_picker_id: u32) -> bool {
+    state
+        .repo_files_move
+        .as_ref()
+        .map(|files_move| {
+            files_move.dir_picker_id == dir_picker_id && files_move.transfer.is_some()
+        })
+        .unwrap_or(false)
+}
+
+/// Files copied into a different repo are never merged with existing ones,
+/// they get a new name instead.
+pub fn select_unused_dest_name(
+    state: &store::State,
+    repo_id: &str,
+    parent_path: &str,
+    name: &str,
+    pending_names: &HashSet<String>,
+) -> String {
+    let mut used_names = pending_names.clone();
+
+    for file in repo_files_selectors::select_files(state, repo_id, parent_path) {
+        if let Ok(name) = file.decrypted_name() {
+            used_names.insert(name.to_lowercase());
+        }
+    }
+
+    name_utils::unused_name(name, |name| used_names.contains(&name.to_lowercase()))
+}
@@ -1,38 +1,65 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use futures::{
+    channel::oneshot::{self, Sender},
+    FutureExt, StreamExt,
+};
 
 use crate::{
     dir_pickers::selectors as dir_pickers_selectors,
+    http,
+    remote::{ApiErrorCode, RemoteError},
     repo_files::{
-        errors::{CreateDirError, LoadFilesError, MoveFileError, RepoFilesErrors},
-        selectors as repo_files_selectors, RepoFilesService,
+        errors::{CreateDirError, DeleteFileError, LoadFilesError, RepoFilesErrors},
+        selectors as repo_files_selectors,
+        state::{RepoFile, RepoFileType, RepoFilesUploadConflictResolution},
+        RepoFilesService,
     },
     repo_files_browsers::selectors as repo_files_browsers_selectors,
     repo_files_dir_pickers::RepoFilesDirPickersService,
+    repo_files_list::RepoFilesListService,
+    repo_files_read::RepoFilesReadService,
     store,
-    utils::path_utils,
+    utils::{abort_reader::AbortReader, path_utils},
 };
 
 use super::{
-    selectors,
-    state::{RepoFilesMoveMode, RepoFilesMoveState},
+    errors::RepoFilesMoveError,
+    mutations, selectors,
+    state::{
+        RepoFilesMoveMode, RepoFilesMoveState, RepoFilesMoveTransferEntry,
+        RepoFilesMoveTransferItem,
+    },
 };
 
 pub struct RepoFilesMoveService {
     repo_files_service: Arc<RepoFilesService>,
+    repo_files_read_service: Arc<RepoFilesReadService>,
+    repo_files_list_service: Arc<RepoFilesListService>,
     repo_files_dir_pickers_service: Arc<RepoFilesDirPickersService>,
     store: Arc<store::Store>,
+    /// Abort of the running transfer by dir picker id
+    transfer_abort_senders: Arc<Mutex<HashMap<u32, Sender<()>>>>,
 }
 
 impl RepoFilesMoveService {
     pub fn new(
         repo_files_service: Arc<RepoFilesService>,
+        repo_files_read_service: Arc<RepoFilesReadService>,
+        repo_files_list_service: Arc<RepoFilesListService>,
         repo_files_dir_pickers_service: Arc<RepoFilesDirPickersService>,
         store: Arc<store::Store>,
     ) -> Self {
         Self {
             repo_files_service,
+            repo_files_read_service,
+            repo_files_list_service,
             repo_files_dir_pickers_service,
             store,
+            transfer_abort_senders: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -64,7 +91,7 @@ impl RepoFilesMoveService {
                 .and_then(|file| file.decrypted_path().ok().map(str::to_string))
         });
 
-        let dir_picker_id = self.repo_files_dir_pickers_service.create(&repo_id);
+        let dir_picker_id = self.repo_files_dir_pickers_service.create(&repo_id, true);
 
         self.store.mutate(store::Event::RepoFilesMove, |state| {
             state.repo_files_move = Some(RepoFilesMoveState {
@@ -72,6 +99,7 @@ impl RepoFilesMoveService {
                 src_file_ids,
                 mode,
                 dir_picker_id,
+                transfer: None,
             });
         });
 
@@ -103,15 +131,16 @@ impl RepoFilesMoveService {
         Ok(())
     }
 
-    pub async fn move_files(&self) -> Result<(), MoveFileError> {
+    pub async fn move_files(&self) -> Result<(), RepoFilesMoveError> {
         let RepoFilesMoveState {
             repo_id,
             src_file_ids,
             mode,
             dir_picker_id,
+            ..
         } = self
             .store
-            .with_state::<_, Result<_, MoveFileError>>(|state| {
+            .with_state::<_, Result<_, RepoFilesMoveError>>(|state| {
                 selectors::select_check_move(state)?;
 
                 Ok(state
@@ -126,26 +155,45 @@ impl RepoFilesMoveService {
                 .ok_or_else(RepoFilesErrors::not_found)
         })?;
 
-        let dest_parent_path = self
+        let (dest_repo_id, dest_parent_path) = self
             .store
-            .with_state::<_, Result<_, MoveFileError>>(|state| {
+            .with_state::<_, Result<_, RepoFilesMoveError>>(|state| {
                 let file = repo_files_selectors::select_file(state, &dest_file_id)
                     .ok_or_else(RepoFilesErrors::not_found)?;
 
-                Ok(file.decrypted_path()?.to_owned())
+                Ok((file.repo_id.clone(), file.decrypted_path()?.to_owned()))
             })?;
 
+        if dest_repo_id != repo_id {
+            let res = self
+                .transfer_files(
+                    dir_picker_id,
+                    &repo_id,
+                    &src_file_ids,
+                    &dest_repo_id,
+                    &dest_parent_path,
+                    mode,
+                )
+                .await;
+
+            // keep the dialog if the user already started a new move
+            if self
+                .store
+                .with_state(|state| selectors::select_dir_picker_id(state) == Some(dir_picker_id))
+            {
+                self.cancel();
+            }
+
+            return res;
+        }
+
         self.cancel();
 
         for src_file_id in src_file_ids {
             let src_path = self
-                .store
-                .with_state::<_, Result<_, MoveFileError>>(|state| {
-                    let file = repo_files_selectors::select_file(state, &src_file_id)
-                        .ok_or_else(RepoFilesErrors::not_found)?;
-
-                    Ok(file.decrypted_path()?.to_owned())
-                })?;
+                .get_src_file(&src_file_id)?
+                .decrypted_path()?
+                .to_owned();
 
             match mode {
                 RepoFilesMoveMode::Copy => {
@@ -164,6 +212,292 @@ impl RepoFilesMoveService {
         Ok(())
     }
 
+    fn get_src_file(&self, src_file_id: &str) -> Result<RepoFile, RepoFilesMoveError> {
+        self.store.with_state(|state| {
+            repo_files_selectors::select_file(state, src_file_id)
+                .cloned()
+                .ok_or_else(|| RepoFilesErrors::not_found().into())
+        })
+    }
+
+    /// Copies files into a different repo. Remote copy cannot be used because
+    /// the repos have different keys, so every file is decrypted with the
+    /// source cipher and encrypted again with the destination cipher. If the
+    /// transfer fails or is cancelled, the partially transferred item is
+    /// removed from the destination, items transferred before it are kept.
+    async fn transfer_files(
+        &self,
+        dir_picker_id: u32,
+        repo_id: &str,
+        src_file_ids: &[String],
+        dest_repo_id: &str,
+        dest_parent_path: &str,
+        mode: RepoFilesMoveMode,
+    ) -> Result<(), RepoFilesMoveError> {
+        self.repo_files_service
+            .load_files(dest_repo_id, dest_parent_path)
+            .await?;
+
+        let mut pending_names = HashSet::new();
+        let mut items: Vec<RepoFilesMoveTransferItem> = Vec::new();
+
+        for src_file_id in src_file_ids {
+            let src_file = self.get_src_file(src_file_id)?;
+            let src_name = src_file.decrypted_name()?.to_owned();
+
+            let dest_name = self.store.with_state(|state| {
+                selectors::select_unused_dest_name(
+                    state,
+                    dest_repo_id,
+                    dest_parent_path,
+                    &src_name,
+                    &pending_names,
+                )
+            });
+            pending_names.insert(dest_name.to_lowercase());
+
+            let dest_path = path_utils::join_path_name(dest_parent_path, &dest_name);
+
+            let entries = match src_file.typ {
+                RepoFileType::Dir => {
+                    let items = self
+                        .repo_files_list_service
+                        .get_list_recursive(&src_file)
+                        .await?
+                        .collect::<Vec<_>>()
+                        .await;
+
+                    mutations::list_recursive_items_to_transfer_entries(items, &dest_path)?
+                }
+                RepoFileType::File => vec![RepoFilesMoveTransferEntry {
+                    file: src_file,
+                    dest_path: dest_path.clone(),
+                }],
+            };
+
+            items.push(RepoFilesMoveTransferItem { dest_path, entries });
+        }
+
+        self.store.mutate(store::Event::RepoFilesMove, |state| {
+            mutations::transfer_started(state, dir_picker_id, dest_repo_id, &items);
+        });
+
+        let (abort_sender, abort_receiver) = oneshot::channel();
+
+        self.transfer_abort_senders
+            .lock()
+            .unwrap()
+            .insert(dir_picker_id, abort_sender);
+
+        let abort: http::HttpRequestAbort = Some(
+            abort_receiver
+                .map(|res| res.map_err(|_| ()))
+                .boxed()
+                .shared(),
+        );
+
+        let res = self
+            .transfer_items(dir_picker_id, repo_id, dest_repo_id, items, mode, abort)
+            .await;
+
+        self.transfer_abort_senders
+            .lock()
+            .unwrap()
+            .remove(&dir_picker_id);
+
+        res
+    }
+
+    async fn transfer_items(
+        &self,
+        dir_picker_id: u32,
+        repo_id: &str,
+        dest_repo_id: &str,
+        items: Vec<RepoFilesMoveTransferItem>,
+        mode: RepoFilesMoveMode,
+        abort: http::HttpRequestAbort,
+    ) -> Result<(), RepoFilesMoveError> {
+        for item in items {
+            let mut transferred_count = 0;
+
+            for entry in &item.entries {
+                let (res, started) = if self.is_transferring(dir_picker_id) {
+                    (
+                        self.transfer_entry(
+                            dir_picker_id,
+                            dest_repo_id,
+                            entry.clone(),
+                            abort.clone(),
+                        )
+                        .await,
+                        true,
+                    )
+                } else {
+                    (Err(RepoFilesMoveError::Cancelled), false)
+                };
+
+                if let Err(err) = res {
+                    let cancelled = !self.is_transferring(dir_picker_id);
+
+                    // an entry aborted by cancel can leave a partial file
+                    if transferred_count > 0 || (started && cancelled) {
+                        self.remove_partial_dest(dest_repo_id, &item.dest_path)
+                            .await;
+                    }
+
+                    return Err(if cancelled {
+                        RepoFilesMoveError::Cancelled
+                    } else {
+                        err
+                    });
+                }
+
+                transferred_count += 1;
+
+                self.store.mutate(store::Event::RepoFilesMove, |state| {
+                    mutations::transfer_entry_done(state, dir_picker_id);
+                });
+            }
+
+            // only delete the source once all of its entries were transferred
+            if let RepoFilesMoveMode::Move = mode {
+                self.delete_transferred_src(repo_id, &item).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A failed cleanup is only logged, the transfer error is returned to the
+    /// user.
+    async fn remove_partial_dest(&self, dest_repo_id: &str, dest_path: &str) {
+        match self
+            .repo_files_service
+            .delete_file(dest_repo_id, dest_path)
+            .await
+        {
+            Ok(())
+            | Err(DeleteFileError::RemoteError(RemoteError::ApiError {
+                code: ApiErrorCode::NotFound,
+                ..
+            })) => {}
+            Err(err) => {
+                log::warn!(
+                    "Failed to remove partially transferred {}: {}",
+                    dest_path,
+                    err
+                );
+            }
+        }
+    }
+
+    /// Deletes only the transferred source files and then the source dirs
+    /// that are empty afterwards, so files added to the source during the
+    /// transfer are kept.
+    async fn delete_transferred_src(
+        &self,
+        repo_id: &str,
+        item: &RepoFilesMoveTransferItem,
+    ) -> Result<(), RepoFilesMoveError> {
+        let mut dir_paths = Vec::new();
+
+        for entry in &item.entries {
+            let path = entry.file.decrypted_path()?;
+
+            match entry.file.typ {
+                RepoFileType::File => {
+                    self.repo_files_service.delete_file(repo_id, path).await?;
+                }
+                RepoFileType::Dir => dir_paths.push(path.to_owned()),
+            }
+        }
+
+        // a child path is always longer than its parent path
+        dir_paths.sort_by(|a, b| b.len().cmp(&a.len()));
+
+        for dir_path in dir_paths {
+            self.repo_files_service
+                .load_files(repo_id, &dir_path)
+                .await?;
+
+            let is_empty = self.store.with_state(|state| {
+                repo_files_selectors::select_files(state, repo_id, &dir_path)
+                    .next()
+                    .is_none()
+            });
+
+            if is_empty {
+                self.repo_files_service
+                    .delete_file(repo_id, &dir_path)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_transferring(&self, dir_picker_id: u32) -> bool {
+        self.store
+            .with_state(|state| selectors::select_is_transferring(state, dir_picker_id))
+    }
+
+    async fn transfer_entry(
+        &self,
+        dir_picker_id: u32,
+        dest_repo_id: &str,
+        entry: RepoFilesMoveTransferEntry,
+        abort: http::HttpRequestAbort,
+    ) -> Result<(), RepoFilesMoveError> {
+        let RepoFilesMoveTransferEntry { file, dest_path } = entry;
+
+        match file.typ {
+            RepoFileType::Dir => {
+                self.repo_files_service
+                    .clone()
+                    .ensure_dirs(dest_repo_id, &dest_path)
+                    .await?;
+            }
+            RepoFileType::File => {
+                let (dest_parent_path, dest_name) = path_utils::split_parent_name(&dest_path)
+                    .ok_or(RepoFilesMoveError::InvalidPath)?;
+
+                let modified = file.modified;
+
+                let file_reader = self
+                    .repo_files_read_service
+                    .clone()
+                    .get_files_reader(&[file])
+                    .await?;
+
+                let progress_store = self.store.clone();
+
+                self.repo_files_service
+                    .clone()
+                    .upload_file_reader(
+                        dest_repo_id,
+                        dest_parent_path,
+                        dest_name,
+                        // the source read stops as well on cancel
+                        Box::pin(AbortReader::new(file_reader.reader, abort.clone())),
+                        file_reader.size,
+                        Some(modified),
+                        RepoFilesUploadConflictResolution::Error,
+                        Some(Box::new(move |n| {
+                            progress_store.mutate(store::Event::RepoFilesMove, |state| {
+                                mutations::transfer_progress(state, dir_picker_id, n as i64);
+                            });
+                        })),
+                        abort,
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Also aborts the running transfer, its partially transferred item is
+    /// removed from the destination.
     pub fn cancel(&self) {
         if let Some(dir_picker_id) = self.store.mutate(store::Event::RepoFilesMove, |state| {
             let dir_picker_id = state.repo_files_move.as_ref().map(|x| x.dir_picker_id);
@@ -173,6 +507,15 @@ impl RepoFilesMoveService {
             dir_picker_id
         }) {
             self.repo_files_dir_pickers_service.destroy(dir_picker_id);
+
+            if let Some(abort_sender) = self
+                .transfer_abort_senders
+                .lock()
+                .unwrap()
+                .remove(&dir_picker_id)
+            {
+                let _ = abort_sender.send(());
+            }
         }
     }
 
@@ -192,3 +535,203 @@ impl RepoFilesMoveService {
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use futures::executor::block_on;
+
+    use crate::{
+        http::mock_http_client::MockHttpResponse,
+        remote::fake_remote::error_response,
+        repo_files::{
+            selectors as repo_files_selectors,
+            test_helpers::{TestContext, TEST_MOUNT_ID},
+        },
+        repo_files_dir_pickers::RepoFilesDirPickersService,
+        store,
+    };
+
+    use super::{
+        super::{
+            errors::RepoFilesMoveError,
+            state::{RepoFilesMoveMode, RepoFilesMoveState},
+        },
+        RepoFilesMoveService,
+    };
+
+    fn setup() -> (TestContext, RepoFilesMoveService) {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault1");
+        ctx.add_repo("r2", "/Vault2");
+
+        let service = RepoFilesMoveService::new(
+            ctx.repo_files_service.clone(),
+            ctx.repo_files_read_service.clone(),
+            ctx.repo_files_list_service.clone(),
+            Arc::new(RepoFilesDirPickersService::new(
+                ctx.repo_files_service.clone(),
+                ctx.store.clone(),
+            )),
+            ctx.store.clone(),
+        );
+
+        (ctx, service)
+    }
+
+    /// Transfers the files from the root of r1 to the root of r2
+    fn transfer(
+        ctx: &TestContext,
+        service: &RepoFilesMoveService,
+        src_paths: &[&str],
+        mode: RepoFilesMoveMode,
+    ) -> Result<(), RepoFilesMoveError> {
+        block_on(ctx.repo_files_service.load_files("r1", "/")).unwrap();
+
+        let src_file_ids = src_paths
+            .iter()
+            .map(|path| repo_files_selectors::get_file_id("r1", path))
+            .collect::<Vec<_>>();
+
+        ctx.store.mutate(store::Event::RepoFilesMove, |state| {
+            state.repo_files_move = Some(RepoFilesMoveState {
+                repo_id: String::from("r1"),
+                src_file_ids: src_file_ids.clone(),
+                mode: mode.clone(),
+                dir_picker_id: 1,
+                transfer: None,
+            });
+        });
+
+        block_on(service.transfer_files(1, "r1", &src_file_ids, "r2", "/", mode))
+    }
+
+    /// Calls on_upload with the number of the upload before it is handled. A
+    /// returned response replaces the upload.
+    fn intercept_uploads(
+        ctx: &TestContext,
+        on_upload: impl Fn(usize) -> Option<MockHttpResponse> + Send + Sync + 'static,
+    ) {
+        let count = AtomicUsize::new(0);
+
+        ctx.fake_remote.set_intercept(Some(Box::new(move |req| {
+            if req.method == "POST" && req.url.contains("/files/put?") {
+                on_upload(count.fetch_add(1, Ordering::SeqCst) + 1).map(Ok)
+            } else {
+                None
+            }
+        })));
+    }
+
+    #[test]
+    fn test_transfer_files_move() {
+        let (ctx, service) = setup();
+        ctx.add_file("r1", "/D1/A.txt", b"aaa");
+        ctx.add_file("r1", "/D1/Sub/B.txt", b"bbb");
+
+        let new_remote_path = ctx.remote_file_path("r1", "/D1/New.txt");
+        let mut new_content = Vec::new();
+        block_on(ctx.cipher("r1").encrypt_data(b"new", &mut new_content)).unwrap();
+        let fake_remote = ctx.fake_remote.clone();
+
+        intercept_uploads(&ctx, move |n| {
+            // a file is added to the source while it is being moved
+            if n == 1 {
+                fake_remote.add_file(TEST_MOUNT_ID, &new_remote_path, new_content.clone(), 1);
+            }
+
+            None
+        });
+
+        transfer(&ctx, &service, &["/D1"], RepoFilesMoveMode::Move).unwrap();
+
+        assert_eq!(
+            ctx.repo_paths("r2"),
+            vec!["/D1", "/D1/A.txt", "/D1/Sub", "/D1/Sub/B.txt"]
+        );
+        assert_eq!(ctx.file_content("r2", "/D1/A.txt"), Some(b"aaa".to_vec()));
+        assert_eq!(
+            ctx.file_content("r2", "/D1/Sub/B.txt"),
+            Some(b"bbb".to_vec())
+        );
+        assert_eq!(ctx.repo_paths("r1"), vec!["/D1", "/D1/New.txt"]);
+    }
+
+    #[test]
+    fn test_transfer_files_failed() {
+        let (ctx, service) = setup();
+        ctx.add_file("r1", "/D1/A.txt", b"aaa");
+        ctx.add_file("r1", "/D1/B.txt", b"bbb");
+
+        intercept_uploads(&ctx, |n| {
+            if n == 2 {
+                Some(error_response(500, "Internal", "Internal error"))
+            } else {
+                None
+            }
+        });
+
+        let res = transfer(&ctx, &service, &["/D1"], RepoFilesMoveMode::Move);
+
+        assert!(matches!(res, Err(RepoFilesMoveError::RemoteError(_))));
+        assert_eq!(ctx.repo_paths("r2"), Vec::<String>::new());
+        assert_eq!(ctx.repo_paths("r1"), vec!["/D1", "/D1/A.txt", "/D1/B.txt"]);
+    }
+
+    #[test]
+    fn test_transfer_files_cancel() {
+        let (ctx, service) = setup();
+        ctx.add_file("r1", "/F1.txt", b"f1");
+        ctx.add_file("r1", "/D1/A.txt", b"aaa");
+        ctx.add_file("r1", "/D1/B.txt", b"bbb");
+
+        let cancel_store = ctx.store.clone();
+
+        intercept_uploads(&ctx, move |n| {
+            // the user closes the dialog while D1 is being transferred
+            if n == 2 {
+                cancel_store.mutate(store::Event::RepoFilesMove, |state| {
+                    state.repo_files_move = None;
+                });
+            }
+
+            None
+        });
+
+        let res = transfer(&ctx, &service, &["/F1.txt", "/D1"], RepoFilesMoveMode::Copy);
+
+        assert!(matches!(res, Err(RepoFilesMoveError::Cancelled)));
+        assert_eq!(ctx.repo_paths("r2"), vec!["/F1.txt"]);
+        assert_eq!(ctx.file_content("r2", "/F1.txt"), Some(b"f1".to_vec()));
+        assert_eq!(
+            ctx.repo_paths("r1"),
+            vec!["/D1", "/D1/A.txt", "/D1/B.txt", "/F1.txt"]
+        );
+    }
+
+    #[test]
+    fn test_transfer_files_cancel_aborts_upload() {
+        let (ctx, service) = setup();
+        ctx.add_file("r1", "/F1.txt", b"f1");
+
+        let service = Arc::new(service);
+        let cancel_service = service.clone();
+
+        intercept_uploads(&ctx, move |_| {
+            // the user cancels while F1.txt is being uploaded
+            cancel_service.cancel();
+
+            None
+        });
+
+        let res = transfer(&ctx, &service, &["/F1.txt"], RepoFilesMoveMode::Move);
+
+        assert!(matches!(res, Err(RepoFilesMoveError::Cancelled)));
+        assert_eq!(ctx.repo_paths("r2"), Vec::<String>::new());
+        assert_eq!(ctx.repo_paths("r1"), vec!["/F1.txt"]);
+    }
+}
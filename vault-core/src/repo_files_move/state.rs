@@ -1,13 +1,41 @@
+use crate::repo_files::state::RepoFile;
+
 #[derive(Clone)]
 pub enum RepoFilesMoveMode {
     Copy,
     Move,
 }
 
+/// Progress of copying or moving files into a different repo. Files are
+/// re-encrypted so the transfer can take a while.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RepoFilesMoveTransfer {
+    pub dest_repo_id: String,
+    pub total_count: usize,
+    pub done_count: usize,
+    pub total_bytes: i64,
+    pub done_bytes: i64,
+}
+
 #[derive(Clone)]
 pub struct RepoFilesMoveState {
     pub repo_id: String,
     pub src_file_ids: Vec<String>,
     pub mode: RepoFilesMoveMode,
     pub dir_picker_id: u32,
+    pub transfer: Option<RepoFilesMoveTransfer>,
+}
+
+/// A single dir or file that has to be created in the destination repo.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RepoFilesMoveTransferEntry {
+    pub file: RepoFile,
+    pub dest_path: String,
+}
+
+/// A selected file or dir with all entries that have to be created for it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RepoFilesMoveTransferItem {
+    pub dest_path: String,
+    pub entries: Vec<RepoFilesMoveTransferEntry>,
 }
@@ -1,7 +1,7 @@
 use thiserror::Error;
 
 use crate::{
-    cipher::errors::{DecryptFilenameError, DecryptSizeError},
+    cipher::errors::{DecryptFilenameError, DecryptHeaderError, DecryptSizeError},
     remote::RemoteError,
     repos::errors::{RepoLockedError, RepoNotFoundError},
     user_error::UserError,
@@ -17,10 +17,16 @@ pub enum GetFilesReaderError {
     FileNotFound,
     #[error("files empty")]
     FilesEmpty,
+    #[error("invalid range")]
+    InvalidRange,
     #[error("{0}")]
     DecryptFilenameError(#[from] DecryptFilenameError),
     #[error("{0}")]
     DecryptSizeError(#[from] DecryptSizeError),
     #[error("{0}")]
+    DecryptHeaderError(#[from] DecryptHeaderError),
+    #[error("{0}")]
     RemoteError(#[from] RemoteError),
+    #[error("failed to read file: {0}")]
+    ReadError(String),
 }
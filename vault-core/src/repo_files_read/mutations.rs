@@ -1,11 +1,14 @@
 use crate::{
     cipher::errors::DecryptFilenameError,
     remote::RemoteError,
-    repo_files::state::{RepoFile, RepoFileType},
+    repo_files::{
+        selectors::is_hidden_path,
+        state::{RepoFile, RepoFileType},
+    },
     repo_files_list::{errors::FilesListRecursiveItemError, state::RepoFilesListRecursiveItem},
 };
 
-use super::state::RemoteZipEntry;
+use super::state::RemoteArchiveEntry;
 
 pub fn zip_date_time_from_millis(millis: i64) -> async_zip_futures::ZipDateTime {
     // millis < 1980-01-01 00:00:00 UTC
@@ -21,10 +24,10 @@ pub fn zip_date_time_from_millis(millis: i64) -> async_zip_futures::ZipDateTime
     async_zip_futures::ZipDateTime::from_chrono(&modified)
 }
 
-pub fn list_recursive_items_to_remote_zip_entries(
+pub fn list_recursive_items_to_remote_archive_entries(
     items: Vec<RepoFilesListRecursiveItem>,
-) -> Result<Vec<RemoteZipEntry>, RemoteError> {
-    let mut entries: Vec<RemoteZipEntry> = Vec::with_capacity(items.len());
+) -> Result<Vec<RemoteArchiveEntry>, RemoteError> {
+    let mut entries: Vec<RemoteArchiveEntry> = Vec::with_capacity(items.len());
 
     for item in items {
         match item {
@@ -45,17 +48,22 @@ pub fn list_recursive_items_to_remote_zip_entries(
                     continue;
                 }
 
+                if matches!(file.decrypted_path(), Ok(path) if is_hidden_path(path)) {
+                    // skip trash and thumbnails cache
+                    continue;
+                }
+
                 let filename = match &file.typ {
                     RepoFileType::Dir => format!("{}/", &relative_repo_path[1..]),
                     RepoFileType::File => relative_repo_path[1..].to_owned(),
                 };
 
-                entries.push(RemoteZipEntry {
+                entries.push(RemoteArchiveEntry {
                     mount_id: file.mount_id.clone(),
                     remote_path: file.remote_path.clone(),
                     repo_id: file.repo_id.clone(),
                     filename,
-                    modified: zip_date_time_from_millis(file.modified),
+                    modified: file.modified,
                     typ: file.typ,
                 });
             }
@@ -77,13 +85,15 @@ pub fn list_recursive_items_to_remote_zip_entries(
     Ok(entries)
 }
 
-pub fn file_to_remote_zip_entry(file: &RepoFile) -> Result<RemoteZipEntry, DecryptFilenameError> {
-    Ok(RemoteZipEntry {
+pub fn file_to_remote_archive_entry(
+    file: &RepoFile,
+) -> Result<RemoteArchiveEntry, DecryptFilenameError> {
+    Ok(RemoteArchiveEntry {
         mount_id: file.mount_id.clone(),
         remote_path: file.remote_path.clone(),
         repo_id: file.repo_id.clone(),
         filename: file.decrypted_name().map(str::to_string)?,
-        modified: zip_date_time_from_millis(file.modified),
+        modified: file.modified,
         typ: file.typ.clone(),
     })
 }
@@ -102,11 +112,11 @@ mod tests {
             errors::FilesListRecursiveItemError, state::RepoFilesListRecursiveItem,
             test_helpers as repo_files_list_test_helpers,
         },
-        repo_files_read::state::RemoteZipEntry,
+        repo_files_read::state::RemoteArchiveEntry,
     };
 
     use super::{
-        file_to_remote_zip_entry, list_recursive_items_to_remote_zip_entries,
+        file_to_remote_archive_entry, list_recursive_items_to_remote_archive_entries,
         zip_date_time_from_millis,
     };
 
@@ -143,7 +153,7 @@ mod tests {
     }
 
     #[test]
-    fn test_list_recursive_items_to_remote_zip_entries() {
+    fn test_list_recursive_items_to_remote_archive_entries() {
         let cipher = cipher_test_helpers::create_cipher();
         let mut file_path_error = repo_files_list_test_helpers::create_list_recursive_item_file(
             "m1", "/Vault", "r1", "/D1", "/INVALID", &cipher,
@@ -160,7 +170,7 @@ mod tests {
             _ => {}
         };
         assert_eq!(
-            list_recursive_items_to_remote_zip_entries(vec![
+            list_recursive_items_to_remote_archive_entries(vec![
                 repo_files_list_test_helpers::create_list_recursive_item_file(
                     "m1", "/Vault", "r1", "/D1", "/", &cipher,
                 ),
@@ -186,7 +196,7 @@ mod tests {
             ])
             .unwrap(),
             vec![
-                RemoteZipEntry {
+                RemoteArchiveEntry {
                     mount_id: String::from("m1"),
                     remote_path: format!(
                         "/Vault/{}/{}",
@@ -195,10 +205,10 @@ mod tests {
                     ),
                     filename: String::from("F1"),
                     repo_id: String::from("r1"),
-                    modified: async_zip_futures::ZipDateTime::default(),
+                    modified: 1,
                     typ: RepoFileType::File,
                 },
-                RemoteZipEntry {
+                RemoteArchiveEntry {
                     mount_id: String::from("m1"),
                     remote_path: format!(
                         "/Vault/{}/{}",
@@ -207,10 +217,10 @@ mod tests {
                     ),
                     filename: String::from("D2/"),
                     repo_id: String::from("r1"),
-                    modified: async_zip_futures::ZipDateTime::default(),
+                    modified: 1,
                     typ: RepoFileType::Dir,
                 },
-                RemoteZipEntry {
+                RemoteArchiveEntry {
                     mount_id: String::from("m1"),
                     remote_path: format!(
                         "/Vault/{}/{}/{}",
@@ -220,7 +230,7 @@ mod tests {
                     ),
                     filename: String::from("D2/F2"),
                     repo_id: String::from("r1"),
-                    modified: async_zip_futures::ZipDateTime::default(),
+                    modified: 1,
                     typ: RepoFileType::File,
                 },
             ]
@@ -228,10 +238,10 @@ mod tests {
     }
 
     #[test]
-    fn test_list_recursive_items_to_remote_zip_entries_remote_error() {
+    fn test_list_recursive_items_to_remote_archive_entries_remote_error() {
         let cipher = cipher_test_helpers::create_cipher();
         assert_eq!(
-            list_recursive_items_to_remote_zip_entries(vec![
+            list_recursive_items_to_remote_archive_entries(vec![
                 repo_files_list_test_helpers::create_list_recursive_item_file(
                     "m1", "/Vault", "r1", "/D1", "/", &cipher,
                 ),
@@ -252,7 +262,47 @@ mod tests {
     }
 
     #[test]
-    fn test_file_to_remote_zip_entry() {
+    fn test_list_recursive_items_to_remote_archive_entries_hidden() {
+        let cipher = cipher_test_helpers::create_cipher();
+        let entries = list_recursive_items_to_remote_archive_entries(vec![
+            repo_files_list_test_helpers::create_list_recursive_item_dir(
+                "m1", "/Vault", "r1", "/", "/", &cipher,
+            ),
+            repo_files_list_test_helpers::create_list_recursive_item_dir(
+                "m1", "/Vault", "r1", "/", "/.trash", &cipher,
+            ),
+            repo_files_list_test_helpers::create_list_recursive_item_file(
+                "m1",
+                "/Vault",
+                "r1",
+                "/",
+                "/.trash/T1",
+                &cipher,
+            ),
+            repo_files_list_test_helpers::create_list_recursive_item_dir(
+                "m1",
+                "/Vault",
+                "r1",
+                "/",
+                "/.thumbnails",
+                &cipher,
+            ),
+            repo_files_list_test_helpers::create_list_recursive_item_file(
+                "m1", "/Vault", "r1", "/", "/F1", &cipher,
+            ),
+        ])
+        .unwrap();
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| entry.filename.as_str())
+                .collect::<Vec<_>>(),
+            vec!["F1"]
+        );
+    }
+
+    #[test]
+    fn test_file_to_remote_archive_entry() {
         let cipher = cipher_test_helpers::create_cipher();
         let remote_file = remote_files_test_helpers::create_file(
             "m1",
@@ -260,25 +310,25 @@ mod tests {
         );
         let file = decrypt_file("r1", "/", &remote_file, &cipher);
         assert_eq!(
-            file_to_remote_zip_entry(&file).unwrap(),
-            RemoteZipEntry {
+            file_to_remote_archive_entry(&file).unwrap(),
+            RemoteArchiveEntry {
                 mount_id: String::from("m1"),
                 remote_path: format!("/Vault/{}", cipher.encrypt_filename("F1")),
                 repo_id: String::from("r1"),
                 filename: String::from("F1"),
-                modified: async_zip_futures::ZipDateTime::default(),
+                modified: 1,
                 typ: RepoFileType::File,
             }
         )
     }
 
     #[test]
-    fn test_file_to_remote_zip_entry_decrypt_error() {
+    fn test_file_to_remote_archive_entry_decrypt_error() {
         let cipher = cipher_test_helpers::create_cipher();
         let remote_file = remote_files_test_helpers::create_file("m1", "/Vault/F1");
         let file = decrypt_file("r1", "/", &remote_file, &cipher);
         assert_eq!(
-            file_to_remote_zip_entry(&file).unwrap_err(),
+            file_to_remote_archive_entry(&file).unwrap_err(),
             DecryptFilenameError::DecodeError(String::from("non-zero trailing bits at 1",))
         )
     }
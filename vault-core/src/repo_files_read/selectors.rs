@@ -10,7 +10,19 @@ use crate::{
     utils::path_utils,
 };
 
+use super::state::RepoFilesArchiveFormat;
+
 pub fn select_files_zip_name(state: &store::State, files: &[RepoFile]) -> String {
+    select_files_archive_name(state, files, RepoFilesArchiveFormat::Zip)
+}
+
+pub fn select_files_archive_name(
+    state: &store::State,
+    files: &[RepoFile],
+    format: RepoFilesArchiveFormat,
+) -> String {
+    let ext = format.ext();
+
     let files_len = files.len();
 
     let file_ids_set = files
@@ -49,14 +61,14 @@ pub fn select_files_zip_name(state: &store::State, files: &[RepoFile]) -> String
             .unwrap_or(false);
 
         match (parent_name, is_all_children) {
-            (Some(parent_name), true) => format!("{}.zip", parent_name),
+            (Some(parent_name), true) => format!("{}.{}", parent_name, ext),
             (Some(parent_name), false) => {
-                format!("{}-{}-selected-items.zip", parent_name, files_len)
+                format!("{}-{}-selected-items.{}", parent_name, files_len, ext)
             }
-            (None, _) => format!("{}-selected-items.zip", files_len),
+            (None, _) => format!("{}-selected-items.{}", files_len, ext),
         }
     } else {
-        format!("{}-selected-items.zip", files_len)
+        format!("{}-selected-items.{}", files_len, ext)
     }
 }
 
@@ -71,7 +83,9 @@ mod tests {
         store,
     };
 
-    use super::select_files_zip_name;
+    use super::{
+        super::state::RepoFilesArchiveFormat, select_files_archive_name, select_files_zip_name,
+    };
 
     #[test]
     fn test_select_files_zip_name() {
@@ -126,5 +140,13 @@ mod tests {
             select_files_zip_name(&state, &[d1.clone(), f1.clone(), f2.clone(), f3.clone()]),
             "4-selected-items.zip"
         );
+        assert_eq!(
+            select_files_archive_name(
+                &state,
+                &[d1.clone(), f1.clone(), f2.clone()],
+                RepoFilesArchiveFormat::TarGz
+            ),
+            "Vault.tar.gz"
+        );
     }
 }
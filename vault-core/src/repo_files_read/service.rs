@@ -1,13 +1,22 @@
 use std::{collections::HashMap, pin::Pin, sync::Arc};
 
+use async_compression::futures::write::GzipEncoder;
 use futures::{
-    channel::mpsc,
+    channel::{mpsc, oneshot},
+    future::Shared,
     io::{self, BufReader},
-    AsyncRead, AsyncWrite, SinkExt, StreamExt, TryStreamExt,
+    AsyncRead, AsyncReadExt, AsyncWrite, SinkExt, StreamExt, TryStreamExt,
 };
 
 use crate::{
-    cipher::{data_cipher::decrypt_size, Cipher},
+    cipher::{
+        constants::FILE_HEADER_SIZE,
+        data_cipher::{decrypt_header, decrypt_size, encrypted_range},
+        nonce::Nonce,
+        Cipher,
+    },
+    http::HttpError,
+    remote::{RemoteError, RemoteFileRange},
     remote_files::RemoteFilesService,
     repo_files::{
         selectors as repo_files_selectors,
@@ -18,13 +27,15 @@ use crate::{
     },
     repos::ReposService,
     runtime, store,
-    utils::{path_utils, sender_writer::SenderWriter},
+    utils::{
+        abort_reader::AbortReader, path_utils, sender_writer::SenderWriter, tar_writer::TarWriter,
+    },
 };
 
 use super::{
     errors::GetFilesReaderError,
     mutations, selectors,
-    state::{RemoteZipEntry, RepoFileReader},
+    state::{RemoteArchiveEntry, RepoFileRange, RepoFileReader, RepoFilesArchiveFormat},
 };
 
 pub struct RepoFilesReadService {
@@ -52,32 +63,188 @@ impl RepoFilesReadService {
         }
     }
 
+    /// Reads fail once the repo is locked. Every read touches the repo so that
+    /// long downloads and transfers do not get auto locked.
+    fn get_lock_abort_reader<R>(
+        &self,
+        repo_id: &str,
+        reader: R,
+    ) -> AbortReader<R, Shared<oneshot::Receiver<()>>> {
+        let repos_service = self.repos_service.clone();
+        let touch_repo_id = repo_id.to_owned();
+
+        AbortReader::new(reader, self.repos_service.get_lock_abort(repo_id)).with_on_read(Box::new(
+            move || {
+                repos_service.touch_repo(&touch_repo_id);
+            },
+        ))
+    }
+
     async fn get_remote_file_reader(
         &self,
+        repo_id: &str,
         mount_id: &str,
         remote_path: &str,
         name: &str,
         content_type: Option<&str>,
         cipher: &Cipher,
     ) -> Result<RepoFileReader, GetFilesReaderError> {
+        self.repos_service.touch_repo(repo_id);
+
         let encrypted_reader = self
             .remote_files_service
-            .get_file_reader(&mount_id, &remote_path)
+            .get_file_reader(&mount_id, &remote_path, None)
             .await?;
 
         let size = decrypt_size(encrypted_reader.size.try_into().unwrap())
             .map_err(GetFilesReaderError::DecryptSizeError)?;
 
-        let decrypt_reader = Box::pin(cipher.decrypt_reader(encrypted_reader.reader));
+        let decrypt_reader = cipher.decrypt_reader(encrypted_reader.reader);
 
         Ok(RepoFileReader {
             name: name.to_owned(),
             size: Some(size),
             content_type: content_type.map(str::to_string),
-            reader: decrypt_reader,
+            content_range: None,
+            reader: Box::pin(self.get_lock_abort_reader(repo_id, decrypt_reader)),
         })
     }
 
+    async fn get_remote_file_nonce(
+        &self,
+        mount_id: &str,
+        remote_path: &str,
+    ) -> Result<Nonce, GetFilesReaderError> {
+        let header_reader = self
+            .remote_files_service
+            .get_file_reader(
+                &mount_id,
+                &remote_path,
+                Some(RemoteFileRange {
+                    start: 0,
+                    end: Some(FILE_HEADER_SIZE as i64 - 1),
+                }),
+            )
+            .await?;
+
+        let mut header = Vec::with_capacity(FILE_HEADER_SIZE);
+
+        header_reader
+            .reader
+            .take(FILE_HEADER_SIZE as u64)
+            .read_to_end(&mut header)
+            .await
+            .map_err(|err| GetFilesReaderError::ReadError(err.to_string()))?;
+
+        Ok(decrypt_header(&header)?)
+    }
+
+    async fn get_remote_file_reader_range(
+        &self,
+        repo_id: &str,
+        mount_id: &str,
+        remote_path: &str,
+        name: &str,
+        content_type: Option<&str>,
+        cipher: &Cipher,
+        size: i64,
+        range: &RepoFileRange,
+    ) -> Result<RepoFileReader, GetFilesReaderError> {
+        self.repos_service.touch_repo(repo_id);
+
+        let content_range = range
+            .content_range(size)
+            .ok_or(GetFilesReaderError::InvalidRange)?;
+
+        let encrypted_range = encrypted_range(content_range.start, Some(content_range.length()));
+
+        // the nonce for blocks after the first one is derived from the file
+        // header so we have to fetch it separately
+        let nonce = if encrypted_range.includes_header() {
+            None
+        } else {
+            Some(self.get_remote_file_nonce(mount_id, remote_path).await?)
+        };
+
+        let encrypted_reader = self
+            .remote_files_service
+            .get_file_reader(
+                &mount_id,
+                &remote_path,
+                Some(RemoteFileRange {
+                    start: encrypted_range.offset,
+                    end: encrypted_range
+                        .length
+                        .map(|length| encrypted_range.offset + length - 1),
+                }),
+            )
+            .await?;
+
+        // the nonce of a block depends on its position, so a range that starts
+        // anywhere else would decrypt to garbage
+        if let Some(remote_content_range) = &encrypted_reader.content_range {
+            if remote_content_range.start != encrypted_range.offset {
+                return Err(GetFilesReaderError::RemoteError(RemoteError::HttpError(
+                    HttpError::ResponseError(format!(
+                        "unexpected content range start {}, expected {}",
+                        remote_content_range.start, encrypted_range.offset
+                    )),
+                )));
+            }
+        }
+
+        let decrypt_reader = match (encrypted_reader.content_range, nonce) {
+            (Some(_), Some(nonce)) => cipher
+                .decrypt_reader_at_block(
+                    encrypted_reader.reader,
+                    nonce,
+                    encrypted_range.block_index,
+                )
+                .with_skip(encrypted_range.skip),
+            (Some(_), None) => cipher
+                .decrypt_reader(encrypted_reader.reader)
+                .with_skip(encrypted_range.skip),
+            // server ignored the range and returned the whole file
+            (None, _) => cipher
+                .decrypt_reader(encrypted_reader.reader)
+                .with_skip(content_range.start as u64),
+        };
+
+        Ok(RepoFileReader {
+            name: name.to_owned(),
+            size: Some(content_range.length()),
+            content_type: content_type.map(str::to_string),
+            reader: Box::pin(self.get_lock_abort_reader(
+                repo_id,
+                decrypt_reader.take(content_range.length() as u64),
+            )),
+            content_range: Some(content_range),
+        })
+    }
+
+    pub async fn get_file_reader_range(
+        &self,
+        file: &RepoFile,
+        range: &RepoFileRange,
+    ) -> Result<RepoFileReader, GetFilesReaderError> {
+        let name = file.decrypted_name()?;
+        let size = file.decrypted_size()?;
+
+        let cipher = self.repos_service.get_cipher(&file.repo_id)?;
+
+        self.get_remote_file_reader_range(
+            &file.repo_id,
+            &file.mount_id,
+            &file.remote_path,
+            name,
+            file.content_type.as_deref(),
+            &cipher,
+            size,
+            range,
+        )
+        .await
+    }
+
     async fn get_file_reader_file(
         &self,
         file: &RepoFile,
@@ -87,6 +254,7 @@ impl RepoFilesReadService {
         let cipher = self.repos_service.get_cipher(&file.repo_id)?;
 
         self.get_remote_file_reader(
+            &file.repo_id,
             &file.mount_id,
             &file.remote_path,
             name,
@@ -99,7 +267,7 @@ impl RepoFilesReadService {
     async fn create_zip<W: AsyncWrite + Unpin>(
         &self,
         writer: W,
-        entries: Vec<RemoteZipEntry>,
+        entries: Vec<RemoteArchiveEntry>,
     ) -> Result<(), std::io::Error> {
         let mut zip_writer = async_zip_futures::write::ZipFileWriter::new(writer);
 
@@ -110,7 +278,7 @@ impl RepoFilesReadService {
                         entry.filename,
                         async_zip_futures::Compression::Stored,
                     )
-                    .last_modification_date(entry.modified)
+                    .last_modification_date(mutations::zip_date_time_from_millis(entry.modified))
                     .unix_permissions(0o755);
 
                     zip_writer
@@ -123,7 +291,7 @@ impl RepoFilesReadService {
                         entry.filename,
                         async_zip_futures::Compression::Stored,
                     )
-                    .last_modification_date(entry.modified)
+                    .last_modification_date(mutations::zip_date_time_from_millis(entry.modified))
                     .unix_permissions(0o644);
 
                     let cipher = self
@@ -133,6 +301,7 @@ impl RepoFilesReadService {
 
                     let reader = self
                         .get_remote_file_reader(
+                            &entry.repo_id,
                             &entry.mount_id,
                             &entry.remote_path,
                             "",
@@ -170,19 +339,85 @@ impl RepoFilesReadService {
         Ok(())
     }
 
-    fn get_zip_reader(
+    async fn create_tar<W: AsyncWrite + Unpin>(
+        &self,
+        writer: W,
+        entries: Vec<RemoteArchiveEntry>,
+    ) -> Result<(), std::io::Error> {
+        let mut tar_writer = TarWriter::new(writer);
+
+        for entry in entries {
+            let mtime = (entry.modified.max(0) / 1000) as u64;
+
+            match &entry.typ {
+                RepoFileType::Dir => {
+                    tar_writer.write_dir(&entry.filename, mtime).await?;
+                }
+                RepoFileType::File => {
+                    let cipher = self
+                        .repos_service
+                        .get_cipher(&entry.repo_id)
+                        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+
+                    let reader = self
+                        .get_remote_file_reader(
+                            &entry.repo_id,
+                            &entry.mount_id,
+                            &entry.remote_path,
+                            "",
+                            None,
+                            &cipher,
+                        )
+                        .await
+                        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+
+                    // tar headers need the size before the content
+                    let size = reader.size.unwrap_or(0) as u64;
+
+                    tar_writer
+                        .write_file(
+                            &entry.filename,
+                            size,
+                            mtime,
+                            BufReader::with_capacity(1024 * 1024, reader.reader),
+                        )
+                        .await?;
+                }
+            }
+        }
+
+        tar_writer.close().await
+    }
+
+    async fn create_archive(
+        &self,
+        writer: SenderWriter,
+        entries: Vec<RemoteArchiveEntry>,
+        format: RepoFilesArchiveFormat,
+    ) -> Result<(), std::io::Error> {
+        match format {
+            RepoFilesArchiveFormat::Zip => self.create_zip(writer, entries).await,
+            RepoFilesArchiveFormat::Tar => self.create_tar(writer, entries).await,
+            RepoFilesArchiveFormat::TarGz => {
+                self.create_tar(GzipEncoder::new(writer), entries).await
+            }
+        }
+    }
+
+    fn get_archive_reader(
         self: Arc<Self>,
-        entries: Vec<RemoteZipEntry>,
+        entries: Vec<RemoteArchiveEntry>,
+        format: RepoFilesArchiveFormat,
     ) -> Pin<Box<dyn AsyncRead + Send + Sync + 'static>> {
         let (tx, rx) = mpsc::channel::<std::io::Result<Vec<u8>>>(10);
 
         let mut error_tx = tx.clone();
 
-        let create_zip_self = self.clone();
+        let create_archive_self = self.clone();
 
         self.runtime.spawn(Box::pin(async move {
-            match create_zip_self
-                .create_zip(SenderWriter::new(tx), entries)
+            match create_archive_self
+                .create_archive(SenderWriter::new(tx), entries, format)
                 .await
             {
                 Ok(_) => {}
@@ -195,11 +430,11 @@ impl RepoFilesReadService {
         Box::pin(BufReader::with_capacity(1024 * 1024, rx.into_async_read()))
     }
 
-    async fn get_file_remote_zip_entries(
+    async fn get_file_remote_archive_entries(
         &self,
         file: &RepoFile,
         dir_path_prefix: Option<String>,
-    ) -> Result<Vec<RemoteZipEntry>, GetFilesReaderError> {
+    ) -> Result<Vec<RemoteArchiveEntry>, GetFilesReaderError> {
         match file.typ {
             RepoFileType::Dir => {
                 let mut items = self
@@ -241,31 +476,33 @@ impl RepoFilesReadService {
                     }
                 }
 
-                Ok(mutations::list_recursive_items_to_remote_zip_entries(
+                Ok(mutations::list_recursive_items_to_remote_archive_entries(
                     items,
                 )?)
             }
-            RepoFileType::File => Ok(vec![mutations::file_to_remote_zip_entry(file)?]),
+            RepoFileType::File => Ok(vec![mutations::file_to_remote_archive_entry(file)?]),
         }
     }
 
-    async fn get_dir_zip_name_entries(
+    async fn get_dir_archive_name_entries(
         &self,
         file: &RepoFile,
-    ) -> Result<(String, Vec<RemoteZipEntry>), GetFilesReaderError> {
-        let zip_name = format!("{}.zip", file.decrypted_name()?);
+        format: RepoFilesArchiveFormat,
+    ) -> Result<(String, Vec<RemoteArchiveEntry>), GetFilesReaderError> {
+        let archive_name = format!("{}.{}", file.decrypted_name()?, format.ext());
 
-        let remote_zip_entries = self.get_file_remote_zip_entries(file, None).await?;
+        let remote_archive_entries = self.get_file_remote_archive_entries(file, None).await?;
 
-        Ok((zip_name, remote_zip_entries))
+        Ok((archive_name, remote_archive_entries))
     }
 
-    async fn get_files_zip_name_entries(
+    async fn get_files_archive_name_entries(
         &self,
         files: &[RepoFile],
-    ) -> Result<(String, Vec<RemoteZipEntry>), GetFilesReaderError> {
-        let (zip_name, file_names) = self.store.with_state(|state| {
-            let zip_name = selectors::select_files_zip_name(state, files);
+        format: RepoFilesArchiveFormat,
+    ) -> Result<(String, Vec<RemoteArchiveEntry>), GetFilesReaderError> {
+        let (archive_name, file_names) = self.store.with_state(|state| {
+            let archive_name = selectors::select_files_archive_name(state, files, format);
 
             let file_names = files
                 .iter()
@@ -275,10 +512,10 @@ impl RepoFilesReadService {
                 })
                 .collect::<HashMap<String, String>>();
 
-            (zip_name, file_names)
+            (archive_name, file_names)
         });
 
-        let mut remote_zip_entries = Vec::new();
+        let mut remote_archive_entries = Vec::new();
 
         for file in files {
             let file_name = match file_names.get(&file.id).cloned() {
@@ -294,41 +531,53 @@ impl RepoFilesReadService {
                 RepoFileType::File => None,
             };
 
-            remote_zip_entries.extend(
-                self.get_file_remote_zip_entries(file, dir_path_prefix)
+            remote_archive_entries.extend(
+                self.get_file_remote_archive_entries(file, dir_path_prefix)
                     .await?
                     .into_iter(),
             );
         }
 
-        Ok((zip_name, remote_zip_entries))
+        Ok((archive_name, remote_archive_entries))
     }
 
     pub async fn get_files_reader(
         self: Arc<Self>,
         files: &[RepoFile],
     ) -> Result<RepoFileReader, GetFilesReaderError> {
-        let (name, remote_zip_entries) = match files.len() {
+        self.get_files_reader_with_format(files, RepoFilesArchiveFormat::default())
+            .await
+    }
+
+    /// Returns the reader of a single file or an archive of the given format
+    /// for dirs and multiple files.
+    pub async fn get_files_reader_with_format(
+        self: Arc<Self>,
+        files: &[RepoFile],
+        format: RepoFilesArchiveFormat,
+    ) -> Result<RepoFileReader, GetFilesReaderError> {
+        let (name, remote_archive_entries) = match files.len() {
             0 => panic!("files cannot be empty"),
             1 => {
                 let file = files.get(0).unwrap();
 
                 match file.typ {
-                    RepoFileType::Dir => self.get_dir_zip_name_entries(file).await?,
+                    RepoFileType::Dir => self.get_dir_archive_name_entries(file, format).await?,
                     RepoFileType::File => {
                         return self.get_file_reader_file(file).await;
                     }
                 }
             }
-            _ => self.get_files_zip_name_entries(files).await?,
+            _ => self.get_files_archive_name_entries(files, format).await?,
         };
 
-        let reader = self.get_zip_reader(remote_zip_entries);
+        let reader = self.get_archive_reader(remote_archive_entries, format);
 
         Ok(RepoFileReader {
             name,
             size: None,
-            content_type: Some(String::from("application/zip")),
+            content_type: Some(format.content_type().to_owned()),
+            content_range: None,
             reader,
         })
     }
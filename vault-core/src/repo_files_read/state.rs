@@ -1,23 +1,140 @@
-use std::pin::Pin;
+use std::{cmp, pin::Pin};
 
 use futures::AsyncRead;
 
 use crate::repo_files::state::RepoFileType;
 
+/// Plaintext byte range. end is inclusive (same as HTTP Range header), None
+/// means until the end of the file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepoFileRange {
+    pub start: i64,
+    pub end: Option<i64>,
+}
+
+impl RepoFileRange {
+    /// Resolves the range for a file of the given size. Returns None if the
+    /// range cannot be satisfied.
+    pub fn content_range(&self, size: i64) -> Option<RepoFileContentRange> {
+        if self.start < 0 || self.start >= size {
+            return None;
+        }
+
+        let end = match self.end {
+            Some(end) if end < self.start => return None,
+            Some(end) => cmp::min(end, size - 1),
+            None => size - 1,
+        };
+
+        Some(RepoFileContentRange {
+            start: self.start,
+            end,
+            size,
+        })
+    }
+}
+
+/// Resolved plaintext byte range. end is inclusive, size is the size of the
+/// whole file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepoFileContentRange {
+    pub start: i64,
+    pub end: i64,
+    pub size: i64,
+}
+
+impl RepoFileContentRange {
+    pub fn length(&self) -> i64 {
+        self.end - self.start + 1
+    }
+}
+
 pub struct RepoFileReader {
     pub name: String,
     pub size: Option<i64>,
     pub content_type: Option<String>,
+    pub content_range: Option<RepoFileContentRange>,
     pub reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepoFilesArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl Default for RepoFilesArchiveFormat {
+    fn default() -> Self {
+        Self::Zip
+    }
+}
+
+impl RepoFilesArchiveFormat {
+    pub fn ext(&self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::Tar => "tar",
+            Self::TarGz => "tar.gz",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Zip => "application/zip",
+            Self::Tar => "application/x-tar",
+            Self::TarGz => "application/gzip",
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct RemoteZipEntry {
+pub struct RemoteArchiveEntry {
     pub mount_id: String,
     pub remote_path: String,
     pub repo_id: String,
     // relative path without leading / (dirs end with /)
     pub filename: String,
-    pub modified: async_zip_futures::ZipDateTime,
+    // unix millis, the original modification time if it was set on upload
+    pub modified: i64,
     pub typ: RepoFileType,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{RepoFileContentRange, RepoFileRange};
+
+    #[test]
+    fn test_repo_file_range_content_range() {
+        let range = |start: i64, end: Option<i64>| RepoFileRange { start, end };
+
+        assert_eq!(
+            range(0, None).content_range(100),
+            Some(RepoFileContentRange {
+                start: 0,
+                end: 99,
+                size: 100
+            })
+        );
+        assert_eq!(
+            range(10, Some(19)).content_range(100),
+            Some(RepoFileContentRange {
+                start: 10,
+                end: 19,
+                size: 100
+            })
+        );
+        assert_eq!(
+            range(90, Some(199)).content_range(100),
+            Some(RepoFileContentRange {
+                start: 90,
+                end: 99,
+                size: 100
+            })
+        );
+        assert_eq!(range(100, None).content_range(100), None);
+        assert_eq!(range(20, Some(10)).content_range(100), None);
+        assert_eq!(range(-1, None).content_range(100), None);
+        assert_eq!(range(0, None).content_range(0), None);
+    }
+}
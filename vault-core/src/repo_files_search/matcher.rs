@@ -0,0 +1,218 @@
+use regex::{Regex, RegexBuilder};
+
+use crate::repo_files::state::{RepoFile, RepoFileType};
+
+use super::{
+    errors::RepoFilesSearchError,
+    state::{RepoFilesSearchFileType, RepoFilesSearchMatchType, RepoFilesSearchQuery},
+};
+
+pub fn glob_to_regex(glob: &str) -> String {
+    let mut pattern = String::with_capacity(glob.len() + 2);
+
+    pattern.push('^');
+
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    pattern.push('$');
+
+    pattern
+}
+
+/// Compiled query that is matched against decrypted files.
+pub struct RepoFilesSearchMatcher {
+    query: RepoFilesSearchQuery,
+    regex: Option<Regex>,
+}
+
+impl RepoFilesSearchMatcher {
+    pub fn new(query: RepoFilesSearchQuery) -> Result<Self, RepoFilesSearchError> {
+        let regex = if query.text.is_empty() {
+            None
+        } else {
+            let (pattern, case_insensitive) = match query.match_type {
+                RepoFilesSearchMatchType::Substring => (regex::escape(&query.text), true),
+                RepoFilesSearchMatchType::Glob => (glob_to_regex(&query.text), true),
+                RepoFilesSearchMatchType::Regex => (query.text.clone(), false),
+            };
+
+            Some(
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(case_insensitive)
+                    .build()
+                    .map_err(|err| RepoFilesSearchError::InvalidPattern(err.to_string()))?,
+            )
+        };
+
+        Ok(Self { query, regex })
+    }
+
+    pub fn matches(&self, file: &RepoFile) -> bool {
+        let name = match file.decrypted_name() {
+            Ok(name) => name,
+            Err(_) => return false,
+        };
+
+        if let Some(regex) = &self.regex {
+            if !regex.is_match(name) {
+                return false;
+            }
+        }
+
+        match (self.query.file_type, &file.typ) {
+            (RepoFilesSearchFileType::File, RepoFileType::Dir)
+            | (RepoFilesSearchFileType::Dir, RepoFileType::File) => return false,
+            _ => {}
+        }
+
+        if self.query.min_size.is_some() || self.query.max_size.is_some() {
+            // dirs have no size
+            let size = match (&file.typ, file.decrypted_size()) {
+                (RepoFileType::File, Ok(size)) => size,
+                _ => return false,
+            };
+
+            if matches!(self.query.min_size, Some(min_size) if size < min_size)
+                || matches!(self.query.max_size, Some(max_size) if size > max_size)
+            {
+                return false;
+            }
+        }
+
+        if matches!(self.query.modified_after, Some(after) if file.modified < after)
+            || matches!(self.query.modified_before, Some(before) if file.modified > before)
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cipher::test_helpers::create_cipher,
+        repo_files::state::{RepoFile, RepoFileSize},
+        repo_files_list::{
+            state::RepoFilesListRecursiveItem,
+            test_helpers::{create_list_recursive_item_dir, create_list_recursive_item_file},
+        },
+    };
+
+    use super::{
+        super::{
+            errors::RepoFilesSearchError,
+            state::{RepoFilesSearchFileType, RepoFilesSearchMatchType, RepoFilesSearchQuery},
+        },
+        glob_to_regex, RepoFilesSearchMatcher,
+    };
+
+    fn item_file(item: RepoFilesListRecursiveItem) -> RepoFile {
+        match item {
+            RepoFilesListRecursiveItem::File { file, .. } => file,
+            RepoFilesListRecursiveItem::Error { .. } => panic!("expected file"),
+        }
+    }
+
+    fn create_file(path: &str, size: i64, modified: i64) -> RepoFile {
+        let cipher = create_cipher();
+
+        let mut file = item_file(create_list_recursive_item_file(
+            "m1", "/Vault", "r1", "/", path, &cipher,
+        ));
+        file.size = RepoFileSize::Decrypted { size };
+        file.modified = modified;
+        file
+    }
+
+    fn create_dir(path: &str) -> RepoFile {
+        let cipher = create_cipher();
+
+        item_file(create_list_recursive_item_dir(
+            "m1", "/Vault", "r1", "/", path, &cipher,
+        ))
+    }
+
+    fn query(text: &str, match_type: RepoFilesSearchMatchType) -> RepoFilesSearchQuery {
+        RepoFilesSearchQuery {
+            text: text.to_owned(),
+            match_type,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_glob_to_regex() {
+        assert_eq!(glob_to_regex("*.jp?g"), r"^.*\.jp.g$");
+    }
+
+    #[test]
+    fn test_matches_name() {
+        let file = create_file("/Photos/Holiday 2022.JPG", 10, 0);
+
+        let matcher =
+            RepoFilesSearchMatcher::new(query("holiday", RepoFilesSearchMatchType::Substring))
+                .unwrap();
+        assert!(matcher.matches(&file));
+
+        let matcher =
+            RepoFilesSearchMatcher::new(query("*.jpg", RepoFilesSearchMatchType::Glob)).unwrap();
+        assert!(matcher.matches(&file));
+
+        let matcher =
+            RepoFilesSearchMatcher::new(query("*.png", RepoFilesSearchMatchType::Glob)).unwrap();
+        assert!(!matcher.matches(&file));
+
+        let matcher =
+            RepoFilesSearchMatcher::new(query(r"^Holiday \d+", RepoFilesSearchMatchType::Regex))
+                .unwrap();
+        assert!(matcher.matches(&file));
+
+        let matcher =
+            RepoFilesSearchMatcher::new(query(r"^holiday", RepoFilesSearchMatchType::Regex))
+                .unwrap();
+        assert!(!matcher.matches(&file));
+
+        assert!(matches!(
+            RepoFilesSearchMatcher::new(query("(", RepoFilesSearchMatchType::Regex)),
+            Err(RepoFilesSearchError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_matches_filters() {
+        let file = create_file("/F1.txt", 100, 5000);
+        let dir = create_dir("/D1");
+
+        let matcher = RepoFilesSearchMatcher::new(RepoFilesSearchQuery {
+            file_type: RepoFilesSearchFileType::Dir,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!matcher.matches(&file));
+        assert!(matcher.matches(&dir));
+
+        let matcher = RepoFilesSearchMatcher::new(RepoFilesSearchQuery {
+            min_size: Some(50),
+            max_size: Some(100),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(matcher.matches(&file));
+        assert!(!matcher.matches(&dir));
+
+        let matcher = RepoFilesSearchMatcher::new(RepoFilesSearchQuery {
+            modified_after: Some(6000),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!matcher.matches(&file));
+    }
+}
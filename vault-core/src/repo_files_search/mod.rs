@@ -0,0 +1,8 @@
+pub mod errors;
+pub mod matcher;
+pub mod mutations;
+pub mod selectors;
+pub mod service;
+pub mod state;
+
+pub use self::service::RepoFilesSearchService;
@@ -0,0 +1,216 @@
+use crate::{
+    common::state::Status,
+    repo_files::{selectors::is_hidden_path, state::RepoFile},
+    repo_files_list::state::RepoFilesListRecursiveItem,
+    store,
+};
+
+use super::{
+    errors::RepoFilesSearchError,
+    matcher::RepoFilesSearchMatcher,
+    state::{RepoFilesSearch, RepoFilesSearchQuery},
+};
+
+pub struct RepoFilesSearchChunk {
+    pub scanned_count: usize,
+    pub skipped_count: usize,
+    pub results: Vec<RepoFile>,
+}
+
+pub fn create(
+    state: &mut store::State,
+    repo_id: &str,
+    path: &str,
+    query: RepoFilesSearchQuery,
+    error: Option<RepoFilesSearchError>,
+) -> u32 {
+    let search_id = state.repo_files_search.next_id;
+
+    state.repo_files_search.next_id += 1;
+
+    state.repo_files_search.searches.insert(
+        search_id,
+        RepoFilesSearch {
+            id: search_id,
+            repo_id: repo_id.to_owned(),
+            path: path.to_owned(),
+            query,
+            status: match error {
+                Some(error) => Status::Error { error },
+                None => Status::Loading,
+            },
+            is_cancelled: false,
+            results: Vec::new(),
+            scanned_count: 0,
+            skipped_count: 0,
+        },
+    );
+
+    search_id
+}
+
+/// Matches a chunk of recursive list items. The searched dir itself and the
+/// hidden dirs (trash, thumbnails cache) are not part of the results.
+pub fn match_items(
+    items: Vec<RepoFilesListRecursiveItem>,
+    matcher: &RepoFilesSearchMatcher,
+) -> RepoFilesSearchChunk {
+    let mut chunk = RepoFilesSearchChunk {
+        scanned_count: 0,
+        skipped_count: 0,
+        results: Vec::new(),
+    };
+
+    for item in items {
+        match item {
+            RepoFilesListRecursiveItem::File {
+                relative_repo_path: Ok(relative_repo_path),
+                file,
+            } => {
+                if relative_repo_path == "/" {
+                    continue;
+                }
+
+                if matches!(file.decrypted_path(), Ok(path) if is_hidden_path(path)) {
+                    continue;
+                }
+
+                chunk.scanned_count += 1;
+
+                if matcher.matches(&file) {
+                    chunk.results.push(file);
+                }
+            }
+            _ => {
+                chunk.skipped_count += 1;
+            }
+        }
+    }
+
+    chunk
+}
+
+/// Matches a chunk of files read from the repo files index.
+pub fn match_files(files: Vec<RepoFile>, matcher: &RepoFilesSearchMatcher) -> RepoFilesSearchChunk {
+    RepoFilesSearchChunk {
+        scanned_count: files.len(),
+        skipped_count: 0,
+        results: files
+            .into_iter()
+            .filter(|file| matcher.matches(file))
+            .collect(),
+    }
+}
+
+pub fn chunk_matched(state: &mut store::State, search_id: u32, chunk: RepoFilesSearchChunk) {
+    if let Some(search) = state.repo_files_search.searches.get_mut(&search_id) {
+        if search.is_cancelled {
+            return;
+        }
+
+        search.scanned_count += chunk.scanned_count;
+        search.skipped_count += chunk.skipped_count;
+        search.results.extend(chunk.results);
+    }
+}
+
+pub fn finished(state: &mut store::State, search_id: u32, res: &Result<(), RepoFilesSearchError>) {
+    if let Some(search) = state.repo_files_search.searches.get_mut(&search_id) {
+        search.status = match res {
+            Ok(()) => Status::Loaded,
+            Err(error) => Status::Error {
+                error: error.clone(),
+            },
+        };
+    }
+}
+
+/// Stops the search and keeps the results found so far.
+pub fn cancel(state: &mut store::State, search_id: u32) {
+    if let Some(search) = state.repo_files_search.searches.get_mut(&search_id) {
+        search.is_cancelled = true;
+
+        if matches!(search.status, Status::Loading) {
+            search.status = Status::Loaded;
+        }
+    }
+}
+
+pub fn destroy(state: &mut store::State, search_id: u32) {
+    state.repo_files_search.searches.remove(&search_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cipher::{errors::DecryptFilenameError, test_helpers::create_cipher},
+        repo_files_list::{
+            errors::FilesListRecursiveItemError,
+            state::RepoFilesListRecursiveItem,
+            test_helpers::{create_list_recursive_item_dir, create_list_recursive_item_file},
+        },
+    };
+
+    use super::{
+        super::{
+            matcher::RepoFilesSearchMatcher,
+            state::{RepoFilesSearchMatchType, RepoFilesSearchQuery},
+        },
+        match_items,
+    };
+
+    #[test]
+    fn test_match_items() {
+        let cipher = create_cipher();
+
+        let matcher = RepoFilesSearchMatcher::new(RepoFilesSearchQuery {
+            text: String::from("report"),
+            match_type: RepoFilesSearchMatchType::Substring,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let chunk = match_items(
+            vec![
+                create_list_recursive_item_dir("m1", "/Vault", "r1", "/", "/", &cipher),
+                create_list_recursive_item_dir("m1", "/Vault", "r1", "/", "/Reports", &cipher),
+                create_list_recursive_item_file(
+                    "m1",
+                    "/Vault",
+                    "r1",
+                    "/",
+                    "/Reports/Report 1.pdf",
+                    &cipher,
+                ),
+                create_list_recursive_item_file("m1", "/Vault", "r1", "/", "/Notes.txt", &cipher),
+                create_list_recursive_item_file(
+                    "m1",
+                    "/Vault",
+                    "r1",
+                    "/",
+                    "/.trash/1-abc/Report 2.pdf",
+                    &cipher,
+                ),
+                RepoFilesListRecursiveItem::Error {
+                    mount_id: String::from("m1"),
+                    remote_path: Some(String::from("/Vault/xxx")),
+                    error: FilesListRecursiveItemError::DecryptFilenameError(
+                        DecryptFilenameError::DecryptError,
+                    ),
+                },
+            ],
+            &matcher,
+        );
+
+        assert_eq!(chunk.scanned_count, 3);
+        assert_eq!(chunk.skipped_count, 1);
+        assert_eq!(
+            chunk
+                .results
+                .iter()
+                .map(|file| file.decrypted_path().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["/Reports", "/Reports/Report 1.pdf"]
+        );
+    }
+}
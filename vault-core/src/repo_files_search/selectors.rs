@@ -0,0 +1,54 @@
+use crate::{repo_files::state::RepoFile, store, utils::path_utils};
+
+use super::state::RepoFilesSearch;
+
+pub fn select_search<'a>(state: &'a store::State, search_id: u32) -> Option<&'a RepoFilesSearch> {
+    state.repo_files_search.searches.get(&search_id)
+}
+
+pub fn select_is_searching(state: &store::State, search_id: u32) -> bool {
+    select_search(state, search_id)
+        .map(|search| !search.is_cancelled)
+        .unwrap_or(false)
+}
+
+pub fn select_result<'a>(
+    state: &'a store::State,
+    search_id: u32,
+    file_id: &str,
+) -> Option<&'a RepoFile> {
+    select_search(state, search_id)
+        .and_then(|search| search.results.iter().find(|file| file.id == file_id))
+}
+
+/// Returns the repo id and the path to open a result in a browser. Files
+/// are shown in their parent dir.
+pub fn select_result_browser_location(
+    state: &store::State,
+    search_id: u32,
+    file_id: &str,
+) -> Option<(String, String)> {
+    select_result(state, search_id, file_id).and_then(|file| {
+        let path = file.decrypted_path().ok()?;
+        let path = if file.typ.is_file() {
+            path_utils::parent_path(path)?
+        } else {
+            path
+        };
+
+        Some((file.repo_id.clone(), path.to_owned()))
+    })
+}
+
+/// Returns the repo id and the path to open a result in a details view.
+pub fn select_result_details_location(
+    state: &store::State,
+    search_id: u32,
+    file_id: &str,
+) -> Option<(String, String)> {
+    select_result(state, search_id, file_id).and_then(|file| {
+        file.decrypted_path()
+            .ok()
+            .map(|path| (file.repo_id.clone(), path.to_owned()))
+    })
+}
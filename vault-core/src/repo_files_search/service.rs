@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use futures::{
+    future::{self, BoxFuture},
+    StreamExt,
+};
+
+use crate::{
+    repo_files::{
+        errors::RepoFilesErrors, selectors as repo_files_selectors, state::RepoFile,
+        RepoFilesService,
+    },
+    repo_files_index::RepoFilesIndexService,
+    repo_files_list::RepoFilesListService,
+    store,
+    utils::path_utils::normalize_path,
+};
+
+use super::{
+    errors::RepoFilesSearchError, matcher::RepoFilesSearchMatcher, mutations, selectors,
+    state::RepoFilesSearchQuery,
+};
+
+/// Items are matched in chunks to limit the number of store updates.
+const CHUNK_SIZE: usize = 100;
+
+/// Searches a repo dir recursively by decrypted names. Results are added to
+/// the store while the list is decrypted. A fresh repo files index is used
+/// instead of listing the repo when it is enabled.
+pub struct RepoFilesSearchService {
+    repo_files_service: Arc<RepoFilesService>,
+    repo_files_list_service: Arc<RepoFilesListService>,
+    repo_files_index_service: Arc<RepoFilesIndexService>,
+    store: Arc<store::Store>,
+}
+
+impl RepoFilesSearchService {
+    pub fn new(
+        repo_files_service: Arc<RepoFilesService>,
+        repo_files_list_service: Arc<RepoFilesListService>,
+        repo_files_index_service: Arc<RepoFilesIndexService>,
+        store: Arc<store::Store>,
+    ) -> Self {
+        Self {
+            repo_files_service,
+            repo_files_list_service,
+            repo_files_index_service,
+            store,
+        }
+    }
+
+    pub fn create(
+        self: Arc<Self>,
+        repo_id: &str,
+        path: &str,
+        query: RepoFilesSearchQuery,
+    ) -> (u32, BoxFuture<'static, Result<(), RepoFilesSearchError>>) {
+        let path = normalize_path(path).unwrap_or_else(|_| String::from("/"));
+
+        let matcher = RepoFilesSearchMatcher::new(query.clone());
+
+        let search_id = self.store.mutate(store::Event::RepoFilesSearch, |state| {
+            mutations::create(
+                state,
+                repo_id,
+                &path,
+                query,
+                matcher.as_ref().err().cloned(),
+            )
+        });
+
+        let search_future: BoxFuture<'static, Result<(), RepoFilesSearchError>> = match matcher {
+            Ok(matcher) => {
+                let search_self = self.clone();
+                let repo_id = repo_id.to_owned();
+
+                Box::pin(async move {
+                    search_self
+                        .search(search_id, &repo_id, &path, matcher)
+                        .await
+                })
+            }
+            Err(err) => Box::pin(future::ready(Err(err))),
+        };
+
+        (search_id, search_future)
+    }
+
+    pub fn cancel(&self, search_id: u32) {
+        self.store.mutate(store::Event::RepoFilesSearch, |state| {
+            mutations::cancel(state, search_id);
+        });
+    }
+
+    pub fn destroy(&self, search_id: u32) {
+        self.store.mutate(store::Event::RepoFilesSearch, |state| {
+            mutations::destroy(state, search_id);
+        });
+    }
+
+    fn is_searching(&self, search_id: u32) -> bool {
+        self.store
+            .with_state(|state| selectors::select_is_searching(state, search_id))
+    }
+
+    async fn search(
+        &self,
+        search_id: u32,
+        repo_id: &str,
+        path: &str,
+        matcher: RepoFilesSearchMatcher,
+    ) -> Result<(), RepoFilesSearchError> {
+        let res = self.search_chunks(search_id, repo_id, path, &matcher).await;
+
+        if self.is_searching(search_id) {
+            self.store.mutate(store::Event::RepoFilesSearch, |state| {
+                mutations::finished(state, search_id, &res);
+            });
+        }
+
+        res
+    }
+
+    async fn search_chunks(
+        &self,
+        search_id: u32,
+        repo_id: &str,
+        path: &str,
+        matcher: &RepoFilesSearchMatcher,
+    ) -> Result<(), RepoFilesSearchError> {
+        if self.repo_files_index_service.is_enabled() {
+            // an index that cannot be loaded falls back to listing the repo
+            let _ = self.repo_files_index_service.ensure_loaded(repo_id).await;
+
+            if let Some(files) = self
+                .repo_files_index_service
+                .get_descendant_files(repo_id, path)
+            {
+                return self.search_index_files(search_id, files, matcher);
+            }
+        }
+
+        self.repo_files_service.load_files(repo_id, path).await?;
+
+        let file = self
+            .store
+            .with_state(|state| {
+                repo_files_selectors::select_file(
+                    state,
+                    &repo_files_selectors::get_file_id(repo_id, path),
+                )
+                .cloned()
+            })
+            .ok_or_else(|| RepoFilesSearchError::RemoteError(RepoFilesErrors::not_found()))?;
+
+        let mut chunks = self
+            .repo_files_list_service
+            .get_list_recursive(&file)
+            .await?
+            .ready_chunks(CHUNK_SIZE);
+
+        while let Some(items) = chunks.next().await {
+            // dropping the stream stops listing
+            if !self.is_searching(search_id) {
+                return Ok(());
+            }
+
+            let chunk = mutations::match_items(items, matcher);
+
+            self.store.mutate(store::Event::RepoFilesSearch, |state| {
+                mutations::chunk_matched(state, search_id, chunk);
+            });
+        }
+
+        Ok(())
+    }
+
+    fn search_index_files(
+        &self,
+        search_id: u32,
+        files: Vec<RepoFile>,
+        matcher: &RepoFilesSearchMatcher,
+    ) -> Result<(), RepoFilesSearchError> {
+        for files in files.chunks(CHUNK_SIZE) {
+            if !self.is_searching(search_id) {
+                return Ok(());
+            }
+
+            let chunk = mutations::match_files(files.to_vec(), matcher);
+
+            self.store.mutate(store::Event::RepoFilesSearch, |state| {
+                mutations::chunk_matched(state, search_id, chunk);
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use crate::{common::state::Status, repo_files::state::RepoFile};
+
+use super::errors::RepoFilesSearchError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepoFilesSearchMatchType {
+    /// Case insensitive substring of the name
+    Substring,
+    /// Case insensitive glob with `*` and `?` matched against the whole name
+    Glob,
+    Regex,
+}
+
+impl Default for RepoFilesSearchMatchType {
+    fn default() -> Self {
+        Self::Substring
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepoFilesSearchFileType {
+    Any,
+    File,
+    Dir,
+}
+
+impl Default for RepoFilesSearchFileType {
+    fn default() -> Self {
+        Self::Any
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RepoFilesSearchQuery {
+    /// An empty text matches all names
+    pub text: String,
+    pub match_type: RepoFilesSearchMatchType,
+    pub file_type: RepoFilesSearchFileType,
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+    pub modified_after: Option<i64>,
+    pub modified_before: Option<i64>,
+}
+
+#[derive(Clone)]
+pub struct RepoFilesSearch {
+    pub id: u32,
+    pub repo_id: String,
+    pub path: String,
+    pub query: RepoFilesSearchQuery,
+    pub status: Status<RepoFilesSearchError>,
+    pub is_cancelled: bool,
+    pub results: Vec<RepoFile>,
+    pub scanned_count: usize,
+    /// Items that could not be listed or decrypted
+    pub skipped_count: usize,
+}
+
+#[derive(Clone, Default)]
+pub struct RepoFilesSearchState {
+    pub searches: HashMap<u32, RepoFilesSearch>,
+    pub next_id: u32,
+}
@@ -0,0 +1,52 @@
+use thiserror::Error;
+
+use crate::{
+    cipher::errors::{DecryptFilenameError, DecryptHeaderError, DecryptSizeError},
+    remote::RemoteError,
+    repo_files_read::errors::GetFilesReaderError,
+    repos::errors::{RepoLockedError, RepoNotFoundError},
+    user_error::UserError,
+};
+
+#[derive(Error, Debug, Clone, UserError)]
+pub enum LoadThumbnailError {
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("file not found")]
+    FileNotFound,
+    #[error("thumbnails are not supported for this file")]
+    NotSupported,
+    #[error("file is too large for a thumbnail")]
+    FileTooLarge,
+    #[error("failed to create thumbnail: {0}")]
+    ImageError(String),
+    #[error("{0}")]
+    DecryptFilenameError(#[from] DecryptFilenameError),
+    #[error("{0}")]
+    DecryptSizeError(#[from] DecryptSizeError),
+    #[error("{0}")]
+    DecryptHeaderError(#[from] DecryptHeaderError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+    #[error("failed to read file: {0}")]
+    ReadError(String),
+}
+
+impl From<GetFilesReaderError> for LoadThumbnailError {
+    fn from(err: GetFilesReaderError) -> Self {
+        match err {
+            GetFilesReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetFilesReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            GetFilesReaderError::FileNotFound
+            | GetFilesReaderError::FilesEmpty
+            | GetFilesReaderError::InvalidRange => Self::FileNotFound,
+            GetFilesReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            GetFilesReaderError::DecryptSizeError(err) => Self::DecryptSizeError(err),
+            GetFilesReaderError::DecryptHeaderError(err) => Self::DecryptHeaderError(err),
+            GetFilesReaderError::RemoteError(err) => Self::RemoteError(err),
+            GetFilesReaderError::ReadError(err) => Self::ReadError(err),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{common::state::Status, store};
+
+use super::{errors::LoadThumbnailError, state::RepoFilesThumbnail};
+
+/// Returns false if the thumbnail of the same content is already queued,
+/// loaded or failed. A changed file keeps the old bytes until the new
+/// thumbnail is loaded.
+pub fn queue(state: &mut store::State, file_id: &str, repo_id: &str, cache_key: &str) -> bool {
+    match state.repo_files_thumbnails.thumbnails.get_mut(file_id) {
+        Some(thumbnail) if thumbnail.cache_key == cache_key => false,
+        Some(thumbnail) => {
+            thumbnail.cache_key = cache_key.to_owned();
+            thumbnail.status = Status::Initial;
+
+            true
+        }
+        None => {
+            state.repo_files_thumbnails.thumbnails.insert(
+                file_id.to_owned(),
+                RepoFilesThumbnail {
+                    repo_id: repo_id.to_owned(),
+                    cache_key: cache_key.to_owned(),
+                    status: Status::Initial,
+                    bytes: None,
+                    version: 0,
+                },
+            );
+
+            true
+        }
+    }
+}
+
+pub fn loading(state: &mut store::State, file_id: &str) {
+    if let Some(thumbnail) = state.repo_files_thumbnails.thumbnails.get_mut(file_id) {
+        thumbnail.status = Status::Loading;
+
+        state.repo_files_thumbnails.loading_count += 1;
+    }
+}
+
+pub fn loaded(
+    state: &mut store::State,
+    file_id: &str,
+    cache_key: &str,
+    res: Result<Vec<u8>, LoadThumbnailError>,
+) {
+    // the thumbnail could have been removed (repo locked) in the meantime
+    state.repo_files_thumbnails.loading_count =
+        state.repo_files_thumbnails.loading_count.saturating_sub(1);
+
+    let next_version = state.repo_files_thumbnails.next_version;
+
+    let thumbnail = match state.repo_files_thumbnails.thumbnails.get_mut(file_id) {
+        Some(thumbnail) if thumbnail.cache_key == cache_key => thumbnail,
+        _ => return,
+    };
+
+    match res {
+        Ok(bytes) => {
+            thumbnail.status = Status::Loaded;
+            thumbnail.bytes = Some(bytes);
+            thumbnail.version = next_version;
+
+            state.repo_files_thumbnails.next_version += 1;
+        }
+        Err(err) => {
+            thumbnail.status = Status::Error { error: err };
+        }
+    }
+}
+
+/// Thumbnail created from an upload, without loading it
+pub fn created(
+    state: &mut store::State,
+    file_id: &str,
+    repo_id: &str,
+    cache_key: &str,
+    bytes: Vec<u8>,
+) {
+    let version = state.repo_files_thumbnails.next_version;
+
+    state.repo_files_thumbnails.next_version += 1;
+
+    state.repo_files_thumbnails.thumbnails.insert(
+        file_id.to_owned(),
+        RepoFilesThumbnail {
+            repo_id: repo_id.to_owned(),
+            cache_key: cache_key.to_owned(),
+            status: Status::Loaded,
+            bytes: Some(bytes),
+            version,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{common::state::Status, repo_files_thumbnails::errors::LoadThumbnailError, store};
+
+    use super::{loaded, loading, queue};
+
+    #[test]
+    fn test_queue_loaded() {
+        let mut state = store::State::default();
+
+        assert!(queue(&mut state, "r1:/a.jpg", "r1", "k1"));
+        assert!(!queue(&mut state, "r1:/a.jpg", "r1", "k1"));
+
+        loading(&mut state, "r1:/a.jpg");
+        assert_eq!(state.repo_files_thumbnails.loading_count, 1);
+
+        loaded(&mut state, "r1:/a.jpg", "k1", Ok(vec![1]));
+        assert_eq!(state.repo_files_thumbnails.loading_count, 0);
+
+        let thumbnail = &state.repo_files_thumbnails.thumbnails["r1:/a.jpg"];
+        assert!(matches!(thumbnail.status, Status::Loaded));
+        assert_eq!(thumbnail.bytes, Some(vec![1]));
+        let version = thumbnail.version;
+
+        // file changed while the old thumbnail was loading
+        assert!(queue(&mut state, "r1:/a.jpg", "r1", "k2"));
+        loading(&mut state, "r1:/a.jpg");
+        loaded(
+            &mut state,
+            "r1:/a.jpg",
+            "k1",
+            Err(LoadThumbnailError::FileNotFound),
+        );
+
+        let thumbnail = &state.repo_files_thumbnails.thumbnails["r1:/a.jpg"];
+        assert!(matches!(thumbnail.status, Status::Loading));
+        assert_eq!(thumbnail.bytes, Some(vec![1]));
+
+        loaded(&mut state, "r1:/a.jpg", "k2", Ok(vec![2]));
+
+        let thumbnail = &state.repo_files_thumbnails.thumbnails["r1:/a.jpg"];
+        assert_eq!(thumbnail.bytes, Some(vec![2]));
+        assert_ne!(thumbnail.version, version);
+    }
+}
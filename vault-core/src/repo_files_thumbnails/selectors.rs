@@ -0,0 +1,81 @@
+use crate::{
+    cipher::Cipher,
+    common::state::Status,
+    file_types::content_type::ext_to_content_type,
+    repo_files::{
+        selectors as repo_files_selectors,
+        state::{RepoFile, RepoFileType},
+    },
+    store,
+    utils::name_utils,
+};
+
+use super::state::{RepoFilesThumbnail, THUMBNAIL_CONTENT_TYPES, THUMBNAIL_MAX_SOURCE_SIZE};
+
+const MAX_CONCURRENCY: u32 = 2;
+
+pub fn select_thumbnail<'a>(
+    state: &'a store::State,
+    file_id: &str,
+) -> Option<&'a RepoFilesThumbnail> {
+    state.repo_files_thumbnails.thumbnails.get(file_id)
+}
+
+pub fn select_thumbnail_bytes<'a>(
+    state: &'a store::State,
+    file_id: &str,
+) -> (Option<&'a [u8]>, u32) {
+    select_thumbnail(state, file_id)
+        .map(|thumbnail| (thumbnail.bytes.as_deref(), thumbnail.version))
+        .unwrap_or((None, 0))
+}
+
+pub fn select_next_file_id(state: &store::State) -> Option<String> {
+    if state.repo_files_thumbnails.loading_count >= MAX_CONCURRENCY {
+        return None;
+    }
+
+    state
+        .repo_files_thumbnails
+        .thumbnails
+        .iter()
+        .find(|(_, thumbnail)| matches!(thumbnail.status, Status::Initial))
+        .map(|(file_id, _)| file_id.clone())
+}
+
+pub fn can_thumbnail(file: &RepoFile) -> bool {
+    file.typ == RepoFileType::File
+        && matches!(
+            &file.content_type,
+            Some(content_type) if THUMBNAIL_CONTENT_TYPES.contains(&content_type.as_str())
+        )
+        && matches!(file.decrypted_size(), Ok(size) if size <= THUMBNAIL_MAX_SOURCE_SIZE)
+        && matches!(file.decrypted_path(), Ok(path) if !repo_files_selectors::is_hidden_path(path))
+}
+
+/// Whether a file with this name could get a thumbnail once it is uploaded,
+/// before its content is sniffed.
+pub fn can_thumbnail_name(name: &str, size: Option<i64>) -> bool {
+    let name_lower = name.to_lowercase();
+
+    matches!(
+        name_utils::name_to_ext(&name_lower).and_then(ext_to_content_type),
+        Some(content_type) if THUMBNAIL_CONTENT_TYPES.contains(&content_type)
+    ) && size.map_or(true, |size| size <= THUMBNAIL_MAX_SOURCE_SIZE)
+}
+
+/// Files with the same content share the cached thumbnail. Files without a
+/// content hash fall back to the path, size and modified time. The key is
+/// used in the cache file name, so it is hashed with the repo key to not
+/// reveal the plaintext content hash.
+pub fn get_cache_key(file: &RepoFile, cipher: &Cipher) -> String {
+    match &file.content_hash {
+        Some(content_hash) => cipher.hash_value(content_hash),
+        None => cipher.hash_value(&format!(
+            "{}:{}:{}",
+            file.id,
+            file.size_force(),
+            file.modified
+        )),
+    }
+}
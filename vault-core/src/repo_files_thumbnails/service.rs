@@ -0,0 +1,243 @@
+use std::{pin::Pin, sync::Arc};
+
+use futures::{io::Cursor, AsyncRead, AsyncReadExt};
+
+use crate::{
+    repo_files::{
+        selectors as repo_files_selectors,
+        state::{RepoFile, RepoFilesUploadConflictResolution},
+        RepoFilesService,
+    },
+    repos::ReposService,
+    runtime, store,
+    utils::{image_utils, path_utils},
+};
+
+use super::{
+    errors::LoadThumbnailError,
+    mutations, selectors,
+    state::{get_cache_name, THUMBNAILS_PATH, THUMBNAIL_MAX_SOURCE_SIZE, THUMBNAIL_SIZE},
+};
+
+pub struct RepoFilesThumbnailsService {
+    repos_service: Arc<ReposService>,
+    repo_files_service: Arc<RepoFilesService>,
+    store: Arc<store::Store>,
+    runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
+}
+
+impl RepoFilesThumbnailsService {
+    pub fn new(
+        repos_service: Arc<ReposService>,
+        repo_files_service: Arc<RepoFilesService>,
+        store: Arc<store::Store>,
+        runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
+    ) -> Self {
+        Self {
+            repos_service,
+            repo_files_service,
+            store,
+            runtime,
+        }
+    }
+
+    /// Queues the thumbnail of the file. Thumbnails are read from the repo's
+    /// encrypted cache, missing ones are generated from the decrypted file and
+    /// stored in the cache.
+    pub fn load(self: Arc<Self>, file_id: &str) -> Result<(), LoadThumbnailError> {
+        let repo_id = self
+            .store
+            .with_state(|state| {
+                repo_files_selectors::select_file(state, file_id).map(|file| file.repo_id.clone())
+            })
+            .ok_or(LoadThumbnailError::FileNotFound)?;
+        let cipher = self.repos_service.get_cipher(&repo_id)?;
+
+        let queued = self.store.mutate(
+            store::Event::RepoFilesThumbnails,
+            |state| -> Result<bool, LoadThumbnailError> {
+                let file = repo_files_selectors::select_file(state, file_id)
+                    .ok_or(LoadThumbnailError::FileNotFound)?;
+
+                if !selectors::can_thumbnail(file) {
+                    return Err(LoadThumbnailError::NotSupported);
+                }
+
+                let cache_key = selectors::get_cache_key(file, &cipher);
+
+                Ok(mutations::queue(state, file_id, &repo_id, &cache_key))
+            },
+        )?;
+
+        if queued {
+            self.process_next();
+        }
+
+        Ok(())
+    }
+
+    /// Creates the thumbnail of an uploaded file from the same plaintext, so
+    /// that it does not have to be downloaded again.
+    pub async fn create_from_reader(
+        &self,
+        file_id: &str,
+        reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>>,
+    ) -> Result<(), LoadThumbnailError> {
+        let file = self
+            .store
+            .with_state(|state| repo_files_selectors::select_file(state, file_id).cloned())
+            .ok_or(LoadThumbnailError::FileNotFound)?;
+
+        if !selectors::can_thumbnail(&file) {
+            return Err(LoadThumbnailError::NotSupported);
+        }
+
+        let cipher = self.repos_service.get_cipher(&file.repo_id)?;
+        let cache_key = selectors::get_cache_key(&file, &cipher);
+
+        let bytes = self
+            .create_thumbnail(&file.repo_id, &cache_key, reader)
+            .await?;
+
+        self.store
+            .mutate(store::Event::RepoFilesThumbnails, |state| {
+                mutations::created(state, file_id, &file.repo_id, &cache_key, bytes);
+            });
+
+        Ok(())
+    }
+
+    fn process_next(self: Arc<Self>) {
+        if let Some((file_id, cache_key, file)) =
+            self.store
+                .mutate(store::Event::RepoFilesThumbnails, |state| {
+                    let file_id = selectors::select_next_file_id(state)?;
+                    let cache_key = selectors::select_thumbnail(state, &file_id)?
+                        .cache_key
+                        .clone();
+                    let file = repo_files_selectors::select_file(state, &file_id).cloned();
+
+                    mutations::loading(state, &file_id);
+
+                    Some((file_id, cache_key, file))
+                })
+        {
+            self.clone().load_thumbnail(file_id, cache_key, file);
+
+            self.process_next();
+        }
+    }
+
+    fn load_thumbnail(self: Arc<Self>, file_id: String, cache_key: String, file: Option<RepoFile>) {
+        let load_self = self.clone();
+
+        self.runtime.spawn(Box::pin(async move {
+            let res = match file {
+                Some(file) => load_self.get_thumbnail(&file, &cache_key).await,
+                None => Err(LoadThumbnailError::FileNotFound),
+            };
+
+            load_self
+                .store
+                .mutate(store::Event::RepoFilesThumbnails, |state| {
+                    mutations::loaded(state, &file_id, &cache_key, res);
+                });
+
+            load_self.process_next();
+        }));
+    }
+
+    async fn get_thumbnail(
+        &self,
+        file: &RepoFile,
+        cache_key: &str,
+    ) -> Result<Vec<u8>, LoadThumbnailError> {
+        if let Some(bytes) = self.get_cached(&file.repo_id, cache_key).await {
+            return Ok(bytes);
+        }
+
+        let reader = self
+            .repo_files_service
+            .clone()
+            .get_file_reader(&file.id, None)
+            .await?;
+
+        self.create_thumbnail(&file.repo_id, cache_key, reader.reader)
+            .await
+    }
+
+    async fn create_thumbnail(
+        &self,
+        repo_id: &str,
+        cache_key: &str,
+        reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>>,
+    ) -> Result<Vec<u8>, LoadThumbnailError> {
+        let bytes = read_limited(reader).await?;
+
+        let thumbnail = image_utils::create_thumbnail(&bytes, THUMBNAIL_SIZE)
+            .map_err(|err| LoadThumbnailError::ImageError(err.to_string()))?;
+
+        self.set_cached(repo_id, cache_key, &thumbnail).await;
+
+        Ok(thumbnail)
+    }
+
+    async fn get_cached(&self, repo_id: &str, cache_key: &str) -> Option<Vec<u8>> {
+        let path = path_utils::join_path_name(THUMBNAILS_PATH, &get_cache_name(cache_key));
+
+        self.repo_files_service
+            .load_file(repo_id, &path)
+            .await
+            .ok()?;
+
+        let reader = self
+            .repo_files_service
+            .clone()
+            .get_file_reader(&repo_files_selectors::get_file_id(repo_id, &path), None)
+            .await
+            .ok()?;
+
+        read_limited(reader.reader).await.ok()
+    }
+
+    /// The thumbnail is still shown if it cannot be cached, it is generated
+    /// again the next time.
+    async fn set_cached(&self, repo_id: &str, cache_key: &str, thumbnail: &[u8]) {
+        if let Err(err) = self
+            .repo_files_service
+            .clone()
+            .upload_file_reader(
+                repo_id,
+                THUMBNAILS_PATH,
+                &get_cache_name(cache_key),
+                Box::pin(Cursor::new(thumbnail.to_vec())),
+                Some(thumbnail.len() as i64),
+                None,
+                RepoFilesUploadConflictResolution::Overwrite,
+                None,
+                None,
+            )
+            .await
+        {
+            log::warn!("Failed to cache thumbnail: {}", err);
+        }
+    }
+}
+
+async fn read_limited(
+    reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>>,
+) -> Result<Vec<u8>, LoadThumbnailError> {
+    let mut buf = Vec::new();
+
+    reader
+        .take(THUMBNAIL_MAX_SOURCE_SIZE as u64 + 1)
+        .read_to_end(&mut buf)
+        .await
+        .map_err(|err| LoadThumbnailError::ReadError(err.to_string()))?;
+
+    if buf.len() as i64 > THUMBNAIL_MAX_SOURCE_SIZE {
+        return Err(LoadThumbnailError::FileTooLarge);
+    }
+
+    Ok(buf)
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use crate::common::state::Status;
+
+use super::errors::LoadThumbnailError;
+
+/// Hidden dir with the encrypted thumbnails cache
+pub const THUMBNAILS_PATH: &str = "/.thumbnails";
+
+/// Thumbnails fit in a square of this size
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+/// Larger files are not downloaded and decoded
+pub const THUMBNAIL_MAX_SOURCE_SIZE: i64 = 32 * 1024 * 1024;
+
+/// Content types of formats that can be decoded. The content type of files
+/// with a missing or wrong extension is corrected by sniffing.
+pub const THUMBNAIL_CONTENT_TYPES: &[&str] = &[
+    "image/gif",
+    "image/jpeg",
+    "image/png",
+    "image/webp",
+    "image/x-ms-bmp",
+];
+
+#[derive(Clone)]
+pub struct RepoFilesThumbnail {
+    pub repo_id: String,
+    pub cache_key: String,
+    pub status: Status<LoadThumbnailError>,
+    pub bytes: Option<Vec<u8>>,
+    pub version: u32,
+}
+
+#[derive(Clone, Default)]
+pub struct RepoFilesThumbnailsState {
+    /// Thumbnails by file id
+    pub thumbnails: HashMap<String, RepoFilesThumbnail>,
+    pub loading_count: u32,
+    pub next_version: u32,
+}
+
+pub fn is_thumbnails_path(path: &str) -> bool {
+    path == THUMBNAILS_PATH || path.starts_with(&format!("{}/", THUMBNAILS_PATH))
+}
+
+/// Name of the cached thumbnail. The size is included so that changing it
+/// does not reuse old thumbnails.
+pub fn get_cache_name(cache_key: &str) -> String {
+    format!("{}-{}", cache_key, THUMBNAIL_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_cache_name, is_thumbnails_path};
+
+    #[test]
+    fn test_paths() {
+        assert!(is_thumbnails_path("/.thumbnails"));
+        assert!(is_thumbnails_path("/.thumbnails/abc-256"));
+        assert!(!is_thumbnails_path("/.thumbnailsx"));
+        assert!(!is_thumbnails_path("/Photos/.thumbnails"));
+
+        assert_eq!(get_cache_name("abc"), "abc-256");
+    }
+}
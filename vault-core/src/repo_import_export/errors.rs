@@ -0,0 +1,123 @@
+use thiserror::Error;
+
+use crate::{
+    cipher::errors::{DecryptFilenameError, DecryptHeaderError, DecryptSizeError},
+    remote::RemoteError,
+    repo_files::errors::{EnsureDirError, LoadFileError, RepoFilesErrors, UploadFileReaderError},
+    repo_files_list::errors::{FilesListRecursiveItemError, GetListRecursiveError},
+    repo_files_read::errors::GetFilesReaderError,
+    repo_trash::errors::RepoTrashError,
+    repos::errors::{RepoLockedError, RepoNotFoundError},
+    user_error::UserError,
+};
+
+#[derive(Error, Debug, Clone, PartialEq, UserError)]
+pub enum RepoImportExportError {
+    #[error("invalid path")]
+    InvalidPath,
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("{0}")]
+    DecryptFilenameError(#[from] DecryptFilenameError),
+    #[error("{0}")]
+    DecryptSizeError(#[from] DecryptSizeError),
+    #[error("{0}")]
+    DecryptHeaderError(#[from] DecryptHeaderError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+    #[error("failed to read file: {0}")]
+    ReadError(String),
+    #[error("verification failed: expected {expected} bytes but found {actual}")]
+    VerifyFailed { expected: i64, actual: Option<i64> },
+}
+
+impl From<LoadFileError> for RepoImportExportError {
+    fn from(err: LoadFileError) -> Self {
+        match err {
+            LoadFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFileError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<EnsureDirError> for RepoImportExportError {
+    fn from(err: EnsureDirError) -> Self {
+        match err {
+            EnsureDirError::RepoNotFound(err) => Self::RepoNotFound(err),
+            EnsureDirError::RepoLocked(err) => Self::RepoLocked(err),
+            EnsureDirError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            EnsureDirError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<UploadFileReaderError> for RepoImportExportError {
+    fn from(err: UploadFileReaderError) -> Self {
+        match err {
+            UploadFileReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            UploadFileReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            UploadFileReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            UploadFileReaderError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<GetFilesReaderError> for RepoImportExportError {
+    fn from(err: GetFilesReaderError) -> Self {
+        match err {
+            GetFilesReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetFilesReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            GetFilesReaderError::FileNotFound | GetFilesReaderError::FilesEmpty => {
+                Self::RemoteError(RepoFilesErrors::not_found())
+            }
+            GetFilesReaderError::InvalidRange => Self::InvalidPath,
+            GetFilesReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            GetFilesReaderError::DecryptSizeError(err) => Self::DecryptSizeError(err),
+            GetFilesReaderError::DecryptHeaderError(err) => Self::DecryptHeaderError(err),
+            GetFilesReaderError::RemoteError(err) => Self::RemoteError(err),
+            GetFilesReaderError::ReadError(err) => Self::ReadError(err),
+        }
+    }
+}
+
+impl From<GetListRecursiveError> for RepoImportExportError {
+    fn from(err: GetListRecursiveError) -> Self {
+        match err {
+            GetListRecursiveError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetListRecursiveError::RepoLocked(err) => Self::RepoLocked(err),
+            GetListRecursiveError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            GetListRecursiveError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<FilesListRecursiveItemError> for RepoImportExportError {
+    fn from(err: FilesListRecursiveItemError) -> Self {
+        match err {
+            FilesListRecursiveItemError::DecryptFilenameError(err) => {
+                Self::DecryptFilenameError(err)
+            }
+            FilesListRecursiveItemError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<RepoTrashError> for RepoImportExportError {
+    fn from(err: RepoTrashError) -> Self {
+        match err {
+            RepoTrashError::InvalidPath => Self::InvalidPath,
+            RepoTrashError::EntryNotFound => Self::RemoteError(RepoFilesErrors::not_found()),
+            RepoTrashError::InvalidInfo(err) => Self::ReadError(err),
+            RepoTrashError::RepoNotFound(err) => Self::RepoNotFound(err),
+            RepoTrashError::RepoLocked(err) => Self::RepoLocked(err),
+            RepoTrashError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            RepoTrashError::DecryptSizeError(err) => Self::DecryptSizeError(err),
+            RepoTrashError::DecryptHeaderError(err) => Self::DecryptHeaderError(err),
+            RepoTrashError::RemoteError(err) => Self::RemoteError(err),
+            RepoTrashError::ReadError(err) => Self::ReadError(err),
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{
+    common::state::Status,
+    remote::{models, RemoteError},
+    remote_files::state::RemoteFileType,
+    repo_files::state::RepoFileType,
+    repo_files_list::state::RepoFilesListRecursiveItem,
+    store,
+};
+
+use super::{
+    errors::RepoImportExportError,
+    state::{
+        RepoImportExportDirection, RepoImportExportEntry, RepoImportExportEntryStatus,
+        RepoImportExportState,
+    },
+};
+
+pub struct RepoImportExportEntryAdded {
+    pub path: String,
+    pub is_dir: bool,
+    pub size: Option<i64>,
+    /// Entries that could not be listed fail right away
+    pub error: Option<RepoImportExportError>,
+}
+
+pub fn init(
+    state: &mut store::State,
+    repo_id: &str,
+    direction: RepoImportExportDirection,
+    mount_id: &str,
+    remote_path: &str,
+    repo_path: &str,
+    delete_source: bool,
+) {
+    state.repo_import_export = Some(RepoImportExportState {
+        repo_id: repo_id.to_owned(),
+        direction,
+        mount_id: mount_id.to_owned(),
+        remote_path: remote_path.to_owned(),
+        repo_path: repo_path.to_owned(),
+        delete_source,
+        status: Status::Loading,
+        entries: Vec::new(),
+        next_entry_id: 1,
+        source_deleted: false,
+    });
+}
+
+pub fn remote_item_to_entry(
+    item: &Result<models::FilesListRecursiveItem, RemoteError>,
+) -> RepoImportExportEntryAdded {
+    match item {
+        Ok(models::FilesListRecursiveItem::File { path, file }) => {
+            let is_dir = RemoteFileType::from(file.typ.as_str()) == RemoteFileType::Dir;
+
+            RepoImportExportEntryAdded {
+                path: path.clone(),
+                is_dir,
+                size: if is_dir { None } else { Some(file.size) },
+                error: None,
+            }
+        }
+        Ok(models::FilesListRecursiveItem::Error { path, error }) => RepoImportExportEntryAdded {
+            path: path.clone().unwrap_or_else(|| String::from("/")),
+            is_dir: false,
+            size: None,
+            error: Some(RepoImportExportError::RemoteError(
+                RemoteError::from_api_error_details(error.clone(), None),
+            )),
+        },
+        Err(err) => RepoImportExportEntryAdded {
+            path: String::from("/"),
+            is_dir: false,
+            size: None,
+            error: Some(RepoImportExportError::RemoteError(err.clone())),
+        },
+    }
+}
+
+pub fn repo_item_to_entry(item: &RepoFilesListRecursiveItem) -> RepoImportExportEntryAdded {
+    match item {
+        RepoFilesListRecursiveItem::File {
+            relative_repo_path,
+            file,
+        } => {
+            let is_dir = file.typ == RepoFileType::Dir;
+
+            match relative_repo_path {
+                Ok(relative_repo_path) => RepoImportExportEntryAdded {
+                    path: relative_repo_path.clone(),
+                    is_dir,
+                    size: if is_dir {
+                        None
+                    } else {
+                        file.decrypted_size().ok()
+                    },
+                    error: None,
+                },
+                Err(err) => RepoImportExportEntryAdded {
+                    path: file.remote_path.clone(),
+                    is_dir,
+                    size: None,
+                    error: Some(RepoImportExportError::DecryptFilenameError(err.clone())),
+                },
+            }
+        }
+        RepoFilesListRecursiveItem::Error {
+            remote_path, error, ..
+        } => RepoImportExportEntryAdded {
+            path: remote_path.clone().unwrap_or_else(|| String::from("/")),
+            is_dir: false,
+            size: None,
+            error: Some(error.clone().into()),
+        },
+    }
+}
+
+/// Returns the ids of the added entries in the same order.
+pub fn entries_added(
+    state: &mut store::State,
+    entries: Vec<RepoImportExportEntryAdded>,
+) -> Vec<u32> {
+    let import_export = match state.repo_import_export.as_mut() {
+        Some(import_export) => import_export,
+        None => return Vec::new(),
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let id = import_export.next_entry_id;
+
+            import_export.next_entry_id += 1;
+
+            import_export.entries.push(RepoImportExportEntry {
+                id,
+                path: entry.path,
+                is_dir: entry.is_dir,
+                size: entry.size,
+                transferred_bytes: 0,
+                status: match entry.error {
+                    Some(error) => RepoImportExportEntryStatus::Failed { error },
+                    None => RepoImportExportEntryStatus::Waiting,
+                },
+            });
+
+            id
+        })
+        .collect()
+}
+
+fn get_entry_mut<'a>(
+    state: &'a mut store::State,
+    id: u32,
+) -> Option<&'a mut RepoImportExportEntry> {
+    state.repo_import_export.as_mut().and_then(|import_export| {
+        import_export
+            .entries
+            .iter_mut()
+            .find(|entry| entry.id == id)
+    })
+}
+
+pub fn entry_started(state: &mut store::State, id: u32) {
+    if let Some(entry) = get_entry_mut(state, id) {
+        entry.status = RepoImportExportEntryStatus::Transferring;
+        entry.transferred_bytes = 0;
+    }
+}
+
+pub fn entry_progress(state: &mut store::State, id: u32, bytes: i64) {
+    if let Some(entry) = get_entry_mut(state, id) {
+        entry.transferred_bytes += bytes;
+    }
+}
+
+pub fn entry_finished(state: &mut store::State, id: u32, res: Result<(), RepoImportExportError>) {
+    if let Some(entry) = get_entry_mut(state, id) {
+        entry.status = match res {
+            Ok(()) => RepoImportExportEntryStatus::Done,
+            Err(error) => RepoImportExportEntryStatus::Failed { error },
+        };
+    }
+}
+
+pub fn source_deleted(state: &mut store::State) {
+    if let Some(ref mut import_export) = state.repo_import_export {
+        import_export.source_deleted = true;
+    }
+}
+
+pub fn finished(state: &mut store::State, res: &Result<(), RepoImportExportError>) {
+    if let Some(ref mut import_export) = state.repo_import_export {
+        import_export.status = match res {
+            Ok(()) => Status::Loaded,
+            Err(err) => Status::Error { error: err.clone() },
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        remote::{models, test_helpers as remote_test_helpers, RemoteError},
+        store,
+    };
+
+    use super::{
+        super::{
+            errors::RepoImportExportError,
+            state::{RepoImportExportDirection, RepoImportExportEntryStatus},
+        },
+        entries_added, entry_finished, init, remote_item_to_entry,
+    };
+
+    #[test]
+    fn test_remote_item_to_entry() {
+        let entry = remote_item_to_entry(&Ok(
+            remote_test_helpers::create_files_list_recursive_item_dir("/", "Photos"),
+        ));
+        assert_eq!(entry.path, "/");
+        assert!(entry.is_dir);
+        assert_eq!(entry.size, None);
+        assert!(entry.error.is_none());
+
+        let entry = remote_item_to_entry(&Ok(
+            remote_test_helpers::create_files_list_recursive_item_file("/D1/F1.jpg", "F1.jpg"),
+        ));
+        assert_eq!(entry.path, "/D1/F1.jpg");
+        assert!(!entry.is_dir);
+        assert_eq!(entry.size, Some(100));
+
+        let entry = remote_item_to_entry(&Ok(
+            remote_test_helpers::create_files_list_recursive_item_error(
+                Some("/D2"),
+                models::ApiErrorDetails {
+                    code: String::from("NotFound"),
+                    message: String::from("Not found"),
+                    extra: None,
+                },
+            ),
+        ));
+        assert_eq!(entry.path, "/D2");
+        assert!(matches!(
+            entry.error,
+            Some(RepoImportExportError::RemoteError(
+                RemoteError::ApiError { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_entries_added_finished() {
+        let mut state = store::State::default();
+
+        init(
+            &mut state,
+            "r1",
+            RepoImportExportDirection::Import,
+            "m1",
+            "/Photos",
+            "/Photos",
+            true,
+        );
+
+        let ids = entries_added(
+            &mut state,
+            vec![
+                remote_item_to_entry(&Ok(
+                    remote_test_helpers::create_files_list_recursive_item_dir("/", "Photos"),
+                )),
+                remote_item_to_entry(&Ok(
+                    remote_test_helpers::create_files_list_recursive_item_file("/F1", "F1"),
+                )),
+            ],
+        );
+
+        assert_eq!(ids, vec![1, 2]);
+
+        entry_finished(&mut state, 2, Ok(()));
+
+        let entries = &state.repo_import_export.as_ref().unwrap().entries;
+
+        assert_eq!(entries[0].status, RepoImportExportEntryStatus::Waiting);
+        assert_eq!(entries[1].status, RepoImportExportEntryStatus::Done);
+    }
+}
@@ -0,0 +1,74 @@
+use crate::store;
+
+use super::state::{RepoImportExportEntry, RepoImportExportEntryStatus};
+
+pub fn select_is_running(state: &store::State, repo_id: &str) -> bool {
+    state
+        .repo_import_export
+        .as_ref()
+        .map(|import_export| import_export.repo_id == repo_id)
+        .unwrap_or(false)
+}
+
+/// The source can only be deleted once every entry was transferred and
+/// verified.
+pub fn can_delete_source(entries: &[RepoImportExportEntry]) -> bool {
+    !entries.is_empty()
+        && entries
+            .iter()
+            .all(|entry| entry.status == RepoImportExportEntryStatus::Done)
+}
+
+pub fn select_can_delete_source(state: &store::State) -> bool {
+    state
+        .repo_import_export
+        .as_ref()
+        .map(|import_export| can_delete_source(&import_export.entries))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repo_files::errors::RepoFilesErrors;
+
+    use super::{
+        super::{
+            errors::RepoImportExportError,
+            state::{RepoImportExportEntry, RepoImportExportEntryStatus},
+        },
+        can_delete_source,
+    };
+
+    fn create_entry(id: u32, status: RepoImportExportEntryStatus) -> RepoImportExportEntry {
+        RepoImportExportEntry {
+            id,
+            path: format!("/F{}", id),
+            is_dir: false,
+            size: Some(10),
+            transferred_bytes: 0,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_can_delete_source() {
+        assert!(!can_delete_source(&[]));
+        assert!(can_delete_source(&[
+            create_entry(1, RepoImportExportEntryStatus::Done),
+            create_entry(2, RepoImportExportEntryStatus::Done),
+        ]));
+        assert!(!can_delete_source(&[
+            create_entry(1, RepoImportExportEntryStatus::Done),
+            create_entry(
+                2,
+                RepoImportExportEntryStatus::Failed {
+                    error: RepoImportExportError::RemoteError(RepoFilesErrors::not_found()),
+                }
+            ),
+        ]));
+        assert!(!can_delete_source(&[create_entry(
+            1,
+            RepoImportExportEntryStatus::Waiting
+        )]));
+    }
+}
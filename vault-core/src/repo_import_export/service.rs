@@ -0,0 +1,464 @@
+use std::sync::Arc;
+
+use futures::StreamExt;
+
+use crate::{
+    remote::{models, RemoteFileUploadConflictResolution},
+    remote_files::{
+        selectors as remote_files_selectors, state::RemoteFileType, RemoteFilesService,
+    },
+    repo_files::{
+        mutations as repo_files_mutations, selectors as repo_files_selectors,
+        state::{RepoFile, RepoFilesUploadConflictResolution},
+        RepoFilesService,
+    },
+    repo_files_list::{state::RepoFilesListRecursiveItem, RepoFilesListService},
+    repo_files_read::RepoFilesReadService,
+    repo_trash::RepoTrashService,
+    repos::{errors::RepoNotFoundError, selectors as repos_selectors},
+    store,
+    utils::path_utils,
+};
+
+use super::{
+    errors::RepoImportExportError, mutations, selectors, state::RepoImportExportDirection,
+};
+
+/// Copies plaintext remote folders into a repo (import) and decrypted repo
+/// folders out into plaintext remote folders (export). The source can be
+/// deleted once every file was transferred and its size verified, an exported
+/// repo source is moved into the repo trash so it can still be restored.
+pub struct RepoImportExportService {
+    remote_files_service: Arc<RemoteFilesService>,
+    repo_files_service: Arc<RepoFilesService>,
+    repo_files_read_service: Arc<RepoFilesReadService>,
+    repo_files_list_service: Arc<RepoFilesListService>,
+    repo_trash_service: Arc<RepoTrashService>,
+    store: Arc<store::Store>,
+}
+
+impl RepoImportExportService {
+    pub fn new(
+        remote_files_service: Arc<RemoteFilesService>,
+        repo_files_service: Arc<RepoFilesService>,
+        repo_files_read_service: Arc<RepoFilesReadService>,
+        repo_files_list_service: Arc<RepoFilesListService>,
+        repo_trash_service: Arc<RepoTrashService>,
+        store: Arc<store::Store>,
+    ) -> Self {
+        Self {
+            remote_files_service,
+            repo_files_service,
+            repo_files_read_service,
+            repo_files_list_service,
+            repo_trash_service,
+            store,
+        }
+    }
+
+    /// Imports the remote file or folder at remote_path into
+    /// repo_parent_path.
+    pub async fn import(
+        &self,
+        repo_id: &str,
+        mount_id: &str,
+        remote_path: &str,
+        repo_parent_path: &str,
+        delete_source: bool,
+    ) -> Result<(), RepoImportExportError> {
+        let name =
+            path_utils::path_to_name(remote_path).ok_or(RepoImportExportError::InvalidPath)?;
+        let repo_path = path_utils::join_path_name(repo_parent_path, name);
+
+        self.store.mutate(store::Event::RepoImportExport, |state| {
+            mutations::init(
+                state,
+                repo_id,
+                RepoImportExportDirection::Import,
+                mount_id,
+                remote_path,
+                &repo_path,
+                delete_source,
+            );
+        });
+
+        let res = self
+            .import_files(repo_id, mount_id, remote_path, &repo_path, delete_source)
+            .await;
+
+        self.store.mutate(store::Event::RepoImportExport, |state| {
+            mutations::finished(state, &res);
+        });
+
+        res
+    }
+
+    /// Exports the repo file or folder at repo_path into remote_parent_path.
+    pub async fn export(
+        &self,
+        repo_id: &str,
+        repo_path: &str,
+        mount_id: &str,
+        remote_parent_path: &str,
+        delete_source: bool,
+    ) -> Result<(), RepoImportExportError> {
+        let file = self.get_repo_file(repo_id, repo_path).await?;
+
+        let name = self
+            .store
+            .with_state(|state| {
+                repo_files_selectors::select_file_name(state, &file).map(str::to_string)
+            })
+            .ok_or(RepoImportExportError::InvalidPath)?;
+        let remote_path = path_utils::join_path_name(remote_parent_path, &name);
+
+        self.store.mutate(store::Event::RepoImportExport, |state| {
+            mutations::init(
+                state,
+                repo_id,
+                RepoImportExportDirection::Export,
+                mount_id,
+                &remote_path,
+                repo_path,
+                delete_source,
+            );
+        });
+
+        let res = self
+            .export_files(&file, mount_id, &remote_path, delete_source)
+            .await;
+
+        self.store.mutate(store::Event::RepoImportExport, |state| {
+            mutations::finished(state, &res);
+        });
+
+        res
+    }
+
+    pub fn destroy(&self, repo_id: &str) {
+        self.store.mutate(store::Event::RepoImportExport, |state| {
+            if selectors::select_is_running(state, repo_id) {
+                state.repo_import_export = None;
+            }
+        });
+    }
+
+    fn is_destroyed(&self, repo_id: &str) -> bool {
+        !self
+            .store
+            .with_state(|state| selectors::select_is_running(state, repo_id))
+    }
+
+    fn can_delete_source(&self) -> bool {
+        self.store.with_state(selectors::select_can_delete_source)
+    }
+
+    async fn get_repo_file(
+        &self,
+        repo_id: &str,
+        path: &str,
+    ) -> Result<RepoFile, RepoImportExportError> {
+        if path == "/" {
+            let location = self.store.with_state(|state| {
+                repos_selectors::select_repo(state, repo_id).map(|repo| repo.get_location())
+            })?;
+
+            self.remote_files_service
+                .load_file(&location.mount_id, &location.path)
+                .await?;
+
+            return self
+                .store
+                .with_state(|state| {
+                    remote_files_selectors::select_file(
+                        state,
+                        &remote_files_selectors::get_file_id(&location.mount_id, &location.path),
+                    )
+                    .map(|remote_file| repo_files_mutations::get_root_file(repo_id, remote_file))
+                })
+                .ok_or(RepoImportExportError::RepoNotFound(RepoNotFoundError));
+        }
+
+        self.repo_files_service.load_file(repo_id, path).await?;
+
+        self.store.with_state(|state| {
+            repo_files_selectors::select_file(
+                state,
+                &repo_files_selectors::get_file_id(repo_id, path),
+            )
+            .cloned()
+            .ok_or(RepoImportExportError::InvalidPath)
+        })
+    }
+
+    async fn import_files(
+        &self,
+        repo_id: &str,
+        mount_id: &str,
+        remote_path: &str,
+        repo_path: &str,
+        delete_source: bool,
+    ) -> Result<(), RepoImportExportError> {
+        let items = self
+            .remote_files_service
+            .get_list_recursive(mount_id, remote_path)
+            .await?
+            .collect::<Vec<_>>()
+            .await;
+
+        let ids = self.store.mutate(store::Event::RepoImportExport, |state| {
+            mutations::entries_added(
+                state,
+                items.iter().map(mutations::remote_item_to_entry).collect(),
+            )
+        });
+
+        for (id, item) in ids.into_iter().zip(items.into_iter()) {
+            if self.is_destroyed(repo_id) {
+                return Ok(());
+            }
+
+            // failed items are already reported
+            let (relative_path, file) = match item {
+                Ok(models::FilesListRecursiveItem::File { path, file }) => (path, file),
+                _ => continue,
+            };
+
+            self.store.mutate(store::Event::RepoImportExport, |state| {
+                mutations::entry_started(state, id);
+            });
+
+            let res = self
+                .import_entry(
+                    id,
+                    repo_id,
+                    mount_id,
+                    &path_utils::join_paths(remote_path, &relative_path),
+                    &path_utils::join_paths(repo_path, &relative_path),
+                    file,
+                )
+                .await;
+
+            self.store.mutate(store::Event::RepoImportExport, |state| {
+                mutations::entry_finished(state, id, res);
+            });
+        }
+
+        if delete_source && self.can_delete_source() {
+            self.remote_files_service
+                .delete_file(mount_id, remote_path)
+                .await?;
+
+            self.store.mutate(store::Event::RepoImportExport, |state| {
+                mutations::source_deleted(state);
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn import_entry(
+        &self,
+        id: u32,
+        repo_id: &str,
+        mount_id: &str,
+        remote_path: &str,
+        repo_path: &str,
+        file: models::FilesFile,
+    ) -> Result<(), RepoImportExportError> {
+        if RemoteFileType::from(file.typ.as_str()) == RemoteFileType::Dir {
+            self.repo_files_service
+                .clone()
+                .ensure_dirs(repo_id, repo_path)
+                .await?;
+
+            return Ok(());
+        }
+
+        let (parent_path, name) =
+            path_utils::split_parent_name(repo_path).ok_or(RepoImportExportError::InvalidPath)?;
+
+        let file_reader = self
+            .remote_files_service
+            .get_file_reader(mount_id, remote_path, None)
+            .await?;
+
+        let progress_store = self.store.clone();
+
+        let upload_result = self
+            .repo_files_service
+            .clone()
+            .upload_file_reader(
+                repo_id,
+                parent_path,
+                name,
+                file_reader.reader,
+                Some(file.size),
+                Some(file.modified),
+                RepoFilesUploadConflictResolution::Error,
+                Some(Box::new(move |n| {
+                    progress_store.mutate(store::Event::RepoImportExport, |state| {
+                        mutations::entry_progress(state, id, n as i64);
+                    });
+                })),
+                None,
+            )
+            .await?;
+
+        let actual = self.store.with_state(|state| {
+            repo_files_selectors::select_file(state, &upload_result.file_id)
+                .and_then(|file| file.decrypted_size().ok())
+        });
+
+        if actual != Some(file.size) {
+            return Err(RepoImportExportError::VerifyFailed {
+                expected: file.size,
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn export_files(
+        &self,
+        file: &RepoFile,
+        mount_id: &str,
+        remote_path: &str,
+        delete_source: bool,
+    ) -> Result<(), RepoImportExportError> {
+        let items = self
+            .repo_files_list_service
+            .get_list_recursive(file)
+            .await?
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            // trash and thumbnails cache are not exported
+            .filter(|item| match item {
+                RepoFilesListRecursiveItem::File { file, .. } => !matches!(
+                    file.decrypted_path(),
+                    Ok(path) if repo_files_selectors::is_hidden_path(path)
+                ),
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+
+        let ids = self.store.mutate(store::Event::RepoImportExport, |state| {
+            mutations::entries_added(
+                state,
+                items.iter().map(mutations::repo_item_to_entry).collect(),
+            )
+        });
+
+        for (id, item) in ids.into_iter().zip(items.into_iter()) {
+            if self.is_destroyed(&file.repo_id) {
+                return Ok(());
+            }
+
+            // failed items are already reported
+            let (relative_path, item_file) = match item {
+                RepoFilesListRecursiveItem::File {
+                    relative_repo_path: Ok(relative_repo_path),
+                    file,
+                } => (relative_repo_path, file),
+                _ => continue,
+            };
+
+            self.store.mutate(store::Event::RepoImportExport, |state| {
+                mutations::entry_started(state, id);
+            });
+
+            let res = self
+                .export_entry(
+                    id,
+                    mount_id,
+                    &path_utils::join_paths(remote_path, &relative_path),
+                    item_file,
+                )
+                .await;
+
+            self.store.mutate(store::Event::RepoImportExport, |state| {
+                mutations::entry_finished(state, id, res);
+            });
+        }
+
+        let repo_path = file.decrypted_path()?;
+
+        // the repo itself is never deleted
+        if delete_source && repo_path != "/" && self.can_delete_source() {
+            self.repo_trash_service
+                .trash_file(&file.repo_id, repo_path)
+                .await?;
+
+            self.store.mutate(store::Event::RepoImportExport, |state| {
+                mutations::source_deleted(state);
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn export_entry(
+        &self,
+        id: u32,
+        mount_id: &str,
+        remote_path: &str,
+        file: RepoFile,
+    ) -> Result<(), RepoImportExportError> {
+        if !file.typ.is_file() {
+            self.remote_files_service
+                .ensure_dirs(mount_id, remote_path)
+                .await?;
+
+            return Ok(());
+        }
+
+        let (parent_path, name) =
+            path_utils::split_parent_name(remote_path).ok_or(RepoImportExportError::InvalidPath)?;
+
+        let expected = file.decrypted_size()?;
+        let modified = file.modified;
+
+        self.remote_files_service
+            .ensure_dirs(mount_id, parent_path)
+            .await?;
+
+        let file_reader = self
+            .repo_files_read_service
+            .clone()
+            .get_files_reader(&[file])
+            .await?;
+
+        let progress_store = self.store.clone();
+
+        let (remote_file_id, _) = self
+            .remote_files_service
+            .upload_file_reader(
+                mount_id,
+                parent_path,
+                name,
+                file_reader.reader,
+                file_reader.size,
+                Some(modified),
+                RemoteFileUploadConflictResolution::Error,
+                Some(Box::new(move |n| {
+                    progress_store.mutate(store::Event::RepoImportExport, |state| {
+                        mutations::entry_progress(state, id, n as i64);
+                    });
+                })),
+                None,
+            )
+            .await?;
+
+        let actual = self.store.with_state(|state| {
+            remote_files_selectors::select_file(state, &remote_file_id).map(|file| file.size)
+        });
+
+        if actual != Some(expected) {
+            return Err(RepoImportExportError::VerifyFailed { expected, actual });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use crate::common::state::Status;
+
+use super::errors::RepoImportExportError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepoImportExportDirection {
+    /// Plaintext remote files are encrypted into the repo
+    Import,
+    /// Repo files are decrypted into a plaintext remote folder
+    Export,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RepoImportExportEntryStatus {
+    Waiting,
+    Transferring,
+    Done,
+    Failed { error: RepoImportExportError },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RepoImportExportEntry {
+    pub id: u32,
+    /// Path relative to the imported or exported folder, "/" for the folder
+    /// itself
+    pub path: String,
+    pub is_dir: bool,
+    pub size: Option<i64>,
+    pub transferred_bytes: i64,
+    pub status: RepoImportExportEntryStatus,
+}
+
+#[derive(Clone)]
+pub struct RepoImportExportState {
+    pub repo_id: String,
+    pub direction: RepoImportExportDirection,
+    pub mount_id: String,
+    /// Source for import, destination for export
+    pub remote_path: String,
+    /// Destination for import, source for export
+    pub repo_path: String,
+    pub delete_source: bool,
+    pub status: Status<RepoImportExportError>,
+    pub entries: Vec<RepoImportExportEntry>,
+    pub next_entry_id: u32,
+    pub source_deleted: bool,
+}
@@ -0,0 +1,71 @@
+use thiserror::Error;
+
+use crate::{
+    cipher::errors::DecryptFilenameError,
+    remote::RemoteError,
+    repo_files_list::errors::{FilesListRecursiveItemError, GetListRecursiveError},
+    repos::errors::{BuildCipherError, InvalidPasswordError, RepoLockedError, RepoNotFoundError},
+    user_error::UserError,
+};
+
+#[derive(Error, Debug, Clone)]
+pub enum RepoRekeyError {
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("{0}")]
+    InvalidPassword(#[from] InvalidPasswordError),
+    #[error("{0}")]
+    DecryptFilenameError(#[from] DecryptFilenameError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+    #[error("invalid rekey journal: {0}")]
+    InvalidJournal(String),
+    #[error("cannot change the password of a Safe Box in the root folder")]
+    RepoInRootFolder,
+    #[error("rekey not started")]
+    NotStarted,
+    #[error("rekey paused")]
+    Paused,
+}
+
+impl UserError for RepoRekeyError {
+    fn user_error(&self) -> String {
+        match self {
+            Self::InvalidPassword(err) => err.user_error(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl From<BuildCipherError> for RepoRekeyError {
+    fn from(err: BuildCipherError) -> Self {
+        match err {
+            BuildCipherError::RepoNotFound(err) => Self::RepoNotFound(err),
+            BuildCipherError::InvalidPassword(err) => Self::InvalidPassword(err),
+        }
+    }
+}
+
+impl From<GetListRecursiveError> for RepoRekeyError {
+    fn from(err: GetListRecursiveError) -> Self {
+        match err {
+            GetListRecursiveError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetListRecursiveError::RepoLocked(err) => Self::RepoLocked(err),
+            GetListRecursiveError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            GetListRecursiveError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<FilesListRecursiveItemError> for RepoRekeyError {
+    fn from(err: FilesListRecursiveItemError) -> Self {
+        match err {
+            FilesListRecursiveItemError::DecryptFilenameError(err) => {
+                Self::DecryptFilenameError(err)
+            }
+            FilesListRecursiveItemError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
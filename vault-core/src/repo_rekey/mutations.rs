@@ -0,0 +1,170 @@
+use crate::{common::state::Status, store};
+
+use super::{
+    errors::RepoRekeyError,
+    state::{RepoRekeyPhase, RepoRekeyState},
+};
+
+pub fn init(state: &mut store::State, repo_id: &str) {
+    state.repo_rekey = Some(RepoRekeyState {
+        repo_id: repo_id.to_owned(),
+        status: Status::Initial,
+        phase: RepoRekeyPhase::Preparing,
+        paused: false,
+        total_count: 0,
+        done_count: 0,
+        total_bytes: 0,
+        done_bytes: 0,
+    });
+}
+
+pub fn start(state: &mut store::State) -> Option<String> {
+    state.repo_rekey.as_mut().map(|repo_rekey| {
+        repo_rekey.status = Status::Loading;
+        repo_rekey.paused = false;
+
+        repo_rekey.repo_id.clone()
+    })
+}
+
+pub fn set_phase(state: &mut store::State, phase: RepoRekeyPhase) {
+    if let Some(ref mut repo_rekey) = state.repo_rekey {
+        repo_rekey.phase = phase;
+    }
+}
+
+pub fn copy_started(state: &mut store::State, total_count: u32, total_bytes: i64) {
+    if let Some(ref mut repo_rekey) = state.repo_rekey {
+        repo_rekey.total_count = total_count;
+        repo_rekey.done_count = 0;
+        repo_rekey.total_bytes = total_bytes;
+        repo_rekey.done_bytes = 0;
+    }
+}
+
+pub fn file_progress(state: &mut store::State, n: i64) {
+    if let Some(ref mut repo_rekey) = state.repo_rekey {
+        repo_rekey.done_bytes += n;
+    }
+}
+
+pub fn file_copied(state: &mut store::State) {
+    if let Some(ref mut repo_rekey) = state.repo_rekey {
+        repo_rekey.done_count += 1;
+    }
+}
+
+pub fn file_skipped(state: &mut store::State, size: i64) {
+    if let Some(ref mut repo_rekey) = state.repo_rekey {
+        repo_rekey.done_count += 1;
+        repo_rekey.done_bytes += size;
+    }
+}
+
+pub fn pause(state: &mut store::State) {
+    if let Some(ref mut repo_rekey) = state.repo_rekey {
+        repo_rekey.paused = true;
+    }
+}
+
+pub fn finish(state: &mut store::State, res: &Result<(), RepoRekeyError>) {
+    if let Some(ref mut repo_rekey) = state.repo_rekey {
+        repo_rekey.status = match res {
+            Ok(()) => Status::Loaded,
+            Err(RepoRekeyError::Paused) => Status::Initial,
+            Err(err) => Status::Error { error: err.clone() },
+        };
+
+        if res.is_ok() {
+            repo_rekey.phase = RepoRekeyPhase::Done;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        common::state::Status,
+        remote::{ApiErrorCode, RemoteError},
+        store,
+    };
+
+    use super::{
+        super::{errors::RepoRekeyError, state::RepoRekeyPhase},
+        copy_started, file_copied, file_progress, file_skipped, finish, init, pause, start,
+    };
+
+    #[test]
+    fn test_progress() {
+        let mut state = store::State::default();
+
+        init(&mut state, "r1");
+        assert_eq!(start(&mut state), Some(String::from("r1")));
+
+        copy_started(&mut state, 3, 300);
+        file_skipped(&mut state, 100);
+        file_progress(&mut state, 50);
+        file_progress(&mut state, 50);
+        file_copied(&mut state);
+
+        let repo_rekey = state.repo_rekey.as_ref().unwrap();
+        assert_eq!(repo_rekey.done_count, 2);
+        assert_eq!(repo_rekey.done_bytes, 200);
+
+        // progress is recalculated when the copy is restarted
+        copy_started(&mut state, 3, 300);
+
+        let repo_rekey = state.repo_rekey.as_ref().unwrap();
+        assert_eq!(repo_rekey.done_count, 0);
+        assert_eq!(repo_rekey.done_bytes, 0);
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let mut state = store::State::default();
+
+        init(&mut state, "r1");
+        start(&mut state);
+        pause(&mut state);
+        finish(&mut state, &Err(RepoRekeyError::Paused));
+
+        let repo_rekey = state.repo_rekey.as_ref().unwrap();
+        assert!(repo_rekey.paused);
+        assert!(matches!(repo_rekey.status, Status::Initial));
+        assert_eq!(repo_rekey.phase, RepoRekeyPhase::Preparing);
+
+        start(&mut state);
+
+        let repo_rekey = state.repo_rekey.as_ref().unwrap();
+        assert!(!repo_rekey.paused);
+        assert!(matches!(repo_rekey.status, Status::Loading));
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut state = store::State::default();
+
+        assert_eq!(start(&mut state), None);
+
+        init(&mut state, "r1");
+        start(&mut state);
+        finish(
+            &mut state,
+            &Err(RepoRekeyError::RemoteError(RemoteError::from_code(
+                ApiErrorCode::NotFound,
+                "Not found",
+            ))),
+        );
+
+        let repo_rekey = state.repo_rekey.as_ref().unwrap();
+        assert!(matches!(repo_rekey.status, Status::Error { .. }));
+        assert_eq!(repo_rekey.phase, RepoRekeyPhase::Preparing);
+
+        start(&mut state);
+        finish(&mut state, &Ok(()));
+
+        let repo_rekey = state.repo_rekey.as_ref().unwrap();
+        assert!(matches!(repo_rekey.status, Status::Loaded));
+        assert_eq!(repo_rekey.phase, RepoRekeyPhase::Done);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{repos::selectors as repos_selectors, store};
+
+use super::state::{RepoRekeyInfo, RepoRekeyPhase};
+
+pub fn select_info<'a>(state: &'a store::State) -> Option<RepoRekeyInfo<'a>> {
+    state.repo_rekey.as_ref().map(|repo_rekey| RepoRekeyInfo {
+        repo_id: &repo_rekey.repo_id,
+        status: &repo_rekey.status,
+        phase: repo_rekey.phase,
+        paused: repo_rekey.paused,
+        total_count: repo_rekey.total_count,
+        done_count: repo_rekey.done_count,
+        total_bytes: repo_rekey.total_bytes,
+        done_bytes: repo_rekey.done_bytes,
+        repo_name: repos_selectors::select_repo(state, &repo_rekey.repo_id)
+            .ok()
+            .map(|repo| repo.name.as_str()),
+    })
+}
+
+pub fn select_phase(state: &store::State) -> Option<RepoRekeyPhase> {
+    state.repo_rekey.as_ref().map(|repo_rekey| repo_rekey.phase)
+}
+
+/// Files written to the repo while it is copied into the staging folder or
+/// swapped would be lost or encrypted with the old key
+pub fn select_is_rekeying(state: &store::State, repo_id: &str) -> bool {
+    state
+        .repo_rekey
+        .as_ref()
+        .map(|repo_rekey| {
+            repo_rekey.repo_id == repo_id
+                && repo_rekey.phase > RepoRekeyPhase::Preparing
+                && repo_rekey.phase < RepoRekeyPhase::Done
+        })
+        .unwrap_or(false)
+}
+
+/// Rekey is stopped if it was paused or destroyed
+pub fn select_is_stopped(state: &store::State) -> bool {
+    state
+        .repo_rekey
+        .as_ref()
+        .map(|repo_rekey| repo_rekey.paused)
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store;
+
+    use super::{
+        super::{mutations, state::RepoRekeyPhase},
+        select_is_rekeying,
+    };
+
+    #[test]
+    fn test_select_is_rekeying() {
+        let mut state = store::State::default();
+
+        assert!(!select_is_rekeying(&state, "r1"));
+
+        mutations::init(&mut state, "r1");
+        mutations::start(&mut state);
+        assert!(!select_is_rekeying(&state, "r1"));
+
+        mutations::set_phase(&mut state, RepoRekeyPhase::Copying);
+        assert!(select_is_rekeying(&state, "r1"));
+        assert!(!select_is_rekeying(&state, "r2"));
+
+        // paused rekey still blocks changes
+        mutations::pause(&mut state);
+        mutations::set_phase(&mut state, RepoRekeyPhase::Swapping);
+        assert!(select_is_rekeying(&state, "r1"));
+
+        mutations::set_phase(&mut state, RepoRekeyPhase::Done);
+        assert!(!select_is_rekeying(&state, "r1"));
+    }
+}
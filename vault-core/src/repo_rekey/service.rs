@@ -0,0 +1,754 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+};
+
+use futures::{
+    channel::oneshot::{self, Sender},
+    io::Cursor,
+    AsyncReadExt, FutureExt, StreamExt,
+};
+
+use crate::{
+    cipher::{
+        data_cipher::{decrypt_on_progress, encrypted_size},
+        random_password::random_password,
+        Cipher,
+    },
+    remote::{models, ApiErrorCode, RemoteError, RemoteFileUploadConflictResolution},
+    remote_files::{
+        selectors as remote_files_selectors, state::RemoteFileType, RemoteFilesService,
+    },
+    repo_files::{
+        mutations as repo_files_mutations,
+        state::{RepoFile, RepoFileType},
+    },
+    repo_files_list::{state::RepoFilesListRecursiveItem, RepoFilesListService},
+    repos::{
+        errors::{InvalidPasswordError, RepoNotFoundError},
+        password_validator::{check_password_validator, generate_password_validator},
+        selectors as repos_selectors, ReposService,
+    },
+    store,
+    utils::path_utils,
+};
+
+use super::{
+    errors::RepoRekeyError,
+    mutations, selectors,
+    state::{RepoRekeyJournal, RepoRekeyLocation, RepoRekeyPhase, REKEY_JOURNAL_NAME},
+};
+
+struct RepoRekeySession {
+    old_cipher: Arc<Cipher>,
+    new_cipher: Arc<Cipher>,
+    journal: RepoRekeyJournal,
+}
+
+/// Re-encrypts all files of a repo with a new password and salt.
+///
+/// Files are copied into a staging folder next to the repo folder. When all
+/// files are copied the folders are swapped and the repo password validator is
+/// updated. A journal file in the staging folder holds the new salt and
+/// password validator so that an interrupted rekey can be continued. While a
+/// rekey is in progress the repo files cannot be changed, a repo with an
+/// interrupted rekey cannot be unlocked until the rekey is resumed.
+pub struct RepoRekeyService {
+    repos_service: Arc<ReposService>,
+    repo_files_list_service: Arc<RepoFilesListService>,
+    remote_files_service: Arc<RemoteFilesService>,
+    store: Arc<store::Store>,
+    session: Arc<RwLock<Option<Arc<RepoRekeySession>>>>,
+    abort_sender: Arc<RwLock<Option<Sender<()>>>>,
+}
+
+impl RepoRekeyService {
+    pub fn new(
+        repos_service: Arc<ReposService>,
+        repo_files_list_service: Arc<RepoFilesListService>,
+        remote_files_service: Arc<RemoteFilesService>,
+        store: Arc<store::Store>,
+    ) -> Self {
+        Self {
+            repos_service,
+            repo_files_list_service,
+            remote_files_service,
+            store,
+            session: Arc::new(RwLock::new(None)),
+            abort_sender: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub fn init(&self, repo_id: &str) {
+        *self.session.write().unwrap() = None;
+
+        self.store.mutate(store::Event::RepoRekey, |state| {
+            mutations::init(state, repo_id);
+        });
+    }
+
+    pub async fn rekey(
+        &self,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), RepoRekeyError> {
+        let repo_id = self.start()?;
+
+        let res = self.rekey_repo(&repo_id, old_password, new_password).await;
+
+        self.finish(res)
+    }
+
+    pub async fn resume(&self) -> Result<(), RepoRekeyError> {
+        let repo_id = self.start()?;
+
+        let session = self.session.read().unwrap().clone();
+
+        let res = match session {
+            Some(session) => self.run(&repo_id, &session).await,
+            None => Err(RepoRekeyError::NotStarted),
+        };
+
+        self.finish(res)
+    }
+
+    pub fn pause(&self) {
+        self.store.mutate(store::Event::RepoRekey, |state| {
+            mutations::pause(state);
+        });
+
+        self.abort_current_file();
+    }
+
+    pub fn destroy(&self, repo_id: &str) {
+        self.store.mutate(store::Event::RepoRekey, |state| {
+            if state.repo_rekey.is_some() && state.repo_rekey.as_ref().unwrap().repo_id == repo_id {
+                state.repo_rekey = None;
+            }
+        });
+
+        self.abort_current_file();
+    }
+
+    fn start(&self) -> Result<String, RepoRekeyError> {
+        self.store
+            .mutate(store::Event::RepoRekey, |state| mutations::start(state))
+            .ok_or(RepoRekeyError::RepoNotFound(RepoNotFoundError))
+    }
+
+    fn finish(&self, res: Result<(), RepoRekeyError>) -> Result<(), RepoRekeyError> {
+        if res.is_ok() {
+            *self.session.write().unwrap() = None;
+        }
+
+        self.store.mutate(store::Event::RepoRekey, |state| {
+            mutations::finish(state, &res);
+        });
+
+        res
+    }
+
+    fn set_phase(&self, phase: RepoRekeyPhase) {
+        self.store.mutate(store::Event::RepoRekey, |state| {
+            mutations::set_phase(state, phase);
+        });
+    }
+
+    fn check_stopped(&self) -> Result<(), RepoRekeyError> {
+        if self.store.with_state(selectors::select_is_stopped) {
+            Err(RepoRekeyError::Paused)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn abort_current_file(&self) {
+        if let Some(sender) = self.abort_sender.write().unwrap().take() {
+            let _ = sender.send(());
+        }
+    }
+
+    fn get_location(&self, repo_id: &str) -> Result<RepoRekeyLocation, RepoRekeyError> {
+        let location = self.store.with_state(|state| {
+            repos_selectors::select_repo(state, repo_id).map(|repo| repo.get_location())
+        })?;
+
+        RepoRekeyLocation::new(&location.mount_id, &location.path)
+            .ok_or(RepoRekeyError::RepoInRootFolder)
+    }
+
+    async fn rekey_repo(
+        &self,
+        repo_id: &str,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), RepoRekeyError> {
+        self.set_phase(RepoRekeyPhase::Preparing);
+
+        let old_cipher = self
+            .repos_service
+            .build_cipher(repo_id, old_password)
+            .await?;
+
+        // files are listed with the unlocked repo cipher
+        if self.repos_service.get_cipher(repo_id).is_err() {
+            self.repos_service
+                .unlock_repo_for_rekey(repo_id, old_password)
+                .await?;
+        }
+
+        let location = self.get_location(repo_id)?;
+
+        let (journal, phase) = self
+            .load_or_create_journal(&location, &old_cipher, new_password)
+            .await?;
+
+        let new_cipher = build_new_cipher(&old_cipher, new_password, journal.salt.as_deref());
+
+        let session = Arc::new(RepoRekeySession {
+            old_cipher: Arc::new(old_cipher),
+            new_cipher: Arc::new(new_cipher),
+            journal,
+        });
+
+        *self.session.write().unwrap() = Some(session.clone());
+
+        self.set_phase(phase);
+
+        self.run(repo_id, &session).await
+    }
+
+    async fn run(&self, repo_id: &str, session: &RepoRekeySession) -> Result<(), RepoRekeyError> {
+        let location = self.get_location(repo_id)?;
+
+        let phase = self
+            .store
+            .with_state(selectors::select_phase)
+            .ok_or(RepoRekeyError::NotStarted)?;
+
+        if phase <= RepoRekeyPhase::Copying {
+            self.set_phase(RepoRekeyPhase::Copying);
+
+            self.copy_files(repo_id, &location, session).await?;
+        }
+
+        if phase <= RepoRekeyPhase::Swapping {
+            self.check_stopped()?;
+
+            self.set_phase(RepoRekeyPhase::Swapping);
+
+            self.swap_folders(&location).await?;
+        }
+
+        self.set_phase(RepoRekeyPhase::Finishing);
+
+        self.update_repo(repo_id, &location, session).await
+    }
+
+    /// Detects where a previous rekey was interrupted. If there is nothing to
+    /// continue a new staging folder and journal are created.
+    async fn load_or_create_journal(
+        &self,
+        location: &RepoRekeyLocation,
+        old_cipher: &Cipher,
+        new_password: &str,
+    ) -> Result<(RepoRekeyJournal, RepoRekeyPhase), RepoRekeyError> {
+        let journal_phase = match self
+            .read_journal(location, &location.repo_path, old_cipher)
+            .await?
+        {
+            Some(journal) => Some((journal, RepoRekeyPhase::Finishing)),
+            None => match self
+                .read_journal(location, &location.staging_path, old_cipher)
+                .await?
+            {
+                Some(journal) => {
+                    if self.file_exists(location, &location.repo_path).await? {
+                        Some((journal, RepoRekeyPhase::Copying))
+                    } else {
+                        Some((journal, RepoRekeyPhase::Swapping))
+                    }
+                }
+                None => None,
+            },
+        };
+
+        if let Some((journal, phase)) = journal_phase {
+            let new_cipher = build_new_cipher(old_cipher, new_password, journal.salt.as_deref());
+
+            if check_password_validator(
+                &new_cipher,
+                &journal.password_validator,
+                &journal.password_validator_encrypted,
+            )
+            .await
+            {
+                return Ok((journal, phase));
+            }
+
+            if phase != RepoRekeyPhase::Copying {
+                // the repo folder already contains files encrypted with the
+                // other new password
+                return Err(RepoRekeyError::InvalidPassword(InvalidPasswordError));
+            }
+        }
+
+        // staging folder without a journal or with a different new password
+        self.delete_file_if_exists(location, &location.staging_path)
+            .await?;
+
+        let (staging_parent_path, staging_name) =
+            path_utils::split_parent_name(&location.staging_path).unwrap();
+
+        self.remote_files_service
+            .create_dir(&location.mount_id, staging_parent_path, staging_name)
+            .await?;
+
+        let salt = random_password(1024).unwrap();
+
+        let new_cipher = build_new_cipher(old_cipher, new_password, Some(&salt));
+
+        let (password_validator, password_validator_encrypted) =
+            generate_password_validator(&new_cipher).await;
+
+        let journal = RepoRekeyJournal {
+            salt: Some(salt),
+            password_validator,
+            password_validator_encrypted,
+        };
+
+        self.write_journal(location, &journal, old_cipher).await?;
+
+        Ok((journal, RepoRekeyPhase::Copying))
+    }
+
+    /// Journal contains the new salt so it is encrypted with the old key
+    async fn read_journal(
+        &self,
+        location: &RepoRekeyLocation,
+        dir_path: &str,
+        old_cipher: &Cipher,
+    ) -> Result<Option<RepoRekeyJournal>, RepoRekeyError> {
+        let path = path_utils::join_path_name(dir_path, REKEY_JOURNAL_NAME);
+
+        let file_reader = match self
+            .remote_files_service
+            .get_file_reader(&location.mount_id, &path, None)
+            .await
+        {
+            Ok(file_reader) => file_reader,
+            Err(RemoteError::ApiError {
+                code: ApiErrorCode::NotFound,
+                ..
+            }) => return Ok(None),
+            Err(err) => return Err(RepoRekeyError::RemoteError(err)),
+        };
+
+        let mut reader = file_reader.reader;
+        let mut bytes = Vec::new();
+
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(|err| RepoRekeyError::InvalidJournal(err.to_string()))?;
+
+        let mut json = Vec::new();
+
+        old_cipher
+            .decrypt_data(&bytes, &mut json)
+            .await
+            .map_err(|err| RepoRekeyError::InvalidJournal(err.to_string()))?;
+
+        serde_json::from_slice(&json)
+            .map(Some)
+            .map_err(|err| RepoRekeyError::InvalidJournal(err.to_string()))
+    }
+
+    async fn write_journal(
+        &self,
+        location: &RepoRekeyLocation,
+        journal: &RepoRekeyJournal,
+        old_cipher: &Cipher,
+    ) -> Result<(), RepoRekeyError> {
+        let json = serde_json::to_vec(journal).unwrap();
+        let mut bytes = Vec::new();
+
+        old_cipher
+            .encrypt_data(&json, &mut bytes)
+            .await
+            .map_err(|err| RepoRekeyError::InvalidJournal(err.to_string()))?;
+
+        let size = bytes.len() as i64;
+
+        self.remote_files_service
+            .upload_file_reader(
+                &location.mount_id,
+                &location.staging_path,
+                REKEY_JOURNAL_NAME,
+                Box::pin(Cursor::new(bytes)),
+                Some(size),
+                None,
+                RemoteFileUploadConflictResolution::Overwrite,
+                None,
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn file_exists(
+        &self,
+        location: &RepoRekeyLocation,
+        path: &str,
+    ) -> Result<bool, RepoRekeyError> {
+        match self
+            .remote_files_service
+            .load_file(&location.mount_id, path)
+            .await
+        {
+            Ok(()) => Ok(true),
+            Err(RemoteError::ApiError {
+                code: ApiErrorCode::NotFound,
+                ..
+            }) => Ok(false),
+            Err(err) => Err(RepoRekeyError::RemoteError(err)),
+        }
+    }
+
+    async fn delete_file_if_exists(
+        &self,
+        location: &RepoRekeyLocation,
+        path: &str,
+    ) -> Result<(), RepoRekeyError> {
+        match self
+            .remote_files_service
+            .delete_file(&location.mount_id, path)
+            .await
+        {
+            Ok(())
+            | Err(RemoteError::ApiError {
+                code: ApiErrorCode::NotFound,
+                ..
+            }) => Ok(()),
+            Err(err) => Err(RepoRekeyError::RemoteError(err)),
+        }
+    }
+
+    async fn get_root_file(
+        &self,
+        repo_id: &str,
+        location: &RepoRekeyLocation,
+    ) -> Result<RepoFile, RepoRekeyError> {
+        self.remote_files_service
+            .load_file(&location.mount_id, &location.repo_path)
+            .await?;
+
+        self.store
+            .with_state(|state| {
+                remote_files_selectors::select_file(
+                    state,
+                    &remote_files_selectors::get_file_id(&location.mount_id, &location.repo_path),
+                )
+                .map(|remote_file| repo_files_mutations::get_root_file(repo_id, remote_file))
+            })
+            .ok_or(RepoRekeyError::RepoNotFound(RepoNotFoundError))
+    }
+
+    /// Returns the encrypted sizes of already copied files and the existing
+    /// dirs in the staging folder, relative to the staging folder.
+    async fn get_staging_files(
+        &self,
+        location: &RepoRekeyLocation,
+    ) -> Result<(HashMap<String, i64>, HashSet<String>), RepoRekeyError> {
+        let mut files = HashMap::new();
+        let mut dirs = HashSet::new();
+
+        let mut items = self
+            .remote_files_service
+            .get_list_recursive(&location.mount_id, &location.staging_path)
+            .await?;
+
+        while let Some(item) = items.next().await {
+            match item? {
+                models::FilesListRecursiveItem::File { path, file } => {
+                    match RemoteFileType::from(file.typ.as_str()) {
+                        RemoteFileType::Dir => {
+                            dirs.insert(path);
+                        }
+                        RemoteFileType::File => {
+                            files.insert(path, file.size);
+                        }
+                    }
+                }
+                models::FilesListRecursiveItem::Error { error, .. } => {
+                    return Err(RepoRekeyError::RemoteError(
+                        RemoteError::from_api_error_details(error, None),
+                    ));
+                }
+            }
+        }
+
+        Ok((files, dirs))
+    }
+
+    async fn copy_files(
+        &self,
+        repo_id: &str,
+        location: &RepoRekeyLocation,
+        session: &RepoRekeySession,
+    ) -> Result<(), RepoRekeyError> {
+        let root_file = self.get_root_file(repo_id, location).await?;
+
+        let items = self
+            .repo_files_list_service
+            .get_list_recursive(&root_file)
+            .await?
+            .collect::<Vec<RepoFilesListRecursiveItem>>()
+            .await;
+
+        let mut files = Vec::with_capacity(items.len());
+
+        // all files have to be re-encrypted, so any error stops the rekey
+        for item in items {
+            match item {
+                RepoFilesListRecursiveItem::File {
+                    relative_repo_path,
+                    file,
+                } => {
+                    let relative_repo_path = relative_repo_path?;
+
+                    if relative_repo_path != "/" {
+                        files.push((relative_repo_path, file));
+                    }
+                }
+                RepoFilesListRecursiveItem::Error { error, .. } => {
+                    return Err(error.into());
+                }
+            }
+        }
+
+        let (staging_files, mut staging_dirs) = self.get_staging_files(location).await?;
+
+        let total_count = files.iter().filter(|(_, file)| file.typ.is_file()).count() as u32;
+        let total_bytes = files
+            .iter()
+            .filter(|(_, file)| file.typ.is_file())
+            .map(|(_, file)| file.size_force())
+            .sum();
+
+        self.store.mutate(store::Event::RepoRekey, |state| {
+            mutations::copy_started(state, total_count, total_bytes);
+        });
+
+        for (relative_repo_path, file) in files {
+            self.check_stopped()?;
+
+            match file.typ {
+                RepoFileType::Dir => {
+                    let path = session.new_cipher.encrypt_path(&relative_repo_path);
+
+                    self.ensure_staging_dirs(location, &path, &mut staging_dirs)
+                        .await?;
+                }
+                RepoFileType::File => {
+                    let path = session.new_cipher.encrypt_file_path(&relative_repo_path);
+                    let size = file.decrypted_size().ok();
+
+                    match (size, staging_files.get(&path)) {
+                        (Some(size), Some(staging_size))
+                            if *staging_size == encrypted_size(size) =>
+                        {
+                            self.store.mutate(store::Event::RepoRekey, |state| {
+                                mutations::file_skipped(state, size);
+                            });
+                        }
+                        _ => {
+                            let parent_path = path_utils::parent_path(&path).unwrap();
+
+                            self.ensure_staging_dirs(location, parent_path, &mut staging_dirs)
+                                .await?;
+
+                            self.copy_file(location, session, &file, &path).await?;
+
+                            self.store.mutate(store::Event::RepoRekey, |state| {
+                                mutations::file_copied(state);
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn ensure_staging_dirs(
+        &self,
+        location: &RepoRekeyLocation,
+        path: &str,
+        staging_dirs: &mut HashSet<String>,
+    ) -> Result<(), RepoRekeyError> {
+        for path in path_utils::paths_chain(path) {
+            if path == "/" || staging_dirs.contains(&path) {
+                continue;
+            }
+
+            let (parent_path, name) = path_utils::split_parent_name(&path).unwrap();
+
+            match self
+                .remote_files_service
+                .create_dir(
+                    &location.mount_id,
+                    &location.staging_file_path(parent_path),
+                    name,
+                )
+                .await
+            {
+                Ok(())
+                | Err(RemoteError::ApiError {
+                    code: ApiErrorCode::AlreadyExists,
+                    ..
+                }) => {}
+                Err(err) => return Err(RepoRekeyError::RemoteError(err)),
+            }
+
+            staging_dirs.insert(path);
+        }
+
+        Ok(())
+    }
+
+    async fn copy_file(
+        &self,
+        location: &RepoRekeyLocation,
+        session: &RepoRekeySession,
+        file: &RepoFile,
+        path: &str,
+    ) -> Result<(), RepoRekeyError> {
+        let (parent_path, name) = path_utils::split_parent_name(path).unwrap();
+
+        let file_reader = self
+            .remote_files_service
+            .get_file_reader(&file.mount_id, &file.remote_path, None)
+            .await?;
+
+        // encrypted size does not depend on the key
+        let size = file_reader.size;
+        let decrypted_reader = session.old_cipher.decrypt_reader(file_reader.reader);
+        let encrypted_reader = session.new_cipher.encrypt_reader(decrypted_reader);
+
+        let (abort_sender, abort_receiver) = oneshot::channel();
+
+        *self.abort_sender.write().unwrap() = Some(abort_sender);
+
+        let abort = Some(
+            abort_receiver
+                .map(|res| res.map_err(|_| ()))
+                .boxed()
+                .shared(),
+        );
+
+        let progress_store = self.store.clone();
+
+        let res = self
+            .remote_files_service
+            .upload_file_reader(
+                &location.mount_id,
+                &location.staging_file_path(parent_path),
+                name,
+                Box::pin(encrypted_reader),
+                Some(size),
+                Some(file.modified),
+                RemoteFileUploadConflictResolution::Overwrite,
+                Some(decrypt_on_progress(Box::new(move |n| {
+                    progress_store.mutate(store::Event::RepoRekey, |state| {
+                        mutations::file_progress(state, n as i64);
+                    });
+                }))),
+                abort,
+            )
+            .await;
+
+        self.abort_sender.write().unwrap().take();
+
+        match res {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                // upload was aborted by pause
+                self.check_stopped()?;
+
+                Err(RepoRekeyError::RemoteError(err))
+            }
+        }
+    }
+
+    async fn swap_folders(&self, location: &RepoRekeyLocation) -> Result<(), RepoRekeyError> {
+        if self.file_exists(location, &location.repo_path).await? {
+            self.delete_file_if_exists(location, &location.backup_path)
+                .await?;
+
+            self.remote_files_service
+                .move_file(
+                    &location.mount_id,
+                    &location.repo_path,
+                    &location.mount_id,
+                    &location.backup_path,
+                )
+                .await?;
+        }
+
+        self.remote_files_service
+            .move_file(
+                &location.mount_id,
+                &location.staging_path,
+                &location.mount_id,
+                &location.repo_path,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn update_repo(
+        &self,
+        repo_id: &str,
+        location: &RepoRekeyLocation,
+        session: &RepoRekeySession,
+    ) -> Result<(), RepoRekeyError> {
+        let RepoRekeyJournal {
+            salt,
+            password_validator,
+            password_validator_encrypted,
+        } = session.journal.clone();
+
+        self.repos_service
+            .update_repo_key(
+                repo_id,
+                session.new_cipher.clone(),
+                salt,
+                password_validator,
+                password_validator_encrypted,
+            )
+            .await?;
+
+        // the journal marks the repo update as pending, it is removed right
+        // after the update so that the repo can be unlocked with the new
+        // password
+        self.delete_file_if_exists(
+            location,
+            &path_utils::join_path_name(&location.repo_path, REKEY_JOURNAL_NAME),
+        )
+        .await?;
+
+        self.delete_file_if_exists(location, &location.backup_path)
+            .await
+    }
+}
+
+fn build_new_cipher(old_cipher: &Cipher, new_password: &str, salt: Option<&str>) -> Cipher {
+    Cipher::new(new_password, salt)
+        .with_filename_encryption(old_cipher.filename_encryption())
+        .with_filename_encoding(old_cipher.filename_encoding())
+        .with_directory_name_encryption(old_cipher.directory_name_encryption())
+}
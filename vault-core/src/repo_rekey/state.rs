@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{common::state::Status, utils::path_utils};
+
+use super::errors::RepoRekeyError;
+
+pub const REKEY_JOURNAL_NAME: &'static str = ".vault-rekey";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RepoRekeyPhase {
+    Preparing,
+    Copying,
+    Swapping,
+    Finishing,
+    Done,
+}
+
+pub struct RepoRekeyInfo<'a> {
+    pub repo_id: &'a str,
+    pub status: &'a Status<RepoRekeyError>,
+    pub phase: RepoRekeyPhase,
+    pub paused: bool,
+    pub total_count: u32,
+    pub done_count: u32,
+    pub total_bytes: i64,
+    pub done_bytes: i64,
+    pub repo_name: Option<&'a str>,
+}
+
+#[derive(Clone)]
+pub struct RepoRekeyState {
+    pub repo_id: String,
+    pub status: Status<RepoRekeyError>,
+    pub phase: RepoRekeyPhase,
+    pub paused: bool,
+    pub total_count: u32,
+    pub done_count: u32,
+    pub total_bytes: i64,
+    pub done_bytes: i64,
+}
+
+/// Stored next to the re-encrypted files so that an interrupted rekey can be
+/// continued with the same salt after a restart. Encrypted with the old key.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RepoRekeyJournal {
+    pub salt: Option<String>,
+    #[serde(rename = "passwordValidator")]
+    pub password_validator: String,
+    #[serde(rename = "passwordValidatorEncrypted")]
+    pub password_validator_encrypted: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepoRekeyLocation {
+    pub mount_id: String,
+    pub repo_path: String,
+    pub staging_path: String,
+    pub backup_path: String,
+}
+
+impl RepoRekeyLocation {
+    pub fn new(mount_id: &str, repo_path: &str) -> Option<Self> {
+        let (parent_path, name) = path_utils::split_parent_name(repo_path)?;
+
+        Some(Self {
+            mount_id: mount_id.to_owned(),
+            repo_path: repo_path.to_owned(),
+            staging_path: path_utils::join_path_name(parent_path, &format!("{}.rekey", name)),
+            backup_path: path_utils::join_path_name(parent_path, &format!("{}.rekey-old", name)),
+        })
+    }
+
+    pub fn staging_file_path(&self, path: &str) -> String {
+        path_utils::join_paths(&self.staging_path, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RepoRekeyLocation;
+
+    #[test]
+    fn test_repo_rekey_location_new() {
+        assert_eq!(
+            RepoRekeyLocation::new("m1", "/Vault"),
+            Some(RepoRekeyLocation {
+                mount_id: String::from("m1"),
+                repo_path: String::from("/Vault"),
+                staging_path: String::from("/Vault.rekey"),
+                backup_path: String::from("/Vault.rekey-old"),
+            })
+        );
+        assert_eq!(
+            RepoRekeyLocation::new("m1", "/Data/My safe box")
+                .unwrap()
+                .staging_path,
+            "/Data/My safe box.rekey"
+        );
+        assert_eq!(RepoRekeyLocation::new("m1", "/"), None);
+    }
+
+    #[test]
+    fn test_repo_rekey_location_staging_file_path() {
+        let location = RepoRekeyLocation::new("m1", "/Vault").unwrap();
+
+        assert_eq!(location.staging_file_path("/"), "/Vault.rekey");
+        assert_eq!(location.staging_file_path("/D1/F1"), "/Vault.rekey/D1/F1");
+    }
+}
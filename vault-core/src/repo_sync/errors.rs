@@ -0,0 +1,190 @@
+use thiserror::Error;
+
+use crate::{
+    cipher::errors::{DecryptFilenameError, DecryptHeaderError, DecryptSizeError},
+    encrypted_storage::errors::EncryptedStorageError,
+    remote::RemoteError,
+    repo_files::errors::{
+        DeleteFileError, EnsureDirError, LoadFileError, LoadFilesError, MoveFileError,
+        RenameFileError, RepoFilesErrors, UploadFileReaderError,
+    },
+    repo_files_list::errors::{FilesListRecursiveItemError, GetListRecursiveError},
+    repo_files_read::errors::GetFilesReaderError,
+    repo_trash::errors::RepoTrashError,
+    repos::errors::{RepoLockedError, RepoNotFoundError},
+    user_error::UserError,
+};
+
+#[derive(Error, Debug, Clone, PartialEq, Eq, UserError)]
+pub enum LocalFsError {
+    #[error("local file not found")]
+    NotFound,
+    #[error("local file error: {0}")]
+    IoError(String),
+}
+
+#[derive(Error, Debug, Clone, UserError)]
+pub enum RepoSyncError {
+    #[error("sync not found")]
+    SyncNotFound,
+    #[error("invalid sync database: {0}")]
+    InvalidDatabase(String),
+    #[error("sync storage error: {0}")]
+    StorageError(String),
+    #[error("{0}")]
+    LocalFsError(#[from] LocalFsError),
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("{0}")]
+    DecryptFilenameError(#[from] DecryptFilenameError),
+    #[error("{0}")]
+    DecryptSizeError(#[from] DecryptSizeError),
+    #[error("{0}")]
+    DecryptHeaderError(#[from] DecryptHeaderError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+    #[error("failed to read file: {0}")]
+    ReadError(String),
+}
+
+impl From<EncryptedStorageError> for RepoSyncError {
+    fn from(err: EncryptedStorageError) -> Self {
+        match err {
+            EncryptedStorageError::InvalidValue(err) => Self::InvalidDatabase(err),
+            EncryptedStorageError::StorageError(err) => Self::StorageError(err),
+        }
+    }
+}
+
+impl From<LoadFilesError> for RepoSyncError {
+    fn from(err: LoadFilesError) -> Self {
+        match err {
+            LoadFilesError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFilesError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFilesError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<LoadFileError> for RepoSyncError {
+    fn from(err: LoadFileError) -> Self {
+        match err {
+            LoadFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFileError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<GetListRecursiveError> for RepoSyncError {
+    fn from(err: GetListRecursiveError) -> Self {
+        match err {
+            GetListRecursiveError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetListRecursiveError::RepoLocked(err) => Self::RepoLocked(err),
+            GetListRecursiveError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            GetListRecursiveError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<FilesListRecursiveItemError> for RepoSyncError {
+    fn from(err: FilesListRecursiveItemError) -> Self {
+        match err {
+            FilesListRecursiveItemError::DecryptFilenameError(err) => {
+                Self::DecryptFilenameError(err)
+            }
+            FilesListRecursiveItemError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<GetFilesReaderError> for RepoSyncError {
+    fn from(err: GetFilesReaderError) -> Self {
+        match err {
+            GetFilesReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetFilesReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            GetFilesReaderError::FileNotFound
+            | GetFilesReaderError::FilesEmpty
+            | GetFilesReaderError::InvalidRange => Self::RemoteError(RepoFilesErrors::not_found()),
+            GetFilesReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            GetFilesReaderError::DecryptSizeError(err) => Self::DecryptSizeError(err),
+            GetFilesReaderError::DecryptHeaderError(err) => Self::DecryptHeaderError(err),
+            GetFilesReaderError::RemoteError(err) => Self::RemoteError(err),
+            GetFilesReaderError::ReadError(err) => Self::ReadError(err),
+        }
+    }
+}
+
+impl From<UploadFileReaderError> for RepoSyncError {
+    fn from(err: UploadFileReaderError) -> Self {
+        match err {
+            UploadFileReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            UploadFileReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            UploadFileReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            UploadFileReaderError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<EnsureDirError> for RepoSyncError {
+    fn from(err: EnsureDirError) -> Self {
+        match err {
+            EnsureDirError::RepoNotFound(err) => Self::RepoNotFound(err),
+            EnsureDirError::RepoLocked(err) => Self::RepoLocked(err),
+            EnsureDirError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            EnsureDirError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<DeleteFileError> for RepoSyncError {
+    fn from(err: DeleteFileError) -> Self {
+        match err {
+            DeleteFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            DeleteFileError::RepoLocked(err) => Self::RepoLocked(err),
+            DeleteFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<MoveFileError> for RepoSyncError {
+    fn from(err: MoveFileError) -> Self {
+        match err {
+            MoveFileError::InvalidPath => Self::RemoteError(RepoFilesErrors::invalid_path()),
+            MoveFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            MoveFileError::RepoLocked(err) => Self::RepoLocked(err),
+            MoveFileError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            MoveFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<RepoTrashError> for RepoSyncError {
+    fn from(err: RepoTrashError) -> Self {
+        match err {
+            RepoTrashError::InvalidPath => Self::RemoteError(RepoFilesErrors::invalid_path()),
+            RepoTrashError::EntryNotFound => Self::RemoteError(RepoFilesErrors::not_found()),
+            RepoTrashError::InvalidInfo(err) => Self::ReadError(err),
+            RepoTrashError::RepoNotFound(err) => Self::RepoNotFound(err),
+            RepoTrashError::RepoLocked(err) => Self::RepoLocked(err),
+            RepoTrashError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            RepoTrashError::DecryptSizeError(err) => Self::DecryptSizeError(err),
+            RepoTrashError::DecryptHeaderError(err) => Self::DecryptHeaderError(err),
+            RepoTrashError::RemoteError(err) => Self::RemoteError(err),
+            RepoTrashError::ReadError(err) => Self::ReadError(err),
+        }
+    }
+}
+
+impl From<RenameFileError> for RepoSyncError {
+    fn from(err: RenameFileError) -> Self {
+        match err {
+            RenameFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            RenameFileError::RepoLocked(err) => Self::RepoLocked(err),
+            RenameFileError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            RenameFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use futures::AsyncRead;
+
+use super::errors::LocalFsError;
+
+pub type LocalFsReader = Pin<Box<dyn AsyncRead + Send + Sync + 'static>>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalFsEntry {
+    /// Path relative to the synced local root, e.g. "/dir/file.txt"
+    pub path: String,
+    pub is_dir: bool,
+    /// 0 for dirs
+    pub size: i64,
+    /// Milliseconds since the epoch
+    pub modified: i64,
+}
+
+/// Local side of a sync. All paths are relative to the synced local root and
+/// use "/" as the separator, so that the platform filesystem (or an in-memory
+/// one in tests) is hidden from the sync engine.
+#[async_trait]
+pub trait LocalFs {
+    /// Returns all entries under the root (the root itself is not included).
+    async fn list(&self) -> Result<Vec<LocalFsEntry>, LocalFsError>;
+
+    async fn reader(&self, path: &str) -> Result<LocalFsReader, LocalFsError>;
+
+    /// Creates or replaces the file (and any missing parent dirs) and sets its
+    /// modification time.
+    async fn write(
+        &self,
+        path: &str,
+        reader: LocalFsReader,
+        modified: i64,
+    ) -> Result<LocalFsEntry, LocalFsError>;
+
+    /// Creates the dir and any missing parent dirs. Existing dirs are not an
+    /// error.
+    async fn create_dir(&self, path: &str) -> Result<LocalFsEntry, LocalFsError>;
+
+    /// Removes a file or a dir with all its contents.
+    async fn remove(&self, path: &str) -> Result<(), LocalFsError>;
+
+    /// Moves a file or a dir, creating missing parent dirs of to_path.
+    async fn rename(&self, path: &str, to_path: &str) -> Result<LocalFsEntry, LocalFsError>;
+}
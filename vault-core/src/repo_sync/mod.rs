@@ -0,0 +1,11 @@
+pub mod errors;
+pub mod local_fs;
+pub mod mutations;
+pub mod selectors;
+pub mod service;
+pub mod state;
+#[cfg(test)]
+pub mod test_helpers;
+
+pub use self::local_fs::LocalFs;
+pub use self::service::RepoSyncService;
@@ -0,0 +1,720 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::{
+    common::state::Status,
+    repo_files::{selectors::is_hidden_path, state::RepoFileType},
+    repo_files_list::{errors::FilesListRecursiveItemError, state::RepoFilesListRecursiveItem},
+    store,
+    utils::{name_utils, path_utils},
+};
+
+use super::{
+    errors::RepoSyncError,
+    local_fs::LocalFsEntry,
+    state::{
+        RepoSync, RepoSyncAction, RepoSyncDatabase, RepoSyncEntry, RepoSyncFile, RepoSyncSummary,
+    },
+};
+
+pub fn create(state: &mut store::State, sync_id: u32, repo_id: &str, path: &str) {
+    state.repo_sync.syncs.insert(
+        sync_id,
+        RepoSync {
+            id: sync_id,
+            repo_id: repo_id.to_owned(),
+            path: path.to_owned(),
+            status: Status::Initial,
+            last_summary: None,
+            last_synced: None,
+        },
+    );
+}
+
+pub fn syncing(state: &mut store::State, sync_id: u32) {
+    if let Some(sync) = state.repo_sync.syncs.get_mut(&sync_id) {
+        sync.status = match sync.status {
+            Status::Initial | Status::Loading | Status::Error { .. } => Status::Loading,
+            Status::Loaded | Status::Reloading => Status::Reloading,
+        };
+    }
+}
+
+pub fn synced(
+    state: &mut store::State,
+    sync_id: u32,
+    res: &Result<RepoSyncSummary, RepoSyncError>,
+    now: i64,
+) {
+    if let Some(sync) = state.repo_sync.syncs.get_mut(&sync_id) {
+        match res {
+            Ok(summary) => {
+                sync.status = Status::Loaded;
+                sync.last_summary = Some(summary.clone());
+                sync.last_synced = Some(now);
+            }
+            Err(err) => {
+                sync.status = Status::Error { error: err.clone() };
+            }
+        }
+    }
+}
+
+pub fn destroy(state: &mut store::State, sync_id: u32) {
+    state.repo_sync.syncs.remove(&sync_id);
+}
+
+pub fn local_entry_to_file(entry: &LocalFsEntry) -> RepoSyncFile {
+    RepoSyncFile {
+        is_dir: entry.is_dir,
+        size: entry.size,
+        modified: entry.modified,
+    }
+}
+
+pub fn local_entries_to_files(entries: Vec<LocalFsEntry>) -> BTreeMap<String, RepoSyncFile> {
+    entries
+        .iter()
+        .filter(|entry| entry.path != "/")
+        .map(|entry| (entry.path.clone(), local_entry_to_file(entry)))
+        .collect()
+}
+
+/// Converts a recursive list of the synced dir to files by relative path.
+/// Hidden dirs and files with names that cannot be decrypted are left out.
+pub fn list_recursive_items_to_files(
+    root_path: &str,
+    items: Vec<RepoFilesListRecursiveItem>,
+) -> Result<BTreeMap<String, RepoSyncFile>, RepoSyncError> {
+    let mut files = BTreeMap::new();
+
+    for item in items {
+        match item {
+            RepoFilesListRecursiveItem::File {
+                relative_repo_path: Ok(path),
+                file,
+            } => {
+                if path == "/" || is_hidden_path(&path_utils::join_paths(root_path, &path)) {
+                    continue;
+                }
+
+                let is_dir = file.typ == RepoFileType::Dir;
+                let size = if is_dir { 0 } else { file.decrypted_size()? };
+
+                files.insert(
+                    path,
+                    RepoSyncFile {
+                        is_dir,
+                        size,
+                        modified: file.modified,
+                    },
+                );
+            }
+            RepoFilesListRecursiveItem::File { .. } => {}
+            RepoFilesListRecursiveItem::Error {
+                error: FilesListRecursiveItemError::DecryptFilenameError(_),
+                ..
+            } => {}
+            RepoFilesListRecursiveItem::Error { error, .. } => return Err(error.into()),
+        }
+    }
+
+    Ok(files)
+}
+
+pub fn is_descendant(path: &str, parent_path: &str) -> bool {
+    path.len() > parent_path.len()
+        && path.starts_with(parent_path)
+        && (parent_path == "/" || path.as_bytes()[parent_path.len()] == b'/')
+}
+
+pub fn record(
+    database: &mut RepoSyncDatabase,
+    path: &str,
+    local: &RepoSyncFile,
+    remote_modified: i64,
+) {
+    database.entries.insert(
+        path.to_owned(),
+        RepoSyncEntry {
+            is_dir: local.is_dir,
+            size: local.size,
+            local_modified: local.modified,
+            remote_modified,
+        },
+    );
+}
+
+/// Removes the entries of the path and all its descendants
+pub fn forget(database: &mut RepoSyncDatabase, path: &str) {
+    database
+        .entries
+        .retain(|entry_path, _| entry_path != path && !is_descendant(entry_path, path));
+}
+
+/// "/dir/name.txt" => "/dir/name (conflict).txt", or "/dir/name (conflict)
+/// (1).txt" if that exists too
+pub fn conflict_path<F>(path: &str, is_dir: bool, exists: F) -> String
+where
+    F: Fn(&str) -> bool,
+{
+    let (parent_path, name) = match path_utils::split_parent_name(path) {
+        Some(val) => val,
+        None => return path.to_owned(),
+    };
+
+    let (base_name, ext) = if is_dir {
+        (name, None)
+    } else {
+        name_utils::split_name_ext(name)
+    };
+
+    let conflict_name = name_utils::join_name_ext(&format!("{} (conflict)", base_name), ext);
+
+    let name = name_utils::unused_name(&conflict_name, |name| {
+        exists(&path_utils::join_path_name(parent_path, name))
+    });
+
+    path_utils::join_path_name(parent_path, &name)
+}
+
+fn is_local_changed(local: &RepoSyncFile, entry: &RepoSyncEntry) -> bool {
+    local.is_dir != entry.is_dir
+        || (!local.is_dir && (local.size != entry.size || local.modified != entry.local_modified))
+}
+
+fn is_remote_changed(remote: &RepoSyncFile, entry: &RepoSyncEntry) -> bool {
+    remote.is_dir != entry.is_dir
+        || (!remote.is_dir
+            && (remote.size != entry.size || remote.modified != entry.remote_modified))
+}
+
+fn plan_path(
+    path: &str,
+    local: Option<&RepoSyncFile>,
+    remote: Option<&RepoSyncFile>,
+    entry: Option<&RepoSyncEntry>,
+) -> Option<RepoSyncAction> {
+    let path = path.to_owned();
+
+    match (local, remote, entry) {
+        (None, None, None) => None,
+        (None, None, Some(_)) => Some(RepoSyncAction::Forget { path }),
+        (Some(local), None, entry) => Some(match entry {
+            Some(entry) if !is_local_changed(local, entry) => RepoSyncAction::DeleteLocal { path },
+            // a local edit wins over a remote delete
+            _ if local.is_dir => RepoSyncAction::CreateRemoteDir { path },
+            _ => RepoSyncAction::Upload { path },
+        }),
+        (None, Some(remote), entry) => Some(match entry {
+            Some(entry) if !is_remote_changed(remote, entry) => {
+                RepoSyncAction::DeleteRemote { path }
+            }
+            _ if remote.is_dir => RepoSyncAction::CreateLocalDir { path },
+            _ => RepoSyncAction::Download { path },
+        }),
+        (Some(local), Some(remote), entry) => {
+            if local.is_dir && remote.is_dir {
+                return match entry {
+                    Some(entry) if entry.is_dir => None,
+                    _ => Some(RepoSyncAction::Record { path }),
+                };
+            }
+
+            if local.is_dir != remote.is_dir {
+                return Some(RepoSyncAction::Conflict { path });
+            }
+
+            let entry = match entry {
+                Some(entry) => entry,
+                // created on both sides, the service compares the content
+                None => return Some(RepoSyncAction::Conflict { path }),
+            };
+
+            match (
+                is_local_changed(local, entry),
+                is_remote_changed(remote, entry),
+            ) {
+                (false, false) => None,
+                (true, false) => Some(RepoSyncAction::Upload { path }),
+                (false, true) => Some(RepoSyncAction::Download { path }),
+                (true, true) => Some(RepoSyncAction::Conflict { path }),
+            }
+        }
+    }
+}
+
+/// A deleted file and a new file with the same size and modification time on
+/// the same side are a move. Only the new side is compared because the
+/// content hash is not part of the recursive listing.
+fn detect_moves(
+    actions: Vec<RepoSyncAction>,
+    local: &BTreeMap<String, RepoSyncFile>,
+    remote: &BTreeMap<String, RepoSyncFile>,
+    database: &RepoSyncDatabase,
+) -> Vec<RepoSyncAction> {
+    let is_new = |path: &str| !database.entries.contains_key(path);
+
+    let mut local_deleted: Vec<&str> = Vec::new();
+    let mut remote_deleted: Vec<&str> = Vec::new();
+
+    for action in &actions {
+        match action {
+            // deleted locally
+            RepoSyncAction::DeleteRemote { path } => local_deleted.push(path),
+            // deleted in the repo
+            RepoSyncAction::DeleteLocal { path } => remote_deleted.push(path),
+            _ => {}
+        }
+    }
+
+    let find_source = |deleted: &[&str],
+                       used: &HashSet<String>,
+                       file: &RepoSyncFile,
+                       is_local: bool|
+     -> Option<String> {
+        deleted
+            .iter()
+            .find(|path| {
+                !used.contains(**path)
+                    && database.entries.get(**path).map_or(false, |entry| {
+                        !entry.is_dir
+                            && entry.size == file.size
+                            && if is_local {
+                                entry.local_modified == file.modified
+                            } else {
+                                entry.remote_modified == file.modified
+                            }
+                    })
+            })
+            .map(|path| (*path).to_owned())
+    };
+
+    let mut used: HashSet<String> = HashSet::new();
+    let mut moves: HashMap<String, String> = HashMap::new();
+
+    for action in &actions {
+        match action {
+            RepoSyncAction::Upload { path } if is_new(path) => {
+                if let Some(from) = find_source(&local_deleted, &used, &local[path], true) {
+                    used.insert(from.clone());
+                    moves.insert(path.clone(), from);
+                }
+            }
+            RepoSyncAction::Download { path } if is_new(path) => {
+                if let Some(from) = find_source(&remote_deleted, &used, &remote[path], false) {
+                    used.insert(from.clone());
+                    moves.insert(path.clone(), from);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    actions
+        .into_iter()
+        .filter_map(|action| match action {
+            RepoSyncAction::DeleteRemote { ref path }
+            | RepoSyncAction::DeleteLocal { ref path }
+                if used.contains(path) =>
+            {
+                None
+            }
+            RepoSyncAction::Upload { path } => Some(match moves.remove(&path) {
+                Some(from) => RepoSyncAction::MoveRemote {
+                    path: from,
+                    to_path: path,
+                },
+                None => RepoSyncAction::Upload { path },
+            }),
+            RepoSyncAction::Download { path } => Some(match moves.remove(&path) {
+                Some(from) => RepoSyncAction::MoveLocal {
+                    path: from,
+                    to_path: path,
+                },
+                None => RepoSyncAction::Download { path },
+            }),
+            action => Some(action),
+        })
+        .collect()
+}
+
+/// A deleted dir is restored if something inside it is still synced, then the
+/// deletes of paths inside deleted dirs are dropped (the dir delete removes
+/// them) and so are the actions inside a local dir that is renamed because of
+/// a conflict (it is uploaded as new on the next sync).
+fn resolve_dirs(
+    actions: Vec<RepoSyncAction>,
+    local: &BTreeMap<String, RepoSyncFile>,
+) -> Vec<RepoSyncAction> {
+    let kept: Vec<String> = actions
+        .iter()
+        .filter(|action| action.phase() < 4)
+        .map(|action| action.path().to_owned())
+        .collect();
+    let has_kept_descendant = |path: &str| kept.iter().any(|kept| is_descendant(kept, path));
+
+    let actions: Vec<RepoSyncAction> = actions
+        .into_iter()
+        .map(|action| match action {
+            RepoSyncAction::DeleteRemote { path } if has_kept_descendant(&path) => {
+                RepoSyncAction::CreateLocalDir { path }
+            }
+            RepoSyncAction::DeleteLocal { path } if has_kept_descendant(&path) => {
+                RepoSyncAction::CreateRemoteDir { path }
+            }
+            action => action,
+        })
+        .collect();
+
+    let mut local_deleted: Vec<String> = Vec::new();
+    let mut remote_deleted: Vec<String> = Vec::new();
+    let mut local_renamed: Vec<String> = Vec::new();
+
+    for action in &actions {
+        match action {
+            RepoSyncAction::DeleteLocal { path } => local_deleted.push(path.clone()),
+            RepoSyncAction::DeleteRemote { path } => remote_deleted.push(path.clone()),
+            RepoSyncAction::Conflict { path }
+                if local.get(path).map_or(false, |file| file.is_dir) =>
+            {
+                local_renamed.push(path.clone())
+            }
+            _ => {}
+        }
+    }
+
+    let is_inside =
+        |paths: &[String], path: &str| paths.iter().any(|parent| is_descendant(path, parent));
+
+    actions
+        .into_iter()
+        .filter(|action| match action {
+            _ if is_inside(&local_renamed, action.path()) => false,
+            RepoSyncAction::DeleteLocal { path } => !is_inside(&local_deleted, path),
+            RepoSyncAction::DeleteRemote { path } => !is_inside(&remote_deleted, path),
+            _ => true,
+        })
+        .collect()
+}
+
+/// Three-way diff of the local and remote files against the database from
+/// the last sync. Returns the actions in the order they have to be applied.
+pub fn plan(
+    local: &BTreeMap<String, RepoSyncFile>,
+    remote: &BTreeMap<String, RepoSyncFile>,
+    database: &RepoSyncDatabase,
+) -> Vec<RepoSyncAction> {
+    let paths: BTreeSet<&str> = local
+        .keys()
+        .chain(remote.keys())
+        .chain(database.entries.keys())
+        .map(|path| path.as_str())
+        .collect();
+
+    let actions = paths
+        .into_iter()
+        .filter_map(|path| {
+            plan_path(
+                path,
+                local.get(path),
+                remote.get(path),
+                database.entries.get(path),
+            )
+        })
+        .collect();
+
+    let actions = detect_moves(actions, local, remote, database);
+
+    let mut actions = resolve_dirs(actions, local);
+
+    actions.sort_by(|a, b| {
+        a.phase()
+            .cmp(&b.phase())
+            .then_with(|| a.path().cmp(b.path()))
+    });
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use futures::executor::block_on;
+
+    use crate::repo_sync::{
+        local_fs::LocalFs,
+        state::{RepoSyncAction, RepoSyncDatabase, RepoSyncEntry, RepoSyncFile},
+        test_helpers::MemoryLocalFs,
+    };
+
+    use super::{conflict_path, is_descendant, local_entries_to_files, plan, record};
+
+    fn file(size: i64, modified: i64) -> RepoSyncFile {
+        RepoSyncFile {
+            is_dir: false,
+            size,
+            modified,
+        }
+    }
+
+    fn dir() -> RepoSyncFile {
+        RepoSyncFile {
+            is_dir: true,
+            size: 0,
+            modified: 0,
+        }
+    }
+
+    fn files(files: &[(&str, RepoSyncFile)]) -> BTreeMap<String, RepoSyncFile> {
+        files
+            .iter()
+            .map(|(path, file)| (path.to_string(), file.clone()))
+            .collect()
+    }
+
+    fn database(entries: &[(&str, bool, i64, i64, i64)]) -> RepoSyncDatabase {
+        RepoSyncDatabase {
+            entries: entries
+                .iter()
+                .map(|(path, is_dir, size, local_modified, remote_modified)| {
+                    (
+                        path.to_string(),
+                        RepoSyncEntry {
+                            is_dir: *is_dir,
+                            size: *size,
+                            local_modified: *local_modified,
+                            remote_modified: *remote_modified,
+                        },
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_initial() {
+        let local = files(&[("/a", dir()), ("/a/1.txt", file(1, 10)), ("/b", dir())]);
+        let remote = files(&[("/b", dir()), ("/c.txt", file(3, 30))]);
+
+        assert_eq!(
+            plan(&local, &remote, &RepoSyncDatabase::default()),
+            vec![
+                RepoSyncAction::CreateRemoteDir {
+                    path: String::from("/a")
+                },
+                RepoSyncAction::Upload {
+                    path: String::from("/a/1.txt")
+                },
+                RepoSyncAction::Record {
+                    path: String::from("/b")
+                },
+                RepoSyncAction::Download {
+                    path: String::from("/c.txt")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_changes() {
+        let database = database(&[
+            ("/edited-local.txt", false, 1, 10, 100),
+            ("/edited-remote.txt", false, 1, 10, 100),
+            ("/edited-both.txt", false, 1, 10, 100),
+            ("/deleted-local.txt", false, 1, 10, 100),
+            ("/deleted-remote.txt", false, 1, 10, 100),
+            ("/deleted-both.txt", false, 1, 10, 100),
+            ("/deleted-local-edited-remote.txt", false, 1, 10, 100),
+            ("/same.txt", false, 1, 10, 100),
+        ]);
+        let local = files(&[
+            ("/edited-local.txt", file(2, 20)),
+            ("/edited-remote.txt", file(1, 10)),
+            ("/edited-both.txt", file(2, 20)),
+            ("/deleted-remote.txt", file(1, 10)),
+            ("/same.txt", file(1, 10)),
+        ]);
+        let remote = files(&[
+            ("/edited-local.txt", file(1, 100)),
+            ("/edited-remote.txt", file(1, 200)),
+            ("/edited-both.txt", file(3, 200)),
+            ("/deleted-local.txt", file(1, 100)),
+            ("/deleted-local-edited-remote.txt", file(5, 200)),
+            ("/same.txt", file(1, 100)),
+        ]);
+
+        assert_eq!(
+            plan(&local, &remote, &database),
+            vec![
+                RepoSyncAction::Conflict {
+                    path: String::from("/edited-both.txt")
+                },
+                RepoSyncAction::Download {
+                    path: String::from("/deleted-local-edited-remote.txt")
+                },
+                RepoSyncAction::Upload {
+                    path: String::from("/edited-local.txt")
+                },
+                RepoSyncAction::Download {
+                    path: String::from("/edited-remote.txt")
+                },
+                RepoSyncAction::Forget {
+                    path: String::from("/deleted-both.txt")
+                },
+                RepoSyncAction::DeleteRemote {
+                    path: String::from("/deleted-local.txt")
+                },
+                RepoSyncAction::DeleteLocal {
+                    path: String::from("/deleted-remote.txt")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_moves() {
+        let database = database(&[
+            ("/a.txt", false, 1, 10, 100),
+            ("/b.txt", false, 2, 20, 200),
+            ("/dir", true, 0, 0, 0),
+        ]);
+        let local = files(&[
+            ("/dir", dir()),
+            ("/dir/a.txt", file(1, 10)),
+            ("/b.txt", file(2, 20)),
+        ]);
+        let remote = files(&[
+            ("/dir", dir()),
+            ("/a.txt", file(1, 100)),
+            ("/c.txt", file(2, 200)),
+        ]);
+
+        assert_eq!(
+            plan(&local, &remote, &database),
+            vec![
+                RepoSyncAction::MoveLocal {
+                    path: String::from("/b.txt"),
+                    to_path: String::from("/c.txt")
+                },
+                RepoSyncAction::MoveRemote {
+                    path: String::from("/a.txt"),
+                    to_path: String::from("/dir/a.txt")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_deleted_dirs() {
+        let database = database(&[
+            ("/deleted", true, 0, 0, 0),
+            ("/deleted/a.txt", false, 1, 10, 100),
+            ("/deleted/sub", true, 0, 0, 0),
+            ("/kept", true, 0, 0, 0),
+            ("/kept/a.txt", false, 1, 10, 100),
+            ("/kept/b.txt", false, 1, 10, 100),
+        ]);
+        let local = files(&[
+            ("/deleted", dir()),
+            ("/deleted/a.txt", file(1, 10)),
+            ("/deleted/sub", dir()),
+            ("/kept", dir()),
+            ("/kept/a.txt", file(1, 10)),
+            ("/kept/b.txt", file(2, 20)),
+        ]);
+        let remote = files(&[]);
+
+        assert_eq!(
+            plan(&local, &remote, &database),
+            vec![
+                RepoSyncAction::CreateRemoteDir {
+                    path: String::from("/kept")
+                },
+                RepoSyncAction::Upload {
+                    path: String::from("/kept/b.txt")
+                },
+                RepoSyncAction::DeleteLocal {
+                    path: String::from("/deleted")
+                },
+                RepoSyncAction::DeleteLocal {
+                    path: String::from("/kept/a.txt")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_type_conflict() {
+        let local = files(&[("/x", dir()), ("/x/a.txt", file(1, 10))]);
+        let remote = files(&[("/x", file(2, 20))]);
+
+        assert_eq!(
+            plan(&local, &remote, &RepoSyncDatabase::default()),
+            vec![RepoSyncAction::Conflict {
+                path: String::from("/x")
+            }]
+        );
+    }
+
+    #[test]
+    fn test_plan_local_dir_moved() {
+        let fs = MemoryLocalFs::new();
+        fs.add_file("/dir/a.txt", b"aaa", 10);
+
+        let remote = files(&[("/dir", dir()), ("/dir/a.txt", file(3, 100))]);
+
+        let local = local_entries_to_files(block_on(fs.list()).unwrap());
+        let mut database = RepoSyncDatabase::default();
+        for (path, file) in &local {
+            record(&mut database, path, file, remote[path].modified);
+        }
+
+        assert_eq!(plan(&local, &remote, &database), vec![]);
+
+        block_on(fs.rename("/dir", "/moved")).unwrap();
+
+        let local = local_entries_to_files(block_on(fs.list()).unwrap());
+
+        assert_eq!(
+            plan(&local, &remote, &database),
+            vec![
+                RepoSyncAction::CreateRemoteDir {
+                    path: String::from("/moved")
+                },
+                RepoSyncAction::MoveRemote {
+                    path: String::from("/dir/a.txt"),
+                    to_path: String::from("/moved/a.txt")
+                },
+                RepoSyncAction::DeleteRemote {
+                    path: String::from("/dir")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conflict_path() {
+        assert_eq!(
+            conflict_path("/dir/file.txt", false, |_| false),
+            "/dir/file (conflict).txt"
+        );
+        assert_eq!(
+            conflict_path("/file.txt", false, |path| path == "/file (conflict).txt"),
+            "/file (conflict) (1).txt"
+        );
+        assert_eq!(
+            conflict_path("/my.dir", true, |_| false),
+            "/my.dir (conflict)"
+        );
+    }
+
+    #[test]
+    fn test_is_descendant() {
+        assert!(is_descendant("/a/b", "/a"));
+        assert!(is_descendant("/a", "/"));
+        assert!(!is_descendant("/a", "/a"));
+        assert!(!is_descendant("/ab", "/a"));
+    }
+}
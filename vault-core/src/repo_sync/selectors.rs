@@ -0,0 +1,7 @@
+use crate::store;
+
+use super::state::RepoSync;
+
+pub fn select_sync<'a>(state: &'a store::State, sync_id: u32) -> Option<&'a RepoSync> {
+    state.repo_sync.syncs.get(&sync_id)
+}
@@ -0,0 +1,676 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use futures::{lock::Mutex as AsyncMutex, StreamExt};
+
+use crate::{
+    encrypted_storage::{EncryptedStorage, EncryptedStorageService},
+    remote::{ApiErrorCode, RemoteError},
+    repo_files::{
+        errors::{RepoFilesErrors, UploadFileReaderError},
+        selectors as repo_files_selectors,
+        state::{RepoFile, RepoFilesUploadConflictResolution},
+        RepoFilesService,
+    },
+    repo_files_list::RepoFilesListService,
+    repo_trash::RepoTrashService,
+    repos::ReposService,
+    store,
+    utils::{
+        hash_reader::{ContentHasher, HashReader},
+        path_utils,
+    },
+};
+
+use super::{
+    errors::{LocalFsError, RepoSyncError},
+    local_fs::LocalFs,
+    mutations,
+    state::{get_storage_key, RepoSyncAction, RepoSyncDatabase, RepoSyncFile, RepoSyncSummary},
+};
+
+struct RepoSyncHandle {
+    repo_id: String,
+    path: String,
+    local_fs: Box<dyn LocalFs + Send + Sync>,
+    storage: EncryptedStorageService,
+    /// Only one pass of a sync can run at a time
+    pass_lock: AsyncMutex<()>,
+}
+
+/// Two-way sync between a local tree and a dir in a repo. Each pass compares
+/// both sides with the database from the previous pass and propagates
+/// creates, updates, deletes and moves. Passes are started by the caller
+/// (e.g. on a timer or on local changes).
+pub struct RepoSyncService {
+    repos_service: Arc<ReposService>,
+    repo_files_service: Arc<RepoFilesService>,
+    repo_files_list_service: Arc<RepoFilesListService>,
+    repo_trash_service: Arc<RepoTrashService>,
+    store: Arc<store::Store>,
+
+    syncs: Mutex<HashMap<u32, Arc<RepoSyncHandle>>>,
+}
+
+impl RepoSyncService {
+    pub fn new(
+        repos_service: Arc<ReposService>,
+        repo_files_service: Arc<RepoFilesService>,
+        repo_files_list_service: Arc<RepoFilesListService>,
+        repo_trash_service: Arc<RepoTrashService>,
+        store: Arc<store::Store>,
+    ) -> Self {
+        Self {
+            repos_service,
+            repo_files_service,
+            repo_files_list_service,
+            repo_trash_service,
+            store,
+
+            syncs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn now(&self) -> i64 {
+        instant::now() as i64
+    }
+
+    /// The storage keeps the sync database of this local root, so it has to be
+    /// different for every local root.
+    pub fn create(
+        &self,
+        repo_id: &str,
+        path: &str,
+        local_fs: Box<dyn LocalFs + Send + Sync>,
+        storage: Box<dyn EncryptedStorage + Send + Sync>,
+    ) -> u32 {
+        let sync_id = self.store.get_next_id();
+
+        self.syncs.lock().unwrap().insert(
+            sync_id,
+            Arc::new(RepoSyncHandle {
+                repo_id: repo_id.to_owned(),
+                path: path.to_owned(),
+                local_fs,
+                storage: EncryptedStorageService::new(storage),
+                pass_lock: AsyncMutex::new(()),
+            }),
+        );
+
+        self.store.mutate(store::Event::RepoSync, |state| {
+            mutations::create(state, sync_id, repo_id, path);
+        });
+
+        sync_id
+    }
+
+    /// Runs one sync pass. Progress is saved even if an action fails, so the
+    /// next pass continues from there.
+    pub async fn sync(&self, sync_id: u32) -> Result<RepoSyncSummary, RepoSyncError> {
+        let handle = self
+            .syncs
+            .lock()
+            .unwrap()
+            .get(&sync_id)
+            .cloned()
+            .ok_or(RepoSyncError::SyncNotFound)?;
+
+        let _pass_guard = handle.pass_lock.lock().await;
+
+        self.store.mutate(store::Event::RepoSync, |state| {
+            mutations::syncing(state, sync_id);
+        });
+
+        let res = self.sync_pass(&handle).await;
+
+        self.store.mutate(store::Event::RepoSync, |state| {
+            mutations::synced(state, sync_id, &res, self.now());
+        });
+
+        res
+    }
+
+    /// Stops the sync and removes its database.
+    pub fn destroy(&self, sync_id: u32) -> Result<(), RepoSyncError> {
+        let handle = self
+            .syncs
+            .lock()
+            .unwrap()
+            .remove(&sync_id)
+            .ok_or(RepoSyncError::SyncNotFound)?;
+
+        self.store.mutate(store::Event::RepoSync, |state| {
+            mutations::destroy(state, sync_id);
+        });
+
+        Ok(handle
+            .storage
+            .remove(&get_storage_key(&handle.repo_id, &handle.path))?)
+    }
+
+    async fn sync_pass(&self, handle: &RepoSyncHandle) -> Result<RepoSyncSummary, RepoSyncError> {
+        let mut database = self.read_database(handle).await?.unwrap_or_default();
+
+        self.repo_files_service
+            .clone()
+            .ensure_dirs(&handle.repo_id, &handle.path)
+            .await?;
+
+        let local = mutations::local_entries_to_files(handle.local_fs.list().await?);
+        let remote = self.list_remote(handle).await?;
+
+        let mut summary = RepoSyncSummary::default();
+        let mut res = Ok(());
+
+        for action in mutations::plan(&local, &remote, &database) {
+            if let Err(err) = self
+                .apply(handle, &local, &remote, &mut database, &mut summary, action)
+                .await
+            {
+                res = Err(err);
+
+                break;
+            }
+        }
+
+        self.write_database(handle, &database).await?;
+
+        res.map(|_| summary)
+    }
+
+    async fn list_remote(
+        &self,
+        handle: &RepoSyncHandle,
+    ) -> Result<BTreeMap<String, RepoSyncFile>, RepoSyncError> {
+        let root_file = self.get_remote_file(&handle.repo_id, &handle.path).await?;
+
+        let items = self
+            .repo_files_list_service
+            .get_list_recursive(&root_file)
+            .await?
+            .collect::<Vec<_>>()
+            .await;
+
+        mutations::list_recursive_items_to_files(&handle.path, items)
+    }
+
+    async fn apply(
+        &self,
+        handle: &RepoSyncHandle,
+        local: &BTreeMap<String, RepoSyncFile>,
+        remote: &BTreeMap<String, RepoSyncFile>,
+        database: &mut RepoSyncDatabase,
+        summary: &mut RepoSyncSummary,
+        action: RepoSyncAction,
+    ) -> Result<(), RepoSyncError> {
+        let remote_modified = |path: &str| remote.get(path).map(|file| file.modified).unwrap_or(0);
+
+        match action {
+            RepoSyncAction::Conflict { path } => {
+                let local_file = &local[&path];
+                let remote_file = &remote[&path];
+
+                if !local_file.is_dir
+                    && !remote_file.is_dir
+                    && self.is_same_content(handle, &path).await?
+                {
+                    mutations::record(database, &path, local_file, remote_file.modified);
+
+                    return Ok(());
+                }
+
+                let conflict_path =
+                    mutations::conflict_path(&path, local_file.is_dir, |conflict_path| {
+                        local.contains_key(conflict_path) || remote.contains_key(conflict_path)
+                    });
+
+                let conflict_entry = handle.local_fs.rename(&path, &conflict_path).await?;
+                mutations::forget(database, &path);
+                summary.conflicts += 1;
+
+                if remote_file.is_dir {
+                    let entry = handle.local_fs.create_dir(&path).await?;
+                    mutations::record(database, &path, &mutations::local_entry_to_file(&entry), 0);
+                } else {
+                    self.download(handle, database, &path).await?;
+                    summary.downloaded += 1;
+                }
+
+                // a renamed dir is uploaded as new by the next pass
+                if !conflict_entry.is_dir
+                    && self
+                        .upload(
+                            handle,
+                            database,
+                            &conflict_path,
+                            &mutations::local_entry_to_file(&conflict_entry),
+                            None,
+                        )
+                        .await?
+                {
+                    summary.uploaded += 1;
+                }
+            }
+            RepoSyncAction::CreateLocalDir { path } => {
+                let entry = handle.local_fs.create_dir(&path).await?;
+
+                mutations::record(
+                    database,
+                    &path,
+                    &mutations::local_entry_to_file(&entry),
+                    remote_modified(&path),
+                );
+            }
+            RepoSyncAction::CreateRemoteDir { path } => {
+                self.repo_files_service
+                    .clone()
+                    .ensure_dirs(&handle.repo_id, &self.repo_path(handle, &path))
+                    .await?;
+
+                mutations::record(database, &path, &local[&path], 0);
+            }
+            RepoSyncAction::MoveLocal { path, to_path } => {
+                let entry = handle.local_fs.rename(&path, &to_path).await?;
+
+                mutations::forget(database, &path);
+                mutations::record(
+                    database,
+                    &to_path,
+                    &mutations::local_entry_to_file(&entry),
+                    remote_modified(&to_path),
+                );
+                summary.moved_local += 1;
+            }
+            RepoSyncAction::MoveRemote { path, to_path } => {
+                let remote_file = self.move_remote(handle, &path, &to_path).await?;
+
+                mutations::forget(database, &path);
+                mutations::record(database, &to_path, &local[&to_path], remote_file.modified);
+                summary.moved_remote += 1;
+            }
+            RepoSyncAction::Download { path } => {
+                self.download(handle, database, &path).await?;
+                summary.downloaded += 1;
+            }
+            RepoSyncAction::Upload { path } => {
+                if self
+                    .upload(handle, database, &path, &local[&path], remote.get(&path))
+                    .await?
+                {
+                    summary.uploaded += 1;
+                }
+            }
+            RepoSyncAction::Record { path } => {
+                mutations::record(database, &path, &local[&path], remote_modified(&path));
+            }
+            RepoSyncAction::DeleteLocal { path } => {
+                match handle.local_fs.remove(&path).await {
+                    Ok(()) | Err(LocalFsError::NotFound) => {}
+                    Err(err) => return Err(err.into()),
+                }
+
+                mutations::forget(database, &path);
+                summary.deleted_local += 1;
+            }
+            RepoSyncAction::DeleteRemote { path } => {
+                // remote deletes can be undone from the trash
+                self.repo_trash_service
+                    .trash_file(&handle.repo_id, &self.repo_path(handle, &path))
+                    .await?;
+
+                mutations::forget(database, &path);
+                summary.deleted_remote += 1;
+            }
+            RepoSyncAction::Forget { path } => {
+                mutations::forget(database, &path);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn repo_path(&self, handle: &RepoSyncHandle, path: &str) -> String {
+        path_utils::join_paths(&handle.path, path)
+    }
+
+    /// Loads the file into the state (needed to read it) and returns it
+    async fn get_remote_file(&self, repo_id: &str, path: &str) -> Result<RepoFile, RepoSyncError> {
+        if path == "/" {
+            self.repo_files_service.load_files(repo_id, path).await?;
+        } else {
+            self.repo_files_service.load_file(repo_id, path).await?;
+        }
+
+        self.store
+            .with_state(|state| {
+                repo_files_selectors::select_file(
+                    state,
+                    &repo_files_selectors::get_file_id(repo_id, path),
+                )
+                .cloned()
+            })
+            .ok_or_else(|| RepoSyncError::RemoteError(RepoFilesErrors::not_found()))
+    }
+
+    async fn download(
+        &self,
+        handle: &RepoSyncHandle,
+        database: &mut RepoSyncDatabase,
+        path: &str,
+    ) -> Result<(), RepoSyncError> {
+        let file = self
+            .get_remote_file(&handle.repo_id, &self.repo_path(handle, path))
+            .await?;
+
+        let reader = self
+            .repo_files_service
+            .clone()
+            .get_file_reader(&file.id, None)
+            .await?;
+
+        let entry = handle
+            .local_fs
+            .write(path, reader.reader, file.modified)
+            .await?;
+
+        mutations::record(
+            database,
+            path,
+            &mutations::local_entry_to_file(&entry),
+            file.modified,
+        );
+
+        Ok(())
+    }
+
+    /// Uploads the local file over the listed remote file. The remote file is
+    /// only overwritten if it did not change since it was listed (a change
+    /// between the check and the upload is still lost) and a new file does not
+    /// overwrite one created in the meantime. Otherwise nothing is recorded,
+    /// the next pass sees both sides changed and creates a conflict copy.
+    /// Returns false if the upload was skipped.
+    async fn upload(
+        &self,
+        handle: &RepoSyncHandle,
+        database: &mut RepoSyncDatabase,
+        path: &str,
+        local_file: &RepoSyncFile,
+        listed_remote_file: Option<&RepoSyncFile>,
+    ) -> Result<bool, RepoSyncError> {
+        let repo_path = self.repo_path(handle, path);
+        let (parent_path, name) = path_utils::split_parent_name(&repo_path)
+            .ok_or_else(|| RepoSyncError::RemoteError(RepoFilesErrors::invalid_path()))?;
+
+        let conflict_resolution = match listed_remote_file {
+            Some(listed_remote_file) => {
+                match self.get_remote_file(&handle.repo_id, &repo_path).await {
+                    Ok(remote_file) if remote_file.modified == listed_remote_file.modified => {}
+                    Ok(_)
+                    | Err(RepoSyncError::RemoteError(RemoteError::ApiError {
+                        code: ApiErrorCode::NotFound,
+                        ..
+                    })) => return Ok(false),
+                    Err(err) => return Err(err),
+                }
+
+                RepoFilesUploadConflictResolution::Overwrite
+            }
+            None => RepoFilesUploadConflictResolution::Error,
+        };
+
+        let reader = handle.local_fs.reader(path).await?;
+
+        match self
+            .repo_files_service
+            .clone()
+            .upload_file_reader(
+                &handle.repo_id,
+                parent_path,
+                name,
+                reader,
+                Some(local_file.size),
+                Some(local_file.modified),
+                conflict_resolution,
+                None,
+                None,
+            )
+            .await
+        {
+            Ok(_) => {}
+            Err(UploadFileReaderError::RemoteError(RemoteError::ApiError {
+                code: ApiErrorCode::AlreadyExists,
+                ..
+            })) => return Ok(false),
+            Err(err) => return Err(err.into()),
+        }
+
+        let remote_file = self.get_remote_file(&handle.repo_id, &repo_path).await?;
+
+        mutations::record(database, path, local_file, remote_file.modified);
+
+        Ok(true)
+    }
+
+    async fn move_remote(
+        &self,
+        handle: &RepoSyncHandle,
+        path: &str,
+        to_path: &str,
+    ) -> Result<RepoFile, RepoSyncError> {
+        let repo_path = self.repo_path(handle, path);
+        let to_repo_path = self.repo_path(handle, to_path);
+
+        let (parent_path, name) = path_utils::split_parent_name(&repo_path)
+            .ok_or_else(|| RepoSyncError::RemoteError(RepoFilesErrors::invalid_path()))?;
+        let (to_parent_path, to_name) = path_utils::split_parent_name(&to_repo_path)
+            .ok_or_else(|| RepoSyncError::RemoteError(RepoFilesErrors::invalid_path()))?;
+
+        if parent_path != to_parent_path {
+            self.repo_files_service
+                .clone()
+                .ensure_dirs(&handle.repo_id, to_parent_path)
+                .await?;
+
+            self.repo_files_service
+                .move_file(&handle.repo_id, &repo_path, to_parent_path)
+                .await?;
+        }
+
+        if name != to_name {
+            let moved_path = path_utils::join_path_name(to_parent_path, name);
+
+            // the file type is needed to encrypt the new name
+            self.get_remote_file(&handle.repo_id, &moved_path).await?;
+
+            self.repo_files_service
+                .rename_file(&handle.repo_id, &moved_path, to_name)
+                .await?;
+        }
+
+        self.get_remote_file(&handle.repo_id, &to_repo_path).await
+    }
+
+    /// Files created or changed on both sides are not a conflict if the
+    /// content is the same. Remote files without a content hash always
+    /// conflict.
+    async fn is_same_content(
+        &self,
+        handle: &RepoSyncHandle,
+        path: &str,
+    ) -> Result<bool, RepoSyncError> {
+        let remote_file = self
+            .get_remote_file(&handle.repo_id, &self.repo_path(handle, path))
+            .await?;
+
+        let content_hash = match remote_file.content_hash {
+            Some(content_hash) => content_hash,
+            None => return Ok(false),
+        };
+
+        let hasher = ContentHasher::new();
+        let reader = HashReader::new(handle.local_fs.reader(path).await?, hasher.clone());
+
+        futures::io::copy(reader, &mut futures::io::sink())
+            .await
+            .map_err(|err| LocalFsError::IoError(err.to_string()))?;
+
+        Ok(hasher.hex_digest() == content_hash)
+    }
+
+    async fn read_database(
+        &self,
+        handle: &RepoSyncHandle,
+    ) -> Result<Option<RepoSyncDatabase>, RepoSyncError> {
+        let cipher = self.repos_service.get_cipher(&handle.repo_id)?;
+
+        Ok(handle
+            .storage
+            .get(&get_storage_key(&handle.repo_id, &handle.path), &cipher)
+            .await?)
+    }
+
+    async fn write_database(
+        &self,
+        handle: &RepoSyncHandle,
+        database: &RepoSyncDatabase,
+    ) -> Result<(), RepoSyncError> {
+        let cipher = self.repos_service.get_cipher(&handle.repo_id)?;
+
+        Ok(handle
+            .storage
+            .set(
+                &get_storage_key(&handle.repo_id, &handle.path),
+                &cipher,
+                database,
+            )
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::executor::block_on;
+
+    use crate::{
+        encrypted_storage::memory_encrypted_storage::MemoryEncryptedStorage,
+        repo_files::test_helpers::{TestContext, TEST_MOUNT_ID},
+        repo_sync::{local_fs::LocalFs, test_helpers::MemoryLocalFs},
+        repo_trash::RepoTrashService,
+    };
+
+    use super::RepoSyncService;
+
+    fn setup() -> (TestContext, RepoSyncService, MemoryLocalFs, u32) {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+
+        let repo_sync_service = RepoSyncService::new(
+            ctx.repos_service.clone(),
+            ctx.repo_files_service.clone(),
+            ctx.repo_files_list_service.clone(),
+            Arc::new(RepoTrashService::new(
+                ctx.repo_files_service.clone(),
+                ctx.store.clone(),
+            )),
+            ctx.store.clone(),
+        );
+
+        let local_fs = MemoryLocalFs::new();
+
+        let sync_id = repo_sync_service.create(
+            "r1",
+            "/Sync",
+            Box::new(local_fs.clone()),
+            Box::new(MemoryEncryptedStorage::new()),
+        );
+
+        (ctx, repo_sync_service, local_fs, sync_id)
+    }
+
+    #[test]
+    fn test_sync_delete_remote_trash() {
+        let (ctx, repo_sync_service, local_fs, sync_id) = setup();
+
+        local_fs.add_file("/a.txt", b"a", 10);
+
+        let summary = block_on(repo_sync_service.sync(sync_id)).unwrap();
+        assert_eq!(summary.uploaded, 1);
+
+        block_on(local_fs.remove("/a.txt")).unwrap();
+
+        let summary = block_on(repo_sync_service.sync(sync_id)).unwrap();
+        assert_eq!(summary.deleted_remote, 1);
+
+        // the deleted file can be restored from the trash
+        let paths = ctx.repo_paths("r1");
+        assert!(!paths.contains(&String::from("/Sync/a.txt")));
+        assert!(paths
+            .iter()
+            .any(|path| path.starts_with("/.trash/") && path.ends_with("/a.txt")));
+    }
+
+    #[test]
+    fn test_sync_upload_remote_changed() {
+        let (ctx, repo_sync_service, local_fs, sync_id) = setup();
+
+        local_fs.add_file("/a.txt", b"v1", 10);
+
+        block_on(repo_sync_service.sync(sync_id)).unwrap();
+
+        local_fs.add_file("/a.txt", b"v2-local", 20);
+
+        // the remote file changes after it was listed
+        let remote_path = ctx.remote_file_path("r1", "/Sync/a.txt");
+        let mut remote_content = Vec::new();
+        block_on(
+            ctx.cipher("r1")
+                .encrypt_data(b"v2-remote", &mut remote_content),
+        )
+        .unwrap();
+        let fake_remote = ctx.fake_remote.clone();
+        let info_url = format!("/files/info?path={}", urlencoding::encode(&remote_path));
+
+        ctx.fake_remote.set_intercept(Some(Box::new(move |req| {
+            if req.method == "GET" && req.url.ends_with(&info_url) {
+                let mut state = fake_remote.state.lock().unwrap();
+                let file = state
+                    .files
+                    .get_mut(&(TEST_MOUNT_ID.to_owned(), remote_path.clone()))
+                    .unwrap();
+                file.content = remote_content.clone();
+                file.modified = 2;
+            }
+
+            None
+        })));
+
+        let summary = block_on(repo_sync_service.sync(sync_id)).unwrap();
+
+        ctx.fake_remote.set_intercept(None);
+
+        // the remote change is not overwritten
+        assert_eq!(summary.uploaded, 0);
+        assert_eq!(
+            ctx.file_content("r1", "/Sync/a.txt").unwrap(),
+            b"v2-remote".to_vec()
+        );
+
+        // the next pass keeps both versions
+        let summary = block_on(repo_sync_service.sync(sync_id)).unwrap();
+
+        assert_eq!(summary.conflicts, 1);
+        assert_eq!(local_fs.content("/a.txt").unwrap(), b"v2-remote".to_vec());
+        assert_eq!(
+            local_fs.content("/a (conflict).txt").unwrap(),
+            b"v2-local".to_vec()
+        );
+        assert_eq!(
+            ctx.file_content("r1", "/Sync/a (conflict).txt").unwrap(),
+            b"v2-local".to_vec()
+        );
+    }
+}
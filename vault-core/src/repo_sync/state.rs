@@ -0,0 +1,153 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::state::Status;
+
+use super::errors::RepoSyncError;
+
+pub const DATABASE_VERSION: u32 = 1;
+
+/// Version of a path that both sides had after the last sync. Changes since
+/// then are detected by comparing sizes and modification times.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoSyncEntry {
+    #[serde(rename = "isDir")]
+    pub is_dir: bool,
+    pub size: i64,
+    #[serde(rename = "localModified")]
+    pub local_modified: i64,
+    #[serde(rename = "remoteModified")]
+    pub remote_modified: i64,
+}
+
+/// Sync state database. Paths are relative to the synced dir.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoSyncDatabase {
+    pub version: u32,
+    pub entries: BTreeMap<String, RepoSyncEntry>,
+}
+
+impl Default for RepoSyncDatabase {
+    fn default() -> Self {
+        Self {
+            version: DATABASE_VERSION,
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+/// Current version of a path on one of the sides
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepoSyncFile {
+    pub is_dir: bool,
+    pub size: i64,
+    pub modified: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RepoSyncAction {
+    /// Both sides changed the path. The remote version keeps the path and the
+    /// local one is renamed to a conflict copy.
+    Conflict {
+        path: String,
+    },
+    CreateLocalDir {
+        path: String,
+    },
+    CreateRemoteDir {
+        path: String,
+    },
+    /// The file was moved in the repo
+    MoveLocal {
+        path: String,
+        to_path: String,
+    },
+    /// The file was moved locally
+    MoveRemote {
+        path: String,
+        to_path: String,
+    },
+    Download {
+        path: String,
+    },
+    Upload {
+        path: String,
+    },
+    /// Both sides already have the same version
+    Record {
+        path: String,
+    },
+    DeleteLocal {
+        path: String,
+    },
+    DeleteRemote {
+        path: String,
+    },
+    /// The path was deleted on both sides
+    Forget {
+        path: String,
+    },
+}
+
+impl RepoSyncAction {
+    /// Resulting path of the action
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Conflict { path }
+            | Self::CreateLocalDir { path }
+            | Self::CreateRemoteDir { path }
+            | Self::Download { path }
+            | Self::Upload { path }
+            | Self::Record { path }
+            | Self::DeleteLocal { path }
+            | Self::DeleteRemote { path }
+            | Self::Forget { path } => path,
+            Self::MoveLocal { to_path, .. } | Self::MoveRemote { to_path, .. } => to_path,
+        }
+    }
+
+    /// Actions are applied in phases: conflicts, dirs (parents first), moves,
+    /// transfers and deletes.
+    pub fn phase(&self) -> u8 {
+        match self {
+            Self::Conflict { .. } => 0,
+            Self::CreateLocalDir { .. } | Self::CreateRemoteDir { .. } => 1,
+            Self::MoveLocal { .. } | Self::MoveRemote { .. } => 2,
+            Self::Download { .. } | Self::Upload { .. } | Self::Record { .. } => 3,
+            Self::DeleteLocal { .. } | Self::DeleteRemote { .. } | Self::Forget { .. } => 4,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepoSyncSummary {
+    pub uploaded: u32,
+    pub downloaded: u32,
+    pub moved_local: u32,
+    pub moved_remote: u32,
+    pub deleted_local: u32,
+    pub deleted_remote: u32,
+    pub conflicts: u32,
+}
+
+#[derive(Clone)]
+pub struct RepoSync {
+    pub id: u32,
+    pub repo_id: String,
+    pub path: String,
+    pub status: Status<RepoSyncError>,
+    pub last_summary: Option<RepoSyncSummary>,
+    pub last_synced: Option<i64>,
+}
+
+#[derive(Clone, Default)]
+pub struct RepoSyncState {
+    pub syncs: HashMap<u32, RepoSync>,
+}
+
+/// Storage is provided per local root, so the key only needs to identify the
+/// synced dir in the repo.
+pub fn get_storage_key(repo_id: &str, path: &str) -> String {
+    format!("vaultRepoSync:{}:{}", repo_id, path)
+}
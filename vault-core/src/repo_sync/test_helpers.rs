@@ -0,0 +1,175 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use futures::{io::Cursor, AsyncReadExt};
+
+use crate::utils::path_utils;
+
+use super::{
+    errors::LocalFsError,
+    local_fs::{LocalFs, LocalFsEntry, LocalFsReader},
+    mutations::is_descendant,
+};
+
+#[derive(Clone, Debug)]
+struct MemoryLocalFsFile {
+    is_dir: bool,
+    content: Vec<u8>,
+    modified: i64,
+}
+
+/// In-memory LocalFs for tests. Clones share the files.
+#[derive(Clone, Default)]
+pub struct MemoryLocalFs {
+    files: Arc<Mutex<BTreeMap<String, MemoryLocalFsFile>>>,
+}
+
+impl MemoryLocalFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_dir(&self, path: &str) {
+        self.ensure_parents(path);
+
+        self.files.lock().unwrap().insert(
+            path.to_owned(),
+            MemoryLocalFsFile {
+                is_dir: true,
+                content: Vec::new(),
+                modified: 0,
+            },
+        );
+    }
+
+    pub fn add_file(&self, path: &str, content: &[u8], modified: i64) {
+        self.ensure_parents(path);
+
+        self.files.lock().unwrap().insert(
+            path.to_owned(),
+            MemoryLocalFsFile {
+                is_dir: false,
+                content: content.to_vec(),
+                modified,
+            },
+        );
+    }
+
+    pub fn content(&self, path: &str) -> Option<Vec<u8>> {
+        self.files
+            .lock()
+            .unwrap()
+            .get(path)
+            .filter(|file| !file.is_dir)
+            .map(|file| file.content.clone())
+    }
+
+    fn ensure_parents(&self, path: &str) {
+        let mut files = self.files.lock().unwrap();
+
+        for parent_path in path_utils::paths_chain(path_utils::parent_path(path).unwrap_or("/")) {
+            if parent_path != "/" {
+                files
+                    .entry(parent_path)
+                    .or_insert_with(|| MemoryLocalFsFile {
+                        is_dir: true,
+                        content: Vec::new(),
+                        modified: 0,
+                    });
+            }
+        }
+    }
+
+    fn entry(path: &str, file: &MemoryLocalFsFile) -> LocalFsEntry {
+        LocalFsEntry {
+            path: path.to_owned(),
+            is_dir: file.is_dir,
+            size: file.content.len() as i64,
+            modified: file.modified,
+        }
+    }
+}
+
+#[async_trait]
+impl LocalFs for MemoryLocalFs {
+    async fn list(&self) -> Result<Vec<LocalFsEntry>, LocalFsError> {
+        Ok(self
+            .files
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, file)| Self::entry(path, file))
+            .collect())
+    }
+
+    async fn reader(&self, path: &str) -> Result<LocalFsReader, LocalFsError> {
+        let content = self.content(path).ok_or(LocalFsError::NotFound)?;
+
+        Ok(Box::pin(Cursor::new(content)))
+    }
+
+    async fn write(
+        &self,
+        path: &str,
+        mut reader: LocalFsReader,
+        modified: i64,
+    ) -> Result<LocalFsEntry, LocalFsError> {
+        let mut content = Vec::new();
+
+        reader
+            .read_to_end(&mut content)
+            .await
+            .map_err(|err| LocalFsError::IoError(err.to_string()))?;
+
+        self.add_file(path, &content, modified);
+
+        let files = self.files.lock().unwrap();
+
+        Ok(Self::entry(path, files.get(path).unwrap()))
+    }
+
+    async fn create_dir(&self, path: &str) -> Result<LocalFsEntry, LocalFsError> {
+        if !self.files.lock().unwrap().contains_key(path) {
+            self.add_dir(path);
+        }
+
+        let files = self.files.lock().unwrap();
+
+        Ok(Self::entry(path, files.get(path).unwrap()))
+    }
+
+    async fn remove(&self, path: &str) -> Result<(), LocalFsError> {
+        let mut files = self.files.lock().unwrap();
+
+        files.remove(path).ok_or(LocalFsError::NotFound)?;
+        files.retain(|file_path, _| !is_descendant(file_path, path));
+
+        Ok(())
+    }
+
+    async fn rename(&self, path: &str, to_path: &str) -> Result<LocalFsEntry, LocalFsError> {
+        self.ensure_parents(to_path);
+
+        let mut files = self.files.lock().unwrap();
+
+        let moved: Vec<(String, MemoryLocalFsFile)> = files
+            .iter()
+            .filter(|(file_path, _)| *file_path == path || is_descendant(file_path, path))
+            .map(|(file_path, file)| (file_path.clone(), file.clone()))
+            .collect();
+
+        if moved.is_empty() {
+            return Err(LocalFsError::NotFound);
+        }
+
+        for (file_path, file) in moved {
+            files.remove(&file_path);
+            files.insert(format!("{}{}", to_path, &file_path[path.len()..]), file);
+        }
+
+        Ok(Self::entry(to_path, files.get(to_path).unwrap()))
+    }
+}
@@ -0,0 +1,130 @@
+use thiserror::Error;
+
+use crate::{
+    cipher::errors::{DecryptFilenameError, DecryptHeaderError, DecryptSizeError},
+    remote::RemoteError,
+    repo_files::errors::{
+        DeleteFileError, EnsureDirError, LoadFileError, LoadFilesError, MoveFileError,
+        RenameFileError, RepoFilesErrors, UploadFileReaderError,
+    },
+    repo_files_read::errors::GetFilesReaderError,
+    repos::errors::{RepoLockedError, RepoNotFoundError},
+    user_error::UserError,
+};
+
+#[derive(Error, Debug, Clone, PartialEq, UserError)]
+pub enum RepoTrashError {
+    #[error("invalid path")]
+    InvalidPath,
+    #[error("trash entry not found")]
+    EntryNotFound,
+    #[error("invalid trash info: {0}")]
+    InvalidInfo(String),
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("{0}")]
+    DecryptFilenameError(#[from] DecryptFilenameError),
+    #[error("{0}")]
+    DecryptSizeError(#[from] DecryptSizeError),
+    #[error("{0}")]
+    DecryptHeaderError(#[from] DecryptHeaderError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+    #[error("failed to read file: {0}")]
+    ReadError(String),
+}
+
+impl From<LoadFilesError> for RepoTrashError {
+    fn from(err: LoadFilesError) -> Self {
+        match err {
+            LoadFilesError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFilesError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFilesError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<LoadFileError> for RepoTrashError {
+    fn from(err: LoadFileError) -> Self {
+        match err {
+            LoadFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFileError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<DeleteFileError> for RepoTrashError {
+    fn from(err: DeleteFileError) -> Self {
+        match err {
+            DeleteFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            DeleteFileError::RepoLocked(err) => Self::RepoLocked(err),
+            DeleteFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<EnsureDirError> for RepoTrashError {
+    fn from(err: EnsureDirError) -> Self {
+        match err {
+            EnsureDirError::RepoNotFound(err) => Self::RepoNotFound(err),
+            EnsureDirError::RepoLocked(err) => Self::RepoLocked(err),
+            EnsureDirError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            EnsureDirError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<MoveFileError> for RepoTrashError {
+    fn from(err: MoveFileError) -> Self {
+        match err {
+            MoveFileError::InvalidPath => Self::InvalidPath,
+            MoveFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            MoveFileError::RepoLocked(err) => Self::RepoLocked(err),
+            MoveFileError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            MoveFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<RenameFileError> for RepoTrashError {
+    fn from(err: RenameFileError) -> Self {
+        match err {
+            RenameFileError::RepoNotFound(err) => Self::RepoNotFound(err),
+            RenameFileError::RepoLocked(err) => Self::RepoLocked(err),
+            RenameFileError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            RenameFileError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<UploadFileReaderError> for RepoTrashError {
+    fn from(err: UploadFileReaderError) -> Self {
+        match err {
+            UploadFileReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            UploadFileReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            UploadFileReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            UploadFileReaderError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<GetFilesReaderError> for RepoTrashError {
+    fn from(err: GetFilesReaderError) -> Self {
+        match err {
+            GetFilesReaderError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetFilesReaderError::RepoLocked(err) => Self::RepoLocked(err),
+            GetFilesReaderError::FileNotFound | GetFilesReaderError::FilesEmpty => {
+                Self::RemoteError(RepoFilesErrors::not_found())
+            }
+            GetFilesReaderError::InvalidRange => Self::InvalidPath,
+            GetFilesReaderError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            GetFilesReaderError::DecryptSizeError(err) => Self::DecryptSizeError(err),
+            GetFilesReaderError::DecryptHeaderError(err) => Self::DecryptHeaderError(err),
+            GetFilesReaderError::RemoteError(err) => Self::RemoteError(err),
+            GetFilesReaderError::ReadError(err) => Self::ReadError(err),
+        }
+    }
+}
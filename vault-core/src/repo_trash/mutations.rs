@@ -0,0 +1,184 @@
+use crate::{common::state::Status, store, utils::path_utils};
+
+use super::{
+    errors::RepoTrashError,
+    state::{RepoTrashEntry, RepoTrashInfo, RepoTrashList, RepoTrashRetention},
+};
+
+/// Ids start with the deletion time so that they sort chronologically.
+pub fn get_entry_id(deleted: i64, random: &str) -> String {
+    format!("{}-{}", deleted, random)
+}
+
+pub fn parse_info(bytes: &[u8]) -> Result<RepoTrashInfo, RepoTrashError> {
+    serde_json::from_slice(bytes).map_err(|err| RepoTrashError::InvalidInfo(err.to_string()))
+}
+
+pub fn info_to_entry(id: &str, info: RepoTrashInfo) -> Result<RepoTrashEntry, RepoTrashError> {
+    let name = path_utils::path_to_name(&info.path)
+        .ok_or(RepoTrashError::InvalidPath)?
+        .to_owned();
+
+    Ok(RepoTrashEntry {
+        id: id.to_owned(),
+        name,
+        original_path: info.path,
+        deleted: info.deleted,
+        is_dir: info.is_dir,
+        size: info.size,
+    })
+}
+
+pub fn get_expired_ids(
+    entries: &[RepoTrashEntry],
+    retention: RepoTrashRetention,
+    now: i64,
+) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|entry| retention.is_expired(entry.deleted, now))
+        .map(|entry| entry.id.clone())
+        .collect()
+}
+
+pub fn set_retention(state: &mut store::State, retention: RepoTrashRetention) {
+    state.repo_trash.retention = retention;
+}
+
+pub fn loading(state: &mut store::State, repo_id: &str) {
+    let entries = match state.repo_trash.list.take() {
+        Some(list) if list.repo_id == repo_id => list.entries,
+        _ => Vec::new(),
+    };
+
+    state.repo_trash.list = Some(RepoTrashList {
+        repo_id: repo_id.to_owned(),
+        status: if entries.is_empty() {
+            Status::Loading
+        } else {
+            Status::Reloading
+        },
+        entries,
+    });
+}
+
+pub fn loaded(
+    state: &mut store::State,
+    repo_id: &str,
+    res: Result<Vec<RepoTrashEntry>, RepoTrashError>,
+) {
+    if let Some(ref mut list) = state.repo_trash.list {
+        if list.repo_id != repo_id {
+            return;
+        }
+
+        match res {
+            Ok(mut entries) => {
+                // newest first
+                entries.sort_by(|a, b| b.deleted.cmp(&a.deleted));
+
+                list.entries = entries;
+                list.status = Status::Loaded;
+            }
+            Err(error) => {
+                list.status = Status::Error { error };
+            }
+        }
+    }
+}
+
+pub fn entries_removed(state: &mut store::State, repo_id: &str, ids: &[String]) {
+    if let Some(ref mut list) = state.repo_trash.list {
+        if list.repo_id != repo_id {
+            return;
+        }
+
+        list.entries.retain(|entry| !ids.contains(&entry.id));
+    }
+}
+
+pub fn destroy(state: &mut store::State, repo_id: &str) {
+    if let Some(ref list) = state.repo_trash.list {
+        if list.repo_id == repo_id {
+            state.repo_trash.list = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store;
+
+    use super::{
+        super::state::{RepoTrashEntry, RepoTrashRetention},
+        entries_removed, get_entry_id, get_expired_ids, info_to_entry, loaded, loading, parse_info,
+    };
+
+    fn create_entry(id: &str, deleted: i64) -> RepoTrashEntry {
+        RepoTrashEntry {
+            id: id.to_owned(),
+            name: String::from("F1"),
+            original_path: String::from("/D1/F1"),
+            deleted,
+            is_dir: false,
+            size: Some(10),
+        }
+    }
+
+    #[test]
+    fn test_parse_info() {
+        let info =
+            parse_info(br#"{"path":"/D1/F1.txt","deleted":1000,"isDir":false,"size":42}"#).unwrap();
+
+        let entry = info_to_entry(&get_entry_id(1000, "abc"), info).unwrap();
+
+        assert_eq!(entry.id, "1000-abc");
+        assert_eq!(entry.name, "F1.txt");
+        assert_eq!(entry.original_path, "/D1/F1.txt");
+        assert_eq!(entry.size, Some(42));
+
+        assert!(parse_info(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_get_expired_ids() {
+        let day = 24 * 60 * 60 * 1000;
+        let entries = vec![create_entry("a", 0), create_entry("b", 20 * day)];
+
+        assert_eq!(
+            get_expired_ids(&entries, RepoTrashRetention::Days30, 31 * day),
+            vec![String::from("a")]
+        );
+        assert!(get_expired_ids(&entries, RepoTrashRetention::Forever, 1000 * day).is_empty());
+    }
+
+    #[test]
+    fn test_loaded_entries_removed() {
+        let mut state = store::State::default();
+
+        loading(&mut state, "r1");
+        loaded(
+            &mut state,
+            "r1",
+            Ok(vec![create_entry("a", 1), create_entry("b", 2)]),
+        );
+
+        let ids = |state: &store::State| {
+            state
+                .repo_trash
+                .list
+                .as_ref()
+                .unwrap()
+                .entries
+                .iter()
+                .map(|entry| entry.id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(&state), vec!["b", "a"]);
+
+        entries_removed(&mut state, "r1", &[String::from("b")]);
+
+        assert_eq!(ids(&state), vec!["a"]);
+    }
+}
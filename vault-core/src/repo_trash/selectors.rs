@@ -0,0 +1,23 @@
+use crate::store;
+
+use super::state::{RepoTrashEntry, RepoTrashList, RepoTrashRetention};
+
+pub fn select_retention(state: &store::State) -> RepoTrashRetention {
+    state.repo_trash.retention
+}
+
+pub fn select_list<'a>(state: &'a store::State, repo_id: &str) -> Option<&'a RepoTrashList> {
+    state
+        .repo_trash
+        .list
+        .as_ref()
+        .filter(|list| list.repo_id == repo_id)
+}
+
+pub fn select_entry<'a>(
+    state: &'a store::State,
+    repo_id: &str,
+    id: &str,
+) -> Option<&'a RepoTrashEntry> {
+    select_list(state, repo_id).and_then(|list| list.entries.iter().find(|entry| entry.id == id))
+}
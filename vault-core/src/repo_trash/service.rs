@@ -0,0 +1,357 @@
+use std::sync::Arc;
+
+use futures::{io::Cursor, AsyncReadExt};
+use uuid::Uuid;
+
+use crate::{
+    remote::{ApiErrorCode, RemoteError},
+    repo_files::{
+        errors::{DeleteFileError, LoadFilesError, RepoFilesErrors},
+        selectors as repo_files_selectors,
+        state::{RepoFileType, RepoFilesUploadConflictResolution},
+        RepoFilesService,
+    },
+    store,
+    utils::{name_utils, path_utils},
+};
+
+use super::{
+    errors::RepoTrashError,
+    mutations, selectors,
+    state::{
+        get_entry_path, get_info_name, info_name_to_id, is_trash_path, RepoTrashEntry,
+        RepoTrashInfo, RepoTrashRestoreConflictResolution, RepoTrashRetention, TRASH_PATH,
+    },
+};
+
+fn is_not_found(err: &RemoteError) -> bool {
+    matches!(
+        err,
+        RemoteError::ApiError {
+            code: ApiErrorCode::NotFound,
+            ..
+        }
+    )
+}
+
+/// Deleted files are moved into an encrypted trash dir inside the repo
+/// together with an encrypted info file that records the original path and
+/// the deletion time. Entries can be restored or purged and are purged
+/// automatically once they are older than the retention.
+pub struct RepoTrashService {
+    repo_files_service: Arc<RepoFilesService>,
+    store: Arc<store::Store>,
+}
+
+impl RepoTrashService {
+    pub fn new(repo_files_service: Arc<RepoFilesService>, store: Arc<store::Store>) -> Self {
+        Self {
+            repo_files_service,
+            store,
+        }
+    }
+
+    pub fn now(&self) -> i64 {
+        instant::now() as i64
+    }
+
+    pub fn set_retention(&self, retention: RepoTrashRetention) {
+        self.store.mutate(store::Event::RepoTrash, |state| {
+            mutations::set_retention(state, retention);
+        });
+    }
+
+    /// Moves the file or dir at path into the trash and returns the entry id.
+    pub async fn trash_file(&self, repo_id: &str, path: &str) -> Result<String, RepoTrashError> {
+        if path == "/" || is_trash_path(path) {
+            return Err(RepoTrashError::InvalidPath);
+        }
+
+        let file_id = repo_files_selectors::get_file_id(repo_id, path);
+
+        if self
+            .store
+            .with_state(|state| repo_files_selectors::select_file(state, &file_id).is_none())
+        {
+            self.repo_files_service.load_file(repo_id, path).await?;
+        }
+
+        let (is_dir, size) = self
+            .store
+            .with_state(|state| {
+                repo_files_selectors::select_file(state, &file_id)
+                    .map(|file| (file.typ == RepoFileType::Dir, file.decrypted_size().ok()))
+            })
+            .ok_or(RepoTrashError::InvalidPath)?;
+
+        let deleted = self.now();
+        let id = mutations::get_entry_id(deleted, &Uuid::new_v4().simple().to_string()[..8]);
+        let entry_path = get_entry_path(&id);
+        let info_name = get_info_name(&id);
+
+        self.repo_files_service
+            .clone()
+            .ensure_dirs(repo_id, &entry_path)
+            .await?;
+
+        let info = RepoTrashInfo {
+            path: path.to_owned(),
+            deleted,
+            is_dir,
+            size,
+        };
+        let bytes = serde_json::to_vec(&info).unwrap();
+        let bytes_size = bytes.len() as i64;
+
+        self.repo_files_service
+            .clone()
+            .upload_file_reader(
+                repo_id,
+                TRASH_PATH,
+                &info_name,
+                Box::pin(Cursor::new(bytes)),
+                Some(bytes_size),
+                None,
+                RepoFilesUploadConflictResolution::Error,
+                None,
+                None,
+            )
+            .await?;
+
+        if let Err(err) = self
+            .repo_files_service
+            .move_file(repo_id, path, &entry_path)
+            .await
+        {
+            let _ = self.delete_entry(repo_id, &id).await;
+
+            return Err(err.into());
+        }
+
+        Ok(id)
+    }
+
+    /// Loads the trash entries and purges the expired ones.
+    pub async fn load(&self, repo_id: &str) -> Result<(), RepoTrashError> {
+        self.store.mutate(store::Event::RepoTrash, |state| {
+            mutations::loading(state, repo_id);
+        });
+
+        let res = self.load_entries(repo_id).await;
+        let res_err = res.as_ref().err().cloned();
+
+        self.store.mutate(store::Event::RepoTrash, |state| {
+            mutations::loaded(state, repo_id, res);
+        });
+
+        if let Some(err) = res_err {
+            return Err(err);
+        }
+
+        self.auto_purge(repo_id).await
+    }
+
+    /// Purges loaded entries that are older than the retention.
+    pub async fn auto_purge(&self, repo_id: &str) -> Result<(), RepoTrashError> {
+        let now = self.now();
+
+        let expired_ids = self.store.with_state(|state| {
+            selectors::select_list(state, repo_id)
+                .map(|list| {
+                    mutations::get_expired_ids(
+                        &list.entries,
+                        selectors::select_retention(state),
+                        now,
+                    )
+                })
+                .unwrap_or_default()
+        });
+
+        for id in expired_ids {
+            self.purge(repo_id, &id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the entry back to its original path and returns the restored
+    /// path.
+    pub async fn restore(
+        &self,
+        repo_id: &str,
+        id: &str,
+        conflict_resolution: RepoTrashRestoreConflictResolution,
+    ) -> Result<String, RepoTrashError> {
+        let original_path = self
+            .store
+            .with_state(|state| {
+                selectors::select_entry(state, repo_id, id).map(|entry| entry.original_path.clone())
+            })
+            .ok_or(RepoTrashError::EntryNotFound)?;
+
+        let (parent_path, name) =
+            path_utils::split_parent_name(&original_path).ok_or(RepoTrashError::InvalidPath)?;
+        let entry_path = get_entry_path(id);
+
+        self.repo_files_service
+            .clone()
+            .ensure_dirs(repo_id, parent_path)
+            .await?;
+        self.repo_files_service
+            .load_files(repo_id, parent_path)
+            .await?;
+
+        let restore_name = self.store.with_state(|state| {
+            let exists = |name: &str| {
+                repo_files_selectors::select_files(state, repo_id, parent_path)
+                    .any(|file| file.decrypted_name().ok() == Some(name))
+            };
+
+            if exists(name) {
+                match conflict_resolution {
+                    RepoTrashRestoreConflictResolution::Error => Err(RepoTrashError::RemoteError(
+                        RepoFilesErrors::already_exists(),
+                    )),
+                    RepoTrashRestoreConflictResolution::KeepBoth => {
+                        Ok(name_utils::unused_name(name, exists))
+                    }
+                }
+            } else {
+                Ok(name.to_owned())
+            }
+        })?;
+
+        if restore_name != name {
+            // the name is part of the encrypted path so rename before moving
+            self.repo_files_service
+                .load_files(repo_id, &entry_path)
+                .await?;
+            self.repo_files_service
+                .rename_file(
+                    repo_id,
+                    &path_utils::join_path_name(&entry_path, name),
+                    &restore_name,
+                )
+                .await?;
+        }
+
+        self.repo_files_service
+            .move_file(
+                repo_id,
+                &path_utils::join_path_name(&entry_path, &restore_name),
+                parent_path,
+            )
+            .await?;
+
+        self.delete_entry(repo_id, id).await?;
+
+        self.store.mutate(store::Event::RepoTrash, |state| {
+            mutations::entries_removed(state, repo_id, &[id.to_owned()]);
+        });
+
+        Ok(path_utils::join_path_name(parent_path, &restore_name))
+    }
+
+    /// Permanently deletes the entry.
+    pub async fn purge(&self, repo_id: &str, id: &str) -> Result<(), RepoTrashError> {
+        self.delete_entry(repo_id, id).await?;
+
+        self.store.mutate(store::Event::RepoTrash, |state| {
+            mutations::entries_removed(state, repo_id, &[id.to_owned()]);
+        });
+
+        Ok(())
+    }
+
+    /// Permanently deletes all entries.
+    pub async fn empty(&self, repo_id: &str) -> Result<(), RepoTrashError> {
+        self.delete_file_if_exists(repo_id, TRASH_PATH).await?;
+
+        self.store.mutate(store::Event::RepoTrash, |state| {
+            mutations::loaded(state, repo_id, Ok(Vec::new()));
+        });
+
+        Ok(())
+    }
+
+    pub fn destroy(&self, repo_id: &str) {
+        self.store.mutate(store::Event::RepoTrash, |state| {
+            mutations::destroy(state, repo_id);
+        });
+    }
+
+    async fn load_entries(&self, repo_id: &str) -> Result<Vec<RepoTrashEntry>, RepoTrashError> {
+        match self
+            .repo_files_service
+            .load_files(repo_id, TRASH_PATH)
+            .await
+        {
+            Ok(()) => {}
+            Err(LoadFilesError::RemoteError(err)) if is_not_found(&err) => {
+                return Ok(Vec::new());
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        let info_files = self.store.with_state(|state| {
+            repo_files_selectors::select_files(state, repo_id, TRASH_PATH)
+                .filter(|file| file.typ == RepoFileType::File)
+                .filter_map(|file| {
+                    file.decrypted_name()
+                        .ok()
+                        .and_then(info_name_to_id)
+                        .map(|id| (id.to_owned(), file.id.clone()))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut entries = Vec::with_capacity(info_files.len());
+
+        for (id, file_id) in info_files {
+            match self.read_info(&file_id).await {
+                Ok(info) => entries.push(mutations::info_to_entry(&id, info)?),
+                // info files of interrupted trash operations are ignored
+                Err(RepoTrashError::InvalidInfo(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn read_info(&self, file_id: &str) -> Result<RepoTrashInfo, RepoTrashError> {
+        let file_reader = self
+            .repo_files_service
+            .clone()
+            .get_file_reader(file_id, None)
+            .await?;
+
+        let mut reader = file_reader.reader;
+        let mut bytes = Vec::new();
+
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(|err| RepoTrashError::InvalidInfo(err.to_string()))?;
+
+        mutations::parse_info(&bytes)
+    }
+
+    async fn delete_entry(&self, repo_id: &str, id: &str) -> Result<(), RepoTrashError> {
+        self.delete_file_if_exists(repo_id, &get_entry_path(id))
+            .await?;
+        self.delete_file_if_exists(
+            repo_id,
+            &path_utils::join_path_name(TRASH_PATH, &get_info_name(id)),
+        )
+        .await
+    }
+
+    async fn delete_file_if_exists(&self, repo_id: &str, path: &str) -> Result<(), RepoTrashError> {
+        match self.repo_files_service.delete_file(repo_id, path).await {
+            Ok(()) => Ok(()),
+            Err(DeleteFileError::RemoteError(err)) if is_not_found(&err) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
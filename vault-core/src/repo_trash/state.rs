@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{common::state::Status, utils::path_utils};
+
+use super::errors::RepoTrashError;
+
+/// Trashed files are moved into this dir in the repo root, so their names and
+/// contents stay encrypted.
+pub const TRASH_PATH: &str = "/.trash";
+
+const INFO_EXT: &str = ".json";
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepoTrashRetention {
+    Forever,
+    Days7,
+    Days30,
+    Days90,
+}
+
+impl Default for RepoTrashRetention {
+    fn default() -> Self {
+        Self::Days30
+    }
+}
+
+impl RepoTrashRetention {
+    pub fn duration_ms(&self) -> Option<i64> {
+        match self {
+            Self::Forever => None,
+            Self::Days7 => Some(7 * DAY_MS),
+            Self::Days30 => Some(30 * DAY_MS),
+            Self::Days90 => Some(90 * DAY_MS),
+        }
+    }
+
+    pub fn is_expired(&self, deleted: i64, now: i64) -> bool {
+        match self.duration_ms() {
+            Some(duration_ms) => now - deleted >= duration_ms,
+            None => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepoTrashRestoreConflictResolution {
+    Error,
+    /// Restore under a new name next to the existing file
+    KeepBoth,
+}
+
+/// Stored as an encrypted json file next to every trashed entry.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RepoTrashInfo {
+    pub path: String,
+    pub deleted: i64,
+    #[serde(rename = "isDir")]
+    pub is_dir: bool,
+    pub size: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RepoTrashEntry {
+    pub id: String,
+    pub name: String,
+    pub original_path: String,
+    pub deleted: i64,
+    pub is_dir: bool,
+    pub size: Option<i64>,
+}
+
+#[derive(Clone)]
+pub struct RepoTrashList {
+    pub repo_id: String,
+    pub status: Status<RepoTrashError>,
+    pub entries: Vec<RepoTrashEntry>,
+}
+
+#[derive(Clone, Default)]
+pub struct RepoTrashState {
+    pub retention: RepoTrashRetention,
+    pub list: Option<RepoTrashList>,
+}
+
+pub fn is_trash_path(path: &str) -> bool {
+    path == TRASH_PATH || path.starts_with(&format!("{}/", TRASH_PATH))
+}
+
+/// Dir that holds the trashed file or dir under its original name.
+pub fn get_entry_path(id: &str) -> String {
+    path_utils::join_path_name(TRASH_PATH, id)
+}
+
+pub fn get_info_name(id: &str) -> String {
+    format!("{}{}", id, INFO_EXT)
+}
+
+pub fn info_name_to_id(name: &str) -> Option<&str> {
+    name.strip_suffix(INFO_EXT).filter(|id| !id.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        get_entry_path, get_info_name, info_name_to_id, is_trash_path, RepoTrashRetention,
+    };
+
+    #[test]
+    fn test_paths() {
+        assert!(is_trash_path("/.trash"));
+        assert!(is_trash_path("/.trash/1-abc/file.txt"));
+        assert!(!is_trash_path("/.trashcan"));
+        assert!(!is_trash_path("/Docs/.trash"));
+
+        assert_eq!(get_entry_path("1-abc"), "/.trash/1-abc");
+        assert_eq!(get_info_name("1-abc"), "1-abc.json");
+        assert_eq!(info_name_to_id("1-abc.json"), Some("1-abc"));
+        assert_eq!(info_name_to_id("1-abc"), None);
+        assert_eq!(info_name_to_id(".json"), None);
+    }
+
+    #[test]
+    fn test_retention_is_expired() {
+        let day = 24 * 60 * 60 * 1000;
+
+        assert!(!RepoTrashRetention::Forever.is_expired(0, 1000 * day));
+        assert!(!RepoTrashRetention::Days7.is_expired(0, 6 * day));
+        assert!(RepoTrashRetention::Days7.is_expired(0, 7 * day));
+        assert!(!RepoTrashRetention::Days30.is_expired(day, 30 * day));
+        assert!(RepoTrashRetention::Days90.is_expired(0, 91 * day));
+    }
+}
@@ -33,7 +33,9 @@ impl RepoUnlockService {
         });
     }
 
-    pub async fn unlock(&self, password: &str) -> Result<(), UnlockRepoError> {
+    /// If remember is set the repo keys are stored on this device and the repo
+    /// is unlocked automatically on the next load.
+    pub async fn unlock(&self, password: &str, remember: bool) -> Result<(), UnlockRepoError> {
         let repo_id = match self.store.mutate(store::Event::RepoUnlock, |state| {
             if let Some(ref mut repo_unlock) = state.repo_unlock {
                 repo_unlock.status = Status::Loading;
@@ -52,6 +54,14 @@ impl RepoUnlockService {
 
         let res = self.repos_service.unlock_repo(&repo_id, password).await;
 
+        if res.is_ok() {
+            if remember {
+                let _ = self.repos_service.remember_repo(&repo_id).await;
+            } else {
+                self.repos_service.forget_repo(&repo_id);
+            }
+        }
+
         self.store.mutate(store::Event::RepoUnlock, |state| {
             if let Some(ref mut repo_unlock) = state.repo_unlock {
                 repo_unlock.status = match &res {
@@ -0,0 +1,32 @@
+use thiserror::Error;
+
+use crate::{
+    remote::RemoteError,
+    repo_files_list::errors::GetListRecursiveError,
+    repos::errors::{RepoLockedError, RepoNotFoundError},
+    user_error::UserError,
+};
+
+#[derive(Error, Debug, Clone, PartialEq, UserError)]
+pub enum RepoVerifyError {
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+    #[error("cannot quarantine files of a Safe Box in the root folder")]
+    RepoInRootFolder,
+}
+
+impl From<GetListRecursiveError> for RepoVerifyError {
+    fn from(err: GetListRecursiveError) -> Self {
+        match err {
+            GetListRecursiveError::RepoNotFound(err) => Self::RepoNotFound(err),
+            GetListRecursiveError::RepoLocked(err) => Self::RepoLocked(err),
+            // root path is always decrypted
+            GetListRecursiveError::DecryptFilenameError(_) => Self::RepoNotFound(RepoNotFoundError),
+            GetListRecursiveError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
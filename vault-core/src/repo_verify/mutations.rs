@@ -0,0 +1,390 @@
+use crate::{
+    cipher::CipherError,
+    common::state::Status,
+    http::HttpError,
+    remote::RemoteError,
+    repo_files::state::{RepoFileName, RepoFileSize},
+    repo_files_list::{errors::FilesListRecursiveItemError, state::RepoFilesListRecursiveItem},
+    store,
+};
+
+use super::{
+    errors::RepoVerifyError,
+    state::{RepoVerifyProblem, RepoVerifyProblemType, RepoVerifyQuarantineState, RepoVerifyState},
+};
+
+pub struct RepoVerifyProblemAdded {
+    pub mount_id: String,
+    pub remote_path: Option<String>,
+    pub path: Option<String>,
+    pub typ: RepoVerifyProblemType,
+}
+
+pub fn init(state: &mut store::State, repo_id: &str) {
+    state.repo_verify = Some(RepoVerifyState {
+        repo_id: repo_id.to_owned(),
+        status: Status::Initial,
+        quarantine_status: Status::Initial,
+        checked_files_count: 0,
+        checked_bytes: 0,
+        problems: Vec::new(),
+        next_problem_id: 1,
+    });
+}
+
+pub fn verify_started(state: &mut store::State) -> Option<String> {
+    state.repo_verify.as_mut().map(|repo_verify| {
+        repo_verify.status = Status::Loading;
+        repo_verify.quarantine_status = Status::Initial;
+        repo_verify.checked_files_count = 0;
+        repo_verify.checked_bytes = 0;
+        repo_verify.problems.clear();
+
+        repo_verify.repo_id.clone()
+    })
+}
+
+pub fn get_item_problems(item: &RepoFilesListRecursiveItem) -> Vec<RepoVerifyProblemAdded> {
+    let mut problems = Vec::new();
+
+    match item {
+        RepoFilesListRecursiveItem::File { file, .. } => {
+            if let RepoFileName::DecryptError { error, .. } = &file.name {
+                problems.push(RepoVerifyProblemAdded {
+                    mount_id: file.mount_id.clone(),
+                    remote_path: Some(file.remote_path.clone()),
+                    path: None,
+                    typ: RepoVerifyProblemType::InvalidName {
+                        error: error.clone(),
+                    },
+                });
+            }
+
+            if let RepoFileSize::DecryptError { error, .. } = &file.size {
+                problems.push(RepoVerifyProblemAdded {
+                    mount_id: file.mount_id.clone(),
+                    remote_path: Some(file.remote_path.clone()),
+                    path: file.decrypted_path().ok().map(str::to_owned),
+                    typ: RepoVerifyProblemType::InvalidSize {
+                        error: error.clone(),
+                    },
+                });
+            }
+        }
+        RepoFilesListRecursiveItem::Error {
+            mount_id,
+            remote_path,
+            error,
+        } => problems.push(RepoVerifyProblemAdded {
+            mount_id: mount_id.clone(),
+            remote_path: remote_path.clone(),
+            path: None,
+            typ: match error {
+                FilesListRecursiveItemError::DecryptFilenameError(error) => {
+                    RepoVerifyProblemType::InvalidName {
+                        error: error.clone(),
+                    }
+                }
+                FilesListRecursiveItemError::RemoteError(error) => {
+                    RepoVerifyProblemType::RemoteError {
+                        error: error.clone(),
+                    }
+                }
+            },
+        }),
+    }
+
+    problems
+}
+
+pub fn content_error_to_problem_type(err: &std::io::Error) -> RepoVerifyProblemType {
+    let inner = err.get_ref();
+
+    if let Some(cipher_error) = inner.and_then(|err| err.downcast_ref::<CipherError>()) {
+        return match cipher_error {
+            CipherError::BlockDecryptionError {
+                block_index,
+                offset,
+            } => RepoVerifyProblemType::InvalidBlock {
+                block_index: *block_index,
+                offset: *offset,
+            },
+            _ => RepoVerifyProblemType::InvalidContent {
+                error: cipher_error.to_string(),
+            },
+        };
+    }
+
+    if let Some(http_error) = inner.and_then(|err| err.downcast_ref::<HttpError>()) {
+        return RepoVerifyProblemType::RemoteError {
+            error: RemoteError::HttpError(http_error.clone()),
+        };
+    }
+
+    RepoVerifyProblemType::InvalidContent {
+        error: err.to_string(),
+    }
+}
+
+pub fn add_problems(state: &mut store::State, problems: Vec<RepoVerifyProblemAdded>) {
+    if let Some(ref mut repo_verify) = state.repo_verify {
+        for problem in problems {
+            let RepoVerifyProblemAdded {
+                mount_id,
+                remote_path,
+                path,
+                typ,
+            } = problem;
+
+            repo_verify.problems.push(RepoVerifyProblem {
+                id: repo_verify.next_problem_id,
+                mount_id,
+                remote_path,
+                path,
+                typ,
+                quarantine_state: RepoVerifyQuarantineState::NotQuarantined,
+            });
+
+            repo_verify.next_problem_id += 1;
+        }
+    }
+}
+
+pub fn file_checked(state: &mut store::State, size: i64) {
+    if let Some(ref mut repo_verify) = state.repo_verify {
+        repo_verify.checked_files_count += 1;
+        repo_verify.checked_bytes += size;
+    }
+}
+
+pub fn verify_finished(state: &mut store::State, res: &Result<(), RepoVerifyError>) {
+    if let Some(ref mut repo_verify) = state.repo_verify {
+        repo_verify.status = match res {
+            Ok(()) => Status::Loaded,
+            Err(err) => Status::Error { error: err.clone() },
+        };
+    }
+}
+
+/// Returns (problem id, mount id, remote path) of the entries that still have
+/// to be moved to quarantine, sorted by remote path.
+pub fn quarantine_started(
+    state: &mut store::State,
+) -> Option<(String, Vec<(u32, String, String)>)> {
+    state.repo_verify.as_mut().map(|repo_verify| {
+        repo_verify.quarantine_status = Status::Loading;
+
+        let mut entries: Vec<(u32, String, String)> = repo_verify
+            .problems
+            .iter()
+            .filter(|problem| {
+                problem.typ.can_quarantine()
+                    && problem.quarantine_state != RepoVerifyQuarantineState::Quarantined
+            })
+            .filter_map(|problem| {
+                problem
+                    .remote_path
+                    .as_ref()
+                    .map(|remote_path| (problem.id, problem.mount_id.clone(), remote_path.clone()))
+            })
+            .collect();
+
+        entries.sort_by(|a, b| a.2.cmp(&b.2));
+
+        (repo_verify.repo_id.clone(), entries)
+    })
+}
+
+pub fn problem_quarantined(state: &mut store::State, id: u32, res: Result<(), RemoteError>) {
+    if let Some(ref mut repo_verify) = state.repo_verify {
+        if let Some(problem) = repo_verify
+            .problems
+            .iter_mut()
+            .find(|problem| problem.id == id)
+        {
+            problem.quarantine_state = match res {
+                Ok(()) => RepoVerifyQuarantineState::Quarantined,
+                Err(error) => RepoVerifyQuarantineState::Failed { error },
+            };
+        }
+    }
+}
+
+pub fn quarantine_finished(state: &mut store::State, res: &Result<(), RepoVerifyError>) {
+    if let Some(ref mut repo_verify) = state.repo_verify {
+        repo_verify.quarantine_status = match res {
+            Ok(()) => Status::Loaded,
+            Err(err) => Status::Error { error: err.clone() },
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cipher::{
+            errors::{DecryptFilenameError, DecryptSizeError},
+            test_helpers::create_cipher,
+            CipherError,
+        },
+        http::HttpError,
+        remote::RemoteError,
+        repo_files::state::{RepoFileName, RepoFileSize},
+        repo_files_list::{
+            errors::FilesListRecursiveItemError, state::RepoFilesListRecursiveItem,
+            test_helpers::create_list_recursive_item_file,
+        },
+        store,
+    };
+
+    use super::{
+        super::state::{RepoVerifyProblemType, RepoVerifyQuarantineState},
+        add_problems, content_error_to_problem_type, get_item_problems, init, problem_quarantined,
+        quarantine_started, RepoVerifyProblemAdded,
+    };
+
+    #[test]
+    fn test_get_item_problems() {
+        let mut item =
+            create_list_recursive_item_file("m1", "/Vault", "r1", "/", "/F1", &create_cipher());
+
+        assert!(get_item_problems(&item).is_empty());
+
+        if let RepoFilesListRecursiveItem::File { ref mut file, .. } = item {
+            file.name = RepoFileName::DecryptError {
+                encrypted_name: String::from("xxx"),
+                encrypted_name_lower: String::from("xxx"),
+                error: DecryptFilenameError::DecryptError,
+            };
+            file.size = RepoFileSize::DecryptError {
+                encrypted_size: 10,
+                error: DecryptSizeError::EncryptedFileTooShort,
+            };
+        }
+
+        let problems = get_item_problems(&item);
+
+        assert_eq!(problems.len(), 2);
+        assert_eq!(
+            problems[0].typ,
+            RepoVerifyProblemType::InvalidName {
+                error: DecryptFilenameError::DecryptError
+            }
+        );
+        assert_eq!(
+            problems[1].typ,
+            RepoVerifyProblemType::InvalidSize {
+                error: DecryptSizeError::EncryptedFileTooShort
+            }
+        );
+
+        let problems = get_item_problems(&RepoFilesListRecursiveItem::Error {
+            mount_id: String::from("m1"),
+            remote_path: Some(String::from("/Vault/xxx/yyy")),
+            error: FilesListRecursiveItemError::DecryptFilenameError(
+                DecryptFilenameError::DecryptError,
+            ),
+        });
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].remote_path.as_deref(), Some("/Vault/xxx/yyy"));
+    }
+
+    #[test]
+    fn test_content_error_to_problem_type() {
+        assert_eq!(
+            content_error_to_problem_type(
+                &CipherError::BlockDecryptionError {
+                    block_index: 2,
+                    offset: 131136,
+                }
+                .into()
+            ),
+            RepoVerifyProblemType::InvalidBlock {
+                block_index: 2,
+                offset: 131136,
+            }
+        );
+        assert_eq!(
+            content_error_to_problem_type(&CipherError::EncryptedBadMagic.into()),
+            RepoVerifyProblemType::InvalidContent {
+                error: String::from("not an encrypted file - bad magic string"),
+            }
+        );
+        assert_eq!(
+            content_error_to_problem_type(&std::io::Error::new(
+                std::io::ErrorKind::Other,
+                HttpError::ResponseError(String::from("network error")),
+            )),
+            RepoVerifyProblemType::RemoteError {
+                error: RemoteError::HttpError(HttpError::ResponseError(String::from(
+                    "network error"
+                ))),
+            }
+        );
+    }
+
+    #[test]
+    fn test_quarantine_started() {
+        let mut state = store::State::default();
+
+        init(&mut state, "r1");
+
+        add_problems(
+            &mut state,
+            vec![
+                RepoVerifyProblemAdded {
+                    mount_id: String::from("m1"),
+                    remote_path: Some(String::from("/Vault/b")),
+                    path: None,
+                    typ: RepoVerifyProblemType::InvalidContent {
+                        error: String::from("err"),
+                    },
+                },
+                RepoVerifyProblemAdded {
+                    mount_id: String::from("m1"),
+                    remote_path: Some(String::from("/Vault/a")),
+                    path: None,
+                    typ: RepoVerifyProblemType::InvalidName {
+                        error: DecryptFilenameError::DecryptError,
+                    },
+                },
+                RepoVerifyProblemAdded {
+                    mount_id: String::from("m1"),
+                    remote_path: None,
+                    path: None,
+                    typ: RepoVerifyProblemType::RemoteError {
+                        error: RemoteError::HttpError(HttpError::ResponseError(String::from(
+                            "err",
+                        ))),
+                    },
+                },
+            ],
+        );
+
+        let (repo_id, entries) = quarantine_started(&mut state).unwrap();
+
+        assert_eq!(repo_id, "r1");
+        assert_eq!(
+            entries,
+            vec![
+                (2, String::from("m1"), String::from("/Vault/a")),
+                (1, String::from("m1"), String::from("/Vault/b")),
+            ]
+        );
+
+        problem_quarantined(&mut state, 2, Ok(()));
+
+        assert_eq!(
+            state.repo_verify.as_ref().unwrap().problems[1].quarantine_state,
+            RepoVerifyQuarantineState::Quarantined
+        );
+
+        let (_, entries) = quarantine_started(&mut state).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![(1, String::from("m1"), String::from("/Vault/b"))]
+        );
+    }
+}
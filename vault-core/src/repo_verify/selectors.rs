@@ -0,0 +1,129 @@
+use crate::{repos::selectors as repos_selectors, store};
+
+use super::state::{
+    RepoVerifyInfo, RepoVerifyProblem, RepoVerifyProblemType, RepoVerifyQuarantineState,
+    RepoVerifySummary,
+};
+
+pub fn get_summary(problems: &[RepoVerifyProblem]) -> RepoVerifySummary {
+    let mut summary = RepoVerifySummary::default();
+
+    for problem in problems {
+        match problem.typ {
+            RepoVerifyProblemType::InvalidName { .. } => summary.invalid_names_count += 1,
+            RepoVerifyProblemType::InvalidSize { .. } => summary.invalid_sizes_count += 1,
+            RepoVerifyProblemType::InvalidBlock { .. }
+            | RepoVerifyProblemType::InvalidContent { .. } => summary.invalid_contents_count += 1,
+            RepoVerifyProblemType::RemoteError { .. } => summary.remote_errors_count += 1,
+        }
+
+        if problem.quarantine_state == RepoVerifyQuarantineState::Quarantined {
+            summary.quarantined_count += 1;
+        }
+    }
+
+    summary
+}
+
+pub fn select_info<'a>(state: &'a store::State) -> Option<RepoVerifyInfo<'a>> {
+    state
+        .repo_verify
+        .as_ref()
+        .map(|repo_verify| RepoVerifyInfo {
+            repo_id: &repo_verify.repo_id,
+            status: &repo_verify.status,
+            quarantine_status: &repo_verify.quarantine_status,
+            checked_files_count: repo_verify.checked_files_count,
+            checked_bytes: repo_verify.checked_bytes,
+            problems: &repo_verify.problems,
+            summary: get_summary(&repo_verify.problems),
+            repo_name: repos_selectors::select_repo(state, &repo_verify.repo_id)
+                .ok()
+                .map(|repo| repo.name.as_str()),
+        })
+}
+
+pub fn select_is_verifying(state: &store::State, repo_id: &str) -> bool {
+    state
+        .repo_verify
+        .as_ref()
+        .filter(|repo_verify| repo_verify.repo_id == repo_id)
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cipher::errors::DecryptFilenameError,
+        http::HttpError,
+        remote::RemoteError,
+        repo_verify::state::{
+            RepoVerifyProblem, RepoVerifyProblemType, RepoVerifyQuarantineState, RepoVerifySummary,
+        },
+    };
+
+    use super::get_summary;
+
+    fn create_problem(
+        id: u32,
+        typ: RepoVerifyProblemType,
+        quarantine_state: RepoVerifyQuarantineState,
+    ) -> RepoVerifyProblem {
+        RepoVerifyProblem {
+            id,
+            mount_id: String::from("m1"),
+            remote_path: Some(format!("/Vault/{}", id)),
+            path: None,
+            typ,
+            quarantine_state,
+        }
+    }
+
+    #[test]
+    fn test_get_summary() {
+        let problems = vec![
+            create_problem(
+                1,
+                RepoVerifyProblemType::InvalidName {
+                    error: DecryptFilenameError::DecryptError,
+                },
+                RepoVerifyQuarantineState::Quarantined,
+            ),
+            create_problem(
+                2,
+                RepoVerifyProblemType::InvalidBlock {
+                    block_index: 0,
+                    offset: 32,
+                },
+                RepoVerifyQuarantineState::NotQuarantined,
+            ),
+            create_problem(
+                3,
+                RepoVerifyProblemType::InvalidContent {
+                    error: String::from("file is too short to be decrypted"),
+                },
+                RepoVerifyQuarantineState::NotQuarantined,
+            ),
+            create_problem(
+                4,
+                RepoVerifyProblemType::RemoteError {
+                    error: RemoteError::HttpError(HttpError::ResponseError(String::from(
+                        "network error",
+                    ))),
+                },
+                RepoVerifyQuarantineState::NotQuarantined,
+            ),
+        ];
+
+        assert_eq!(
+            get_summary(&problems),
+            RepoVerifySummary {
+                invalid_names_count: 1,
+                invalid_sizes_count: 0,
+                invalid_contents_count: 2,
+                remote_errors_count: 1,
+                quarantined_count: 1,
+            }
+        );
+    }
+}
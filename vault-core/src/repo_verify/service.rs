@@ -0,0 +1,258 @@
+use std::sync::Arc;
+
+use futures::StreamExt;
+
+use crate::{
+    cipher::Cipher,
+    remote::RemoteError,
+    remote_files::{selectors as remote_files_selectors, RemoteFilesService},
+    repo_files::{mutations as repo_files_mutations, state::RepoFile},
+    repo_files_list::{state::RepoFilesListRecursiveItem, RepoFilesListService},
+    repos::{errors::RepoNotFoundError, selectors as repos_selectors, ReposService},
+    store,
+    utils::path_utils,
+};
+
+use super::{
+    errors::RepoVerifyError,
+    mutations::{self, RepoVerifyProblemAdded},
+    selectors,
+    state::{get_quarantine_path, RepoVerifyProblemType},
+};
+
+/// Checks that all names, sizes and blocks of a repo can be decrypted. Broken
+/// entries can be moved into a quarantine folder next to the repo folder.
+pub struct RepoVerifyService {
+    repos_service: Arc<ReposService>,
+    repo_files_list_service: Arc<RepoFilesListService>,
+    remote_files_service: Arc<RemoteFilesService>,
+    store: Arc<store::Store>,
+}
+
+impl RepoVerifyService {
+    pub fn new(
+        repos_service: Arc<ReposService>,
+        repo_files_list_service: Arc<RepoFilesListService>,
+        remote_files_service: Arc<RemoteFilesService>,
+        store: Arc<store::Store>,
+    ) -> Self {
+        Self {
+            repos_service,
+            repo_files_list_service,
+            remote_files_service,
+            store,
+        }
+    }
+
+    pub fn init(&self, repo_id: &str) {
+        self.store.mutate(store::Event::RepoVerify, |state| {
+            mutations::init(state, repo_id);
+        });
+    }
+
+    pub async fn verify(&self) -> Result<(), RepoVerifyError> {
+        let repo_id = self
+            .store
+            .mutate(store::Event::RepoVerify, mutations::verify_started)
+            .ok_or(RepoVerifyError::RepoNotFound(RepoNotFoundError))?;
+
+        let res = self.verify_repo(&repo_id).await;
+
+        self.store.mutate(store::Event::RepoVerify, |state| {
+            mutations::verify_finished(state, &res);
+        });
+
+        res
+    }
+
+    pub async fn quarantine(&self) -> Result<(), RepoVerifyError> {
+        let (repo_id, entries) = self
+            .store
+            .mutate(store::Event::RepoVerify, mutations::quarantine_started)
+            .ok_or(RepoVerifyError::RepoNotFound(RepoNotFoundError))?;
+
+        let res = self.quarantine_entries(&repo_id, entries).await;
+
+        self.store.mutate(store::Event::RepoVerify, |state| {
+            mutations::quarantine_finished(state, &res);
+        });
+
+        res
+    }
+
+    pub fn destroy(&self, repo_id: &str) {
+        self.store.mutate(store::Event::RepoVerify, |state| {
+            if state.repo_verify.is_some() && state.repo_verify.as_ref().unwrap().repo_id == repo_id
+            {
+                state.repo_verify = None;
+            }
+        });
+    }
+
+    fn is_destroyed(&self, repo_id: &str) -> bool {
+        !self
+            .store
+            .with_state(|state| selectors::select_is_verifying(state, repo_id))
+    }
+
+    async fn get_root_file(&self, repo_id: &str) -> Result<RepoFile, RepoVerifyError> {
+        let location = self.store.with_state(|state| {
+            repos_selectors::select_repo(state, repo_id).map(|repo| repo.get_location())
+        })?;
+
+        self.remote_files_service
+            .load_file(&location.mount_id, &location.path)
+            .await?;
+
+        self.store
+            .with_state(|state| {
+                remote_files_selectors::select_file(
+                    state,
+                    &remote_files_selectors::get_file_id(&location.mount_id, &location.path),
+                )
+                .map(|remote_file| repo_files_mutations::get_root_file(repo_id, remote_file))
+            })
+            .ok_or(RepoVerifyError::RepoNotFound(RepoNotFoundError))
+    }
+
+    async fn verify_repo(&self, repo_id: &str) -> Result<(), RepoVerifyError> {
+        let cipher = self.repos_service.get_cipher(repo_id)?;
+
+        let root_file = self.get_root_file(repo_id).await?;
+
+        let mut items = self
+            .repo_files_list_service
+            .get_list_recursive(&root_file)
+            .await?;
+
+        while let Some(item) = items.next().await {
+            if self.is_destroyed(repo_id) {
+                return Ok(());
+            }
+
+            let problems = mutations::get_item_problems(&item);
+
+            self.store.mutate(store::Event::RepoVerify, |state| {
+                mutations::add_problems(state, problems);
+            });
+
+            if let RepoFilesListRecursiveItem::File { file, .. } = item {
+                if !file.typ.is_file() {
+                    continue;
+                }
+
+                // files with invalid sizes are already reported
+                if let Ok(size) = file.decrypted_size() {
+                    if let Some(problem) = self.verify_file_content(&cipher, &file).await {
+                        self.store.mutate(store::Event::RepoVerify, |state| {
+                            mutations::add_problems(state, vec![problem]);
+                        });
+                    }
+
+                    self.store.mutate(store::Event::RepoVerify, |state| {
+                        mutations::file_checked(state, size);
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the whole file so that every block is authenticated.
+    async fn verify_file_content(
+        &self,
+        cipher: &Cipher,
+        file: &RepoFile,
+    ) -> Option<RepoVerifyProblemAdded> {
+        let typ = match self
+            .remote_files_service
+            .get_file_reader(&file.mount_id, &file.remote_path, None)
+            .await
+        {
+            Ok(file_reader) => {
+                let reader = cipher.decrypt_reader(file_reader.reader);
+
+                match futures::io::copy(reader, &mut futures::io::sink()).await {
+                    Ok(_) => return None,
+                    Err(err) => mutations::content_error_to_problem_type(&err),
+                }
+            }
+            Err(error) => RepoVerifyProblemType::RemoteError { error },
+        };
+
+        Some(RepoVerifyProblemAdded {
+            mount_id: file.mount_id.clone(),
+            remote_path: Some(file.remote_path.clone()),
+            path: file.decrypted_path().ok().map(str::to_owned),
+            typ,
+        })
+    }
+
+    async fn quarantine_entries(
+        &self,
+        repo_id: &str,
+        entries: Vec<(u32, String, String)>,
+    ) -> Result<(), RepoVerifyError> {
+        let location = self.store.with_state(|state| {
+            repos_selectors::select_repo(state, repo_id).map(|repo| repo.get_location())
+        })?;
+
+        let quarantine_path =
+            get_quarantine_path(&location.path).ok_or(RepoVerifyError::RepoInRootFolder)?;
+
+        let mut moved_paths: Vec<String> = Vec::new();
+
+        for (id, mount_id, remote_path) in entries {
+            if self.is_destroyed(repo_id) {
+                return Ok(());
+            }
+
+            // entries are sorted, so a moved parent dir comes before its children
+            let moved_with_parent = moved_paths.iter().any(|moved_path| {
+                remote_path.starts_with(&format!("{}/", moved_path.trim_end_matches('/')))
+            });
+
+            let res = if moved_with_parent {
+                Ok(())
+            } else {
+                self.quarantine_entry(&location.path, &quarantine_path, &mount_id, &remote_path)
+                    .await
+            };
+
+            if res.is_ok() {
+                moved_paths.push(remote_path);
+            }
+
+            self.store.mutate(store::Event::RepoVerify, |state| {
+                mutations::problem_quarantined(state, id, res);
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn quarantine_entry(
+        &self,
+        repo_path: &str,
+        quarantine_path: &str,
+        mount_id: &str,
+        remote_path: &str,
+    ) -> Result<(), RemoteError> {
+        let relative_path = remote_path
+            .strip_prefix(repo_path.trim_end_matches('/'))
+            .unwrap_or(remote_path);
+
+        let to_path = path_utils::join_paths(quarantine_path, relative_path);
+
+        if let Some(to_parent_path) = path_utils::parent_path(&to_path) {
+            self.remote_files_service
+                .ensure_dirs(mount_id, to_parent_path)
+                .await?;
+        }
+
+        self.remote_files_service
+            .move_file(mount_id, remote_path, mount_id, &to_path)
+            .await
+    }
+}
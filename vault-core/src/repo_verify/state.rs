@@ -0,0 +1,117 @@
+use crate::{
+    cipher::errors::{DecryptFilenameError, DecryptSizeError},
+    common::state::Status,
+    remote::RemoteError,
+    utils::path_utils,
+};
+
+use super::errors::RepoVerifyError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RepoVerifyProblemType {
+    InvalidName {
+        error: DecryptFilenameError,
+    },
+    InvalidSize {
+        error: DecryptSizeError,
+    },
+    /// Poly1305 tag of the block failed. offset is the offset of the block in
+    /// the encrypted file
+    InvalidBlock {
+        block_index: u64,
+        offset: u64,
+    },
+    /// File header is missing or the file is truncated
+    InvalidContent {
+        error: String,
+    },
+    /// Remote error while listing or reading the entry
+    RemoteError {
+        error: RemoteError,
+    },
+}
+
+impl RepoVerifyProblemType {
+    pub fn can_quarantine(&self) -> bool {
+        match self {
+            Self::RemoteError { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RepoVerifyQuarantineState {
+    NotQuarantined,
+    Quarantined,
+    Failed { error: RemoteError },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepoVerifyProblem {
+    pub id: u32,
+    pub mount_id: String,
+    /// Missing if the listing of the repo failed
+    pub remote_path: Option<String>,
+    /// Decrypted repo path if the name could be decrypted
+    pub path: Option<String>,
+    pub typ: RepoVerifyProblemType,
+    pub quarantine_state: RepoVerifyQuarantineState,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepoVerifySummary {
+    pub invalid_names_count: u32,
+    pub invalid_sizes_count: u32,
+    pub invalid_contents_count: u32,
+    pub remote_errors_count: u32,
+    pub quarantined_count: u32,
+}
+
+pub struct RepoVerifyInfo<'a> {
+    pub repo_id: &'a str,
+    pub status: &'a Status<RepoVerifyError>,
+    pub quarantine_status: &'a Status<RepoVerifyError>,
+    pub checked_files_count: u32,
+    pub checked_bytes: i64,
+    pub problems: &'a [RepoVerifyProblem],
+    pub summary: RepoVerifySummary,
+    pub repo_name: Option<&'a str>,
+}
+
+#[derive(Clone)]
+pub struct RepoVerifyState {
+    pub repo_id: String,
+    pub status: Status<RepoVerifyError>,
+    pub quarantine_status: Status<RepoVerifyError>,
+    pub checked_files_count: u32,
+    pub checked_bytes: i64,
+    pub problems: Vec<RepoVerifyProblem>,
+    pub next_problem_id: u32,
+}
+
+/// Broken entries are moved into a folder next to the repo folder so that
+/// they can be inspected or restored later.
+pub fn get_quarantine_path(repo_path: &str) -> Option<String> {
+    path_utils::split_parent_name(repo_path).map(|(parent_path, name)| {
+        path_utils::join_path_name(parent_path, &format!("{}.quarantine", name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_quarantine_path;
+
+    #[test]
+    fn test_get_quarantine_path() {
+        assert_eq!(
+            get_quarantine_path("/Vault").as_deref(),
+            Some("/Vault.quarantine")
+        );
+        assert_eq!(
+            get_quarantine_path("/Data/Vault").as_deref(),
+            Some("/Data/Vault.quarantine")
+        );
+        assert_eq!(get_quarantine_path("/"), None);
+    }
+}
@@ -11,6 +11,12 @@ pub struct RepoNotFoundError;
 #[error("repo locked")]
 pub struct RepoLockedError;
 
+/// The repo uses settings (e.g. a filename encryption mode) that are not
+/// supported, its files could not be decrypted correctly.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("unsupported repo: {0}")]
+pub struct UnsupportedRepoError(pub String);
+
 #[derive(Error, Debug, Clone, PartialEq)]
 #[error("invalid password")]
 pub struct InvalidPasswordError;
@@ -52,12 +58,19 @@ pub enum UnlockRepoError {
     RepoNotFound(#[from] RepoNotFoundError),
     #[error("{0}")]
     InvalidPassword(#[from] InvalidPasswordError),
+    #[error("repo rekey was interrupted and must be resumed")]
+    RekeyPending,
+    #[error("{0}")]
+    RemoteError(#[from] remote::RemoteError),
 }
 
 impl UserError for UnlockRepoError {
     fn user_error(&self) -> String {
         match self {
             Self::InvalidPassword(err) => err.user_error(),
+            Self::RekeyPending => {
+                String::from("Changing the Safe Key was interrupted. Change it again to finish.")
+            }
             _ => self.to_string(),
         }
     }
@@ -1,26 +1,49 @@
 use urlencoding::encode;
 
+use crate::cipher::name_cipher::{FilenameEncoding, FilenameEncryption};
 use crate::remote::models;
 use crate::remote_files::selectors as remote_files_selectors;
 use crate::repo_files::selectors as repo_files_selectors;
 use crate::store;
 
-use super::errors::RepoNotFoundError;
+use super::errors::{RepoNotFoundError, UnsupportedRepoError};
 use super::selectors::select_repo;
 use super::state::{Repo, RepoState};
 
-fn vault_repo_to_repo(repo: models::VaultRepo, base_url: &str) -> Repo {
+fn vault_repo_to_repo(
+    repo: models::VaultRepo,
+    base_url: &str,
+) -> Result<Repo, UnsupportedRepoError> {
     let models::VaultRepo {
         id,
         name,
         mount_id,
         path,
         salt,
+        filename_encryption,
+        filename_encoding,
+        directory_name_encryption,
         password_validator,
         password_validator_encrypted,
         added,
     } = repo;
 
+    // a missing value means the default, an unknown one must not fall back to
+    // the default or names would be decrypted with the wrong settings
+    let filename_encryption = match filename_encryption.as_deref() {
+        Some(value) => FilenameEncryption::parse(value).ok_or_else(|| {
+            UnsupportedRepoError(format!("unsupported filename_encryption: {}", value))
+        })?,
+        None => FilenameEncryption::default(),
+    };
+    let filename_encoding = match filename_encoding.as_deref() {
+        Some(value) => FilenameEncoding::parse(value).ok_or_else(|| {
+            UnsupportedRepoError(format!("unsupported filename_encoding: {}", value))
+        })?,
+        None => FilenameEncoding::default(),
+    };
+    let directory_name_encryption = directory_name_encryption.unwrap_or(true);
+
     let web_url = format!(
         "{}/app/storage/{}?path={}",
         base_url,
@@ -28,22 +51,28 @@ fn vault_repo_to_repo(repo: models::VaultRepo, base_url: &str) -> Repo {
         encode(&path)
     );
 
-    Repo {
+    Ok(Repo {
         id,
         name,
         mount_id,
         path,
         salt,
+        filename_encryption,
+        filename_encoding,
+        directory_name_encryption,
         added,
         password_validator,
         password_validator_encrypted,
         state: RepoState::Locked,
         web_url,
-    }
+    })
 }
 
-pub fn repo_loaded(state: &mut store::State, repo: models::VaultRepo) {
-    let repo = vault_repo_to_repo(repo, &state.config.base_url);
+pub fn repo_loaded(
+    state: &mut store::State,
+    repo: models::VaultRepo,
+) -> Result<(), UnsupportedRepoError> {
+    let repo = vault_repo_to_repo(repo, &state.config.base_url)?;
 
     state.repos.repo_ids_by_remote_file_id.insert(
         remote_files_selectors::get_file_id(&repo.mount_id, &repo.path),
@@ -51,13 +80,20 @@ pub fn repo_loaded(state: &mut store::State, repo: models::VaultRepo) {
     );
 
     state.repos.repos_by_id.insert(repo.id.clone(), repo);
+
+    Ok(())
 }
 
 pub fn repos_loaded(state: &mut store::State, repos: Vec<models::VaultRepo>) {
     state.repos.repos_by_id.clear();
 
     for repo in repos {
-        repo_loaded(state, repo);
+        let repo_id = repo.id.clone();
+
+        // unsupported repos are not listed, they could not be unlocked
+        if let Err(err) = repo_loaded(state, repo) {
+            log::warn!("Skipping repo {}: {}", repo_id, err);
+        }
     }
 }
 
@@ -74,6 +110,19 @@ pub fn lock_repo(state: &mut store::State, repo_id: &str) -> Result<(), RepoNotF
         .children
         .retain(|key, _| !key.starts_with(&file_id_prefix));
 
+    state
+        .repo_files
+        .sniffs
+        .retain(|key, _| !key.starts_with(&file_id_prefix));
+
+    // the decrypted index and thumbnails must not outlive the unlocked repo
+    state.repo_files_index.repos.remove(repo_id);
+
+    state
+        .repo_files_thumbnails
+        .thumbnails
+        .retain(|key, _| !key.starts_with(&file_id_prefix));
+
     match state.repos.repos_by_id.get_mut(repo_id) {
         Some(repo) => {
             repo.state = RepoState::Locked;
@@ -107,4 +156,60 @@ pub fn remove_repo(state: &mut store::State, repo_id: &str) {
     }
 
     state.repos.repos_by_id.remove(repo_id);
+    state.repo_files_index.repos.remove(repo_id);
+}
+
+pub fn repos_remembered(state: &mut store::State, repo_ids: Vec<String>) {
+    state.repos.remembered_repo_ids = repo_ids.into_iter().collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cipher::name_cipher::FilenameEncryption, remote::test_helpers as remote_test_helpers, store,
+    };
+
+    use super::{super::errors::UnsupportedRepoError, repo_loaded, repos_loaded};
+
+    #[test]
+    fn test_repos_loaded_unsupported() {
+        let mut state = store::State::default();
+
+        let mut unsupported_encryption = remote_test_helpers::create_repo("r1", "m1", "/R1");
+        unsupported_encryption.filename_encryption = Some(String::from("future"));
+        let mut unsupported_encoding = remote_test_helpers::create_repo("r2", "m1", "/R2");
+        unsupported_encoding.filename_encoding = Some(String::from("future"));
+        let mut obfuscate = remote_test_helpers::create_repo("r3", "m1", "/R3");
+        obfuscate.filename_encryption = Some(String::from("obfuscate"));
+        let default = remote_test_helpers::create_repo("r4", "m1", "/R4");
+
+        assert_eq!(
+            repo_loaded(&mut state, unsupported_encryption.clone()),
+            Err(UnsupportedRepoError(String::from(
+                "unsupported filename_encryption: future"
+            )))
+        );
+
+        repos_loaded(
+            &mut state,
+            vec![
+                unsupported_encryption,
+                unsupported_encoding,
+                obfuscate,
+                default,
+            ],
+        );
+
+        let mut repo_ids = state.repos.repos_by_id.keys().cloned().collect::<Vec<_>>();
+        repo_ids.sort();
+        assert_eq!(repo_ids, vec!["r3", "r4"]);
+        assert_eq!(
+            state.repos.repos_by_id["r3"].filename_encryption,
+            FilenameEncryption::Obfuscate
+        );
+        assert_eq!(
+            state.repos.repos_by_id["r4"].filename_encryption,
+            FilenameEncryption::Standard
+        );
+    }
 }
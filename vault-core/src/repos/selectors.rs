@@ -50,5 +50,10 @@ pub fn select_repo_info<'a>(state: &'a store::State, repo_id: &str) -> RepoInfo<
     RepoInfo {
         status,
         repo: repo.ok(),
+        is_remembered: select_is_remembered(state, repo_id),
     }
 }
+
+pub fn select_is_remembered(state: &store::State, repo_id: &str) -> bool {
+    state.repos.remembered_repo_ids.contains(repo_id)
+}
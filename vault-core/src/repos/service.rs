@@ -2,11 +2,23 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::RwLock;
 
+use futures::channel::oneshot::{self, Receiver, Sender};
+use futures::future::Shared;
+use futures::{AsyncReadExt, FutureExt};
+
+use data_encoding::BASE64;
+
+use crate::cipher::cipher_keys::DerivedKeys;
 use crate::cipher::Cipher;
 use crate::common::state::Status;
+use crate::device_key_store::{DeviceKeyStore, DeviceKeyStoreError};
+use crate::http::HttpError;
 use crate::rclone;
 use crate::remote;
+use crate::repo_rekey::state::REKEY_JOURNAL_NAME;
+use crate::secure_storage::SecureStorageService;
 use crate::store;
+use crate::utils::path_utils;
 
 use super::errors::BuildCipherError;
 use super::errors::InvalidPasswordError;
@@ -14,26 +26,54 @@ use super::errors::RepoConfigError;
 use super::errors::UnlockRepoError;
 use super::errors::{RemoveRepoError, RepoLockedError, RepoNotFoundError};
 use super::password_validator::check_password_validator;
-use super::state::RepoConfig;
+use super::state::{Repo, RepoConfig};
 use super::{mutations, selectors};
 
+const REMEMBERED_REPOS_STORAGE_KEY: &str = "vaultRememberedRepos";
+
 pub struct ReposService {
     remote: Arc<remote::Remote>,
+    secure_storage_service: Arc<SecureStorageService>,
+    device_key_store: Arc<Box<dyn DeviceKeyStore + Send + Sync>>,
     store: Arc<store::Store>,
     ciphers: Arc<RwLock<HashMap<String, Arc<Cipher>>>>,
+    last_activity: Arc<RwLock<HashMap<String, i64>>>,
+    lock_abort_senders: Arc<RwLock<HashMap<String, Sender<()>>>>,
+    lock_abort_receivers: Arc<RwLock<HashMap<String, Shared<Receiver<()>>>>>,
 }
 
 impl ReposService {
-    pub fn new(remote: Arc<remote::Remote>, store: Arc<store::Store>) -> Self {
+    pub fn new(
+        remote: Arc<remote::Remote>,
+        secure_storage_service: Arc<SecureStorageService>,
+        device_key_store: Arc<Box<dyn DeviceKeyStore + Send + Sync>>,
+        store: Arc<store::Store>,
+    ) -> Self {
         Self {
             remote,
+            secure_storage_service,
+            device_key_store,
             store,
             ciphers: Arc::new(RwLock::new(HashMap::new())),
+            last_activity: Arc::new(RwLock::new(HashMap::new())),
+            lock_abort_senders: Arc::new(RwLock::new(HashMap::new())),
+            lock_abort_receivers: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
     pub fn reset(&self) {
-        self.ciphers.write().unwrap().clear()
+        self.ciphers.write().unwrap().clear();
+        self.last_activity.write().unwrap().clear();
+
+        for (_, sender) in self.lock_abort_senders.write().unwrap().drain() {
+            let _ = sender.send(());
+        }
+
+        self.lock_abort_receivers.write().unwrap().clear();
+    }
+
+    pub fn now(&self) -> i64 {
+        instant::now() as i64
     }
 
     pub async fn load_repos(&self) -> Result<(), remote::RemoteError> {
@@ -52,7 +92,7 @@ impl ReposService {
     }
 
     pub fn lock_repo(&self, repo_id: &str) -> Result<(), RepoNotFoundError> {
-        self.ciphers.write().unwrap().remove(repo_id);
+        self.remove_cipher(repo_id);
 
         self.store.mutate(store::Event::Repos, |state| {
             mutations::lock_repo(state, repo_id)
@@ -64,21 +104,18 @@ impl ReposService {
         repo_id: &str,
         password: &str,
     ) -> Result<Cipher, BuildCipherError> {
-        let (salt, password_validator, password_validator_encrypted) =
-            self.store.with_state(|state| {
-                selectors::select_repo(state, repo_id).map(|repo| {
-                    (
-                        repo.salt.clone(),
-                        repo.password_validator.clone(),
-                        repo.password_validator_encrypted.clone(),
-                    )
-                })
-            })?;
+        let repo = self
+            .store
+            .with_state(|state| selectors::select_repo(state, repo_id).map(|repo| repo.clone()))?;
 
-        let cipher = Cipher::new(password, salt.as_deref());
+        let cipher = with_repo_settings(Cipher::new(password, repo.salt.as_deref()), &repo);
 
-        if !check_password_validator(&cipher, &password_validator, &password_validator_encrypted)
-            .await
+        if !check_password_validator(
+            &cipher,
+            &repo.password_validator,
+            &repo.password_validator_encrypted,
+        )
+        .await
         {
             return Err(BuildCipherError::InvalidPassword(InvalidPasswordError));
         }
@@ -89,10 +126,27 @@ impl ReposService {
     pub async fn unlock_repo(&self, repo_id: &str, password: &str) -> Result<(), UnlockRepoError> {
         let cipher = self.build_cipher(repo_id, password).await?;
 
-        self.ciphers
-            .write()
-            .unwrap()
-            .insert(repo_id.to_owned(), Arc::new(cipher));
+        self.check_rekey_journal(repo_id, &cipher).await?;
+
+        self.insert_cipher(repo_id, cipher);
+
+        self.store.mutate(store::Event::Repos, |state| {
+            mutations::unlock_repo(state, repo_id)
+        })?;
+
+        Ok(())
+    }
+
+    /// Unlocks the repo even if a rekey was interrupted. Used by the rekey to
+    /// resume it.
+    pub async fn unlock_repo_for_rekey(
+        &self,
+        repo_id: &str,
+        password: &str,
+    ) -> Result<(), BuildCipherError> {
+        let cipher = self.build_cipher(repo_id, password).await?;
+
+        self.insert_cipher(repo_id, cipher);
 
         self.store.mutate(store::Event::Repos, |state| {
             mutations::unlock_repo(state, repo_id)
@@ -101,6 +155,75 @@ impl ReposService {
         Ok(())
     }
 
+    /// After the folders are swapped the repo folder files are encrypted with
+    /// the new password while the repo still has the old one, so it must not
+    /// be unlocked until the rekey is resumed. The journal is encrypted with
+    /// the old password. If it cannot be decrypted the repo was already
+    /// updated and only the journal was left behind.
+    async fn check_rekey_journal(
+        &self,
+        repo_id: &str,
+        cipher: &Cipher,
+    ) -> Result<(), UnlockRepoError> {
+        let location = self.store.with_state(|state| {
+            selectors::select_repo(state, repo_id).map(|repo| repo.get_location())
+        })?;
+
+        let journal_path = path_utils::join_path_name(&location.path, REKEY_JOURNAL_NAME);
+
+        let file_reader = match self
+            .remote
+            .get_file_reader(&location.mount_id, &journal_path, None)
+            .await
+        {
+            Ok(file_reader) => file_reader,
+            Err(remote::RemoteError::ApiError {
+                code: remote::ApiErrorCode::NotFound,
+                ..
+            }) => return Ok(()),
+            Err(err) => return Err(UnlockRepoError::RemoteError(err)),
+        };
+
+        let mut reader = file_reader.reader;
+        let mut bytes = Vec::new();
+
+        reader.read_to_end(&mut bytes).await.map_err(|err| {
+            UnlockRepoError::RemoteError(remote::RemoteError::HttpError(HttpError::ResponseError(
+                err.to_string(),
+            )))
+        })?;
+
+        if cipher.decrypt_data(&bytes, &mut Vec::new()).await.is_ok() {
+            return Err(UnlockRepoError::RekeyPending);
+        }
+
+        match self
+            .remote
+            .delete_file(&location.mount_id, &journal_path)
+            .await
+        {
+            Ok(())
+            | Err(remote::RemoteError::ApiError {
+                code: remote::ApiErrorCode::NotFound,
+                ..
+            }) => Ok(()),
+            Err(err) => Err(UnlockRepoError::RemoteError(err)),
+        }
+    }
+
+    /// Unlocks the repo with already derived keys, skipping the slow key
+    /// derivation.
+    #[cfg(test)]
+    pub fn unlock_repo_with_cipher(&self, repo_id: &str, cipher: Cipher) {
+        self.insert_cipher(repo_id, cipher);
+
+        self.store
+            .mutate(store::Event::Repos, |state| {
+                mutations::unlock_repo(state, repo_id)
+            })
+            .unwrap();
+    }
+
     pub async fn remove_repo(&self, repo_id: &str, password: &str) -> Result<(), RemoveRepoError> {
         let _ = self.build_cipher(repo_id, password).await?;
 
@@ -115,7 +238,9 @@ impl ReposService {
                 _ => RemoveRepoError::RemoteError(e),
             })?;
 
-        self.ciphers.write().unwrap().remove(repo_id);
+        self.remove_cipher(repo_id);
+
+        self.forget_repo(repo_id);
 
         self.store.mutate(store::Event::Repos, |state| {
             mutations::remove_repo(state, repo_id)
@@ -124,6 +249,57 @@ impl ReposService {
         Ok(())
     }
 
+    pub async fn update_repo_key(
+        &self,
+        repo_id: &str,
+        cipher: Arc<Cipher>,
+        salt: Option<String>,
+        password_validator: String,
+        password_validator_encrypted: String,
+    ) -> Result<(), remote::RemoteError> {
+        let repo = self
+            .remote
+            .update_vault_repo(
+                repo_id,
+                remote::models::VaultRepoUpdate {
+                    salt,
+                    password_validator,
+                    password_validator_encrypted,
+                },
+            )
+            .await?;
+
+        if self
+            .store
+            .with_state(|state| selectors::select_is_remembered(state, repo_id))
+        {
+            // remembered keys were derived from the old password
+            self.remember_cipher(repo_id, &cipher).await;
+        }
+
+        let was_unlocked = match self.ciphers.write().unwrap().get_mut(repo_id) {
+            Some(repo_cipher) => {
+                *repo_cipher = cipher;
+
+                true
+            }
+            None => false,
+        };
+
+        self.store.mutate(store::Event::Repos, |state| {
+            // files decrypted with the old cipher have stale remote paths
+            let _ = mutations::lock_repo(state, repo_id);
+
+            let _ = mutations::repo_loaded(state, repo);
+
+            if was_unlocked {
+                let _ = mutations::unlock_repo(state, repo_id);
+            }
+        });
+
+        Ok(())
+    }
+
     pub async fn get_repo_config(
         &self,
         repo_id: &str,
@@ -139,6 +315,9 @@ impl ReposService {
                 path: repo.path.clone(),
                 password: password.to_owned(),
                 salt: repo.salt.clone(),
+                filename_encryption: repo.filename_encryption,
+                filename_encoding: repo.filename_encoding,
+                directory_name_encryption: repo.directory_name_encryption,
             });
 
             Ok(RepoConfig {
@@ -157,4 +336,247 @@ impl ReposService {
 
         Ok(cipher.clone())
     }
+
+    /// Marks the repo as used by the user. Idle repos are locked by auto lock.
+    pub fn touch_repo(&self, repo_id: &str) {
+        self.last_activity
+            .write()
+            .unwrap()
+            .insert(repo_id.to_owned(), self.now());
+    }
+
+    pub fn get_last_activity(&self, repo_id: &str) -> Option<i64> {
+        self.last_activity.read().unwrap().get(repo_id).cloned()
+    }
+
+    /// Resolves when the repo is locked. Used to abort in-flight reads.
+    pub fn get_lock_abort(&self, repo_id: &str) -> Option<Shared<Receiver<()>>> {
+        self.lock_abort_receivers
+            .read()
+            .unwrap()
+            .get(repo_id)
+            .cloned()
+    }
+
+    fn insert_cipher(&self, repo_id: &str, cipher: Cipher) {
+        self.ciphers
+            .write()
+            .unwrap()
+            .insert(repo_id.to_owned(), Arc::new(cipher));
+
+        // reads of an already unlocked repo keep their abort
+        if !self
+            .lock_abort_senders
+            .read()
+            .unwrap()
+            .contains_key(repo_id)
+        {
+            let (abort_sender, abort_receiver) = oneshot::channel();
+
+            self.lock_abort_senders
+                .write()
+                .unwrap()
+                .insert(repo_id.to_owned(), abort_sender);
+            self.lock_abort_receivers
+                .write()
+                .unwrap()
+                .insert(repo_id.to_owned(), abort_receiver.shared());
+        }
+
+        self.touch_repo(repo_id);
+    }
+
+    fn remove_cipher(&self, repo_id: &str) {
+        self.ciphers.write().unwrap().remove(repo_id);
+        self.last_activity.write().unwrap().remove(repo_id);
+
+        if let Some(sender) = self.lock_abort_senders.write().unwrap().remove(repo_id) {
+            let _ = sender.send(());
+        }
+
+        self.lock_abort_receivers.write().unwrap().remove(repo_id);
+    }
+
+    /// Stores the derived keys of an unlocked repo on this device so that it
+    /// can be unlocked without the password. Keys are wrapped with the device
+    /// key from the platform keystore, the password is never stored.
+    pub async fn remember_repo(&self, repo_id: &str) -> Result<(), RepoLockedError> {
+        let cipher = self.get_cipher(repo_id)?;
+
+        self.remember_cipher(repo_id, &cipher).await;
+
+        Ok(())
+    }
+
+    pub fn forget_repo(&self, repo_id: &str) {
+        let mut remembered = self.load_remembered_repos();
+
+        if remembered.remove(repo_id).is_some() {
+            self.save_remembered_repos(&remembered);
+        }
+
+        self.store.mutate(store::Event::Repos, |state| {
+            mutations::repos_remembered(state, remembered.keys().cloned().collect());
+        });
+    }
+
+    /// The device key stays in the keystore, nothing is wrapped with it
+    /// anymore.
+    pub fn forget_all_repos(&self) {
+        let _ = self
+            .secure_storage_service
+            .remove(REMEMBERED_REPOS_STORAGE_KEY);
+
+        self.store.mutate(store::Event::Repos, |state| {
+            mutations::repos_remembered(state, Vec::new());
+        });
+    }
+
+    /// Unlocks remembered repos. Remembered keys are forgotten only if they
+    /// can never be used again: the device key is gone or the repo password
+    /// was changed. On any other failure (e.g. a keystore error or a repo
+    /// missing from the loaded list) the repo is skipped and its keys are
+    /// kept for the next start.
+    pub async fn unlock_remembered_repos(&self) {
+        let mut remembered = self.load_remembered_repos();
+
+        if remembered.is_empty() {
+            return;
+        }
+
+        let mut forgotten_repo_ids = Vec::new();
+
+        for (repo_id, wrapped_keys) in remembered.iter() {
+            let repo = match self
+                .store
+                .with_state(|state| selectors::select_repo(state, repo_id).map(|repo| repo.clone()))
+            {
+                Ok(repo) => repo,
+                Err(_) => continue,
+            };
+
+            if repo.state.is_unlocked() {
+                continue;
+            }
+
+            let wrapped_keys = match BASE64.decode(wrapped_keys.as_bytes()) {
+                Ok(wrapped_keys) => wrapped_keys,
+                Err(err) => {
+                    log::warn!("Failed to decode remembered repo keys: {}", err);
+
+                    continue;
+                }
+            };
+
+            let keys = match self.device_key_store.unwrap_key(&wrapped_keys).await {
+                Ok(keys) => keys,
+                Err(DeviceKeyStoreError::KeyNotFound) => {
+                    forgotten_repo_ids.push(repo_id.clone());
+
+                    continue;
+                }
+                Err(err) => {
+                    log::warn!("Failed to unwrap remembered repo keys: {}", err);
+
+                    continue;
+                }
+            };
+
+            let cipher = match DerivedKeys::from_bytes(&keys) {
+                Some(DerivedKeys {
+                    data_key,
+                    name_key,
+                    name_tweak,
+                }) => with_repo_settings(Cipher::with_keys(data_key, name_key, name_tweak), &repo),
+                None => {
+                    log::warn!("Invalid remembered repo keys");
+
+                    continue;
+                }
+            };
+
+            if !check_password_validator(
+                &cipher,
+                &repo.password_validator,
+                &repo.password_validator_encrypted,
+            )
+            .await
+            {
+                forgotten_repo_ids.push(repo_id.clone());
+
+                continue;
+            }
+
+            if self.check_rekey_journal(repo_id, &cipher).await.is_err() {
+                continue;
+            }
+
+            self.insert_cipher(repo_id, cipher);
+
+            self.store.mutate(store::Event::Repos, |state| {
+                let _ = mutations::unlock_repo(state, repo_id);
+            });
+        }
+
+        if !forgotten_repo_ids.is_empty() {
+            for repo_id in forgotten_repo_ids {
+                remembered.remove(&repo_id);
+            }
+
+            self.save_remembered_repos(&remembered);
+        }
+
+        self.store.mutate(store::Event::Repos, |state| {
+            mutations::repos_remembered(state, remembered.keys().cloned().collect());
+        });
+    }
+
+    async fn remember_cipher(&self, repo_id: &str, cipher: &Cipher) {
+        let wrapped_keys = match self
+            .device_key_store
+            .wrap_key(&cipher.derived_keys().to_bytes())
+            .await
+        {
+            Ok(wrapped_keys) => wrapped_keys,
+            Err(err) => {
+                log::warn!("Failed to wrap repo keys: {}", err);
+
+                return;
+            }
+        };
+
+        let mut remembered = self.load_remembered_repos();
+
+        remembered.insert(repo_id.to_owned(), BASE64.encode(&wrapped_keys));
+
+        self.save_remembered_repos(&remembered);
+
+        self.store.mutate(store::Event::Repos, |state| {
+            mutations::repos_remembered(state, remembered.keys().cloned().collect());
+        });
+    }
+
+    fn load_remembered_repos(&self) -> HashMap<String, String> {
+        self.secure_storage_service
+            .get(REMEMBERED_REPOS_STORAGE_KEY)
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    fn save_remembered_repos(&self, remembered: &HashMap<String, String>) {
+        if let Err(err) = self
+            .secure_storage_service
+            .set(REMEMBERED_REPOS_STORAGE_KEY, remembered)
+        {
+            log::warn!("Failed to store remembered repos: {}", err);
+        }
+    }
+}
+
+fn with_repo_settings(cipher: Cipher, repo: &Repo) -> Cipher {
+    cipher
+        .with_filename_encryption(repo.filename_encryption)
+        .with_filename_encoding(repo.filename_encoding)
+        .with_directory_name_encryption(repo.directory_name_encryption)
 }
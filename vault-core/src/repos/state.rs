@@ -1,6 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::{common::state::Status, remote::RemoteError, remote_files::state::RemoteFilesLocation};
+use crate::{
+    cipher::name_cipher::{FilenameEncoding, FilenameEncryption},
+    common::state::Status,
+    remote::RemoteError,
+    remote_files::state::RemoteFilesLocation,
+};
 
 use super::errors::RepoInfoError;
 
@@ -30,6 +35,9 @@ pub struct Repo {
     pub mount_id: String,
     pub path: String,
     pub salt: Option<String>,
+    pub filename_encryption: FilenameEncryption,
+    pub filename_encoding: FilenameEncoding,
+    pub directory_name_encryption: bool,
     pub added: i64,
     pub password_validator: String,
     pub password_validator_encrypted: String,
@@ -50,6 +58,8 @@ impl Repo {
 pub struct RepoInfo<'a> {
     pub status: Status<RepoInfoError>,
     pub repo: Option<&'a Repo>,
+    /// Repo keys are stored on this device
+    pub is_remembered: bool,
 }
 
 #[derive(Clone)]
@@ -66,4 +76,5 @@ pub struct ReposState {
     pub status: Status<RemoteError>,
     pub repos_by_id: HashMap<String, Repo>,
     pub repo_ids_by_remote_file_id: HashMap<String, String>,
+    pub remembered_repo_ids: HashSet<String>,
 }
@@ -1,3 +1,5 @@
 pub mod runtime;
+#[cfg(test)]
+pub mod test_runtime;
 
 pub use self::runtime::Runtime;
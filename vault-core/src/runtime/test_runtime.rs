@@ -0,0 +1,26 @@
+use std::{thread, time::Duration};
+
+use futures::{channel::oneshot, executor::block_on, future::BoxFuture, FutureExt};
+
+use super::Runtime;
+
+/// Runs every spawned future on its own thread. Used in service tests.
+pub struct TestRuntime;
+
+impl Runtime for TestRuntime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        thread::spawn(move || block_on(future));
+    }
+
+    fn sleep(&self, duration_ms: i32) -> BoxFuture<'static, ()> {
+        let (sender, receiver) = oneshot::channel();
+
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(duration_ms.max(0) as u64));
+
+            let _ = sender.send(());
+        });
+
+        receiver.map(|_| ()).boxed()
+    }
+}
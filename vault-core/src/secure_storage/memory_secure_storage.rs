@@ -0,0 +1,36 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use super::SecureStorage;
+
+/// Keeps the items in memory. Used in tests.
+#[derive(Default)]
+pub struct MemorySecureStorage {
+    items: Mutex<HashMap<String, String>>,
+}
+
+impl MemorySecureStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SecureStorage for MemorySecureStorage {
+    fn get_item(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.items.lock().unwrap().get(key).cloned())
+    }
+
+    fn set_item(&self, key: &str, value: &str) -> Result<(), String> {
+        self.items
+            .lock()
+            .unwrap()
+            .insert(key.to_owned(), value.to_owned());
+
+        Ok(())
+    }
+
+    fn remove_item(&self, key: &str) -> Result<(), String> {
+        self.items.lock().unwrap().remove(key);
+
+        Ok(())
+    }
+}
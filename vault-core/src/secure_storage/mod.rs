@@ -1,4 +1,5 @@
 pub mod errors;
+pub mod memory_secure_storage;
 pub mod secure_storage;
 pub mod service;
 
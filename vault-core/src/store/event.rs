@@ -7,14 +7,25 @@ pub enum Event {
     Repos,
     RepoCreate,
     RepoUnlock,
+    RepoAutoLock,
     RepoRemove,
+    RepoRekey,
+    RepoVerify,
     RepoConfigBackup,
     RepoSpaceUsage,
+    RepoDuplicates,
     RepoFiles,
     RepoFilesBrowsers,
     RepoFilesDetails,
+    RepoFilesIndex,
     RepoFilesMove,
+    RepoFilesSearch,
+    RepoFilesThumbnails,
+    RepoImportExport,
+    RepoSync,
+    RepoTrash,
     Uploads,
+    Downloads,
     DirPickers,
     SpaceUsage,
 }
@@ -29,14 +40,25 @@ impl Event {
             Self::Repos,
             Self::RepoCreate,
             Self::RepoUnlock,
+            Self::RepoAutoLock,
             Self::RepoRemove,
+            Self::RepoRekey,
+            Self::RepoVerify,
             Self::RepoConfigBackup,
             Self::RepoSpaceUsage,
+            Self::RepoDuplicates,
             Self::RepoFiles,
             Self::RepoFilesBrowsers,
             Self::RepoFilesDetails,
+            Self::RepoFilesIndex,
             Self::RepoFilesMove,
+            Self::RepoFilesSearch,
+            Self::RepoFilesThumbnails,
+            Self::RepoImportExport,
+            Self::RepoSync,
+            Self::RepoTrash,
             Self::Uploads,
+            Self::Downloads,
             Self::DirPickers,
             Self::SpaceUsage,
         ]
@@ -1,13 +1,19 @@
 use crate::{
     config::state::ConfigState, dir_pickers::state::DirPickersState,
-    notifications::state::NotificationsState, oauth2::state::OAuth2State,
-    remote_files::state::RemoteFilesState, repo_config_backup::state::RepoConfigBackupState,
-    repo_create::state::RepoCreateState, repo_files::state::RepoFilesState,
-    repo_files_browsers::state::RepoFilesBrowsersState,
-    repo_files_details::state::RepoFilesDetailsState, repo_files_move::state::RepoFilesMoveState,
+    downloads::state::DownloadsState, notifications::state::NotificationsState,
+    oauth2::state::OAuth2State, remote_files::state::RemoteFilesState,
+    repo_auto_lock::state::RepoAutoLockState, repo_config_backup::state::RepoConfigBackupState,
+    repo_create::state::RepoCreateState, repo_duplicates::state::RepoDuplicatesState,
+    repo_files::state::RepoFilesState, repo_files_browsers::state::RepoFilesBrowsersState,
+    repo_files_details::state::RepoFilesDetailsState, repo_files_index::state::RepoFilesIndexState,
+    repo_files_move::state::RepoFilesMoveState, repo_files_search::state::RepoFilesSearchState,
+    repo_files_thumbnails::state::RepoFilesThumbnailsState,
+    repo_import_export::state::RepoImportExportState, repo_rekey::state::RepoRekeyState,
     repo_remove::state::RepoRemoveState, repo_space_usage::state::RepoSpaceUsageState,
-    repo_unlock::state::RepoUnlockState, repos::state::ReposState,
-    space_usage::state::SpaceUsageState, uploads::state::UploadsState, user::state::UserState,
+    repo_sync::state::RepoSyncState, repo_trash::state::RepoTrashState,
+    repo_unlock::state::RepoUnlockState, repo_verify::state::RepoVerifyState,
+    repos::state::ReposState, space_usage::state::SpaceUsageState, uploads::state::UploadsState,
+    user::state::UserState,
 };
 
 #[derive(Clone, Default)]
@@ -20,21 +26,33 @@ pub struct State {
     pub repos: ReposState,
     pub repo_create: Option<RepoCreateState>,
     pub repo_unlock: Option<RepoUnlockState>,
+    pub repo_auto_lock: RepoAutoLockState,
     pub repo_remove: Option<RepoRemoveState>,
+    pub repo_rekey: Option<RepoRekeyState>,
+    pub repo_verify: Option<RepoVerifyState>,
     pub repo_config_backup: Option<RepoConfigBackupState>,
     pub repo_space_usage: Option<RepoSpaceUsageState>,
+    pub repo_duplicates: Option<RepoDuplicatesState>,
     pub repo_files: RepoFilesState,
     pub repo_files_browsers: RepoFilesBrowsersState,
     pub repo_files_details: RepoFilesDetailsState,
+    pub repo_files_index: RepoFilesIndexState,
     pub repo_files_move: Option<RepoFilesMoveState>,
+    pub repo_files_search: RepoFilesSearchState,
+    pub repo_files_thumbnails: RepoFilesThumbnailsState,
+    pub repo_import_export: Option<RepoImportExportState>,
+    pub repo_sync: RepoSyncState,
+    pub repo_trash: RepoTrashState,
     pub uploads: UploadsState,
+    pub downloads: DownloadsState,
     pub dir_pickers: DirPickersState,
     pub space_usage: SpaceUsageState,
 }
 
 impl State {
     pub fn reset(&mut self) {
-        // config is not reset
+        // config, repo_auto_lock, the repo_files_index settings and the
+        // repo_trash retention are not reset
         self.notifications = Default::default();
         self.oauth2 = Default::default();
         self.user = Default::default();
@@ -43,12 +61,22 @@ impl State {
         self.repo_create = Default::default();
         self.repo_unlock = Default::default();
         self.repo_remove = Default::default();
+        self.repo_rekey = Default::default();
+        self.repo_verify = Default::default();
         self.repo_config_backup = Default::default();
         self.repo_space_usage = Default::default();
+        self.repo_duplicates = Default::default();
         self.repo_files = Default::default();
         self.repo_files_browsers = Default::default();
+        self.repo_files_index.repos.clear();
         self.repo_files_move = Default::default();
+        self.repo_files_search = Default::default();
+        self.repo_files_thumbnails = Default::default();
+        self.repo_import_export = Default::default();
+        self.repo_sync = Default::default();
+        self.repo_trash.list = None;
         self.uploads = Default::default();
+        self.downloads = Default::default();
         self.dir_pickers = Default::default();
         self.space_usage = Default::default();
     }
@@ -2,7 +2,9 @@ use thiserror::Error;
 
 use crate::{
     cipher::errors::DecryptFilenameError,
+    encrypted_storage::errors::EncryptedStorageError,
     remote::RemoteError,
+    repo_files::errors::{EnsureDirError, LoadFilesError},
     repos::errors::{RepoLockedError, RepoNotFoundError},
     user_error::UserError,
 };
@@ -17,6 +19,81 @@ pub enum UploadError {
     DecryptFilenameError(#[from] DecryptFilenameError),
     #[error("{0}")]
     RemoteError(#[from] RemoteError),
+    #[error("upload source not found")]
+    SourceNotFound,
     #[error("upload aborted")]
     Aborted,
 }
+
+#[derive(Error, Debug, Clone, UserError)]
+pub enum UploadExtractError {
+    #[error("invalid archive: {0}")]
+    InvalidArchive(String),
+    #[error("invalid path in archive: {0}")]
+    InvalidPath(String),
+    #[error("{0}")]
+    RepoNotFound(#[from] RepoNotFoundError),
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("{0}")]
+    DecryptFilenameError(#[from] DecryptFilenameError),
+    #[error("{0}")]
+    RemoteError(#[from] RemoteError),
+    #[error("upload source not found")]
+    SourceNotFound,
+    #[error("upload aborted")]
+    Aborted,
+}
+
+impl From<UploadError> for UploadExtractError {
+    fn from(err: UploadError) -> Self {
+        match err {
+            UploadError::RepoNotFound(err) => Self::RepoNotFound(err),
+            UploadError::RepoLocked(err) => Self::RepoLocked(err),
+            UploadError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            UploadError::RemoteError(err) => Self::RemoteError(err),
+            UploadError::SourceNotFound => Self::SourceNotFound,
+            UploadError::Aborted => Self::Aborted,
+        }
+    }
+}
+
+impl From<LoadFilesError> for UploadExtractError {
+    fn from(err: LoadFilesError) -> Self {
+        match err {
+            LoadFilesError::RepoNotFound(err) => Self::RepoNotFound(err),
+            LoadFilesError::RepoLocked(err) => Self::RepoLocked(err),
+            LoadFilesError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+impl From<EnsureDirError> for UploadExtractError {
+    fn from(err: EnsureDirError) -> Self {
+        match err {
+            EnsureDirError::RepoNotFound(err) => Self::RepoNotFound(err),
+            EnsureDirError::RepoLocked(err) => Self::RepoLocked(err),
+            EnsureDirError::DecryptFilenameError(err) => Self::DecryptFilenameError(err),
+            EnsureDirError::RemoteError(err) => Self::RemoteError(err),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, UserError)]
+pub enum UploadsQueueError {
+    #[error("{0}")]
+    RepoLocked(#[from] RepoLockedError),
+    #[error("invalid uploads queue: {0}")]
+    InvalidQueue(String),
+    #[error("storage error: {0}")]
+    StorageError(String),
+}
+
+impl From<EncryptedStorageError> for UploadsQueueError {
+    fn from(err: EncryptedStorageError) -> Self {
+        match err {
+            EncryptedStorageError::InvalidValue(err) => Self::InvalidQueue(err),
+            EncryptedStorageError::StorageError(err) => Self::StorageError(err),
+        }
+    }
+}
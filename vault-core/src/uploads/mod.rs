@@ -1,7 +1,9 @@
 pub mod errors;
 pub mod mutations;
+pub mod persistence;
 pub mod selectors;
 pub mod service;
 pub mod state;
 
+pub use self::persistence::{UploadSources, UploadsPersistence};
 pub use self::service::UploadsService;
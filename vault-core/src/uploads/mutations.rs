@@ -27,6 +27,9 @@ pub struct FileUploadAdded {
     pub name: String,
     pub size: Option<i64>,
     pub is_persistent: bool,
+    pub is_retryable: bool,
+    pub source: Option<String>,
+    pub skip_identical: bool,
 }
 
 pub fn file_upload_added(state: &mut store::State, file: FileUploadAdded, now: i64) {
@@ -46,6 +49,9 @@ pub fn file_upload_added(state: &mut store::State, file: FileUploadAdded, now: i
             icon_type,
             started: now,
             is_persistent: file.is_persistent,
+            is_retryable: file.is_retryable,
+            source: file.source,
+            skip_identical: file.skip_identical,
             state: FileUploadState::Waiting,
             uploaded_bytes: 0,
             attempts: 0,
@@ -139,6 +145,22 @@ pub fn file_upload_failed(state: &mut store::State, id: u32, err: UploadError) {
     }
 }
 
+/// Fails a restored upload whose source could not be reopened. It can only be
+/// discarded.
+pub fn file_upload_source_not_found(state: &mut store::State, id: u32) {
+    if let Some(file) = state.uploads.files.get_mut(&id) {
+        file.state = FileUploadState::Failed {
+            error: UploadError::SourceNotFound,
+        };
+        file.is_retryable = false;
+
+        if let Some(size) = file.size {
+            state.uploads.failed_bytes += size;
+        }
+        state.uploads.failed_count += 1;
+    }
+}
+
 pub fn file_upload_abort(state: &mut store::State, id: u32) {
     if let Some(file) = state.uploads.files.get(&id) {
         state.uploads.total_count -= 1;
@@ -195,6 +217,10 @@ pub fn file_upload_abort_all(state: &mut store::State) -> Vec<u32> {
 
 pub fn file_upload_retry(state: &mut store::State, id: u32, now: i64) {
     if let Some(file) = state.uploads.files.get_mut(&id) {
+        if !file.is_retryable {
+            return;
+        }
+
         file.state = FileUploadState::Waiting;
         file.uploaded_bytes;
 
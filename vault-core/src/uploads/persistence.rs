@@ -0,0 +1,18 @@
+use crate::encrypted_storage::EncryptedStorage;
+
+use super::service::Uploadable;
+
+/// Reopens the sources of persisted uploads (see `RepoFileUploadable::source`).
+pub trait UploadSources {
+    /// Returns None if the source is no longer available.
+    fn open_source(&self, source: &str) -> Option<Uploadable>;
+}
+
+/// Pending uploads are persisted so that they are not lost after a restart.
+/// Uploads are resumed if their source can be reopened, otherwise they are
+/// restored as failed with SourceNotFound. sources is None if the platform
+/// cannot reopen sources, e.g. browser files after a reload.
+pub struct UploadsPersistence {
+    pub storage: Box<dyn EncryptedStorage + Send + Sync>,
+    pub sources: Option<Box<dyn UploadSources + Send + Sync>>,
+}
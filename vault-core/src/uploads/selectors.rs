@@ -5,7 +5,10 @@ use crate::{
     utils::name_utils,
 };
 
-use super::state::{FileUpload, FileUploadState};
+use super::{
+    errors::UploadError,
+    state::{FileUpload, FileUploadState, PersistedUpload},
+};
 
 const MAX_CONCURRENCY: u32 = 3;
 const MAX_AUTO_ATTEMPTS: u32 = 5;
@@ -101,10 +104,74 @@ pub fn select_can_auto_retry(state: &store::State, id: u32) -> bool {
         .uploads
         .files
         .get(&id)
-        .map(|file| file.attempts < MAX_AUTO_ATTEMPTS)
+        .map(|file| {
+            file.is_retryable
+                && file.attempts < MAX_AUTO_ATTEMPTS
+                && match &file.state {
+                    // retrying cannot succeed until the repo is unlocked again
+                    FileUploadState::Failed {
+                        error: UploadError::RepoLocked(_),
+                    } => false,
+                    _ => true,
+                }
+        })
         .unwrap_or(false)
 }
 
+pub fn select_repo_has_pending(state: &store::State, repo_id: &str) -> bool {
+    state.uploads.files.values().any(|file| {
+        file.repo_id == repo_id
+            && match file.state {
+                FileUploadState::Waiting | FileUploadState::Uploading => true,
+                _ => false,
+            }
+    })
+}
+
+pub fn select_repo_uploading_ids(state: &store::State, repo_id: &str) -> Vec<u32> {
+    state
+        .uploads
+        .files
+        .values()
+        .filter(|file| {
+            file.repo_id == repo_id
+                && match file.state {
+                    FileUploadState::Uploading => true,
+                    _ => false,
+                }
+        })
+        .map(|file| file.id)
+        .collect()
+}
+
+/// Pending uploads of the repo that can be resumed after a restart.
+pub fn select_repo_persisted_uploads(state: &store::State, repo_id: &str) -> Vec<PersistedUpload> {
+    select_files(state)
+        .into_iter()
+        .filter(|file| file.repo_id == repo_id && file.is_persistent)
+        .filter(|file| match file.state {
+            FileUploadState::Done => false,
+            _ => true,
+        })
+        .map(|file| PersistedUpload {
+            repo_id: file.repo_id.clone(),
+            parent_path: file.parent_path.clone(),
+            name: file.name.clone(),
+            size: file.size,
+            source: file.source.clone(),
+            skip_identical: file.skip_identical,
+        })
+        .collect()
+}
+
+pub fn select_repo_has_source(state: &store::State, repo_id: &str, source: &str) -> bool {
+    state
+        .uploads
+        .files
+        .values()
+        .any(|file| file.repo_id == repo_id && file.source.as_deref() == Some(source))
+}
+
 pub fn select_unused_name(state: &store::State, id: u32) -> Option<String> {
     let file = state.uploads.files.get(&id)?;
 
@@ -1,51 +1,137 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
+use std::io::Cursor;
 use std::pin::Pin;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
+use futures::channel::mpsc;
 use futures::channel::oneshot::{self, Receiver, Sender};
 use futures::future::Shared;
-use futures::FutureExt;
+use futures::{AsyncRead, AsyncWriteExt, FutureExt, TryStreamExt};
 
+use crate::encrypted_storage::EncryptedStorageService;
 use crate::repo_files::errors::UploadFileReaderError;
-use crate::repo_files::state::{RepoFilesUploadConflictResolution, RepoFilesUploadResult};
+use crate::repo_files::selectors as repo_files_selectors;
+use crate::repo_files::state::{
+    RepoFileUploadable, RepoFilesUploadConflictResolution, RepoFilesUploadResult,
+    RepoFilesUploadSession,
+};
+use crate::repo_files_thumbnails::{
+    selectors as repo_files_thumbnails_selectors, state::THUMBNAIL_MAX_SOURCE_SIZE,
+    RepoFilesThumbnailsService,
+};
+use crate::repos::errors::RepoLockedError;
+use crate::repos::selectors as repos_selectors;
+use crate::repos::ReposService;
 use crate::runtime;
+use crate::utils::{
+    capture_reader::{CaptureBuffer, CaptureReader},
+    error_reader, path_utils,
+    sender_writer::SenderWriter,
+    zip_reader::{ZipEntry, ZipReader},
+};
 use crate::{
     repo_files::{self, RepoFilesService},
     store,
 };
 
 use super::selectors;
-use super::{errors::UploadError, mutations};
+use super::{
+    errors::{UploadError, UploadExtractError, UploadsQueueError},
+    mutations,
+    persistence::{UploadSources, UploadsPersistence},
+    state::{get_storage_key, PersistedUpload},
+};
 
 pub type Uploadable = Pin<Box<dyn repo_files::state::RepoFileUploadable + Send + Sync>>;
 
 pub type UploadResult = Result<RepoFilesUploadResult, UploadError>;
 
+/// Files of at least this size are uploaded in chunks if the uploadable can be
+/// read at an offset, so that a failed upload continues where it stopped.
+pub const RESUMABLE_UPLOAD_MIN_SIZE: i64 = 64 * 1024 * 1024;
+
+/// Queue changes are batched into a single save.
+const SAVE_DELAY: i32 = 1000;
+
+/// Zip entries are streamed from the archive so they can only be read once.
+struct ArchiveEntryUploadable {
+    size: Option<i64>,
+    modified: Option<i64>,
+    reader: Mutex<Option<Pin<Box<dyn AsyncRead + Send + Sync + 'static>>>>,
+}
+
+impl RepoFileUploadable for ArchiveEntryUploadable {
+    fn size(&self) -> Option<i64> {
+        self.size
+    }
+
+    fn reader(&self) -> Pin<Box<dyn AsyncRead + Send + Sync + 'static>> {
+        match self.reader.lock().unwrap().take() {
+            Some(reader) => reader,
+            None => error_reader::error_reader(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "archive entry was already read",
+            )),
+        }
+    }
+
+    fn modified(&self) -> Option<i64> {
+        self.modified
+    }
+}
+
 pub struct UploadsService {
+    repos_service: Arc<ReposService>,
     repo_files_service: Arc<RepoFilesService>,
+    repo_files_thumbnails_service: Arc<RepoFilesThumbnailsService>,
+    /// None if the platform does not persist the queue.
+    storage: Option<EncryptedStorageService>,
+    /// None if the platform cannot reopen upload sources.
+    sources: Option<Box<dyn UploadSources + Send + Sync>>,
     store: Arc<store::Store>,
     runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
     uploadables: Arc<RwLock<HashMap<u32, Uploadable>>>,
     results: Arc<RwLock<HashMap<u32, Sender<UploadResult>>>>,
     abort_senders: Arc<RwLock<HashMap<u32, Sender<()>>>>,
     abort_receivers: Arc<RwLock<HashMap<u32, Shared<Receiver<()>>>>>,
+    upload_sessions: Arc<RwLock<HashMap<u32, RepoFilesUploadSession>>>,
+    pending_saves: Arc<Mutex<HashSet<String>>>,
+    restored_repos: Arc<Mutex<HashSet<String>>>,
 }
 
 impl UploadsService {
     pub fn new(
+        repos_service: Arc<ReposService>,
         repo_files_service: Arc<RepoFilesService>,
+        repo_files_thumbnails_service: Arc<RepoFilesThumbnailsService>,
+        persistence: Option<UploadsPersistence>,
         store: Arc<store::Store>,
         runtime: Arc<Box<dyn runtime::Runtime + Send + Sync>>,
     ) -> Self {
+        let (storage, sources) = match persistence {
+            Some(persistence) => (
+                Some(EncryptedStorageService::new(persistence.storage)),
+                persistence.sources,
+            ),
+            None => (None, None),
+        };
+
         Self {
+            repos_service,
             repo_files_service,
+            repo_files_thumbnails_service,
+            storage,
+            sources,
             store,
             runtime,
             uploadables: Arc::new(RwLock::new(HashMap::new())),
             results: Arc::new(RwLock::new(HashMap::new())),
             abort_senders: Arc::new(RwLock::new(HashMap::new())),
             abort_receivers: Arc::new(RwLock::new(HashMap::new())),
+            upload_sessions: Arc::new(RwLock::new(HashMap::new())),
+            pending_saves: Arc::new(Mutex::new(HashSet::new())),
+            restored_repos: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
@@ -69,10 +155,31 @@ impl UploadsService {
         parent_path: &str,
         name: &str,
         uploadable: Uploadable,
+        skip_identical: bool,
     ) -> impl Future<Output = UploadResult> {
+        let (_, result) =
+            self.add_upload(repo_id, parent_path, name, uploadable, true, skip_identical);
+
+        result
+    }
+
+    fn add_upload(
+        self: Arc<Self>,
+        repo_id: &str,
+        parent_path: &str,
+        name: &str,
+        uploadable: Uploadable,
+        is_retryable: bool,
+        skip_identical: bool,
+    ) -> (u32, impl Future<Output = UploadResult>) {
         let id = self.get_next_id();
 
         let size = uploadable.size();
+        let is_persistent = self.storage.is_some();
+        // sources are only kept if they can be reopened
+        let source = uploadable
+            .source()
+            .filter(|_| is_persistent && self.sources.is_some());
 
         self.uploadables.write().unwrap().insert(id, uploadable);
 
@@ -97,23 +204,364 @@ impl UploadsService {
                     parent_path: parent_path.to_owned(),
                     name: name.to_owned(),
                     size,
-                    is_persistent: false,
+                    is_persistent,
+                    is_retryable,
+                    source,
+                    skip_identical,
                 },
                 self.now(),
             );
         });
 
+        if is_persistent {
+            self.clone().schedule_save(repo_id);
+        }
+
         self.process_next();
 
-        async move { result_receiver.await.unwrap() }
+        (id, async move { result_receiver.await.unwrap() })
     }
 
-    pub fn abort_file(&self, id: u32) {
+    /// Restores the persisted uploads of repos when they are unlocked. Called
+    /// once after the service is created.
+    pub fn restore_on_unlock(self: Arc<Self>) {
+        if self.storage.is_none() {
+            return;
+        }
+
+        let restore_self = self.clone();
+
+        self.store.on(
+            self.store.get_next_id(),
+            &[store::Event::Repos],
+            Box::new(move || {
+                restore_self.clone().restore_unlocked();
+            }),
+        );
+
+        self.restore_unlocked();
+    }
+
+    fn restore_unlocked(self: Arc<Self>) {
+        let repo_ids = self.store.with_state(|state| {
+            repos_selectors::select_repos(state)
+                .into_iter()
+                .filter(|repo| repo.state.is_unlocked())
+                .map(|repo| repo.id.clone())
+                .collect::<Vec<_>>()
+        });
+
+        for repo_id in repo_ids {
+            if self.restored_repos.lock().unwrap().contains(&repo_id) {
+                continue;
+            }
+
+            let restore_self = self.clone();
+
+            self.runtime.spawn(Box::pin(async move {
+                if let Err(err) = restore_self.restore(&repo_id).await {
+                    log::warn!("Failed to restore uploads: {}", err);
+                }
+            }));
+        }
+    }
+
+    /// Restores the persisted uploads of an unlocked repo. Uploads whose
+    /// source can be reopened are queued again, the others fail with
+    /// SourceNotFound and can only be discarded.
+    pub async fn restore(self: Arc<Self>, repo_id: &str) -> Result<(), UploadsQueueError> {
+        if self.storage.is_none() {
+            return Ok(());
+        }
+
+        if !self
+            .restored_repos
+            .lock()
+            .unwrap()
+            .insert(repo_id.to_owned())
+        {
+            return Ok(());
+        }
+
+        let uploads = match self.read_queue(repo_id).await {
+            Ok(uploads) => uploads,
+            Err(err) => {
+                self.restored_repos.lock().unwrap().remove(repo_id);
+
+                return Err(err);
+            }
+        };
+
+        for upload in uploads {
+            if let Some(source) = &upload.source {
+                if self
+                    .store
+                    .with_state(|state| selectors::select_repo_has_source(state, repo_id, source))
+                {
+                    continue;
+                }
+            }
+
+            let uploadable = match (&self.sources, &upload.source) {
+                (Some(sources), Some(source)) => sources.open_source(source),
+                _ => None,
+            };
+
+            match uploadable {
+                Some(uploadable) => {
+                    let _ = self.clone().add_upload(
+                        repo_id,
+                        &upload.parent_path,
+                        &upload.name,
+                        uploadable,
+                        true,
+                        upload.skip_identical,
+                    );
+                }
+                None => {
+                    let id = self.get_next_id();
+
+                    self.store.mutate(store::Event::Uploads, |state| {
+                        mutations::file_upload_added(
+                            state,
+                            mutations::FileUploadAdded {
+                                id,
+                                repo_id: repo_id.to_owned(),
+                                parent_path: upload.parent_path,
+                                name: upload.name,
+                                size: upload.size,
+                                is_persistent: true,
+                                is_retryable: false,
+                                source: upload.source,
+                                skip_identical: upload.skip_identical,
+                            },
+                            self.now(),
+                        );
+
+                        mutations::file_upload_source_not_found(state, id);
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn schedule_save(self: Arc<Self>, repo_id: &str) {
+        if self.storage.is_none() {
+            return;
+        }
+
+        if !self
+            .pending_saves
+            .lock()
+            .unwrap()
+            .insert(repo_id.to_owned())
+        {
+            return;
+        }
+
+        let save_self = self.clone();
+        let repo_id = repo_id.to_owned();
+
+        self.runtime.spawn(Box::pin(async move {
+            save_self.runtime.sleep(SAVE_DELAY).await;
+
+            save_self.pending_saves.lock().unwrap().remove(&repo_id);
+
+            // uploads stored before the restart must not be overwritten
+            if save_self.clone().restore(&repo_id).await.is_err() {
+                return;
+            }
+
+            let uploads = save_self
+                .store
+                .with_state(|state| selectors::select_repo_persisted_uploads(state, &repo_id));
+
+            let _ = save_self.write_queue(&repo_id, &uploads).await;
+        }));
+    }
+
+    fn persisted_repo_id(&self, id: u32) -> Option<String> {
+        self.store.with_state(|state| {
+            selectors::select_file(state, id)
+                .filter(|file| file.is_persistent)
+                .map(|file| file.repo_id.clone())
+        })
+    }
+
+    async fn read_queue(&self, repo_id: &str) -> Result<Vec<PersistedUpload>, UploadsQueueError> {
+        let storage = match &self.storage {
+            Some(storage) => storage,
+            None => return Ok(Vec::new()),
+        };
+
+        let cipher = self.repos_service.get_cipher(repo_id)?;
+
+        Ok(storage
+            .get(&get_storage_key(repo_id), &cipher)
+            .await?
+            .unwrap_or_default())
+    }
+
+    async fn write_queue(
+        &self,
+        repo_id: &str,
+        uploads: &[PersistedUpload],
+    ) -> Result<(), UploadsQueueError> {
+        let storage = match &self.storage {
+            Some(storage) => storage,
+            None => return Ok(()),
+        };
+
+        if uploads.is_empty() {
+            return Ok(storage.remove(&get_storage_key(repo_id))?);
+        }
+
+        let cipher = self.repos_service.get_cipher(repo_id)?;
+
+        Ok(storage
+            .set(&get_storage_key(repo_id), &cipher, &uploads)
+            .await?)
+    }
+
+    /// Uploads a zip archive and extracts it into parent_path. The archive is
+    /// streamed, each file entry is uploaded as a separate file once the
+    /// previous one is done.
+    pub async fn upload_extract(
+        self: Arc<Self>,
+        repo_id: &str,
+        parent_path: &str,
+        uploadable: Uploadable,
+    ) -> Result<Vec<RepoFilesUploadResult>, UploadExtractError> {
+        let mut zip_reader = ZipReader::new(uploadable.reader());
+
+        let mut results = Vec::new();
+        let mut loaded_dirs = HashSet::new();
+
+        while let Some(entry) = zip_reader
+            .next_entry()
+            .await
+            .map_err(|err| UploadExtractError::InvalidArchive(err.to_string()))?
+        {
+            // normalize_path rejects . and .. segments and backslashes (see
+            // validate_path) so entries cannot be extracted outside of
+            // parent_path
+            let entry_path = match path_utils::normalize_path(&entry.name) {
+                Ok(path) => path,
+                Err(_) => return Err(UploadExtractError::InvalidPath(entry.name)),
+            };
+
+            let path = path_utils::join_paths(parent_path, &entry_path);
+
+            // entries must not be extracted into the trash or thumbnails cache
+            if repo_files_selectors::is_hidden_path(&path) {
+                return Err(UploadExtractError::InvalidPath(entry.name));
+            }
+
+            if entry.is_dir {
+                if entry_path != "/" {
+                    self.repo_files_service
+                        .clone()
+                        .ensure_dirs(repo_id, &path)
+                        .await?;
+                }
+
+                continue;
+            }
+
+            let (entry_parent_path, name) = match path_utils::split_parent_name(&path) {
+                Some(parent_name) if entry_path != "/" => parent_name,
+                _ => return Err(UploadExtractError::InvalidPath(entry.name)),
+            };
+
+            self.repo_files_service
+                .clone()
+                .ensure_dirs(repo_id, entry_parent_path)
+                .await?;
+
+            // unused names are picked from the loaded files, so that existing
+            // files and duplicate entries are autorenamed
+            if loaded_dirs.insert(entry_parent_path.to_owned()) {
+                self.repo_files_service
+                    .load_files(repo_id, entry_parent_path)
+                    .await?;
+            }
+
+            results.push(
+                self.clone()
+                    .upload_archive_entry(repo_id, entry_parent_path, name, &entry, &mut zip_reader)
+                    .await?,
+            );
+        }
+
+        Ok(results)
+    }
+
+    async fn upload_archive_entry<R: AsyncRead + Unpin>(
+        self: Arc<Self>,
+        repo_id: &str,
+        parent_path: &str,
+        name: &str,
+        entry: &ZipEntry,
+        zip_reader: &mut ZipReader<R>,
+    ) -> Result<RepoFilesUploadResult, UploadExtractError> {
+        let (tx, rx) = mpsc::channel::<std::io::Result<Vec<u8>>>(10);
+
+        let mut error_tx = tx.clone();
+
+        let uploadable = ArchiveEntryUploadable {
+            size: entry.size.map(|size| size as i64),
+            modified: entry.modified,
+            reader: Mutex::new(Some(Box::pin(rx.into_async_read()))),
+        };
+
+        // entries can only be read once so they cannot be compared before the
+        // upload
+        let (id, result) = self.clone().add_upload(
+            repo_id,
+            parent_path,
+            name,
+            Box::pin(uploadable),
+            false,
+            false,
+        );
+
+        let mut writer = SenderWriter::new(tx);
+
+        match zip_reader.copy_entry(&mut writer).await {
+            // broken pipe means that the upload failed or was aborted, the
+            // error is returned from the result
+            Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => {
+                // fail the reader so that a truncated file is not uploaded
+                let _ = error_tx.try_send(Err(std::io::Error::new(err.kind(), err.to_string())));
+
+                self.abort_file(id);
+
+                Err(UploadExtractError::InvalidArchive(err.to_string()))
+            }
+            _ => {
+                drop(error_tx);
+
+                let _ = writer.close().await;
+
+                Ok(result.await?)
+            }
+        }
+    }
+
+    pub fn abort_file(self: Arc<Self>, id: u32) {
+        let persisted_repo_id = self.persisted_repo_id(id);
+
         self.store.mutate(store::Event::Uploads, |state| {
             mutations::file_upload_abort(state, id);
         });
 
         self.abort_file_cleanup(id);
+
+        if let Some(repo_id) = persisted_repo_id {
+            self.schedule_save(&repo_id);
+        }
     }
 
     pub fn abort_file_cleanup(&self, id: u32) {
@@ -128,9 +576,21 @@ impl UploadsService {
         }
 
         self.abort_receivers.write().unwrap().remove(&id);
+
+        self.upload_sessions.write().unwrap().remove(&id);
     }
 
-    pub fn abort_all(&self) {
+    pub fn abort_all(self: Arc<Self>) {
+        let persisted_repo_ids = self.store.with_state(|state| {
+            state
+                .uploads
+                .files
+                .values()
+                .filter(|file| file.is_persistent)
+                .map(|file| file.repo_id.clone())
+                .collect::<HashSet<String>>()
+        });
+
         let ids = self.store.mutate(store::Event::Uploads, |state| {
             mutations::file_upload_abort_all(state)
         });
@@ -138,6 +598,31 @@ impl UploadsService {
         for id in ids {
             self.abort_file_cleanup(id);
         }
+
+        for repo_id in persisted_repo_ids {
+            self.clone().schedule_save(&repo_id);
+        }
+    }
+
+    /// Aborts in-flight uploads of a locked repo. They fail with RepoLocked and
+    /// can be retried once the repo is unlocked again.
+    pub fn pause_repo(&self, repo_id: &str) {
+        let ids = self
+            .store
+            .with_state(|state| selectors::select_repo_uploading_ids(state, repo_id));
+
+        for id in ids {
+            let (abort_sender, abort_receiver) = oneshot::channel();
+
+            if let Some(sender) = self.abort_senders.write().unwrap().insert(id, abort_sender) {
+                let _ = sender.send(());
+            }
+
+            self.abort_receivers
+                .write()
+                .unwrap()
+                .insert(id, abort_receiver.shared());
+        }
     }
 
     pub fn retry_file(self: Arc<Self>, id: u32) {
@@ -172,23 +657,25 @@ impl UploadsService {
             mutations::file_upload_uploading(state, id, self.now());
         });
 
-        let (repo_id, parent_path, autorename_name) = match self.store.with_state(|state| {
-            selectors::select_file(state, id).map(|file| {
-                (
-                    file.repo_id.clone(),
-                    file.parent_path.clone(),
-                    file.autorename_name
-                        .as_ref()
-                        .cloned()
-                        .unwrap_or_else(|| file.name.clone()),
-                )
-            })
-        }) {
-            Some(file) => file,
-            None => {
-                return;
-            }
-        };
+        let (repo_id, parent_path, autorename_name, skip_identical) =
+            match self.store.with_state(|state| {
+                selectors::select_file(state, id).map(|file| {
+                    (
+                        file.repo_id.clone(),
+                        file.parent_path.clone(),
+                        file.autorename_name
+                            .as_ref()
+                            .cloned()
+                            .unwrap_or_else(|| file.name.clone()),
+                        file.skip_identical,
+                    )
+                })
+            }) {
+                Some(file) => file,
+                None => {
+                    return;
+                }
+            };
 
         let uploadable = match self.uploadables.write().unwrap().remove(&id) {
             Some(uploadable) => uploadable,
@@ -209,33 +696,125 @@ impl UploadsService {
         };
 
         let size = uploadable.size();
-        let reader = uploadable.reader();
 
         let upload_future_self = self.clone();
 
         let upload_future = async move {
             let progress_self = upload_future_self.clone();
 
-            match upload_future_self
-                .repo_files_service
-                .clone()
-                .upload_file_reader(
-                    &repo_id,
-                    &parent_path,
-                    &autorename_name,
-                    reader,
-                    size,
-                    RepoFilesUploadConflictResolution::Error,
-                    Some(Box::new(move |n| {
-                        progress_self.store.mutate(store::Event::Uploads, |state| {
-                            mutations::file_upload_progress(state, id, n as i64);
-                        });
-                    })),
-                    abort,
-                )
-                .await
-            {
+            // a resumed upload reports the already uploaded bytes as progress
+            // first, uploaded bytes were reset by file_upload_uploading so
+            // done bytes stay consistent
+            let on_progress: Box<dyn Fn(usize) + Send + Sync> = Box::new(move |n| {
+                progress_self.store.mutate(store::Event::Uploads, |state| {
+                    mutations::file_upload_progress(state, id, n as i64);
+                });
+            });
+
+            let identical_file = if skip_identical {
+                upload_future_self
+                    .repo_files_service
+                    .find_identical_file(&repo_id, &parent_path, &autorename_name, &*uploadable)
+                    .await
+            } else {
+                None
+            };
+
+            let is_identical = identical_file.is_some();
+
+            // the uploadable might only be readable once, so the plaintext of a
+            // possible thumbnail source is kept while it is uploaded. Resumable
+            // uploads are larger than thumbnail sources and identical files
+            // already have a thumbnail.
+            let thumbnail_source = (!is_identical
+                && repo_files_thumbnails_selectors::can_thumbnail_name(&autorename_name, size))
+            .then(|| CaptureBuffer::new(THUMBNAIL_MAX_SOURCE_SIZE as usize));
+
+            let result = match (identical_file, size) {
+                // the destination already has the same content
+                (Some(identical_file), _) => Ok(identical_file),
+                (None, Some(size))
+                    if size >= RESUMABLE_UPLOAD_MIN_SIZE && uploadable.reader_at(0).is_some() =>
+                {
+                    let session = upload_future_self
+                        .upload_sessions
+                        .read()
+                        .unwrap()
+                        .get(&id)
+                        .cloned();
+
+                    let session_self = upload_future_self.clone();
+
+                    upload_future_self
+                        .repo_files_service
+                        .clone()
+                        .upload_file_resumable(
+                            &repo_id,
+                            &parent_path,
+                            &autorename_name,
+                            &*uploadable,
+                            size,
+                            RepoFilesUploadConflictResolution::Error,
+                            session,
+                            Box::new(move |session| {
+                                session_self
+                                    .upload_sessions
+                                    .write()
+                                    .unwrap()
+                                    .insert(id, session);
+                            }),
+                            Some(on_progress),
+                            abort,
+                        )
+                        .await
+                }
+                _ => {
+                    let reader = uploadable.reader();
+                    let reader: Pin<Box<dyn AsyncRead + Send + Sync + 'static>> =
+                        match &thumbnail_source {
+                            Some(buffer) => Box::pin(CaptureReader::new(reader, buffer.clone())),
+                            None => reader,
+                        };
+
+                    upload_future_self
+                        .repo_files_service
+                        .clone()
+                        .upload_file_reader(
+                            &repo_id,
+                            &parent_path,
+                            &autorename_name,
+                            reader,
+                            size,
+                            uploadable.modified(),
+                            RepoFilesUploadConflictResolution::Error,
+                            Some(on_progress),
+                            abort,
+                        )
+                        .await
+                }
+            };
+
+            match result {
                 Ok(res) => {
+                    upload_future_self
+                        .upload_sessions
+                        .write()
+                        .unwrap()
+                        .remove(&id);
+
+                    let persisted_repo_id = upload_future_self.persisted_repo_id(id);
+
+                    let thumbnail = thumbnail_source
+                        .and_then(|buffer| buffer.take())
+                        .map(|bytes| (res.file_id.clone(), bytes))
+                        .filter(|(file_id, _)| {
+                            upload_future_self.store.with_state(|state| {
+                                repo_files_selectors::select_file(state, file_id)
+                                    .map(repo_files_thumbnails_selectors::can_thumbnail)
+                                    .unwrap_or(false)
+                            })
+                        });
+
                     upload_future_self
                         .store
                         .mutate(store::Event::Uploads, |state| {
@@ -246,7 +825,23 @@ impl UploadsService {
                         let _ = sender.send(Ok(res));
                     }
 
-                    upload_future_self.process_next();
+                    if let Some(repo_id) = persisted_repo_id {
+                        upload_future_self.clone().schedule_save(&repo_id);
+                    }
+
+                    upload_future_self.clone().process_next();
+
+                    // the thumbnail is created from the captured plaintext,
+                    // after the next upload was started
+                    if let Some((file_id, bytes)) = thumbnail {
+                        if let Err(err) = upload_future_self
+                            .repo_files_thumbnails_service
+                            .create_from_reader(&file_id, Box::pin(Cursor::new(bytes)))
+                            .await
+                        {
+                            log::warn!("Failed to create thumbnail: {}", err);
+                        }
+                    }
                 }
                 Err(err) => {
                     let err = match err {
@@ -258,16 +853,33 @@ impl UploadsService {
                         UploadFileReaderError::RemoteError(err) => UploadError::RemoteError(err),
                     };
 
+                    // upload was paused because the repo was locked
+                    let err = if upload_future_self.store.with_state(|state| {
+                        repos_selectors::select_repo(state, &repo_id)
+                            .map(|repo| repo.state.is_locked())
+                            .unwrap_or(false)
+                    }) {
+                        UploadError::RepoLocked(RepoLockedError)
+                    } else {
+                        err
+                    };
+
                     upload_future_self
                         .uploadables
                         .write()
                         .unwrap()
                         .insert(id, uploadable);
 
+                    let is_retryable = upload_future_self.store.with_state(|state| {
+                        selectors::select_file(state, id)
+                            .map(|file| file.is_retryable)
+                            .unwrap_or(true)
+                    });
+
                     upload_future_self
                         .store
                         .mutate(store::Event::Uploads, |state| {
-                            mutations::file_upload_failed(state, id, err);
+                            mutations::file_upload_failed(state, id, err.clone());
                         });
 
                     if upload_future_self
@@ -276,6 +888,14 @@ impl UploadsService {
                     {
                         upload_future_self.retry_file(id);
                     } else {
+                        if !is_retryable {
+                            if let Some(sender) =
+                                upload_future_self.results.write().unwrap().remove(&id)
+                            {
+                                let _ = sender.send(Err(err));
+                            }
+                        }
+
                         upload_future_self.process_next();
                     }
                 }
@@ -285,3 +905,314 @@ impl UploadsService {
         self.runtime.spawn(Box::pin(upload_future));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, sync::Arc, thread, time::Duration};
+
+    use futures::executor::block_on;
+    use image::{DynamicImage, ImageOutputFormat, RgbImage};
+
+    use crate::{
+        cipher::test_helpers::create_cipher,
+        encrypted_storage::{
+            memory_encrypted_storage::MemoryEncryptedStorage, EncryptedStorageService,
+        },
+        repo_files::test_helpers::{BytesUploadable, TestContext, TEST_MOUNT_ID},
+        repo_files_thumbnails::{
+            selectors as repo_files_thumbnails_selectors, RepoFilesThumbnailsService,
+        },
+        utils::crc32::crc32,
+    };
+
+    use super::{
+        super::{
+            errors::{UploadError, UploadExtractError},
+            selectors,
+            state::{get_storage_key, FileUpload, FileUploadState, PersistedUpload},
+            UploadsPersistence,
+        },
+        UploadsService,
+    };
+
+    fn setup() -> (TestContext, Arc<UploadsService>) {
+        setup_with_persistence(None)
+    }
+
+    fn setup_with_persistence(
+        persistence: Option<UploadsPersistence>,
+    ) -> (TestContext, Arc<UploadsService>) {
+        let ctx = TestContext::new();
+        ctx.add_repo("r1", "/Vault");
+
+        let uploads_service = Arc::new(UploadsService::new(
+            ctx.repos_service.clone(),
+            ctx.repo_files_service.clone(),
+            Arc::new(RepoFilesThumbnailsService::new(
+                ctx.repos_service.clone(),
+                ctx.repo_files_service.clone(),
+                ctx.store.clone(),
+                ctx.runtime.clone(),
+            )),
+            persistence,
+            ctx.store.clone(),
+            ctx.runtime.clone(),
+        ));
+
+        (ctx, uploads_service)
+    }
+
+    /// Uploads persisted before a restart, sources cannot be reopened
+    fn setup_persisted(uploads: &[PersistedUpload]) -> (TestContext, Arc<UploadsService>) {
+        let storage = MemoryEncryptedStorage::new();
+
+        let (ctx, uploads_service) = setup_with_persistence(Some(UploadsPersistence {
+            storage: Box::new(storage.clone()),
+            sources: None,
+        }));
+
+        block_on(EncryptedStorageService::new(Box::new(storage)).set(
+            &get_storage_key("r1"),
+            &ctx.cipher("r1"),
+            &uploads,
+        ))
+        .unwrap();
+
+        (ctx, uploads_service)
+    }
+
+    fn persisted_upload(name: &str) -> PersistedUpload {
+        PersistedUpload {
+            repo_id: String::from("r1"),
+            parent_path: String::from("/"),
+            name: name.to_owned(),
+            size: Some(3),
+            source: None,
+            skip_identical: false,
+        }
+    }
+
+    fn upload_files(ctx: &TestContext) -> Vec<FileUpload> {
+        ctx.store.with_state(|state| {
+            selectors::select_files(state)
+                .into_iter()
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// Zip archive with stored entries
+    fn zip_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = Vec::new();
+
+        for (name, content) in entries {
+            zip.extend_from_slice(&0x04034b50u32.to_le_bytes());
+            zip.extend_from_slice(&20u16.to_le_bytes());
+            zip.extend_from_slice(&0u16.to_le_bytes());
+            zip.extend_from_slice(&0u16.to_le_bytes());
+            zip.extend_from_slice(&0x63cau16.to_le_bytes());
+            zip.extend_from_slice(&0x5669u16.to_le_bytes());
+            zip.extend_from_slice(&crc32(content).to_le_bytes());
+            zip.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            zip.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            zip.extend_from_slice(&0u16.to_le_bytes());
+            zip.extend_from_slice(name.as_bytes());
+            zip.extend_from_slice(content);
+        }
+
+        zip.extend_from_slice(&0x02014b50u32.to_le_bytes());
+
+        zip
+    }
+
+    #[test]
+    fn test_upload_extract() {
+        let (ctx, uploads_service) = setup();
+        ctx.add_file("r1", "/Extract/F.txt", b"existing");
+
+        let archive = zip_archive(&[
+            ("D1/", b""),
+            ("D1/F1.txt", b"f1"),
+            ("F.txt", b"first"),
+            ("F.txt", b"second"),
+        ]);
+
+        let res = block_on(uploads_service.clone().upload_extract(
+            "r1",
+            "/Extract",
+            Box::pin(BytesUploadable::new(&archive)),
+        ))
+        .unwrap();
+
+        assert_eq!(
+            res.iter().map(|res| res.name.as_str()).collect::<Vec<_>>(),
+            vec!["F1.txt", "F (1).txt", "F (2).txt"]
+        );
+        assert_eq!(
+            ctx.repo_paths("r1"),
+            vec![
+                "/Extract",
+                "/Extract/D1",
+                "/Extract/D1/F1.txt",
+                "/Extract/F (1).txt",
+                "/Extract/F (2).txt",
+                "/Extract/F.txt",
+            ]
+        );
+        assert_eq!(
+            ctx.file_content("r1", "/Extract/F.txt").unwrap(),
+            b"existing".to_vec()
+        );
+        assert_eq!(
+            ctx.file_content("r1", "/Extract/F (1).txt").unwrap(),
+            b"first".to_vec()
+        );
+        assert_eq!(
+            ctx.file_content("r1", "/Extract/F (2).txt").unwrap(),
+            b"second".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_upload_extract_invalid_path() {
+        for invalid_name in ["../x", "a/../../x", "a\\..\\x", "./x", "D1/../../x/"] {
+            let (ctx, uploads_service) = setup();
+
+            let archive = zip_archive(&[("ok.txt", b"ok"), (invalid_name, b"x")]);
+
+            let res = block_on(uploads_service.clone().upload_extract(
+                "r1",
+                "/Extract",
+                Box::pin(BytesUploadable::new(&archive)),
+            ));
+
+            assert!(
+                matches!(&res, Err(UploadExtractError::InvalidPath(name)) if name == invalid_name),
+                "{}",
+                invalid_name
+            );
+            assert_eq!(ctx.repo_paths("r1"), vec!["/Extract", "/Extract/ok.txt"]);
+            assert!(ctx
+                .fake_remote
+                .paths(TEST_MOUNT_ID)
+                .iter()
+                .all(|path| path == "/Vault" || path.starts_with("/Vault/")));
+        }
+    }
+
+    #[test]
+    fn test_upload_extract_hidden_path() {
+        for hidden_name in [".trash/x", ".thumbnails/", ".trash/"] {
+            let (ctx, uploads_service) = setup();
+
+            let archive = zip_archive(&[("ok.txt", b"ok"), (hidden_name, b"")]);
+
+            let res = block_on(uploads_service.clone().upload_extract(
+                "r1",
+                "/",
+                Box::pin(BytesUploadable::new(&archive)),
+            ));
+
+            assert!(
+                matches!(&res, Err(UploadExtractError::InvalidPath(name)) if name == hidden_name),
+                "{}",
+                hidden_name
+            );
+            assert_eq!(ctx.repo_paths("r1"), vec!["/ok.txt"]);
+        }
+    }
+
+    #[test]
+    fn test_upload_extract_thumbnail() {
+        let (ctx, uploads_service) = setup();
+
+        let mut image = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(600, 300))
+            .write_to(&mut Cursor::new(&mut image), ImageOutputFormat::Png)
+            .unwrap();
+
+        // archive entries can only be read once, the thumbnail is created from
+        // the plaintext captured during the upload
+        let archive = zip_archive(&[("image.png", image.as_slice())]);
+
+        let res = block_on(uploads_service.clone().upload_extract(
+            "r1",
+            "/Extract",
+            Box::pin(BytesUploadable::new(&archive)),
+        ))
+        .unwrap();
+
+        let file_id = res[0].file_id.clone();
+
+        // the thumbnail is created after the upload result is sent
+        let created = (0..500).any(|_| {
+            let created = ctx.store.with_state(|state| {
+                repo_files_thumbnails_selectors::select_thumbnail_bytes(state, &file_id)
+                    .0
+                    .is_some()
+            });
+
+            if !created {
+                thread::sleep(Duration::from_millis(10));
+            }
+
+            created
+        });
+
+        assert!(created);
+        assert_eq!(ctx.file_content("r1", "/Extract/image.png").unwrap(), image);
+    }
+
+    #[test]
+    fn test_restore_source_not_found() {
+        let (ctx, uploads_service) = setup_persisted(&[persisted_upload("a.txt")]);
+
+        block_on(uploads_service.clone().restore("r1")).unwrap();
+
+        let files = upload_files(&ctx);
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "a.txt");
+        assert!(files[0].is_persistent);
+        assert!(!files[0].is_retryable);
+        assert!(matches!(
+            files[0].state,
+            FileUploadState::Failed {
+                error: UploadError::SourceNotFound
+            }
+        ));
+
+        // restoring again does not duplicate the uploads
+        block_on(uploads_service.clone().restore("r1")).unwrap();
+
+        assert_eq!(upload_files(&ctx).len(), 1);
+    }
+
+    #[test]
+    fn test_restore_on_unlock() {
+        let (ctx, uploads_service) = setup_persisted(&[persisted_upload("a.txt")]);
+
+        ctx.repos_service.lock_repo("r1").unwrap();
+
+        uploads_service.clone().restore_on_unlock();
+
+        assert!(upload_files(&ctx).is_empty());
+
+        ctx.repos_service
+            .unlock_repo_with_cipher("r1", create_cipher());
+
+        // the uploads are restored in the background
+        let restored = (0..500).any(|_| {
+            let restored = !upload_files(&ctx).is_empty();
+
+            if !restored {
+                thread::sleep(Duration::from_millis(10));
+            }
+
+            restored
+        });
+
+        assert!(restored);
+    }
+}
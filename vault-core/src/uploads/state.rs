@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
     file_types::file_icon_type::FileIconType, repo_files::selectors as repo_files_selectors,
 };
@@ -25,6 +27,10 @@ pub struct FileUpload {
     pub icon_type: FileIconType,
     pub started: i64,
     pub is_persistent: bool,
+    pub is_retryable: bool,
+    pub source: Option<String>,
+    /// Skip the upload if the destination file has the same content
+    pub skip_identical: bool,
     pub state: FileUploadState,
     pub uploaded_bytes: i64,
     pub attempts: u32,
@@ -50,3 +56,20 @@ pub struct UploadsState {
     pub failed_bytes: i64,
     pub total_bytes: i64,
 }
+
+/// Pending upload stored in the uploads queue (see `UploadsPersistence`).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedUpload {
+    pub repo_id: String,
+    pub parent_path: String,
+    pub name: String,
+    pub size: Option<i64>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub skip_identical: bool,
+}
+
+pub fn get_storage_key(repo_id: &str) -> String {
+    format!("vaultUploads:{}", repo_id)
+}
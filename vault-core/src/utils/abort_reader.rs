@@ -0,0 +1,112 @@
+use futures::task::{Context, Poll};
+use futures::{AsyncRead, Future, FutureExt};
+use pin_project_lite::pin_project;
+use std::io::{Error, ErrorKind, Result};
+use std::pin::Pin;
+
+pin_project! {
+    /// Fails all reads once the abort future resolves (with any output).
+    pub struct AbortReader<R, F> {
+        #[pin]
+        inner: R,
+        abort: Option<F>,
+        on_read: Option<Box<dyn Fn() + Send + Sync>>,
+    }
+}
+
+impl<R, F> AbortReader<R, F> {
+    pub fn new(inner: R, abort: Option<F>) -> Self {
+        Self {
+            inner,
+            abort,
+            on_read: None,
+        }
+    }
+
+    /// on_read is called after every successful read.
+    pub fn with_on_read(self, on_read: Box<dyn Fn() + Send + Sync>) -> Self {
+        Self {
+            on_read: Some(on_read),
+            ..self
+        }
+    }
+}
+
+impl<R: AsyncRead, F: Future + Unpin> AsyncRead for AbortReader<R, F> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let this = self.project();
+
+        if let Some(abort) = this.abort {
+            if abort.poll_unpin(cx).is_ready() {
+                return Poll::Ready(Err(Error::new(ErrorKind::Other, "read aborted")));
+            }
+        }
+
+        let res = this.inner.poll_read(cx, buf);
+
+        if let (Poll::Ready(Ok(_)), Some(on_read)) = (&res, this.on_read) {
+            on_read();
+        }
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use futures::{channel::oneshot, executor::block_on, future, AsyncReadExt, FutureExt};
+
+    use super::AbortReader;
+
+    #[test]
+    fn test_abort_reader() {
+        block_on(async {
+            let (abort_sender, abort_receiver) = oneshot::channel::<()>();
+            let abort = abort_receiver.shared();
+
+            let mut reader = AbortReader::new(&b"hello"[..], Some(abort.clone()));
+            let mut buf = [0u8; 2];
+
+            assert_eq!(reader.read(&mut buf).await.unwrap(), 2);
+
+            abort_sender.send(()).unwrap();
+
+            assert!(reader.read(&mut buf).await.is_err());
+
+            let mut reader = AbortReader::new(&b"hello"[..], None::<future::Ready<()>>);
+            let mut data = Vec::new();
+
+            reader.read_to_end(&mut data).await.unwrap();
+
+            assert_eq!(data, b"hello");
+        });
+    }
+
+    #[test]
+    fn test_abort_reader_on_read() {
+        block_on(async {
+            let reads = Arc::new(AtomicUsize::new(0));
+            let on_read_reads = reads.clone();
+
+            let mut reader = AbortReader::new(&b"hello"[..], None::<future::Ready<()>>)
+                .with_on_read(Box::new(move || {
+                    on_read_reads.fetch_add(1, Ordering::SeqCst);
+                }));
+            let mut buf = [0u8; 2];
+
+            assert_eq!(reader.read(&mut buf).await.unwrap(), 2);
+            assert_eq!(reader.read(&mut buf).await.unwrap(), 2);
+
+            assert_eq!(reads.load(Ordering::SeqCst), 2);
+        });
+    }
+}
@@ -0,0 +1,108 @@
+use std::io::Result;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use futures::task::{Context, Poll};
+use futures::{ready, AsyncRead};
+use pin_project_lite::pin_project;
+
+/// Data read through the readers, up to a limit. The data is dropped once it
+/// exceeds the limit.
+#[derive(Clone)]
+pub struct CaptureBuffer {
+    buf: Arc<Mutex<Option<Vec<u8>>>>,
+    limit: usize,
+}
+
+impl CaptureBuffer {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            buf: Arc::new(Mutex::new(Some(Vec::new()))),
+            limit,
+        }
+    }
+
+    pub fn append(&self, data: &[u8]) {
+        let mut buf = self.buf.lock().unwrap();
+
+        if let Some(captured) = buf.as_mut() {
+            if captured.len() + data.len() > self.limit {
+                *buf = None;
+            } else {
+                captured.extend_from_slice(data);
+            }
+        }
+    }
+
+    /// None if the data exceeded the limit
+    pub fn take(&self) -> Option<Vec<u8>> {
+        self.buf.lock().unwrap().take()
+    }
+}
+
+pin_project! {
+    /// Copies the data to the buffer as it is read.
+    pub struct CaptureReader<R> {
+        #[pin]
+        inner: R,
+        buffer: CaptureBuffer,
+    }
+}
+
+impl<R> CaptureReader<R> {
+    pub fn new(inner: R, buffer: CaptureBuffer) -> Self {
+        Self { inner, buffer }
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for CaptureReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let this = self.project();
+
+        let n = ready!(this.inner.poll_read(cx, buf))?;
+
+        this.buffer.append(&buf[..n]);
+
+        Poll::Ready(Ok(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, AsyncReadExt};
+
+    use super::{CaptureBuffer, CaptureReader};
+
+    #[test]
+    fn test_capture_reader() {
+        block_on(async {
+            let buffer = CaptureBuffer::new(5);
+
+            let mut reader = CaptureReader::new(&b"hello"[..], buffer.clone());
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).await.unwrap();
+
+            assert_eq!(data, b"hello");
+            assert_eq!(buffer.take(), Some(b"hello".to_vec()));
+        });
+    }
+
+    #[test]
+    fn test_capture_reader_limit() {
+        block_on(async {
+            let buffer = CaptureBuffer::new(4);
+
+            let mut reader = CaptureReader::new(&b"hello"[..], buffer.clone());
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).await.unwrap();
+
+            // the data is still read, only the capture is dropped
+            assert_eq!(data, b"hello");
+            assert_eq!(buffer.take(), None);
+        });
+    }
+}
@@ -0,0 +1,8 @@
+use std::pin::Pin;
+
+use futures::{AsyncRead, TryStreamExt};
+
+/// Returns a reader that fails with err on the first read.
+pub fn error_reader(err: std::io::Error) -> Pin<Box<dyn AsyncRead + Send + Sync + 'static>> {
+    Box::pin(futures::stream::iter(vec![Err::<Vec<u8>, _>(err)]).into_async_read())
+}
@@ -0,0 +1,89 @@
+use std::io::Result;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use data_encoding::HEXLOWER;
+use futures::task::{Context, Poll};
+use futures::{ready, AsyncRead};
+use pin_project_lite::pin_project;
+use sha2::{Digest, Sha256};
+
+/// SHA-256 state shared between readers, e.g. the readers of the chunks of a
+/// resumable upload.
+#[derive(Clone, Default)]
+pub struct ContentHasher {
+    hasher: Arc<Mutex<Sha256>>,
+}
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&self, data: &[u8]) {
+        self.hasher.lock().unwrap().input(data);
+    }
+
+    /// Lowercase hex digest of the data hashed so far
+    pub fn hex_digest(&self) -> String {
+        HEXLOWER.encode(&self.hasher.lock().unwrap().clone().result())
+    }
+}
+
+pin_project! {
+    /// Hashes the data as it is read.
+    pub struct HashReader<R> {
+        #[pin]
+        inner: R,
+        hasher: ContentHasher,
+    }
+}
+
+impl<R> HashReader<R> {
+    pub fn new(inner: R, hasher: ContentHasher) -> Self {
+        Self { inner, hasher }
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for HashReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let this = self.project();
+
+        let n = ready!(this.inner.poll_read(cx, buf))?;
+
+        this.hasher.update(&buf[..n]);
+
+        Poll::Ready(Ok(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, AsyncReadExt};
+
+    use super::{ContentHasher, HashReader};
+
+    #[test]
+    fn test_hash_reader() {
+        block_on(async {
+            let hasher = ContentHasher::new();
+
+            let mut reader = HashReader::new(&b"hel"[..], hasher.clone());
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).await.unwrap();
+
+            let mut reader = HashReader::new(&b"lo"[..], hasher.clone());
+            reader.read_to_end(&mut data).await.unwrap();
+
+            assert_eq!(data, b"hello");
+            assert_eq!(
+                hasher.hex_digest(),
+                "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+            );
+        });
+    }
+}
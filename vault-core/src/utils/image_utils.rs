@@ -0,0 +1,84 @@
+use std::io::Cursor;
+
+use image::{io::Limits, DynamicImage, ImageOutputFormat, ImageResult};
+
+/// Decoding larger images would need too much memory (4 bytes per pixel)
+const MAX_ALLOC: u64 = 256 * 1024 * 1024;
+
+const JPEG_QUALITY: u8 = 80;
+
+/// Decodes the image (format is guessed from the content) and downscales it to
+/// fit in a max_size square. Images with an alpha channel are encoded as PNG,
+/// others as JPEG.
+pub fn create_thumbnail(bytes: &[u8], max_size: u32) -> ImageResult<Vec<u8>> {
+    let mut limits = Limits::default();
+    limits.max_alloc = Some(MAX_ALLOC);
+
+    let mut reader = image::io::Reader::new(Cursor::new(bytes)).with_guessed_format()?;
+    reader.limits(limits);
+
+    let image = reader.decode()?;
+
+    let thumbnail = if image.width() > max_size || image.height() > max_size {
+        image.thumbnail(max_size, max_size)
+    } else {
+        image
+    };
+
+    let mut buf = Vec::new();
+
+    if thumbnail.color().has_alpha() {
+        thumbnail.write_to(&mut Cursor::new(&mut buf), ImageOutputFormat::Png)?;
+    } else {
+        DynamicImage::ImageRgb8(thumbnail.to_rgb8()).write_to(
+            &mut Cursor::new(&mut buf),
+            ImageOutputFormat::Jpeg(JPEG_QUALITY),
+        )?;
+    }
+
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageFormat, ImageOutputFormat, RgbImage, RgbaImage};
+
+    use super::create_thumbnail;
+
+    fn encode(image: DynamicImage) -> Vec<u8> {
+        let mut buf = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut buf), ImageOutputFormat::Png)
+            .unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_create_thumbnail() {
+        let bytes = encode(DynamicImage::ImageRgb8(RgbImage::new(600, 300)));
+
+        let thumbnail = create_thumbnail(&bytes, 256).unwrap();
+
+        assert_eq!(image::guess_format(&thumbnail).unwrap(), ImageFormat::Jpeg);
+        let thumbnail = image::load_from_memory(&thumbnail).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (256, 128));
+    }
+
+    #[test]
+    fn test_create_thumbnail_small_alpha() {
+        let bytes = encode(DynamicImage::ImageRgba8(RgbaImage::new(100, 50)));
+
+        let thumbnail = create_thumbnail(&bytes, 256).unwrap();
+
+        assert_eq!(image::guess_format(&thumbnail).unwrap(), ImageFormat::Png);
+        let thumbnail = image::load_from_memory(&thumbnail).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (100, 50));
+    }
+
+    #[test]
+    fn test_create_thumbnail_invalid() {
+        assert!(create_thumbnail(b"not an image", 256).is_err());
+    }
+}
@@ -1,5 +1,13 @@
+pub mod abort_reader;
+pub mod capture_reader;
+pub mod crc32;
+pub mod error_reader;
+pub mod hash_reader;
+pub mod image_utils;
 pub mod name_utils;
 pub mod path_utils;
 pub mod progress_reader;
 pub mod reader_stream;
 pub mod sender_writer;
+pub mod tar_writer;
+pub mod zip_reader;
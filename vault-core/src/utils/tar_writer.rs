@@ -0,0 +1,315 @@
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+pub const BLOCK_SIZE: usize = 512;
+
+const NAME_SIZE: usize = 100;
+// 11 octal digits
+const MAX_OCTAL_VALUE: u64 = 0o77777777777;
+const PAX_HEADER_NAME: &str = "././@PaxHeader";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TarEntryType {
+    File,
+    Dir,
+}
+
+impl TarEntryType {
+    fn typeflag(&self) -> u8 {
+        match self {
+            Self::File => b'0',
+            Self::Dir => b'5',
+        }
+    }
+
+    fn mode(&self) -> u64 {
+        match self {
+            Self::File => 0o644,
+            Self::Dir => 0o755,
+        }
+    }
+}
+
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
+
+fn header_block(name: &str, typ: u8, mode: u64, size: u64, mtime: u64) -> [u8; BLOCK_SIZE] {
+    let mut block = [0u8; BLOCK_SIZE];
+
+    // long names are truncated, the full name is stored in the pax header
+    let name = name.as_bytes();
+    let name_len = name.len().min(NAME_SIZE);
+    block[..name_len].copy_from_slice(&name[..name_len]);
+
+    write_octal(&mut block[100..108], mode);
+    write_octal(&mut block[108..116], 0);
+    write_octal(&mut block[116..124], 0);
+    write_octal(&mut block[124..136], size);
+    write_octal(&mut block[136..148], mtime);
+    block[156] = typ;
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+
+    // checksum is calculated with the checksum field filled with spaces
+    block[148..156].copy_from_slice(b"        ");
+    let checksum: u64 = block.iter().map(|b| *b as u64).sum();
+    write_octal(&mut block[148..155], checksum);
+    block[155] = b' ';
+
+    block
+}
+
+/// Returns a pax extended header record. The length prefix includes itself.
+pub fn pax_record(key: &str, value: &str) -> String {
+    // space, equals sign and newline
+    let base_len = key.len() + value.len() + 3;
+
+    let mut len = base_len + 1;
+
+    loop {
+        let new_len = base_len + len.to_string().len();
+
+        if new_len == len {
+            break;
+        }
+
+        len = new_len;
+    }
+
+    format!("{} {}={}\n", len, key, value)
+}
+
+pub fn padding_len(size: u64) -> usize {
+    (BLOCK_SIZE - (size % BLOCK_SIZE as u64) as usize) % BLOCK_SIZE
+}
+
+/// Returns the header blocks of an entry. Names longer than 100 bytes and
+/// sizes of 8 GiB or more are stored in a pax extended header.
+pub fn entry_header(path: &str, typ: TarEntryType, size: u64, mtime: u64) -> Vec<u8> {
+    let name = match typ {
+        TarEntryType::File => path.to_owned(),
+        TarEntryType::Dir => format!("{}/", path.trim_end_matches('/')),
+    };
+
+    let mut pax = String::new();
+
+    if name.len() > NAME_SIZE {
+        pax.push_str(&pax_record("path", &name));
+    }
+
+    let header_size = if size > MAX_OCTAL_VALUE {
+        pax.push_str(&pax_record("size", &size.to_string()));
+
+        0
+    } else {
+        size
+    };
+
+    let mut header = Vec::with_capacity(BLOCK_SIZE * 3);
+
+    if !pax.is_empty() {
+        header.extend_from_slice(&header_block(
+            PAX_HEADER_NAME,
+            b'x',
+            0o644,
+            pax.len() as u64,
+            mtime,
+        ));
+        header.extend_from_slice(pax.as_bytes());
+        header.resize(header.len() + padding_len(pax.len() as u64), 0);
+    }
+
+    header.extend_from_slice(&header_block(
+        &name,
+        typ.typeflag(),
+        typ.mode(),
+        header_size,
+        mtime.min(MAX_OCTAL_VALUE),
+    ));
+
+    header
+}
+
+/// Streaming tar writer. Entry sizes have to be known before the content is
+/// written.
+pub struct TarWriter<W: AsyncWrite + Unpin> {
+    writer: W,
+}
+
+impl<W: AsyncWrite + Unpin> TarWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub async fn write_dir(&mut self, path: &str, mtime: u64) -> std::io::Result<()> {
+        self.writer
+            .write_all(&entry_header(path, TarEntryType::Dir, 0, mtime))
+            .await
+    }
+
+    pub async fn write_file<R: AsyncRead + Unpin>(
+        &mut self,
+        path: &str,
+        size: u64,
+        mtime: u64,
+        reader: R,
+    ) -> std::io::Result<()> {
+        self.writer
+            .write_all(&entry_header(path, TarEntryType::File, size, mtime))
+            .await?;
+
+        let written = futures::io::copy(reader.take(size), &mut self.writer).await?;
+
+        // a shorter file would corrupt the rest of the archive
+        if written != size {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("expected {} bytes, got {}", size, written),
+            ));
+        }
+
+        self.writer.write_all(&vec![0; padding_len(size)]).await
+    }
+
+    pub async fn close(mut self) -> std::io::Result<()> {
+        // end of archive is marked by two zero blocks
+        self.writer.write_all(&[0; BLOCK_SIZE * 2]).await?;
+
+        self.writer.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::io::Cursor;
+
+    use super::{entry_header, padding_len, pax_record, TarEntryType, TarWriter, BLOCK_SIZE};
+
+    fn header_field(header: &[u8], start: usize, end: usize) -> &str {
+        std::str::from_utf8(&header[start..end])
+            .unwrap()
+            .trim_end_matches('\0')
+    }
+
+    fn checksum_valid(block: &[u8]) -> bool {
+        let expected = u64::from_str_radix(header_field(block, 148, 155), 8).unwrap();
+
+        let actual: u64 = block
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    *b as u64
+                }
+            })
+            .sum();
+
+        expected == actual
+    }
+
+    #[test]
+    fn test_pax_record() {
+        assert_eq!(pax_record("path", "a"), "9 path=a\n");
+        assert_eq!(pax_record("path", "ab"), "11 path=ab\n");
+        assert_eq!(pax_record("path", "abcdefghij"), "19 path=abcdefghij\n");
+    }
+
+    #[test]
+    fn test_padding_len() {
+        assert_eq!(padding_len(0), 0);
+        assert_eq!(padding_len(1), 511);
+        assert_eq!(padding_len(512), 0);
+        assert_eq!(padding_len(513), 511);
+    }
+
+    #[test]
+    fn test_entry_header_file() {
+        let header = entry_header("D1/F1.txt", TarEntryType::File, 100, 1678358492);
+
+        assert_eq!(header.len(), BLOCK_SIZE);
+        assert_eq!(header_field(&header, 0, 100), "D1/F1.txt");
+        assert_eq!(header_field(&header, 100, 108), "0000644");
+        assert_eq!(header_field(&header, 124, 136), "00000000144");
+        assert_eq!(header_field(&header, 136, 148), "14402333734");
+        assert_eq!(header[156], b'0');
+        assert_eq!(header_field(&header, 257, 263), "ustar");
+        assert!(checksum_valid(&header));
+    }
+
+    #[test]
+    fn test_entry_header_dir() {
+        let header = entry_header("D1", TarEntryType::Dir, 0, 0);
+
+        assert_eq!(header.len(), BLOCK_SIZE);
+        assert_eq!(header_field(&header, 0, 100), "D1/");
+        assert_eq!(header_field(&header, 100, 108), "0000755");
+        assert_eq!(header[156], b'5');
+        assert!(checksum_valid(&header));
+    }
+
+    #[test]
+    fn test_entry_header_pax() {
+        let path = format!("{}/F1", "D".repeat(120));
+
+        let header = entry_header(&path, TarEntryType::File, 0o100000000000, 0);
+
+        assert_eq!(header.len(), BLOCK_SIZE * 3);
+        assert_eq!(header_field(&header, 0, 100), "././@PaxHeader");
+        assert_eq!(header[156], b'x');
+        assert!(checksum_valid(&header[..BLOCK_SIZE]));
+
+        let pax = format!(
+            "{}{}",
+            pax_record("path", &path),
+            pax_record("size", "8589934592")
+        );
+        assert_eq!(&header[BLOCK_SIZE..BLOCK_SIZE + pax.len()], pax.as_bytes());
+
+        let entry = &header[BLOCK_SIZE * 2..];
+        assert_eq!(header_field(entry, 0, 100), &path[..100]);
+        assert_eq!(header_field(entry, 124, 136), "00000000000");
+        assert!(checksum_valid(entry));
+    }
+
+    #[test]
+    fn test_tar_writer() {
+        futures::executor::block_on(async {
+            let mut out = Vec::new();
+
+            let mut writer = TarWriter::new(Cursor::new(&mut out));
+            writer.write_dir("D1", 1).await.unwrap();
+            writer
+                .write_file("D1/F1", 3, 1, Cursor::new(b"abc".to_vec()))
+                .await
+                .unwrap();
+            writer.close().await.unwrap();
+
+            assert_eq!(out.len(), BLOCK_SIZE * 5);
+            assert_eq!(&out[BLOCK_SIZE * 2..BLOCK_SIZE * 2 + 3], b"abc");
+            assert!(out[BLOCK_SIZE * 3..].iter().all(|b| *b == 0));
+        });
+    }
+
+    #[test]
+    fn test_tar_writer_short_file() {
+        futures::executor::block_on(async {
+            let mut out = Vec::new();
+
+            let mut writer = TarWriter::new(Cursor::new(&mut out));
+
+            assert_eq!(
+                writer
+                    .write_file("F1", 10, 1, Cursor::new(b"abc".to_vec()))
+                    .await
+                    .unwrap_err()
+                    .kind(),
+                std::io::ErrorKind::UnexpectedEof
+            );
+        });
+    }
+}
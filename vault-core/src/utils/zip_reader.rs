@@ -0,0 +1,708 @@
+use std::{
+    cmp::min,
+    io::{Error, ErrorKind},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_compression::futures::bufread::DeflateDecoder;
+use futures::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::crc32::Crc32;
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06064b50;
+const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x08074b50;
+const ZIP64_EXTRA_FIELD_ID: u16 = 0x0001;
+
+const FLAG_ENCRYPTED: u16 = 1 << 0;
+const FLAG_DATA_DESCRIPTOR: u16 = 1 << 3;
+
+const BUF_CAPACITY: usize = 64 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ZipCompression {
+    Stored,
+    Deflate,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZipEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub compression: ZipCompression,
+    pub compressed_size: Option<u64>,
+    pub size: Option<u64>,
+    /// None if the crc32 is written to the data descriptor after the data
+    pub crc32: Option<u32>,
+    pub modified: Option<i64>,
+    pub has_data_descriptor: bool,
+    pub is_zip64: bool,
+}
+
+fn invalid_data(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.to_owned())
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+/// Converts MS-DOS date and time to unix millis. Zip timestamps have no time
+/// zone, they are treated as UTC.
+pub fn dos_date_time_to_millis(date: u16, time: u16) -> Option<i64> {
+    let year = (date >> 9) as i32 + 1980;
+    let month = ((date >> 5) & 0x0f) as u32;
+    let day = (date & 0x1f) as u32;
+    let hour = (time >> 11) as u32;
+    let minute = ((time >> 5) & 0x3f) as u32;
+    let second = ((time & 0x1f) * 2) as u32;
+
+    chrono::NaiveDate::from_ymd_opt(year, month, day)
+        .and_then(|date| date.and_hms_opt(hour, minute, second))
+        .map(|date_time| date_time.timestamp_millis())
+}
+
+fn data_descriptor_len(is_zip64: bool) -> usize {
+    if is_zip64 {
+        24
+    } else {
+        16
+    }
+}
+
+/// Finds a data descriptor of a stored entry. A stored entry has the same
+/// compressed and uncompressed size and both have to match the number of bytes
+/// before the descriptor.
+pub fn find_stored_data_descriptor(data: &[u8], offset: u64, is_zip64: bool) -> Option<usize> {
+    let len = data_descriptor_len(is_zip64);
+
+    if data.len() < len {
+        return None;
+    }
+
+    (0..=data.len() - len).find(|pos| {
+        let size = offset + *pos as u64;
+
+        read_u32(data, *pos) == DATA_DESCRIPTOR_SIGNATURE
+            && if is_zip64 {
+                read_u64(data, pos + 8) == size && read_u64(data, pos + 16) == size
+            } else {
+                read_u32(data, pos + 8) as u64 == size && read_u32(data, pos + 12) as u64 == size
+            }
+    })
+}
+
+/// Buffered reader that can push bytes back.
+pub struct PeekReader<R: AsyncRead + Unpin> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: AsyncRead + Unpin> PeekReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    pub fn unread(&mut self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+
+        let mut buf = Vec::with_capacity(data.len() + self.buf.len() - self.pos);
+        buf.extend_from_slice(data);
+        buf.extend_from_slice(&self.buf[self.pos..]);
+
+        self.buf = buf;
+        self.pos = 0;
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncBufRead for PeekReader<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+        let this = self.get_mut();
+
+        if this.pos >= this.buf.len() {
+            this.buf.resize(BUF_CAPACITY, 0);
+            this.pos = 0;
+
+            match Pin::new(&mut this.inner).poll_read(cx, &mut this.buf) {
+                Poll::Ready(Ok(n)) => this.buf.truncate(n),
+                Poll::Ready(Err(err)) => {
+                    this.buf.clear();
+
+                    return Poll::Ready(Err(err));
+                }
+                Poll::Pending => {
+                    this.buf.clear();
+
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        Poll::Ready(Ok(&this.buf[this.pos..]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+
+        this.pos = min(this.pos + amt, this.buf.len());
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for PeekReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let available = futures::ready!(self.as_mut().poll_fill_buf(cx))?;
+
+        let n = min(available.len(), buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+
+        self.consume(n);
+
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// Computes the CRC-32 of the data written to the inner writer.
+struct Crc32Writer<'a, W: AsyncWrite + Unpin> {
+    inner: &'a mut W,
+    crc: Crc32,
+}
+
+impl<'a, W: AsyncWrite + Unpin> Crc32Writer<'a, W> {
+    fn new(inner: &'a mut W) -> Self {
+        Self {
+            inner,
+            crc: Crc32::new(),
+        }
+    }
+
+    fn crc32(&self) -> u32 {
+        self.crc.finalize()
+    }
+}
+
+impl<'a, W: AsyncWrite + Unpin> AsyncWrite for Crc32Writer<'a, W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+
+        let n = futures::ready!(Pin::new(&mut *this.inner).poll_write(cx, buf))?;
+
+        this.crc.update(&buf[..n]);
+
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut *self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut *self.get_mut().inner).poll_close(cx)
+    }
+}
+
+/// Streaming zip reader. Entries are read from local file headers, the central
+/// directory is never read. Entries have to be read in order.
+pub struct ZipReader<R: AsyncRead + Unpin> {
+    reader: PeekReader<R>,
+    entry: Option<ZipEntry>,
+    is_done: bool,
+}
+
+impl<R: AsyncRead + Unpin> ZipReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: PeekReader::new(reader),
+            entry: None,
+            is_done: false,
+        }
+    }
+
+    async fn read_vec(&mut self, len: usize) -> std::io::Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+
+        self.reader.read_exact(&mut buf).await?;
+
+        Ok(buf)
+    }
+
+    /// Returns the next entry. Data of the previous entry is skipped if it was
+    /// not copied.
+    pub async fn next_entry(&mut self) -> std::io::Result<Option<ZipEntry>> {
+        if self.entry.is_some() {
+            // skipped data is not verified
+            self.read_entry(&mut futures::io::sink(), false).await?;
+        }
+
+        if self.is_done {
+            return Ok(None);
+        }
+
+        let signature = self.read_vec(4).await?;
+
+        match read_u32(&signature, 0) {
+            LOCAL_FILE_HEADER_SIGNATURE => {}
+            CENTRAL_DIRECTORY_SIGNATURE
+            | END_OF_CENTRAL_DIRECTORY_SIGNATURE
+            | ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE => {
+                self.is_done = true;
+
+                return Ok(None);
+            }
+            _ => return Err(invalid_data("invalid zip signature")),
+        }
+
+        let header = self.read_vec(26).await?;
+
+        let flags = read_u16(&header, 2);
+        let method = read_u16(&header, 4);
+        let time = read_u16(&header, 6);
+        let date = read_u16(&header, 8);
+        let crc32 = read_u32(&header, 10);
+        let mut compressed_size = read_u32(&header, 14) as u64;
+        let mut size = read_u32(&header, 18) as u64;
+        let name_len = read_u16(&header, 22) as usize;
+        let extra_len = read_u16(&header, 24) as usize;
+
+        let name = String::from_utf8_lossy(&self.read_vec(name_len).await?).to_string();
+        let extra = self.read_vec(extra_len).await?;
+
+        if flags & FLAG_ENCRYPTED != 0 {
+            return Err(invalid_data("encrypted zip entries are not supported"));
+        }
+
+        let compression = match method {
+            0 => ZipCompression::Stored,
+            8 => ZipCompression::Deflate,
+            _ => {
+                return Err(invalid_data(&format!(
+                    "unsupported zip compression method: {}",
+                    method
+                )))
+            }
+        };
+
+        let mut is_zip64 = false;
+        let mut offset = 0;
+
+        while offset + 4 <= extra.len() {
+            let field_id = read_u16(&extra, offset);
+            let field_len = read_u16(&extra, offset + 2) as usize;
+            let field = &extra[offset + 4..min(offset + 4 + field_len, extra.len())];
+
+            if field_id == ZIP64_EXTRA_FIELD_ID {
+                is_zip64 = true;
+
+                let mut field_offset = 0;
+
+                if size == u32::MAX as u64 && field_offset + 8 <= field.len() {
+                    size = read_u64(field, field_offset);
+                    field_offset += 8;
+                }
+                if compressed_size == u32::MAX as u64 && field_offset + 8 <= field.len() {
+                    compressed_size = read_u64(field, field_offset);
+                }
+            }
+
+            offset += 4 + field_len;
+        }
+
+        let has_data_descriptor = flags & FLAG_DATA_DESCRIPTOR != 0;
+
+        // sizes are written to the data descriptor after the data
+        let (compressed_size, size) = if has_data_descriptor && compressed_size == 0 {
+            (None, None)
+        } else {
+            (Some(compressed_size), Some(size))
+        };
+
+        let entry = ZipEntry {
+            is_dir: name.ends_with('/'),
+            name,
+            compression,
+            compressed_size,
+            size,
+            crc32: if has_data_descriptor {
+                None
+            } else {
+                Some(crc32)
+            },
+            modified: dos_date_time_to_millis(date, time),
+            has_data_descriptor,
+            is_zip64,
+        };
+
+        self.entry = Some(entry.clone());
+
+        Ok(Some(entry))
+    }
+
+    /// Copies the uncompressed data of the current entry and returns the number
+    /// of bytes written. Fails if the size or the crc32 of the data does not
+    /// match the local file header or the data descriptor.
+    pub async fn copy_entry<W: AsyncWrite + Unpin>(
+        &mut self,
+        writer: &mut W,
+    ) -> std::io::Result<u64> {
+        self.read_entry(writer, true).await
+    }
+
+    async fn read_entry<W: AsyncWrite + Unpin>(
+        &mut self,
+        writer: &mut W,
+        verify_crc32: bool,
+    ) -> std::io::Result<u64> {
+        let entry = match self.entry.take() {
+            Some(entry) => entry,
+            None => return Err(Error::new(ErrorKind::InvalidInput, "no current zip entry")),
+        };
+
+        let mut writer = Crc32Writer::new(writer);
+
+        let (written, descriptor_crc32) = match (entry.compression, entry.compressed_size) {
+            (ZipCompression::Stored, Some(compressed_size)) => {
+                let written =
+                    futures::io::copy((&mut self.reader).take(compressed_size), &mut writer)
+                        .await?;
+
+                if written != compressed_size {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "unexpected end of zip entry",
+                    ));
+                }
+
+                (written, None)
+            }
+            (ZipCompression::Stored, None) => {
+                let (written, crc32) = self
+                    .copy_stored_until_data_descriptor(&mut writer, entry.is_zip64)
+                    .await?;
+
+                (written, Some(crc32))
+            }
+            (ZipCompression::Deflate, _) => {
+                // the decoder only consumes the compressed stream
+                let decoder = DeflateDecoder::new(&mut self.reader);
+
+                (futures::io::copy(decoder, &mut writer).await?, None)
+            }
+        };
+
+        if let Some(size) = entry.size {
+            if written != size {
+                return Err(invalid_data("zip entry size mismatch"));
+            }
+        }
+
+        let crc32 = match (entry.crc32, descriptor_crc32) {
+            (Some(crc32), _) | (None, Some(crc32)) => crc32,
+            (None, None) => self.read_data_descriptor(entry.is_zip64).await?,
+        };
+
+        if verify_crc32 && writer.crc32() != crc32 {
+            return Err(invalid_data("zip entry crc32 mismatch"));
+        }
+
+        Ok(written)
+    }
+
+    /// Reads the data descriptor and returns its crc32
+    async fn read_data_descriptor(&mut self, is_zip64: bool) -> std::io::Result<u32> {
+        let sizes_len = if is_zip64 { 16 } else { 8 };
+
+        // the signature is optional, without it the first field is crc32
+        let signature = self.read_vec(4).await?;
+
+        if read_u32(&signature, 0) == DATA_DESCRIPTOR_SIGNATURE {
+            let descriptor = self.read_vec(4 + sizes_len).await?;
+
+            Ok(read_u32(&descriptor, 0))
+        } else {
+            self.read_vec(sizes_len).await?;
+
+            Ok(read_u32(&signature, 0))
+        }
+    }
+
+    async fn copy_stored_until_data_descriptor<W: AsyncWrite + Unpin>(
+        &mut self,
+        writer: &mut W,
+        is_zip64: bool,
+    ) -> std::io::Result<(u64, u32)> {
+        let descriptor_len = data_descriptor_len(is_zip64);
+
+        let mut pending: Vec<u8> = Vec::new();
+        let mut written: u64 = 0;
+
+        loop {
+            if let Some(pos) = find_stored_data_descriptor(&pending, written, is_zip64) {
+                writer.write_all(&pending[..pos]).await?;
+                written += pos as u64;
+
+                self.reader.unread(&pending[pos + descriptor_len..]);
+
+                return Ok((written, read_u32(&pending, pos + 4)));
+            }
+
+            // keep the tail, a data descriptor could start there
+            let flush_len = pending.len().saturating_sub(descriptor_len - 1);
+
+            if flush_len > 0 {
+                writer.write_all(&pending[..flush_len]).await?;
+                written += flush_len as u64;
+
+                pending.drain(..flush_len);
+            }
+
+            let buf = self.reader.fill_buf().await?;
+
+            if buf.is_empty() {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "zip data descriptor not found",
+                ));
+            }
+
+            pending.extend_from_slice(buf);
+
+            let len = buf.len();
+            self.reader.consume_unpin(len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::io::Cursor;
+
+    use crate::utils::crc32::crc32;
+
+    use super::{
+        dos_date_time_to_millis, find_stored_data_descriptor, PeekReader, ZipCompression, ZipEntry,
+        ZipReader,
+    };
+    use futures::AsyncReadExt;
+
+    // "abc" compressed with raw deflate
+    const DEFLATE_ABC: [u8; 5] = [0x4b, 0x4c, 0x4a, 0x06, 0x00];
+
+    fn local_file_header(
+        name: &str,
+        flags: u16,
+        method: u16,
+        crc32: u32,
+        compressed_size: u32,
+        size: u32,
+    ) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        header.extend_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&flags.to_le_bytes());
+        header.extend_from_slice(&method.to_le_bytes());
+        // 12:30:20
+        header.extend_from_slice(&0x63cau16.to_le_bytes());
+        // 2023-03-09
+        header.extend_from_slice(&0x5669u16.to_le_bytes());
+        header.extend_from_slice(&crc32.to_le_bytes());
+        header.extend_from_slice(&compressed_size.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        header
+    }
+
+    fn data_descriptor(crc32: u32, size: u32) -> Vec<u8> {
+        let mut descriptor = Vec::new();
+        descriptor.extend_from_slice(&0x08074b50u32.to_le_bytes());
+        descriptor.extend_from_slice(&crc32.to_le_bytes());
+        descriptor.extend_from_slice(&size.to_le_bytes());
+        descriptor.extend_from_slice(&size.to_le_bytes());
+        descriptor
+    }
+
+    fn central_directory() -> Vec<u8> {
+        0x02014b50u32.to_le_bytes().to_vec()
+    }
+
+    async fn read_all(zip: Vec<u8>) -> Vec<(ZipEntry, Vec<u8>)> {
+        let mut reader = ZipReader::new(Cursor::new(zip));
+        let mut entries = Vec::new();
+
+        while let Some(entry) = reader.next_entry().await.unwrap() {
+            let mut data = Vec::new();
+            reader.copy_entry(&mut data).await.unwrap();
+            entries.push((entry, data));
+        }
+
+        entries
+    }
+
+    #[test]
+    fn test_dos_date_time_to_millis() {
+        assert_eq!(dos_date_time_to_millis(0x5669, 0x63ca), Some(1678365020000));
+        assert_eq!(dos_date_time_to_millis(0, 0), None);
+    }
+
+    #[test]
+    fn test_find_stored_data_descriptor() {
+        let mut data = b"abc".to_vec();
+        data.extend(data_descriptor(0, 5));
+        assert_eq!(find_stored_data_descriptor(&data, 0, false), None);
+        assert_eq!(find_stored_data_descriptor(&data, 2, false), Some(3));
+    }
+
+    #[test]
+    fn test_peek_reader_unread() {
+        futures::executor::block_on(async {
+            let mut reader = PeekReader::new(Cursor::new(b"cd".to_vec()));
+            reader.unread(b"ab");
+
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).await.unwrap();
+
+            assert_eq!(data, b"abcd");
+        });
+    }
+
+    #[test]
+    fn test_zip_reader() {
+        futures::executor::block_on(async {
+            let mut zip = Vec::new();
+            zip.extend(local_file_header("D1/", 0, 0, 0, 0, 0));
+            zip.extend(local_file_header("D1/F1.txt", 0, 0, crc32(b"abc"), 3, 3));
+            zip.extend(b"abc");
+            zip.extend(local_file_header("D1/F2.txt", 0, 8, crc32(b"abc"), 5, 3));
+            zip.extend(DEFLATE_ABC);
+            zip.extend(central_directory());
+
+            let entries = read_all(zip).await;
+
+            assert_eq!(entries.len(), 3);
+            assert_eq!(
+                entries[0].0,
+                ZipEntry {
+                    name: String::from("D1/"),
+                    is_dir: true,
+                    compression: ZipCompression::Stored,
+                    compressed_size: Some(0),
+                    size: Some(0),
+                    crc32: Some(0),
+                    modified: Some(1678365020000),
+                    has_data_descriptor: false,
+                    is_zip64: false,
+                }
+            );
+            assert_eq!(entries[1].0.name, "D1/F1.txt");
+            assert_eq!(entries[1].1, b"abc");
+            assert_eq!(entries[2].0.compression, ZipCompression::Deflate);
+            assert_eq!(entries[2].1, b"abc");
+        });
+    }
+
+    #[test]
+    fn test_zip_reader_data_descriptor() {
+        futures::executor::block_on(async {
+            let mut zip = Vec::new();
+            zip.extend(local_file_header("F1.txt", 1 << 3, 0, 0, 0, 0));
+            zip.extend(b"abc");
+            zip.extend(data_descriptor(crc32(b"abc"), 3));
+            zip.extend(local_file_header("F2.txt", 1 << 3, 8, 0, 0, 0));
+            zip.extend(DEFLATE_ABC);
+            zip.extend(data_descriptor(crc32(b"abc"), 5)[4..].to_vec());
+            zip.extend(central_directory());
+
+            let entries = read_all(zip).await;
+
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[0].0.size, None);
+            assert_eq!(entries[0].1, b"abc");
+            assert_eq!(entries[1].1, b"abc");
+        });
+    }
+
+    #[test]
+    fn test_zip_reader_skip_entry() {
+        futures::executor::block_on(async {
+            let mut zip = Vec::new();
+            zip.extend(local_file_header("F1.txt", 0, 0, crc32(b"abc"), 3, 3));
+            zip.extend(b"abc");
+            zip.extend(local_file_header("F2.txt", 0, 0, crc32(b"def"), 3, 3));
+            zip.extend(b"def");
+            zip.extend(central_directory());
+
+            let mut reader = ZipReader::new(Cursor::new(zip));
+
+            assert_eq!(reader.next_entry().await.unwrap().unwrap().name, "F1.txt");
+            assert_eq!(reader.next_entry().await.unwrap().unwrap().name, "F2.txt");
+
+            let mut data = Vec::new();
+            reader.copy_entry(&mut data).await.unwrap();
+            assert_eq!(data, b"def");
+
+            assert!(reader.next_entry().await.unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_zip_reader_crc32_mismatch() {
+        futures::executor::block_on(async {
+            let mut zip = Vec::new();
+            zip.extend(local_file_header("F1.txt", 0, 0, crc32(b"abc"), 3, 3));
+            zip.extend(b"abd");
+            zip.extend(central_directory());
+
+            let mut reader = ZipReader::new(Cursor::new(zip));
+
+            assert_eq!(reader.next_entry().await.unwrap().unwrap().name, "F1.txt");
+            assert!(reader.copy_entry(&mut Vec::new()).await.is_err());
+
+            let mut reader = ZipReader::new(Cursor::new(
+                [
+                    local_file_header("F2.txt", 1 << 3, 0, 0, 0, 0),
+                    b"abc".to_vec(),
+                    data_descriptor(crc32(b"abd"), 3),
+                ]
+                .concat(),
+            ));
+
+            assert_eq!(reader.next_entry().await.unwrap().unwrap().name, "F2.txt");
+            assert!(reader.copy_entry(&mut Vec::new()).await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_zip_reader_invalid() {
+        futures::executor::block_on(async {
+            let mut reader = ZipReader::new(Cursor::new(b"not a zip file".to_vec()));
+
+            assert!(reader.next_entry().await.is_err());
+        });
+    }
+}
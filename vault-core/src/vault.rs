@@ -4,6 +4,9 @@ use futures::future::BoxFuture;
 
 use crate::auth;
 use crate::config;
+use crate::device_key_store;
+use crate::downloads;
+use crate::encrypted_storage;
 use crate::eventstream;
 use crate::http;
 use crate::lifecycle;
@@ -12,18 +15,28 @@ use crate::oauth2;
 use crate::remote;
 use crate::remote_files;
 use crate::remote_files_dir_pickers;
+use crate::repo_auto_lock;
 use crate::repo_config_backup;
 use crate::repo_create;
+use crate::repo_duplicates;
 use crate::repo_files;
 use crate::repo_files_browsers;
 use crate::repo_files_details;
 use crate::repo_files_dir_pickers;
+use crate::repo_files_index;
 use crate::repo_files_list;
 use crate::repo_files_move;
 use crate::repo_files_read;
+use crate::repo_files_search;
+use crate::repo_files_thumbnails;
+use crate::repo_import_export;
+use crate::repo_rekey;
 use crate::repo_remove;
 use crate::repo_space_usage;
+use crate::repo_sync;
+use crate::repo_trash;
 use crate::repo_unlock;
+use crate::repo_verify;
 use crate::repos;
 use crate::runtime;
 use crate::secure_storage;
@@ -39,20 +52,31 @@ pub struct Vault {
     oauth2_service: Arc<oauth2::OAuth2Service>,
     user_service: Arc<user::UserService>,
     uploads_service: Arc<uploads::UploadsService>,
+    downloads_service: Arc<downloads::DownloadsService>,
     remote_files_service: Arc<remote_files::RemoteFilesService>,
     remote_files_dir_pickers_service: Arc<remote_files_dir_pickers::RemoteFilesDirPickersService>,
     repos_service: Arc<repos::ReposService>,
     repo_create_service: Arc<repo_create::RepoCreateService>,
     repo_unlock_service: Arc<repo_unlock::RepoUnlockService>,
+    repo_auto_lock_service: Arc<repo_auto_lock::RepoAutoLockService>,
     repo_remove_service: Arc<repo_remove::RepoRemoveService>,
+    repo_rekey_service: Arc<repo_rekey::RepoRekeyService>,
+    repo_verify_service: Arc<repo_verify::RepoVerifyService>,
     repo_config_backup_service: Arc<repo_config_backup::RepoConfigBackupService>,
     repo_space_usage_service: Arc<repo_space_usage::RepoSpaceUsageService>,
+    repo_duplicates_service: Arc<repo_duplicates::RepoDuplicatesService>,
     repo_files_service: Arc<repo_files::RepoFilesService>,
+    repo_files_index_service: Arc<repo_files_index::RepoFilesIndexService>,
     eventstream_service: Arc<eventstream::EventStreamService>,
     repo_files_dir_pickers_service: Arc<repo_files_dir_pickers::RepoFilesDirPickersService>,
     repo_files_browsers_service: Arc<repo_files_browsers::RepoFilesBrowsersService>,
     repo_files_details_service: Arc<repo_files_details::RepoFilesDetailsService>,
     repo_files_move_service: Arc<repo_files_move::RepoFilesMoveService>,
+    repo_files_search_service: Arc<repo_files_search::RepoFilesSearchService>,
+    repo_files_thumbnails_service: Arc<repo_files_thumbnails::RepoFilesThumbnailsService>,
+    repo_import_export_service: Arc<repo_import_export::RepoImportExportService>,
+    repo_sync_service: Arc<repo_sync::RepoSyncService>,
+    repo_trash_service: Arc<repo_trash::RepoTrashService>,
     space_usage_service: Arc<space_usage::SpaceUsageService>,
     lifecycle_service: Arc<lifecycle::LifecycleService>,
 }
@@ -64,6 +88,9 @@ impl Vault {
         http_client: Box<dyn http::HttpClient + Send + Sync>,
         eventstream_websocket_client: Box<dyn eventstream::WebSocketClient + Send + Sync>,
         secure_storage: Box<dyn secure_storage::SecureStorage + Send + Sync>,
+        device_key_store: Box<dyn device_key_store::DeviceKeyStore + Send + Sync>,
+        repo_files_index_storage: Box<dyn encrypted_storage::EncryptedStorage + Send + Sync>,
+        uploads_persistence: Option<uploads::UploadsPersistence>,
         runtime: Box<dyn runtime::Runtime + Send + Sync>,
     ) -> Self {
         let state = store::State {
@@ -104,7 +131,12 @@ impl Vault {
                 remote_files_service.clone(),
                 store.clone(),
             ));
-        let repos_service = Arc::new(repos::ReposService::new(remote.clone(), store.clone()));
+        let repos_service = Arc::new(repos::ReposService::new(
+            remote.clone(),
+            secure_storage_service.clone(),
+            Arc::new(device_key_store),
+            store.clone(),
+        ));
         let repo_unlock_service = Arc::new(repo_unlock::RepoUnlockService::new(
             repos_service.clone(),
             store.clone(),
@@ -124,6 +156,18 @@ impl Vault {
             repos_service.clone(),
             remote_files_service.clone(),
         ));
+        let repo_rekey_service = Arc::new(repo_rekey::RepoRekeyService::new(
+            repos_service.clone(),
+            repo_files_list_service.clone(),
+            remote_files_service.clone(),
+            store.clone(),
+        ));
+        let repo_verify_service = Arc::new(repo_verify::RepoVerifyService::new(
+            repos_service.clone(),
+            repo_files_list_service.clone(),
+            remote_files_service.clone(),
+            store.clone(),
+        ));
         let repo_files_read_service = Arc::new(repo_files_read::RepoFilesReadService::new(
             repos_service.clone(),
             remote_files_service.clone(),
@@ -137,6 +181,11 @@ impl Vault {
             repo_files_read_service.clone(),
             store.clone(),
         ));
+        let repo_duplicates_service = Arc::new(repo_duplicates::RepoDuplicatesService::new(
+            repo_files_service.clone(),
+            repo_files_list_service.clone(),
+            store.clone(),
+        ));
         let repo_create_service = Arc::new(repo_create::RepoCreateService::new(
             remote.clone(),
             repos_service.clone(),
@@ -144,8 +193,38 @@ impl Vault {
             remote_files_dir_pickers_service.clone(),
             store.clone(),
         ));
+        let repo_files_thumbnails_service =
+            Arc::new(repo_files_thumbnails::RepoFilesThumbnailsService::new(
+                repos_service.clone(),
+                repo_files_service.clone(),
+                store.clone(),
+                runtime.clone(),
+            ));
         let uploads_service = Arc::new(uploads::UploadsService::new(
+            repos_service.clone(),
+            repo_files_service.clone(),
+            repo_files_thumbnails_service.clone(),
+            uploads_persistence,
+            store.clone(),
+            runtime.clone(),
+        ));
+        uploads_service.clone().restore_on_unlock();
+        let downloads_service = Arc::new(downloads::DownloadsService::new(
+            repo_files_read_service.clone(),
+            store.clone(),
+            runtime.clone(),
+        ));
+        let repo_auto_lock_service = Arc::new(repo_auto_lock::RepoAutoLockService::new(
+            repos_service.clone(),
+            uploads_service.clone(),
+            store.clone(),
+            runtime.clone(),
+        ));
+        let repo_files_index_service = Arc::new(repo_files_index::RepoFilesIndexService::new(
+            repos_service.clone(),
             repo_files_service.clone(),
+            repo_files_list_service.clone(),
+            repo_files_index_storage,
             store.clone(),
             runtime.clone(),
         ));
@@ -154,6 +233,7 @@ impl Vault {
             eventstream_websocket_client,
             auth_provider.clone(),
             repo_files_service.clone(),
+            repo_files_index_service.clone(),
             runtime.clone(),
         ));
         let repo_files_dir_pickers_service =
@@ -161,12 +241,19 @@ impl Vault {
                 repo_files_service.clone(),
                 store.clone(),
             ));
+        let repo_trash_service = Arc::new(repo_trash::RepoTrashService::new(
+            repo_files_service.clone(),
+            store.clone(),
+        ));
         let repo_files_browsers_service =
             Arc::new(repo_files_browsers::RepoFilesBrowsersService::new(
                 repo_files_service.clone(),
                 repo_files_read_service.clone(),
+                repo_trash_service.clone(),
+                repo_files_index_service.clone(),
                 eventstream_service.clone(),
                 store.clone(),
+                runtime.clone(),
             ));
         let repo_files_details_service =
             Arc::new(repo_files_details::RepoFilesDetailsService::new(
@@ -177,9 +264,33 @@ impl Vault {
             ));
         let repo_files_move_service = Arc::new(repo_files_move::RepoFilesMoveService::new(
             repo_files_service.clone(),
+            repo_files_read_service.clone(),
+            repo_files_list_service.clone(),
             repo_files_dir_pickers_service.clone(),
             store.clone(),
         ));
+        let repo_files_search_service = Arc::new(repo_files_search::RepoFilesSearchService::new(
+            repo_files_service.clone(),
+            repo_files_list_service.clone(),
+            repo_files_index_service.clone(),
+            store.clone(),
+        ));
+        let repo_import_export_service =
+            Arc::new(repo_import_export::RepoImportExportService::new(
+                remote_files_service.clone(),
+                repo_files_service.clone(),
+                repo_files_read_service.clone(),
+                repo_files_list_service.clone(),
+                repo_trash_service.clone(),
+                store.clone(),
+            ));
+        let repo_sync_service = Arc::new(repo_sync::RepoSyncService::new(
+            repos_service.clone(),
+            repo_files_service.clone(),
+            repo_files_list_service.clone(),
+            repo_trash_service.clone(),
+            store.clone(),
+        ));
         let space_usage_service = Arc::new(space_usage::SpaceUsageService::new(
             remote.clone(),
             store.clone(),
@@ -206,20 +317,31 @@ impl Vault {
             oauth2_service,
             user_service,
             uploads_service,
+            downloads_service,
             remote_files_service,
             remote_files_dir_pickers_service,
             repos_service,
             repo_create_service,
             repo_unlock_service,
+            repo_auto_lock_service,
             repo_remove_service,
+            repo_rekey_service,
+            repo_verify_service,
             repo_config_backup_service,
             repo_space_usage_service,
+            repo_duplicates_service,
             repo_files_service,
+            repo_files_index_service,
             eventstream_service,
             repo_files_dir_pickers_service,
             repo_files_browsers_service,
             repo_files_details_service,
             repo_files_move_service,
+            repo_files_search_service,
+            repo_files_thumbnails_service,
+            repo_import_export_service,
+            repo_sync_service,
+            repo_trash_service,
             space_usage_service,
             lifecycle_service,
         }
@@ -313,6 +435,16 @@ impl Vault {
         self.repos_service.lock_repo(repo_id)
     }
 
+    pub fn repos_forget_all_remembered(&self) {
+        self.repos_service.forget_all_repos()
+    }
+
+    // repo_auto_lock
+
+    pub fn repo_auto_lock_set_policy(&self, policy: repo_auto_lock::state::RepoAutoLock) {
+        self.repo_auto_lock_service.clone().set_policy(policy)
+    }
+
     // repo_create
 
     pub async fn repo_create_init(&self) {
@@ -381,8 +513,9 @@ impl Vault {
     pub async fn repo_unlock_unlock(
         &self,
         password: &str,
+        remember: bool,
     ) -> Result<(), repos::errors::UnlockRepoError> {
-        self.repo_unlock_service.unlock(password).await
+        self.repo_unlock_service.unlock(password, remember).await
     }
 
     pub fn repo_unlock_destroy(&self, repo_id: &str) {
@@ -406,6 +539,52 @@ impl Vault {
         self.repo_remove_service.destroy(repo_id)
     }
 
+    // repo_rekey
+
+    pub fn repo_rekey_init(&self, repo_id: &str) {
+        self.repo_rekey_service.init(repo_id)
+    }
+
+    pub async fn repo_rekey_rekey(
+        &self,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), repo_rekey::errors::RepoRekeyError> {
+        self.repo_rekey_service
+            .rekey(old_password, new_password)
+            .await
+    }
+
+    pub fn repo_rekey_pause(&self) {
+        self.repo_rekey_service.pause()
+    }
+
+    pub async fn repo_rekey_resume(&self) -> Result<(), repo_rekey::errors::RepoRekeyError> {
+        self.repo_rekey_service.resume().await
+    }
+
+    pub fn repo_rekey_destroy(&self, repo_id: &str) {
+        self.repo_rekey_service.destroy(repo_id)
+    }
+
+    // repo_verify
+
+    pub fn repo_verify_init(&self, repo_id: &str) {
+        self.repo_verify_service.init(repo_id)
+    }
+
+    pub async fn repo_verify_verify(&self) -> Result<(), repo_verify::errors::RepoVerifyError> {
+        self.repo_verify_service.verify().await
+    }
+
+    pub async fn repo_verify_quarantine(&self) -> Result<(), repo_verify::errors::RepoVerifyError> {
+        self.repo_verify_service.quarantine().await
+    }
+
+    pub fn repo_verify_destroy(&self, repo_id: &str) {
+        self.repo_verify_service.destroy(repo_id)
+    }
+
     // repo_config_backup
 
     pub fn repo_config_backup_init(&self, repo_id: &str) {
@@ -439,6 +618,22 @@ impl Vault {
         self.repo_space_usage_service.destroy(repo_id)
     }
 
+    // repo_duplicates
+
+    pub fn repo_duplicates_init(&self, repo_id: &str) {
+        self.repo_duplicates_service.init(repo_id)
+    }
+
+    pub async fn repo_duplicates_find(
+        &self,
+    ) -> Result<(), repo_duplicates::errors::RepoDuplicatesError> {
+        self.repo_duplicates_service.find().await
+    }
+
+    pub fn repo_duplicates_destroy(&self, repo_id: &str) {
+        self.repo_duplicates_service.destroy(repo_id)
+    }
+
     // repo_files
 
     pub async fn repo_files_load_files(
@@ -449,14 +644,19 @@ impl Vault {
         self.repo_files_service.load_files(repo_id, path).await
     }
 
+    pub fn repo_files_set_content_hashes_enabled(&self, enabled: bool) {
+        self.repo_files_service.set_content_hashes_enabled(enabled)
+    }
+
     pub async fn repo_files_get_file_reader(
         self: Arc<Self>,
         file_id: &str,
+        range: Option<repo_files_read::state::RepoFileRange>,
     ) -> Result<repo_files_read::state::RepoFileReader, repo_files_read::errors::GetFilesReaderError>
     {
         self.repo_files_service
             .clone()
-            .get_file_reader(file_id)
+            .get_file_reader(file_id, range)
             .await
     }
 
@@ -489,6 +689,33 @@ impl Vault {
             .await
     }
 
+    // repo_files_index
+
+    pub fn repo_files_index_set_enabled(&self, enabled: bool) {
+        self.repo_files_index_service.set_enabled(enabled)
+    }
+
+    pub async fn repo_files_index_ensure_loaded(
+        &self,
+        repo_id: &str,
+    ) -> Result<(), repo_files_index::errors::RepoFilesIndexError> {
+        self.repo_files_index_service.ensure_loaded(repo_id).await
+    }
+
+    pub async fn repo_files_index_rebuild(
+        &self,
+        repo_id: &str,
+    ) -> Result<(), repo_files_index::errors::RepoFilesIndexError> {
+        self.repo_files_index_service.rebuild(repo_id).await
+    }
+
+    pub fn repo_files_index_clear(
+        &self,
+        repo_id: &str,
+    ) -> Result<(), repo_files_index::errors::RepoFilesIndexError> {
+        self.repo_files_index_service.clear(repo_id)
+    }
+
     // uploads
 
     pub async fn uploads_upload(
@@ -497,19 +724,40 @@ impl Vault {
         parent_path: &str,
         name: &str,
         uploadable: uploads::service::Uploadable,
+        skip_identical: bool,
     ) -> Result<repo_files::state::RepoFilesUploadResult, uploads::errors::UploadError> {
         self.uploads_service
             .clone()
-            .upload(repo_id, parent_path, name, uploadable)
+            .upload(repo_id, parent_path, name, uploadable, skip_identical)
             .await
     }
 
+    pub async fn uploads_upload_extract(
+        &self,
+        repo_id: &str,
+        parent_path: &str,
+        uploadable: uploads::service::Uploadable,
+    ) -> Result<Vec<repo_files::state::RepoFilesUploadResult>, uploads::errors::UploadExtractError>
+    {
+        self.uploads_service
+            .clone()
+            .upload_extract(repo_id, parent_path, uploadable)
+            .await
+    }
+
+    pub async fn uploads_restore(
+        &self,
+        repo_id: &str,
+    ) -> Result<(), uploads::errors::UploadsQueueError> {
+        self.uploads_service.clone().restore(repo_id).await
+    }
+
     pub fn uploads_abort_file(&self, id: u32) {
-        self.uploads_service.abort_file(id);
+        self.uploads_service.clone().abort_file(id);
     }
 
     pub fn uploads_abort_all(&self) {
-        self.uploads_service.abort_all();
+        self.uploads_service.clone().abort_all();
     }
 
     pub fn uploads_retry_file(&self, id: u32) {
@@ -520,6 +768,36 @@ impl Vault {
         self.uploads_service.clone().retry_all();
     }
 
+    // downloads
+
+    pub async fn downloads_download(
+        &self,
+        file_ids: &[String],
+        archive_format: repo_files_read::state::RepoFilesArchiveFormat,
+        downloadable: downloads::service::Downloadable,
+    ) -> Result<(), downloads::errors::DownloadError> {
+        self.downloads_service
+            .clone()
+            .download(file_ids, archive_format, downloadable)
+            .await
+    }
+
+    pub fn downloads_abort_file(&self, id: u32) {
+        self.downloads_service.abort_file(id);
+    }
+
+    pub fn downloads_abort_all(&self) {
+        self.downloads_service.abort_all();
+    }
+
+    pub fn downloads_retry_file(&self, id: u32) {
+        self.downloads_service.clone().retry_file(id);
+    }
+
+    pub fn downloads_retry_all(&self) {
+        self.downloads_service.clone().retry_all();
+    }
+
     // repo_files_browsers
 
     pub fn repo_files_browsers_create(
@@ -591,11 +869,12 @@ impl Vault {
     pub async fn repo_files_browsers_get_selected_reader(
         self: Arc<Self>,
         browser_id: u32,
+        archive_format: repo_files_read::state::RepoFilesArchiveFormat,
     ) -> Result<repo_files_read::state::RepoFileReader, repo_files_read::errors::GetFilesReaderError>
     {
         self.repo_files_browsers_service
             .clone()
-            .get_selected_reader(browser_id)
+            .get_selected_reader(browser_id, archive_format)
             .await
     }
 
@@ -618,10 +897,30 @@ impl Vault {
             .await
     }
 
+    pub fn repo_files_browsers_check_create_file(
+        &self,
+        browser_id: u32,
+        name: &str,
+    ) -> Result<(), repo_files::errors::CreateFileError> {
+        self.repo_files_browsers_service
+            .check_create_file(browser_id, name)
+    }
+
+    pub async fn repo_files_browsers_create_file(
+        &self,
+        browser_id: u32,
+        name: &str,
+        template: repo_files_browsers::state::RepoFilesBrowserCreateFileTemplate,
+    ) -> Result<String, repo_files::errors::CreateFileError> {
+        self.repo_files_browsers_service
+            .create_file(browser_id, name, template)
+            .await
+    }
+
     pub async fn repo_files_browsers_delete_selected(
         &self,
         browser_id: u32,
-    ) -> Result<(), repo_files::errors::DeleteFileError> {
+    ) -> Result<(), repo_trash::errors::RepoTrashError> {
         self.repo_files_browsers_service
             .delete_selected(browser_id)
             .await
@@ -656,6 +955,31 @@ impl Vault {
             .await
     }
 
+    pub fn repo_files_details_edit_content(&self, details_id: u32, bytes: Vec<u8>) {
+        self.repo_files_details_service
+            .edit_content(details_id, bytes)
+    }
+
+    pub async fn repo_files_details_save_content(
+        self: Arc<Self>,
+        details_id: u32,
+    ) -> Result<(), repo_files_details::errors::SaveContentError> {
+        self.repo_files_details_service
+            .clone()
+            .save_content(details_id)
+            .await
+    }
+
+    pub async fn repo_files_details_save_content_as_new_name(
+        self: Arc<Self>,
+        details_id: u32,
+    ) -> Result<String, repo_files_details::errors::SaveContentError> {
+        self.repo_files_details_service
+            .clone()
+            .save_content_as_new_name(details_id)
+            .await
+    }
+
     pub async fn repo_files_details_get_file_reader(
         self: Arc<Self>,
         details_id: u32,
@@ -679,7 +1003,7 @@ impl Vault {
 
     pub async fn repo_files_move_move_files(
         &self,
-    ) -> Result<(), repo_files::errors::MoveFileError> {
+    ) -> Result<(), repo_files_move::errors::RepoFilesMoveError> {
         self.repo_files_move_service.move_files().await
     }
 
@@ -701,6 +1025,150 @@ impl Vault {
         self.repo_files_move_service.create_dir(name).await
     }
 
+    // repo_files_search
+
+    pub fn repo_files_search_create(
+        &self,
+        repo_id: &str,
+        path: &str,
+        query: repo_files_search::state::RepoFilesSearchQuery,
+    ) -> (
+        u32,
+        BoxFuture<'static, Result<(), repo_files_search::errors::RepoFilesSearchError>>,
+    ) {
+        self.repo_files_search_service
+            .clone()
+            .create(repo_id, path, query)
+    }
+
+    pub fn repo_files_search_cancel(&self, search_id: u32) {
+        self.repo_files_search_service.cancel(search_id)
+    }
+
+    pub fn repo_files_search_destroy(&self, search_id: u32) {
+        self.repo_files_search_service.destroy(search_id)
+    }
+
+    // repo_files_thumbnails
+
+    pub fn repo_files_thumbnails_load(
+        &self,
+        file_id: &str,
+    ) -> Result<(), repo_files_thumbnails::errors::LoadThumbnailError> {
+        self.repo_files_thumbnails_service.clone().load(file_id)
+    }
+
+    // repo_import_export
+
+    pub async fn repo_import_export_import(
+        &self,
+        repo_id: &str,
+        mount_id: &str,
+        remote_path: &str,
+        repo_parent_path: &str,
+        delete_source: bool,
+    ) -> Result<(), repo_import_export::errors::RepoImportExportError> {
+        self.repo_import_export_service
+            .import(
+                repo_id,
+                mount_id,
+                remote_path,
+                repo_parent_path,
+                delete_source,
+            )
+            .await
+    }
+
+    pub async fn repo_import_export_export(
+        &self,
+        repo_id: &str,
+        repo_path: &str,
+        mount_id: &str,
+        remote_parent_path: &str,
+        delete_source: bool,
+    ) -> Result<(), repo_import_export::errors::RepoImportExportError> {
+        self.repo_import_export_service
+            .export(
+                repo_id,
+                repo_path,
+                mount_id,
+                remote_parent_path,
+                delete_source,
+            )
+            .await
+    }
+
+    pub fn repo_import_export_destroy(&self, repo_id: &str) {
+        self.repo_import_export_service.destroy(repo_id)
+    }
+
+    // repo_sync
+
+    pub fn repo_sync_create(
+        &self,
+        repo_id: &str,
+        path: &str,
+        local_fs: Box<dyn repo_sync::LocalFs + Send + Sync>,
+        storage: Box<dyn encrypted_storage::EncryptedStorage + Send + Sync>,
+    ) -> u32 {
+        self.repo_sync_service
+            .create(repo_id, path, local_fs, storage)
+    }
+
+    pub async fn repo_sync_sync(
+        &self,
+        sync_id: u32,
+    ) -> Result<repo_sync::state::RepoSyncSummary, repo_sync::errors::RepoSyncError> {
+        self.repo_sync_service.sync(sync_id).await
+    }
+
+    pub fn repo_sync_destroy(&self, sync_id: u32) -> Result<(), repo_sync::errors::RepoSyncError> {
+        self.repo_sync_service.destroy(sync_id)
+    }
+
+    // repo_trash
+
+    pub fn repo_trash_set_retention(&self, retention: repo_trash::state::RepoTrashRetention) {
+        self.repo_trash_service.set_retention(retention)
+    }
+
+    pub async fn repo_trash_load(
+        &self,
+        repo_id: &str,
+    ) -> Result<(), repo_trash::errors::RepoTrashError> {
+        self.repo_trash_service.load(repo_id).await
+    }
+
+    pub async fn repo_trash_restore(
+        &self,
+        repo_id: &str,
+        id: &str,
+        conflict_resolution: repo_trash::state::RepoTrashRestoreConflictResolution,
+    ) -> Result<String, repo_trash::errors::RepoTrashError> {
+        self.repo_trash_service
+            .restore(repo_id, id, conflict_resolution)
+            .await
+    }
+
+    pub async fn repo_trash_purge(
+        &self,
+        repo_id: &str,
+        id: &str,
+    ) -> Result<(), repo_trash::errors::RepoTrashError> {
+        self.repo_trash_service.purge(repo_id, id).await
+    }
+
+    pub async fn repo_trash_empty(
+        &self,
+        repo_id: &str,
+    ) -> Result<(), repo_trash::errors::RepoTrashError> {
+        self.repo_trash_service.empty(repo_id).await
+    }
+
+    pub fn repo_trash_destroy(&self, repo_id: &str) {
+        self.repo_trash_service.destroy(repo_id)
+    }
+
     // remote_files_dir_pickers
 
     pub async fn remote_files_dir_pickers_load(
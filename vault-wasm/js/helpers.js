@@ -43,3 +43,104 @@ export function supportsReadableByteStream() {
 export function sleep(durationMs) {
   return new Promise((resolve) => setTimeout(resolve, durationMs));
 }
+
+const DEVICE_KEY_DB_NAME = "vaultDeviceKey";
+const DEVICE_KEY_STORE_NAME = "keys";
+const DEVICE_KEY_ID = "deviceKey";
+const DEVICE_KEY_IV_LENGTH = 12;
+
+function requestToPromise(request) {
+  return new Promise((resolve, reject) => {
+    request.onsuccess = () => resolve(request.result);
+    request.onerror = () => reject(request.error);
+  });
+}
+
+function openDeviceKeyDb() {
+  const request = indexedDB.open(DEVICE_KEY_DB_NAME, 1);
+
+  request.onupgradeneeded = () => {
+    request.result.createObjectStore(DEVICE_KEY_STORE_NAME);
+  };
+
+  return requestToPromise(request);
+}
+
+async function getDeviceKey(create) {
+  const db = await openDeviceKeyDb();
+
+  try {
+    const existingKey = await requestToPromise(
+      db
+        .transaction(DEVICE_KEY_STORE_NAME, "readonly")
+        .objectStore(DEVICE_KEY_STORE_NAME)
+        .get(DEVICE_KEY_ID)
+    );
+
+    if (existingKey !== undefined || !create) {
+      return existingKey;
+    }
+
+    // non-extractable, the raw key bytes can never be read by any script
+    const key = await crypto.subtle.generateKey(
+      { name: "AES-GCM", length: 256 },
+      false,
+      ["encrypt", "decrypt"]
+    );
+
+    await requestToPromise(
+      db
+        .transaction(DEVICE_KEY_STORE_NAME, "readwrite")
+        .objectStore(DEVICE_KEY_STORE_NAME)
+        .put(key, DEVICE_KEY_ID)
+    );
+
+    return key;
+  } finally {
+    db.close();
+  }
+}
+
+// data is a view into WASM memory, it has to be copied before any await
+export async function deviceKeyWrap(data) {
+  data = data.slice();
+
+  const key = await getDeviceKey(true);
+  const iv = crypto.getRandomValues(new Uint8Array(DEVICE_KEY_IV_LENGTH));
+  const ciphertext = new Uint8Array(
+    await crypto.subtle.encrypt({ name: "AES-GCM", iv }, key, data)
+  );
+
+  const wrapped = new Uint8Array(iv.length + ciphertext.length);
+  wrapped.set(iv);
+  wrapped.set(ciphertext, iv.length);
+
+  return wrapped;
+}
+
+// resolves to null if the device key does not exist or the data was wrapped
+// with a different device key
+export async function deviceKeyUnwrap(wrapped) {
+  wrapped = wrapped.slice();
+
+  const key = await getDeviceKey(false);
+
+  if (key === undefined) {
+    return null;
+  }
+
+  const iv = wrapped.slice(0, DEVICE_KEY_IV_LENGTH);
+  const ciphertext = wrapped.slice(DEVICE_KEY_IV_LENGTH);
+
+  try {
+    return new Uint8Array(
+      await crypto.subtle.decrypt({ name: "AES-GCM", iv }, key, ciphertext)
+    );
+  } catch (e) {
+    if (e.name === "OperationError") {
+      return null;
+    }
+
+    throw e;
+  }
+}
@@ -0,0 +1,62 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use wasm_bindgen_futures::JsFuture;
+
+use vault_core::device_key_store::{DeviceKeyStore, DeviceKeyStoreError};
+
+use crate::helpers;
+
+/// Device key is a non-extractable WebCrypto key stored in IndexedDB, apart
+/// from the wrapped keys in localStorage.
+pub struct BrowserDeviceKeyStore;
+
+impl BrowserDeviceKeyStore {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// JS promises are not Send but WASM runs on a single thread
+struct SendFuture<F>(F);
+
+unsafe impl<F> Send for SendFuture<F> {}
+
+impl<F: Future> Future for SendFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        unsafe { self.map_unchecked_mut(|future| &mut future.0) }.poll(cx)
+    }
+}
+
+async fn promise_to_bytes(promise: js_sys::Promise) -> Result<Vec<u8>, String> {
+    let value = JsFuture::from(promise)
+        .await
+        .map_err(|err| format!("{:?}", err))?;
+
+    Ok(js_sys::Uint8Array::new(&value).to_vec())
+}
+
+#[async_trait]
+impl DeviceKeyStore for BrowserDeviceKeyStore {
+    async fn wrap_key(&self, key: &[u8]) -> Result<Vec<u8>, String> {
+        SendFuture(promise_to_bytes(helpers::device_key_wrap(key))).await
+    }
+
+    async fn unwrap_key(&self, wrapped: &[u8]) -> Result<Vec<u8>, DeviceKeyStoreError> {
+        let value = SendFuture(JsFuture::from(helpers::device_key_unwrap(wrapped)))
+            .await
+            .map_err(|err| DeviceKeyStoreError::Other(format!("{:?}", err)))?;
+
+        if value.is_null() {
+            return Err(DeviceKeyStoreError::KeyNotFound);
+        }
+
+        Ok(js_sys::Uint8Array::new(&value).to_vec())
+    }
+}
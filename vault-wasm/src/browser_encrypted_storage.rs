@@ -0,0 +1,35 @@
+use vault_core::encrypted_storage::EncryptedStorage;
+
+pub struct BrowserEncryptedStorage {
+    local_storage: web_sys::Storage,
+}
+
+unsafe impl Send for BrowserEncryptedStorage {}
+unsafe impl Sync for BrowserEncryptedStorage {}
+
+impl BrowserEncryptedStorage {
+    pub fn new() -> Self {
+        Self {
+            local_storage: web_sys::window().unwrap().local_storage().unwrap().unwrap(),
+        }
+    }
+}
+
+impl EncryptedStorage for BrowserEncryptedStorage {
+    fn get_item(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.local_storage.get_item(key).unwrap_or(None))
+    }
+
+    fn set_item(&self, key: &str, value: &str) -> Result<(), String> {
+        // fails once the storage quota is exceeded
+        self.local_storage
+            .set_item(key, value)
+            .map_err(|err| format!("{:?}", err))
+    }
+
+    fn remove_item(&self, key: &str) -> Result<(), String> {
+        let _ = self.local_storage.remove_item(key);
+
+        Ok(())
+    }
+}
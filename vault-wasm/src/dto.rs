@@ -232,6 +232,8 @@ impl From<&store::State> for Repos {
 pub struct RepoInfo {
     pub status: Status,
     pub repo: Option<Repo>,
+    #[serde(rename = "isRemembered")]
+    pub is_remembered: bool,
 }
 
 impl<'a> From<&repos_state::RepoInfo<'a>> for RepoInfo {
@@ -239,6 +241,7 @@ impl<'a> From<&repos_state::RepoInfo<'a>> for RepoInfo {
         Self {
             status: (&info.status).into(),
             repo: info.repo.map(Into::into),
+            is_remembered: info.is_remembered,
         }
     }
 }
@@ -630,6 +633,8 @@ pub struct RepoFilesBrowserItem {
     pub file_id: String,
     #[serde(rename = "isSelected")]
     pub is_selected: bool,
+    #[serde(rename = "canThumbnail")]
+    pub can_thumbnail: bool,
 }
 
 impl<'a> From<&repo_files_browsers_state::RepoFilesBrowserItem<'a>> for RepoFilesBrowserItem {
@@ -637,6 +642,7 @@ impl<'a> From<&repo_files_browsers_state::RepoFilesBrowserItem<'a>> for RepoFile
         Self {
             file_id: item.file.id.clone(),
             is_selected: item.is_selected,
+            can_thumbnail: item.can_thumbnail,
         }
     }
 }
@@ -760,6 +766,29 @@ impl From<&repo_files_move_state::RepoFilesMoveMode> for RepoFilesMoveInfoMode {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Tsify)]
+pub struct RepoFilesMoveInfoTransfer {
+    #[serde(rename = "totalCount")]
+    pub total_count: usize,
+    #[serde(rename = "doneCount")]
+    pub done_count: usize,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: i64,
+    #[serde(rename = "doneBytes")]
+    pub done_bytes: i64,
+}
+
+impl From<&repo_files_move_state::RepoFilesMoveTransfer> for RepoFilesMoveInfoTransfer {
+    fn from(transfer: &repo_files_move_state::RepoFilesMoveTransfer) -> Self {
+        Self {
+            total_count: transfer.total_count,
+            done_count: transfer.done_count,
+            total_bytes: transfer.total_bytes,
+            done_bytes: transfer.done_bytes,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Tsify)]
 pub struct RepoFilesMoveInfo {
     #[serde(rename = "srcFilesCount")]
@@ -773,6 +802,7 @@ pub struct RepoFilesMoveInfo {
     pub can_show_create_dir: bool,
     #[serde(rename = "canMove")]
     pub can_move: bool,
+    pub transfer: Option<RepoFilesMoveInfoTransfer>,
 }
 
 pub fn format_speed(bytes: i64, duration: Duration) -> Option<String> {
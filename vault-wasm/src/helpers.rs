@@ -23,6 +23,12 @@ extern "C" {
 
     #[wasm_bindgen(js_name = "sleep")]
     pub fn sleep(duration_ms: i32) -> js_sys::Promise;
+
+    #[wasm_bindgen(js_name = "deviceKeyWrap")]
+    pub fn device_key_wrap(data: &[u8]) -> js_sys::Promise;
+
+    #[wasm_bindgen(js_name = "deviceKeyUnwrap")]
+    pub fn device_key_unwrap(wrapped: &[u8]) -> js_sys::Promise;
 }
 
 pub fn bytes_to_array(bytes: &[u8]) -> JsValue {
@@ -1,3 +1,5 @@
+pub mod browser_device_key_store;
+pub mod browser_encrypted_storage;
 pub mod browser_eventstream_websocket_client;
 pub mod browser_http_client;
 pub mod browser_runtime;
@@ -42,6 +42,26 @@ impl Uploadable {
     pub fn reader(&self) -> Pin<Box<dyn AsyncRead + Send + Sync + 'static>> {
         helpers::stream_to_reader(self.stream())
     }
+
+    pub fn reader_at(
+        &self,
+        offset: i64,
+    ) -> Option<Pin<Box<dyn AsyncRead + Send + Sync + 'static>>> {
+        let blob = match self {
+            Self::File(file) => file.slice_with_f64(offset as f64),
+            Self::Blob(blob) => blob.slice_with_f64(offset as f64),
+        }
+        .ok()?;
+
+        Some(helpers::stream_to_reader(blob.stream()))
+    }
+
+    pub fn modified(&self) -> Option<i64> {
+        match self {
+            Self::File(file) => Some(file.last_modified() as i64),
+            Self::Blob(_) => None,
+        }
+    }
 }
 
 impl vault_core::repo_files::state::RepoFileUploadable for Uploadable {
@@ -52,4 +72,12 @@ impl vault_core::repo_files::state::RepoFileUploadable for Uploadable {
     fn reader(&self) -> Pin<Box<dyn AsyncRead + Send + Sync + 'static>> {
         self.reader()
     }
+
+    fn reader_at(&self, offset: i64) -> Option<Pin<Box<dyn AsyncRead + Send + Sync + 'static>>> {
+        self.reader_at(offset)
+    }
+
+    fn modified(&self) -> Option<i64> {
+        self.modified()
+    }
 }
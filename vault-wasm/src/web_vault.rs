@@ -7,6 +7,8 @@ use wasm_bindgen_futures::spawn_local;
 
 use vault_core::store::Event;
 
+use crate::browser_device_key_store::BrowserDeviceKeyStore;
+use crate::browser_encrypted_storage::BrowserEncryptedStorage;
 use crate::browser_eventstream_websocket_client::{
     BrowserEventstreamWebSocketClient, BrowserEventstreamWebSocketDelegate,
 };
@@ -145,6 +147,7 @@ struct SubscriptionData {
     repo_files_browsers_breadcrumbs: Data<Vec<dto::RepoFilesBreadcrumb>>,
     repo_files_details_info: Data<Option<dto::RepoFilesDetailsInfo>>,
     repo_files_details_content_bytes: Data<VersionedFileBytes>,
+    repo_files_thumbnails_bytes: Data<VersionedFileBytes>,
     repo_files_move_info: Data<Option<dto::RepoFilesMoveInfo>>,
     space_usage: Data<Option<dto::SpaceUsage>>,
 }
@@ -183,6 +186,14 @@ impl WebVault {
                 browser_eventstream_websocket_delegate,
             )),
             Box::new(BrowserSecureStorage::new()),
+            Box::new(BrowserDeviceKeyStore::new()),
+            Box::new(BrowserEncryptedStorage::new()),
+            // browser files cannot be reopened after a reload, restored
+            // uploads fail with SourceNotFound
+            Some(vault_core::uploads::UploadsPersistence {
+                storage: Box::new(BrowserEncryptedStorage::new()),
+                sources: None,
+            }),
             Box::new(BrowserRuntime::new()),
         ));
 
@@ -448,6 +459,11 @@ impl WebVault {
         self.handle_result(self.vault.repos_lock_repo(repo_id))
     }
 
+    #[wasm_bindgen(js_name = reposForgetAllRemembered)]
+    pub fn repos_forget_all_remembered(&self) {
+        self.vault.repos_forget_all_remembered()
+    }
+
     // repo_create
 
     #[wasm_bindgen(js_name = repoCreateInfoSubscribe)]
@@ -627,8 +643,8 @@ impl WebVault {
     }
 
     #[wasm_bindgen(js_name = repoUnlockUnlock)]
-    pub async fn repo_unlock_unlock(&self, password: &str) {
-        let _ = self.vault.repo_unlock_unlock(password).await;
+    pub async fn repo_unlock_unlock(&self, password: &str, remember: bool) {
+        let _ = self.vault.repo_unlock_unlock(password, remember).await;
     }
 
     #[wasm_bindgen(js_name = repoUnlockDestroy)]
@@ -818,7 +834,10 @@ impl WebVault {
         force_blob: bool,
     ) -> FileStreamOption {
         self.repo_file_reader_to_file_stream(
-            self.vault.clone().repo_files_get_file_reader(file_id).await,
+            self.vault
+                .clone()
+                .repo_files_get_file_reader(file_id, None)
+                .await,
             force_blob,
         )
         .await
@@ -930,7 +949,7 @@ impl WebVault {
 
         match self
             .vault
-            .uploads_upload(repo_id, parent_path, name, uploadable)
+            .uploads_upload(repo_id, parent_path, name, uploadable, false)
             .await
         {
             Ok(res) => to_js(&dto::RepoFilesUploadResult::from(res)),
@@ -1169,7 +1188,7 @@ impl WebVault {
         self.repo_file_reader_to_file_stream(
             self.vault
                 .clone()
-                .repo_files_browsers_get_selected_reader(browser_id)
+                .repo_files_browsers_get_selected_reader(browser_id, Default::default())
                 .await,
             force_blob,
         )
@@ -1201,6 +1220,71 @@ impl WebVault {
         )
     }
 
+    // repo_files_thumbnails
+
+    #[wasm_bindgen(js_name = repoFilesThumbnailsLoad)]
+    pub fn repo_files_thumbnails_load(&self, file_id: String) {
+        self.handle_result(self.vault.repo_files_thumbnails_load(&file_id));
+    }
+
+    #[wasm_bindgen(js_name = repoFilesThumbnailsBytesSubscribe)]
+    pub fn repo_files_thumbnails_bytes_subscribe(
+        &self,
+        file_id: String,
+        cb: js_sys::Function,
+    ) -> u32 {
+        self.subscribe_changed(
+            &[Event::RepoFilesThumbnails],
+            cb,
+            self.subscription_data.repo_files_thumbnails_bytes.clone(),
+            move |vault, entry| {
+                vault.with_state(|state| {
+                    let (bytes, version) =
+                        vault_core::repo_files_thumbnails::selectors::select_thumbnail_bytes(
+                            state, &file_id,
+                        );
+
+                    let get_value = || VersionedFileBytes {
+                        value: (match bytes {
+                            Some(bytes) => helpers::bytes_to_array(&bytes),
+                            None => JsValue::UNDEFINED,
+                        })
+                        .into(),
+                        version,
+                    };
+
+                    match entry {
+                        hash_map::Entry::Occupied(mut o) => {
+                            if version == o.get().version {
+                                return false;
+                            } else {
+                                o.insert(get_value());
+
+                                true
+                            }
+                        }
+                        hash_map::Entry::Vacant(v) => {
+                            v.insert(get_value());
+
+                            true
+                        }
+                    }
+                })
+            },
+        )
+    }
+
+    #[wasm_bindgen(js_name = repoFilesThumbnailsBytesData)]
+    pub fn repo_files_thumbnails_bytes_data(&self, id: u32) -> FileBytes {
+        self.get_data(
+            id,
+            self.subscription_data.repo_files_thumbnails_bytes.clone(),
+        )
+        .map(|data| data.value)
+        .unwrap_or(JsValue::UNDEFINED)
+        .into()
+    }
+
     // repo_files_details
 
     #[wasm_bindgen(js_name = repoFilesDetailsCreate)]
@@ -1362,6 +1446,10 @@ impl WebVault {
                                 state,
                             )
                             .is_ok(),
+                            transfer: vault_core::repo_files_move::selectors::select_transfer(
+                                state,
+                            )
+                            .map(Into::into),
                         })
                 })
             },
@@ -1384,7 +1472,11 @@ impl WebVault {
 
     #[wasm_bindgen(js_name = repoFilesMoveMoveFiles)]
     pub async fn repo_files_move_move_files(&self) {
-        self.handle_result(self.vault.repo_files_move_move_files().await)
+        match self.vault.repo_files_move_move_files().await {
+            // the user closed the dialog
+            Err(vault_core::repo_files_move::errors::RepoFilesMoveError::Cancelled) => (),
+            res => self.handle_result(res),
+        }
     }
 
     #[wasm_bindgen(js_name = repoFilesMoveCancel)]
@@ -4,6 +4,7 @@ import { memo, useCallback } from 'react';
 
 import { Button } from '../../components/Button';
 import { CreateDirModal } from '../../components/CreateDirModal';
+import { Progress } from '../../components/Progress';
 import {
   Modal,
   ModalBody,
@@ -38,6 +39,7 @@ export const RepoFilesMoveModalContent = memo<{
       destFileName,
       canMove,
       canShowCreateDir,
+      transfer,
     },
     cancel,
   }) => {
@@ -114,6 +116,30 @@ export const RepoFilesMoveModalContent = memo<{
           >
             <RepoFilesDirPicker dirPickerId={dirPickerId} />
           </div>
+          {transfer !== undefined ? (
+            <div
+              className={css`
+                margin-top: 15px;
+              `}
+            >
+              <div
+                className={css`
+                  margin-bottom: 5px;
+                `}
+              >
+                {mode === 'Copy' ? 'Copying' : 'Moving'} {transfer.doneCount}{' '}
+                of {transfer.totalCount}{' '}
+                {transfer.totalCount === 1 ? 'item' : 'items'}
+              </div>
+              <Progress
+                percentage={
+                  transfer.totalBytes > 0
+                    ? (transfer.doneBytes / transfer.totalBytes) * 100
+                    : 0
+                }
+              />
+            </div>
+          ) : null}
         </ModalBody>
         <ModalFooter>
           <ModalFooterExtra>
@@ -131,8 +157,10 @@ export const RepoFilesMoveModalContent = memo<{
             </ModalFooterButton>
             <ModalFooterButton
               type="button"
-              variant={canMove ? 'primary' : 'disabled'}
-              disabled={!canMove}
+              variant={
+                canMove && transfer === undefined ? 'primary' : 'disabled'
+              }
+              disabled={!canMove || transfer !== undefined}
               onClick={move}
             >
               {mode === 'Copy' ? 'Copy' : 'Move'}
@@ -2,6 +2,7 @@ import { css, cx } from '@emotion/css';
 import { memo, useCallback, useEffect, useMemo, useState } from 'react';
 
 import { Button } from '../../components/Button';
+import { Checkbox } from '../../components/Checkbox';
 import { AutoFocusPasswordInput } from '../../components/PasswordInput';
 import { DashboardLayout } from '../../components/dashboard/DashboardLayout';
 import { useSingleNavbarBreadcrumb } from '../../components/navbar/useSingleNavbarBreadcrumb';
@@ -24,13 +25,15 @@ export const RepoUnlock = memo<{ repoId: string }>(({ repoId }) => {
     []
   );
   const [password, setPassword] = useState('');
+  const [remember, setRemember] = useState(false);
+  const toggleRemember = useCallback(() => setRemember((v) => !v), []);
   const onSubmit = useCallback(
     (event: React.FormEvent) => {
       event.preventDefault();
 
-      webVault.repoUnlockUnlock(password);
+      webVault.repoUnlockUnlock(password, remember);
     },
-    [webVault, password]
+    [webVault, password, remember]
   );
   const navbarHeader = useSingleNavbarBreadcrumb(info?.repoName ?? '');
 
@@ -108,6 +111,28 @@ export const RepoUnlock = memo<{ repoId: string }>(({ repoId }) => {
               inputAriaLabel="Safe Key"
             />
           </div>
+          <div
+            className={css`
+              display: flex;
+              justify-content: center;
+              align-items: center;
+              margin: -10px 0 15px;
+            `}
+          >
+            <Checkbox
+              value={remember ? 'checked' : 'unchecked'}
+              onClick={toggleRemember}
+            />
+            <span
+              className={css`
+                cursor: pointer;
+                user-select: none;
+              `}
+              onClick={toggleRemember}
+            >
+              Remember on this device
+            </span>
+          </div>
           <Button
             type="submit"
             variant={info.status.type === 'Loading' ? 'disabled' : 'primary'}